@@ -0,0 +1,189 @@
+use super::{Config, Device, EntryStyle};
+use crate::network::wol;
+use std::collections::BTreeSet;
+
+// Devices that point at the same machine: sharing a host or IP across any of their capabilities,
+// or a MAC. Configs migrated from the separate RDP and WoL lists often have the same PC twice
+// under different names; merging folds them into one device.
+
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    // Indexes into config.devices, in config order
+    pub devices: Vec<usize>,
+    // What the devices have in common, e.g. "IP 192.168.1.20"
+    pub shared: Vec<String>,
+}
+
+impl DuplicateGroup {
+    // Stable across reordering, so an ignored group stays ignored
+    pub fn fingerprint(&self, config: &Config) -> String {
+        let mut names: Vec<&str> = self.devices.iter().map(|&i| config.devices[i].name.as_str()).collect();
+        names.sort();
+        names.join("|")
+    }
+}
+
+// Identifiers a device can share with others
+fn keys(device: &Device) -> Vec<String> {
+    let mut hosts = Vec::new();
+    if let Some(wol) = &device.wol {
+        hosts.push(wol.ip_address.as_str());
+    }
+    hosts.extend(device.rdp.as_ref().map(|r| r.host.as_str()));
+    hosts.extend(device.ssh.as_ref().map(|s| s.host.as_str()));
+    hosts.extend(device.vnc.as_ref().map(|v| v.host.as_str()));
+    hosts.push(device.monitor.address.as_str());
+
+    let mut keys = BTreeSet::new();
+    for host in hosts {
+        let host = host.trim().to_lowercase();
+        // The broadcast default for WoL devices without an IP says nothing about the machine
+        if !host.is_empty() && host != "255.255.255.255" {
+            let label = if host.parse::<std::net::IpAddr>().is_ok() { "IP" } else { "Host" };
+            keys.insert(format!("{} {}", label, host));
+        }
+    }
+    if let Some(mac) = device.wol.as_ref().map(|w| w.mac_address.as_str()).filter(|mac| wol::validate_mac_address(mac)) {
+        keys.insert(format!("MAC {}", wol::format_mac_address(mac)));
+    }
+    keys.into_iter().collect()
+}
+
+// Groups of devices connected by shared keys, minus ignored ones
+pub fn find(config: &Config) -> Vec<DuplicateGroup> {
+    let entries: Vec<Vec<String>> = config.devices.iter().map(keys).collect();
+
+    let mut assigned = vec![false; entries.len()];
+    let mut groups = Vec::new();
+
+    for start in 0..entries.len() {
+        if assigned[start] {
+            continue;
+        }
+        assigned[start] = true;
+
+        // Grow the group until no other device shares a key with it
+        let mut members = vec![start];
+        let mut shared = BTreeSet::new();
+        let mut next = 0;
+        while next < members.len() {
+            let current = members[next];
+            next += 1;
+            for other in 0..entries.len() {
+                if assigned[other] {
+                    continue;
+                }
+                let common: Vec<&String> = entries[current].iter().filter(|k| entries[other].contains(k)).collect();
+                if !common.is_empty() {
+                    shared.extend(common.into_iter().cloned());
+                    assigned[other] = true;
+                    members.push(other);
+                }
+            }
+        }
+
+        if members.len() < 2 {
+            continue;
+        }
+
+        members.sort_unstable();
+        let group = DuplicateGroup {
+            devices: members,
+            shared: shared.into_iter().collect(),
+        };
+        if !config.ignored_duplicates.contains(&group.fingerprint(config)) {
+            groups.push(group);
+        }
+    }
+
+    groups
+}
+
+// Folds the group into its first device: capabilities it lacks and empty fields come from the
+// others, which are then removed. Schedules and mutes naming a removed device move to the kept one.
+pub fn merge(config: &mut Config, group: &DuplicateGroup) {
+    let Some((&keep, rest)) = group.devices.split_first() else { return };
+    let kept_name = config.devices[keep].name.clone();
+
+    for &index in rest {
+        let other = config.devices[index].clone();
+        let kept = &mut config.devices[keep];
+        fill(&mut kept.tag, &other.tag);
+        fill(&mut kept.monitor.address, &other.monitor.address);
+        kept.monitor.maintenance_until = kept.monitor.maintenance_until.max(other.monitor.maintenance_until);
+        if kept.style == EntryStyle::default() {
+            kept.style = other.style.clone();
+        }
+
+        match (&mut kept.wol, other.wol) {
+            (None, wol) => kept.wol = wol,
+            (Some(kept_wol), Some(other_wol)) => {
+                if kept_wol.ip_address.is_empty() || kept_wol.ip_address == "255.255.255.255" {
+                    kept_wol.ip_address = other_wol.ip_address;
+                }
+                if kept_wol.relay.is_none() {
+                    kept_wol.relay = other_wol.relay;
+                }
+            }
+            _ => {}
+        }
+        match (&mut kept.rdp, other.rdp) {
+            (None, rdp) => kept.rdp = rdp,
+            (Some(kept_rdp), Some(other_rdp)) => {
+                fill(&mut kept_rdp.username, &other_rdp.username);
+                fill(&mut kept_rdp.password, &other_rdp.password);
+                if kept_rdp.domain.is_none() {
+                    kept_rdp.domain = other_rdp.domain;
+                }
+                for (key, value) in other_rdp.env_vars {
+                    kept_rdp.env_vars.entry(key).or_insert(value);
+                }
+            }
+            _ => {}
+        }
+        match (&mut kept.ssh, other.ssh) {
+            (None, ssh) => kept.ssh = ssh,
+            (Some(kept_ssh), Some(other_ssh)) => {
+                fill(&mut kept_ssh.username, &other_ssh.username);
+                fill(&mut kept_ssh.key_path, &other_ssh.key_path);
+            }
+            _ => {}
+        }
+        if kept.vnc.is_none() {
+            kept.vnc = other.vnc;
+        }
+        kept.notes.extend(other.notes);
+        kept.sync_names();
+
+        for action in config.scheduled_actions.iter_mut()
+            .filter(|a| a.kind == super::schedule::ScheduledActionKind::WakeDevice && a.target == other.name)
+        {
+            action.target = kept_name.clone();
+        }
+        if config.notifications.is_muted(&other.name) {
+            config.notifications.set_muted(&other.name, false);
+            config.notifications.set_muted(&kept_name, true);
+        }
+    }
+
+    // Highest index first so the earlier ones stay valid
+    for &index in rest.iter().rev() {
+        config.devices.remove(index);
+    }
+
+    log::info!("Merged {} devices sharing {} into {}", group.devices.len(), group.shared.join(", "), kept_name);
+}
+
+// Keeps the group from being reported again
+pub fn ignore(config: &mut Config, group: &DuplicateGroup) {
+    let fingerprint = group.fingerprint(config);
+    if !config.ignored_duplicates.contains(&fingerprint) {
+        config.ignored_duplicates.push(fingerprint);
+    }
+}
+
+fn fill(target: &mut String, source: &str) {
+    if target.is_empty() {
+        *target = source.to_string();
+    }
+}
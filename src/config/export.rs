@@ -0,0 +1,286 @@
+use super::{Config, VpnType};
+use crate::network::{preview, wol};
+
+// Inventory exports for seeding automation tooling. Passwords are never written; commands in the
+// shell script come from the same argument lists the app runs, every word single-quoted, and read
+// secrets from environment variables the caller sets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    AnsibleInventory,
+    ShellScript,
+}
+
+impl ExportFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::AnsibleInventory => "Ansible Inventory",
+            ExportFormat::ShellScript => "Shell Script",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::AnsibleInventory => "vpn-manager-inventory.yml",
+            ExportFormat::ShellScript => "vpn-manager.sh",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::AnsibleInventory => "yml",
+            ExportFormat::ShellScript => "sh",
+        }
+    }
+}
+
+pub fn render(config: &Config, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::AnsibleInventory => render_ansible(config),
+        ExportFormat::ShellScript => render_script(config),
+    }
+}
+
+pub fn export(config: &Config, format: ExportFormat, path: &std::path::Path) -> anyhow::Result<()> {
+    std::fs::write(path, render(config, format))?;
+
+    #[cfg(unix)]
+    if format == ExportFormat::ShellScript {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+// YAML inventory: devices become hosts in an `rdp`, `wol` and `ssh` group per capability, VPN
+// profiles are listed under `all.vars` since they aren't hosts themselves
+fn render_ansible(config: &Config) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("# Generated by VPN Manager v{}; passwords are not exported\n", env!("CARGO_PKG_VERSION")));
+    out.push_str("all:\n");
+
+    if !config.vpn_configs.is_empty() {
+        out.push_str("  vars:\n    vpn_profiles:\n");
+        for vpn_config in &config.vpn_configs {
+            out.push_str(&format!("      - name: {}\n", yaml_string(&vpn_config.name)));
+            out.push_str(&format!("        type: {}\n", vpn_type_name(&vpn_config.vpn_type)));
+            if vpn_config.vpn_type == VpnType::OpenConnect {
+                out.push_str(&format!("        server: {}\n", yaml_string(&vpn_config.server)));
+                if !vpn_config.group.is_empty() {
+                    out.push_str(&format!("        group: {}\n", yaml_string(&vpn_config.group)));
+                }
+            } else {
+                out.push_str(&format!("        config: {}\n", yaml_string(&vpn_config.resolved_config_path())));
+            }
+            if !vpn_config.username.is_empty() {
+                out.push_str(&format!("        username: {}\n", yaml_string(&vpn_config.username)));
+            }
+        }
+    }
+
+    out.push_str("  children:\n");
+
+    out.push_str("    rdp:\n      hosts:\n");
+    if !config.devices.iter().any(|d| d.rdp.is_some()) {
+        out.push_str("        {}\n");
+    }
+    for (device, rdp_config) in config.devices.iter().filter_map(|d| Some((d, d.rdp.as_ref()?))) {
+        out.push_str(&format!("        {}:\n", host_name(&device.name)));
+        out.push_str(&format!("          display_name: {}\n", yaml_string(&device.name)));
+        out.push_str(&format!("          ansible_host: {}\n", yaml_string(&rdp_config.host)));
+        out.push_str(&format!("          rdp_port: {}\n", rdp_config.port));
+        if !rdp_config.username.is_empty() {
+            out.push_str(&format!("          rdp_username: {}\n", yaml_string(&rdp_config.username)));
+        }
+        if let Some(domain) = rdp_config.domain.as_ref().filter(|d| !d.is_empty()) {
+            out.push_str(&format!("          rdp_domain: {}\n", yaml_string(domain)));
+        }
+        push_tag(&mut out, &device.tag);
+    }
+
+    out.push_str("    wol:\n      hosts:\n");
+    if !config.devices.iter().any(|d| d.wol.is_some()) {
+        out.push_str("        {}\n");
+    }
+    for (device, wol_device) in config.devices.iter().filter_map(|d| Some((d, d.wol.as_ref()?))) {
+        out.push_str(&format!("        {}:\n", host_name(&device.name)));
+        out.push_str(&format!("          display_name: {}\n", yaml_string(&device.name)));
+        if let Some(address) = device.monitor_address() {
+            out.push_str(&format!("          ansible_host: {}\n", yaml_string(&address)));
+        }
+        out.push_str(&format!("          wol_mac: {}\n", yaml_string(&wol::format_mac_address(&wol_device.mac_address))));
+        out.push_str(&format!("          wol_port: {}\n", wol_device.port));
+        push_tag(&mut out, &device.tag);
+    }
+
+    out.push_str("    ssh:\n      hosts:\n");
+    if !config.devices.iter().any(|d| d.ssh.is_some()) {
+        out.push_str("        {}\n");
+    }
+    for (device, ssh_config) in config.devices.iter().filter_map(|d| Some((d, d.ssh.as_ref()?))) {
+        out.push_str(&format!("        {}:\n", host_name(&device.name)));
+        out.push_str(&format!("          display_name: {}\n", yaml_string(&device.name)));
+        out.push_str(&format!("          ansible_host: {}\n", yaml_string(&ssh_config.host)));
+        out.push_str(&format!("          ansible_port: {}\n", ssh_config.port));
+        if !ssh_config.username.is_empty() {
+            out.push_str(&format!("          ansible_user: {}\n", yaml_string(&ssh_config.username)));
+        }
+        if !ssh_config.key_path.is_empty() {
+            out.push_str(&format!("          ansible_ssh_private_key_file: {}\n", yaml_string(&ssh_config.key_path)));
+        }
+        push_tag(&mut out, &device.tag);
+    }
+
+    out
+}
+
+// POSIX sh skeleton with one function per action, called by name: ./vpn-manager.sh wake_nas
+fn render_script(config: &Config) -> String {
+    let mut out = String::new();
+    out.push_str("#!/bin/sh\n");
+    out.push_str(&format!("# Generated by VPN Manager v{}\n", env!("CARGO_PKG_VERSION")));
+    out.push_str("# Commands match what the app runs on this machine. Secrets were not exported: set\n");
+    out.push_str("# the variables a function reads, e.g. VPN_MANAGER_PASSWORD, before calling it\n");
+    out.push_str("set -e\n");
+
+    for vpn_config in &config.vpn_configs {
+        let name = host_name(&vpn_config.name);
+        push_comment(&mut out, &format!("VPN: {} ({})", vpn_config.name, vpn_type_name(&vpn_config.vpn_type)));
+        push_function(&mut out, &format!("vpn_{}_up", name), &preview::vpn_connect(vpn_config).script);
+        push_function(&mut out, &format!("vpn_{}_down", name), &preview::vpn_disconnect(vpn_config).script);
+    }
+
+    for device in &config.devices {
+        let name = host_name(&device.name);
+        if let Some(rdp_config) = &device.rdp {
+            push_comment(&mut out, &format!("RDP: {}", device.name));
+            push_function(&mut out, &format!("rdp_{}", name), &preview::rdp_connect(rdp_config).script);
+        }
+        if let Some(ssh_config) = &device.ssh {
+            push_comment(&mut out, &format!("SSH: {}", device.name));
+            push_function(&mut out, &format!("ssh_{}", name), &[preview::ssh_command(ssh_config).script()]);
+        }
+        if let Some(wol_device) = &device.wol {
+            push_comment(&mut out, &format!("Wake-on-LAN: {}", device.name));
+            push_function(&mut out, &format!("wake_{}", name), &[preview::wake_command(wol_device).script()]);
+            if let Some(relay) = preview::wake_relay(wol_device) {
+                push_function(&mut out, &format!("wake_{}_relay", name), &[relay.script()]);
+            }
+        }
+    }
+
+    out.push_str("\nif [ $# -eq 0 ]; then\n");
+    out.push_str("    echo \"Usage: $0 <action>\" >&2\n");
+    out.push_str("    sed -n 's/^\\([a-z0-9_]*\\)() {$/  \\1/p' \"$0\" >&2\n");
+    out.push_str("    exit 1\n");
+    out.push_str("fi\n");
+    out.push_str("\"$@\"\n");
+    out
+}
+
+// Names can hold anything, a line break included
+fn push_comment(out: &mut String, text: &str) {
+    out.push_str(&format!("\n# {}\n", text.replace(['\r', '\n'], " ")));
+}
+
+fn push_function(out: &mut String, name: &str, lines: &[String]) {
+    out.push_str(&format!("{}() {{\n", name));
+    for line in lines {
+        out.push_str(&format!("    {}\n", line));
+    }
+    out.push_str("}\n");
+}
+
+fn vpn_type_name(vpn_type: &VpnType) -> &'static str {
+    match vpn_type {
+        VpnType::OpenVpn => "openvpn",
+        VpnType::WireGuard => "wireguard",
+        VpnType::Ikev2 => "ikev2",
+        VpnType::OpenConnect => "openconnect",
+        VpnType::Custom => "custom",
+    }
+}
+
+// Lowercase identifier usable as an Ansible host alias and a shell function name
+fn host_name(name: &str) -> String {
+    let mut host: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    host = host.trim_matches('_').to_string();
+    if host.is_empty() || host.starts_with(|c: char| c.is_ascii_digit()) {
+        host.insert_str(0, "entry_");
+    }
+    host
+}
+
+fn push_tag(out: &mut String, tag: &str) {
+    if !tag.is_empty() {
+        out.push_str(&format!("          tag: {}\n", yaml_string(tag)));
+    }
+}
+
+// JSON strings are valid double-quoted YAML scalars
+fn yaml_string(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTILE: &str = "it's $(touch pwned); `id` | cat & \"x\"\nnext";
+
+    fn hostile_config() -> Config {
+        let vpn_configs = ["OpenConnect", "Custom"]
+            .iter()
+            .map(|vpn_type| {
+                serde_json::from_value(serde_json::json!({
+                    "name": format!("{} {}", vpn_type, HOSTILE),
+                    "vpn_type": vpn_type,
+                    "config_path": "",
+                    "server": HOSTILE,
+                    "username": HOSTILE,
+                    "password": "pa$$ 'word'",
+                    "group": HOSTILE,
+                    "auto_connect": false,
+                    "env_vars": { "TOKEN": "secret token", "not a name": "x" },
+                    "custom": { "connect": "client --user {username} --password {password} {server}", "disconnect": "" },
+                }))
+                .unwrap()
+            })
+            .collect();
+        let devices = vec![serde_json::from_value(serde_json::json!({
+            "name": HOSTILE,
+            "ssh": { "host": "host.example", "port": 2222, "username": "admin", "key_path": HOSTILE },
+            "wol": {
+                "mac_address": "AA:BB:CC:DD:EE:FF",
+                "ip_address": "192.168.1.20",
+                "port": 9,
+                "relay": { "host": "relay.example", "port": 22, "username": "pi" },
+            },
+        }))
+        .unwrap()];
+        Config { vpn_configs, devices, ..Config::default() }
+    }
+
+    #[test]
+    fn script_quotes_every_word() {
+        let script = render(&hostile_config(), ExportFormat::ShellScript);
+        assert!(!script.contains("pa$$"), "{}", script);
+        assert!(!script.contains("secret token"), "{}", script);
+        assert!(script.contains(r#"printf '%s\n' "${VPN_MANAGER_PASSWORD:?}" | "#), "{}", script);
+        assert!(script.contains(r#"--password "${VPN_MANAGER_PASSWORD:?}""#), "{}", script);
+        assert!(script.contains(r#"'TOKEN'="${TOKEN:?}""#), "{}", script);
+        assert!(script.contains(r#"'not a name'="${not_a_name:?}""#), "{}", script);
+        assert!(script.contains(r#"'it'\''s $(touch pwned); `id` | cat & "x"
+next'"#), "{}", script);
+
+        let path = std::env::temp_dir().join(format!("vpn-manager-test-export-{}.sh", std::process::id()));
+        std::fs::write(&path, &script).unwrap();
+        let output = std::process::Command::new("sh").arg("-n").arg(&path).output().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(output.status.success(), "{}\n{}", String::from_utf8_lossy(&output.stderr), script);
+    }
+}
@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Keep the file small enough to load on every start
+const MAX_EVENTS: usize = 5000;
+
+static HISTORY: Mutex<Option<History>> = Mutex::new(None);
+
+// Per device: online secs, offline secs, current state and since when
+type DeviceTime = (u64, u64, Option<(HistoryEventKind, u64)>);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum HistoryEventKind {
+    VpnConnect,
+    VpnDisconnect,
+    Wake,
+    RdpConnect,
+    SshConnect,
+    VncConnect,
+    // Device status as seen by the periodic checks; maintenance marks a period that doesn't count
+    DeviceOnline,
+    DeviceOffline,
+    DeviceMaintenance,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    pub timestamp: u64,
+    pub kind: HistoryEventKind,
+    pub name: String,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    pub events: Vec<HistoryEvent>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageStats {
+    pub most_used_vpn: Option<(String, usize)>,
+    pub total_tunnel_secs: u64,
+    pub wake_attempts: usize,
+    pub wake_successes: usize,
+    pub rdp_sessions: usize,
+    pub tracked_since: Option<u64>,
+    // Percentage of monitored time each device was online, excluding maintenance windows
+    pub device_availability: BTreeMap<String, f32>,
+}
+
+impl UsageStats {
+    pub fn wake_success_rate(&self) -> Option<f32> {
+        if self.wake_attempts == 0 {
+            None
+        } else {
+            Some(self.wake_successes as f32 / self.wake_attempts as f32 * 100.0)
+        }
+    }
+}
+
+impl History {
+    fn load() -> Self {
+        let path = Self::history_path();
+        std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let path = Self::history_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn history_path() -> PathBuf {
+        crate::system::paths::state_dir().join("history.json")
+    }
+
+    pub fn stats(&self) -> UsageStats {
+        let mut stats = UsageStats {
+            tracked_since: self.events.first().map(|e| e.timestamp),
+            ..Default::default()
+        };
+        let mut vpn_counts: HashMap<&str, usize> = HashMap::new();
+        let mut open_tunnels: HashMap<&str, u64> = HashMap::new();
+        let mut device_time: HashMap<&str, DeviceTime> = HashMap::new();
+
+        for event in &self.events {
+            match event.kind {
+                HistoryEventKind::VpnConnect if event.success => {
+                    *vpn_counts.entry(&event.name).or_default() += 1;
+                    open_tunnels.insert(&event.name, event.timestamp);
+                }
+                HistoryEventKind::VpnDisconnect => {
+                    if let Some(start) = open_tunnels.remove(event.name.as_str()) {
+                        stats.total_tunnel_secs += event.timestamp.saturating_sub(start);
+                    }
+                }
+                HistoryEventKind::Wake => {
+                    stats.wake_attempts += 1;
+                    if event.success {
+                        stats.wake_successes += 1;
+                    }
+                }
+                HistoryEventKind::RdpConnect if event.success => {
+                    stats.rdp_sessions += 1;
+                }
+                HistoryEventKind::DeviceOnline | HistoryEventKind::DeviceOffline | HistoryEventKind::DeviceMaintenance => {
+                    let entry = device_time.entry(&event.name).or_default();
+                    add_device_time(entry, event.timestamp);
+                    entry.2 = Some((event.kind.clone(), event.timestamp));
+                }
+                _ => {}
+            }
+        }
+
+        // Tunnels that are still up count until now
+        let now = now_secs();
+        for start in open_tunnels.values() {
+            stats.total_tunnel_secs += now.saturating_sub(*start);
+        }
+        
+        for (name, mut entry) in device_time {
+            add_device_time(&mut entry, now);
+            let (online, offline, _) = entry;
+            if online + offline > 0 {
+                stats.device_availability.insert(name.to_string(), online as f32 / (online + offline) as f32 * 100.0);
+            }
+        }
+
+        stats.most_used_vpn = vpn_counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(name, count)| (name.to_string(), count));
+
+        stats
+    }
+}
+
+// Credits the time since the device's last status event to that state
+fn add_device_time(entry: &mut (u64, u64, Option<(HistoryEventKind, u64)>), until: u64) {
+    match &entry.2 {
+        Some((HistoryEventKind::DeviceOnline, since)) => entry.0 += until.saturating_sub(*since),
+        Some((HistoryEventKind::DeviceOffline, since)) => entry.1 += until.saturating_sub(*since),
+        _ => {}
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn with_history<R>(f: impl FnOnce(&mut History) -> R) -> R {
+    let mut guard = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    let history = guard.get_or_insert_with(History::load);
+    f(history)
+}
+
+pub fn record(kind: HistoryEventKind, name: &str, success: bool) {
+    with_history(|history| {
+        history.events.push(HistoryEvent {
+            timestamp: now_secs(),
+            kind,
+            name: name.to_string(),
+            success,
+        });
+
+        if history.events.len() > MAX_EVENTS {
+            let excess = history.events.len() - MAX_EVENTS;
+            history.events.drain(..excess);
+        }
+
+        if let Err(e) = history.save() {
+            log::warn!("Failed to save history: {}", e);
+        }
+    });
+}
+
+// Moves the events of these kinds from one entry name to another, returning how many
+pub fn rename(kinds: &[HistoryEventKind], old: &str, new: &str) -> usize {
+    with_history(|history| {
+        let mut count = 0;
+        for event in history.events.iter_mut().filter(|event| event.name == old && kinds.contains(&event.kind)) {
+            event.name = new.to_string();
+            count += 1;
+        }
+        if count > 0 {
+            if let Err(e) = history.save() {
+                log::warn!("Failed to save history: {}", e);
+            }
+        }
+        count
+    })
+}
+
+pub fn stats() -> UsageStats {
+    with_history(|history| history.stats())
+}
+
+pub fn export(path: &std::path::Path) -> anyhow::Result<()> {
+    let content = with_history(|history| {
+        serde_json::to_string_pretty(&serde_json::json!({
+            "stats": history.stats(),
+            "events": history.events,
+        }))
+    })?;
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+pub fn clear() -> anyhow::Result<()> {
+    with_history(|history| {
+        history.events.clear();
+        history.save()
+    })
+}
@@ -0,0 +1,271 @@
+use super::{CertFiles, Config, VpnType};
+use crate::network::{certs, custom, dns, killswitch, netns, openvpn, otp, proxy, routes, wireguard, wol};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryKind {
+    Vpn,
+    Device,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixIt {
+    BrowseConfigFile,
+    FormatMac,
+    RenameDuplicate,
+    Remove,
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub kind: EntryKind,
+    pub index: usize,
+    pub name: String,
+    pub severity: Severity,
+    pub message: String,
+    pub fixes: Vec<FixIt>,
+}
+
+impl EntryKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EntryKind::Vpn => "VPN",
+            EntryKind::Device => "Device",
+        }
+    }
+}
+
+impl FixIt {
+    pub fn label(&self) -> &'static str {
+        match self {
+            FixIt::BrowseConfigFile => "Browse...",
+            FixIt::FormatMac => "Normalize MAC",
+            FixIt::RenameDuplicate => "Rename",
+            FixIt::Remove => "Remove Entry",
+        }
+    }
+}
+
+pub fn lint(config: &Config) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    for (index, vpn_config) in config.vpn_configs.iter().enumerate() {
+        let mut problem = |severity, message: String, fixes: Vec<FixIt>| {
+            problems.push(Problem {
+                kind: EntryKind::Vpn,
+                index,
+                name: vpn_config.name.clone(),
+                severity,
+                message,
+                fixes,
+            });
+        };
+
+        if vpn_config.kill_switch && !killswitch::is_supported(&vpn_config.vpn_type) {
+            problem(Severity::Warning, "Kill switch isn't supported for this VPN type on this platform".to_string(), Vec::new());
+        }
+
+        if netns::is_enabled(vpn_config) && !netns::is_supported() {
+            problem(Severity::Warning, "Per-application tunneling is only available on Linux".to_string(), Vec::new());
+        }
+
+        for server in vpn_config.dns_servers.iter().filter(|s| s.parse::<std::net::IpAddr>().is_err()) {
+            problem(Severity::Warning, format!("DNS server is not an IP address: {}", server), Vec::new());
+        }
+        for domain in vpn_config.dns_search_domains.iter().filter(|d| !dns::is_valid_domain(d)) {
+            problem(Severity::Warning, format!("Search domain is not valid: {}", domain), Vec::new());
+        }
+
+        for route in vpn_config.split_routes.iter().filter(|route| routes::parse_cidr(&route.cidr).is_none()) {
+            problem(Severity::Warning, format!("Split tunnel route is not a valid network: {}", route.cidr), Vec::new());
+        }
+
+        if let Some(message) = proxy::problem(vpn_config) {
+            problem(Severity::Warning, message, Vec::new());
+        }
+        for (severity, message) in certs::problems(vpn_config) {
+            problem(severity, message, Vec::new());
+        }
+
+        if !vpn_config.uses_config_file() {
+            if vpn_config.vpn_type == VpnType::OpenConnect && vpn_config.server.trim().is_empty() {
+                problem(Severity::Error, "No server set".to_string(), vec![FixIt::Remove]);
+            } else if vpn_config.vpn_type == VpnType::Ikev2 && vpn_config.config_path.trim().is_empty() {
+                problem(Severity::Error, "No connection name set".to_string(), vec![FixIt::Remove]);
+            } else if vpn_config.vpn_type == VpnType::Custom {
+                if vpn_config.custom.connect.trim().is_empty() {
+                    problem(Severity::Error, "No connect command set".to_string(), vec![FixIt::Remove]);
+                }
+                for placeholder in custom::missing_values(vpn_config) {
+                    problem(Severity::Warning, format!("The commands use {} but the entry has no value for it", placeholder), Vec::new());
+                }
+                if custom::password_on_command_line(vpn_config) {
+                    problem(Severity::Warning,
+                        "{password} puts the password on the command line, where other users can see it; use $VPN_MANAGER_PASSWORD".to_string(),
+                        Vec::new());
+                }
+                let resolved_path = vpn_config.resolved_config_path();
+                if !vpn_config.config_path.trim().is_empty() && !Path::new(&resolved_path).exists() {
+                    problem(Severity::Error, format!("Config file not found: {}", resolved_path), vec![FixIt::Remove]);
+                }
+            }
+            continue;
+        }
+
+        let resolved_path = vpn_config.resolved_config_path();
+        let path = Path::new(&resolved_path);
+        if vpn_config.config_path.trim().is_empty() {
+            problem(Severity::Error, "No config file set".to_string(), vec![FixIt::BrowseConfigFile, FixIt::Remove]);
+        } else if !path.exists() {
+            problem(Severity::Error, format!("Config file not found: {}", resolved_path),
+                vec![FixIt::BrowseConfigFile, FixIt::Remove]);
+        } else if std::fs::File::open(path).is_err() {
+            problem(Severity::Error, format!("Config file is not readable: {}", resolved_path),
+                vec![FixIt::BrowseConfigFile]);
+        } else {
+            let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            let expected = match vpn_config.vpn_type {
+                VpnType::OpenVpn => "ovpn",
+                VpnType::WireGuard => "conf",
+                VpnType::Ikev2 | VpnType::OpenConnect | VpnType::Custom => continue,
+            };
+            if !extension.eq_ignore_ascii_case(expected) {
+                problem(Severity::Warning, format!("Expected a .{} file for this VPN type", expected),
+                    vec![FixIt::BrowseConfigFile]);
+            }
+            if std::fs::read_to_string(path).is_ok_and(|profile| proxy::needs_tcp(vpn_config, &profile)) {
+                problem(Severity::Warning, "An HTTP proxy needs the profile to connect over TCP (proto tcp-client)".to_string(), Vec::new());
+            }
+            for (severity, message) in config_file(&vpn_config.vpn_type, &resolved_path, &vpn_config.certificates) {
+                problem(severity, message, Vec::new());
+            }
+            if vpn_config.vpn_type == VpnType::OpenVpn && !vpn_config.prompt_otp && otp::challenge_text(vpn_config).is_some() {
+                problem(Severity::Warning, "The profile has a static challenge; turn on asking for a one-time code (🔑)".to_string(), Vec::new());
+            }
+        }
+    }
+
+    for (index, device) in config.devices.iter().enumerate() {
+        let mut problem = |severity, message: String, fixes: Vec<FixIt>| {
+            problems.push(Problem {
+                kind: EntryKind::Device,
+                index,
+                name: device.name.clone(),
+                severity,
+                message,
+                fixes,
+            });
+        };
+        // Removing the device would take its other capabilities along
+        let removable = device.capabilities().len() <= 1;
+        let remove = || if removable { vec![FixIt::Remove] } else { Vec::new() };
+
+        if device.capabilities().is_empty() {
+            problem(Severity::Warning, "No Wake-on-LAN, RDP, SSH or VNC set up".to_string(), vec![FixIt::Remove]);
+        }
+
+        for (label, host) in [
+            ("RDP", device.rdp.as_ref().map(|r| &r.host)),
+            ("SSH", device.ssh.as_ref().map(|s| &s.host)),
+            ("VNC", device.vnc.as_ref().map(|v| &v.host)),
+        ] {
+            if host.is_some_and(|host| host.trim().is_empty()) {
+                problem(Severity::Error, format!("{} host is empty", label), remove());
+            }
+        }
+
+        if let Some(wol_device) = &device.wol {
+            if !wol::validate_mac_address(&wol_device.mac_address) {
+                problem(Severity::Error, format!("Invalid MAC address: {}", wol_device.mac_address), remove());
+            } else if wol::format_mac_address(&wol_device.mac_address) != wol_device.mac_address {
+                problem(Severity::Warning, format!("MAC address is not in canonical form: {}", wol_device.mac_address),
+                    vec![FixIt::FormatMac]);
+            }
+
+            if !wol_device.ip_address.is_empty() && wol_device.ip_address.parse::<std::net::IpAddr>().is_err() {
+                problem(Severity::Warning, format!("IP address is not valid: {}", wol_device.ip_address), Vec::new());
+            }
+        }
+    }
+
+    lint_duplicates(EntryKind::Vpn, config.vpn_configs.iter().map(|c| &c.name), &mut problems);
+    lint_duplicates(EntryKind::Device, config.devices.iter().map(|d| &d.name), &mut problems);
+
+    problems
+}
+
+// What is wrong inside an OpenVPN or WireGuard config file, also checked by the add form before
+// the entry exists
+pub fn config_file(vpn_type: &VpnType, path: &str, certificates: &CertFiles) -> Vec<(Severity, String)> {
+    let problems = match vpn_type {
+        VpnType::OpenVpn => openvpn::validate_config(path, certificates),
+        VpnType::WireGuard => wireguard::validate_config(path),
+        VpnType::Ikev2 | VpnType::OpenConnect | VpnType::Custom => Ok(Vec::new()),
+    };
+    problems.unwrap_or_else(|e| vec![(Severity::Error, format!("Config file is not readable: {}", e))])
+}
+
+fn lint_duplicates<'a>(kind: EntryKind, names: impl Iterator<Item = &'a String>, problems: &mut Vec<Problem>) {
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for (index, name) in names.enumerate() {
+        // The first entry keeps its name, later ones are flagged
+        if seen.insert(name.as_str(), index).is_some() {
+            problems.push(Problem {
+                kind,
+                index,
+                name: name.clone(),
+                severity: Severity::Warning,
+                message: format!("Another {} entry is also named '{}'", kind.label(), name),
+                fixes: vec![FixIt::RenameDuplicate, FixIt::Remove],
+            });
+        }
+    }
+}
+
+// Applies a fix-it to the config, returning true if anything changed
+pub fn apply_fix(config: &mut Config, problem: &Problem, fix: &FixIt) -> bool {
+    match fix {
+        // Needs a file dialog, so the UI handles it
+        FixIt::BrowseConfigFile => false,
+        FixIt::FormatMac => {
+            let Some(wol_device) = config.devices.get_mut(problem.index).and_then(|d| d.wol.as_mut()) else { return false };
+            wol_device.mac_address = wol::format_mac_address(&wol_device.mac_address);
+            true
+        }
+        FixIt::RenameDuplicate => {
+            let names: Vec<String> = match problem.kind {
+                EntryKind::Vpn => config.vpn_configs.iter().map(|c| c.name.clone()).collect(),
+                EntryKind::Device => config.devices.iter().map(|d| d.name.clone()).collect(),
+            };
+            let new_name = (2..)
+                .map(|n| format!("{} ({})", problem.name, n))
+                .find(|candidate| !names.contains(candidate))
+                .unwrap_or_else(|| problem.name.clone());
+            match problem.kind {
+                EntryKind::Vpn => config.vpn_configs.get_mut(problem.index).map(|c| c.name = new_name).is_some(),
+                EntryKind::Device => config.devices.get_mut(problem.index).map(|d| {
+                    d.name = new_name;
+                    d.sync_names();
+                }).is_some(),
+            }
+        }
+        FixIt::Remove => match problem.kind {
+            EntryKind::Vpn if problem.index < config.vpn_configs.len() => {
+                config.vpn_configs.remove(problem.index);
+                true
+            }
+            EntryKind::Device if problem.index < config.devices.len() => {
+                config.devices.remove(problem.index);
+                true
+            }
+            _ => false,
+        },
+    }
+}
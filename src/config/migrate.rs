@@ -0,0 +1,240 @@
+use super::history::{self, HistoryEventKind};
+use super::lint::EntryKind;
+use super::schedule::ScheduledActionKind;
+use super::{Config, VpnConfig};
+use crate::system::{boot, client_log, services, thumbnails};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::{BTreeMap, HashSet};
+
+// Data that refers to entries by name follows them when they are renamed: scheduled actions,
+// the failover order, the always-on profile, muted notifications, the connection history, and
+// the client logs and thumbnails named after the entry. Each entry has a stable id, and
+// config.json keeps the name each id had when it was last saved, so a rename made in config.json
+// is noticed on the next start. What can't be carried over, e.g. a boot unit only root can
+// rename, or a reference to a name no entry has, ends up in a report shown to the user.
+
+const VPN_EVENTS: [HistoryEventKind; 2] = [HistoryEventKind::VpnConnect, HistoryEventKind::VpnDisconnect];
+const DEVICE_EVENTS: [HistoryEventKind; 7] = [
+    HistoryEventKind::Wake,
+    HistoryEventKind::RdpConnect,
+    HistoryEventKind::SshConnect,
+    HistoryEventKind::VncConnect,
+    HistoryEventKind::DeviceOnline,
+    HistoryEventKind::DeviceOffline,
+    HistoryEventKind::DeviceMaintenance,
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    // Old and new name
+    pub renamed: Vec<(String, String)>,
+    // What was carried over
+    pub moved: Vec<String>,
+    // What still points at a name no entry has, or has to be redone by hand
+    pub unmapped: Vec<String>,
+}
+
+impl MigrationReport {
+    pub fn needs_attention(&self) -> bool {
+        !self.unmapped.is_empty()
+    }
+
+    pub fn merge(&mut self, other: MigrationReport) {
+        self.renamed.extend(other.renamed);
+        self.moved.extend(other.moved);
+        self.unmapped.extend(other.unmapped);
+    }
+    
+    // After a rename in the app: logs what moved and hands what didn't to the report window
+    pub fn queue(self, pending: &mut Option<MigrationReport>) {
+        for line in &self.moved {
+            log::info!("{}", line);
+        }
+        if !self.needs_attention() {
+            return;
+        }
+        match pending {
+            Some(pending) => pending.merge(self),
+            None => *pending = Some(self),
+        }
+    }
+}
+
+// A random (version 4) UUID
+pub fn new_id() -> String {
+    let mut bytes = [0u8; 16];
+    if SystemRandom::new().fill(&mut bytes).is_err() {
+        // Only unique within the config, which is all it needs to be
+        bytes[..8].copy_from_slice(&std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos().to_le_bytes()[..8]);
+    }
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+// What Config::save writes as entry_names
+pub fn entry_names(config: &Config) -> BTreeMap<String, String> {
+    config.vpn_configs.iter().map(|vpn| (vpn.id.clone(), vpn.name.clone()))
+        .chain(config.devices.iter().map(|device| (device.id.clone(), device.name.clone())))
+        .collect()
+}
+
+// After loading: gives copied entries ids of their own and carries references over to entries
+// renamed since the last save. A config from before ids only has its references checked.
+pub fn on_load(config: &mut Config) -> MigrationReport {
+    let mut report = MigrationReport::default();
+    let first_run = config.entry_names.is_empty() && !(config.vpn_configs.is_empty() && config.devices.is_empty());
+
+    // A copied entry keeps the id; the copy with a name the id didn't have gets a new one
+    let mut seen = HashSet::new();
+    let remembered = config.entry_names.clone();
+    let mut order: Vec<(EntryKind, usize)> = (0..config.vpn_configs.len()).map(|i| (EntryKind::Vpn, i)).collect();
+    order.extend((0..config.devices.len()).map(|i| (EntryKind::Device, i)));
+    order.sort_by_key(|(kind, index)| {
+        let (id, name) = entry(config, *kind, *index);
+        remembered.get(id) != Some(name)
+    });
+    for (kind, index) in order {
+        let id = entry(config, kind, index).0.clone();
+        if !seen.insert(id) {
+            let fresh = new_id();
+            match kind {
+                EntryKind::Vpn => config.vpn_configs[index].id = fresh,
+                EntryKind::Device => config.devices[index].id = fresh,
+            }
+        }
+    }
+
+    let renames: Vec<(EntryKind, String, String)> = config.vpn_configs.iter().map(|vpn| (EntryKind::Vpn, &vpn.id, &vpn.name))
+        .chain(config.devices.iter().map(|device| (EntryKind::Device, &device.id, &device.name)))
+        .filter_map(|(kind, id, name)| {
+            let old = remembered.get(id)?;
+            (old != name).then(|| (kind, old.clone(), name.clone()))
+        })
+        .collect();
+    for (kind, old, new) in &renames {
+        log::info!("{} was renamed to {} since the last start", old, new);
+        report.merge(rename(config, *kind, old, new));
+    }
+
+    if first_run || !renames.is_empty() {
+        report.unmapped.extend(dangling(config));
+    }
+    config.entry_names = entry_names(config);
+    report
+}
+
+fn entry(config: &Config, kind: EntryKind, index: usize) -> (&String, &String) {
+    match kind {
+        EntryKind::Vpn => (&config.vpn_configs[index].id, &config.vpn_configs[index].name),
+        EntryKind::Device => (&config.devices[index].id, &config.devices[index].name),
+    }
+}
+
+// Points what referred to `old` at `new`; the entry itself is already renamed
+pub fn rename(config: &mut Config, kind: EntryKind, old: &str, new: &str) -> MigrationReport {
+    let mut report = MigrationReport {
+        renamed: vec![(old.to_string(), new.to_string())],
+        ..Default::default()
+    };
+    if old == new {
+        return report;
+    }
+    let mut moved = |count: usize, what: &str| {
+        if count > 0 {
+            report.moved.push(format!("{} {} of {} now on {}", count, what, old, new));
+        }
+    };
+
+    let schedule_kind = if kind == EntryKind::Vpn { ScheduledActionKind::ConnectVpn } else { ScheduledActionKind::WakeDevice };
+    let mut count = 0;
+    for action in config.scheduled_actions.iter_mut().filter(|action| action.kind == schedule_kind && action.target == old) {
+        action.target = new.to_string();
+        count += 1;
+    }
+    moved(count, "scheduled actions");
+
+    match kind {
+        EntryKind::Vpn => {
+            let mut count = 0;
+            for name in config.failover.order.iter_mut().filter(|name| *name == old) {
+                *name = new.to_string();
+                count += 1;
+            }
+            moved(count, "failover places");
+            if config.always_on.vpn == old {
+                config.always_on.vpn = new.to_string();
+                moved(1, "always-on setting");
+            }
+            moved(history::rename(&VPN_EVENTS, old, new), "history events");
+            moved(client_log::rename("vpn", old, new) as usize, "client log");
+        }
+        EntryKind::Device => {
+            let mut count = 0;
+            for name in config.notifications.muted.iter_mut().filter(|name| *name == old) {
+                *name = new.to_string();
+                count += 1;
+            }
+            moved(count, "notification mutes");
+            moved(history::rename(&DEVICE_EVENTS, old, new), "history events");
+            moved(client_log::rename("rdp", old, new) as usize, "client log");
+            moved(thumbnails::rename(old, new) as usize, "thumbnail");
+        }
+    }
+
+    // Installed under the old name by root, so they stay behind until reinstalled
+    if let Some(vpn) = config.vpn_configs.iter().find(|vpn| vpn.name == new).filter(|_| kind == EntryKind::Vpn) {
+        let previous = VpnConfig { name: old.to_string(), ..vpn.clone() };
+        if boot::is_enabled(&previous) {
+            report.unmapped.push(format!("{} starts at boot as {}; click ⏻ twice to install it under the new name", new, boot::unit_name(&previous)));
+        }
+        if vpn.system_service && services::unit_name(&previous) != services::unit_name(vpn) {
+            report.unmapped.push(format!("{} runs as {}; reinstall it from the ⚙ menu to use the new name", new, services::unit_name(&previous)));
+        }
+    }
+    report
+}
+
+// References to names no entry has
+pub fn dangling(config: &Config) -> Vec<String> {
+    let vpn_names: HashSet<&str> = config.vpn_configs.iter().map(|vpn| vpn.name.as_str()).collect();
+    let device_names: HashSet<&str> = config.devices.iter().map(|device| device.name.as_str()).collect();
+    let mut unmapped = Vec::new();
+
+    for action in &config.scheduled_actions {
+        let (names, what) = match action.kind {
+            ScheduledActionKind::ConnectVpn => (&vpn_names, "Scheduled connect"),
+            ScheduledActionKind::WakeDevice => (&device_names, "Scheduled wake"),
+        };
+        if !names.contains(action.target.as_str()) {
+            unmapped.push(format!("{} at {} targets {}, which no entry is called", what, action.time, action.target));
+        }
+    }
+    for name in config.failover.order.iter().filter(|name| !vpn_names.contains(name.as_str())) {
+        unmapped.push(format!("The failover order lists {}, which no VPN is called", name));
+    }
+    if !config.always_on.vpn.is_empty() && !vpn_names.contains(config.always_on.vpn.as_str()) {
+        unmapped.push(format!("The always-on VPN is {}, which no VPN is called", config.always_on.vpn));
+    }
+    for name in config.notifications.muted.iter().filter(|name| !device_names.contains(name.as_str())) {
+        unmapped.push(format!("Notifications are muted for {}, which no device is called", name));
+    }
+    unmapped
+}
+
+// Drops the references `dangling` lists
+pub fn remove_dangling(config: &mut Config) {
+    let vpn_names: HashSet<String> = config.vpn_configs.iter().map(|vpn| vpn.name.clone()).collect();
+    let device_names: HashSet<String> = config.devices.iter().map(|device| device.name.clone()).collect();
+    config.scheduled_actions.retain(|action| match action.kind {
+        ScheduledActionKind::ConnectVpn => vpn_names.contains(&action.target),
+        ScheduledActionKind::WakeDevice => device_names.contains(&action.target),
+    });
+    config.failover.order.retain(|name| vpn_names.contains(name));
+    if !config.always_on.vpn.is_empty() && !vpn_names.contains(&config.always_on.vpn) {
+        config.always_on.vpn.clear();
+        config.always_on.enabled = false;
+    }
+    config.notifications.muted.retain(|name| device_names.contains(name));
+}
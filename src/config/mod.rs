@@ -1,21 +1,266 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod duplicates;
+pub mod export;
+pub mod history;
+pub mod lint;
+pub mod migrate;
+pub mod schedule;
+pub mod secrets;
+pub mod vault;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct VpnConfig {
+    // Stays the same through renames, see config::migrate
+    #[serde(default = "migrate::new_id")]
+    pub id: String,
     pub name: String,
     pub config_path: String,
     pub username: String,
     pub password: String,
+    // OpenVPN only: ask for a one-time code on each connect, see network::otp
+    #[serde(default)]
+    pub prompt_otp: bool,
+    // Connected at startup when auto-connect is on; set on one entry at most
     pub auto_connect: bool,
     #[serde(default)]
     pub vpn_type: VpnType,
+    #[serde(default)]
+    pub env_vars: BTreeMap<String, String>,
+    // OpenConnect gateway (host or URL) and optional auth group
+    #[serde(default)]
+    pub server: String,
+    #[serde(default)]
+    pub group: String,
+    // Block all traffic outside the tunnel while connected, see network::killswitch
+    #[serde(default)]
+    pub kill_switch: bool,
+    // Routes added after connecting, see network::routes
+    #[serde(default)]
+    pub split_routes: Vec<SplitRoute>,
+    // Applications run in their own network namespace, see network::netns
+    #[serde(default)]
+    pub app_tunnel: AppTunnel,
+    // Replace the DNS the VPN pushes while connected, see network::dns
+    #[serde(default)]
+    pub dns_servers: Vec<String>,
+    #[serde(default)]
+    pub dns_search_domains: Vec<String>,
+    // Reconnect when the tunnel drops on its own, see network::reconnect
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    // Installed as an openvpn-client@ or wg-quick@ unit, see system::services
+    #[serde(default)]
+    pub system_service: bool,
+    // Pings through the tunnel while connected, see network::health
+    #[serde(default)]
+    pub health: HealthCheck,
+    // OpenVPN only: the proxy it reaches the server through, see network::proxy
+    #[serde(default)]
+    pub proxy: VpnProxy,
+    // OpenVPN only: certificates and keys attached to the entry, see network::certs
+    #[serde(default)]
+    pub certificates: CertFiles,
+    // Custom only: the commands that connect, disconnect and check it
+    #[serde(default)]
+    pub custom: CustomCommands,
+    #[serde(default)]
+    pub style: EntryStyle,
+}
+
+// Shell command lines with {config}, {username}, {password}, {server}, {group} and {name}
+// placeholders; only connect is required
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct CustomCommands {
+    pub connect: String,
+    // Empty stops a client that stayed in the foreground after connecting
+    #[serde(default)]
+    pub disconnect: String,
+    // Exits with 0 while connected
+    #[serde(default)]
+    pub status: String,
+    // Tunnel interface, e.g. cscotun0 or ppp*, for the kill switch, routes and traffic counters
+    #[serde(default)]
+    pub interface: String,
+}
+
+// Retries after the tunnel went down without being disconnected from the app
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    pub max_retries: u32,
+    // Wait before the first attempt, doubled after each failed one up to max_delay_secs
+    pub base_delay_secs: u64,
+    pub max_delay_secs: u64,
+    // WireGuard only: re-handshake, then reconnect, when the handshake goes stale while connected
+    #[serde(default)]
+    pub on_stale_handshake: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_retries: 5,
+            base_delay_secs: 5,
+            max_delay_secs: 120,
+            on_stale_handshake: false,
+        }
+    }
+}
+
+// A host pinged through the tunnel while it is up, e.g. the VPN gateway or an internal server,
+// to notice a tunnel that is connected but passes no traffic
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct HealthCheck {
+    pub enabled: bool,
+    pub host: String,
+    pub interval_secs: u64,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            interval_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum ProxyKind {
+    #[default]
+    None,
+    Http,
+    Socks,
+}
+
+impl ProxyKind {
+    pub const ALL: [ProxyKind; 3] = [ProxyKind::None, ProxyKind::Http, ProxyKind::Socks];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProxyKind::None => "No proxy",
+            ProxyKind::Http => "HTTP",
+            ProxyKind::Socks => "SOCKS5",
+        }
+    }
+}
+
+// Credentials are optional; HTTP proxies get them with basic auth
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct VpnProxy {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+// Files attached to an OpenVPN entry, used instead of those its profile names; empty keeps the
+// profile's. Relative paths are managed copies, like config_path.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct CertFiles {
+    #[serde(default)]
+    pub ca: String,
+    #[serde(default)]
+    pub cert: String,
+    #[serde(default)]
+    pub key: String,
+    #[serde(default)]
+    pub tls_auth: String,
+}
+
+// Profiles tried in priority order when one fails to connect or keeps dropping, see
+// network::failover
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct FailoverPolicy {
+    pub enabled: bool,
+    // VPN entry names, most preferred first
+    pub order: Vec<String>,
+    // Drops within network::failover::DROP_WINDOW before a profile is passed over
+    pub max_drops: u32,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            order: Vec::new(),
+            max_drops: 3,
+        }
+    }
+}
+
+// One profile kept up while the app runs: connected at startup and reconnected whenever it
+// drops, see network::always_on
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct AlwaysOn {
+    #[serde(default)]
+    pub enabled: bool,
+    // VPN entry name
+    #[serde(default)]
+    pub vpn: String,
+    // The app is registered to start minimized at login, see system::autostart
+    #[serde(default)]
+    pub start_at_login: bool,
+}
+
+// Icon and accent color picked for an entry so its card stands out on a crowded dashboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct EntryStyle {
+    // One of ui::components::ENTRY_ICONS; empty uses the default icon for the entry
+    #[serde(default)]
+    pub icon: String,
+    // sRGB; None follows the theme
+    #[serde(default)]
+    pub color: Option<[u8; 3]>,
+}
+
+// A network sent through the tunnel, or around it via the regular default gateway
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct SplitRoute {
+    pub cidr: String,
+    pub via_tunnel: bool,
+}
+
+// Applications that alone go through the tunnel, or alone around it
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AppTunnel {
+    pub enabled: bool,
+    pub via_tunnel: bool,
+    // Command lines, run through sh
+    pub apps: Vec<String>,
+    pub launch_on_connect: bool,
+}
+
+impl Default for AppTunnel {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            via_tunnel: true,
+            apps: Vec::new(),
+            launch_on_connect: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 pub enum VpnType {
     OpenVpn,
     WireGuard,
+    // strongSwan on Linux, the built-in RAS client on Windows
+    Ikev2,
+    // Cisco AnyConnect-compatible gateways via openconnect
+    OpenConnect,
+    // Any other client, driven by the entry's own commands, see network::custom
+    Custom,
 }
 
 impl Default for VpnType {
@@ -24,55 +269,815 @@ impl Default for VpnType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// A machine and the ways to reach it. Each capability keeps its own settings and carries the
+// device's name, so the code acting on one doesn't need the device around it.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Device {
+    #[serde(default = "migrate::new_id")]
+    pub id: String,
+    pub name: String,
+    // Free-form label for grouping, e.g. "lab" or "office"
+    #[serde(default)]
+    pub tag: String,
+    #[serde(default)]
+    pub wol: Option<WolDevice>,
+    #[serde(default)]
+    pub rdp: Option<RdpConfig>,
+    #[serde(default)]
+    pub ssh: Option<SshConfig>,
+    #[serde(default)]
+    pub vnc: Option<VncConfig>,
+    #[serde(default)]
+    pub monitor: MonitorSettings,
+    #[serde(default)]
+    pub style: EntryStyle,
+    // Encrypted with the vault, e.g. a BIOS password or the iLO address
+    #[serde(default)]
+    pub notes: Vec<vault::SecretNote>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RdpConfig {
+    // Taken from the owning Device
+    #[serde(skip)]
     pub name: String,
     pub host: String,
     pub port: u16,
     pub username: String,
     pub password: String,
     pub domain: Option<String>,
+    #[serde(default)]
+    pub env_vars: BTreeMap<String, String>,
+    // Round trip measured before launching, see network::latency
+    #[serde(default)]
+    pub latency: LatencyPolicy,
+    #[serde(default)]
+    pub display: RdpDisplay,
+    #[serde(default)]
+    pub redirect: RdpRedirection,
+    // Windows: hand the username and password to mstsc through the Credential Manager, see
+    // network::rdp
+    #[serde(default)]
+    pub store_credentials: bool,
+    // Remote Desktop Gateway the session is tunnelled through, for hosts behind a corporate one
+    #[serde(default)]
+    pub gateway: Option<RdpGateway>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct RdpGateway {
+    // host or host:port
+    pub host: String,
+    // DOMAIN\user; empty signs in to the gateway with the session's own credentials
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+}
+
+// Local resources made available in the session
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct RdpRedirection {
+    pub clipboard: bool,
+    // Play the session's sound here
+    pub audio: bool,
+    pub microphone: bool,
+    // All local drives, or mount points on Linux and macOS
+    pub drives: bool,
+}
+
+impl Default for RdpRedirection {
+    fn default() -> Self {
+        Self {
+            clipboard: true,
+            audio: true,
+            microphone: false,
+            drives: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum RdpScreenMode {
+    Fullscreen,
+    Windowed,
+}
+
+// Size and scaling of the remote desktop, see network::rdp for how each client gets them
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct RdpDisplay {
+    pub mode: RdpScreenMode,
+    // Resolution of the remote desktop; 0 fits the local screen, or the window when windowed
+    #[serde(default)]
+    pub width: u32,
+    #[serde(default)]
+    pub height: u32,
+    // Scale of text and apps in the session, in percent, as in Windows' display settings
+    #[serde(default = "default_rdp_scale")]
+    pub scale: u32,
+    // Shrink the desktop to fit the window instead of showing scrollbars
+    #[serde(default)]
+    pub smart_sizing: bool,
+    // Span the session over all local monitors; always fullscreen, the size is theirs
+    #[serde(default)]
+    pub multimon: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_rdp_scale() -> u32 {
+    100
+}
+
+impl Default for RdpDisplay {
+    fn default() -> Self {
+        Self {
+            mode: RdpScreenMode::Fullscreen,
+            width: 0,
+            height: 0,
+            scale: default_rdp_scale(),
+            smart_sizing: false,
+            multimon: false,
+        }
+    }
+}
+
+impl RdpDisplay {
+    // Scale factors the server accepts
+    pub const SCALES: [u32; 5] = [100, 125, 150, 175, 200];
+
+    pub fn has_size(&self) -> bool {
+        !self.multimon && self.width > 0 && self.height > 0
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.multimon || self.mode == RdpScreenMode::Fullscreen
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum SlowLinkAction {
+    Warn,
+    // Start the session with the client's low-bandwidth settings
+    LowerQuality,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct LatencyPolicy {
+    pub enabled: bool,
+    pub max_rtt_ms: u32,
+    pub on_slow: SlowLinkAction,
+}
+
+impl Default for LatencyPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_rtt_ms: 150,
+            on_slow: SlowLinkAction::Warn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WolDevice {
+    #[serde(skip)]
     pub name: String,
     pub mac_address: String,
     pub ip_address: String,
     pub port: u16,
+    // An always-on machine on the device's LAN that sends the magic packet over SSH when the
+    // packets from here don't wake it, e.g. from another subnet or over a VPN
+    #[serde(default)]
+    pub relay: Option<SshConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SshConfig {
+    #[serde(skip)]
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub username: String,
+    // Private key file; empty leaves it to ssh's defaults and the agent
+    #[serde(default)]
+    pub key_path: String,
+    // A device's session only: wake it over WoL and wait for sshd before opening the session
+    #[serde(default)]
+    pub wake_first: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VncConfig {
+    #[serde(skip)]
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    // Viewer program, a name on the PATH or a full path; empty finds an installed one
+    #[serde(default)]
+    pub viewer: String,
+}
+
+// Periodic online checks for a device
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MonitorSettings {
+    pub enabled: bool,
+    // Address to probe; empty uses the first capability's host
+    #[serde(default)]
+    pub address: String,
+    // Unix time the maintenance window ends; while it lasts the device raises no notifications
+    // and its downtime doesn't count against availability
+    #[serde(default)]
+    pub maintenance_until: Option<u64>,
+}
+
+impl Default for MonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            address: String::new(),
+            maintenance_until: None,
+        }
+    }
+}
+
+impl Device {
+    pub fn new(name: &str) -> Self {
+        Self {
+            id: migrate::new_id(),
+            name: name.to_string(),
+            tag: String::new(),
+            wol: None,
+            rdp: None,
+            ssh: None,
+            vnc: None,
+            monitor: MonitorSettings::default(),
+            style: EntryStyle::default(),
+            notes: Vec::new(),
+        }
+    }
+    
+    // Icon when the device hasn't picked one: a desktop for RDP machines, a screen for VNC ones,
+    // else a laptop
+    pub fn default_icon(&self) -> &'static str {
+        if self.rdp.is_some() {
+            "🖥"
+        } else if self.vnc.is_some() {
+            "📺"
+        } else {
+            "💻"
+        }
+    }
+    
+    // Call after creating, renaming or moving capabilities between devices
+    pub fn sync_names(&mut self) {
+        if let Some(wol) = &mut self.wol {
+            wol.name = self.name.clone();
+            if let Some(relay) = &mut wol.relay {
+                relay.name = self.name.clone();
+            }
+        }
+        if let Some(rdp) = &mut self.rdp {
+            rdp.name = self.name.clone();
+        }
+        if let Some(ssh) = &mut self.ssh {
+            ssh.name = self.name.clone();
+        }
+        if let Some(vnc) = &mut self.vnc {
+            vnc.name = self.name.clone();
+        }
+    }
+    
+    // Labels of the configured capabilities, in display order
+    pub fn capabilities(&self) -> Vec<&'static str> {
+        [(self.wol.is_some(), "WoL"), (self.rdp.is_some(), "RDP"), (self.ssh.is_some(), "SSH"), (self.vnc.is_some(), "VNC")]
+            .into_iter()
+            .filter(|(present, _)| *present)
+            .map(|(_, label)| label)
+            .collect()
+    }
+    
+    // Address the periodic checks probe, None when monitoring is off or nothing is known
+    pub fn monitor_address(&self) -> Option<String> {
+        if !self.monitor.enabled {
+            return None;
+        }
+        if !self.monitor.address.trim().is_empty() {
+            return Some(self.monitor.address.trim().to_string());
+        }
+        // The WoL address may be the broadcast default, which says nothing about the machine
+        let wol = self.wol.as_ref().map(|w| w.ip_address.as_str()).filter(|ip| *ip != "255.255.255.255");
+        [wol, self.rdp.as_ref().map(|r| r.host.as_str()), self.ssh.as_ref().map(|s| s.host.as_str()),
+         self.vnc.as_ref().map(|v| v.host.as_str())]
+            .into_iter()
+            .flatten()
+            .find(|host| !host.trim().is_empty())
+            .map(|host| host.trim().to_string())
+    }
+    
+    pub fn in_maintenance(&self) -> bool {
+        self.maintenance_end().is_some()
+    }
+    
+    // End of the current maintenance window, None when there is none or it has passed
+    pub fn maintenance_end(&self) -> Option<chrono::DateTime<chrono::Local>> {
+        let end = chrono::DateTime::from_timestamp(self.monitor.maintenance_until? as i64, 0)?.with_timezone(&chrono::Local);
+        (end > chrono::Local::now()).then_some(end)
+    }
+    
+    pub fn start_maintenance(&mut self, duration: chrono::Duration) {
+        self.monitor.maintenance_until = Some((chrono::Local::now() + duration).timestamp() as u64);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
     pub vpn_configs: Vec<VpnConfig>,
-    pub rdp_configs: Vec<RdpConfig>,
-    pub wol_devices: Vec<WolDevice>,
+    // Replaced the separate rdp_configs and wol_devices lists, see migrate_legacy_entries
+    #[serde(default)]
+    pub devices: Vec<Device>,
     pub dark_mode: bool,
     #[serde(default)]
     pub auto_connect_vpn: bool,
+    #[serde(default = "default_true")]
+    pub check_captive_portal: bool,
+    // Copy imported VPN files next to config.json and store them as relative paths
+    #[serde(default)]
+    pub manage_config_files: bool,
+    #[serde(default)]
+    pub theme_schedule: ThemeSchedule,
+    #[serde(default)]
+    pub status_palette: StatusPalette,
+    // Draw online/offline/connecting as different shapes, not just different colors
+    #[serde(default)]
+    pub status_shapes: bool,
+    #[serde(default)]
+    pub scheduled_actions: Vec<schedule::ScheduledAction>,
+    // Proxy URL for update checks and downloads; empty uses the system proxy
+    #[serde(default)]
+    pub update_proxy: String,
+    #[serde(default)]
+    pub update_source: UpdateSource,
+    #[serde(default)]
+    pub web_dashboard: WebDashboard,
+    #[serde(default)]
+    pub notifications: Notifications,
+    // Duplicate groups the user marked as distinct, by DuplicateGroup::fingerprint
+    #[serde(default)]
+    pub ignored_duplicates: Vec<String>,
+    #[serde(default)]
+    pub wireguard_backend: WireGuardBackend,
+    // Linux: OpenVPN and WireGuard through NetworkManager, see network::nmcli
+    #[serde(default)]
+    pub use_network_manager: bool,
+    // Screenshot RDP and VNC sessions launched from the app for the device cards
+    #[serde(default)]
+    pub capture_thumbnails: bool,
+    // Terminal SSH sessions open in, a name on the PATH or a full path; empty finds one, see
+    // network::ssh
+    #[serde(default)]
+    pub ssh_terminal: String,
+    // Interface the exported router peers are added to; empty for wg0
+    #[serde(default)]
+    pub wg_router_interface: String,
+    // Passphrase check for encrypted notes, None until a passphrase is chosen
+    #[serde(default)]
+    pub vault: Option<vault::VaultSettings>,
+    // Master password check when the passwords and keys in this file are encrypted, see
+    // config::secrets
+    #[serde(default)]
+    pub encryption: Option<vault::VaultSettings>,
+    // Loaded with those fields still encrypted; cleared by secrets::unlock
+    #[serde(skip)]
+    pub locked: bool,
+    #[serde(default)]
+    pub failover: FailoverPolicy,
+    #[serde(default)]
+    pub always_on: AlwaysOn,
+    #[serde(default)]
+    pub language: Language,
+    #[serde(default)]
+    pub developer: DeveloperSettings,
+    #[serde(default)]
+    pub providers: ProviderAccounts,
+    // Entry names by id as of the last save, to notice renames made in this file; written by
+    // save, see config::migrate
+    #[serde(default)]
+    pub entry_names: BTreeMap<String, String>,
+}
+
+// Accounts with the VPN providers profiles are generated for, see network::providers
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ProviderAccounts {
+    #[serde(default)]
+    pub mullvad: MullvadAccount,
+    #[serde(default)]
+    pub proton: ProtonAccount,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct MullvadAccount {
+    #[serde(default)]
+    pub account_number: String,
+    // The WireGuard key registered as a device of the account, shared by every Mullvad profile;
+    // empty until the first profile is created
+    #[serde(default)]
+    pub private_key: String,
+    // The tunnel addresses Mullvad assigned to that key, comma-separated
+    #[serde(default)]
+    pub addresses: String,
+}
+
+// Proton has no API for these without its own login, so they are copied from the account page
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct ProtonAccount {
+    // The OpenVPN / IKEv2 credentials, not the Proton login
+    #[serde(default)]
+    pub openvpn_username: String,
+    #[serde(default)]
+    pub openvpn_password: String,
+    // Any .ovpn downloaded from Proton; its certificates and options are reused, the server changed
+    #[serde(default)]
+    pub openvpn_template: String,
+    // The PrivateKey of a WireGuard config made on the account page
+    #[serde(default)]
+    pub wireguard_private_key: String,
+}
+
+// Diagnostics for reports of a laggy UI, see ui::devtools. The Settings card only shows once
+// unlocked by clicking the version in About a few times.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct DeveloperSettings {
+    #[serde(default)]
+    pub unlocked: bool,
+    // Frame time overlay
+    #[serde(default)]
+    pub frame_stats: bool,
+    // Draws every frame instead of on input and timers, to measure the steady state
+    #[serde(default)]
+    pub continuous_repaint: bool,
+    // egui's own overlays: widget details on hover and the areas that take clicks
+    #[serde(default)]
+    pub debug_on_hover: bool,
+    #[serde(default)]
+    pub widget_hits: bool,
+    // egui's inspection and memory window
+    #[serde(default)]
+    pub inspection: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum UpdateSourceKind {
+    GitHub,
+    Manifest,
+}
+
+// Where update checks look: GitHub (or GitHub Enterprise) releases, or a JSON manifest on any
+// HTTP server for organizations that mirror releases internally
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct UpdateSource {
+    pub kind: UpdateSourceKind,
+    // e.g. https://api.github.com or https://github.example.com/api/v3
+    pub github_api_url: String,
+    // owner/name
+    pub github_repo: String,
+    pub manifest_url: String,
+}
+
+impl Default for UpdateSource {
+    fn default() -> Self {
+        Self {
+            kind: UpdateSourceKind::GitHub,
+            github_api_url: "https://api.github.com".to_string(),
+            github_repo: "EmmanouelKontos/vpn-aio-rust".to_string(),
+            manifest_url: String::new(),
+        }
+    }
+}
+
+impl UpdateSource {
+    // Human-facing page listing releases, if the source has one
+    pub fn releases_page(&self) -> Option<String> {
+        match self.kind {
+            UpdateSourceKind::GitHub => {
+                let api_url = self.github_api_url.trim_end_matches('/');
+                // api.github.com -> github.com, ghe.example.com/api/v3 -> ghe.example.com
+                let web_url = if api_url == "https://api.github.com" {
+                    "https://github.com"
+                } else {
+                    api_url.strip_suffix("/api/v3").unwrap_or(api_url)
+                };
+                Some(format!("{}/{}/releases", web_url, self.github_repo))
+            }
+            UpdateSourceKind::Manifest => None,
+        }
+    }
+}
+
+// Read-only status page served by the app, see network::dashboard
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct WebDashboard {
+    pub enabled: bool,
+    // Listen on all interfaces instead of localhost; requests must then carry the token
+    pub allow_lan: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for WebDashboard {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_lan: false,
+            port: 8787,
+            token: String::new(),
+        }
+    }
+}
+
+// Desktop notifications for device status changes, see system::notify
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Notifications {
+    pub enabled: bool,
+    pub quiet_hours: bool,
+    pub quiet_from: String,
+    pub quiet_until: String,
+    // Entries by name that never notify, e.g. a machine that sleeps on its own schedule
+    #[serde(default)]
+    pub muted: Vec<String>,
+    // Short tones for status changes, under the same quiet hours and mutes, see system::sound
+    #[serde(default)]
+    pub sounds: SoundCues,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            quiet_hours: false,
+            quiet_from: "22:00".to_string(),
+            quiet_until: "07:00".to_string(),
+            muted: Vec::new(),
+            sounds: SoundCues::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub struct SoundCues {
+    pub enabled: bool,
+    // Percent
+    pub volume: u8,
+    pub vpn_connected: bool,
+    pub vpn_dropped: bool,
+    // A device that came online after being sent a wake
+    pub device_woken: bool,
+    pub device_online: bool,
+    pub device_offline: bool,
+}
+
+impl Default for SoundCues {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            volume: 50,
+            vpn_connected: true,
+            vpn_dropped: true,
+            device_woken: true,
+            device_online: false,
+            device_offline: false,
+        }
+    }
+}
+
+impl Notifications {
+    // Whether quiet hours are on and cover the current local time
+    pub fn is_quiet(&self) -> bool {
+        if !self.quiet_hours {
+            return false;
+        }
+        let Ok(from) = chrono::NaiveTime::parse_from_str(self.quiet_from.trim(), "%H:%M") else { return false };
+        let Ok(until) = chrono::NaiveTime::parse_from_str(self.quiet_until.trim(), "%H:%M") else { return false };
+        let now = chrono::Local::now().time();
+        
+        if from <= until {
+            now >= from && now < until
+        } else {
+            // Quiet period wraps past midnight
+            now >= from || now < until
+        }
+    }
+    
+    pub fn is_muted(&self, name: &str) -> bool {
+        self.muted.iter().any(|muted| muted == name)
+    }
+    
+    pub fn set_muted(&mut self, name: &str, muted: bool) {
+        self.muted.retain(|entry| entry != name);
+        if muted {
+            self.muted.push(name.to_string());
+        }
+    }
+    
+    // Whether a notification about `name` should be shown right now
+    pub fn allows(&self, name: &str) -> bool {
+        self.enabled && !self.is_quiet() && !self.is_muted(name)
+    }
+}
+
+// How WireGuard tunnels are brought up, see network::wg_userspace
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum WireGuardBackend {
+    // The kernel module when it's there, otherwise a userspace implementation if one is found
+    #[default]
+    Auto,
+    // wg-quick (or the WireGuard service on Windows)
+    Kernel,
+    // boringtun or wireguard-go
+    Userspace,
+}
+
+// Colors used for online/offline/connecting/error states
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum StatusPalette {
+    #[default]
+    Standard,
+    HighContrast,
+    // Okabe-Ito colors, distinguishable with the common forms of color blindness
+    ColorblindSafe,
+}
+
+// Language of error messages, see crate::errors
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::German, Language::Spanish];
+    
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::Spanish => "Español",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum ThemeScheduleMode {
+    Off,
+    Fixed,
+    SunriseSunset,
+}
+
+// Switches between light and dark automatically, overriding `dark_mode` while enabled
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ThemeSchedule {
+    pub mode: ThemeScheduleMode,
+    pub light_from: String,
+    pub dark_from: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Default for ThemeSchedule {
+    fn default() -> Self {
+        Self {
+            mode: ThemeScheduleMode::Off,
+            light_from: "07:00".to_string(),
+            dark_from: "19:00".to_string(),
+            latitude: 0.0,
+            longitude: 0.0,
+        }
+    }
+}
+
+impl ThemeSchedule {
+    // Whether the schedule wants the dark theme right now, or None when it is off or misconfigured
+    pub fn prefers_dark(&self) -> Option<bool> {
+        match self.mode {
+            ThemeScheduleMode::Off => None,
+            ThemeScheduleMode::Fixed => {
+                let light_from = chrono::NaiveTime::parse_from_str(&self.light_from, "%H:%M").ok()?;
+                let dark_from = chrono::NaiveTime::parse_from_str(&self.dark_from, "%H:%M").ok()?;
+                let now = chrono::Local::now().time();
+                
+                let is_light = if light_from <= dark_from {
+                    now >= light_from && now < dark_from
+                } else {
+                    // Light period wraps past midnight
+                    now >= light_from || now < dark_from
+                };
+                Some(!is_light)
+            }
+            ThemeScheduleMode::SunriseSunset => {
+                let now = chrono::Utc::now().timestamp();
+                Some(!crate::system::sun::is_daylight(now, self.latitude, self.longitude))
+            }
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// Parses `KEY=VALUE` lines into environment variables for spawned clients
+pub fn parse_env_vars(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (key, value) = line.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+            Some((key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// The reverse of parse_env_vars, for putting an entry back into a form
+pub fn format_env_vars(env_vars: &BTreeMap<String, String>) -> String {
+    env_vars.iter().map(|(key, value)| format!("{}={}\n", key, value)).collect()
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             vpn_configs: Vec::new(),
-            rdp_configs: Vec::new(),
-            wol_devices: Vec::new(),
+            devices: Vec::new(),
             dark_mode: true,
             auto_connect_vpn: false,
+            check_captive_portal: true,
+            manage_config_files: false,
+            theme_schedule: ThemeSchedule::default(),
+            status_palette: StatusPalette::Standard,
+            status_shapes: false,
+            scheduled_actions: Vec::new(),
+            update_proxy: String::new(),
+            update_source: UpdateSource::default(),
+            web_dashboard: WebDashboard::default(),
+            notifications: Notifications::default(),
+            ignored_duplicates: Vec::new(),
+            wireguard_backend: WireGuardBackend::Auto,
+            use_network_manager: false,
+            capture_thumbnails: false,
+            ssh_terminal: String::new(),
+            wg_router_interface: String::new(),
+            vault: None,
+            encryption: None,
+            locked: false,
+            failover: FailoverPolicy::default(),
+            always_on: AlwaysOn::default(),
+            language: Language::English,
+            developer: DeveloperSettings::default(),
+            providers: ProviderAccounts::default(),
+            entry_names: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
+    // JSON Schema of config.json, derived from the serde types so external tools validate
+    // against exactly what load accepts
+    pub fn json_schema() -> String {
+        serde_json::to_string_pretty(&schemars::schema_for!(Config)).unwrap_or_default()
+    }
+    
     pub fn load() -> anyhow::Result<Self> {
         let config_path = Self::config_path();
         if config_path.exists() {
             let content = std::fs::read_to_string(config_path)?;
-            let mut config: Self = serde_json::from_str(&content)?;
+            let mut value: serde_json::Value = serde_json::from_str(&content)?;
+            let legacy_devices = migrate_legacy_entries(&mut value);
+            let mut config: Self = serde_json::from_value(value)?;
+            
+            config.devices.extend(legacy_devices);
+            config.locked = config.encryption.is_some();
+            for device in &mut config.devices {
+                device.sync_names();
+            }
+            
+            // Auto-connect used to take the first entry; it is marked so startup keeps doing that
+            if config.auto_connect_vpn && config.startup_vpn().is_none() {
+                if let Some(first) = config.vpn_configs.first_mut() {
+                    first.auto_connect = true;
+                }
+            }
             
             // Ensure all VPN configs have a type (for backwards compatibility)
             for vpn_config in &mut config.vpn_configs {
+                if !vpn_config.uses_config_file() {
+                    continue;
+                }
                 if vpn_config.config_path.ends_with(".ovpn") {
                     vpn_config.vpn_type = VpnType::OpenVpn;
                 } else if vpn_config.config_path.ends_with(".conf") {
@@ -91,15 +1096,225 @@ impl Config {
         if let Some(parent) = config_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
-        let content = serde_json::to_string_pretty(self)?;
+        let mut value = if self.encryption.is_some() {
+            let mut sealed = self.clone();
+            secrets::seal(&mut sealed)?;
+            serde_json::to_value(&sealed)?
+        } else {
+            serde_json::to_value(self)?
+        };
+        value["entry_names"] = serde_json::to_value(migrate::entry_names(self))?;
+        let content = serde_json::to_string_pretty(&value)?;
         std::fs::write(config_path, content)?;
         Ok(())
     }
 
-    fn config_path() -> PathBuf {
+    pub fn device(&self, name: &str) -> Option<&Device> {
+        self.devices.iter().find(|d| d.name == name)
+    }
+    
+    pub fn wol_devices(&self) -> impl Iterator<Item = &WolDevice> {
+        self.devices.iter().filter_map(|d| d.wol.as_ref())
+    }
+    
+    pub fn config_path() -> PathBuf {
+        Self::config_dir().join("config.json")
+    }
+
+    pub fn effective_dark_mode(&self) -> bool {
+        self.theme_schedule.prefers_dark().unwrap_or(self.dark_mode)
+    }
+
+    pub fn config_dir() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("vpn-manager")
-            .join("config.json")
     }
-}
\ No newline at end of file
+
+    pub fn managed_files_dir() -> PathBuf {
+        Self::config_dir().join(MANAGED_DIR)
+    }
+
+    // Copies a VPN config file into the managed directory and returns the path to store,
+    // relative to the config directory
+    pub fn import_config_file(source: &Path) -> anyhow::Result<String> {
+        let file_name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid config file name: {}", source.display()))?;
+        Self::store_managed_file(file_name, &std::fs::read(source)?)
+    }
+
+    // Saves a VPN config file under the given name in the managed directory and returns the
+    // path to store
+    pub fn store_managed_file(file_name: &str, content: &[u8]) -> anyhow::Result<String> {
+        let managed_dir = Self::managed_files_dir();
+        std::fs::create_dir_all(&managed_dir)?;
+
+        // WireGuard derives the interface name from the file stem, so keep the original
+        // name unless a different file already uses it
+        let stem = Path::new(file_name).file_stem().and_then(|s| s.to_str()).unwrap_or(file_name);
+        let extension = Path::new(file_name).extension().and_then(|e| e.to_str());
+        let mut target_name = file_name.to_string();
+        for n in 2.. {
+            let target = managed_dir.join(&target_name);
+            match std::fs::read(&target) {
+                Ok(existing) if existing == *content => break,
+                Ok(_) => {
+                    target_name = match extension {
+                        Some(ext) => format!("{}-{}.{}", stem, n, ext),
+                        None => format!("{}-{}", stem, n),
+                    };
+                }
+                Err(_) => {
+                    std::fs::write(&target, content)?;
+                    restrict_permissions(&target);
+                    break;
+                }
+            }
+        }
+
+        Ok(format!("{}/{}", MANAGED_DIR, target_name))
+    }
+
+    // The entry connected at startup while auto-connect is on
+    pub fn startup_vpn(&self) -> Option<&VpnConfig> {
+        self.vpn_configs.iter().find(|vpn_config| vpn_config.auto_connect)
+    }
+
+    // Makes the named entry the only one connected at startup, or none
+    pub fn set_startup_vpn(&mut self, name: Option<&str>) {
+        for vpn_config in &mut self.vpn_configs {
+            vpn_config.auto_connect = Some(vpn_config.name.as_str()) == name;
+        }
+    }
+
+    // The entry kept up while always-on is enabled
+    pub fn always_on_vpn(&self) -> Option<&VpnConfig> {
+        if !self.always_on.enabled {
+            return None;
+        }
+        self.vpn_configs.iter().find(|vpn_config| vpn_config.name == self.always_on.vpn)
+    }
+    
+    // The profiles to try for a connection to `first`: it, then the rest of the failover order.
+    // Just `first` while failover is off or it isn't in the order.
+    pub fn failover_chain(&self, first: &str) -> Vec<&VpnConfig> {
+        let find = |name: &str| self.vpn_configs.iter().find(|vpn_config| vpn_config.name == name);
+        let mut chain: Vec<&VpnConfig> = find(first).into_iter().collect();
+        if self.failover.enabled && self.failover.order.iter().any(|name| name == first) {
+            chain.extend(self.failover.order.iter().filter(|name| *name != first).filter_map(|name| find(name)));
+        }
+        chain
+    }
+    
+    // Moves every VPN entry that still points outside the managed directory into it
+    pub fn adopt_config_files(&mut self) -> anyhow::Result<usize> {
+        let mut adopted = 0;
+        for vpn_config in &mut self.vpn_configs {
+            if !vpn_config.uses_config_file() || Path::new(&vpn_config.config_path).is_relative() {
+                continue;
+            }
+            vpn_config.config_path = Self::import_config_file(Path::new(&vpn_config.config_path))?;
+            adopted += 1;
+        }
+        Ok(adopted)
+    }
+}
+
+const MANAGED_DIR: &str = "profiles";
+
+impl VpnConfig {
+    // IKEv2 entries name a system connection and OpenConnect entries a server, instead of
+    // pointing at a file; custom commands may use one or not
+    pub fn uses_config_file(&self) -> bool {
+        !matches!(self.vpn_type, VpnType::Ikev2 | VpnType::OpenConnect | VpnType::Custom)
+    }
+
+    // Relative paths are stored for managed files and resolve against the config directory
+    pub fn resolved_config_path(&self) -> String {
+        let path = Path::new(&self.config_path);
+        if path.is_relative() && !self.config_path.is_empty() {
+            Config::config_dir().join(path).display().to_string()
+        } else {
+            self.config_path.clone()
+        }
+    }
+}
+
+// VPN files usually embed private keys
+fn restrict_permissions(path: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+    }
+}
+
+// Configs from before devices kept RDP connections and WoL devices in two lists. Each WoL device
+// becomes a device; an RDP connection joins the WoL device it was linked to or shares a name
+// with, otherwise it becomes a device of its own.
+fn migrate_legacy_entries(value: &mut serde_json::Value) -> Vec<Device> {
+    let Some(object) = value.as_object_mut() else { return Vec::new() };
+    let wol_entries = object.remove("wol_devices");
+    let rdp_entries = object.remove("rdp_configs");
+    
+    let entries = |entries: Option<serde_json::Value>| match entries {
+        Some(serde_json::Value::Array(entries)) => entries,
+        _ => Vec::new(),
+    };
+    let text = |entry: &serde_json::Value, key: &str| entry.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    
+    let mut devices: Vec<Device> = Vec::new();
+    
+    for entry in entries(wol_entries) {
+        let mut device = Device::new(&text(&entry, "name"));
+        device.tag = text(&entry, "tag");
+        device.monitor.maintenance_until = entry.get("maintenance_until").and_then(|v| v.as_u64());
+        match serde_json::from_value::<WolDevice>(entry) {
+            Ok(wol) => device.wol = Some(wol),
+            Err(e) => log::warn!("Dropping unreadable WoL device {}: {}", device.name, e),
+        }
+        devices.push(device);
+    }
+    
+    for entry in entries(rdp_entries) {
+        let name = text(&entry, "name");
+        let linked = text(&entry, "wol_device");
+        let tag = text(&entry, "tag");
+        let rdp = match serde_json::from_value::<RdpConfig>(entry) {
+            Ok(rdp) => rdp,
+            Err(e) => {
+                log::warn!("Dropping unreadable RDP connection {}: {}", name, e);
+                continue;
+            }
+        };
+        
+        let owner = devices.iter().position(|d| d.rdp.is_none() && !linked.is_empty() && d.name == linked)
+            .or_else(|| devices.iter().position(|d| d.rdp.is_none() && d.name == name));
+        match owner {
+            Some(index) => {
+                let device = &mut devices[index];
+                if device.tag.is_empty() {
+                    device.tag = tag;
+                }
+                device.rdp = Some(rdp);
+            }
+            None => {
+                let mut device = Device::new(&name);
+                device.tag = tag;
+                device.rdp = Some(rdp);
+                devices.push(device);
+            }
+        }
+    }
+    
+    if !devices.is_empty() {
+        log::info!("Migrated {} RDP/WoL entries to devices", devices.len());
+    }
+    devices
+}
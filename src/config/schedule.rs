@@ -0,0 +1,79 @@
+use super::Config;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Upcoming runs closer than this get a countdown badge on their card
+pub const UPCOMING_WINDOW_SECS: i64 = 24 * 3600;
+// Runs missed by more than this (e.g. while suspended) are skipped rather than fired late
+const MISSED_GRACE_SECS: i64 = 5 * 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+pub enum ScheduledActionKind {
+    WakeDevice,
+    ConnectVpn,
+}
+
+// Recurring action at a local time of day, targeting a WoL device or VPN by name
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScheduledAction {
+    pub kind: ScheduledActionKind,
+    pub target: String,
+    pub time: String,
+    // Monday first
+    pub days: [bool; 7],
+    pub enabled: bool,
+}
+
+impl ScheduledAction {
+    pub fn new(kind: ScheduledActionKind, target: String, time: String) -> Self {
+        Self {
+            kind,
+            target,
+            time,
+            days: [true; 7],
+            enabled: true,
+        }
+    }
+
+    pub fn parsed_time(&self) -> Option<NaiveTime> {
+        NaiveTime::parse_from_str(self.time.trim(), "%H:%M").ok()
+    }
+
+    // First run strictly after `after`, or None when disabled, misconfigured or on no days
+    pub fn next_run(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        if !self.enabled {
+            return None;
+        }
+        let time = self.parsed_time()?;
+
+        (0..=7).find_map(|offset| {
+            let date = after.date_naive() + Duration::days(offset);
+            if !self.days[date.weekday().num_days_from_monday() as usize] {
+                return None;
+            }
+            // Skipped if the time doesn't exist that day (DST gap)
+            let run = date.and_time(time).and_local_timezone(Local).earliest()?;
+            (run > after).then_some(run)
+        })
+    }
+
+    // Whether a run fell in (since, now], so each run fires once even if frames are sparse
+    pub fn is_due(&self, since: DateTime<Local>, now: DateTime<Local>) -> bool {
+        self.next_run(since)
+            .is_some_and(|run| run <= now && (now - run).num_seconds() <= MISSED_GRACE_SECS)
+    }
+}
+
+impl Config {
+    // Soonest upcoming run for the given target, if it's within the badge window
+    pub fn upcoming_action(&self, kind: ScheduledActionKind, target: &str) -> Option<DateTime<Local>> {
+        let now = Local::now();
+        self.scheduled_actions
+            .iter()
+            .filter(|action| action.kind == kind && action.target == target)
+            .filter_map(|action| action.next_run(now))
+            .filter(|run| (*run - now).num_seconds() <= UPCOMING_WINDOW_SECS)
+            .min()
+    }
+}
@@ -0,0 +1,126 @@
+use super::vault;
+use super::Config;
+use crate::errors::{self, ErrorCode};
+use anyhow::Result;
+use std::sync::Mutex;
+
+// Passwords, keys and tokens in config.json encrypted under a master password, for machines
+// where nothing else protects the file. Each field is sealed on its own with the vault's
+// AES-256-GCM, so the rest of the file stays readable and diffable. The app loads the config
+// with the fields still sealed and asks for the password before doing anything with it; the
+// key is then kept in memory until the app exits, to seal the fields again on every save.
+
+// Marks a sealed value; the rest is the vault's base64 of nonce, ciphertext and tag
+const PREFIX: &str = "sealed:";
+
+static KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+// The fields that are sealed
+fn sensitive(config: &mut Config) -> Vec<&mut String> {
+    let mut fields = Vec::new();
+    for vpn_config in &mut config.vpn_configs {
+        fields.push(&mut vpn_config.password);
+        fields.push(&mut vpn_config.proxy.password);
+    }
+    for device in &mut config.devices {
+        if let Some(rdp) = &mut device.rdp {
+            fields.push(&mut rdp.password);
+            if let Some(gateway) = &mut rdp.gateway {
+                fields.push(&mut gateway.password);
+            }
+        }
+    }
+    fields.push(&mut config.providers.mullvad.account_number);
+    fields.push(&mut config.providers.mullvad.private_key);
+    fields.push(&mut config.providers.proton.openvpn_password);
+    fields.push(&mut config.providers.proton.wireguard_private_key);
+    fields.push(&mut config.web_dashboard.token);
+    fields
+}
+
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+// Encrypts the fields in place, for the copy Config::save writes. Fields already sealed stay as
+// they are, which is what a config still locked holds.
+pub fn seal(config: &mut Config) -> Result<()> {
+    let key = *KEY.lock().unwrap();
+    for field in sensitive(config) {
+        if field.is_empty() || is_sealed(field) {
+            continue;
+        }
+        let Some(key) = &key else {
+            return Err(errors::error(ErrorCode::ConfigLocked, ""));
+        };
+        *field = format!("{}{}", PREFIX, vault::seal(key, field)?);
+    }
+    Ok(())
+}
+
+// Decrypts the fields of the loaded config and keeps the key for saving
+pub fn unlock(config: &mut Config, password: &str) -> Result<()> {
+    let settings = config.encryption.clone().ok_or_else(|| anyhow::anyhow!("The config isn't encrypted"))?;
+    let key = vault::key_for(&settings, password)?;
+
+    // All or nothing, a field that doesn't open leaves the config locked
+    let mut fields = sensitive(config);
+    let mut opened = Vec::new();
+    for field in fields.iter() {
+        opened.push(match field.strip_prefix(PREFIX) {
+            Some(sealed) => Some(vault::open(&key, sealed)?),
+            None => None,
+        });
+    }
+    for (field, text) in fields.iter_mut().zip(opened) {
+        if let Some(text) = text {
+            **field = text;
+        }
+    }
+
+    *KEY.lock().unwrap() = Some(key);
+    config.locked = false;
+    log::info!("Config unlocked");
+    Ok(())
+}
+
+// Turns encryption on, or changes the master password; the caller saves the config to re-encrypt
+pub fn set_password(config: &mut Config, password: &str) -> Result<()> {
+    if config.locked {
+        return Err(errors::error(ErrorCode::ConfigLocked, ""));
+    }
+    let (settings, key) = vault::new_key(password)?;
+    config.encryption = Some(settings);
+    *KEY.lock().unwrap() = Some(key);
+    Ok(())
+}
+
+// The current password, asked again before turning encryption off or changing it
+pub fn verify(config: &Config, password: &str) -> Result<()> {
+    match &config.encryption {
+        Some(settings) => vault::key_for(settings, password).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+// Turns encryption off; the next save writes the fields in plain text
+pub fn disable(config: &mut Config) {
+    config.encryption = None;
+    *KEY.lock().unwrap() = None;
+}
+
+// For a forgotten password: drops the sealed fields, which can't be read anymore, and turns
+// encryption off. Returns how many fields were cleared.
+pub fn reset(config: &mut Config) -> usize {
+    let mut cleared = 0;
+    for field in sensitive(config) {
+        if is_sealed(field) {
+            field.clear();
+            cleared += 1;
+        }
+    }
+    disable(config);
+    config.locked = false;
+    log::warn!("Config encryption reset, {} sealed fields cleared", cleared);
+    cleared
+}
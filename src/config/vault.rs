@@ -0,0 +1,176 @@
+use crate::errors::{self, ErrorCode};
+use anyhow::Result;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Secrets encrypted under a passphrase: AES-256-GCM with a key derived by PBKDF2-HMAC-SHA256, so
+// config.json only holds ciphertext. The key is kept in memory from unlocking until locking, or
+// until it times out, and is never written anywhere.
+
+const ITERATIONS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+// Encrypted when the vault is created; decrypting it tells a wrong passphrase from a right one
+const CHECK_TEXT: &str = "vpn-manager vault";
+// Locked again this long after unlocking
+pub const AUTO_LOCK: Duration = Duration::from_secs(10 * 60);
+
+static KEY: Mutex<Option<([u8; 32], Instant)>> = Mutex::new(None);
+
+// What config.json holds about the vault; no key material
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct VaultSettings {
+    pub salt: String,
+    pub check: String,
+}
+
+// A note attached to a device; the title stays readable, the text only after unlocking
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SecretNote {
+    pub title: String,
+    pub sealed: String,
+}
+
+impl SecretNote {
+    pub fn new(title: &str, text: &str) -> Result<Self> {
+        Ok(Self {
+            title: title.trim().to_string(),
+            sealed: encrypt(text)?,
+        })
+    }
+
+    pub fn text(&self) -> Result<String> {
+        decrypt(&self.sealed)
+    }
+}
+
+// Sets up the vault with a new passphrase and leaves it unlocked
+pub fn create(passphrase: &str) -> Result<VaultSettings> {
+    let (settings, key) = new_key(passphrase)?;
+    *KEY.lock().unwrap() = Some((key, Instant::now()));
+    log::info!("Vault created");
+    Ok(settings)
+}
+
+pub fn unlock(settings: &VaultSettings, passphrase: &str) -> Result<()> {
+    let key = key_for(settings, passphrase)?;
+    *KEY.lock().unwrap() = Some((key, Instant::now()));
+    Ok(())
+}
+
+// A key derived from the passphrase with a fresh salt, and the settings to check it against later.
+// Also used for the master password of config.json, see config::secrets.
+pub fn new_key(passphrase: &str) -> Result<(VaultSettings, [u8; 32])> {
+    if passphrase.is_empty() {
+        return Err(anyhow::anyhow!("The passphrase can't be empty"));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new().fill(&mut salt).map_err(|_| anyhow::anyhow!("No random numbers available"))?;
+    let key = derive_key(passphrase, &salt);
+
+    let settings = VaultSettings {
+        salt: encode(&salt),
+        check: seal(&key, CHECK_TEXT)?,
+    };
+    Ok((settings, key))
+}
+
+// The key for the passphrase, if it is the one the settings were made with
+pub fn key_for(settings: &VaultSettings, passphrase: &str) -> Result<[u8; 32]> {
+    let salt = decode(&settings.salt)?;
+    let key = derive_key(passphrase, &salt);
+    if open(&key, &settings.check).ok().as_deref() != Some(CHECK_TEXT) {
+        return Err(errors::error(ErrorCode::WrongPassphrase, ""));
+    }
+    Ok(key)
+}
+
+pub fn lock() {
+    *KEY.lock().unwrap() = None;
+}
+
+pub fn is_unlocked() -> bool {
+    current_key().is_some()
+}
+
+// Time left before the vault locks itself
+pub fn remaining() -> Option<Duration> {
+    KEY.lock().unwrap().as_ref().and_then(|(_, unlocked_at)| AUTO_LOCK.checked_sub(unlocked_at.elapsed()))
+}
+
+pub fn encrypt(text: &str) -> Result<String> {
+    let key = current_key().ok_or_else(|| errors::error(ErrorCode::VaultLocked, ""))?;
+    seal(&key, text)
+}
+
+pub fn decrypt(sealed: &str) -> Result<String> {
+    let key = current_key().ok_or_else(|| errors::error(ErrorCode::VaultLocked, ""))?;
+    open(&key, sealed)
+}
+
+fn current_key() -> Option<[u8; 32]> {
+    let mut key = KEY.lock().unwrap();
+    if key.as_ref().is_some_and(|(_, unlocked_at)| unlocked_at.elapsed() >= AUTO_LOCK) {
+        *key = None;
+        log::info!("Vault locked after {} minutes", AUTO_LOCK.as_secs() / 60);
+    }
+    key.as_ref().map(|(key, _)| *key)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    ring::pbkdf2::derive(
+        ring::pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(ITERATIONS).unwrap(),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    key
+}
+
+// Base64 of a fresh nonce followed by the ciphertext and its tag
+pub fn seal(key: &[u8; 32], text: &str) -> Result<String> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| anyhow::anyhow!("No random numbers available"))?;
+    let mut data = text.as_bytes().to_vec();
+    aead_key(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(data);
+    Ok(encode(&sealed))
+}
+
+pub fn open(key: &[u8; 32], sealed: &str) -> Result<String> {
+    let sealed = decode(sealed)?;
+    if sealed.len() < NONCE_LEN {
+        return Err(anyhow::anyhow!("Encrypted data is truncated"));
+    }
+    let (nonce, data) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow::anyhow!("Invalid nonce"))?;
+    let mut data = data.to_vec();
+    let text = aead_key(key)?
+        .open_in_place(nonce, Aad::empty(), &mut data)
+        .map_err(|_| anyhow::anyhow!("Encrypted data doesn't match the vault key"))?;
+    Ok(String::from_utf8(text.to_vec())?)
+}
+
+fn aead_key(key: &[u8; 32]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow::anyhow!("Invalid vault key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn encode(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
+
+fn decode(text: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::STANDARD.decode(text.trim())?)
+}
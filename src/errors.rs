@@ -0,0 +1,532 @@
+use crate::config::Language;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Errors the user can do something about, each with a stable code. The code stays the same in
+// every language, so it can be searched for in docs/errors.md and in bug reports; the message and
+// the hint on what to do follow the language picked in Settings. Coded errors travel inside
+// anyhow::Error like any other, and whatever shows an error finds the code in its chain.
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+const DOCS_URL: &str = "https://github.com/EmmanouelKontos/vpn-aio-rust/blob/main/docs/errors.md";
+
+pub fn language() -> Language {
+    match LANGUAGE.load(Ordering::Relaxed) {
+        x if x == Language::German as u8 => Language::German,
+        x if x == Language::Spanish as u8 => Language::Spanish,
+        _ => Language::English,
+    }
+}
+
+pub fn set_language(language: Language) {
+    LANGUAGE.store(language as u8, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCode {
+    VpnClientMissing,
+    VpnConfigMissing,
+    VpnCredentialsMissing,
+    VpnAuthFailed,
+    VpnConnectTimeout,
+    VpnKeyPassword,
+    TunnelNotFound,
+    KillSwitchFailed,
+    VpnOtpMissing,
+    VpnClientFailed,
+    VpnServerMissing,
+    KillSwitchNoServer,
+    RdpClientMissing,
+    RdpClientFailed,
+    SshClientMissing,
+    TerminalMissing,
+    TerminalNotFound,
+    VncViewerMissing,
+    VncViewerNotFound,
+    InvalidMac,
+    WakeFailed,
+    NoDisplay,
+    SshWithoutDisplay,
+    ScreenshotToolMissing,
+    PackageManagerUnknown,
+    SystemdMissing,
+    UpdateCheckFailed,
+    UpdateAssetMissing,
+    VaultLocked,
+    WrongPassphrase,
+    ConfigLocked,
+}
+
+// Every code, for the tests below
+#[cfg(test)]
+const ALL: &[ErrorCode] = &[
+    ErrorCode::VpnClientMissing,
+    ErrorCode::VpnConfigMissing,
+    ErrorCode::VpnCredentialsMissing,
+    ErrorCode::VpnAuthFailed,
+    ErrorCode::VpnConnectTimeout,
+    ErrorCode::VpnKeyPassword,
+    ErrorCode::TunnelNotFound,
+    ErrorCode::KillSwitchFailed,
+    ErrorCode::VpnOtpMissing,
+    ErrorCode::VpnClientFailed,
+    ErrorCode::VpnServerMissing,
+    ErrorCode::KillSwitchNoServer,
+    ErrorCode::RdpClientMissing,
+    ErrorCode::RdpClientFailed,
+    ErrorCode::SshClientMissing,
+    ErrorCode::TerminalMissing,
+    ErrorCode::TerminalNotFound,
+    ErrorCode::VncViewerMissing,
+    ErrorCode::VncViewerNotFound,
+    ErrorCode::InvalidMac,
+    ErrorCode::WakeFailed,
+    ErrorCode::NoDisplay,
+    ErrorCode::SshWithoutDisplay,
+    ErrorCode::ScreenshotToolMissing,
+    ErrorCode::PackageManagerUnknown,
+    ErrorCode::SystemdMissing,
+    ErrorCode::UpdateCheckFailed,
+    ErrorCode::UpdateAssetMissing,
+    ErrorCode::VaultLocked,
+    ErrorCode::WrongPassphrase,
+    ErrorCode::ConfigLocked,
+];
+
+impl ErrorCode {
+    // Never renumbered; retired codes aren't reused
+    pub fn id(self) -> &'static str {
+        match self {
+            ErrorCode::VpnClientMissing => "VPN-001",
+            ErrorCode::VpnConfigMissing => "VPN-002",
+            ErrorCode::VpnCredentialsMissing => "VPN-003",
+            ErrorCode::VpnAuthFailed => "VPN-004",
+            ErrorCode::VpnConnectTimeout => "VPN-005",
+            ErrorCode::VpnKeyPassword => "VPN-006",
+            ErrorCode::TunnelNotFound => "VPN-007",
+            ErrorCode::KillSwitchFailed => "VPN-008",
+            ErrorCode::VpnOtpMissing => "VPN-009",
+            ErrorCode::VpnClientFailed => "VPN-010",
+            ErrorCode::VpnServerMissing => "VPN-011",
+            ErrorCode::KillSwitchNoServer => "VPN-012",
+            ErrorCode::RdpClientMissing => "RDP-001",
+            ErrorCode::RdpClientFailed => "RDP-002",
+            ErrorCode::SshClientMissing => "SSH-001",
+            ErrorCode::TerminalMissing => "SSH-002",
+            ErrorCode::TerminalNotFound => "SSH-003",
+            ErrorCode::VncViewerMissing => "VNC-001",
+            ErrorCode::VncViewerNotFound => "VNC-002",
+            ErrorCode::InvalidMac => "WOL-001",
+            ErrorCode::WakeFailed => "WOL-002",
+            ErrorCode::NoDisplay => "SYS-001",
+            ErrorCode::SshWithoutDisplay => "SYS-002",
+            ErrorCode::ScreenshotToolMissing => "SYS-003",
+            ErrorCode::PackageManagerUnknown => "SYS-004",
+            ErrorCode::SystemdMissing => "SYS-005",
+            ErrorCode::UpdateCheckFailed => "UPD-001",
+            ErrorCode::UpdateAssetMissing => "UPD-002",
+            ErrorCode::VaultLocked => "VLT-001",
+            ErrorCode::WrongPassphrase => "VLT-002",
+            ErrorCode::ConfigLocked => "VLT-003",
+        }
+    }
+
+    pub fn docs_url(self) -> String {
+        format!("{}#{}", DOCS_URL, self.id().to_lowercase())
+    }
+
+    // What went wrong; {} is replaced with the error's argument
+    pub fn message(self, language: Language) -> &'static str {
+        let [en, de, es] = match self {
+            ErrorCode::VpnClientMissing => [
+                "{} is not installed",
+                "{} ist nicht installiert",
+                "{} no está instalado",
+            ],
+            ErrorCode::VpnConfigMissing => [
+                "VPN config file not found: {}",
+                "VPN-Konfigurationsdatei nicht gefunden: {}",
+                "No se encontró el archivo de configuración VPN: {}",
+            ],
+            ErrorCode::VpnCredentialsMissing => [
+                "The server asks for a username and password, but none are saved for {}",
+                "Der Server verlangt Benutzername und Passwort, für {} sind aber keine gespeichert",
+                "El servidor pide usuario y contraseña, pero no hay ninguno guardado para {}",
+            ],
+            ErrorCode::VpnAuthFailed => [
+                "The server rejected the username or password",
+                "Der Server hat Benutzername oder Passwort abgelehnt",
+                "El servidor rechazó el usuario o la contraseña",
+            ],
+            ErrorCode::VpnConnectTimeout => [
+                "The VPN did not connect within {} seconds",
+                "Das VPN hat sich nicht innerhalb von {} Sekunden verbunden",
+                "La VPN no se conectó en {} segundos",
+            ],
+            ErrorCode::VpnKeyPassword => [
+                "The private key is encrypted and no password is set",
+                "Der private Schlüssel ist verschlüsselt und es ist kein Passwort gesetzt",
+                "La clave privada está cifrada y no hay contraseña configurada",
+            ],
+            ErrorCode::TunnelNotFound => [
+                "Tunnel interface not found",
+                "Tunnel-Schnittstelle nicht gefunden",
+                "No se encontró la interfaz del túnel",
+            ],
+            ErrorCode::KillSwitchFailed => [
+                "Failed to update kill switch rules: {}",
+                "Kill-Switch-Regeln konnten nicht geändert werden: {}",
+                "No se pudieron actualizar las reglas del kill switch: {}",
+            ],
+            ErrorCode::VpnOtpMissing => [
+                "The server asks for a one-time code, and none was entered for {}",
+                "Der Server verlangt einen Einmalcode, für {} wurde aber keiner eingegeben",
+                "El servidor pide un código de un solo uso, pero no se introdujo ninguno para {}",
+            ],
+            ErrorCode::VpnClientFailed => [
+                "The VPN client failed: {}",
+                "Der VPN-Client ist fehlgeschlagen: {}",
+                "El cliente VPN falló: {}",
+            ],
+            ErrorCode::VpnServerMissing => [
+                "No server set for {}",
+                "Für {} ist kein Server eingetragen",
+                "No hay ningún servidor configurado para {}",
+            ],
+            ErrorCode::KillSwitchNoServer => [
+                "Couldn't determine the VPN server address for {}",
+                "Die Adresse des VPN-Servers für {} konnte nicht ermittelt werden",
+                "No se pudo determinar la dirección del servidor VPN de {}",
+            ],
+            ErrorCode::RdpClientMissing => [
+                "No RDP client compatible with the {} session was found",
+                "Kein RDP-Client gefunden, der mit der {}-Sitzung funktioniert",
+                "No se encontró ningún cliente RDP compatible con la sesión {}",
+            ],
+            ErrorCode::RdpClientFailed => [
+                "The RDP client failed: {}",
+                "Der RDP-Client ist fehlgeschlagen: {}",
+                "El cliente RDP falló: {}",
+            ],
+            ErrorCode::SshClientMissing => [
+                "ssh was not found",
+                "ssh wurde nicht gefunden",
+                "No se encontró ssh",
+            ],
+            ErrorCode::TerminalMissing => [
+                "No terminal emulator found",
+                "Kein Terminalemulator gefunden",
+                "No se encontró ningún emulador de terminal",
+            ],
+            ErrorCode::TerminalNotFound => [
+                "The terminal {} set in Settings wasn't found",
+                "Das in den Einstellungen gewählte Terminal {} wurde nicht gefunden",
+                "No se encontró el terminal {} elegido en Ajustes",
+            ],
+            ErrorCode::VncViewerMissing => [
+                "No VNC viewer found",
+                "Kein VNC-Viewer gefunden",
+                "No se encontró ningún visor VNC",
+            ],
+            ErrorCode::VncViewerNotFound => [
+                "The VNC viewer {} wasn't found",
+                "Der VNC-Viewer {} wurde nicht gefunden",
+                "No se encontró el visor VNC {}",
+            ],
+            ErrorCode::InvalidMac => [
+                "Invalid MAC address: {}",
+                "Ungültige MAC-Adresse: {}",
+                "Dirección MAC no válida: {}",
+            ],
+            ErrorCode::WakeFailed => [
+                "The Wake-on-LAN packet couldn't be sent: {}",
+                "Das Wake-on-LAN-Paket konnte nicht gesendet werden: {}",
+                "No se pudo enviar el paquete Wake-on-LAN: {}",
+            ],
+            ErrorCode::NoDisplay => [
+                "Cannot launch {}: neither DISPLAY nor WAYLAND_DISPLAY is set",
+                "{} kann nicht gestartet werden: weder DISPLAY noch WAYLAND_DISPLAY ist gesetzt",
+                "No se puede iniciar {}: no están definidos ni DISPLAY ni WAYLAND_DISPLAY",
+            ],
+            ErrorCode::SshWithoutDisplay => [
+                "Cannot launch {}: this is an SSH session without a display",
+                "{} kann nicht gestartet werden: dies ist eine SSH-Sitzung ohne Anzeige",
+                "No se puede iniciar {}: es una sesión SSH sin pantalla",
+            ],
+            ErrorCode::ScreenshotToolMissing => [
+                "No screenshot tool found",
+                "Kein Programm für Bildschirmfotos gefunden",
+                "No se encontró ninguna herramienta de capturas de pantalla",
+            ],
+            ErrorCode::PackageManagerUnknown => [
+                "No supported package manager found",
+                "Kein unterstützter Paketmanager gefunden",
+                "No se encontró ningún gestor de paquetes compatible",
+            ],
+            ErrorCode::SystemdMissing => [
+                "This needs systemd, which isn't running here",
+                "Dafür wird systemd benötigt, das hier nicht läuft",
+                "Esto necesita systemd, que no se está ejecutando aquí",
+            ],
+            ErrorCode::UpdateCheckFailed => [
+                "Could not fetch update information: {}",
+                "Update-Informationen konnten nicht abgerufen werden: {}",
+                "No se pudo obtener la información de actualización: {}",
+            ],
+            ErrorCode::UpdateAssetMissing => [
+                "The release has no download for {}",
+                "Die Version enthält keinen Download für {}",
+                "La versión no tiene descarga para {}",
+            ],
+            ErrorCode::VaultLocked => [
+                "The vault is locked",
+                "Der Tresor ist gesperrt",
+                "La bóveda está bloqueada",
+            ],
+            ErrorCode::WrongPassphrase => [
+                "Wrong passphrase",
+                "Falsche Passphrase",
+                "Frase de contraseña incorrecta",
+            ],
+            ErrorCode::ConfigLocked => [
+                "The configuration is still encrypted",
+                "Die Konfiguration ist noch verschlüsselt",
+                "La configuración sigue cifrada",
+            ],
+        };
+        pick(language, en, de, es)
+    }
+
+    // What to do about it
+    pub fn hint(self, language: Language) -> &'static str {
+        let [en, de, es] = match self {
+            ErrorCode::VpnClientMissing => [
+                "Install it under Settings → System Dependencies or with your package manager.",
+                "Unter Einstellungen → Systemabhängigkeiten oder mit dem Paketmanager installieren.",
+                "Instálalo en Ajustes → Dependencias del sistema o con tu gestor de paquetes.",
+            ],
+            ErrorCode::VpnConfigMissing => [
+                "Check the connection's config path, or import the file again.",
+                "Den Konfigurationspfad der Verbindung prüfen oder die Datei erneut importieren.",
+                "Comprueba la ruta de configuración de la conexión o vuelve a importar el archivo.",
+            ],
+            ErrorCode::VpnCredentialsMissing => [
+                "Enter the username and password for the connection.",
+                "Benutzername und Passwort für die Verbindung eintragen.",
+                "Introduce el usuario y la contraseña de la conexión.",
+            ],
+            ErrorCode::VpnAuthFailed => [
+                "Check the saved username and password; some providers issue separate VPN credentials.",
+                "Gespeicherten Benutzernamen und Passwort prüfen; manche Anbieter vergeben eigene VPN-Zugangsdaten.",
+                "Comprueba el usuario y la contraseña guardados; algunos proveedores dan credenciales VPN aparte.",
+            ],
+            ErrorCode::VpnConnectTimeout => [
+                "Check that the server is reachable, and see the client log for details.",
+                "Prüfen, ob der Server erreichbar ist; Details stehen im Client-Protokoll.",
+                "Comprueba que el servidor es accesible y mira el registro del cliente.",
+            ],
+            ErrorCode::VpnKeyPassword => [
+                "Save the key's passphrase as the connection's password.",
+                "Die Passphrase des Schlüssels als Passwort der Verbindung speichern.",
+                "Guarda la frase de contraseña de la clave como contraseña de la conexión.",
+            ],
+            ErrorCode::TunnelNotFound => [
+                "The tunnel may have dropped right after connecting; connect again.",
+                "Der Tunnel ist eventuell direkt nach dem Verbinden abgebrochen; erneut verbinden.",
+                "Puede que el túnel se cayera justo después de conectar; vuelve a conectar.",
+            ],
+            ErrorCode::KillSwitchFailed => [
+                "The firewall rules need sudo on Linux and an elevated app on Windows.",
+                "Die Firewall-Regeln brauchen sudo unter Linux und Administratorrechte unter Windows.",
+                "Las reglas del cortafuegos necesitan sudo en Linux y permisos de administrador en Windows.",
+            ],
+            ErrorCode::VpnOtpMissing => [
+                "Turn on \"Ask for a one-time code\" for the connection and connect it from the app.",
+                "\"Nach Einmalcode fragen\" für die Verbindung einschalten und sie aus der App verbinden.",
+                "Activa \"Pedir un código de un solo uso\" en la conexión y conéctala desde la app.",
+            ],
+            ErrorCode::VpnClientFailed => [
+                "The client log, opened with the 📄 button next to the connection, has its own output.",
+                "Das Client-Protokoll, geöffnet mit der 📄-Schaltfläche neben der Verbindung, enthält seine Ausgabe.",
+                "El registro del cliente, abierto con el botón 📄 junto a la conexión, tiene su salida.",
+            ],
+            ErrorCode::VpnServerMissing => [
+                "Enter the server, or for IKEv2 the connection name, in the connection's settings.",
+                "Den Server, bei IKEv2 den Verbindungsnamen, in den Einstellungen der Verbindung eintragen.",
+                "Introduce el servidor, o en IKEv2 el nombre de la conexión, en los ajustes de la conexión.",
+            ],
+            ErrorCode::KillSwitchNoServer => [
+                "Check the server name in the profile; it has to resolve before the kill switch can let it through.",
+                "Den Servernamen im Profil prüfen; er muss sich auflösen lassen, damit der Kill-Switch ihn durchlässt.",
+                "Comprueba el nombre del servidor en el perfil; tiene que resolverse para que el kill switch lo deje pasar.",
+            ],
+            ErrorCode::RdpClientMissing => [
+                "Install FreeRDP (xfreerdp or wlfreerdp) or Remmina.",
+                "FreeRDP (xfreerdp oder wlfreerdp) oder Remmina installieren.",
+                "Instala FreeRDP (xfreerdp o wlfreerdp) o Remmina.",
+            ],
+            ErrorCode::RdpClientFailed => [
+                "Check that the host is reachable and has Remote Desktop turned on; the client log has details.",
+                "Prüfen, ob der Host erreichbar ist und Remotedesktop aktiviert hat; Details stehen im Client-Protokoll.",
+                "Comprueba que el equipo es accesible y tiene el Escritorio remoto activado; el registro del cliente tiene detalles.",
+            ],
+            ErrorCode::SshClientMissing => [
+                "Install an OpenSSH client.",
+                "Einen OpenSSH-Client installieren.",
+                "Instala un cliente OpenSSH.",
+            ],
+            ErrorCode::TerminalMissing => [
+                "Set $TERMINAL or install xterm.",
+                "$TERMINAL setzen oder xterm installieren.",
+                "Define $TERMINAL o instala xterm.",
+            ],
+            ErrorCode::TerminalNotFound => [
+                "Install it, or clear the terminal under Settings → Remote Access to use an installed one.",
+                "Es installieren oder das Terminal unter Einstellungen → Fernzugriff leeren, um ein installiertes zu nehmen.",
+                "Instálalo o deja vacío el terminal en Ajustes → Acceso remoto para usar uno instalado.",
+            ],
+            ErrorCode::VncViewerMissing => [
+                "Install TigerVNC (vncviewer) or Remmina, or set the viewer on the device.",
+                "TigerVNC (vncviewer) oder Remmina installieren oder den Viewer beim Gerät angeben.",
+                "Instala TigerVNC (vncviewer) o Remmina, o indica el visor en el dispositivo.",
+            ],
+            ErrorCode::VncViewerNotFound => [
+                "Install it, or clear the device's viewer to use an installed one.",
+                "Ihn installieren oder den Viewer beim Gerät leeren, um einen installierten zu nehmen.",
+                "Instálalo o deja vacío el visor del dispositivo para usar uno instalado.",
+            ],
+            ErrorCode::InvalidMac => [
+                "Use six pairs of hex digits, e.g. AA:BB:CC:DD:EE:FF.",
+                "Sechs Paare Hexadezimalziffern verwenden, z. B. AA:BB:CC:DD:EE:FF.",
+                "Usa seis pares de dígitos hexadecimales, p. ej. AA:BB:CC:DD:EE:FF.",
+            ],
+            ErrorCode::WakeFailed => [
+                "Check that a network interface is up; a relay has to be reachable over SSH with a key.",
+                "Prüfen, ob eine Netzwerkschnittstelle aktiv ist; ein Relay muss per SSH mit Schlüssel erreichbar sein.",
+                "Comprueba que hay una interfaz de red activa; un relé tiene que ser accesible por SSH con clave.",
+            ],
+            ErrorCode::NoDisplay => [
+                "Start the app from a graphical X11 or Wayland session.",
+                "Die App aus einer grafischen X11- oder Wayland-Sitzung starten.",
+                "Inicia la aplicación desde una sesión gráfica X11 o Wayland.",
+            ],
+            ErrorCode::SshWithoutDisplay => [
+                "Reconnect with X11 forwarding (ssh -X or ssh -Y) or run the app in the desktop session.",
+                "Mit X11-Weiterleitung (ssh -X oder ssh -Y) neu verbinden oder die App in der Desktop-Sitzung starten.",
+                "Vuelve a conectar con reenvío X11 (ssh -X o ssh -Y) o ejecuta la aplicación en la sesión de escritorio.",
+            ],
+            ErrorCode::ScreenshotToolMissing => [
+                "Install grim, gnome-screenshot, spectacle, scrot or ImageMagick.",
+                "grim, gnome-screenshot, spectacle, scrot oder ImageMagick installieren.",
+                "Instala grim, gnome-screenshot, spectacle, scrot o ImageMagick.",
+            ],
+            ErrorCode::PackageManagerUnknown => [
+                "Install the missing packages by hand.",
+                "Die fehlenden Pakete von Hand installieren.",
+                "Instala a mano los paquetes que faltan.",
+            ],
+            ErrorCode::SystemdMissing => [
+                "Connect from the app instead, or turn on auto-connect at startup.",
+                "Stattdessen aus der App verbinden oder das automatische Verbinden beim Start einschalten.",
+                "Conecta desde la app o activa la conexión automática al iniciar.",
+            ],
+            ErrorCode::UpdateCheckFailed => [
+                "Check the network connection, and the update proxy and source in Settings.",
+                "Netzwerkverbindung sowie Update-Proxy und -Quelle in den Einstellungen prüfen.",
+                "Comprueba la conexión de red y el proxy y el origen de actualizaciones en Ajustes.",
+            ],
+            ErrorCode::UpdateAssetMissing => [
+                "Download the build for your platform from the releases page.",
+                "Den Build für die eigene Plattform von der Release-Seite herunterladen.",
+                "Descarga la versión para tu plataforma desde la página de versiones.",
+            ],
+            ErrorCode::VaultLocked => [
+                "Unlock the notes with your passphrase.",
+                "Die Notizen mit der Passphrase entsperren.",
+                "Desbloquea las notas con tu frase de contraseña.",
+            ],
+            ErrorCode::WrongPassphrase => [
+                "The passphrase can't be recovered; check the keyboard layout and Caps Lock.",
+                "Die Passphrase lässt sich nicht wiederherstellen; Tastaturbelegung und Feststelltaste prüfen.",
+                "La frase de contraseña no se puede recuperar; revisa la distribución del teclado y Bloq Mayús.",
+            ],
+            ErrorCode::ConfigLocked => [
+                "Unlock it with the master password first.",
+                "Sie zuerst mit dem Master-Passwort entsperren.",
+                "Desbloquéala primero con la contraseña maestra.",
+            ],
+        };
+        pick(language, en, de, es)
+    }
+}
+
+fn pick(language: Language, en: &'static str, de: &'static str, es: &'static str) -> &'static str {
+    match language {
+        Language::English => en,
+        Language::German => de,
+        Language::Spanish => es,
+    }
+}
+
+// Shows as "[VPN-002] <message>. <hint>" in the current language
+#[derive(Debug)]
+pub struct CodedError {
+    pub code: ErrorCode,
+    pub argument: String,
+}
+
+impl std::fmt::Display for CodedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let language = language();
+        let message = self.code.message(language).replace("{}", &self.argument);
+        write!(f, "[{}] {}. {}", self.code.id(), message, self.code.hint(language))
+    }
+}
+
+impl std::error::Error for CodedError {}
+
+pub fn error(code: ErrorCode, argument: impl std::fmt::Display) -> anyhow::Error {
+    CodedError { code, argument: argument.to_string() }.into()
+}
+
+// The code of the first coded error in the chain, however much context was added around it
+pub fn code_of(error: &anyhow::Error) -> Option<ErrorCode> {
+    error.chain().find_map(|e| e.downcast_ref::<CodedError>()).map(|coded| coded.code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_unique() {
+        let mut ids: Vec<&str> = ALL.iter().map(|code| code.id()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), ALL.len());
+    }
+
+    #[test]
+    fn every_language_has_the_same_placeholders() {
+        for &code in ALL {
+            let count = |language| code.message(language).matches("{}").count();
+            assert_eq!(count(Language::German), count(Language::English), "{}", code.id());
+            assert_eq!(count(Language::Spanish), count(Language::English), "{}", code.id());
+        }
+    }
+
+    #[test]
+    fn every_code_is_documented() {
+        let docs = include_str!("../docs/errors.md");
+        for &code in ALL {
+            assert!(docs.contains(&format!("\n## {}\n", code.id())), "{} is missing from docs/errors.md", code.id());
+        }
+    }
+
+    #[test]
+    fn code_is_found_through_context() {
+        let coded = error(ErrorCode::WakeFailed, "no route").context("Failed to wake nas");
+        assert_eq!(code_of(&coded), Some(ErrorCode::WakeFailed));
+        assert_eq!(code_of(&anyhow::anyhow!("[WOL-002] only in the text")), None);
+    }
+}
@@ -1,10 +1,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use eframe::egui;
-use log::{info, error, warn};
+use log::{info, error};
 use std::panic;
 
 mod config;
+mod errors;
 mod network;
 mod system;
 mod ui;
@@ -12,6 +13,9 @@ mod ui;
 use ui::App;
 
 fn main() -> eframe::Result<()> {
+    // Before the log file is opened in its new place
+    let migrated = system::paths::migrate_legacy();
+    
     // Initialize logging with better configuration
     // In release mode on Windows, log to file instead of console
     #[cfg(all(windows, not(debug_assertions)))]
@@ -20,9 +24,7 @@ fn main() -> eframe::Result<()> {
         use std::io::Write;
         
         // Create logs directory
-        let log_dir = dirs::data_dir()
-            .unwrap_or_else(|| std::env::current_dir().unwrap())
-            .join("vpn-manager");
+        let log_dir = system::paths::logs_dir();
         std::fs::create_dir_all(&log_dir).ok();
         
         // Set up file logging
@@ -46,6 +48,57 @@ fn main() -> eframe::Result<()> {
             .init();
     }
     
+    if migrated > 0 {
+        info!("Moved {} files and folders from earlier versions to {}", migrated, system::paths::state_dir().display());
+    }
+    
+    // Headless subcommands run without creating a window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(|arg| arg.as_str()) == Some("doctor") {
+        std::process::exit(system::doctor::run());
+    }
+    if args.first().map(|arg| arg.as_str()) == Some("schema") {
+        println!("{}", config::Config::json_schema());
+        return Ok(());
+    }
+    if args.first().map(|arg| arg.as_str()) == Some("completions") {
+        std::process::exit(system::completions::run(&args[1..]));
+    }
+    // Called by the completion scripts for the names after --select
+    if args.first().map(|arg| arg.as_str()) == Some("complete-entries") {
+        std::process::exit(system::completions::print_entries(args.get(1).map(|arg| arg.as_str())));
+    }
+    
+    // Started at login for the always-on VPN, see system::autostart
+    let minimized = args.iter().any(|arg| arg == system::autostart::MINIMIZED_ARG);
+    // For when something started at launch hangs the app, see ui::SAFE_MODE_ARG
+    let safe_mode = args.iter().any(|arg| arg == ui::SAFE_MODE_ARG);
+    // Passed on as they are when the app starts again with another renderer
+    let mut launch_args = args.clone();
+    let mut args: Vec<String> = args.into_iter()
+        .filter(|arg| arg != system::autostart::MINIMIZED_ARG && arg != ui::SAFE_MODE_ARG)
+        .collect();
+    // OpenGL unless asked or the environment says otherwise, see system::renderer
+    let requested_renderer = match system::renderer::take_arg(&mut args) {
+        Ok(renderer) => renderer,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", ui::route::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let _ = system::renderer::take_arg(&mut launch_args);
+    
+    // Optional panel/entry to open, from --panel/--select or a vpn-aio:// link
+    let route = match ui::route::Route::from_args(&args) {
+        Ok(route) => route,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            eprintln!("{}", ui::route::USAGE);
+            std::process::exit(2);
+        }
+    };
+    
     // Set up panic handler for better error reporting
     panic::set_hook(Box::new(|panic_info| {
         error!("Application panic: {}", panic_info);
@@ -65,7 +118,7 @@ fn main() -> eframe::Result<()> {
     let icon_data = include_bytes!("../assets/vpn-aio.png");
     let icon = eframe::icon_data::from_png_bytes(icon_data).unwrap_or_default();
     
-    let options = eframe::NativeOptions {
+    let mut options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1000.0, 650.0])
             .with_min_inner_size([700.0, 500.0])
@@ -75,13 +128,21 @@ fn main() -> eframe::Result<()> {
             .with_icon(icon),
         ..Default::default()
     };
+    let renderer = system::renderer::choose(requested_renderer);
+    renderer.renderer.apply(&mut options);
+    if renderer.reason.is_empty() {
+        info!("Renderer: {}", renderer.renderer.label());
+    } else {
+        info!("Renderer: {} ({})", renderer.renderer.label(), renderer.reason);
+    }
+    system::renderer::set_active(renderer.clone());
 
     info!("Initializing GUI...");
     let result = eframe::run_native(
         "VPN Manager",
         options,
-        Box::new(|cc| {
-            match initialize_app(cc) {
+        Box::new(move |cc| {
+            match initialize_app(cc, route, minimized, safe_mode) {
                 Ok(app) => Ok(app),
                 Err(e) => {
                     error!("Failed to initialize app: {}", e);
@@ -97,6 +158,13 @@ fn main() -> eframe::Result<()> {
             Ok(())
         }
         Err(e) => {
+            // Rather than not starting at all, try the next renderer
+            if let Some(next) = renderer.renderer.next().filter(|_| renderer.automatic && system::renderer::is_renderer_error(&e)) {
+                match system::renderer::relaunch(renderer.renderer, next, &e, &launch_args) {
+                    Ok(code) => std::process::exit(code),
+                    Err(relaunch_error) => error!("Could not start again with {}: {}", next.label(), relaunch_error),
+                }
+            }
             error!("VPN Manager exited with error: {}", e);
             Err(e)
         }
@@ -108,23 +176,30 @@ fn check_system_requirements() -> Result<(), String> {
     if !cfg!(any(target_os = "linux", target_os = "windows", target_os = "macos")) {
         return Err("This application currently supports Linux, Windows, and macOS".to_string());
     }
+    
+    #[cfg(target_os = "macos")]
+    system::add_homebrew_to_path();
 
     // Check if we have a display (Linux/Unix specific)
-    #[cfg(unix)]
-    {
-        if std::env::var("DISPLAY").is_err() && std::env::var("WAYLAND_DISPLAY").is_err() {
-            warn!("No display environment detected. Running in headless mode may not work.");
-        }
+    let session = system::session::DisplaySession::detect();
+    info!("Display session: {}", session.description());
+    if let Err(e) = session.require_display("VPN Manager") {
+        return Err(e.to_string());
     }
 
     Ok(())
 }
 
-fn initialize_app(cc: &eframe::CreationContext<'_>) -> Result<Box<dyn eframe::App>, String> {
+fn initialize_app(cc: &eframe::CreationContext<'_>, route: Option<ui::route::Route>, minimized: bool, safe_mode: bool) -> Result<Box<dyn eframe::App>, String> {
+    system::renderer::set_device(cc);
     egui_extras::install_image_loaders(&cc.egui_ctx);
     info!("Image loaders installed successfully");
+    
+    if minimized {
+        cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(true));
+    }
 
-    match App::new(cc) {
+    match App::new(cc, route, safe_mode) {
         Ok(app) => {
             info!("Application initialized successfully");
             Ok(Box::new(app))
@@ -0,0 +1,60 @@
+use crate::config::VpnConfig;
+use crate::network::VpnStatus;
+use std::time::{Duration, Instant};
+
+// Keeping the always-on profile (config::AlwaysOn) up: it is connected when the app starts,
+// handed to the reconnect loop whenever it drops even if its own policy is off, and tried again
+// a while after the loop gives up. A profile disconnected from the app stays down until it is
+// connected again. The app decides when; this holds the policy and the state shown as a shield.
+
+// After the reconnect loop gave up
+pub const RETRY_AFTER: Duration = Duration::from_secs(60);
+// Attempts of the reconnect loop when the profile's own policy is off or allows fewer
+const MIN_ATTEMPTS: u32 = 5;
+
+// The profile with its reconnect policy switched on for always-on
+pub fn with_reconnect(config: &VpnConfig) -> VpnConfig {
+    let mut config = config.clone();
+    if !config.reconnect.enabled {
+        config.reconnect = Default::default();
+        config.reconnect.enabled = true;
+    }
+    config.reconnect.max_retries = config.reconnect.max_retries.max(MIN_ATTEMPTS);
+    config
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shield {
+    // The profile is up
+    Enforced,
+    // Reconnecting, or waiting to try again
+    Restoring,
+    // Another profile took over, e.g. through failover
+    Substituted(String),
+    // Down and not being brought back, e.g. after disconnecting it by hand
+    Down,
+}
+
+impl Shield {
+    pub fn of(vpn: &str, status: &VpnStatus, retry_at: Option<Instant>) -> Self {
+        match status {
+            VpnStatus::Connected(name) | VpnStatus::Degraded { name, .. } if name == vpn => Shield::Enforced,
+            VpnStatus::Connected(name) | VpnStatus::Degraded { name, .. } => Shield::Substituted(name.clone()),
+            VpnStatus::Checking | VpnStatus::Connecting | VpnStatus::Reconnecting { .. } => Shield::Restoring,
+            _ if retry_at.is_some() => Shield::Restoring,
+            _ => Shield::Down,
+        }
+    }
+
+    pub fn describe(&self, vpn: &str, retry_at: Option<Instant>) -> String {
+        match self {
+            Shield::Enforced => format!("Always-on: {} is up", vpn),
+            Shield::Restoring => match retry_at {
+                Some(at) => format!("Always-on: {} is down, trying again in {} s", vpn, at.saturating_duration_since(Instant::now()).as_secs()),
+                None => format!("Always-on: bringing {} back up", vpn),
+            },
+            Shield::Substituted(other) => format!("Always-on: {} is down, {} is connected instead", vpn, other),
+            Shield::Down => format!("Always-on: {} is down; connect it to resume", vpn),
+        }
+    }
+}
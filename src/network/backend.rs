@@ -0,0 +1,297 @@
+use crate::config::{VpnConfig, VpnType, WolDevice};
+use crate::network::monitor::DeviceDetectionResult;
+use crate::network::{custom, dns, ikev2, killswitch, monitor, netns, nmcli, openconnect, routes, vpn, wireguard, wol};
+use crate::system::{boot, services};
+use anyhow::Result;
+use async_trait::async_trait;
+
+// Backends sit between NetworkManager and the OS so the orchestration logic
+// (status bookkeeping, wake-and-verify loops) can run against in-memory mocks.
+
+#[async_trait]
+pub trait VpnControl: Send + Sync {
+    async fn connect(&self, config: &VpnConfig) -> Result<()>;
+    async fn disconnect(&self, config: &VpnConfig) -> Result<()>;
+    async fn is_connected(&self, config: &VpnConfig) -> Result<bool>;
+    // Tunnels without handshakes have nothing to report
+    async fn handshake(&self, _config: &VpnConfig) -> Result<Option<wireguard::Handshake>> {
+        Ok(None)
+    }
+    async fn rehandshake(&self, config: &VpnConfig) -> Result<()> {
+        Err(anyhow::anyhow!("{} has no handshake to renew", config.name))
+    }
+}
+
+#[async_trait]
+pub trait WakeSender: Send + Sync {
+    async fn send_wake(&self, device: &WolDevice) -> Result<()>;
+    // Through the device's SSH relay, for when the direct packets don't reach it
+    async fn send_wake_relay(&self, device: &WolDevice) -> Result<()> {
+        Err(anyhow::anyhow!("{} has no wake relay", device.name))
+    }
+}
+
+#[async_trait]
+pub trait Prober: Send + Sync {
+    async fn detect(&self, ip: &str) -> Result<DeviceDetectionResult>;
+    async fn quick_check(&self, ip: &str) -> bool;
+}
+
+// Process-spawning implementations used by the application
+
+pub struct SystemVpnControl;
+
+#[async_trait]
+impl VpnControl for SystemVpnControl {
+    async fn connect(&self, config: &VpnConfig) -> Result<()> {
+        // Bypass routes keep using the gateway from before the tunnel, which may replace it
+        let gateway = if routes::needs_gateway(config) || netns::needs_gateway(config) {
+            routes::default_gateway().await.map_err(|e| log::warn!("{}", e)).ok()
+        } else {
+            None
+        };
+
+        // Entries started at boot are brought up by their unit, so systemd keeps track of them
+        if boot::is_enabled(config) {
+            boot::start(config).await?;
+        } else if config.system_service {
+            services::start(config).await?;
+        } else if nmcli::handles(config) {
+            nmcli::connect(config).await?;
+        } else {
+            match config.vpn_type {
+                VpnType::OpenVpn => vpn::connect(config).await?,
+                VpnType::WireGuard => wireguard::connect(config).await?,
+                VpnType::Ikev2 => ikev2::connect(config).await?,
+                VpnType::OpenConnect => openconnect::connect(config).await?,
+                VpnType::Custom => custom::connect(config).await?,
+            }
+        }
+
+        let routes_result = routes::apply(config, gateway.as_ref()).await;
+        let netns_result = netns::setup(config, gateway.as_ref()).await;
+        let dns_result = dns::apply(config).await;
+
+        // The rules need the tunnel interface to exist; a tunnel the user asked to protect
+        // isn't left running unprotected
+        if config.kill_switch {
+            if let Err(e) = killswitch::enable(config).await {
+                let _ = self.disconnect(config).await;
+                routes::remove(config).await;
+                netns::teardown(config).await;
+                dns::restore(config).await;
+                return Err(anyhow::anyhow!("Kill switch could not be enabled, disconnected: {}", e));
+            }
+        }
+
+        // The tunnel stays up when only some routes, the application tunnel or DNS failed
+        routes_result.and(netns_result).and(dns_result)
+    }
+
+    async fn disconnect(&self, config: &VpnConfig) -> Result<()> {
+        // While the tunnel interface still exists
+        dns::restore(config).await;
+
+        if boot::is_enabled(config) && boot::is_active(config).await {
+            boot::stop(config).await?;
+        } else if config.system_service {
+            services::stop(config).await?;
+        } else if nmcli::handles(config) {
+            nmcli::disconnect(config).await?;
+        } else {
+            match config.vpn_type {
+                VpnType::OpenVpn => vpn::disconnect(config).await?,
+                VpnType::WireGuard => wireguard::disconnect(config).await?,
+                VpnType::Ikev2 => ikev2::disconnect(config).await?,
+                VpnType::OpenConnect => openconnect::disconnect(config).await?,
+                VpnType::Custom => custom::disconnect(config).await?,
+            }
+        }
+
+        routes::remove(config).await;
+        netns::teardown(config).await;
+
+        // Also covers the switch having been turned off on the entry while connected
+        if config.kill_switch || killswitch::is_active().await {
+            killswitch::disable(config).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn is_connected(&self, config: &VpnConfig) -> Result<bool> {
+        match config.vpn_type {
+            // OpenVPN started by its unit has no management interface, but runs as long as the unit
+            VpnType::OpenVpn if boot::is_enabled(config) && boot::is_active(config).await => Ok(true),
+            _ if config.system_service => Ok(services::is_active(config).await),
+            _ if nmcli::handles(config) => nmcli::check_connection_status(config).await,
+            VpnType::OpenVpn => vpn::check_connection_status(config).await,
+            VpnType::WireGuard => wireguard::check_connection_status(config).await,
+            VpnType::Ikev2 => ikev2::check_connection_status(config).await,
+            VpnType::OpenConnect => openconnect::check_connection_status(config).await,
+            VpnType::Custom => custom::check_connection_status(config).await,
+        }
+    }
+
+    async fn handshake(&self, config: &VpnConfig) -> Result<Option<wireguard::Handshake>> {
+        if config.vpn_type != VpnType::WireGuard {
+            return Ok(None);
+        }
+        let interface = wireguard::get_interface_from_config(&config.resolved_config_path()).await?;
+        Ok(Some(wireguard::handshake(&interface).await?))
+    }
+
+    async fn rehandshake(&self, config: &VpnConfig) -> Result<()> {
+        match config.vpn_type {
+            VpnType::WireGuard => wireguard::rehandshake(config).await,
+            _ => Err(anyhow::anyhow!("{} has no handshake to renew", config.name)),
+        }
+    }
+}
+
+pub struct SystemWakeSender;
+
+#[async_trait]
+impl WakeSender for SystemWakeSender {
+    async fn send_wake(&self, device: &WolDevice) -> Result<()> {
+        wol::wake_device(device).await
+    }
+
+    async fn send_wake_relay(&self, device: &WolDevice) -> Result<()> {
+        wol::wake_via_relay(device).await
+    }
+}
+
+pub struct SystemProber;
+
+#[async_trait]
+impl Prober for SystemProber {
+    async fn detect(&self, ip: &str) -> Result<DeviceDetectionResult> {
+        monitor::detect_device(ip).await
+    }
+
+    async fn quick_check(&self, ip: &str) -> bool {
+        monitor::quick_device_check(ip).await
+    }
+}
+
+// In-memory implementations for exercising NetworkManager without real tunnels
+#[cfg(any(test, feature = "mock"))]
+pub mod mock {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    pub struct MockVpnControl {
+        pub connected: Mutex<HashSet<String>>,
+        pub fail_with: Mutex<Option<String>>,
+        pub calls: Mutex<Vec<String>>,
+    }
+
+    impl MockVpnControl {
+        pub fn new() -> Arc<Self> {
+            Arc::new(Self::default())
+        }
+
+        pub fn fail_next(&self, message: &str) {
+            *self.fail_with.lock().unwrap() = Some(message.to_string());
+        }
+
+        pub fn drop_tunnel(&self, name: &str) {
+            self.connected.lock().unwrap().remove(name);
+        }
+
+        fn record(&self, call: String) -> Result<()> {
+            self.calls.lock().unwrap().push(call);
+            match self.fail_with.lock().unwrap().take() {
+                Some(message) => Err(anyhow::anyhow!(message)),
+                None => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl VpnControl for MockVpnControl {
+        async fn connect(&self, config: &VpnConfig) -> Result<()> {
+            self.record(format!("connect:{}", config.name))?;
+            self.connected.lock().unwrap().insert(config.name.clone());
+            Ok(())
+        }
+
+        async fn disconnect(&self, config: &VpnConfig) -> Result<()> {
+            self.record(format!("disconnect:{}", config.name))?;
+            self.connected.lock().unwrap().remove(&config.name);
+            Ok(())
+        }
+
+        async fn is_connected(&self, config: &VpnConfig) -> Result<bool> {
+            Ok(self.connected.lock().unwrap().contains(&config.name))
+        }
+    }
+
+    #[derive(Default)]
+    pub struct MockWakeSender {
+        pub sent: Mutex<Vec<String>>,
+        pub fail_with: Mutex<Option<String>>,
+    }
+
+    impl MockWakeSender {
+        pub fn new() -> Arc<Self> {
+            Arc::new(Self::default())
+        }
+    }
+
+    #[async_trait]
+    impl WakeSender for MockWakeSender {
+        async fn send_wake(&self, device: &WolDevice) -> Result<()> {
+            if let Some(message) = self.fail_with.lock().unwrap().take() {
+                return Err(anyhow::anyhow!(message));
+            }
+            self.sent.lock().unwrap().push(device.mac_address.clone());
+            Ok(())
+        }
+
+        async fn send_wake_relay(&self, device: &WolDevice) -> Result<()> {
+            self.sent.lock().unwrap().push(format!("relay:{}", device.mac_address));
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    pub struct MockProber {
+        pub online: Mutex<HashSet<String>>,
+    }
+
+    impl MockProber {
+        pub fn new() -> Arc<Self> {
+            Arc::new(Self::default())
+        }
+
+        pub fn set_online(&self, ip: &str, is_online: bool) {
+            let mut online = self.online.lock().unwrap();
+            if is_online {
+                online.insert(ip.to_string());
+            } else {
+                online.remove(ip);
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Prober for MockProber {
+        async fn detect(&self, ip: &str) -> Result<DeviceDetectionResult> {
+            let is_online = self.online.lock().unwrap().contains(ip);
+            Ok(DeviceDetectionResult {
+                is_online,
+                method_used: "MOCK".to_string(),
+                response_time: None,
+                details: format!("Mock device {} is {}", ip, if is_online { "online" } else { "offline" }),
+            })
+        }
+
+        async fn quick_check(&self, ip: &str) -> bool {
+            self.online.lock().unwrap().contains(ip)
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use std::time::Duration;
+
+// Well-known endpoints that return an empty 204 when the internet is reachable
+const CHECK_URLS: [&str; 2] = [
+    "http://connectivitycheck.gstatic.com/generate_204",
+    "http://clients3.google.com/generate_204",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptivePortalStatus {
+    Clear,
+    Detected(String),
+    Unreachable(String),
+}
+
+pub async fn check_captive_portal() -> Result<CaptivePortalStatus> {
+    // Don't follow redirects - a redirect is exactly what a portal looks like
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .timeout(Duration::from_secs(5))
+        .build()?;
+
+    let mut last_error = String::new();
+
+    for url in CHECK_URLS {
+        match client.get(url).header("User-Agent", "vpn-manager").send().await {
+            Ok(response) => {
+                let status = response.status();
+
+                if status == reqwest::StatusCode::NO_CONTENT {
+                    log::debug!("Captive portal check via {} passed", url);
+                    return Ok(CaptivePortalStatus::Clear);
+                }
+
+                if status.is_redirection() {
+                    let login_url = response
+                        .headers()
+                        .get(reqwest::header::LOCATION)
+                        .and_then(|value| value.to_str().ok())
+                        .unwrap_or(url)
+                        .to_string();
+                    log::warn!("Captive portal detected, redirected to {}", login_url);
+                    return Ok(CaptivePortalStatus::Detected(login_url));
+                }
+
+                // Any other answer means something intercepted the request
+                log::warn!("Captive portal suspected, {} answered with {}", url, status);
+                return Ok(CaptivePortalStatus::Detected(url.to_string()));
+            }
+            Err(e) => {
+                log::debug!("Captive portal check via {} failed: {}", url, e);
+                last_error = e.to_string();
+            }
+        }
+    }
+
+    Ok(CaptivePortalStatus::Unreachable(last_error))
+}
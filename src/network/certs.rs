@@ -0,0 +1,467 @@
+use crate::config::lint::Severity;
+use crate::config::{CertFiles, Config, VpnConfig, VpnType};
+use crate::network::ovpn_bundle;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Certificates and keys attached to an OpenVPN entry: the CA, the client certificate and key, and
+// the tls-auth key. Attached files are passed after the profile (--ca, --cert, ...), so they
+// replace what the profile names or has inline; the rest still comes from the profile. Expiry
+// dates are read from the certificates in use, attached or not, so the VPN panel can show them
+// and warn before a server starts refusing the client.
+
+// Warned about this long before a certificate expires
+pub const WARN_DAYS: i64 = 30;
+// Expiry dates are read again after this, or when the attached files change
+const CACHE_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CertKind {
+    Ca,
+    Cert,
+    Key,
+    TlsAuth,
+}
+
+impl CertKind {
+    pub const ALL: [CertKind; 4] = [CertKind::Ca, CertKind::Cert, CertKind::Key, CertKind::TlsAuth];
+
+    // The OpenVPN option, also the tag of its inline block
+    pub fn directive(self) -> &'static str {
+        match self {
+            CertKind::Ca => "ca",
+            CertKind::Cert => "cert",
+            CertKind::Key => "key",
+            CertKind::TlsAuth => "tls-auth",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CertKind::Ca => "CA certificate",
+            CertKind::Cert => "Client certificate",
+            CertKind::Key => "Private key",
+            CertKind::TlsAuth => "TLS auth key",
+        }
+    }
+
+    pub fn path(self, files: &CertFiles) -> &String {
+        match self {
+            CertKind::Ca => &files.ca,
+            CertKind::Cert => &files.cert,
+            CertKind::Key => &files.key,
+            CertKind::TlsAuth => &files.tls_auth,
+        }
+    }
+
+    pub fn path_mut(self, files: &mut CertFiles) -> &mut String {
+        match self {
+            CertKind::Ca => &mut files.ca,
+            CertKind::Cert => &mut files.cert,
+            CertKind::Key => &mut files.key,
+            CertKind::TlsAuth => &mut files.tls_auth,
+        }
+    }
+
+    // Text the file holds when it is what it is attached as
+    fn marker(self) -> &'static str {
+        match self {
+            CertKind::Ca | CertKind::Cert => "-----BEGIN CERTIFICATE-----",
+            CertKind::Key => "PRIVATE KEY-----",
+            CertKind::TlsAuth => "-----BEGIN OpenVPN Static key",
+        }
+    }
+}
+
+// Where a certificate or key of the profile comes from
+enum Source {
+    Attached(PathBuf),
+    Inline(String),
+    File(PathBuf),
+}
+
+// The attached file of `kind`, resolved like config_path
+fn attached(config: &VpnConfig, kind: CertKind) -> Option<PathBuf> {
+    let path = kind.path(&config.certificates).trim();
+    if path.is_empty() || config.vpn_type != VpnType::OpenVpn {
+        return None;
+    }
+    let path = Path::new(path);
+    Some(if path.is_relative() { Config::config_dir().join(path) } else { path.to_path_buf() })
+}
+
+// What the profile itself says for `kind`: an inline block or a file next to it
+fn in_profile(profile: &str, profile_dir: &Path, kind: CertKind) -> Option<Source> {
+    let open = format!("<{}>", kind.directive());
+    let close = format!("</{}>", kind.directive());
+    if let Some(start) = profile.find(&open) {
+        let body = &profile[start + open.len()..];
+        let end = body.find(&close).unwrap_or(body.len());
+        return Some(Source::Inline(body[..end].to_string()));
+    }
+    profile.lines().find_map(|line| {
+        let words = ovpn_bundle::split_words(line.trim());
+        match words.as_slice() {
+            [directive, file, ..] if directive == kind.directive() && file != "[inline]" => {
+                Some(Source::File(profile_dir.join(file)))
+            }
+            _ => None,
+        }
+    })
+}
+
+fn source(config: &VpnConfig, profile: &str, kind: CertKind) -> Option<Source> {
+    if let Some(path) = attached(config, kind) {
+        return Some(Source::Attached(path));
+    }
+    let profile_path = config.resolved_config_path();
+    let dir = Path::new(&profile_path).parent().unwrap_or(Path::new("."));
+    in_profile(profile, dir, kind)
+}
+
+// A `key-direction` line or the direction after the tls-auth file, for an attached key
+fn tls_auth_direction(profile: &str) -> Option<String> {
+    profile.lines().find_map(|line| {
+        let words = ovpn_bundle::split_words(line.trim());
+        match words.as_slice() {
+            [directive, direction] if directive == "key-direction" => Some(direction.clone()),
+            [directive, _, direction, ..] if directive == "tls-auth" => Some(direction.clone()),
+            _ => None,
+        }
+    })
+}
+
+fn read_profile(config: &VpnConfig) -> String {
+    std::fs::read_to_string(config.resolved_config_path()).unwrap_or_default()
+}
+
+// Arguments for openvpn, after the profile
+pub fn args(config: &VpnConfig) -> Vec<String> {
+    let mut args = Vec::new();
+    for kind in CertKind::ALL {
+        if let Some(path) = attached(config, kind) {
+            args.push(format!("--{}", kind.directive()));
+            args.push(path.display().to_string());
+            if kind == CertKind::TlsAuth {
+                // Without it an attached key would be used in both directions
+                args.extend(tls_auth_direction(&read_profile(config)));
+            }
+        }
+    }
+    args
+}
+
+// The profile with the attached files inlined in place of its own, for a copy that runs from
+// another folder
+pub fn inject(content: &str, config: &VpnConfig) -> anyhow::Result<String> {
+    let kinds: Vec<CertKind> = CertKind::ALL.into_iter().filter(|kind| attached(config, *kind).is_some()).collect();
+    if kinds.is_empty() {
+        return Ok(content.to_string());
+    }
+    let direction = tls_auth_direction(content);
+    let mut lines = Vec::new();
+    let mut skipping: Option<String> = None;
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if let Some(close) = &skipping {
+            if trimmed == close {
+                skipping = None;
+            }
+            continue;
+        }
+        let replaced = kinds.iter().find(|kind| {
+            trimmed == format!("<{}>", kind.directive())
+                || ovpn_bundle::split_words(trimmed).first().map(String::as_str) == Some(kind.directive())
+        });
+        match replaced {
+            Some(kind) if trimmed.starts_with('<') => skipping = Some(format!("</{}>", kind.directive())),
+            Some(_) => {}
+            None => lines.push(line.to_string()),
+        }
+    }
+    for kind in kinds {
+        let path = attached(config, kind).unwrap_or_default();
+        let data = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Could not read the {} {}: {}", kind.label().to_lowercase(), path.display(), e))?;
+        lines.push(format!("<{}>", kind.directive()));
+        lines.push(data.trim_end().to_string());
+        lines.push(format!("</{}>", kind.directive()));
+    }
+    if let Some(direction) = direction.filter(|_| attached(config, CertKind::TlsAuth).is_some()) {
+        if !lines.iter().any(|line| line.trim_start().starts_with("key-direction")) {
+            lines.push(format!("key-direction {}", direction));
+        }
+    }
+    Ok(lines.join("\n") + "\n")
+}
+
+// The attached files as NetworkManager's OpenVPN plugin keeps them in vpn.data
+pub fn nm_data(config: &VpnConfig) -> Vec<String> {
+    let mut data = Vec::new();
+    for kind in CertKind::ALL {
+        if let Some(path) = attached(config, kind) {
+            let key = if kind == CertKind::TlsAuth { "ta" } else { kind.directive() };
+            data.push(format!("{}={}", key, path.display()));
+            if kind == CertKind::TlsAuth {
+                if let Some(direction) = tls_auth_direction(&read_profile(config)) {
+                    data.push(format!("ta-dir={}", direction));
+                }
+            }
+        }
+    }
+    data
+}
+
+// Attached files that are missing or aren't what they are attached as, and directives that
+// don't go together, for the Problems tab and the certificates menu
+pub fn problems(config: &VpnConfig) -> Vec<(Severity, String)> {
+    let mut problems = Vec::new();
+    if config.vpn_type != VpnType::OpenVpn {
+        if CertKind::ALL.iter().any(|kind| !kind.path(&config.certificates).trim().is_empty()) {
+            problems.push((Severity::Warning, "Attached certificates are only used by OpenVPN connections".to_string()));
+        }
+        return problems;
+    }
+
+    for kind in CertKind::ALL {
+        let Some(path) = attached(config, kind) else { continue };
+        match std::fs::read_to_string(&path) {
+            Err(_) if !path.exists() => {
+                problems.push((Severity::Error, format!("{} not found: {}", kind.label(), path.display())));
+            }
+            Err(e) => problems.push((Severity::Error, format!("{} is not readable: {}", kind.label(), e))),
+            Ok(data) if !data.contains(kind.marker()) => {
+                problems.push((Severity::Error, format!("{} {} doesn't hold a {}", kind.label(), path.display(), kind.label().to_lowercase())));
+            }
+            Ok(_) => {}
+        }
+    }
+
+    let profile = read_profile(config);
+    let has = |kind: CertKind| source(config, &profile, kind).is_some();
+    let uses_pkcs12 = profile.lines().any(|line| line.trim_start().starts_with("pkcs12") || line.trim() == "<pkcs12>");
+    if !uses_pkcs12 {
+        if has(CertKind::Cert) && !has(CertKind::Key) {
+            problems.push((Severity::Error, "A client certificate is set but no private key".to_string()));
+        } else if has(CertKind::Key) && !has(CertKind::Cert) {
+            problems.push((Severity::Error, "A private key is set but no client certificate".to_string()));
+        }
+        if !has(CertKind::Ca) && !profile.is_empty() {
+            problems.push((Severity::Warning, "Neither the profile nor the entry has a CA certificate".to_string()));
+        }
+    }
+    if attached(config, CertKind::TlsAuth).is_some() {
+        let other = profile.lines().find_map(|line| {
+            let directive = line.trim().trim_start_matches('<').split(|c: char| c.is_whitespace() || c == '>').next().unwrap_or_default();
+            ["tls-crypt", "tls-crypt-v2"].into_iter().find(|other| *other == directive)
+        });
+        if let Some(other) = other {
+            problems.push((Severity::Warning, format!("The profile uses {}, which the attached TLS auth key can't be used with", other)));
+        }
+    }
+    for (kind, not_after) in expiry(config) {
+        let days = (not_after - Utc::now()).num_days();
+        if not_after < Utc::now() {
+            problems.push((Severity::Error, format!("{} expired on {}", kind.label(), not_after.format("%Y-%m-%d"))));
+        } else if days < WARN_DAYS {
+            problems.push((Severity::Warning, format!("{} expires on {} ({} days)", kind.label(), not_after.format("%Y-%m-%d"), days)));
+        }
+    }
+    problems
+}
+
+// Per connection: when the dates were read and what they were
+type Expiries = (Instant, Vec<(CertKind, DateTime<Utc>)>);
+
+static EXPIRY: Mutex<BTreeMap<String, Expiries>> = Mutex::new(BTreeMap::new());
+
+// When the CA and client certificates in use expire; a CA file holding a chain counts with the
+// first of them to expire. Cached briefly, the panel asks every frame.
+pub fn expiry(config: &VpnConfig) -> Vec<(CertKind, DateTime<Utc>)> {
+    if config.vpn_type != VpnType::OpenVpn {
+        return Vec::new();
+    }
+    let key = format!("{}\n{:?}", config.resolved_config_path(), config.certificates);
+    if let Some((at, dates)) = EXPIRY.lock().ok().and_then(|cache| cache.get(&key).cloned()) {
+        if at.elapsed() < Duration::from_secs(CACHE_SECS) {
+            return dates;
+        }
+    }
+
+    let profile = read_profile(config);
+    let dates: Vec<(CertKind, DateTime<Utc>)> = [CertKind::Ca, CertKind::Cert]
+        .into_iter()
+        .filter_map(|kind| {
+            let pem = match source(config, &profile, kind)? {
+                Source::Inline(text) => text,
+                Source::Attached(path) | Source::File(path) => std::fs::read_to_string(path).ok()?,
+            };
+            not_after_all(&pem).into_iter().min().map(|date| (kind, date))
+        })
+        .collect();
+    if let Ok(mut cache) = EXPIRY.lock() {
+        cache.insert(key, (Instant::now(), dates.clone()));
+    }
+    dates
+}
+
+// The certificate that expires first, if it is within WARN_DAYS or already past
+pub fn expiring(config: &VpnConfig) -> Option<(CertKind, DateTime<Utc>)> {
+    expiry(config)
+        .into_iter()
+        .min_by_key(|(_, date)| *date)
+        .filter(|(_, date)| (*date - Utc::now()).num_days() < WARN_DAYS)
+}
+
+// notAfter of each certificate in PEM text
+fn not_after_all(pem: &str) -> Vec<DateTime<Utc>> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut dates = Vec::new();
+    let mut rest = pem;
+    while let Some(start) = rest.find(BEGIN) {
+        let body = &rest[start + BEGIN.len()..];
+        let Some(end) = body.find(END) else { break };
+        let encoded: String = body[..end].chars().filter(|c| !c.is_whitespace()).collect();
+        if let Some(date) = base64::engine::general_purpose::STANDARD.decode(encoded).ok().and_then(|der| not_after(&der)) {
+            dates.push(date);
+        }
+        rest = &body[end + END.len()..];
+    }
+    dates
+}
+
+// One DER element: its tag, its contents and what follows it
+fn element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let length = rest[..count].iter().fold(0usize, |length, byte| (length << 8) | *byte as usize);
+        (length, &rest[count..])
+    };
+    (rest.len() >= length).then(|| (tag, &rest[..length], &rest[length..]))
+}
+
+// Certificate → TBSCertificate → validity → notAfter
+fn not_after(der: &[u8]) -> Option<DateTime<Utc>> {
+    const SEQUENCE: u8 = 0x30;
+    let (SEQUENCE, certificate, _) = element(der)? else { return None };
+    let (SEQUENCE, tbs, _) = element(certificate)? else { return None };
+    let mut rest = tbs;
+    // [0] version, left out for version 1
+    if rest.first() == Some(&0xa0) {
+        rest = element(rest)?.2;
+    }
+    // serial number, signature algorithm, issuer
+    for _ in 0..3 {
+        rest = element(rest)?.2;
+    }
+    let (SEQUENCE, validity, _) = element(rest)? else { return None };
+    let (_, _, after_not_before) = element(validity)?;
+    let (tag, time, _) = element(after_not_before)?;
+    parse_time(tag, std::str::from_utf8(time).ok()?)
+}
+
+// UTCTime (YYMMDDHHMMSSZ, years from 1950) or GeneralizedTime (YYYYMMDDHHMMSSZ)
+fn parse_time(tag: u8, text: &str) -> Option<DateTime<Utc>> {
+    let (year, rest) = match tag {
+        0x17 => {
+            let year: i32 = text.get(..2)?.parse().ok()?;
+            (if year < 50 { 2000 + year } else { 1900 + year }, text.get(2..)?)
+        }
+        0x18 => (text.get(..4)?.parse().ok()?, text.get(4..)?),
+        _ => return None,
+    };
+    let field = |range: std::ops::Range<usize>| rest.get(range)?.parse::<u32>().ok();
+    let date = NaiveDate::from_ymd_opt(year, field(0..2)?, field(2..4)?)?;
+    Some(date.and_hms_opt(field(4..6)?, field(6..8)?, field(8..10)?)?.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed, notAfter 2030-06-15 12:00:00 as UTCTime
+    const CERT_2030: &str = "-----BEGIN CERTIFICATE-----
+MIIBdDCCARmgAwIBAgIUbGRjwDJikYHjN9mcfhRHNMdHemwwCgYIKoZIzj0EAwIw
+DzENMAsGA1UEAwwEdGVzdDAeFw0yNDAxMDEwMDAwMDBaFw0zMDA2MTUxMjAwMDBa
+MA8xDTALBgNVBAMMBHRlc3QwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAASSFsqD
+2NmBOYngc3kvwhW9yDA0HJnCr5WPGAnU6MV9FHzxPYvoe8MBZbnsltF0juR6qk9P
+J1AlnFjERBoHRqhXo1MwUTAdBgNVHQ4EFgQUAzZiIF9+OPibr3dVSDrTUtaU22kw
+HwYDVR0jBBgwFoAUAzZiIF9+OPibr3dVSDrTUtaU22kwDwYDVR0TAQH/BAUwAwEB
+/zAKBggqhkjOPQQDAgNJADBGAiEAxIpKlpsi249eAAkDa6UAw7IjTEnk/dOmOD8x
+ZyqDhskCIQDTGi3k5rh+h7rQdHi/BFQZxxbaBqJjJByAJeGI/Mmn6g==
+-----END CERTIFICATE-----
+";
+
+    // Self-signed, notAfter 2060-01-01 00:00:00 as GeneralizedTime
+    const CERT_2060: &str = "-----BEGIN CERTIFICATE-----
+MIIBeDCCAR2gAwIBAgIUSdpcrkT/Ebe/0IewWKed1jc1IFEwCgYIKoZIzj0EAwIw
+EDEOMAwGA1UEAwwFbGF0ZXIwIBcNMjQwMTAxMDAwMDAwWhgPMjA2MDAxMDEwMDAw
+MDBaMBAxDjAMBgNVBAMMBWxhdGVyMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE
+C1IDa/x7hqCWhOtuxLDts01TecB43v7Olx8nS4o5oZisJNOz+d2myIahQp3hUacT
+CZblPrb8M4J75SlyCa5B3aNTMFEwHQYDVR0OBBYEFCh02GkF7TaLV1wbxEqCWmGn
+zE/fMB8GA1UdIwQYMBaAFCh02GkF7TaLV1wbxEqCWmGnzE/fMA8GA1UdEwEB/wQF
+MAMBAf8wCgYIKoZIzj0EAwIDSQAwRgIhALfQPp+gj60HTwkJurA4O1jlEy1SZmJ2
+2Tmr+W5d37Y3AiEA55Myr5qKcVap23jd/yx+VaJQGdNIHctbp4KaiThngtY=
+-----END CERTIFICATE-----
+";
+
+    fn der(pem: &str) -> Vec<u8> {
+        let encoded: String = pem.lines().filter(|line| !line.starts_with("-----")).collect();
+        base64::engine::general_purpose::STANDARD.decode(encoded).unwrap()
+    }
+
+    fn date(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn reads_not_after_of_every_certificate() {
+        let pem = format!("junk before\n{}\n{}", CERT_2030, CERT_2060);
+        assert_eq!(not_after_all(&pem), vec![date("2030-06-15T12:00:00Z"), date("2060-01-01T00:00:00Z")]);
+    }
+
+    #[test]
+    fn truncated_certificates_have_no_date() {
+        let der = der(CERT_2030);
+        assert!(not_after(&der).is_some());
+        for length in 0..der.len() {
+            assert_eq!(not_after(&der[..length]), None, "cut at {}", length);
+        }
+        let cut = CERT_2030.replace("ZyqDhskCIQDTGi3k5rh+h7rQdHi/BFQZxxbaBqJjJByAJeGI/Mmn6g==\n", "");
+        assert!(not_after_all(&cut).is_empty());
+    }
+
+    #[test]
+    fn oversize_lengths_are_refused() {
+        // Longer than the data
+        assert_eq!(element(&[0x30, 0x05, 0x01, 0x02]), None);
+        assert_eq!(element(&[0x30, 0x84, 0xff, 0xff, 0xff, 0xff, 0x00]), None);
+        // More length bytes than four, the indefinite form, and length bytes cut off
+        assert_eq!(element(&[0x30, 0x85, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00]), None);
+        assert_eq!(element(&[0x30, 0x80, 0x00, 0x00]), None);
+        assert_eq!(element(&[0x30, 0x82, 0x01]), None);
+
+        let (tag, contents, rest) = element(&[0x30, 0x81, 0x02, 0xaa, 0xbb, 0xcc]).unwrap();
+        assert_eq!((tag, contents, rest), (0x30, &[0xaa, 0xbb][..], &[0xcc][..]));
+    }
+
+    #[test]
+    fn utc_time_years_wrap_at_1950() {
+        assert_eq!(parse_time(0x17, "491231235959Z"), Some(date("2049-12-31T23:59:59Z")));
+        assert_eq!(parse_time(0x17, "500101000000Z"), Some(date("1950-01-01T00:00:00Z")));
+        assert_eq!(parse_time(0x17, "301332000000Z"), None);
+        assert_eq!(parse_time(0x04, "300101000000Z"), None);
+    }
+}
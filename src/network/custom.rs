@@ -0,0 +1,293 @@
+use crate::config::VpnConfig;
+use crate::network::tunnel;
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::process::Command;
+
+// Clients without a backend of their own (Cisco Secure Client, FortiClient, openfortivpn,
+// corporate wrappers), driven by command lines from the entry. Each runs through the shell with
+// the placeholders replaced by the entry's values, quoted for the shell. The same values are in
+// VPN_MANAGER_* environment variables; only those keep the password out of the process list, which
+// {password} puts it in, so the Problems tab warns about it. A connect command still running after
+// CONNECT_WAIT is taken for a client that stays in the foreground: it is left running, and stopped
+// on disconnect when the entry has no disconnect command. On Linux and macOS the shell execs the
+// connect command, so that is the client's pid rather than the shell's.
+
+const CONNECT_WAIT: Duration = Duration::from_secs(30);
+
+// Placeholder, environment variable it is also passed in, and the entry's value
+fn values(config: &VpnConfig) -> [(&'static str, &'static str, String); 6] {
+    [
+        ("{config}", "VPN_MANAGER_CONFIG", if config.config_path.trim().is_empty() { String::new() } else { config.resolved_config_path() }),
+        ("{username}", "VPN_MANAGER_USERNAME", config.username.clone()),
+        ("{password}", "VPN_MANAGER_PASSWORD", config.password.clone()),
+        ("{server}", "VPN_MANAGER_SERVER", config.server.trim().to_string()),
+        ("{group}", "VPN_MANAGER_GROUP", config.group.trim().to_string()),
+        ("{name}", "VPN_MANAGER_NAME", config.name.clone()),
+    ]
+}
+
+pub const PLACEHOLDERS: [&str; 6] = ["{config}", "{username}", "{password}", "{server}", "{group}", "{name}"];
+
+// Entries connected from the app this session, with the pid of a client left in the foreground
+static STARTED: Mutex<BTreeMap<String, Option<u32>>> = Mutex::new(BTreeMap::new());
+
+// The command line with the placeholders filled in; with redact, the password is masked as in
+// the command preview and the client log
+pub fn expand(template: &str, config: &VpnConfig, redact: bool) -> String {
+    fill(template, config, |placeholder, _, value| {
+        if redact && placeholder == "{password}" && !value.is_empty() {
+            quote_value("********")
+        } else {
+            quote_value(value)
+        }
+    })
+}
+
+// The command line for an exported script: the values exported as the app passes them, with the
+// password left for the caller to set
+pub fn script(template: &str, config: &VpnConfig) -> Vec<String> {
+    let exports: Vec<String> = values(config)
+        .iter()
+        .filter(|(placeholder, _, _)| *placeholder != "{password}")
+        .map(|(_, variable, value)| format!("{}={}", variable, shell_quote(value)))
+        .collect();
+    let command = fill(template, config, |placeholder, variable, value| {
+        if placeholder == "{password}" {
+            format!("\"${{{}:?}}\"", variable)
+        } else {
+            shell_quote(value)
+        }
+    });
+    vec![format!("export {}", exports.join(" ")), command]
+}
+
+// Replaces the placeholders in one pass, so one in a value stays as it is
+fn fill(template: &str, config: &VpnConfig, value_for: impl Fn(&str, &str, &str) -> String) -> String {
+    let values = values(config);
+    let mut command = String::new();
+    let mut rest = template.trim();
+    while let Some(start) = rest.find('{') {
+        command.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match values.iter().find(|(placeholder, _, _)| rest.starts_with(placeholder)) {
+            Some((placeholder, variable, value)) => {
+                command.push_str(&value_for(placeholder, variable, value));
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                command.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    command.push_str(rest);
+    command
+}
+
+// Whether the commands put the password on their command line, readable in the process list
+pub fn password_on_command_line(config: &VpnConfig) -> bool {
+    [&config.custom.connect, &config.custom.disconnect, &config.custom.status]
+        .iter()
+        .any(|command| command.contains("{password}"))
+}
+
+// Placeholders in the commands that the entry has no value for, e.g. {config} without a file
+pub fn missing_values(config: &VpnConfig) -> Vec<&'static str> {
+    let commands = [&config.custom.connect, &config.custom.disconnect, &config.custom.status];
+    values(config)
+        .into_iter()
+        .filter(|(placeholder, _, value)| value.is_empty() && commands.iter().any(|command| command.contains(placeholder)))
+        .map(|(placeholder, _, _)| placeholder)
+        .collect()
+}
+
+// Single quotes for POSIX sh, nothing within them is special
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(unix)]
+fn quote_value(value: &str) -> String {
+    shell_quote(value)
+}
+
+// Within double quotes cmd leaves ^, & and | alone but still expands %VAR%, so each % is put
+// outside them, escaped as ^%
+#[cfg(windows)]
+fn quote_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\"").replace('%', "\"^%\""))
+}
+
+fn shell(command: &str, config: &VpnConfig) -> Command {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+
+    #[cfg(windows)]
+    let mut cmd = {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = Command::new("cmd");
+        // Passed as is, cmd does its own parsing of the quotes
+        cmd.arg("/C").raw_arg(command);
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd
+    };
+
+    cmd.envs(&config.env_vars)
+        .envs(values(config).into_iter().map(|(_, variable, value)| (variable, value)))
+        .stdin(Stdio::null());
+    cmd
+}
+
+pub async fn connect(config: &VpnConfig) -> Result<()> {
+    let template = config.custom.connect.trim();
+    if template.is_empty() {
+        return Err(anyhow::anyhow!("No connect command set for {}", config.name));
+    }
+
+    // Output goes straight to the client log, the command may never exit
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(client_log::prepare("vpn", &config.name))?;
+    #[cfg(unix)]
+    let command = format!("exec {}", expand(template, config, false));
+    #[cfg(windows)]
+    let command = expand(template, config, false);
+    let mut cmd = shell(&command, config);
+    cmd.stdout(Stdio::from(log.try_clone()?)).stderr(Stdio::from(log));
+
+    log::info!("Connecting {}: {}", config.name, expand(template, config, true));
+    // Only starting it waits its turn; other privileged commands mustn't queue behind the wait
+    let mut child = {
+        let _privileged = executor::privileged().await;
+        cmd.spawn()?
+    };
+    let exited = tokio::time::timeout(CONNECT_WAIT, child.wait()).await;
+
+    let pid = match exited {
+        Ok(status) => {
+            let status = status?;
+            if !status.success() {
+                return Err(anyhow::anyhow!(
+                    "The connect command of {} failed ({}), see the client log for details",
+                    config.name,
+                    status
+                ));
+            }
+            None
+        }
+        Err(_) => {
+            log::info!("The connect command of {} keeps running, taken for the client itself", config.name);
+            child.id()
+        }
+    };
+    STARTED.lock().unwrap().insert(config.name.clone(), pid);
+    Ok(())
+}
+
+pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    let pid = STARTED.lock().unwrap().remove(&config.name).flatten();
+
+    let template = config.custom.disconnect.trim();
+    if template.is_empty() {
+        return match pid {
+            Some(pid) => stop(config, pid).await,
+            None => Err(anyhow::anyhow!("No disconnect command set for {}", config.name)),
+        };
+    }
+
+    let mut cmd = shell(&expand(template, config, false), config);
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, &expand(template, config, true), &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "The disconnect command of {} failed: {}",
+            config.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// Ends a client that stayed in the foreground, and what it started
+async fn stop(config: &VpnConfig, pid: u32) -> Result<()> {
+    #[cfg(unix)]
+    let mut cmd = {
+        let mut cmd = Command::new("kill");
+        cmd.args(["-TERM", &pid.to_string()]);
+        cmd
+    };
+
+    #[cfg(windows)]
+    let mut cmd = {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = Command::new("taskkill");
+        cmd.args(["/PID", &pid.to_string(), "/T", "/F"]);
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        cmd
+    };
+
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).stdin(Stdio::null());
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, &format!("stop process {}", pid), &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to stop the client of {}: {}",
+            config.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// The status command when there is one, else whether the tunnel interface is up, else whether
+// the app connected it and its client, if it stayed in the foreground, still runs
+pub async fn check_connection_status(config: &VpnConfig) -> Result<bool> {
+    let template = config.custom.status.trim();
+    if !template.is_empty() {
+        let mut cmd = shell(&expand(template, config, false), config);
+        cmd.stdout(Stdio::null()).stderr(Stdio::null());
+        return Ok(cmd.status().await?.success());
+    }
+    if !config.custom.interface.trim().is_empty() {
+        return Ok(!tunnel::adapters(config).await.is_empty());
+    }
+
+    let started = STARTED.lock().unwrap().get(&config.name).copied();
+    match started {
+        None => Ok(false),
+        Some(None) => Ok(true),
+        Some(Some(pid)) => is_running(pid).await,
+    }
+}
+
+#[cfg(unix)]
+async fn is_running(pid: u32) -> Result<bool> {
+    let output = Command::new("ps").args(["-p", &pid.to_string()]).output().await?;
+    Ok(output.status.success())
+}
+
+#[cfg(windows)]
+async fn is_running(pid: u32) -> Result<bool> {
+    use std::os::windows::process::CommandExt;
+    let mut cmd = Command::new("tasklist");
+    cmd.args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null())
+        .creation_flags(0x08000000); // CREATE_NO_WINDOW
+    let output = cmd.output().await?;
+    Ok(String::from_utf8_lossy(&output.stdout).split_whitespace().any(|word| word == pid.to_string()))
+}
@@ -0,0 +1,507 @@
+use crate::config::WebDashboard;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// Read-only status page for phones on the same network. Localhost-only by default; on the LAN
+// every request must carry the token. The only action is waking a configured device, which is
+// handed to the app the same way a click on its Wake button would be. Requests must name the
+// dashboard's own address as their Host, and a wake must come from its own page, so a web page
+// open in a browser here can't reach it, not even through a DNS name rebound to 127.0.0.1.
+
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+// Served over plain http, so a Host without a port means this one
+const HTTP_PORT: u16 = 80;
+
+// How long a pairing QR code stays valid if nobody scans it
+const PAIRING_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DashboardSnapshot {
+    pub vpn_status: String,
+    pub vpns: Vec<DashboardVpn>,
+    pub devices: Vec<DashboardDevice>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardVpn {
+    pub name: String,
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardDevice {
+    pub name: String,
+    pub ip_address: String,
+    pub online: bool,
+    pub waking: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum DashboardCommand {
+    Wake(String),
+}
+
+// One-time code a phone trades for the real token by scanning the pairing QR code, so the
+// code on screen (or in a photo of it) is useless once it has been used
+struct PairingCode {
+    code: String,
+    expires: std::time::Instant,
+}
+
+// Running server; dropping it stops listening
+pub struct DashboardServer {
+    snapshot: Arc<Mutex<DashboardSnapshot>>,
+    pairing: Arc<Mutex<Option<PairingCode>>>,
+    commands: mpsc::Receiver<DashboardCommand>,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl DashboardServer {
+    pub fn start(settings: &WebDashboard) -> anyhow::Result<Self> {
+        if settings.allow_lan && settings.token.is_empty() {
+            anyhow::bail!("A token is required before the dashboard can be opened to the LAN");
+        }
+
+        let ip = if settings.allow_lan { IpAddr::V4(Ipv4Addr::UNSPECIFIED) } else { IpAddr::V4(Ipv4Addr::LOCALHOST) };
+        let address = SocketAddr::new(ip, settings.port);
+
+        // Bind up front so a port conflict is reported to the caller
+        let listener = std::net::TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        let snapshot = Arc::new(Mutex::new(DashboardSnapshot::default()));
+        let (command_tx, command_rx) = mpsc::channel();
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::oneshot::channel();
+        let token = if settings.allow_lan { Some(settings.token.clone()) } else { None };
+        let shared = snapshot.clone();
+        let pairing = Arc::new(Mutex::new(None));
+        let shared_pairing = pairing.clone();
+
+        let thread = std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async move {
+                let listener = match TcpListener::from_std(listener) {
+                    Ok(listener) => listener,
+                    Err(e) => {
+                        log::error!("Web dashboard failed to start: {}", e);
+                        return;
+                    }
+                };
+                log::info!("Web dashboard listening on {}", address);
+
+                loop {
+                    tokio::select! {
+                        _ = &mut shutdown_rx => break,
+                        accepted = listener.accept() => {
+                            let Ok((stream, peer)) = accepted else { continue };
+                            let token = token.clone();
+                            let shared = shared.clone();
+                            let pairing = shared_pairing.clone();
+                            let command_tx = command_tx.clone();
+                            tokio::spawn(async move {
+                                if let Err(e) = handle_connection(stream, address.port(), token, shared, pairing, command_tx).await {
+                                    log::debug!("Dashboard request from {} failed: {}", peer, e);
+                                }
+                            });
+                        }
+                    }
+                }
+
+                log::info!("Web dashboard stopped");
+            });
+        });
+
+        Ok(Self {
+            snapshot,
+            pairing,
+            commands: command_rx,
+            shutdown: Some(shutdown_tx),
+            thread: Some(thread),
+        })
+    }
+
+    pub fn update(&self, snapshot: DashboardSnapshot) {
+        *self.snapshot.lock().unwrap() = snapshot;
+    }
+
+    pub fn try_recv(&self) -> Option<DashboardCommand> {
+        self.commands.try_recv().ok()
+    }
+
+    // Issues a new pairing code, replacing any earlier one that hasn't been used
+    pub fn start_pairing(&self) -> anyhow::Result<String> {
+        let code = generate_token()?;
+        *self.pairing.lock().unwrap() = Some(PairingCode {
+            code: code.clone(),
+            expires: std::time::Instant::now() + PAIRING_TTL,
+        });
+        Ok(code)
+    }
+
+    // False once the current code has been scanned or has expired
+    pub fn pairing_active(&self) -> bool {
+        self.pairing.lock().unwrap().as_ref().is_some_and(|p| p.expires > std::time::Instant::now())
+    }
+
+    pub fn cancel_pairing(&self) {
+        *self.pairing.lock().unwrap() = None;
+    }
+}
+
+impl Drop for DashboardServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        // Wait for the listener to close so a restart can bind the same port
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// 128 random bits from the OS, as hex
+pub fn generate_token() -> anyhow::Result<String> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| anyhow::anyhow!("No random numbers available"))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+// Addresses other devices on the LAN can reach the dashboard at
+pub fn lan_urls(settings: &WebDashboard) -> Vec<String> {
+    lan_addresses()
+        .into_iter()
+        .map(|ip| format!("http://{}:{}/?token={}", ip, settings.port, settings.token))
+        .collect()
+}
+
+// Link encoded in the pairing QR code; the phone is redirected to the tokened URL on first use
+pub fn pairing_url(settings: &WebDashboard, code: &str) -> Option<String> {
+    let ip = lan_addresses().into_iter().next()?;
+    Some(format!("http://{}:{}/?pair={}", ip, settings.port, code))
+}
+
+fn lan_addresses() -> Vec<Ipv4Addr> {
+    use network_interface::NetworkInterfaceConfig;
+
+    let Ok(interfaces) = network_interface::NetworkInterface::show() else { return Vec::new() };
+    interfaces
+        .iter()
+        .flat_map(|iface| iface.addr.iter())
+        .filter_map(|addr| match addr.ip() {
+            IpAddr::V4(ip) if !ip.is_loopback() && !ip.is_link_local() => Some(ip),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn local_url(settings: &WebDashboard) -> String {
+    format!("http://127.0.0.1:{}/", settings.port)
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    port: u16,
+    token: Option<String>,
+    snapshot: Arc<Mutex<DashboardSnapshot>>,
+    pairing: Arc<Mutex<Option<PairingCode>>>,
+    commands: mpsc::Sender<DashboardCommand>,
+) -> anyhow::Result<()> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = tokio::time::timeout(std::time::Duration::from_secs(5), stream.read(&mut chunk)).await??;
+        if read == 0 || buffer.len() + read > MAX_REQUEST_BYTES {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    let request = String::from_utf8_lossy(&buffer);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let target = request_line.next().unwrap_or("/");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let host = header(&request, "Host").unwrap_or_default();
+    if !is_own_host(&host, port, token.is_some()) {
+        return respond(&mut stream, "421 Misdirected Request", "text/plain", "Unknown host").await;
+    }
+
+    if let (Some(token), Some(code)) = (&token, query_param(query, "pair")) {
+        if method != "GET" || path != "/" {
+            return respond(&mut stream, "404 Not Found", "text/plain", "Not found").await;
+        }
+        // Redeeming consumes the code whether or not the phone follows the redirect
+        let redeemed = {
+            let mut pairing = pairing.lock().unwrap();
+            let valid = pairing
+                .as_ref()
+                .is_some_and(|p| p.expires > std::time::Instant::now() && constant_time_eq(&code, &p.code));
+            if valid {
+                *pairing = None;
+            }
+            valid
+        };
+        if !redeemed {
+            return respond(&mut stream, "401 Unauthorized", "text/plain", "Pairing code expired or already used").await;
+        }
+        let response = format!(
+            "HTTP/1.1 303 See Other\r\nLocation: /?token={}\r\nCache-Control: no-store\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            token
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await?;
+        return Ok(());
+    }
+
+    if let Some(token) = &token {
+        if !constant_time_eq(query_param(query, "token").as_deref().unwrap_or(""), token) {
+            return respond(&mut stream, "401 Unauthorized", "text/plain", "Missing or wrong token").await;
+        }
+    }
+
+    match (method, path) {
+        ("GET", "/") => respond(&mut stream, "200 OK", "text/html; charset=utf-8", PAGE).await,
+        ("GET", "/api/status") => {
+            let body = serde_json::to_string(&*snapshot.lock().unwrap())?;
+            respond(&mut stream, "200 OK", "application/json", &body).await
+        }
+        ("POST", "/api/wake") => {
+            // Browsers send the Origin of the page making the request; only this dashboard's own
+            // page may wake devices
+            if header(&request, "Origin").is_some_and(|origin| origin != format!("http://{}", host)) {
+                return respond(&mut stream, "403 Forbidden", "text/plain", "Cross-origin request refused").await;
+            }
+            let name = query_param(query, "name").unwrap_or_default();
+            let known = snapshot.lock().unwrap().devices.iter().any(|d| d.name == name);
+            if !known {
+                return respond(&mut stream, "404 Not Found", "text/plain", "Unknown device").await;
+            }
+            let _ = commands.send(DashboardCommand::Wake(name));
+            respond(&mut stream, "202 Accepted", "text/plain", "Wake requested").await
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", "Not found").await,
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> anyhow::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn header(request: &str, name: &str) -> Option<String> {
+    request
+        .lines()
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim().to_string())
+}
+
+// The Host header names an address the dashboard listens on: the loopback address, and with
+// LAN access one of this machine's, never a DNS name
+fn is_own_host(host: &str, port: u16, allow_lan: bool) -> bool {
+    let Some((name, host_port)) = split_host(host) else { return false };
+    if host_port != port {
+        return false;
+    }
+    if name.eq_ignore_ascii_case("localhost") {
+        return true;
+    }
+    match name.parse::<IpAddr>() {
+        Ok(ip) if ip.is_loopback() => true,
+        Ok(IpAddr::V4(ip)) => allow_lan && lan_addresses().contains(&ip),
+        _ => false,
+    }
+}
+
+// Name and port of a Host header: `name`, `name:port`, `[v6]` or `[v6]:port`. An IPv6 address
+// must be in brackets, otherwise its last group would read as the port.
+fn split_host(host: &str) -> Option<(&str, u16)> {
+    let (name, rest) = match host.strip_prefix('[') {
+        Some(bracketed) => {
+            let (address, rest) = bracketed.split_once(']')?;
+            address.parse::<Ipv6Addr>().ok()?;
+            (address, rest)
+        }
+        None => match host.find(':') {
+            Some(colon) => host.split_at(colon),
+            None => (host, ""),
+        },
+    };
+    if name.is_empty() {
+        return None;
+    }
+    match rest {
+        "" => Some((name, HTTP_PORT)),
+        _ => Some((name, rest.strip_prefix(':')?.parse().ok()?)),
+    }
+}
+
+fn query_param(query: &str, key: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => match text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+                None => decoded.push(b'%'),
+            },
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>VPN Manager</title>
+<style>
+body { font-family: system-ui, sans-serif; margin: 0; padding: 16px; background: #121418; color: #e6e6e6; }
+h1 { font-size: 20px; } h2 { font-size: 16px; color: #9aa0a6; margin-top: 24px; }
+.row { display: flex; align-items: center; justify-content: space-between; padding: 12px; margin: 6px 0; background: #1e2127; border-radius: 8px; }
+.dot { display: inline-block; width: 10px; height: 10px; border-radius: 50%; margin-right: 8px; }
+.on { background: #4caf50; } .off { background: #757575; } .busy { background: #ffb300; }
+.sub { color: #9aa0a6; font-size: 13px; }
+button { background: #3b82f6; color: white; border: 0; border-radius: 6px; padding: 8px 14px; font-size: 15px; }
+button:disabled { opacity: 0.5; }
+</style>
+</head>
+<body>
+<h1>VPN Manager</h1>
+<div id="vpn-status" class="sub"></div>
+<h2>VPN</h2><div id="vpns"></div>
+<h2>Devices</h2><div id="devices"></div>
+<script>
+const token = new URLSearchParams(location.search).get("token") || "";
+const q = token ? "token=" + encodeURIComponent(token) : "";
+function esc(s) { const d = document.createElement("div"); d.textContent = s; return d.innerHTML; }
+async function wake(name, button) {
+  button.disabled = true;
+  await fetch("/api/wake?name=" + encodeURIComponent(name) + (q ? "&" + q : ""), { method: "POST" });
+  setTimeout(refresh, 1000);
+}
+async function refresh() {
+  const r = await fetch("/api/status" + (q ? "?" + q : ""));
+  if (!r.ok) { document.getElementById("vpn-status").textContent = "Error " + r.status; return; }
+  const s = await r.json();
+  document.getElementById("vpn-status").textContent = s.vpn_status;
+  document.getElementById("vpns").innerHTML = s.vpns.map(v =>
+    `<div class="row"><span><span class="dot ${v.connected ? "on" : "off"}"></span>${esc(v.name)}</span>` +
+    `<span class="sub">${v.connected ? "Connected" : "Disconnected"}</span></div>`).join("") || '<div class="sub">None</div>';
+  const devices = document.getElementById("devices");
+  devices.innerHTML = "";
+  for (const d of s.devices) {
+    const row = document.createElement("div");
+    row.className = "row";
+    row.innerHTML = `<span><span class="dot ${d.waking ? "busy" : d.online ? "on" : "off"}"></span>${esc(d.name)}` +
+      ` <span class="sub">${esc(d.ip_address)}</span></span>`;
+    const button = document.createElement("button");
+    button.textContent = d.waking ? "Waking..." : "Wake";
+    button.disabled = d.online || d.waking;
+    button.onclick = () => wake(d.name, button);
+    row.appendChild(button);
+    devices.appendChild(row);
+  }
+  if (!s.devices.length) devices.innerHTML = '<div class="sub">None</div>';
+}
+refresh();
+setInterval(refresh, 5000);
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_is_128_bits_of_hex() {
+        let (first, second) = (generate_token().unwrap(), generate_token().unwrap());
+        assert_eq!(first.len(), 32);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn headers_are_found_by_any_case() {
+        let request = "POST /api/wake?name=nas HTTP/1.1\r\nhost: 127.0.0.1:8787\r\nOrigin: http://evil.example\r\n\r\n";
+        assert_eq!(header(request, "Host").as_deref(), Some("127.0.0.1:8787"));
+        assert_eq!(header(request, "origin").as_deref(), Some("http://evil.example"));
+        assert_eq!(header(request, "Referer"), None);
+    }
+
+    #[test]
+    fn only_own_addresses_are_hosts() {
+        assert!(is_own_host("127.0.0.1:8787", 8787, false));
+        assert!(is_own_host("localhost:8787", 8787, false));
+        assert!(is_own_host("[::1]:8787", 8787, false));
+        assert!(!is_own_host("127.0.0.1:8080", 8787, false));
+        assert!(!is_own_host("127.0.0.1", 8787, false));
+        // A DNS name pointed at 127.0.0.1 by the site that owns it
+        assert!(!is_own_host("rebind.example:8787", 8787, true));
+        assert!(!is_own_host("192.0.2.10:8787", 8787, false));
+    }
+
+    #[test]
+    fn a_host_without_port_means_port_80() {
+        assert!(is_own_host("localhost", 80, false));
+        assert!(is_own_host("LOCALHOST:80", 80, false));
+        assert!(!is_own_host("localhost", 8787, false));
+        assert!(is_own_host("[::1]", 80, false));
+        assert!(!is_own_host("[::1]", 8787, false));
+    }
+
+    #[test]
+    fn ipv6_hosts_need_brackets() {
+        assert!(is_own_host("[::1]:8787", 8787, false));
+        assert!(!is_own_host("[::1]:8080", 8787, false));
+        assert!(!is_own_host("::1:8787", 8787, false));
+        assert!(!is_own_host("[::1]8787", 8787, false));
+        assert!(!is_own_host("[localhost]:8787", 8787, false));
+        assert!(!is_own_host("[::1:8787", 8787, false));
+        assert!(!is_own_host(":8787", 8787, false));
+    }
+}
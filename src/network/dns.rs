@@ -0,0 +1,222 @@
+use crate::config::VpnConfig;
+use crate::network::tunnel;
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::process::Stdio;
+use std::sync::Mutex;
+use tokio::process::Command;
+
+// Per-profile DNS servers and search domains set on the tunnel interface after connecting,
+// replacing what the VPN pushed, and reverted on disconnect. systemd-resolved (resolvectl) is
+// preferred on Linux with NetworkManager as the fallback; Windows sets the adapter's servers and
+// prepends the domains to the global suffix search list.
+
+static APPLIED: Mutex<Vec<AppliedDns>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+struct AppliedDns {
+    vpn: String,
+    // Device name on Linux, interface index on Windows
+    interface: String,
+    // Search list before ours was prepended
+    #[cfg(windows)]
+    previous_suffixes: Option<String>,
+}
+
+pub fn is_configured(config: &VpnConfig) -> bool {
+    !config.dns_servers.is_empty() || !config.dns_search_domains.is_empty()
+}
+
+// Splits a comma or whitespace separated list as typed in the UI
+pub fn parse_list(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+        .map(|item| item.to_string())
+        .collect()
+}
+
+// Letters, digits, dots and dashes; a leading ~ marks a routing-only domain for systemd-resolved
+pub fn is_valid_domain(domain: &str) -> bool {
+    let domain = domain.strip_prefix('~').unwrap_or(domain);
+    !domain.is_empty() && domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+}
+
+pub async fn apply(config: &VpnConfig) -> Result<()> {
+    if !is_configured(config) {
+        return Ok(());
+    }
+    if let Some(server) = config.dns_servers.iter().find(|s| s.parse::<std::net::IpAddr>().is_err()) {
+        return Err(anyhow::anyhow!("DNS server is not an IP address: {}", server));
+    }
+    if let Some(domain) = config.dns_search_domains.iter().find(|d| !is_valid_domain(d)) {
+        return Err(anyhow::anyhow!("Search domain is not valid: {}", domain));
+    }
+
+    let adapter = tunnel::adapters(config).await.into_iter().next()
+        .ok_or_else(|| anyhow::anyhow!("Tunnel interface not found, DNS settings not applied"))?;
+
+    #[cfg(windows)]
+    {
+        let interface = adapter.index.to_string();
+        let previous_suffixes = apply_windows(config, &interface).await?;
+        APPLIED.lock().unwrap().push(AppliedDns { vpn: config.name.clone(), interface, previous_suffixes });
+    }
+
+    #[cfg(unix)]
+    {
+        apply_unix(config, &adapter.name).await?;
+        APPLIED.lock().unwrap().push(AppliedDns { vpn: config.name.clone(), interface: adapter.name });
+    }
+
+    Ok(())
+}
+
+// Reverts the interface; run before the tunnel goes down so the interface still exists. Failures
+// are only logged, the settings vanish with the interface anyway.
+pub async fn restore(config: &VpnConfig) {
+    let applied: Vec<AppliedDns> = {
+        let mut applied = APPLIED.lock().unwrap();
+        let (mine, others) = applied.drain(..).partition(|dns| dns.vpn == config.name);
+        *applied = others;
+        mine
+    };
+
+    for dns in applied {
+        #[cfg(windows)]
+        let result = restore_windows(config, &dns).await;
+        #[cfg(unix)]
+        let result = restore_unix(config, &dns).await;
+
+        if let Err(e) = result {
+            log::debug!("DNS settings on {} not restored: {}", dns.interface, e);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn use_resolvectl() -> bool {
+    which::which("resolvectl").is_ok()
+}
+
+#[cfg(unix)]
+async fn apply_unix(config: &VpnConfig, interface: &str) -> Result<()> {
+    if use_resolvectl() {
+        if !config.dns_servers.is_empty() {
+            let mut args = vec!["resolvectl".to_string(), "dns".to_string(), interface.to_string()];
+            args.extend(config.dns_servers.iter().cloned());
+            run(config, "sudo", &args).await?;
+        }
+        if !config.dns_search_domains.is_empty() {
+            let mut args = vec!["resolvectl".to_string(), "domain".to_string(), interface.to_string()];
+            args.extend(config.dns_search_domains.iter().cloned());
+            run(config, "sudo", &args).await?;
+        }
+        return Ok(());
+    }
+
+    if which::which("nmcli").is_err() {
+        return Err(anyhow::anyhow!("Neither resolvectl nor nmcli is available to set DNS servers"));
+    }
+
+    // Changes the active connection on the device only, not the saved profile
+    let mut args = vec!["device".to_string(), "modify".to_string(), interface.to_string()];
+    let (v4, v6): (Vec<&String>, Vec<&String>) = config.dns_servers.iter().partition(|server| !server.contains(':'));
+    if !v4.is_empty() {
+        args.extend(["ipv4.dns".to_string(), v4.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(",")]);
+        args.extend(["ipv4.ignore-auto-dns".to_string(), "yes".to_string()]);
+    }
+    if !v6.is_empty() {
+        args.extend(["ipv6.dns".to_string(), v6.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(",")]);
+        args.extend(["ipv6.ignore-auto-dns".to_string(), "yes".to_string()]);
+    }
+    if !config.dns_search_domains.is_empty() {
+        args.extend(["ipv4.dns-search".to_string(), config.dns_search_domains.join(",")]);
+    }
+    run(config, "nmcli", &args).await
+}
+
+#[cfg(unix)]
+async fn restore_unix(config: &VpnConfig, dns: &AppliedDns) -> Result<()> {
+    if use_resolvectl() {
+        let args = ["resolvectl", "revert", dns.interface.as_str()].map(String::from);
+        return run(config, "sudo", &args).await;
+    }
+
+    // Reapplying the connection drops the runtime changes
+    let args = ["device", "reapply", dns.interface.as_str()].map(String::from);
+    run(config, "nmcli", &args).await
+}
+
+#[cfg(windows)]
+async fn apply_windows(config: &VpnConfig, interface: &str) -> Result<Option<String>> {
+    if !config.dns_servers.is_empty() {
+        let servers = config.dns_servers.iter().map(|s| format!("'{}'", s)).collect::<Vec<_>>().join(",");
+        let script = format!("Set-DnsClientServerAddress -InterfaceIndex {} -ServerAddresses @({})", interface, servers);
+        run_powershell(config, &script).await?;
+    }
+
+    if config.dns_search_domains.is_empty() {
+        return Ok(None);
+    }
+
+    let previous = run_powershell(config, "(Get-DnsClientGlobalSetting).SuffixSearchList -join ','").await?;
+    let previous = previous.trim().to_string();
+    // Windows has no routing-only domains, so ~ is dropped
+    let mut suffixes: Vec<String> = config.dns_search_domains.iter().map(|d| d.trim_start_matches('~').to_string()).collect();
+    let previous_list: Vec<String> = previous.split(',').filter(|s| !s.is_empty() && !suffixes.iter().any(|d| d == s)).map(String::from).collect();
+    suffixes.extend(previous_list);
+    let list = suffixes.iter().map(|s| format!("'{}'", s)).collect::<Vec<_>>().join(",");
+    run_powershell(config, &format!("Set-DnsClientGlobalSetting -SuffixSearchList @({})", list)).await?;
+
+    Ok(Some(previous))
+}
+
+#[cfg(windows)]
+async fn restore_windows(config: &VpnConfig, dns: &AppliedDns) -> Result<()> {
+    run_powershell(config, &format!("Set-DnsClientServerAddress -InterfaceIndex {} -ResetServerAddresses", dns.interface)).await?;
+    if let Some(previous) = &dns.previous_suffixes {
+        let list = previous.split(',').filter(|s| !s.is_empty()).map(|s| format!("'{}'", s)).collect::<Vec<_>>().join(",");
+        run_powershell(config, &format!("Set-DnsClientGlobalSetting -SuffixSearchList @({})", list)).await?;
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+async fn run_powershell(config: &VpnConfig, script: &str) -> Result<String> {
+    use std::os::windows::process::CommandExt;
+
+    let mut cmd = Command::new("powershell");
+    cmd.args(&["-NoProfile", "-Command", script])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, script, &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(unix)]
+async fn run(config: &VpnConfig, program: &str, args: &[String]) -> Result<()> {
+    let output = executor::output(Command::new(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null()))
+        .await?;
+    client_log::record("vpn", &config.name, &format!("{} {}", program, args.join(" ")), &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to set DNS: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::config::VpnConfig;
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+
+// DNS leak test. Lookups of unique names under a leak-test domain reach its authoritative servers
+// through whatever resolvers the system uses; the service then reports which resolver IPs asked,
+// along with the network (ASN) each belongs to. Resolvers on the same network as the tunnel's
+// exit IP, or set as the profile's DNS servers, count as the tunnel's; any other resolver
+// answered outside it, usually the ISP's.
+
+const SERVICE: &str = "bash.ws";
+// Several lookups so resolvers picked round-robin all show up
+const LOOKUPS: usize = 10;
+
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    pub ip: String,
+    // ASN and operator as reported by the service, e.g. "AS13335 Cloudflare, Inc."
+    pub network: String,
+    pub country: String,
+    pub through_tunnel: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct LeakTestResult {
+    // Public address the service saw the test come from, i.e. the tunnel's exit
+    pub exit_ip: Option<String>,
+    pub exit_network: String,
+    pub resolvers: Vec<Resolver>,
+    pub tested_at: chrono::DateTime<chrono::Local>,
+}
+
+impl LeakTestResult {
+    pub fn passed(&self) -> bool {
+        !self.resolvers.is_empty() && self.resolvers.iter().all(|r| r.through_tunnel)
+    }
+    
+    pub fn leaks(&self) -> usize {
+        self.resolvers.iter().filter(|r| !r.through_tunnel).count()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceEntry {
+    #[serde(default)]
+    ip: String,
+    #[serde(default)]
+    country_name: String,
+    #[serde(default)]
+    asn: String,
+    // "ip" for the client, "dns" for each resolver, "conclusion" for the service's own verdict
+    #[serde(rename = "type", default)]
+    kind: String,
+}
+
+pub async fn run(config: &VpnConfig) -> Result<LeakTestResult> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(15))
+        .build()?;
+    
+    let id = client.get(format!("https://{}/id", SERVICE))
+        .header("User-Agent", "vpn-manager")
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let id = id.trim();
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(anyhow::anyhow!("Leak test service returned an unexpected test id"));
+    }
+    
+    // The names don't resolve; only the queries reaching the service matter
+    for i in 1..=LOOKUPS {
+        let name = format!("{}.{}.{}:80", i, id, SERVICE);
+        let _ = tokio::time::timeout(Duration::from_secs(3), tokio::net::lookup_host(name)).await;
+    }
+    
+    let entries: Vec<ServiceEntry> = client.get(format!("https://{}/dnsleak/test/{}?json", SERVICE, id))
+        .header("User-Agent", "vpn-manager")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    
+    let exit = entries.iter().find(|e| e.kind == "ip");
+    let exit_network = exit.map(|e| e.asn.clone()).unwrap_or_default();
+    let resolvers: Vec<Resolver> = entries.iter()
+        .filter(|e| e.kind == "dns")
+        .map(|e| Resolver {
+            ip: e.ip.clone(),
+            network: e.asn.clone(),
+            country: e.country_name.clone(),
+            through_tunnel: (!exit_network.is_empty() && same_network(&e.asn, &exit_network))
+                || config.dns_servers.iter().any(|server| server == &e.ip),
+        })
+        .collect();
+    
+    if resolvers.is_empty() {
+        return Err(anyhow::anyhow!("No resolver reached the leak test service; DNS may be blocked"));
+    }
+    
+    let result = LeakTestResult {
+        exit_ip: exit.map(|e| e.ip.clone()),
+        exit_network,
+        resolvers,
+        tested_at: chrono::Local::now(),
+    };
+    log::info!("DNS leak test for {}: {} of {} resolvers outside the tunnel", config.name, result.leaks(), result.resolvers.len());
+    Ok(result)
+}
+
+// Compares the AS numbers, since the operator names the service reports vary in spelling
+fn same_network(a: &str, b: &str) -> bool {
+    let asn = |text: &str| text.split_whitespace().next().map(|s| s.to_uppercase());
+    match (asn(a), asn(b)) {
+        (Some(a), Some(b)) if a.starts_with("AS") => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
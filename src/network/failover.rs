@@ -0,0 +1,85 @@
+use crate::config::VpnConfig;
+use crate::network::{dns, routes, NetworkManager, VpnStatus};
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+// Falling back to other VPN profiles: a connection is tried along the failover order until one
+// comes up (NetworkManager::connect_first), and a profile that keeps dropping is passed over for
+// a while so the app stops bouncing back to it. Drops are counted here; the app decides when to
+// fail over. Walking the order can take a connect timeout per profile, so it runs on a thread of
+// its own like the startup checks, and reports as it goes.
+
+// Drops older than this no longer count against a profile
+pub const DROP_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Default)]
+pub struct DropTracker {
+    drops: HashMap<String, Vec<Instant>>,
+}
+
+impl DropTracker {
+    // Counts a drop and returns how many happened within the window
+    pub fn record(&mut self, name: &str) -> u32 {
+        let drops = self.drops.entry(name.to_string()).or_default();
+        drops.retain(|at| at.elapsed() < DROP_WINDOW);
+        drops.push(Instant::now());
+        drops.len() as u32
+    }
+
+    // Dropped max_drops times or more within the window
+    pub fn is_unstable(&self, name: &str, max_drops: u32) -> bool {
+        let recent = self.drops.get(name).map_or(0, |drops| drops.iter().filter(|at| at.elapsed() < DROP_WINDOW).count());
+        max_drops > 0 && recent as u32 >= max_drops
+    }
+
+    // Connected by hand, so its record starts over
+    pub fn forget(&mut self, name: &str) {
+        self.drops.remove(name);
+    }
+}
+
+#[derive(Debug)]
+pub enum FailoverEvent {
+    // The profile being tried now
+    Trying(String),
+    // The profile that came up, or why none did, and the status that left
+    Finished { status: VpnStatus, result: anyhow::Result<String> },
+}
+
+pub struct FailoverTask {
+    receiver: Receiver<FailoverEvent>,
+}
+
+impl FailoverTask {
+    // Connects the first profile of the chain that comes up. A tunnel that dropped is passed as
+    // well, its routes and DNS settings went with the old interface and are cleaned up first.
+    pub fn start(network_manager: &NetworkManager, chain: Vec<VpnConfig>, dropped: Option<VpnConfig>) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut network_manager = network_manager.clone();
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                if let Some(vpn_config) = &dropped {
+                    routes::remove(vpn_config).await;
+                    dns::restore(vpn_config).await;
+                }
+                let chain: Vec<&VpnConfig> = chain.iter().collect();
+                let result = network_manager
+                    .connect_first_reporting(&chain, |name| {
+                        let _ = tx.send(FailoverEvent::Trying(name.to_string()));
+                    })
+                    .await;
+                let _ = tx.send(FailoverEvent::Finished { status: network_manager.vpn_status.clone(), result });
+            });
+        });
+
+        Self { receiver: rx }
+    }
+
+    // The next report; Disconnected once the thread is done
+    pub fn poll(&self) -> Result<FailoverEvent, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
@@ -0,0 +1,90 @@
+use crate::config::VpnConfig;
+use crate::network::tunnel;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Whether a connected tunnel actually passes traffic. The client process running and its
+// interface being up say nothing about the server still answering, so a host on the far side
+// (the VPN gateway or an internal server) is pinged through the tunnel interface now and then.
+
+const TIMEOUT_SECS: u64 = 2;
+// Probes kept for the loss figure
+const WINDOW: usize = 10;
+// Consecutive lost probes before the tunnel counts as not passing traffic
+const LOST_IN_A_ROW: usize = 3;
+
+#[derive(Debug, Clone)]
+pub struct TunnelHealth {
+    pub host: String,
+    // Round trip of each probe, None when it got no answer; oldest first
+    probes: VecDeque<Option<Duration>>,
+}
+
+impl TunnelHealth {
+    pub fn new(host: &str) -> Self {
+        Self { host: host.to_string(), probes: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, rtt: Option<Duration>) {
+        self.probes.push_back(rtt);
+        while self.probes.len() > WINDOW {
+            self.probes.pop_front();
+        }
+    }
+
+    pub fn last_rtt(&self) -> Option<Duration> {
+        self.probes.back().copied().flatten()
+    }
+
+    // Share of the recent probes that got no answer, None before the first one
+    pub fn loss_percent(&self) -> Option<u32> {
+        if self.probes.is_empty() {
+            return None;
+        }
+        let lost = self.probes.iter().filter(|rtt| rtt.is_none()).count();
+        Some((lost * 100 / self.probes.len()) as u32)
+    }
+
+    pub fn not_passing(&self) -> bool {
+        self.probes.len() >= LOST_IN_A_ROW && self.probes.iter().rev().take(LOST_IN_A_ROW).all(|rtt| rtt.is_none())
+    }
+}
+
+// One ping to the host, sent out of the tunnel's interface so it can't take the physical link
+// instead; None when nothing came back in time
+pub async fn probe(config: &VpnConfig, host: &str) -> Option<Duration> {
+    let adapter = tunnel::adapters(config).await.into_iter().next()?;
+    let mut cmd = tokio::process::Command::new("ping");
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // Windows ping binds to a source address rather than an interface
+        let source = adapter.addr.iter().map(|addr| addr.ip()).find(|ip| ip.is_ipv4())?;
+        cmd.args(&["-n", "1", "-w", &(TIMEOUT_SECS * 1000).to_string(), "-S", &source.to_string(), host]);
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    #[cfg(not(windows))]
+    cmd.args(["-c", "1", "-W", &TIMEOUT_SECS.to_string(), "-I", &adapter.name, host]);
+    cmd.stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    let started = Instant::now();
+    let output = cmd.output().await.ok()?;
+    let elapsed = started.elapsed();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Windows ping exits 0 for "Destination host unreachable" too, only a TTL means an answer
+    if !output.status.success() || !stdout.to_lowercase().contains("ttl=") {
+        return None;
+    }
+    Some(parse_rtt(&stdout).unwrap_or(elapsed))
+}
+
+// "time=12.3 ms" on Linux and macOS, "time=12ms" or "time<1ms" on Windows
+fn parse_rtt(output: &str) -> Option<Duration> {
+    let lower = output.to_lowercase();
+    let start = lower.find("time=").or_else(|| lower.find("time<"))? + 5;
+    let number: String = lower[start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let ms: f64 = number.parse().ok()?;
+    Some(Duration::from_secs_f64(ms / 1000.0))
+}
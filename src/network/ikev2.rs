@@ -0,0 +1,169 @@
+use crate::config::VpnConfig;
+use crate::errors::{self, ErrorCode};
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use tokio::process::Command;
+
+// IKEv2 entries don't point at a file: the config path holds the name of a connection that is
+// already defined in the system (a strongSwan connection in swanctl.conf/ipsec.conf on Linux,
+// a VPN connection from the Windows network settings on Windows)
+pub fn connection_name(config: &VpnConfig) -> &str {
+    config.config_path.trim()
+}
+
+pub async fn connect(config: &VpnConfig) -> Result<()> {
+    if connection_name(config).is_empty() {
+        return Err(errors::error(ErrorCode::VpnServerMissing, &config.name));
+    }
+
+    let (program, args) = connect_command(config);
+    run(config, program, args, "Failed to start IKEv2 connection").await
+}
+
+pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    let (program, args) = disconnect_command(config);
+    run(config, program, args, "Failed to stop IKEv2 connection").await
+}
+
+// Program and arguments for bringing the connection up, shared with the command preview
+pub fn connect_command(config: &VpnConfig) -> (&'static str, Vec<String>) {
+    let name = connection_name(config).to_string();
+
+    if cfg!(windows) {
+        // rasdial prompts for nothing, so credentials are passed inline when the entry has them
+        let mut args = vec![name];
+        if !config.username.is_empty() {
+            args.push(config.username.clone());
+            args.push(config.password.clone());
+        }
+        return ("rasdial", args);
+    }
+
+    match strongswan_tool() {
+        StrongSwanTool::Swanctl => ("sudo", vec!["swanctl".to_string(), "--initiate".to_string(), "--ike".to_string(), name]),
+        StrongSwanTool::Ipsec => ("sudo", vec!["ipsec".to_string(), "up".to_string(), name]),
+    }
+}
+
+pub fn disconnect_command(config: &VpnConfig) -> (&'static str, Vec<String>) {
+    let name = connection_name(config).to_string();
+
+    if cfg!(windows) {
+        return ("rasdial", vec![name, "/disconnect".to_string()]);
+    }
+
+    match strongswan_tool() {
+        StrongSwanTool::Swanctl => ("sudo", vec!["swanctl".to_string(), "--terminate".to_string(), "--ike".to_string(), name]),
+        StrongSwanTool::Ipsec => ("sudo", vec!["ipsec".to_string(), "down".to_string(), name]),
+    }
+}
+
+pub async fn check_connection_status(config: &VpnConfig) -> Result<bool> {
+    #[cfg(windows)]
+    {
+        get_status_windows(connection_name(config)).await
+    }
+
+    #[cfg(unix)]
+    {
+        get_status_unix(connection_name(config)).await
+    }
+}
+
+#[cfg(windows)]
+pub async fn get_status_windows(connection: &str) -> Result<bool> {
+    // Without arguments rasdial lists the active connections, one per line:
+    //   Connected to
+    //   Office VPN
+    //   Command completed successfully.
+    let mut cmd = Command::new("rasdial");
+    cmd.stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().await?;
+
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    Ok(output_str.lines().any(|line| line.trim().eq_ignore_ascii_case(connection)))
+}
+
+#[cfg(unix)]
+pub async fn get_status_unix(connection: &str) -> Result<bool> {
+    let args = match strongswan_tool() {
+        StrongSwanTool::Swanctl => vec!["swanctl", "--list-sas", "--ike", connection],
+        StrongSwanTool::Ipsec => vec!["ipsec", "status", connection],
+    };
+
+    // Both tools talk to the charon daemon over a root-owned socket
+    let output = executor::output(Command::new("sudo")
+        .arg("-n")
+        .args(&args))
+        .await?;
+
+    if !output.status.success() {
+        return Ok(false);
+    }
+
+    // An IKE_SA that is still negotiating shows as CONNECTING, only ESTABLISHED counts
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    Ok(output_str.contains("ESTABLISHED"))
+}
+
+enum StrongSwanTool {
+    // vici-based interface, the default since strongSwan 5.8
+    Swanctl,
+    // Legacy stroke interface reading /etc/ipsec.conf
+    Ipsec,
+}
+
+fn strongswan_tool() -> StrongSwanTool {
+    if which::which("swanctl").is_ok() || which::which("ipsec").is_err() {
+        StrongSwanTool::Swanctl
+    } else {
+        StrongSwanTool::Ipsec
+    }
+}
+
+async fn run(config: &VpnConfig, program: &str, args: Vec<String>, context: &str) -> Result<()> {
+    let mut cmd = Command::new(program);
+    cmd.args(&args)
+        .envs(&config.env_vars)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = executor::output(&mut cmd).await?;
+
+    // Log the subcommand but never the rasdial credentials
+    let label = if program == "sudo" {
+        args.iter().take(2).cloned().collect::<Vec<_>>().join(" ")
+    } else {
+        format!("{} {}", program, args.get(1).filter(|a| a.starts_with('/')).map_or("", |a| a.as_str()))
+    };
+    client_log::record("vpn", &config.name, label.trim(), &output);
+
+    if !output.status.success() {
+        // rasdial reports errors on stdout
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = if stderr.trim().is_empty() {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        } else {
+            stderr.trim().to_string()
+        };
+        return Err(anyhow::anyhow!("{}: {}", context, message));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,446 @@
+use crate::config::{VpnConfig, VpnType};
+use crate::errors::{self, ErrorCode};
+use crate::network::tunnel;
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::net::IpAddr;
+use std::process::Stdio;
+use tokio::process::Command;
+
+// Firewall rules that only let traffic out through the tunnel, plus to the VPN server itself so
+// the client can reconnect. They are installed once the tunnel is up and deliberately stay in
+// place if it drops, until the entry is disconnected from the app. Linux uses nftables, or
+// iptables where nft is missing; Windows uses netsh, saving each profile's default policy before
+// switching it to block and putting it back on disconnect.
+
+// nftables table and iptables chain on Linux, firewall rule name on Windows
+#[cfg(unix)]
+const NFT_TABLE: &str = "vpn_manager_killswitch";
+#[cfg(unix)]
+const IPTABLES_CHAIN: &str = "VPN_MANAGER_KS";
+#[cfg(windows)]
+const RULE_NAME: &str = "VPN Manager Kill Switch";
+// The profiles netsh lists a policy for, in the order it lists them
+#[cfg(windows)]
+const FIREWALL_PROFILES: [&str; 3] = ["domainprofile", "privateprofile", "publicprofile"];
+// What Windows ships with, restored when the policy from before wasn't saved
+#[cfg(windows)]
+const DEFAULT_FIREWALL_POLICY: &str = "blockinbound,allowoutbound";
+
+// strongSwan on Linux has no tunnel interface to allow, its traffic leaves encrypted through the
+// physical one
+pub fn is_supported(vpn_type: &VpnType) -> bool {
+    cfg!(windows) || *vpn_type != VpnType::Ikev2
+}
+
+pub async fn enable(config: &VpnConfig) -> Result<()> {
+    if !is_supported(&config.vpn_type) {
+        return Err(anyhow::anyhow!("The kill switch isn't supported for IKEv2 connections on this platform"));
+    }
+
+    let servers = server_addresses(config).await?;
+    if servers.is_empty() {
+        return Err(errors::error(ErrorCode::KillSwitchNoServer, &config.name));
+    }
+
+    #[cfg(windows)]
+    {
+        enable_windows(config, &servers).await
+    }
+
+    #[cfg(unix)]
+    {
+        enable_unix(config, &servers).await
+    }
+}
+
+pub async fn disable(config: &VpnConfig) -> Result<()> {
+    #[cfg(windows)]
+    {
+        disable_windows(config).await
+    }
+
+    #[cfg(unix)]
+    {
+        disable_unix(config).await
+    }
+}
+
+pub async fn is_active() -> bool {
+    #[cfg(windows)]
+    {
+        let rule_name = format!("name={}", RULE_NAME);
+        let mut cmd = Command::new("netsh");
+        cmd.args(&["advfirewall", "firewall", "show", "rule", rule_name.as_str()])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .stdin(Stdio::null());
+
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+
+        saved_policy_path().exists() || cmd.output().await.map(|output| output.status.success()).unwrap_or(false)
+    }
+
+    // Asking the firewall takes sudo, which may want a password again by the time the entry
+    // disconnects, so this goes by what enable left on disk
+    #[cfg(unix)]
+    {
+        marker_path().exists()
+    }
+}
+
+// Hosts the client connects to outside the tunnel, with the port to resolve them on
+async fn server_endpoints(config: &VpnConfig) -> Vec<(String, u16)> {
+    match config.vpn_type {
+        VpnType::OpenVpn => {
+            // remote <host> [port] [proto], possibly several for failover
+            let content = std::fs::read_to_string(config.resolved_config_path()).unwrap_or_default();
+            content
+                .lines()
+                .filter_map(|line| {
+                    let mut parts = line.split_whitespace();
+                    if parts.next()? != "remote" {
+                        return None;
+                    }
+                    let host = parts.next()?.to_string();
+                    let port = parts.next().and_then(|p| p.parse().ok()).unwrap_or(1194);
+                    Some((host, port))
+                })
+                .collect()
+        }
+        VpnType::WireGuard => {
+            let content = std::fs::read_to_string(config.resolved_config_path()).unwrap_or_default();
+            content
+                .lines()
+                .filter_map(|line| {
+                    let (key, value) = line.split_once('=')?;
+                    if !key.trim().eq_ignore_ascii_case("Endpoint") {
+                        return None;
+                    }
+                    let (host, port) = value.trim().rsplit_once(':')?;
+                    Some((host.trim_matches(|c| c == '[' || c == ']').to_string(), port.parse().ok()?))
+                })
+                .collect()
+        }
+        // Custom clients only say where they connect to through the entry's server, if at all
+        VpnType::OpenConnect | VpnType::Custom if !config.server.trim().is_empty() => {
+            // Accepts a bare host, host:port or a URL
+            let server = config.server.trim();
+            let server = server.split_once("://").map_or(server, |(_, rest)| rest);
+            let authority = server.split('/').next().unwrap_or("");
+            let (host, port) = match authority.rsplit_once(':') {
+                Some((host, port)) if port.parse::<u16>().is_ok() => (host, port.parse().unwrap_or(443)),
+                _ => (authority, 443),
+            };
+            vec![(host.to_string(), port)]
+        }
+        VpnType::Ikev2 => ikev2_server(config).await.map(|host| vec![(host, 500)]).unwrap_or_default(),
+        VpnType::OpenConnect | VpnType::Custom => Vec::new(),
+    }
+}
+
+async fn server_addresses(config: &VpnConfig) -> Result<Vec<IpAddr>> {
+    let mut addresses = Vec::new();
+    for (host, port) in server_endpoints(config).await {
+        if host.is_empty() {
+            continue;
+        }
+        // Resolved now, while DNS outside the tunnel is still reachable
+        for address in tokio::net::lookup_host((host.as_str(), port)).await? {
+            if !addresses.contains(&address.ip()) {
+                addresses.push(address.ip());
+            }
+        }
+    }
+    Ok(addresses)
+}
+
+#[cfg(windows)]
+async fn ikev2_server(config: &VpnConfig) -> Option<String> {
+    let script = format!(
+        "(Get-VpnConnection -Name '{}').ServerAddress",
+        crate::network::ikev2::connection_name(config).replace('\'', "''")
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(&["-NoProfile", "-Command", script.as_str()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().await.ok()?;
+    let server = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!server.is_empty()).then_some(server)
+}
+
+#[cfg(unix)]
+async fn ikev2_server(_config: &VpnConfig) -> Option<String> {
+    None
+}
+
+#[cfg(unix)]
+fn use_nftables() -> bool {
+    which::which("nft").is_ok()
+}
+
+#[cfg(unix)]
+pub async fn enable_unix(config: &VpnConfig, servers: &[IpAddr]) -> Result<()> {
+    let interface = tunnel::interface_pattern(config).await;
+    let v4: Vec<String> = servers.iter().filter(|ip| ip.is_ipv4()).map(|ip| ip.to_string()).collect();
+    let v6: Vec<String> = servers.iter().filter(|ip| ip.is_ipv6()).map(|ip| ip.to_string()).collect();
+
+    if use_nftables() {
+        // Declaring then deleting the table first makes re-enabling replace the old rules
+        let mut script = format!("table inet {table}\ndelete table inet {table}\n", table = NFT_TABLE);
+        script.push_str(&format!("table inet {} {{\n    chain output {{\n", NFT_TABLE));
+        script.push_str("        type filter hook output priority 0; policy drop;\n");
+        script.push_str("        oifname \"lo\" accept\n");
+        script.push_str(&format!("        oifname \"{}\" accept\n", interface));
+        if !v4.is_empty() {
+            script.push_str(&format!("        ip daddr {{ {} }} accept\n", v4.join(", ")));
+        }
+        if !v6.is_empty() {
+            script.push_str(&format!("        ip6 daddr {{ {} }} accept\n", v6.join(", ")));
+        }
+        // Keep DHCP leases renewing on the physical interface
+        script.push_str("        udp dport { 67, 547 } accept\n");
+        script.push_str("    }\n}\n");
+
+        run_unix(config, "nft -f - (kill switch on)", &["nft", "-f", "-"], Some(script)).await?;
+        return mark_active();
+    }
+
+    let interface = interface.replace('*', "+");
+    let mut script = vec!["set -e".to_string()];
+    for (binary, addresses, dhcp_port) in [("iptables", &v4, "67"), ("ip6tables", &v6, "547")] {
+        script.push(format!("{b} -N {c} 2>/dev/null || {b} -F {c}", b = binary, c = IPTABLES_CHAIN));
+        script.push(format!("{b} -C OUTPUT -j {c} 2>/dev/null || {b} -I OUTPUT 1 -j {c}", b = binary, c = IPTABLES_CHAIN));
+        script.push(format!("{} -A {} -o lo -j RETURN", binary, IPTABLES_CHAIN));
+        script.push(format!("{} -A {} -o {} -j RETURN", binary, IPTABLES_CHAIN, interface));
+        for address in addresses.iter() {
+            script.push(format!("{} -A {} -d {} -j RETURN", binary, IPTABLES_CHAIN, address));
+        }
+        script.push(format!("{} -A {} -p udp --dport {} -j RETURN", binary, IPTABLES_CHAIN, dhcp_port));
+        script.push(format!("{} -A {} -j REJECT", binary, IPTABLES_CHAIN));
+    }
+    let script = script.join("\n");
+
+    run_unix(config, "iptables (kill switch on)", &["sh", "-c", script.as_str()], None).await?;
+    mark_active()
+}
+
+#[cfg(unix)]
+pub async fn disable_unix(config: &VpnConfig) -> Result<()> {
+    // Both succeed when the rules are already gone, so disconnecting never has to check first
+    if use_nftables() {
+        let script = format!("table inet {table}\ndelete table inet {table}\n", table = NFT_TABLE);
+        run_unix(config, "nft delete table (kill switch off)", &["nft", "-f", "-"], Some(script)).await?;
+    } else {
+        let script = ["iptables", "ip6tables"]
+            .iter()
+            .map(|b| format!("{b} -D OUTPUT -j {c} 2>/dev/null; {b} -F {c} 2>/dev/null; {b} -X {c} 2>/dev/null", b = b, c = IPTABLES_CHAIN))
+            .collect::<Vec<_>>()
+            .join("\n");
+        run_unix(config, "iptables (kill switch off)", &["sh", "-c", format!("{}\ntrue", script).as_str()], None).await?;
+    }
+
+    let _ = std::fs::remove_file(marker_path());
+    Ok(())
+}
+
+// Kept on disk like the saved policy on Windows, so a restarted app still takes the rules down
+#[cfg(unix)]
+fn marker_path() -> std::path::PathBuf {
+    crate::system::paths::state_dir().join("killswitch-on")
+}
+
+#[cfg(unix)]
+fn mark_active() -> Result<()> {
+    let path = marker_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, "")?;
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn run_unix(config: &VpnConfig, label: &str, args: &[&str], stdin: Option<String>) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let _privileged = executor::privileged().await;
+    let mut child = Command::new("sudo")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(if stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .spawn()?;
+
+    if let (Some(mut pipe), Some(input)) = (child.stdin.take(), stdin) {
+        pipe.write_all(input.as_bytes()).await?;
+    }
+
+    let output = child.wait_with_output().await?;
+    client_log::record("vpn", &config.name, label, &output);
+
+    if !output.status.success() {
+        return Err(errors::error(
+            ErrorCode::KillSwitchFailed,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+pub async fn enable_windows(config: &VpnConfig, servers: &[IpAddr]) -> Result<()> {
+    // Rules can't name an adapter, so tunnel traffic is matched by the tunnel's own addresses;
+    // RAS connections (rasdial) have their own interface type
+    let tunnel_rule = if config.vpn_type == VpnType::Ikev2 {
+        "interfacetype=ras".to_string()
+    } else {
+        let addresses = tunnel_addresses(config).await?;
+        if addresses.is_empty() {
+            return Err(errors::error(ErrorCode::TunnelNotFound, ""));
+        }
+        format!("localip={}", addresses.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(","))
+    };
+    let servers = servers.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(",");
+    let rule_name = format!("name={}", RULE_NAME);
+
+    // Already on from an earlier connect, the saved policy is still the one to go back to
+    if !saved_policy_path().exists() {
+        save_firewall_policy(config).await?;
+    }
+
+    let _ = run_windows(config, "netsh delete rule (kill switch)", &["advfirewall", "firewall", "delete", "rule", &rule_name]).await;
+    run_windows(config, "netsh add rule (kill switch server)",
+        &["advfirewall", "firewall", "add", "rule", &rule_name, "dir=out", "action=allow", &format!("remoteip={}", servers)]).await?;
+    run_windows(config, "netsh add rule (kill switch tunnel)",
+        &["advfirewall", "firewall", "add", "rule", &rule_name, "dir=out", "action=allow", &tunnel_rule]).await?;
+    run_windows(config, "netsh set firewallpolicy (kill switch on)",
+        &["advfirewall", "set", "allprofiles", "firewallpolicy", "blockinbound,blockoutbound"]).await
+}
+
+#[cfg(windows)]
+pub async fn disable_windows(config: &VpnConfig) -> Result<()> {
+    // Restoring the policy below would otherwise reset one the kill switch never touched
+    if !is_active().await {
+        return Ok(());
+    }
+
+    let rule_name = format!("name={}", RULE_NAME);
+    // Restore the policy from before the kill switch before dropping the rules, so nothing is
+    // left blocked
+    let path = saved_policy_path();
+    let saved: Vec<(String, String)> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    if saved.is_empty() {
+        log::warn!("No firewall policy saved from before the kill switch, restoring the Windows default");
+        run_windows(config, "netsh set firewallpolicy (kill switch off)",
+            &["advfirewall", "set", "allprofiles", "firewallpolicy", DEFAULT_FIREWALL_POLICY]).await?;
+    }
+    for (profile, policy) in &saved {
+        run_windows(config, &format!("netsh set {} firewallpolicy (kill switch off)", profile),
+            &["advfirewall", "set", profile, "firewallpolicy", policy]).await?;
+    }
+    let _ = std::fs::remove_file(&path);
+    run_windows(config, "netsh delete rule (kill switch)", &["advfirewall", "firewall", "delete", "rule", &rule_name]).await
+}
+
+// Kept on disk, the kill switch stays on across restarts of the app until the entry disconnects
+#[cfg(windows)]
+fn saved_policy_path() -> std::path::PathBuf {
+    crate::system::paths::state_dir().join("killswitch-firewall-policy.json")
+}
+
+#[cfg(windows)]
+async fn save_firewall_policy(config: &VpnConfig) -> Result<()> {
+    let mut cmd = Command::new("netsh");
+    cmd.args(["advfirewall", "show", "allprofiles", "firewallpolicy"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, "netsh show firewallpolicy (kill switch)", &output);
+    let policies = parse_firewall_policies(&String::from_utf8_lossy(&output.stdout));
+    if !output.status.success() || policies.len() != FIREWALL_PROFILES.len() {
+        return Err(errors::error(ErrorCode::KillSwitchFailed, "couldn't read the current firewall policy"));
+    }
+
+    let path = saved_policy_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string(&policies)?)?;
+    Ok(())
+}
+
+// The headings and labels are in the system's language, the values aren't:
+// "Firewall Policy    BlockInbound,AllowOutbound" once per profile
+#[cfg(windows)]
+fn parse_firewall_policies(output: &str) -> Vec<(String, String)> {
+    let is_action = |word: &str| {
+        let word = word.to_ascii_lowercase();
+        word == "notconfigured" || word.starts_with("block") || word.starts_with("allow")
+    };
+    let policies = output.lines().filter_map(|line| {
+        let value = line.split_whitespace().last()?;
+        let (inbound, outbound) = value.split_once(',')?;
+        (is_action(inbound) && is_action(outbound)).then(|| value.to_ascii_lowercase())
+    });
+    FIREWALL_PROFILES.iter().map(|profile| profile.to_string()).zip(policies).collect()
+}
+
+// Addresses assigned to the tunnel adapter
+#[cfg(windows)]
+async fn tunnel_addresses(config: &VpnConfig) -> Result<Vec<IpAddr>> {
+    Ok(tunnel::adapters(config)
+        .await
+        .iter()
+        .flat_map(|iface| iface.addr.iter().map(|addr| addr.ip()))
+        .collect())
+}
+
+#[cfg(windows)]
+async fn run_windows(config: &VpnConfig, label: &str, args: &[&str]) -> Result<()> {
+    let mut cmd = Command::new("netsh");
+    cmd.args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, label, &output);
+
+    if !output.status.success() {
+        // netsh reports errors on stdout
+        return Err(errors::error(
+            ErrorCode::KillSwitchFailed,
+            String::from_utf8_lossy(&output.stdout).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use crate::config::{LatencyPolicy, SlowLinkAction};
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+// Round-trip time to a remote desktop host, measured right before a session is launched so a
+// slow link (typically a VPN to another continent) is noticed before the session is. A TCP
+// handshake with the RDP port is timed rather than a ping: it needs no privileges, passes the
+// firewalls that drop ICMP and takes the same path as the session.
+
+const SAMPLES: usize = 3;
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+// Median of a few connects, so one retransmitted SYN doesn't count as a slow link
+pub async fn measure(host: &str, port: u16) -> Result<Duration> {
+    let port = if port == 0 { 3389 } else { port };
+    let mut samples = Vec::new();
+    let mut last_error = None;
+    for _ in 0..SAMPLES {
+        let start = Instant::now();
+        match tokio::time::timeout(TIMEOUT, TcpStream::connect((host, port))).await {
+            Ok(Ok(_)) => samples.push(start.elapsed()),
+            Ok(Err(e)) => last_error = Some(e.to_string()),
+            Err(_) => last_error = Some(format!("no answer within {} s", TIMEOUT.as_secs())),
+        }
+    }
+    if samples.is_empty() {
+        return Err(anyhow::anyhow!("Could not reach {}:{}: {}", host, port, last_error.unwrap_or_default()));
+    }
+    samples.sort();
+    Ok(samples[samples.len() / 2])
+}
+
+// A round trip over the device's limit
+#[derive(Debug, Clone)]
+pub struct SlowLink {
+    pub rtt: Duration,
+    pub limit: Duration,
+    // The session was started with the low-bandwidth settings
+    pub lowered: bool,
+}
+
+impl SlowLink {
+    pub fn message(&self, name: &str) -> String {
+        let what = if self.lowered { "connecting with reduced quality" } else { "the session may be sluggish" };
+        format!("{}: {} ms round trip, over the {} ms limit; {}", name, self.rtt.as_millis(), self.limit.as_millis(), what)
+    }
+}
+
+// Measures when the policy asks for it; None when the link is fine or couldn't be measured,
+// in which case the launch goes ahead as usual
+pub async fn check(policy: &LatencyPolicy, host: &str, port: u16) -> Option<SlowLink> {
+    if !policy.enabled {
+        return None;
+    }
+    let rtt = match measure(host, port).await {
+        Ok(rtt) => rtt,
+        Err(e) => {
+            log::warn!("Latency check skipped: {}", e);
+            return None;
+        }
+    };
+    let limit = Duration::from_millis(policy.max_rtt_ms as u64);
+    log::info!("Round trip to {}: {} ms (limit {} ms)", host, rtt.as_millis(), limit.as_millis());
+    (rtt > limit).then(|| SlowLink {
+        rtt,
+        limit,
+        lowered: policy.on_slow == SlowLinkAction::LowerQuality,
+    })
+}
@@ -1,28 +1,94 @@
-use crate::config::{RdpConfig, VpnConfig, VpnType, WolDevice};
+use crate::config::history::{self, HistoryEventKind};
+use crate::config::{Device, RdpConfig, SshConfig, VncConfig, VpnConfig, WolDevice};
+use crate::system::client_log::{self, LogLevel};
+use crate::system::executor;
 use anyhow::Result;
+use std::sync::Arc;
 use std::time::Duration;
 
+pub mod always_on;
+pub mod backend;
+pub mod captive;
+pub mod custom;
+pub mod certs;
+pub mod dashboard;
+pub mod dns;
+pub mod dnsleak;
+pub mod failover;
+pub mod health;
+pub mod ikev2;
+pub mod killswitch;
+pub mod latency;
 pub mod monitor;
+pub mod netns;
+pub mod nmcli;
+pub mod openconnect;
+pub mod openvpn;
+pub mod otp;
+pub mod ovpn_bundle;
+pub mod preview;
+pub mod providers;
+pub mod proxy;
+pub mod publicip;
+pub mod reconnect;
 pub mod vpn;
+pub mod wg_qr;
+pub mod wg_router;
+pub mod wg_userspace;
 pub mod wireguard;
 pub mod rdp;
+pub mod retry;
+pub mod route_table;
+pub mod routes;
+pub mod ssh;
+pub mod startup;
+pub mod stats;
+pub mod tunnel;
+pub mod vnc;
 pub mod wol;
 
 #[derive(Clone)]
 pub struct NetworkManager {
     pub vpn_status: VpnStatus,
     pub rdp_connections: Vec<RdpConnection>,
-    pub wol_devices: Vec<WolDeviceStatus>,
+    // Monitored devices, see Device::monitor_address
+    pub devices: Vec<DeviceStatus>,
+    vpn_control: Arc<dyn backend::VpnControl>,
+    wake_sender: Arc<dyn backend::WakeSender>,
+    prober: Arc<dyn backend::Prober>,
+    // Bytes sent at the last handshake check, to tell a dead tunnel from an idle one
+    handshake_sent: Option<(String, u64)>,
 }
 
 #[derive(Debug, Clone)]
 pub enum VpnStatus {
+    // Not known yet, while the startup checks run, see network::startup
+    Checking,
     Disconnected,
     Connecting,
     Connected(String),
+    // Dropped on its own; attempt is 0 while waiting for the first one
+    Reconnecting { name: String, attempt: u32, max_attempts: u32 },
+    // Still up, but the WireGuard handshake went stale while sending, so traffic is likely lost;
+    // handshake_secs is None if there never was one
+    Degraded { name: String, handshake_secs: Option<u64> },
     Error(String),
 }
 
+impl VpnStatus {
+    // The entry whose tunnel is up, degraded or not
+    pub fn connected_name(&self) -> Option<&str> {
+        match self {
+            VpnStatus::Connected(name) | VpnStatus::Degraded { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+// WireGuard renews the handshake every two minutes while traffic flows and gives up on a
+// session after three
+const STALE_HANDSHAKE: Duration = Duration::from_secs(180);
+
 #[derive(Debug, Clone)]
 pub struct RdpConnection {
     pub config: RdpConfig,
@@ -38,22 +104,40 @@ pub enum ConnectionStatus {
 }
 
 #[derive(Debug, Clone)]
-pub struct WolDeviceStatus {
-    pub device: WolDevice,
+pub struct DeviceStatus {
+    pub name: String,
+    pub address: String,
     pub is_online: bool,
     pub last_checked: std::time::Instant,
 }
 
 impl NetworkManager {
     pub fn new() -> Self {
+        Self::with_backends(
+            Arc::new(backend::SystemVpnControl),
+            Arc::new(backend::SystemWakeSender),
+            Arc::new(backend::SystemProber),
+        )
+    }
+    
+    // Lets the tests below drive the manager with the in-memory backends from `backend::mock`
+    pub fn with_backends(
+        vpn_control: Arc<dyn backend::VpnControl>,
+        wake_sender: Arc<dyn backend::WakeSender>,
+        prober: Arc<dyn backend::Prober>,
+    ) -> Self {
         Self {
             vpn_status: VpnStatus::Disconnected,
             rdp_connections: Vec::new(),
-            wol_devices: Vec::new(),
+            devices: Vec::new(),
+            vpn_control,
+            wake_sender,
+            prober,
+            handshake_sent: None,
         }
     }
     
-    pub async fn initialize(&mut self, vpn_configs: &[VpnConfig], wol_devices: &[WolDevice]) -> Result<()> {
+    pub async fn initialize(&mut self, vpn_configs: &[VpnConfig], devices: &[Device]) -> Result<()> {
         // Check if any VPN is already connected
         for config in vpn_configs {
             if let Ok(is_connected) = self.check_vpn_status(config).await {
@@ -64,25 +148,28 @@ impl NetworkManager {
             }
         }
         
-        // Initialize WoL device statuses
-        self.wol_devices = wol_devices.iter().map(|device| {
-            WolDeviceStatus {
-                device: device.clone(),
-                is_online: false,
-                last_checked: std::time::Instant::now() - Duration::from_secs(60), // Force initial check
-            }
-        }).collect();
+        // Initialize device statuses
+        self.devices.clear();
+        self.sync_devices(devices);
         
         Ok(())
     }
     
     pub async fn refresh_vpn_status(&mut self, vpn_configs: &[VpnConfig]) -> Result<()> {
         // First check if currently connected VPN is still active
-        if let VpnStatus::Connected(name) = &self.vpn_status {
-            if let Some(config) = vpn_configs.iter().find(|c| &c.name == name) {
+        if let Some(name) = self.vpn_status.connected_name() {
+            if let Some(config) = vpn_configs.iter().find(|c| c.name == name) {
                 if let Ok(is_connected) = self.check_vpn_status(config).await {
                     if !is_connected {
-                        self.vpn_status = VpnStatus::Disconnected;
+                        log::info!("VPN {} is no longer connected", config.name);
+                        history::record(HistoryEventKind::VpnDisconnect, &config.name, false);
+                        self.vpn_status = if config.reconnect.enabled && config.reconnect.max_retries > 0 {
+                            VpnStatus::Reconnecting { name: config.name.clone(), attempt: 0, max_attempts: config.reconnect.max_retries }
+                        } else {
+                            VpnStatus::Disconnected
+                        };
+                    } else {
+                        self.check_handshake(config).await;
                     }
                     return Ok(());
                 }
@@ -100,38 +187,126 @@ impl NetworkManager {
         }
         
         // If no VPN is connected, mark as disconnected
-        if !matches!(self.vpn_status, VpnStatus::Connecting) {
+        if !matches!(self.vpn_status, VpnStatus::Connecting | VpnStatus::Reconnecting { .. }) {
             self.vpn_status = VpnStatus::Disconnected;
         }
         
         Ok(())
     }
 
+    // A handshake older than STALE_HANDSHAKE while the interface keeps sending means the server
+    // stopped answering; an idle tunnel just doesn't renew it, so that alone changes nothing
+    async fn check_handshake(&mut self, config: &VpnConfig) {
+        let handshake = match self.vpn_control.handshake(config).await {
+            Ok(Some(handshake)) => handshake,
+            Ok(None) => return,
+            Err(e) => {
+                log::debug!("Handshake of {} not checked: {}", config.name, e);
+                return;
+            }
+        };
+        let sending = matches!(&self.handshake_sent, Some((name, sent)) if *name == config.name && handshake.sent > *sent);
+        self.handshake_sent = Some((config.name.clone(), handshake.sent));
+
+        if handshake.age.is_some_and(|age| age <= STALE_HANDSHAKE) {
+            if matches!(self.vpn_status, VpnStatus::Degraded { .. }) {
+                log::info!("Handshake of {} is fresh again", config.name);
+            }
+            self.vpn_status = VpnStatus::Connected(config.name.clone());
+        } else if sending {
+            if !matches!(self.vpn_status, VpnStatus::Degraded { .. }) {
+                log::warn!("Handshake of {} is stale while sending, the tunnel may be down", config.name);
+            }
+            self.vpn_status = VpnStatus::Degraded {
+                name: config.name.clone(),
+                handshake_secs: handshake.age.map(|age| age.as_secs()),
+            };
+        }
+    }
+
+    pub async fn rehandshake_vpn(&mut self, config: &VpnConfig) -> Result<()> {
+        let _entry = executor::entry(&config.name).await;
+        self.vpn_control.rehandshake(config).await
+    }
+
+    // Takes the tunnel down and hands it to the reconnect loop, for a degraded tunnel that is
+    // still up and so wouldn't be noticed as dropped
+    pub async fn restart_vpn(&mut self, config: &VpnConfig) -> Result<()> {
+        let _entry = executor::entry(&config.name).await;
+        self.vpn_control.disconnect(config).await?;
+        history::record(HistoryEventKind::VpnDisconnect, &config.name, false);
+        self.vpn_status = VpnStatus::Reconnecting {
+            name: config.name.clone(),
+            attempt: 0,
+            max_attempts: config.reconnect.max_retries.max(1),
+        };
+        Ok(())
+    }
+
+    // Waits for an operation already running on the same entry, e.g. a reconnect attempt
     pub async fn connect_vpn(&mut self, config: &VpnConfig) -> Result<()> {
+        let _entry = executor::entry(&config.name).await;
         self.vpn_status = VpnStatus::Connecting;
+        client_log::push_live("vpn", &config.name, LogLevel::Info, &format!("Connecting {}", config.name));
         
-        let result = match config.vpn_type {
-            VpnType::OpenVpn => vpn::connect(config).await,
-            VpnType::WireGuard => wireguard::connect(config).await,
-        };
+        let result = self.vpn_control.connect(config).await;
+        history::record(HistoryEventKind::VpnConnect, &config.name, result.is_ok());
         
         match result {
             Ok(_) => {
+                client_log::push_live("vpn", &config.name, LogLevel::Info, &format!("Connected {}", config.name));
                 self.vpn_status = VpnStatus::Connected(config.name.clone());
                 Ok(())
             }
             Err(e) => {
+                client_log::push_live("vpn", &config.name, LogLevel::Error, &format!("Connecting failed: {}", e));
                 self.vpn_status = VpnStatus::Error(e.to_string());
                 Err(e)
             }
         }
     }
 
-    pub async fn disconnect_vpn(&mut self, config: &VpnConfig) -> Result<()> {
-        let result = match config.vpn_type {
-            VpnType::OpenVpn => vpn::disconnect().await,
-            VpnType::WireGuard => wireguard::disconnect(config).await,
+    // Tries the profiles in order until one connects and returns its name; the error lists why
+    // each one failed
+    pub async fn connect_first(&mut self, chain: &[&VpnConfig]) -> Result<String> {
+        self.connect_first_reporting(chain, |_| {}).await
+    }
+
+    // connect_first, telling which profile it tries before each
+    pub async fn connect_first_reporting(&mut self, chain: &[&VpnConfig], mut trying: impl FnMut(&str)) -> Result<String> {
+        if let [config] = chain {
+            trying(&config.name);
+            return self.connect_vpn(config).await.map(|_| config.name.clone());
+        }
+        let mut errors = Vec::new();
+        let mut first_error = None;
+        for config in chain {
+            if !errors.is_empty() {
+                log::info!("Failing over to {}", config.name);
+            }
+            trying(&config.name);
+            match self.connect_vpn(config).await {
+                Ok(()) => return Ok(config.name.clone()),
+                Err(e) => {
+                    log::warn!("Connecting to {} failed: {}", config.name, e);
+                    errors.push(format!("{}: {}", config.name, e));
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        let Some(first_error) = first_error else {
+            return Err(anyhow::anyhow!("No VPN profile to connect"));
         };
+        let error = errors.join("; ");
+        self.vpn_status = VpnStatus::Error(error.clone());
+        // The first failure stays in the chain so its error code can still be found
+        Err(first_error.context(error))
+    }
+
+    pub async fn disconnect_vpn(&mut self, config: &VpnConfig) -> Result<()> {
+        let _entry = executor::entry(&config.name).await;
+        let result = self.vpn_control.disconnect(config).await;
+        history::record(HistoryEventKind::VpnDisconnect, &config.name, result.is_ok());
         
         match result {
             Ok(_) => {
@@ -146,10 +321,7 @@ impl NetworkManager {
     }
 
     pub async fn check_vpn_status(&mut self, config: &VpnConfig) -> Result<bool> {
-        match config.vpn_type {
-            VpnType::OpenVpn => vpn::check_connection_status().await,
-            VpnType::WireGuard => wireguard::check_connection_status(config).await,
-        }
+        self.vpn_control.is_connected(config).await
     }
     
     pub async fn check_any_vpn_connected(&mut self, vpn_configs: &[VpnConfig]) -> Result<Option<String>> {
@@ -163,51 +335,116 @@ impl NetworkManager {
         Ok(None)
     }
 
-    pub async fn connect_rdp(&mut self, config: &RdpConfig) -> Result<()> {
-        rdp::connect(config).await
+    // Checks the round trip first if the device asks for it; a link over its limit is returned
+    // so the caller can tell the user
+    pub async fn connect_rdp(&mut self, config: &RdpConfig) -> Result<Option<latency::SlowLink>> {
+        let slow_link = latency::check(&config.latency, &config.host, config.port).await;
+        let low_bandwidth = slow_link.as_ref().is_some_and(|slow_link| slow_link.lowered);
+        let result = rdp::connect(config, low_bandwidth).await.map(|_| slow_link);
+        history::record(HistoryEventKind::RdpConnect, &config.name, result.is_ok());
+        result
+    }
+
+    // Both only spawn a window, nothing to wait for
+    pub fn connect_ssh(&mut self, config: &SshConfig, terminal: &str) -> Result<()> {
+        let result = ssh::connect(config, terminal);
+        history::record(HistoryEventKind::SshConnect, &config.name, result.is_ok());
+        result
+    }
+
+    // Wakes the device unless its SSH port already answers, waits for the port while it boots,
+    // then opens the session
+    pub async fn wake_then_ssh(&mut self, wol: &WolDevice, config: &SshConfig, terminal: &str) -> Result<()> {
+        if !ssh::wait_for_port(config, Duration::ZERO).await {
+            self.wake_device(wol).await?;
+            if !ssh::wait_for_port(config, ssh::WAKE_TIMEOUT).await {
+                let message = format!("{} didn't answer on port {} within {} s of the wake", config.name, config.port, ssh::WAKE_TIMEOUT.as_secs());
+                history::record(HistoryEventKind::SshConnect, &config.name, false);
+                return Err(anyhow::anyhow!(message));
+            }
+        }
+        self.connect_ssh(config, terminal)
+    }
+
+    pub fn connect_vnc(&mut self, config: &VncConfig) -> Result<()> {
+        let result = vnc::connect(config);
+        history::record(HistoryEventKind::VncConnect, &config.name, result.is_ok());
+        result
     }
 
     pub async fn wake_device(&mut self, device: &WolDevice) -> Result<()> {
-        let result = wol::wake_device(device).await;
+        let mut result = self.wake_sender.send_wake(device).await;
+        let mut relayed = false;
+        if let (Err(e), Some(relay)) = (&result, &device.relay) {
+            log::warn!("Direct WoL for {} failed ({}), trying relay {}", device.name, e, relay.host);
+            result = self.wake_sender.send_wake_relay(device).await;
+            relayed = true;
+        }
         
-        // After sending wake packet, wait a bit then check status multiple times
-        if result.is_ok() {
-            log::info!("WoL packet sent to {}, waiting for device to wake up...", device.name);
-            
-            // Check status multiple times with increasing delays
-            for i in 0..5 {
-                let delay = Duration::from_millis(2000 + (i * 1000)); // 2s, 3s, 4s, 5s, 6s
-                tokio::time::sleep(delay).await;
-                
-                let is_online = self.check_device_status(device).await;
-                if is_online {
-                    log::info!("Device {} is now online after WoL", device.name);
-                    break;
-                }
-                
-                log::debug!("Device {} still offline, attempt {} of 5", device.name, i + 1);
+        let mut came_online = result.is_ok() && self.wait_for_wake(device).await;
+        
+        // The direct packets went out but may not have reached the device's network
+        if result.is_ok() && !came_online && !relayed && device.relay.is_some() {
+            log::info!("{} didn't come up, sending the wake through its relay", device.name);
+            match self.wake_sender.send_wake_relay(device).await {
+                Ok(()) => came_online = self.wait_for_wake(device).await,
+                Err(e) => log::warn!("Relayed WoL for {} failed: {}", device.name, e),
             }
         }
         
+        history::record(HistoryEventKind::Wake, &device.name, came_online);
         result
     }
+    
+    // Checks the device a few times with increasing delays after a wake was sent; false right
+    // away when there is no address to check
+    async fn wait_for_wake(&mut self, device: &WolDevice) -> bool {
+        log::info!("WoL packet sent to {}, waiting for device to wake up...", device.name);
+        
+        for i in 0..5 {
+            let delay = Duration::from_millis(2000 + (i * 1000)); // 2s, 3s, 4s, 5s, 6s
+            tokio::time::sleep(delay).await;
+            
+            let Some(address) = self.device_address(device) else { return false };
+            if self.check_device_status(&device.name, &address).await {
+                log::info!("Device {} is now online after WoL", device.name);
+                return true;
+            }
+            
+            log::debug!("Device {} still offline, attempt {} of 5", device.name, i + 1);
+        }
+        false
+    }
+
+    // Where to look for a woken device: its monitored address, else the WoL target unless that's
+    // the broadcast default
+    fn device_address(&self, device: &WolDevice) -> Option<String> {
+        self.devices.iter()
+            .find(|status| status.name == device.name)
+            .map(|status| status.address.clone())
+            .or_else(|| (!device.ip_address.is_empty() && device.ip_address != "255.255.255.255").then(|| device.ip_address.clone()))
+    }
+    
+    pub fn is_online(&self, name: &str) -> bool {
+        self.devices.iter().any(|status| status.name == name && status.is_online)
+    }
 
-    pub async fn check_device_status(&mut self, device: &WolDevice) -> bool {
-        let detection_result = monitor::detect_device(&device.ip_address).await;
+    pub async fn check_device_status(&mut self, name: &str, address: &str) -> bool {
+        let detection_result = self.prober.detect(address).await;
         
         let is_online = match detection_result {
             Ok(result) => {
-                log::info!("Device {} detection: {}", device.name, result.details);
+                log::info!("Device {} detection: {}", name, result.details);
                 result.is_online
             }
             Err(e) => {
-                log::warn!("Failed to detect device {}: {}", device.name, e);
+                log::warn!("Failed to detect device {}: {}", name, e);
                 false
             }
         };
         
         // Update the device status in our list
-        if let Some(device_status) = self.wol_devices.iter_mut().find(|d| d.device.name == device.name) {
+        if let Some(device_status) = self.devices.iter_mut().find(|d| d.name == name) {
             device_status.is_online = is_online;
             device_status.last_checked = std::time::Instant::now();
         }
@@ -218,15 +455,15 @@ impl NetworkManager {
     pub async fn update_device_statuses(&mut self) -> Result<()> {
         let mut updates = Vec::new();
         
-        for (index, device_status) in self.wol_devices.iter().enumerate() {
+        for (index, device_status) in self.devices.iter().enumerate() {
             if device_status.last_checked.elapsed() > Duration::from_secs(30) {
-                match monitor::detect_device(&device_status.device.ip_address).await {
+                match self.prober.detect(&device_status.address).await {
                     Ok(detection_result) => {
-                        log::debug!("Device {} status update: {}", device_status.device.name, detection_result.details);
+                        log::debug!("Device {} status update: {}", device_status.name, detection_result.details);
                         updates.push((index, detection_result.is_online));
                     }
                     Err(e) => {
-                        log::warn!("Failed to detect device {}: {}", device_status.device.name, e);
+                        log::warn!("Failed to detect device {}: {}", device_status.name, e);
                         // Still update last_checked to avoid constant retries
                         updates.push((index, false));
                     }
@@ -235,7 +472,7 @@ impl NetworkManager {
         }
         
         for (index, is_online) in updates {
-            if let Some(device_status) = self.wol_devices.get_mut(index) {
+            if let Some(device_status) = self.devices.get_mut(index) {
                 device_status.is_online = is_online;
                 device_status.last_checked = std::time::Instant::now();
             }
@@ -244,32 +481,41 @@ impl NetworkManager {
         Ok(())
     }
     
-    pub fn sync_wol_devices(&mut self, config_devices: &[WolDevice]) {
-        // Remove devices that are no longer in config
-        self.wol_devices.retain(|status| {
-            config_devices.iter().any(|config_device| config_device.name == status.device.name)
-        });
+    pub fn sync_devices(&mut self, config_devices: &[Device]) {
+        let monitored: Vec<(&str, String)> = config_devices.iter()
+            .filter_map(|device| Some((device.name.as_str(), device.monitor_address()?)))
+            .collect();
+        
+        // Remove devices that are no longer in config or no longer monitored
+        self.devices.retain(|status| monitored.iter().any(|(name, _)| *name == status.name));
         
-        // Add new devices from config
-        for config_device in config_devices {
-            if !self.wol_devices.iter().any(|status| status.device.name == config_device.name) {
-                self.wol_devices.push(WolDeviceStatus {
-                    device: config_device.clone(),
+        for (name, address) in monitored {
+            match self.devices.iter_mut().find(|status| status.name == name) {
+                // An edited address invalidates the last result
+                Some(status) if status.address != address => {
+                    status.address = address;
+                    status.is_online = false;
+                    status.last_checked = std::time::Instant::now() - Duration::from_secs(60);
+                }
+                Some(_) => {}
+                None => self.devices.push(DeviceStatus {
+                    name: name.to_string(),
+                    address,
                     is_online: false,
                     last_checked: std::time::Instant::now() - Duration::from_secs(60), // Force initial check
-                });
+                }),
             }
         }
     }
     
     pub async fn quick_update_device_statuses(&mut self) -> Result<()> {
         // Use quick checks for more frequent updates
-        for device_status in &mut self.wol_devices {
+        for device_status in &mut self.devices {
             if device_status.last_checked.elapsed() > Duration::from_secs(10) {
-                let is_online = monitor::quick_device_check(&device_status.device.ip_address).await;
+                let is_online = self.prober.quick_check(&device_status.address).await;
                 if device_status.is_online != is_online {
                     log::info!("Device {} status changed: {} -> {}", 
-                        device_status.device.name, 
+                        device_status.name, 
                         device_status.is_online, 
                         is_online
                     );
@@ -280,4 +526,135 @@ impl NetworkManager {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::backend::mock::{MockProber, MockVpnControl, MockWakeSender};
+    use super::*;
+
+    fn vpn_config(name: &str) -> VpnConfig {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "config_path": "",
+            "username": "",
+            "password": "",
+            "auto_connect": false,
+        }))
+        .unwrap()
+    }
+
+    fn wol_device(mac_address: &str, ip_address: &str) -> WolDevice {
+        WolDevice {
+            name: "desktop".to_string(),
+            mac_address: mac_address.to_string(),
+            ip_address: ip_address.to_string(),
+            port: 9,
+            relay: None,
+        }
+    }
+
+    fn manager() -> (NetworkManager, Arc<MockVpnControl>, Arc<MockWakeSender>, Arc<MockProber>) {
+        let (vpn_control, wake_sender, prober) = (MockVpnControl::new(), MockWakeSender::new(), MockProber::new());
+        let manager = NetworkManager::with_backends(vpn_control.clone(), wake_sender.clone(), prober.clone());
+        (manager, vpn_control, wake_sender, prober)
+    }
+
+    #[tokio::test]
+    async fn connect_and_disconnect() {
+        let (mut manager, vpn_control, _, _) = manager();
+        let config = vpn_config("office");
+
+        manager.connect_vpn(&config).await.unwrap();
+        assert!(matches!(&manager.vpn_status, VpnStatus::Connected(name) if name == "office"));
+        assert!(manager.check_vpn_status(&config).await.unwrap());
+
+        manager.disconnect_vpn(&config).await.unwrap();
+        assert!(matches!(manager.vpn_status, VpnStatus::Disconnected));
+        assert!(!manager.check_vpn_status(&config).await.unwrap());
+        assert_eq!(*vpn_control.calls.lock().unwrap(), ["connect:office", "disconnect:office"]);
+    }
+
+    #[tokio::test]
+    async fn failed_connect_leaves_it_disconnected() {
+        let (mut manager, vpn_control, _, _) = manager();
+        let config = vpn_config("office");
+        vpn_control.fail_next("no route to server");
+
+        let error = manager.connect_vpn(&config).await.unwrap_err();
+        assert_eq!(error.to_string(), "no route to server");
+        assert!(matches!(&manager.vpn_status, VpnStatus::Error(message) if message == "no route to server"));
+        assert!(!manager.check_vpn_status(&config).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn connect_first_fails_over() {
+        let (mut manager, vpn_control, _, _) = manager();
+        let (primary, backup) = (vpn_config("primary"), vpn_config("backup"));
+        vpn_control.fail_next("timed out");
+
+        assert_eq!(manager.connect_first(&[&primary, &backup]).await.unwrap(), "backup");
+        assert!(matches!(&manager.vpn_status, VpnStatus::Connected(name) if name == "backup"));
+        assert_eq!(*vpn_control.calls.lock().unwrap(), ["connect:primary", "connect:backup"]);
+    }
+
+    #[tokio::test]
+    async fn dropped_tunnel_is_noticed() {
+        let (mut manager, vpn_control, _, _) = manager();
+        let mut config = vpn_config("office");
+        config.reconnect.enabled = true;
+        let configs = [config.clone()];
+
+        manager.connect_vpn(&config).await.unwrap();
+        vpn_control.drop_tunnel("office");
+        manager.refresh_vpn_status(&configs).await.unwrap();
+        assert!(matches!(&manager.vpn_status, VpnStatus::Reconnecting { name, attempt: 0, .. } if name == "office"));
+    }
+
+    // The checks after a wake sleep for seconds; the paused clock skips ahead instead
+    #[tokio::test(start_paused = true)]
+    async fn wake_goes_through_the_sender() {
+        let (mut manager, _, wake_sender, prober) = manager();
+        let device = wol_device("AA:BB:CC:DD:EE:FF", "192.168.1.20");
+        prober.set_online("192.168.1.20", true);
+
+        manager.wake_device(&device).await.unwrap();
+        assert_eq!(*wake_sender.sent.lock().unwrap(), ["AA:BB:CC:DD:EE:FF"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn wake_waits_for_the_device() {
+        let (mut manager, _, _, prober) = manager();
+        let device = wol_device("AA:BB:CC:DD:EE:FF", "192.168.1.20");
+        let start = tokio::time::Instant::now();
+        let wake = tokio::spawn(async move { manager.wake_device(&device).await });
+
+        // Still asleep at the first check, two seconds in; up at the second, three later
+        tokio::task::yield_now().await;
+        tokio::time::advance(Duration::from_secs(2)).await;
+        tokio::task::yield_now().await;
+        prober.set_online("192.168.1.20", true);
+
+        wake.await.unwrap().unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_wake_falls_back_to_the_relay() {
+        let (mut manager, _, wake_sender, prober) = manager();
+        let mut device = wol_device("AA:BB:CC:DD:EE:FF", "192.168.1.20");
+        device.relay = Some(SshConfig {
+            name: "nas".to_string(),
+            host: "192.168.1.2".to_string(),
+            port: 22,
+            username: String::new(),
+            key_path: String::new(),
+            wake_first: false,
+        });
+        *wake_sender.fail_with.lock().unwrap() = Some("no interface on that network".to_string());
+        prober.set_online("192.168.1.20", true);
+
+        manager.wake_device(&device).await.unwrap();
+        assert_eq!(*wake_sender.sent.lock().unwrap(), ["relay:AA:BB:CC:DD:EE:FF"]);
+    }
+}
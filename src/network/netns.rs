@@ -0,0 +1,261 @@
+use crate::config::VpnConfig;
+use crate::errors::{self, ErrorCode};
+use crate::network::routes::Gateway;
+use crate::network::tunnel;
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::process::Stdio;
+use std::sync::Mutex;
+use tokio::process::Command;
+
+// Per-application split tunneling on Linux. Chosen applications are started inside a network
+// namespace that reaches the outside through a veth pair; a policy routing rule sends everything
+// from the pair's subnet either into the tunnel or out via the pre-VPN gateway, so only those
+// applications use (or avoid) the VPN while the rest of the system is left alone.
+
+// Namespaces set up per entry, each with its own slot for names, subnet and routing table
+static ACTIVE: Mutex<Vec<Namespace>> = Mutex::new(Vec::new());
+
+const MAX_SLOTS: u8 = 64;
+// Policy routing table and rule priority, offset by the slot
+const TABLE_BASE: u32 = 7100;
+// Reachable through any full tunnel, unlike a LAN resolver
+const FALLBACK_DNS: &str = "1.1.1.1";
+// Passed into the namespace so GUI applications find the session
+const SESSION_VARS: &[&str] = &[
+    "DISPLAY", "WAYLAND_DISPLAY", "XAUTHORITY", "XDG_RUNTIME_DIR", "DBUS_SESSION_BUS_ADDRESS", "HOME", "LANG", "PATH",
+];
+
+#[derive(Debug, Clone)]
+struct Namespace {
+    vpn: String,
+    slot: u8,
+    // Interface the namespace's traffic leaves through, for the NAT and forward rules
+    out_interface: String,
+}
+
+impl Namespace {
+    fn name(&self) -> String {
+        format!("vpnm{}", self.slot)
+    }
+
+    fn host_veth(&self) -> String {
+        format!("vpnm{}h", self.slot)
+    }
+
+    fn inner_veth(&self) -> String {
+        format!("vpnm{}n", self.slot)
+    }
+
+    fn subnet(&self) -> String {
+        format!("10.200.{}.0/30", self.slot)
+    }
+
+    fn table(&self) -> u32 {
+        TABLE_BASE + self.slot as u32
+    }
+}
+
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+pub fn is_enabled(config: &VpnConfig) -> bool {
+    config.app_tunnel.enabled && !config.app_tunnel.apps.is_empty()
+}
+
+pub fn needs_gateway(config: &VpnConfig) -> bool {
+    is_enabled(config) && !config.app_tunnel.via_tunnel
+}
+
+pub fn is_active(config: &VpnConfig) -> bool {
+    ACTIVE.lock().unwrap().iter().any(|ns| ns.vpn == config.name)
+}
+
+// Creates the entry's namespace once the tunnel is up and starts the apps marked to launch with it
+pub async fn setup(config: &VpnConfig, gateway: Option<&Gateway>) -> Result<()> {
+    if !is_enabled(config) {
+        return Ok(());
+    }
+    if !is_supported() {
+        return Err(anyhow::anyhow!("Per-application split tunneling is only available on Linux"));
+    }
+    if is_active(config) {
+        teardown(config).await;
+    }
+
+    let (route, out_interface) = if !config.app_tunnel.via_tunnel {
+        let gateway = gateway.ok_or_else(|| anyhow::anyhow!("No default gateway to bypass the tunnel through"))?;
+        (format!("via {} dev {}", gateway.address, gateway.interface), gateway.interface.clone())
+    } else {
+        let interface = tunnel::adapters(config).await.into_iter().next()
+            .ok_or_else(|| errors::error(ErrorCode::TunnelNotFound, ""))?
+            .name;
+        (format!("dev {}", interface), interface)
+    };
+
+    let namespace = {
+        let mut active = ACTIVE.lock().unwrap();
+        let slot = (0..MAX_SLOTS)
+            .find(|slot| !active.iter().any(|ns| ns.slot == *slot))
+            .ok_or_else(|| anyhow::anyhow!("Too many application tunnels"))?;
+        let namespace = Namespace { vpn: config.name.clone(), slot, out_interface };
+        active.push(namespace.clone());
+        namespace
+    };
+
+    let dns = if !config.app_tunnel.via_tunnel { host_nameservers() } else { Vec::new() };
+    let dns = if dns.is_empty() { vec![FALLBACK_DNS.to_string()] } else { dns };
+
+    let (ns, host, inner, subnet, table, out) = (
+        namespace.name(),
+        namespace.host_veth(),
+        namespace.inner_veth(),
+        namespace.subnet(),
+        namespace.table(),
+        &namespace.out_interface,
+    );
+    let prefix = format!("10.200.{}", namespace.slot);
+    // Clearing the slot first covers leftovers from a session that didn't shut down cleanly
+    let script = [
+        cleanup_script(&namespace),
+        "set -e".to_string(),
+        format!("ip netns add {}", ns),
+        format!("ip link add {} type veth peer name {}", host, inner),
+        format!("ip link set {} netns {}", inner, ns),
+        format!("ip addr add {}.1/30 dev {}", prefix, host),
+        format!("ip link set {} up", host),
+        format!("ip netns exec {} ip link set lo up", ns),
+        format!("ip netns exec {} ip addr add {}.2/30 dev {}", ns, prefix, inner),
+        format!("ip netns exec {} ip link set {} up", ns, inner),
+        format!("ip netns exec {} ip route add default via {}.1", ns, prefix),
+        format!("ip route replace default {} table {}", route, table),
+        format!("ip rule add from {} lookup {} priority {}", subnet, table, table),
+        "sysctl -q -w net.ipv4.ip_forward=1".to_string(),
+        format!("iptables -t nat -A POSTROUTING -s {} -o {} -j MASQUERADE", subnet, out),
+        // Firewalls with a drop policy on FORWARD (e.g. Docker's) would stop the namespace otherwise
+        format!("iptables -I FORWARD 1 -i {} -o {} -j ACCEPT", host, out),
+        format!("iptables -I FORWARD 1 -i {} -o {} -m state --state RELATED,ESTABLISHED -j ACCEPT", out, host),
+        // ip netns exec bind-mounts this over /etc/resolv.conf, which often points at a local
+        // stub resolver the namespace can't reach
+        format!("mkdir -p /etc/netns/{}", ns),
+        format!(
+            "printf '{}' > /etc/netns/{}/resolv.conf",
+            dns.iter().map(|server| format!("nameserver {}\\n", server)).collect::<String>(),
+            ns
+        ),
+    ]
+    .join("\n");
+
+    if let Err(e) = run(config, "netns setup", &script).await {
+        teardown(config).await;
+        return Err(e);
+    }
+
+    if config.app_tunnel.launch_on_connect {
+        for app in &config.app_tunnel.apps {
+            if let Err(e) = launch(config, app) {
+                log::error!("Failed to start {}: {}", app, e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Removes the namespace; applications still inside lose their network rather than falling back
+// to the normal routes
+pub async fn teardown(config: &VpnConfig) {
+    let namespaces: Vec<Namespace> = {
+        let mut active = ACTIVE.lock().unwrap();
+        let (mine, others) = active.drain(..).partition(|ns| ns.vpn == config.name);
+        *active = others;
+        mine
+    };
+
+    for namespace in namespaces {
+        if let Err(e) = run(config, "netns teardown", &cleanup_script(&namespace)).await {
+            log::warn!("Failed to remove namespace {}: {}", namespace.name(), e);
+        }
+    }
+}
+
+// Each step may already be gone, so errors don't stop the rest
+fn cleanup_script(namespace: &Namespace) -> String {
+    let (ns, host, subnet, table, out) = (
+        namespace.name(),
+        namespace.host_veth(),
+        namespace.subnet(),
+        namespace.table(),
+        &namespace.out_interface,
+    );
+    [
+        format!("ip link del {} 2>/dev/null", host),
+        format!("ip netns del {} 2>/dev/null", ns),
+        format!("ip rule del priority {} 2>/dev/null", table),
+        format!("ip route flush table {} 2>/dev/null", table),
+        format!("iptables -t nat -D POSTROUTING -s {} -o {} -j MASQUERADE 2>/dev/null", subnet, out),
+        format!("iptables -D FORWARD -i {} -o {} -j ACCEPT 2>/dev/null", host, out),
+        format!("iptables -D FORWARD -i {} -o {} -m state --state RELATED,ESTABLISHED -j ACCEPT 2>/dev/null", out, host),
+        format!("rm -rf /etc/netns/{}", ns),
+        "true".to_string(),
+    ]
+    .join("\n")
+}
+
+// Starts a command line inside the entry's namespace as the current user
+pub fn launch(config: &VpnConfig, command_line: &str) -> Result<()> {
+    let ns = ACTIVE.lock().unwrap().iter()
+        .find(|ns| ns.vpn == config.name)
+        .map(|ns| ns.name())
+        .ok_or_else(|| anyhow::anyhow!("{} is not connected with application tunneling", config.name))?;
+
+    let user = std::env::var("USER").map_err(|_| anyhow::anyhow!("Can't determine the current user"))?;
+
+    // ip netns exec needs root; sudo -u drops back to the user for the application itself
+    let mut cmd = std::process::Command::new("sudo");
+    cmd.args(["ip", "netns", "exec", &ns, "sudo", "-u", &user, "env"]);
+    for var in SESSION_VARS {
+        if let Ok(value) = std::env::var(var) {
+            cmd.arg(format!("{}={}", var, value));
+        }
+    }
+    cmd.args(["sh", "-c", command_line])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    log::info!("Starting '{}' in namespace {}", command_line, ns);
+    cmd.spawn()?;
+    Ok(())
+}
+
+// Non-loopback resolvers from the host's resolv.conf
+fn host_nameservers() -> Vec<String> {
+    let content = std::fs::read_to_string("/etc/resolv.conf").unwrap_or_default();
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .map(|server| server.trim().to_string())
+        .filter(|server| server.parse::<std::net::IpAddr>().is_ok_and(|ip| !ip.is_loopback()))
+        .collect()
+}
+
+async fn run(config: &VpnConfig, label: &str, script: &str) -> Result<()> {
+    let output = executor::output(Command::new("sudo")
+        .args(["sh", "-c", script])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null()))
+        .await?;
+    client_log::record("vpn", &config.name, label, &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to set up the application tunnel: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use crate::config::{ProxyKind, VpnConfig, VpnType};
+use crate::errors::{self, ErrorCode};
+use crate::network::{certs, otp, proxy, wireguard};
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::process::Command;
+
+// OpenVPN and WireGuard through NetworkManager instead of spawning the clients with sudo. The
+// entry's file is imported as an NM connection named after it, replaced on every connect so edits
+// to the file carry over, and brought up and down with nmcli; polkit lets the desktop user do
+// that without a password. The connection shows up in the desktop's network menu as well.
+// Linux only, and only where nmcli is installed.
+
+// Chosen in the settings, switched at runtime by the app
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux") && which::which("nmcli").is_ok()
+}
+
+// NetworkManager imports only these from a file; the other types keep their own clients
+pub fn supports(vpn_type: &VpnType) -> bool {
+    matches!(vpn_type, VpnType::OpenVpn | VpnType::WireGuard)
+}
+
+// Whether the entry goes through NetworkManager with the current settings
+pub fn handles(config: &VpnConfig) -> bool {
+    is_enabled() && supports(&config.vpn_type) && is_supported()
+}
+
+pub fn connection_name(config: &VpnConfig) -> String {
+    format!("vpn-manager-{}", config.name)
+}
+
+async fn nmcli(config: &VpnConfig, args: &[&str], context: &str) -> Result<String> {
+    let mut cmd = Command::new("nmcli");
+    cmd.args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, &format!("nmcli {}", args.join(" ")), &output);
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("{}: {}", context, stderr.trim().trim_start_matches("Error: ")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// Names of the NM connections, only the active ones if asked
+async fn connection_names(active: bool) -> Result<Vec<String>> {
+    let mut args = vec!["-t", "-f", "NAME", "connection", "show"];
+    if active {
+        args.push("--active");
+    }
+    let output = Command::new("nmcli")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    // Terse output escapes the separator in names
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(|line| line.replace("\\:", ":")).collect())
+}
+
+// Replaces the entry's NM connection with a fresh import of its file
+async fn import(config: &VpnConfig) -> Result<()> {
+    let name = connection_name(config);
+    if connection_names(false).await?.contains(&name) {
+        nmcli(config, &["connection", "delete", "id", &name], "Removing the previous NetworkManager connection failed").await?;
+    }
+
+    let path = config.resolved_config_path();
+    let kind = if config.vpn_type == VpnType::WireGuard { "wireguard" } else { "openvpn" };
+    let output = nmcli(config, &["connection", "import", "type", kind, "file", &path], "NetworkManager could not import the config").await?;
+    // "Connection 'client' (5f1c...) successfully added."
+    let uuid = output
+        .split('(')
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .ok_or_else(|| anyhow::anyhow!("NetworkManager did not report the imported connection"))?
+        .to_string();
+
+    let mut settings = vec!["connection", "modify", "uuid", &uuid, "connection.id", &name, "connection.autoconnect", "no"];
+    let user_data = format!("username={}", config.username);
+    if config.vpn_type == VpnType::OpenVpn && !config.username.is_empty() {
+        settings.extend(["+vpn.data", user_data.as_str()]);
+    }
+    let cert_data = certs::nm_data(config).join(",");
+    if !cert_data.is_empty() {
+        settings.extend(["+vpn.data", cert_data.as_str()]);
+    }
+    let proxy_data = proxy::nm_data(config).join(",");
+    if !proxy_data.is_empty() {
+        settings.extend(["+vpn.data", proxy_data.as_str()]);
+    }
+    nmcli(config, &settings, "Naming the NetworkManager connection failed").await?;
+    Ok(())
+}
+
+pub async fn connect(config: &VpnConfig) -> Result<()> {
+    if !is_supported() {
+        return Err(errors::error(ErrorCode::VpnClientMissing, "NetworkManager (nmcli)"));
+    }
+    let path = config.resolved_config_path();
+    if !Path::new(&path).exists() {
+        return Err(errors::error(ErrorCode::VpnConfigMissing, &path));
+    }
+    import(config).await?;
+
+    let name = connection_name(config);
+    let mut args = vec!["--wait", "60", "connection", "up", "id", name.as_str()];
+    // Handed over for this activation only, so NetworkManager doesn't keep the password
+    let secrets = std::env::temp_dir().join(format!("vpn-manager-nm-{}.secrets", std::process::id()));
+    let secrets_arg = secrets.display().to_string();
+    let mut secret_lines = String::new();
+    // Through NetworkManager the code is always appended to the password
+    let password = match otp::take(&config.name) {
+        Some(code) => format!("{}{}", config.password, code),
+        None if otp::needs_code(config) => return Err(errors::error(ErrorCode::VpnOtpMissing, &config.name)),
+        None => config.password.clone(),
+    };
+    if config.vpn_type == VpnType::OpenVpn && !password.is_empty() {
+        secret_lines.push_str(&format!("vpn.secrets.password:{}\n", password));
+    }
+    if proxy::is_set(config) && config.proxy.kind == ProxyKind::Http && !config.proxy.password.is_empty() {
+        secret_lines.push_str(&format!("vpn.secrets.http-proxy-password:{}\n", config.proxy.password));
+    }
+    if !secret_lines.is_empty() {
+        wireguard::write_private(&secrets, &secret_lines)?;
+        args.extend(["passwd-file", secrets_arg.as_str()]);
+    }
+    let result = nmcli(config, &args, "NetworkManager could not activate the VPN").await;
+    let _ = std::fs::remove_file(&secrets);
+    result?;
+    log::info!("Activated NetworkManager connection {}", name);
+    Ok(())
+}
+
+pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    let name = connection_name(config);
+    nmcli(config, &["connection", "down", "id", &name], "NetworkManager could not deactivate the VPN").await?;
+    log::info!("Deactivated NetworkManager connection {}", name);
+    Ok(())
+}
+
+pub async fn check_connection_status(config: &VpnConfig) -> Result<bool> {
+    Ok(connection_names(true).await?.contains(&connection_name(config)))
+}
@@ -0,0 +1,269 @@
+use crate::config::VpnConfig;
+use crate::errors::{self, ErrorCode};
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+// Cisco AnyConnect (and compatible) gateways through the openconnect client. The entry's server,
+// group and username are passed on the command line, the password on stdin.
+
+// openconnect writes its pid here once it has backgrounded, which is what status and
+// disconnect go by
+pub fn pid_file(config: &VpnConfig) -> PathBuf {
+    client_log::log_path("vpn", &config.name).with_extension("pid")
+}
+
+// Arguments for the connect invocation (openconnect on Windows, sudo on Unix),
+// shared with the command preview
+pub fn connect_args(config: &VpnConfig) -> Vec<String> {
+    if cfg!(windows) {
+        return client_args(config);
+    }
+
+    let mut args = Vec::new();
+
+    // sudo resets the environment, so explicitly keep the per-entry variables
+    if !config.env_vars.is_empty() {
+        let keys: Vec<&str> = config.env_vars.keys().map(|k| k.as_str()).collect();
+        args.push(format!("--preserve-env={}", keys.join(",")));
+    }
+
+    args.push("openconnect".to_string());
+    args.extend(client_args(config));
+    args
+}
+
+fn client_args(config: &VpnConfig) -> Vec<String> {
+    let mut args = vec!["--protocol=anyconnect".to_string()];
+
+    if !config.username.is_empty() {
+        args.push(format!("--user={}", config.username));
+    }
+    if !config.group.trim().is_empty() {
+        args.push(format!("--authgroup={}", config.group.trim()));
+    }
+    if !config.password.is_empty() {
+        args.push("--passwd-on-stdin".to_string());
+    }
+
+    // Fail instead of waiting on a prompt nobody can answer (extra auth fields, unknown certificate)
+    args.push("--non-inter".to_string());
+
+    if !cfg!(windows) {
+        args.push("--background".to_string());
+        args.push(format!("--pid-file={}", pid_file(config).display()));
+    }
+
+    args.push(config.server.trim().to_string());
+    args
+}
+
+pub async fn connect(config: &VpnConfig) -> Result<()> {
+    if config.server.trim().is_empty() {
+        return Err(errors::error(ErrorCode::VpnServerMissing, &config.name));
+    }
+
+    #[cfg(windows)]
+    {
+        connect_windows(config).await
+    }
+
+    #[cfg(unix)]
+    {
+        connect_unix(config).await
+    }
+}
+
+// The client keeps running after it connects, so its output goes straight to the client log
+// instead of pipes that would never close
+fn log_stdio(config: &VpnConfig) -> Result<(Stdio, Stdio)> {
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(client_log::prepare("vpn", &config.name))?;
+    Ok((Stdio::from(log.try_clone()?), Stdio::from(log)))
+}
+
+async fn send_password(child: &mut tokio::process::Child, config: &VpnConfig) -> Result<()> {
+    if let Some(mut stdin) = child.stdin.take() {
+        if !config.password.is_empty() {
+            stdin.write_all(format!("{}\n", config.password).as_bytes()).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub async fn connect_windows(config: &VpnConfig) -> Result<()> {
+    let (stdout, stderr) = log_stdio(config)?;
+    let mut cmd = Command::new("openconnect");
+    cmd.args(connect_args(config))
+        .envs(&config.env_vars)
+        .stdout(stdout)
+        .stderr(stderr)
+        .stdin(Stdio::piped());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let privileged = executor::privileged().await;
+    let mut child = cmd.spawn()?;
+    send_password(&mut child, config).await?;
+
+    // No --background on Windows: the client stays in the foreground for the whole session,
+    // so only an early exit tells us the login failed
+    let exited = tokio::time::timeout(std::time::Duration::from_secs(10), child.wait()).await;
+    drop(privileged);
+    match exited {
+        Ok(status) => Err(errors::error(
+            ErrorCode::VpnClientFailed,
+            format!("OpenConnect exited during login ({})", status?)
+        )),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+pub async fn connect_unix(config: &VpnConfig) -> Result<()> {
+    let (stdout, stderr) = log_stdio(config)?;
+
+    // openconnect needs root to create the tun device and set routes
+    let privileged = executor::privileged().await;
+    let mut child = Command::new("sudo")
+        .args(connect_args(config))
+        .envs(&config.env_vars)
+        .stdout(stdout)
+        .stderr(stderr)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    send_password(&mut child, config).await?;
+
+    // With --background the foreground process exits once the tunnel is up
+    let status = child.wait().await?;
+    drop(privileged);
+    if !status.success() {
+        return Err(errors::error(ErrorCode::VpnClientFailed, format!("OpenConnect ({})", status)));
+    }
+
+    Ok(())
+}
+
+pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    #[cfg(windows)]
+    {
+        disconnect_windows(config).await
+    }
+
+    #[cfg(unix)]
+    {
+        disconnect_unix(config).await
+    }
+}
+
+#[cfg(windows)]
+pub async fn disconnect_windows(config: &VpnConfig) -> Result<()> {
+    let mut cmd = Command::new("taskkill");
+    cmd.args(&["/F", "/IM", "openconnect.exe"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, "taskkill openconnect.exe", &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to stop OpenConnect: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(unix)]
+pub async fn disconnect_unix(config: &VpnConfig) -> Result<()> {
+    // Only the entry's own client; another openconnect may belong to another entry or to
+    // something outside the app
+    let Some(pid) = read_pid(config) else {
+        return Err(anyhow::anyhow!(
+            "No OpenConnect process recorded for {} ({} is missing), stop it by hand",
+            config.name,
+            pid_file(config).display()
+        ));
+    };
+    // SIGINT makes openconnect log out of the gateway before exiting, unlike SIGTERM
+    let output = executor::output(Command::new("sudo").args(["kill", "-INT", &pid.to_string()])).await?;
+    client_log::record("vpn", &config.name, "kill -INT openconnect", &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to stop OpenConnect: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let _ = std::fs::remove_file(pid_file(config));
+    Ok(())
+}
+
+pub async fn check_connection_status(config: &VpnConfig) -> Result<bool> {
+    #[cfg(windows)]
+    {
+        get_status_windows().await
+    }
+
+    #[cfg(unix)]
+    {
+        get_status_unix(config).await
+    }
+}
+
+#[cfg(windows)]
+pub async fn get_status_windows() -> Result<bool> {
+    let mut cmd = Command::new("tasklist");
+    cmd.arg("/FI")
+        .arg("IMAGENAME eq openconnect.exe")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().await?;
+
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    Ok(output_str.contains("openconnect.exe"))
+}
+
+#[cfg(unix)]
+pub async fn get_status_unix(config: &VpnConfig) -> Result<bool> {
+    // The pid file ties the process to this entry; an openconnect without one isn't this
+    // entry's, and disconnect wouldn't stop it either
+    let Some(pid) = read_pid(config) else {
+        return Ok(false);
+    };
+    let output = Command::new("ps").args(["-p", &pid.to_string()]).output().await?;
+
+    Ok(output.status.success())
+}
+
+fn read_pid(config: &VpnConfig) -> Option<u32> {
+    std::fs::read_to_string(pid_file(config)).ok()?.trim().parse().ok()
+}
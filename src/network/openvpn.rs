@@ -0,0 +1,112 @@
+use crate::config::lint::Severity;
+use crate::config::CertFiles;
+use crate::network::certs::CertKind;
+use crate::network::ovpn_bundle::{self, INLINE_DIRECTIVES};
+use anyhow::Result;
+use std::path::Path;
+
+// Checks of an .ovpn profile, the counterpart of wireguard::validate_config: what openvpn would
+// refuse, or only find out about at connect time, such as a missing `remote`, files it names
+// that aren't there and ciphers current versions no longer use.
+
+// Options removed from OpenVPN, with what replaced them
+const REMOVED: &[(&str, &str)] = &[
+    ("tls-remote", "was removed in OpenVPN 2.4, use verify-x509-name"),
+    ("key-method", "was removed in OpenVPN 2.5 and can be deleted"),
+    ("no-iv", "was removed in OpenVPN 2.5 and can be deleted"),
+    ("ncp-disable", "was removed in OpenVPN 2.6, use data-ciphers"),
+];
+// Options that still work but are on their way out
+const DEPRECATED: &[(&str, &str)] = &[
+    ("comp-lzo", "is deprecated; compression is off by default since OpenVPN 2.6 (VORACLE)"),
+    ("ns-cert-type", "is deprecated, use remote-cert-tls server"),
+    ("secret", "(a static key) is deprecated in OpenVPN 2.6, servers move to TLS"),
+];
+
+// Err only when the profile can't be read. Attached certificates replace the profile's files, so
+// those aren't looked for.
+pub fn validate_config(config_path: &str, attached: &CertFiles) -> Result<Vec<(Severity, String)>> {
+    let content = std::fs::read_to_string(config_path)?;
+    let dir = Path::new(config_path).parent().unwrap_or(Path::new("."));
+    let replaced = |directive: &str| CertKind::ALL.iter()
+        .any(|kind| kind.directive() == directive && !kind.path(attached).trim().is_empty());
+    let mut problems = Vec::new();
+
+    let mut has_remote = false;
+    // The inline block being read; its lines are data, except in a <connection>
+    let mut block: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(tag) = line.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')) {
+            match tag.strip_prefix('/') {
+                Some(closing) if block.as_deref() == Some(closing) => block = None,
+                Some(closing) => problems.push((Severity::Error, format!("</{}> without a matching <{}>", closing, closing))),
+                None if block.is_some() => {}
+                None => block = Some(tag.to_string()),
+            }
+            continue;
+        }
+        if block.as_deref().is_some_and(|tag| tag != "connection") || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        let words = ovpn_bundle::split_words(line);
+        let Some(directive) = words.first().map(|d| d.trim_start_matches("--")) else { continue };
+        let argument = words.get(1).map(String::as_str);
+        match directive {
+            "remote" => match argument {
+                Some(_) => has_remote = true,
+                None => problems.push((Severity::Error, "A remote line names no server".to_string())),
+            },
+            "cipher" => match argument {
+                Some(cipher) if cipher.eq_ignore_ascii_case("none") => {
+                    problems.push((Severity::Warning, "cipher none sends traffic unencrypted".to_string()));
+                }
+                Some(cipher) if is_weak_cipher(cipher) => problems.push((Severity::Warning, format!(
+                    "cipher {} is deprecated; OpenVPN 2.6 only uses it if data-ciphers lists it", cipher))),
+                _ => {}
+            },
+            "data-ciphers" | "ncp-ciphers" | "data-ciphers-fallback" => {
+                for cipher in argument.unwrap_or_default().split(':').filter(|cipher| is_weak_cipher(cipher)) {
+                    problems.push((Severity::Warning, format!("{} lists the deprecated cipher {}", directive, cipher)));
+                }
+            }
+            "auth" if argument.is_some_and(|digest| digest.eq_ignore_ascii_case("md5") || digest.eq_ignore_ascii_case("none")) => {
+                problems.push((Severity::Warning, format!("auth {} leaves packets without a secure digest, use SHA256", argument.unwrap_or_default())));
+            }
+            "compress" if argument.is_some_and(|algorithm| algorithm != "stub" && algorithm != "stub-v2") => {
+                problems.push((Severity::Warning, "Compression is deprecated and off by default since OpenVPN 2.6 (VORACLE)".to_string()));
+            }
+            _ => {}
+        }
+        if let Some((_, why)) = REMOVED.iter().find(|(removed, _)| *removed == directive) {
+            problems.push((Severity::Error, format!("{} {}", directive, why)));
+        } else if let Some((_, why)) = DEPRECATED.iter().find(|(deprecated, _)| *deprecated == directive) {
+            problems.push((Severity::Warning, format!("{} {}", directive, why)));
+        }
+
+        // Files, which openvpn reads relative to the profile like the bundle import does
+        let names_file = INLINE_DIRECTIVES.contains(&directive) || directive == "auth-user-pass";
+        if let Some(file) = argument.filter(|file| names_file && *file != "[inline]" && !replaced(directive)) {
+            let path = dir.join(file);
+            if let Err(e) = std::fs::File::open(&path) {
+                let reason = if path.exists() { e.to_string() } else { "not found".to_string() };
+                problems.push((Severity::Error, format!("{} file {} can't be read: {}", directive, path.display(), reason)));
+            }
+        }
+    }
+
+    if let Some(tag) = block {
+        problems.push((Severity::Error, format!("<{}> is never closed with </{}>", tag, tag)));
+    }
+    if !has_remote {
+        problems.push((Severity::Error, "No remote line, the profile doesn't say which server to connect to".to_string()));
+    }
+    Ok(problems)
+}
+
+// Ciphers with 64-bit blocks or worse (SWEET32), which OpenVPN 2.6 dropped from its defaults
+fn is_weak_cipher(cipher: &str) -> bool {
+    let cipher = cipher.trim().to_uppercase();
+    ["BF-", "DES", "RC2-", "CAST5-", "IDEA-", "SEED-"].iter().any(|prefix| cipher.starts_with(prefix))
+}
@@ -0,0 +1,55 @@
+use crate::config::{VpnConfig, VpnType};
+use base64::Engine;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+// One-time codes for OpenVPN servers that want a second factor with the password. An entry with
+// prompt_otp set asks for the code before each connect from the app; the code is handed over
+// here and taken by that connect only, so a reconnect nobody is watching fails with VPN-009
+// instead of sending a used code. The code is appended to the password, or sent as the answer to
+// the profile's `static-challenge` when it has one.
+
+// Entry name to the code for its next connect
+static CODES: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+// Whether connecting the entry needs a code from the user first
+pub fn needs_code(config: &VpnConfig) -> bool {
+    config.prompt_otp && config.vpn_type == VpnType::OpenVpn
+}
+
+// The code for the next connect of `name`, replacing one that wasn't used
+pub fn provide(name: &str, code: &str) {
+    if let Ok(mut codes) = CODES.lock() {
+        codes.insert(name.to_string(), code.trim().to_string());
+    }
+}
+
+pub fn take(name: &str) -> Option<String> {
+    CODES.lock().ok()?.remove(name)
+}
+
+// The prompt of the profile's `static-challenge "text" echo` line, shown when asking for the code
+pub fn challenge_text(config: &VpnConfig) -> Option<String> {
+    let content = std::fs::read_to_string(config.resolved_config_path()).ok()?;
+    content.lines().find_map(|line| {
+        let rest = line.trim_start().strip_prefix("static-challenge")?;
+        let rest = rest.trim_start();
+        let text = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+            None => rest.split_whitespace().next().unwrap_or_default(),
+        };
+        Some(text.to_string())
+    })
+}
+
+// The password to answer a `>PASSWORD:Need 'Auth'` request with. A request for a static
+// challenge reads `>PASSWORD:Need 'Auth' username/password SC:<echo>,<text>` and wants both
+// base64 encoded in an SCRV1 answer.
+pub fn password(config: &VpnConfig, code: &str, request: &str) -> String {
+    if request.contains(" SC:") {
+        let encode = |value: &str| base64::engine::general_purpose::STANDARD.encode(value);
+        format!("SCRV1:{}:{}", encode(&config.password), encode(code))
+    } else {
+        format!("{}{}", config.password, code)
+    }
+}
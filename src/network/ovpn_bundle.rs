@@ -0,0 +1,393 @@
+use crate::config::{Config, VpnConfig, VpnType};
+use anyhow::Result;
+use base64::Engine;
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::Path;
+
+// OpenVPN profiles shipped as a bundle: a provider's zip or a Tunnelblick .tblk folder, holding
+// one or more .ovpn files next to the certificates and keys they name. Each profile is rewritten
+// with those files inlined, so it stands on its own, and saved in the app's profiles folder.
+
+// Directives naming a file that OpenVPN also takes inline as <directive>...</directive>
+pub const INLINE_DIRECTIVES: &[&str] = &[
+    "ca", "cert", "key", "extra-certs", "dh", "tls-auth", "tls-crypt", "tls-crypt-v2", "crl-verify", "pkcs12", "secret",
+];
+// More than any profile bundle holds; guards against unpacking something else by mistake
+const MAX_SIZE: u64 = 32 * 1024 * 1024;
+
+// Paths inside the bundle, separated by '/', and the files' contents
+type Files = BTreeMap<String, Vec<u8>>;
+
+// A profile ready to save, with the credentials its auth-user-pass file held
+struct Profile {
+    name: String,
+    content: String,
+    credentials: Option<(String, String)>,
+}
+
+// Reads the bundle and saves its profiles; nothing is saved if any of them names a file the
+// bundle doesn't have. A single profile takes `name` if one is given, several keep their file
+// names.
+pub fn import(path: &Path, name: &str) -> Result<Vec<VpnConfig>> {
+    let files = if path.is_dir() { read_dir(path)? } else { read_zip(path)? };
+    let profile_paths: Vec<&String> = files.keys()
+        .filter(|file| !file.starts_with("__MACOSX/") && file.to_lowercase().ends_with(".ovpn"))
+        .collect();
+    if profile_paths.is_empty() {
+        return Err(anyhow::anyhow!("No OpenVPN profile (.ovpn) found in {}", path.display()));
+    }
+
+    let single = profile_paths.len() == 1;
+    let mut profiles = Vec::new();
+    for profile_path in profile_paths {
+        let mut profile = inline(&files, profile_path)?;
+        if single && !name.trim().is_empty() {
+            profile.name = name.trim().to_string();
+        } else if profile.name == "config" {
+            // Tunnelblick names the file config.ovpn inside <name>.tblk
+            profile.name = profile_path.split('/')
+                .find_map(|part| part.strip_suffix(".tblk"))
+                .or_else(|| path.file_stem().and_then(|stem| stem.to_str()))
+                .unwrap_or("OpenVPN")
+                .to_string();
+        }
+        profiles.push(profile);
+    }
+
+    let mut imported = Vec::new();
+    for profile in profiles {
+        let file_name: String = profile.name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+            .collect();
+        let config_path = Config::store_managed_file(&format!("{}.ovpn", file_name), profile.content.as_bytes())?;
+        let (username, password) = profile.credentials.unwrap_or_default();
+        imported.push(VpnConfig {
+            id: crate::config::migrate::new_id(),
+            name: profile.name,
+            config_path,
+            username,
+            password,
+            prompt_otp: false,
+            auto_connect: false,
+            vpn_type: VpnType::OpenVpn,
+            env_vars: Default::default(),
+            server: String::new(),
+            group: String::new(),
+            kill_switch: false,
+            split_routes: Vec::new(),
+            app_tunnel: Default::default(),
+            dns_servers: Vec::new(),
+            dns_search_domains: Vec::new(),
+            reconnect: Default::default(),
+            system_service: false,
+            health: Default::default(),
+            proxy: Default::default(),
+            certificates: Default::default(),
+            custom: Default::default(),
+            style: Default::default(),
+        });
+    }
+    log::info!("Imported {} OpenVPN profile(s) from {}", imported.len(), path.display());
+    Ok(imported)
+}
+
+// A profile on disk with the files it names inlined, so a copy of it works from another folder,
+// e.g. a system service's. The auth-user-pass line loses its file, like on import.
+pub fn standalone(path: &Path) -> Result<String> {
+    const PROFILE: &str = "profile.ovpn";
+    let content = std::fs::read(path)?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut files = Files::new();
+    for line in String::from_utf8_lossy(&content).lines() {
+        let words = split_words(line.trim());
+        let (Some(directive), Some(file)) = (words.first(), words.get(1)) else { continue };
+        if INLINE_DIRECTIVES.contains(&directive.as_str()) || directive == "auth-user-pass" {
+            if let Ok(data) = std::fs::read(dir.join(file)) {
+                files.insert(normalize(file), data);
+            }
+        }
+    }
+    files.insert(PROFILE.to_string(), content);
+    Ok(inline(&files, PROFILE)?.content)
+}
+
+fn read_zip(path: &Path) -> Result<Files> {
+    let file = std::fs::File::open(path).map_err(|e| anyhow::anyhow!("Could not open {}: {}", path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| anyhow::anyhow!("{} is not a zip archive: {}", path.display(), e))?;
+
+    let mut files = Files::new();
+    let mut total = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        // Names climbing out of the archive are skipped
+        let Some(name) = entry.enclosed_name().map(|name| name.to_string_lossy().replace('\\', "/")) else {
+            continue;
+        };
+        total += entry.size();
+        if total > MAX_SIZE {
+            return Err(anyhow::anyhow!("{} is too large for a profile bundle", path.display()));
+        }
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        files.insert(name, data);
+    }
+    Ok(files)
+}
+
+fn read_dir(root: &Path) -> Result<Files> {
+    let mut files = Files::new();
+    let mut total = 0;
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let data = std::fs::read(&path)?;
+            total += data.len() as u64;
+            if total > MAX_SIZE {
+                return Err(anyhow::anyhow!("{} is too large for a profile bundle", root.display()));
+            }
+            let name = path.strip_prefix(root)?.to_string_lossy().replace('\\', "/");
+            files.insert(name, data);
+        }
+    }
+    Ok(files)
+}
+
+// The profile with every file it names inlined
+fn inline(files: &Files, profile_path: &str) -> Result<Profile> {
+    let content = String::from_utf8_lossy(&files[profile_path]);
+    let mut lines = Vec::new();
+    let mut credentials = None;
+    let mut missing = Vec::new();
+    let mut open_block: Option<String> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        // Blocks that are inline already are kept as they are
+        if let Some(tag) = &open_block {
+            if trimmed == format!("</{}>", tag) {
+                open_block = None;
+            }
+            lines.push(line.to_string());
+            continue;
+        }
+        if let Some(tag) = trimmed.strip_prefix('<').and_then(|tag| tag.strip_suffix('>')) {
+            open_block = Some(tag.to_string());
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let words = split_words(trimmed);
+        let (Some(directive), Some(file)) = (words.first().map(String::as_str), words.get(1)) else {
+            lines.push(line.to_string());
+            continue;
+        };
+        if file == "[inline]" || (directive == "crl-verify" && words.get(2).map(String::as_str) == Some("dir")) {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        if directive == "auth-user-pass" {
+            // Taken into the entry, whose credentials are answered over the management interface
+            match find(files, profile_path, file) {
+                Some(data) => {
+                    let text = String::from_utf8_lossy(data);
+                    let mut fields = text.lines();
+                    credentials = Some((
+                        fields.next().unwrap_or_default().trim().to_string(),
+                        fields.next().unwrap_or_default().trim().to_string(),
+                    ));
+                }
+                None => missing.push(file.clone()),
+            }
+            lines.push("auth-user-pass".to_string());
+        } else if INLINE_DIRECTIVES.contains(&directive) {
+            let Some(data) = find(files, profile_path, file) else {
+                missing.push(file.clone());
+                continue;
+            };
+            // The direction argument has its own directive once the key is inline
+            if let Some(direction) = words.get(2).filter(|_| directive == "tls-auth" || directive == "secret") {
+                lines.push(format!("key-direction {}", direction));
+            }
+            let body = if directive == "pkcs12" {
+                // Binary, so OpenVPN wants it in base64
+                let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+                encoded.as_bytes().chunks(64).map(|chunk| String::from_utf8_lossy(chunk).to_string()).collect::<Vec<_>>().join("\n")
+            } else {
+                String::from_utf8_lossy(data).trim_end().to_string()
+            };
+            lines.push(format!("<{}>", directive));
+            lines.push(body);
+            lines.push(format!("</{}>", directive));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(anyhow::anyhow!("{} names files missing from the bundle: {}", profile_path, missing.join(", ")));
+    }
+    let name = profile_path.rsplit('/').next().unwrap_or(profile_path);
+    Ok(Profile {
+        name: name[..name.len() - ".ovpn".len()].to_string(),
+        content: lines.join("\n") + "\n",
+        credentials,
+    })
+}
+
+// A file named by a profile, relative to the profile like OpenVPN run from its folder would
+// read it, else the only file in the bundle with that name: providers often write the paths
+// for wherever they expect the files to be unpacked
+fn find<'a>(files: &'a Files, profile_path: &str, reference: &str) -> Option<&'a Vec<u8>> {
+    let dir = profile_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    if let Some(data) = files.get(&normalize(&format!("{}/{}", dir, reference))) {
+        return Some(data);
+    }
+    let file_name = reference.rsplit(['/', '\\']).next()?;
+    let mut matches = files.iter().filter(|(path, _)| path.rsplit('/').next() == Some(file_name));
+    match (matches.next(), matches.next()) {
+        (Some((_, data)), None) => Some(data),
+        _ => None,
+    }
+}
+
+fn normalize(path: &str) -> String {
+    let mut parts = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+// Words of a config line, with double quotes around paths that have spaces
+pub fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quoted = false;
+    for c in line.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            c => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(files: &[(&str, &[u8])]) -> Files {
+        files.iter().map(|(path, data)| (path.to_string(), data.to_vec())).collect()
+    }
+
+    #[test]
+    fn inlines_the_files_a_profile_names() {
+        let files = bundle(&[
+            ("us/us-east.ovpn", b"client\nremote vpn.example 1194\nca ../certs/ca.crt\ntls-auth \"ta key.key\" 1\nauth-user-pass login.txt\npkcs12 client.p12\n"),
+            ("certs/ca.crt", b"-----BEGIN CERTIFICATE-----\nCA\n-----END CERTIFICATE-----\n\n"),
+            ("us/ta key.key", b"static key"),
+            ("us/login.txt", b"alice\n secret \n"),
+            ("us/client.p12", &[0xff, 0x00, 0x01]),
+        ]);
+        let profile = inline(&files, "us/us-east.ovpn").unwrap();
+        assert_eq!(profile.name, "us-east");
+        assert_eq!(profile.credentials, Some(("alice".to_string(), "secret".to_string())));
+        assert_eq!(profile.content, "\
+client
+remote vpn.example 1194
+<ca>
+-----BEGIN CERTIFICATE-----
+CA
+-----END CERTIFICATE-----
+</ca>
+key-direction 1
+<tls-auth>
+static key
+</tls-auth>
+auth-user-pass
+<pkcs12>
+/wAB
+</pkcs12>
+");
+    }
+
+    #[test]
+    fn finds_files_by_name_when_the_path_is_elsewhere() {
+        let files = bundle(&[
+            ("vpn.ovpn", b"ca /etc/openvpn/keys/ca.crt\ncert \"C:\\Program Files\\OpenVPN\\config\\client.crt\"\n"),
+            ("keys/ca.crt", b"ca"),
+            ("keys/client.crt", b"cert"),
+        ]);
+        let content = inline(&files, "vpn.ovpn").unwrap().content;
+        assert!(content.contains("<ca>\nca\n</ca>"));
+        assert!(content.contains("<cert>\ncert\n</cert>"));
+    }
+
+    #[test]
+    fn missing_and_ambiguous_files_fail() {
+        let files = bundle(&[
+            ("vpn.ovpn", b"ca ca.crt\nkey client.key\n"),
+            ("a/ca.crt", b"one"),
+            ("b/ca.crt", b"two"),
+        ]);
+        let error = inline(&files, "vpn.ovpn").err().unwrap().to_string();
+        assert!(error.ends_with("ca.crt, client.key"), "{}", error);
+    }
+
+    #[test]
+    fn keeps_blocks_that_are_inline_already() {
+        let files = bundle(&[("vpn.ovpn", b"<ca>\nkey not-a-directive\n</ca>\ncert [inline]\ncrl-verify crls dir\n")]);
+        let content = inline(&files, "vpn.ovpn").unwrap().content;
+        assert_eq!(content, "<ca>\nkey not-a-directive\n</ca>\ncert [inline]\ncrl-verify crls dir\n");
+    }
+
+    #[test]
+    fn normalizes_paths() {
+        assert_eq!(normalize("us/./../certs//ca.crt"), "certs/ca.crt");
+        assert_eq!(normalize("..\\..\\ca.crt"), "ca.crt");
+        assert_eq!(normalize("/etc/openvpn/ca.crt"), "etc/openvpn/ca.crt");
+    }
+
+    #[test]
+    fn splits_quoted_words() {
+        assert_eq!(split_words("ca \"my certs/ca.crt\"  extra"), vec!["ca", "my certs/ca.crt", "extra"]);
+        assert!(split_words("   ").is_empty());
+    }
+
+    #[test]
+    fn standalone_profiles_carry_their_files() {
+        let dir = std::env::temp_dir().join(format!("vpn-manager-test-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("keys")).unwrap();
+        std::fs::write(dir.join("keys/ca.crt"), "ca").unwrap();
+        std::fs::write(dir.join("login.txt"), "bob\npw\n").unwrap();
+        std::fs::write(dir.join("home.ovpn"), "client\nca keys/ca.crt\nauth-user-pass login.txt\n").unwrap();
+        let content = standalone(&dir.join("home.ovpn"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(content.unwrap(), "client\n<ca>\nca\n</ca>\nauth-user-pass\n");
+    }
+}
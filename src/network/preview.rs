@@ -0,0 +1,429 @@
+use crate::config::{RdpConfig, SshConfig, VncConfig, VpnConfig, VpnType, WolDevice};
+use crate::network::{custom, ikev2, nmcli, openconnect, rdp, ssh, vnc, vpn, wg_userspace, wireguard, wol};
+use crate::system::{boot, services};
+use std::collections::BTreeMap;
+
+const REDACTED: &str = "********";
+
+// What an action would run, built from the same argument lists the real spawn uses. The script
+// lines run the same commands from POSIX sh, for the exported script.
+#[derive(Debug, Clone)]
+pub struct CommandPreview {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub script: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+// A command as the argument list that is spawned
+#[derive(Debug, Clone)]
+pub struct Argv {
+    program: String,
+    args: Vec<String>,
+    env_keys: Vec<String>,
+    // Secret values within the arguments, and the variable a script reads each one from
+    secrets: Vec<(String, &'static str)>,
+    // Variable whose value is written to the command's stdin
+    stdin: Option<&'static str>,
+}
+
+impl Argv {
+    fn new(program: &str, args: &[String], env_vars: &BTreeMap<String, String>) -> Self {
+        Argv {
+            program: program.to_string(),
+            args: args.to_vec(),
+            env_keys: env_vars.keys().cloned().collect(),
+            secrets: Vec::new(),
+            stdin: None,
+        }
+    }
+
+    fn secret(mut self, value: &str, variable: &'static str) -> Self {
+        if !value.is_empty() {
+            self.secrets.push((value.to_string(), variable));
+        }
+        self
+    }
+
+    fn stdin(mut self, variable: &'static str) -> Self {
+        self.stdin = Some(variable);
+        self
+    }
+
+    // For the preview, with the secrets masked
+    pub fn display(&self) -> String {
+        // Environment values often carry tokens or proxy credentials, so only the keys are shown
+        let mut parts: Vec<String> = self.env_keys.iter().map(|key| format!("{}={}", key, REDACTED)).collect();
+        parts.push(quote(&self.program));
+        parts.extend(self.args.iter().map(|arg| {
+            quote(&self.secrets.iter().fold(arg.clone(), |arg, (value, _)| arg.replace(value.as_str(), REDACTED)))
+        }));
+        parts.join(" ")
+    }
+
+    // For sh: every word single-quoted, secrets and environment values read from variables the
+    // caller sets, failing when one isn't
+    pub fn script(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.env_keys.is_empty() {
+            parts.push("env".to_string());
+            parts.extend(self.env_keys.iter().map(|key| format!("{}={}", custom::shell_quote(key), variable(&script_variable(key)))));
+        }
+        parts.push(custom::shell_quote(&self.program));
+        parts.extend(self.args.iter().map(|arg| self.script_word(arg)));
+        let command = parts.join(" ");
+        match self.stdin {
+            Some(stdin) => format!("printf '%s\\n' {} | {}", variable(stdin), command),
+            None => command,
+        }
+    }
+
+    fn script_word(&self, arg: &str) -> String {
+        let mut word = String::new();
+        let mut rest = arg;
+        loop {
+            let next = self
+                .secrets
+                .iter()
+                .filter_map(|(value, name)| Some((rest.find(value.as_str())?, value.len(), *name)))
+                .min_by_key(|(index, _, _)| *index);
+            let Some((index, len, name)) = next else { break };
+            if index > 0 {
+                word.push_str(&custom::shell_quote(&rest[..index]));
+            }
+            word.push_str(&variable(name));
+            rest = &rest[index + len..];
+        }
+        if !rest.is_empty() || word.is_empty() {
+            word.push_str(&custom::shell_quote(rest));
+        }
+        word
+    }
+}
+
+// A reference to a variable that has to be set, for sh
+fn variable(name: &str) -> String {
+    format!("\"${{{}:?}}\"", name)
+}
+
+// Environment keys that aren't valid sh names are read from a sanitized one
+fn script_variable(key: &str) -> String {
+    let mut name: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+// Lines the app carries out itself rather than as a command, e.g. signalling over OpenVPN's
+// management interface; a script calling them fails with the explanation
+fn unscriptable(what: &str) -> String {
+    format!("echo {} >&2; return 1", custom::shell_quote(&format!("Not available from a script: {}", what)))
+}
+
+impl CommandPreview {
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+}
+
+pub fn vpn_connect(config: &VpnConfig) -> CommandPreview {
+    if boot::is_enabled(config) {
+        return managed(config, "Connect", "start");
+    }
+    if config.system_service {
+        return service(config, "Connect", "start");
+    }
+    if nmcli::handles(config) {
+        return network_manager(config, "Connect", "up");
+    }
+    let (line, script) = match config.vpn_type {
+        VpnType::OpenVpn => vpn_command("openvpn", &vpn::openvpn_args(config), config),
+        VpnType::WireGuard => {
+            let program = if cfg!(windows) { "wireguard" } else { "sudo" };
+            vpn_command(program, &wireguard::connect_args(config), config)
+        }
+        VpnType::Ikev2 => {
+            let (program, args) = ikev2::connect_command(config);
+            vpn_command(program, &args, config)
+        }
+        VpnType::OpenConnect => {
+            let program = if cfg!(windows) { "openconnect" } else { "sudo" };
+            let mut argv = Argv::new(program, &openconnect::connect_args(config), &config.env_vars).secret(&config.password, PASSWORD);
+            if !config.password.is_empty() {
+                argv = argv.stdin(PASSWORD);
+            }
+            (argv.display(), vec![argv.script()])
+        }
+        VpnType::Custom => (custom::expand(&config.custom.connect, config, true), custom::script(&config.custom.connect, config)),
+    };
+
+    let mut notes = Vec::new();
+    if config.vpn_type == VpnType::OpenVpn {
+        notes.push(format!(
+            "Credentials are answered over the management interface on port {}",
+            vpn::management_port(config)
+        ));
+    }
+    if config.vpn_type == VpnType::OpenVpn && cfg!(windows) {
+        notes.push("Started by the OpenVPN Interactive Service when it is running, else directly".to_string());
+    }
+    if config.vpn_type == VpnType::WireGuard && wg_userspace::should_use() {
+        let program = wg_userspace::find_implementation().map_or("boringtun-cli or wireguard-go", |(implementation, _)| implementation.program());
+        notes.push(format!("Brought up with {} in userspace instead; addresses and routes are set with ip", program));
+    }
+    if config.vpn_type == VpnType::OpenConnect && !config.password.is_empty() {
+        notes.push("The password is written to openconnect's stdin".to_string());
+    }
+    if config.vpn_type == VpnType::Custom {
+        notes.push("Run through the shell, with the entry's values also in VPN_MANAGER_* environment variables".to_string());
+    }
+
+    CommandPreview {
+        title: format!("Connect {}", config.name),
+        lines: vec![line],
+        script,
+        notes,
+    }
+}
+
+pub fn vpn_disconnect(config: &VpnConfig) -> CommandPreview {
+    if boot::is_enabled(config) {
+        return managed(config, "Disconnect", "stop");
+    }
+    if config.system_service {
+        return service(config, "Disconnect", "stop");
+    }
+    if nmcli::handles(config) {
+        return network_manager(config, "Disconnect", "down");
+    }
+    let described = |line: String| {
+        let script = vec![unscriptable(&line)];
+        (line, script)
+    };
+    let (line, script) = match config.vpn_type {
+        VpnType::OpenVpn => described(format!("signal SIGTERM (management interface 127.0.0.1:{})", vpn::management_port(config))),
+        VpnType::WireGuard if cfg!(windows) => described("wireguard /uninstalltunnelservice <interface>".to_string()),
+        VpnType::WireGuard => {
+            let argv = Argv::new("sudo", &["wg-quick".to_string(), "down".to_string(), config.resolved_config_path()], &BTreeMap::new());
+            (argv.display(), vec![argv.script()])
+        }
+        VpnType::Ikev2 => {
+            let (program, args) = ikev2::disconnect_command(config);
+            let argv = Argv::new(program, &args, &BTreeMap::new());
+            (argv.display(), vec![argv.script()])
+        }
+        VpnType::OpenConnect if cfg!(windows) => {
+            let argv = Argv::new("taskkill", &["/F".to_string(), "/IM".to_string(), "openconnect.exe".to_string()], &BTreeMap::new());
+            (argv.display(), vec![argv.script()])
+        }
+        VpnType::OpenConnect => {
+            let pid_file = openconnect::pid_file(config).display().to_string();
+            (
+                format!("sudo kill -INT $(cat {})", quote(&pid_file)),
+                vec![format!("sudo kill -INT \"$(cat {})\"", custom::shell_quote(&pid_file))],
+            )
+        }
+        VpnType::Custom if config.custom.disconnect.trim().is_empty() => described("stop the connect command's process".to_string()),
+        VpnType::Custom => (custom::expand(&config.custom.disconnect, config, true), custom::script(&config.custom.disconnect, config)),
+    };
+
+    CommandPreview {
+        title: format!("Disconnect {}", config.name),
+        lines: vec![line],
+        script,
+        notes: Vec::new(),
+    }
+}
+
+// Where scripts take a VPN entry's password from, the same variable custom commands get it in
+const PASSWORD: &str = "VPN_MANAGER_PASSWORD";
+
+fn vpn_command(program: &str, args: &[String], config: &VpnConfig) -> (String, Vec<String>) {
+    let argv = Argv::new(program, args, &config.env_vars).secret(&config.password, PASSWORD);
+    (argv.display(), vec![argv.script()])
+}
+
+// Entries started at boot go through their systemd unit
+fn managed(config: &VpnConfig, action: &str, verb: &str) -> CommandPreview {
+    let unit = boot::unit_name(config);
+    let argv = Argv::new("sudo", &["systemctl".to_string(), verb.to_string(), unit.clone()], &BTreeMap::new());
+    CommandPreview {
+        title: format!("{} {}", action, config.name),
+        lines: vec![argv.display()],
+        script: vec![argv.script()],
+        notes: vec![format!("Managed by the system; the command the unit runs is in /etc/systemd/system/{}", unit)],
+    }
+}
+
+// Entries installed as a system service, see system::services
+fn service(config: &VpnConfig, action: &str, verb: &str) -> CommandPreview {
+    let argv = Argv::new("sudo", &["systemctl".to_string(), verb.to_string(), services::unit_name(config)], &BTreeMap::new());
+    CommandPreview {
+        title: format!("{} {}", action, config.name),
+        lines: vec![argv.display()],
+        script: vec![argv.script()],
+        notes: vec![format!("Runs the copy of the profile in {}", services::profile_path(config).display())],
+    }
+}
+
+// Entries going through NetworkManager are imported from their file first, see network::nmcli
+fn network_manager(config: &VpnConfig, action: &str, verb: &str) -> CommandPreview {
+    let name = quote(&nmcli::connection_name(config));
+    let mut lines = Vec::new();
+    let mut notes = Vec::new();
+    // The import is the app's, a script uses the connection it left in NetworkManager
+    let argv = Argv::new("nmcli", &["connection".to_string(), verb.to_string(), "id".to_string(), nmcli::connection_name(config)], &BTreeMap::new());
+    if verb == "up" {
+        let kind = if config.vpn_type == VpnType::WireGuard { "wireguard" } else { "openvpn" };
+        lines.push(format!("nmcli connection import type {} file {}", kind, quote(&config.resolved_config_path())));
+        lines.push(format!("nmcli connection modify <uuid> connection.id {} connection.autoconnect no", name));
+        if !config.password.is_empty() && config.vpn_type == VpnType::OpenVpn {
+            notes.push("The password is passed in a temporary passwd-file, not stored in NetworkManager".to_string());
+        }
+        notes.push("A previous import of the same name is deleted first".to_string());
+    }
+    lines.push(argv.display());
+    CommandPreview {
+        title: format!("{} {}", action, config.name),
+        lines,
+        script: vec![argv.script()],
+        notes,
+    }
+}
+
+pub fn rdp_connect(config: &RdpConfig) -> CommandPreview {
+    let mut notes = Vec::new();
+
+    if cfg!(windows) && config.store_credentials && !config.password.is_empty() {
+        notes.push(format!("cmdkey /generic:{} /user:{} /pass:{} saves the credentials before mstsc starts, they are deleted 30 seconds later", rdp::credential_target(config), config.username, REDACTED));
+    }
+    let argv = if cfg!(windows) && rdp::needs_rdp_file(config, false) {
+        notes.push(format!("The temporary .rdp file holds: {}", rdp::rdp_file_lines(config, false).join(", ")));
+        Argv::new("mstsc", &[format!("{}.rdp", config.name)], &config.env_vars)
+    } else if cfg!(windows) {
+        notes.push("If mstsc rejects the address, a temporary .rdp file is tried instead".to_string());
+        Argv::new("mstsc", &["/v".to_string(), rdp::mstsc_target(config)], &config.env_vars)
+    } else if cfg!(target_os = "macos") {
+        notes.push("With Windows App or Microsoft Remote Desktop in Applications, a temporary .rdp file is opened in it instead".to_string());
+        Argv::new("open", &[rdp::rdp_url(config)], &config.env_vars)
+    } else {
+        #[cfg(unix)]
+        let client = rdp::select_freerdp_client(&crate::system::session::DisplaySession::detect());
+        #[cfg(not(unix))]
+        let client: Option<&str> = None;
+
+        match client {
+            Some(client) => {
+                let gateway_password = config.gateway.as_ref().map_or("", |gateway| gateway.password.as_str());
+                Argv::new(client, &rdp::freerdp_args(config, false), &config.env_vars).secret(gateway_password, "RDP_GATEWAY_PASSWORD")
+            }
+            None => {
+                notes.push("No compatible FreeRDP client found, Remmina would be used".to_string());
+                if config.gateway.is_some() {
+                    notes.push("Remmina takes the gateway from its own profile".to_string());
+                }
+                Argv::new("remmina", &rdp::remmina_args(config), &config.env_vars)
+            }
+        }
+    }
+    .secret(&config.password, "RDP_PASSWORD");
+
+    CommandPreview {
+        title: format!("Connect {}", config.name),
+        lines: vec![argv.display()],
+        script: vec![argv.script()],
+        notes,
+    }
+}
+
+// The ssh command line alone, for scripts that run in a terminal already
+pub fn ssh_command(config: &SshConfig) -> Argv {
+    Argv::new("ssh", &ssh::ssh_args(config), &BTreeMap::new())
+}
+
+pub fn ssh_connect(config: &SshConfig, terminal: &str) -> CommandPreview {
+    let mut notes = Vec::new();
+    let line = match ssh::command_line(config, terminal) {
+        Ok((program, args)) => {
+            if ssh::is_putty(&program) && !config.key_path.is_empty() && !config.key_path.to_lowercase().ends_with(".ppk") {
+                notes.push("PuTTY only reads keys in its own .ppk format, convert the key with PuTTYgen".to_string());
+            }
+            Argv::new(&program, &args, &BTreeMap::new()).display()
+        }
+        Err(e) => {
+            notes.push(e.to_string());
+            ssh_command(config).display()
+        }
+    };
+    if config.wake_first {
+        notes.push(format!("Woken over Wake-on-LAN first unless port {} answers, then waited for up to {} s", config.port, ssh::WAKE_TIMEOUT.as_secs()));
+    }
+
+    CommandPreview {
+        title: format!("SSH {}", config.name),
+        lines: vec![line],
+        script: vec![ssh_command(config).script()],
+        notes,
+    }
+}
+
+pub fn vnc_connect(config: &VncConfig) -> CommandPreview {
+    let (viewer, notes) = match vnc::viewer(config) {
+        Ok(viewer) => (viewer, Vec::new()),
+        Err(e) => (vnc::Viewer::Program("vncviewer".into()), vec![e.to_string()]),
+    };
+    let argv = Argv::new(&viewer.program(), &viewer.args(config), &BTreeMap::new());
+    CommandPreview {
+        title: format!("VNC {}", config.name),
+        lines: vec![argv.display()],
+        script: vec![argv.script()],
+        notes,
+    }
+}
+
+// Wake-on-LAN sends UDP packets directly instead of spawning a process
+pub async fn wake(device: &WolDevice) -> CommandPreview {
+    let lines = wol::wake_targets(device)
+        .await
+        .into_iter()
+        .map(|target| format!("udp send {} magic packet for {}", target, wol::format_mac_address(&device.mac_address)))
+        .collect();
+
+    let mut notes = vec![format!("Equivalent to: {}", wake_command(device).display())];
+    if let Some(warning) = wol::route_warning(device).await {
+        notes.push(format!("Warning: {}", warning));
+    }
+    if let Some(relay) = wake_relay(device) {
+        notes.push(format!("If the device doesn't come up, the relay is used: {}", relay.display()));
+    }
+
+    CommandPreview {
+        title: format!("Wake {}", device.name),
+        lines,
+        script: vec![wake_command(device).script()],
+        notes,
+    }
+}
+
+// The usual wake tool, standing in for the packets the app sends itself
+pub fn wake_command(device: &WolDevice) -> Argv {
+    let args = ["-p".to_string(), device.port.to_string(), wol::format_mac_address(&device.mac_address)];
+    Argv::new("wakeonlan", &args, &BTreeMap::new())
+}
+
+// The ssh command waking the device through its relay, if it has one
+pub fn wake_relay(device: &WolDevice) -> Option<Argv> {
+    let relay = device.relay.as_ref()?;
+    Some(Argv::new("ssh", &wol::relay_args(device, relay), &BTreeMap::new()))
+}
+
+fn quote(arg: &str) -> String {
+    if arg.is_empty() {
+        "\"\"".to_string()
+    } else if arg.contains(char::is_whitespace) || arg.contains('"') {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        arg.to_string()
+    }
+}
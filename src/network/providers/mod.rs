@@ -0,0 +1,162 @@
+use crate::config::{Config, ProviderAccounts, VpnConfig, VpnType};
+use anyhow::Result;
+use std::time::Duration;
+
+pub mod mullvad;
+pub mod proton;
+
+// Commercial VPN providers whose server list the app reads, so a profile for a chosen country,
+// city or server can be generated instead of downloaded by hand. The profile is saved in the
+// app's profiles folder and added as an ordinary entry; nothing ties it to the provider after.
+
+// The lists run to a few megabytes
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provider {
+    Mullvad,
+    Proton,
+}
+
+// A server as both providers describe it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Server {
+    // se-got-wg-001, CH#12
+    pub name: String,
+    pub country: String,
+    pub city: String,
+    // The address clients connect to
+    pub address: String,
+    // The server's WireGuard key; empty when it has no WireGuard
+    pub public_key: String,
+    pub openvpn: bool,
+    // Percent in use, where the provider says
+    pub load: Option<u8>,
+    // Included in Proton's free plan
+    pub free: bool,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 2] = [Provider::Mullvad, Provider::Proton];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Provider::Mullvad => "Mullvad",
+            Provider::Proton => "ProtonVPN",
+        }
+    }
+
+    // Mullvad dropped OpenVPN in 2026
+    pub fn protocols(self) -> &'static [VpnType] {
+        match self {
+            Provider::Mullvad => &[VpnType::WireGuard],
+            Provider::Proton => &[VpnType::WireGuard, VpnType::OpenVpn],
+        }
+    }
+
+    // Servers in service, sorted by country, city and name
+    pub async fn servers(self) -> Result<Vec<Server>> {
+        let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+        let mut servers = match self {
+            Provider::Mullvad => mullvad::servers(&client).await?,
+            Provider::Proton => proton::servers(&client).await?,
+        };
+        servers.sort_by(|a, b| (&a.country, &a.city, &a.name).cmp(&(&b.country, &b.city, &b.name)));
+        Ok(servers)
+    }
+
+    // Writes a profile for the server and returns the entry for it. Mullvad registers a key with
+    // the account the first time, kept in `accounts` for the next profiles.
+    pub async fn create_profile(self, accounts: &mut ProviderAccounts, server: &Server, vpn_type: &VpnType, name: &str) -> Result<VpnConfig> {
+        if !self.protocols().contains(vpn_type) {
+            return Err(anyhow::anyhow!("{} doesn't offer {:?}", self.label(), vpn_type));
+        }
+        let (content, username, password) = match (self, vpn_type) {
+            (Provider::Mullvad, _) => {
+                let client = reqwest::Client::builder().timeout(TIMEOUT).build()?;
+                (mullvad::wireguard_config(&client, &mut accounts.mullvad, server).await?, String::new(), String::new())
+            }
+            (Provider::Proton, VpnType::OpenVpn) => (
+                proton::openvpn_config(&accounts.proton, server)?,
+                accounts.proton.openvpn_username.trim().to_string(),
+                accounts.proton.openvpn_password.clone(),
+            ),
+            (Provider::Proton, _) => (proton::wireguard_config(&accounts.proton, server)?, String::new(), String::new()),
+        };
+
+        // WireGuard names the interface after the file, which allows 15 characters
+        let stem: String = format!("{}-{}", self.label(), server.name)
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+            .take(15)
+            .collect();
+        let extension = if *vpn_type == VpnType::OpenVpn { "ovpn" } else { "conf" };
+        let config_path = Config::store_managed_file(&format!("{}.{}", stem.trim_end_matches('-'), extension), content.as_bytes())?;
+        log::info!("Created a {} profile for {}", self.label(), server.name);
+
+        Ok(VpnConfig {
+            id: crate::config::migrate::new_id(),
+            name: name.to_string(),
+            config_path,
+            username,
+            password,
+            prompt_otp: false,
+            auto_connect: false,
+            vpn_type: vpn_type.clone(),
+            env_vars: Default::default(),
+            server: String::new(),
+            group: String::new(),
+            kill_switch: false,
+            split_routes: Vec::new(),
+            app_tunnel: Default::default(),
+            dns_servers: Vec::new(),
+            dns_search_domains: Vec::new(),
+            reconnect: Default::default(),
+            system_service: false,
+            health: Default::default(),
+            proxy: Default::default(),
+            certificates: Default::default(),
+            custom: Default::default(),
+            style: Default::default(),
+        })
+    }
+}
+
+pub fn countries(servers: &[Server]) -> Vec<String> {
+    let mut countries: Vec<String> = servers.iter().map(|server| server.country.clone()).collect();
+    countries.dedup();
+    countries
+}
+
+pub fn cities(servers: &[Server], country: &str) -> Vec<String> {
+    let mut cities: Vec<String> = servers.iter().filter(|server| server.country == country).map(|server| server.city.clone()).collect();
+    cities.sort();
+    cities.dedup();
+    cities
+}
+
+// Servers of the country, and city unless it is empty, that speak the protocol
+pub fn matching<'a>(servers: &'a [Server], country: &str, city: &str, vpn_type: &VpnType) -> Vec<&'a Server> {
+    servers.iter()
+        .filter(|server| server.country == country && (city.is_empty() || server.city == city))
+        .filter(|server| match vpn_type {
+            VpnType::WireGuard => !server.public_key.is_empty(),
+            _ => server.openvpn,
+        })
+        .collect()
+}
+
+// The least loaded of the matching servers; the first where the provider doesn't report load
+pub fn best<'a>(servers: &[&'a Server]) -> Option<&'a Server> {
+    servers.iter().copied().min_by_key(|server| server.load.unwrap_or(0))
+}
+
+// The entry name offered for a server, e.g. "Mullvad Gothenburg"
+pub fn default_name(provider: Provider, country: &str, city: &str, server: Option<&Server>) -> String {
+    match server {
+        Some(server) => format!("{} {}", provider.label(), server.name),
+        None if !city.is_empty() => format!("{} {}", provider.label(), city),
+        None => format!("{} {}", provider.label(), country),
+    }
+}
@@ -0,0 +1,133 @@
+use super::Server;
+use crate::config::MullvadAccount;
+use crate::network::wireguard;
+use anyhow::Result;
+use serde::Deserialize;
+
+// Mullvad's relay list is public. A WireGuard profile needs a key registered with the account,
+// which takes an access token for the account number; the key and the addresses Mullvad assigns
+// it are kept and reused, since an account holds only five devices.
+
+const RELAYS_URL: &str = "https://api.mullvad.net/www/relays/wireguard/";
+const TOKEN_URL: &str = "https://api.mullvad.net/auth/v1/token";
+const DEVICES_URL: &str = "https://api.mullvad.net/accounts/v1/devices";
+// Mullvad's resolver inside the tunnel
+const DNS: &str = "10.64.0.1";
+const PORT: u16 = 51820;
+
+#[derive(Deserialize)]
+struct Relay {
+    hostname: String,
+    #[serde(default)]
+    country_name: String,
+    #[serde(default)]
+    city_name: String,
+    #[serde(default)]
+    active: bool,
+    #[serde(default)]
+    ipv4_addr_in: String,
+    #[serde(default)]
+    pubkey: String,
+}
+
+#[derive(Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Deserialize)]
+struct Device {
+    ipv4_address: String,
+    #[serde(default)]
+    ipv6_address: String,
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    detail: String,
+}
+
+pub async fn servers(client: &reqwest::Client) -> Result<Vec<Server>> {
+    let relays: Vec<Relay> = client.get(RELAYS_URL)
+        .header("User-Agent", "vpn-manager")
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .map_err(|e| anyhow::anyhow!("Unexpected Mullvad relay list: {}", e))?;
+    Ok(relays.into_iter()
+        .filter(|relay| relay.active && !relay.ipv4_addr_in.is_empty())
+        .map(|relay| Server {
+            name: relay.hostname,
+            country: relay.country_name,
+            city: relay.city_name,
+            address: relay.ipv4_addr_in,
+            public_key: relay.pubkey,
+            openvpn: false,
+            load: None,
+            free: false,
+        })
+        .collect())
+}
+
+pub async fn wireguard_config(client: &reqwest::Client, account: &mut MullvadAccount, server: &Server) -> Result<String> {
+    if account.private_key.trim().is_empty() || account.addresses.trim().is_empty() {
+        register(client, account).await?;
+    }
+    Ok(format!(
+        "[Interface]\nPrivateKey = {}\nAddress = {}\nDNS = {}\n\n[Peer]\n# {}\nPublicKey = {}\nAllowedIPs = 0.0.0.0/0, ::/0\nEndpoint = {}:{}\n",
+        account.private_key.trim(), account.addresses.trim(), DNS, server.name, server.public_key, server.address, PORT,
+    ))
+}
+
+// Adds a new key as a device of the account
+async fn register(client: &reqwest::Client, account: &mut MullvadAccount) -> Result<()> {
+    let number: String = account.account_number.chars().filter(|c| c.is_ascii_digit()).collect();
+    if number.is_empty() {
+        return Err(anyhow::anyhow!("Enter your Mullvad account number first"));
+    }
+    let keys = wireguard::generate_keypair().await?;
+
+    let response = client.post(TOKEN_URL)
+        .json(&serde_json::json!({ "account_number": number }))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(api_error("Mullvad didn't accept the account number", response).await);
+    }
+    let token: Token = response.json().await?;
+
+    let response = client.post(DEVICES_URL)
+        .bearer_auth(&token.access_token)
+        .json(&serde_json::json!({ "pubkey": keys.public_key, "hijack_dns": false }))
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(api_error("Mullvad didn't register the key", response).await);
+    }
+    let device: Device = response.json().await?;
+
+    account.private_key = keys.private_key;
+    account.addresses = [device.ipv4_address, device.ipv6_address]
+        .into_iter()
+        .filter(|address| !address.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ");
+    log::info!("Registered a WireGuard key with the Mullvad account");
+    Ok(())
+}
+
+async fn api_error(context: &str, response: reqwest::Response) -> anyhow::Error {
+    let status = response.status();
+    match response.json::<ApiError>().await {
+        Ok(error) if error.code == "MAX_DEVICES_REACHED" => {
+            anyhow::anyhow!("{}: the account already has 5 devices, remove one in your Mullvad account first", context)
+        }
+        Ok(error) if !error.detail.is_empty() => anyhow::anyhow!("{}: {}", context, error.detail),
+        _ => anyhow::anyhow!("{}: HTTP {}", context, status),
+    }
+}
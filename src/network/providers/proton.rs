@@ -0,0 +1,134 @@
+use super::Server;
+use crate::config::ProtonAccount;
+use crate::network::{ovpn_bundle, wireguard};
+use anyhow::Result;
+use serde::Deserialize;
+
+// Proton's list of logical servers, each with one or more physical servers behind it. Keys and
+// certificates only come with Proton's own login, so the WireGuard key is one the user made on
+// the account page, and OpenVPN profiles are a downloaded profile pointed at another server.
+
+const LOGICALS_URL: &str = "https://api.protonvpn.ch/vpn/logicals";
+// Proton's API turns away clients that don't name themselves
+const APP_VERSION: &str = "linux-vpn@4.0.0";
+// Proton's resolver and the address every client gets inside the tunnel
+const DNS: &str = "10.2.0.1";
+const ADDRESS: &str = "10.2.0.2/32";
+const PORT: u16 = 51820;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Logicals {
+    #[serde(default)]
+    logical_servers: Vec<Logical>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Logical {
+    name: String,
+    #[serde(default)]
+    exit_country: String,
+    #[serde(default)]
+    city: Option<String>,
+    #[serde(default)]
+    tier: u8,
+    // 0 while in maintenance
+    #[serde(default)]
+    status: u8,
+    #[serde(default)]
+    load: Option<u8>,
+    #[serde(default)]
+    servers: Vec<Physical>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Physical {
+    #[serde(rename = "EntryIP")]
+    entry_ip: String,
+    #[serde(default, rename = "X25519PublicKey")]
+    x25519_public_key: Option<String>,
+    #[serde(default)]
+    status: u8,
+}
+
+pub async fn servers(client: &reqwest::Client) -> Result<Vec<Server>> {
+    let response = client.get(LOGICALS_URL)
+        .header("x-pm-appversion", APP_VERSION)
+        .header("User-Agent", "vpn-manager")
+        .send()
+        .await?;
+    let status = response.status();
+    let logicals: Logicals = response.json().await
+        .map_err(|e| anyhow::anyhow!("Unexpected Proton server list (HTTP {}): {}", status, e))?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("Proton refused the server list: {}", logicals.error.unwrap_or_else(|| format!("HTTP {}", status))));
+    }
+
+    Ok(logicals.logical_servers.into_iter()
+        .filter(|logical| logical.status != 0)
+        .filter_map(|logical| {
+            // The first physical server in service stands for the logical one
+            let physical = logical.servers.into_iter().find(|physical| physical.status != 0)?;
+            Some(Server {
+                name: logical.name,
+                city: logical.city.unwrap_or_default(),
+                country: logical.exit_country,
+                address: physical.entry_ip,
+                public_key: physical.x25519_public_key.unwrap_or_default(),
+                openvpn: true,
+                load: logical.load,
+                free: logical.tier == 0,
+            })
+        })
+        .collect())
+}
+
+pub fn wireguard_config(account: &ProtonAccount, server: &Server) -> Result<String> {
+    let private_key = account.wireguard_private_key.trim();
+    if private_key.is_empty() {
+        return Err(anyhow::anyhow!("Paste the private key of a WireGuard config from your Proton account first"));
+    }
+    if !wireguard::is_valid_key(private_key) {
+        return Err(anyhow::anyhow!("The Proton WireGuard private key is not a valid key"));
+    }
+    Ok(format!(
+        "[Interface]\nPrivateKey = {}\nAddress = {}\nDNS = {}\n\n[Peer]\n# {}\nPublicKey = {}\nAllowedIPs = 0.0.0.0/0, ::/0\nEndpoint = {}:{}\n",
+        private_key, ADDRESS, DNS, server.name, server.public_key, server.address, PORT,
+    ))
+}
+
+// The template profile with its remote lines pointed at the server, ports and all kept
+pub fn openvpn_config(account: &ProtonAccount, server: &Server) -> Result<String> {
+    let template_path = account.openvpn_template.trim();
+    if template_path.is_empty() {
+        return Err(anyhow::anyhow!("Choose an OpenVPN profile downloaded from your Proton account as the template first"));
+    }
+    if account.openvpn_username.trim().is_empty() {
+        return Err(anyhow::anyhow!("Enter your Proton OpenVPN username first"));
+    }
+    // Certificates the template names next to it are inlined, so the copy stands on its own
+    let template = ovpn_bundle::standalone(std::path::Path::new(template_path))
+        .map_err(|e| anyhow::anyhow!("Could not read the OpenVPN template {}: {}", template_path, e))?;
+
+    let mut remotes = 0;
+    let lines: Vec<String> = template.lines()
+        .map(|line| {
+            let words = ovpn_bundle::split_words(line.trim());
+            match words.as_slice() {
+                [directive, _host, rest @ ..] if directive == "remote" => {
+                    remotes += 1;
+                    std::iter::once(format!("remote {}", server.address)).chain(rest.iter().cloned()).collect::<Vec<_>>().join(" ")
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+    if remotes == 0 {
+        return Err(anyhow::anyhow!("The OpenVPN template has no remote line to point at {}", server.name));
+    }
+    Ok(format!("# {}\n{}\n", server.name, lines.join("\n")))
+}
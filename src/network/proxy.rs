@@ -0,0 +1,141 @@
+use crate::config::{ProxyKind, VpnConfig, VpnProxy};
+use std::path::Path;
+
+// OpenVPN through an HTTP or SOCKS5 proxy, for networks that only let traffic out that way. The
+// app passes --http-proxy or --socks-proxy after the profile, so they replace a proxy the
+// profile names. Credentials are asked for over the management interface like the login, so
+// they never reach the command line. Tunnels that run without the app (system::boot and
+// system::services) read them from a file only root can read instead. An HTTP proxy only
+// carries TCP, so the profile needs `proto tcp-client`; SOCKS5 also relays UDP.
+
+// Whether the entry connects through a proxy
+pub fn is_set(config: &VpnConfig) -> bool {
+    config.vpn_type == crate::config::VpnType::OpenVpn
+        && config.proxy.kind != ProxyKind::None
+        && !config.proxy.host.trim().is_empty()
+        && config.proxy.port != 0
+}
+
+// The option and its parameters, without the leading dashes. `auth` is the credentials file,
+// or "stdin" to be asked over the management interface.
+fn directive(proxy: &VpnProxy, auth: Option<&str>) -> Vec<String> {
+    let mut words = vec![
+        match proxy.kind {
+            ProxyKind::Socks => "socks-proxy".to_string(),
+            _ => "http-proxy".to_string(),
+        },
+        proxy.host.trim().to_string(),
+        proxy.port.to_string(),
+    ];
+    if let Some(auth) = auth.filter(|_| !proxy.username.is_empty()) {
+        words.push(auth.to_string());
+        if proxy.kind == ProxyKind::Http {
+            words.push("basic".to_string());
+        }
+    }
+    words
+}
+
+fn option(config: &VpnConfig, auth: &str) -> Vec<String> {
+    if !is_set(config) {
+        return Vec::new();
+    }
+    let mut words = directive(&config.proxy, Some(auth));
+    words[0] = format!("--{}", words[0]);
+    words
+}
+
+// Arguments for openvpn started by the app
+pub fn args(config: &VpnConfig) -> Vec<String> {
+    option(config, "stdin")
+}
+
+// Arguments for a tunnel that can't ask, with the credentials in `auth_path`
+pub fn args_with_auth_file(config: &VpnConfig, auth_path: &Path) -> Vec<String> {
+    option(config, &auth_path.display().to_string())
+}
+
+// Contents of the credentials file, None without a username
+pub fn credentials(config: &VpnConfig) -> Option<String> {
+    (is_set(config) && !config.proxy.username.is_empty())
+        .then(|| format!("{}\n{}\n", config.proxy.username, config.proxy.password))
+}
+
+// The profile with the entry's proxy in place of any it names itself, for a copy that runs
+// without the app
+pub fn inject(content: &str, config: &VpnConfig, auth_path: &Path) -> String {
+    if !is_set(config) {
+        return content.to_string();
+    }
+    let mut lines: Vec<String> = content
+        .lines()
+        .filter(|line| {
+            let option = line.split_whitespace().next().unwrap_or_default();
+            !option.starts_with("http-proxy") && !option.starts_with("socks-proxy")
+        })
+        .map(|line| line.to_string())
+        .collect();
+    lines.push(directive(&config.proxy, Some(&auth_path.display().to_string())).join(" "));
+    lines.join("\n") + "\n"
+}
+
+// The proxy as NetworkManager's OpenVPN plugin keeps it in vpn.data; the password goes with the
+// other secrets as http-proxy-password
+pub fn nm_data(config: &VpnConfig) -> Vec<String> {
+    if !is_set(config) {
+        return Vec::new();
+    }
+    let mut data = vec![
+        format!("proxy-type={}", if config.proxy.kind == ProxyKind::Socks { "socks" } else { "http" }),
+        format!("proxy-server={}", config.proxy.host.trim()),
+        format!("proxy-port={}", config.proxy.port),
+    ];
+    if config.proxy.kind == ProxyKind::Http && !config.proxy.username.is_empty() {
+        data.push(format!("http-proxy-username={}", config.proxy.username));
+    }
+    data
+}
+
+// The credential type in a `>PASSWORD:Need '...'` request for the proxy
+pub fn prompt_type(line: &str) -> Option<&'static str> {
+    ["HTTP Proxy", "SOCKS Proxy"]
+        .into_iter()
+        .find(|kind| line.starts_with(&format!(">PASSWORD:Need '{}'", kind)))
+}
+
+// Reasons the settings can't work, for the form and the problems list
+pub fn problem(config: &VpnConfig) -> Option<String> {
+    if config.proxy.kind == ProxyKind::None {
+        return None;
+    }
+    if config.vpn_type != crate::config::VpnType::OpenVpn {
+        return Some("A proxy is only used by OpenVPN connections".to_string());
+    }
+    if config.proxy.host.trim().is_empty() {
+        return Some("No proxy host set".to_string());
+    }
+    if config.proxy.port == 0 {
+        return Some("No proxy port set".to_string());
+    }
+    None
+}
+
+// An HTTP proxy with a profile that connects over UDP, which OpenVPN refuses
+pub fn needs_tcp(config: &VpnConfig, profile: &str) -> bool {
+    if !is_set(config) || config.proxy.kind != ProxyKind::Http {
+        return false;
+    }
+    // UDP unless the profile says otherwise; `remote host port proto` and <connection> blocks
+    // may each say it again
+    let protos: Vec<String> = profile
+        .lines()
+        .filter_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["proto", proto, ..] | ["remote", _, _, proto, ..] => Some(proto.to_lowercase()),
+                _ => None,
+            }
+        })
+        .collect();
+    protos.is_empty() || protos.iter().any(|proto| proto.starts_with("udp"))
+}
@@ -0,0 +1,149 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// The address the internet sees this machine at, to tell whether traffic really leaves through
+// the tunnel. Looked up over HTTPS from a public service and cached for a while, since every
+// frame of the Home tab asks; a change of the VPN state fetches it anew.
+
+const SERVICES: [&str; 2] = ["https://ipwho.is/", "https://ipinfo.io/json"];
+const MAX_AGE: Duration = Duration::from_secs(300);
+// After the VPN state changes, for routes and DNS to settle before looking again
+const SETTLE: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublicIp {
+    pub ip: String,
+    // Name or code, whichever the service gives; empty if it gives none
+    pub country: String,
+    pub checked_at: chrono::DateTime<chrono::Local>,
+}
+
+impl PublicIp {
+    pub fn describe(&self) -> String {
+        if self.country.is_empty() {
+            self.ip.clone()
+        } else {
+            format!("{} ({})", self.ip, self.country)
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ServiceAnswer {
+    #[serde(default)]
+    ip: String,
+    #[serde(default)]
+    country: String,
+}
+
+static CACHE: Mutex<Option<(Instant, PublicIp)>> = Mutex::new(None);
+
+// The cached address while it is fresh, else one from the first service that answers
+pub async fn lookup(fresh: bool) -> Result<PublicIp> {
+    if !fresh {
+        if let Some((at, cached)) = CACHE.lock().unwrap().as_ref() {
+            if at.elapsed() < MAX_AGE {
+                return Ok(cached.clone());
+            }
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let mut last_error = None;
+    for service in SERVICES {
+        let answer = async {
+            client.get(service)
+                .header("User-Agent", "vpn-manager")
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ServiceAnswer>()
+                .await
+        };
+        match answer.await {
+            Ok(answer) if !answer.ip.is_empty() => {
+                let found = PublicIp { ip: answer.ip, country: answer.country, checked_at: chrono::Local::now() };
+                *CACHE.lock().unwrap() = Some((Instant::now(), found.clone()));
+                return Ok(found);
+            }
+            Ok(_) => last_error = Some(format!("{} returned no address", service)),
+            Err(e) => last_error = Some(format!("{}: {}", service, e)),
+        }
+    }
+    Err(anyhow::anyhow!("Could not look up the public IP: {}", last_error.unwrap_or_default()))
+}
+
+// The public address for the Home tab, with the one seen before the last VPN state change
+#[derive(Default)]
+pub struct PublicIpWatch {
+    pub current: Option<Result<PublicIp, String>>,
+    pub before: Option<PublicIp>,
+    // The connected VPN the address was looked up for; None before the first lookup
+    seen_for: Option<Option<String>>,
+    receiver: Option<Receiver<Result<PublicIp, String>>>,
+}
+
+impl PublicIpWatch {
+    pub fn is_checking(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    // The VPN the address goes out through; None while disconnected
+    pub fn vpn(&self) -> Option<&str> {
+        self.seen_for.as_ref().and_then(|vpn| vpn.as_deref())
+    }
+
+    // Looks the address up again once the connected VPN changes, and the first time
+    pub fn sync(&mut self, connected: Option<&str>) {
+        let connected = connected.map(|name| name.to_string());
+        match &self.seen_for {
+            Some(seen) if *seen == connected => {}
+            Some(_) => {
+                self.before = self.current.as_ref().and_then(|current| current.as_ref().ok()).cloned();
+                self.seen_for = Some(connected);
+                self.start(true, SETTLE);
+            }
+            None => {
+                self.seen_for = Some(connected);
+                self.start(false, Duration::ZERO);
+            }
+        }
+    }
+
+    pub fn refresh(&mut self) {
+        self.start(true, Duration::ZERO);
+    }
+
+    fn start(&mut self, fresh: bool, delay: Duration) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.receiver = Some(rx);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(async {
+                tokio::time::sleep(delay).await;
+                lookup(fresh).await
+            });
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else { return };
+        match receiver.try_recv() {
+            Ok(result) => {
+                if let Ok(found) = &result {
+                    log::info!("Public IP: {}", found.describe());
+                }
+                self.current = Some(result);
+                self.receiver = None;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => self.receiver = None,
+        }
+    }
+}
@@ -1,23 +1,64 @@
-use crate::config::RdpConfig;
+use crate::config::{RdpConfig, RdpScreenMode};
+use crate::system::client_log;
 use anyhow::Result;
 use tokio::process::Command;
 
-pub async fn connect(config: &RdpConfig) -> Result<()> {
-    log::info!("Attempting RDP connection to {}:{} with user '{}' and domain '{}'", 
+#[cfg(unix)]
+use crate::errors::{self, ErrorCode};
+#[cfg(target_os = "macos")]
+use std::path::PathBuf;
+#[cfg(unix)]
+use crate::system::session::{DisplaySession, SessionType};
+
+// Low bandwidth trades colors, wallpaper and animations for responsiveness on a slow link
+pub async fn connect(config: &RdpConfig, low_bandwidth: bool) -> Result<()> {
+    log::info!("Attempting RDP connection to {}:{} with user '{}' and domain '{}'{}", 
                config.host, config.port, config.username, 
-               config.domain.as_deref().unwrap_or("none"));
+               config.domain.as_deref().unwrap_or("none"),
+               if low_bandwidth { " (low bandwidth)" } else { "" });
     
     #[cfg(windows)]
     {
-        connect_with_mstsc(config).await
+        connect_with_mstsc(config, low_bandwidth).await
     }
     
-    #[cfg(unix)]
+    #[cfg(target_os = "macos")]
     {
-        connect_with_xfreerdp(config).await
+        connect_with_macos_app(config, low_bandwidth).await
+    }
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let session = DisplaySession::detect();
+        session.require_display("the RDP client")?;
+        
+        if select_freerdp_client(&session).is_some() {
+            connect_with_xfreerdp(config, low_bandwidth).await
+        } else if which::which("remmina").is_ok() {
+            log::info!("No FreeRDP client compatible with {} session, falling back to Remmina", session.description());
+            if low_bandwidth {
+                log::info!("Remmina keeps the quality set in its own profile");
+            }
+            connect_with_remmina(config).await
+        } else {
+            Err(errors::error(ErrorCode::RdpClientMissing, session.description()))
+        }
     }
 }
 
+// Picks the FreeRDP frontend that can actually open a window in the current session
+#[cfg(unix)]
+pub fn select_freerdp_client(session: &DisplaySession) -> Option<&'static str> {
+    let candidates: &[&'static str] = match session.session_type {
+        SessionType::Wayland if session.supports_x11_clients() => &["wlfreerdp", "xfreerdp"],
+        SessionType::Wayland => &["wlfreerdp"],
+        SessionType::Headless => &[],
+        _ => &["xfreerdp"],
+    };
+    
+    candidates.iter().copied().find(|binary| which::which(binary).is_ok())
+}
+
 #[cfg(windows)]
 pub async fn test_mstsc_basic() -> Result<()> {
     log::info!("Testing basic mstsc functionality");
@@ -61,21 +102,90 @@ pub async fn test_mstsc_basic() -> Result<()> {
     }
 }
 
+// How long mstsc gets to read the credentials before they are deleted again
+#[cfg(windows)]
+pub const CREDENTIAL_LIFETIME: std::time::Duration = std::time::Duration::from_secs(30);
+
+// mstsc takes no password on its command line or in a .rdp file; it reads the one saved for
+// TERMSRV/<host> in the Credential Manager. With store_credentials, cmdkey saves it there just
+// before launching, and it is deleted again once mstsc had time to read it. Credentials the user
+// saved for the host themselves are left alone. cmdkey only takes the password as an argument,
+// so for that moment it shows in the process list of this session.
+pub fn credential_target(config: &RdpConfig) -> String {
+    format!("TERMSRV/{}", config.host)
+}
+
+#[cfg(windows)]
+fn cmdkey(args: &[String]) -> std::io::Result<std::process::Output> {
+    use std::os::windows::process::CommandExt;
+    let mut cmd = std::process::Command::new("cmdkey");
+    cmd.args(args).stdin(std::process::Stdio::null());
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    cmd.output()
+}
+
+// Saves the credentials for mstsc; false when the user's own are already saved for the host
+#[cfg(windows)]
+fn store_credentials(config: &RdpConfig) -> Result<bool> {
+    let target = credential_target(config);
+    let listed = cmdkey(&[format!("/list:{}", target)])?;
+    let listing = String::from_utf8_lossy(&listed.stdout).to_lowercase();
+    if listing.contains(&format!("target={}", target.to_lowercase())) {
+        log::info!("Credentials for {} are already saved, leaving them to mstsc", target);
+        return Ok(false);
+    }
+    
+    let user = match config.domain.as_deref().filter(|domain| !domain.is_empty()) {
+        Some(domain) => format!("{}\\{}", domain, config.username),
+        None => config.username.clone(),
+    };
+    let output = cmdkey(&[format!("/generic:{}", target), format!("/user:{}", user), format!("/pass:{}", config.password)])?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("cmdkey failed: {}", String::from_utf8_lossy(&output.stdout).trim()));
+    }
+    log::info!("Saved the credentials for {} for mstsc", target);
+    Ok(true)
+}
+
+// On a thread of its own, the runtime of the launch is gone by then
 #[cfg(windows)]
-pub async fn connect_with_mstsc(config: &RdpConfig) -> Result<()> {
+fn delete_credentials_later(config: &RdpConfig) {
+    let target = credential_target(config);
+    std::thread::spawn(move || {
+        std::thread::sleep(CREDENTIAL_LIFETIME);
+        match cmdkey(&[format!("/delete:{}", target)]) {
+            Ok(output) if output.status.success() => log::info!("Removed the credentials for {}", target),
+            Ok(output) => log::warn!("Could not remove the credentials for {}: {}", target, String::from_utf8_lossy(&output.stdout).trim()),
+            Err(e) => log::warn!("Could not remove the credentials for {}: {}", target, e),
+        }
+    });
+}
+
+#[cfg(windows)]
+pub async fn connect_with_mstsc(config: &RdpConfig, low_bandwidth: bool) -> Result<()> {
     let port = if config.port == 0 { 3389 } else { config.port };
     
     log::info!("Attempting RDP connection to {}:{}", config.host, port);
     
+    if config.store_credentials && !config.password.is_empty() {
+        match store_credentials(config) {
+            Ok(true) => delete_credentials_later(config),
+            Ok(false) => {}
+            Err(e) => log::warn!("mstsc will ask for the password, saving it failed: {}", e),
+        }
+    }
+    
+    // The display settings can only be given in a file
+    if needs_rdp_file(config, low_bandwidth) {
+        return connect_with_rdp_file_simple(config, low_bandwidth).await;
+    }
+    
     // Try the most straightforward approach that should work
-    let connection_string = if port == 3389 {
-        config.host.clone()
-    } else {
-        format!("{}:{}", config.host, port)
-    };
+    let connection_string = mstsc_target(config);
     
     // Method 1: Direct mstsc command with /v parameter (most reliable)
     let mut cmd = std::process::Command::new("mstsc");
+    cmd.envs(&config.env_vars);
     cmd.arg("/v");
     cmd.arg(&connection_string);
     
@@ -93,6 +203,7 @@ pub async fn connect_with_mstsc(config: &RdpConfig) -> Result<()> {
     
     // Method 2: Try with colon format
     let mut cmd = std::process::Command::new("mstsc");
+    cmd.envs(&config.env_vars);
     cmd.arg(format!("/v:{}", connection_string));
     
     log::info!("Executing: mstsc /v:{}", connection_string);
@@ -108,26 +219,28 @@ pub async fn connect_with_mstsc(config: &RdpConfig) -> Result<()> {
     }
     
     // Method 3: Create minimal RDP file
-    connect_with_rdp_file_simple(config).await
+    connect_with_rdp_file_simple(config, false).await
+}
+
+// Host argument for mstsc /v, omitting the default port
+pub fn mstsc_target(config: &RdpConfig) -> String {
+    let port = if config.port == 0 { 3389 } else { config.port };
+    if port == 3389 {
+        config.host.clone()
+    } else {
+        format!("{}:{}", config.host, port)
+    }
 }
 
 #[cfg(windows)]
-pub async fn connect_with_rdp_file_simple(config: &RdpConfig) -> Result<()> {
+pub async fn connect_with_rdp_file_simple(config: &RdpConfig, low_bandwidth: bool) -> Result<()> {
     let temp_dir = std::env::temp_dir();
     let rdp_file = temp_dir.join(format!("{}.rdp", config.name));
     
     let port = if config.port == 0 { 3389 } else { config.port };
     
-    // Create the absolute minimal RDP file that Windows will accept
-    let rdp_content = format!(
-        "full address:s:{}:{}\r\n\
-         username:s:{}\r\n\
-         prompt for credentials:i:1\r\n\
-         administrative session:i:1\r\n",
-        config.host, 
-        port, 
-        config.username
-    );
+    let mut rdp_content = rdp_file_content(config, low_bandwidth);
+    rdp_content.push_str("administrative session:i:1\r\n");
     
     log::info!("Creating RDP file with content:\n{}", rdp_content);
     std::fs::write(&rdp_file, rdp_content)?;
@@ -136,6 +249,7 @@ pub async fn connect_with_rdp_file_simple(config: &RdpConfig) -> Result<()> {
     
     // Method 1: Direct mstsc with file
     let mut cmd = std::process::Command::new("mstsc");
+    cmd.envs(&config.env_vars);
     cmd.arg(&rdp_file);
     
     log::info!("Trying: mstsc {}", rdp_file.display());
@@ -204,57 +318,200 @@ pub async fn connect_with_rdp_file_simple(config: &RdpConfig) -> Result<()> {
         Err(e) => {
             log::error!("All RDP connection methods failed. Last error: {}", e);
             let _ = std::fs::remove_file(&rdp_file); // Clean up immediately on failure
-            return Err(anyhow::anyhow!(
-                "Failed to start RDP connection to {}:{}. \
-                 Please ensure:\n\
-                 1. The host is reachable\n\
-                 2. RDP is enabled on the target machine\n\
-                 3. Windows Remote Desktop Client (mstsc) is available\n\
-                 \nAs a last resort, manually connect to: {}:{}\n\
-                 \nLast error: {}", 
-                config.host, port, config.host, port, e
+            return Err(errors::error(
+                ErrorCode::RdpClientFailed,
+                format!("mstsc couldn't connect to {}:{} ({})", config.host, port, e)
             ));
         }
     }
 }
 
 #[cfg(unix)]
-pub async fn connect_with_xfreerdp(config: &RdpConfig) -> Result<()> {
-    let mut cmd = Command::new("xfreerdp");
-    
-    cmd.arg(format!("/v:{}", config.host));
-    cmd.arg(format!("/port:{}", config.port));
-    cmd.arg(format!("/u:{}", config.username));
-    
-    if !config.password.is_empty() {
-        cmd.arg(format!("/p:{}", config.password));
-    }
-    
-    if let Some(domain) = &config.domain {
-        cmd.arg(format!("/d:{}", domain));
-    }
-    
-    cmd.arg("/cert-ignore");
-    cmd.arg("/compression");
-    cmd.arg("/clipboard");
-    cmd.arg("/auto-reconnect");
-    cmd.arg("/f");
+pub async fn connect_with_xfreerdp(config: &RdpConfig, low_bandwidth: bool) -> Result<()> {
+    let session = DisplaySession::detect();
+    let client = select_freerdp_client(&session).unwrap_or("xfreerdp");
+    log::info!("Using {} for {} session", client, session.description());
+    
+    let mut cmd = Command::new(client);
+    cmd.envs(&config.env_vars);
+    cmd.args(freerdp_args(config, low_bandwidth));
 
     let output = cmd.output().await?;
+    client_log::record("rdp", &config.name, client, &output);
 
     if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to start RDP connection: {}",
-            String::from_utf8_lossy(&output.stderr)
+        return Err(errors::error(
+            ErrorCode::RdpClientFailed,
+            format!("{}: {}", client, String::from_utf8_lossy(&output.stderr).trim())
         ));
     }
 
     Ok(())
 }
 
-pub async fn connect_with_remmina(config: &RdpConfig) -> Result<()> {
-    let mut cmd = Command::new("remmina");
+// The modem connection type turns off wallpaper, themes and animations on the server's side too
+const LOW_BANDWIDTH_FREERDP: &[&str] = &[
+    "/network:modem", "/bpp:16", "/compression-level:2", "-wallpaper", "-themes", "-aero", "-menu-anims", "-window-drag",
+];
+const LOW_BANDWIDTH_RDP_FILE: &[&str] = &[
+    "connection type:i:1",
+    "networkautodetect:i:0",
+    "bandwidthautodetect:i:0",
+    "session bpp:i:16",
+    "disable wallpaper:i:1",
+    "disable themes:i:1",
+    "disable full window drag:i:1",
+    "disable menu anims:i:1",
+    "allow font smoothing:i:0",
+    "allow desktop composition:i:0",
+    "bitmapcachepersistenable:i:1",
+];
+
+// Whether mstsc needs a .rdp file for the connection, as its command line only takes the address
+pub fn needs_rdp_file(config: &RdpConfig, low_bandwidth: bool) -> bool {
+    low_bandwidth || config.display != Default::default() || config.redirect != Default::default() || config.gateway.is_some()
+}
+
+// The .rdp file mstsc and Microsoft's app on macOS open: address, user and display settings, and
+// the low bandwidth settings when asked for. Never the password, the client asks for it.
+pub fn rdp_file_content(config: &RdpConfig, low_bandwidth: bool) -> String {
+    rdp_file_lines(config, low_bandwidth).iter().map(|line| format!("{}\r\n", line)).collect()
+}
+
+// The connection as a .rdp file for someone else's client. It always asks for the credentials,
+// a sign-in saved with cmdkey only exists on this computer, and leaves out the low bandwidth
+// settings, which depend on the link at the time.
+pub fn export_rdp_file(config: &RdpConfig, path: &std::path::Path) -> Result<()> {
+    let portable = RdpConfig { store_credentials: false, ..config.clone() };
+    std::fs::write(path, rdp_file_content(&portable, false))?;
+    log::info!("Exported the RDP connection {} to {}", config.name, path.display());
+    Ok(())
+}
+
+pub fn rdp_file_lines(config: &RdpConfig, low_bandwidth: bool) -> Vec<String> {
+    let port = if config.port == 0 { 3389 } else { config.port };
+    let mut lines = vec![format!("full address:s:{}:{}", config.host, port)];
+    if !config.username.is_empty() {
+        lines.push(format!("username:s:{}", config.username));
+    }
+    if let Some(domain) = config.domain.as_deref().filter(|domain| !domain.is_empty()) {
+        lines.push(format!("domain:s:{}", domain));
+    }
+    // Saved credentials are only used when the file doesn't ask for them
+    let saved = cfg!(windows) && config.store_credentials && !config.password.is_empty();
+    lines.push(format!("prompt for credentials:i:{}", if saved { 0 } else { 1 }));
+    
+    let display = &config.display;
+    lines.push(format!("screen mode id:i:{}", if display.is_fullscreen() { 2 } else { 1 }));
+    if display.multimon {
+        lines.push("use multimon:i:1".to_string());
+    }
+    if display.has_size() {
+        lines.push(format!("desktopwidth:i:{}", display.width));
+        lines.push(format!("desktopheight:i:{}", display.height));
+    } else {
+        // The session follows the window or screen it is shown in
+        lines.push("dynamic resolution:i:1".to_string());
+    }
+    if display.scale != 100 {
+        lines.push(format!("desktopscalefactor:i:{}", display.scale));
+    }
+    if display.smart_sizing {
+        lines.push("smart sizing:i:1".to_string());
+    }
+    
+    let redirect = &config.redirect;
+    lines.push(format!("redirectclipboard:i:{}", redirect.clipboard as u8));
+    // 0 plays on this computer, 2 nowhere
+    lines.push(format!("audiomode:i:{}", if redirect.audio { 0 } else { 2 }));
+    lines.push(format!("audiocapturemode:i:{}", redirect.microphone as u8));
+    if redirect.drives {
+        lines.push("drivestoredirect:s:*".to_string());
+    }
+    
+    // Always through the gateway, even on the host's own network; the client asks for the
+    // gateway's password, or reuses the session's when there is no gateway user
+    if let Some(gateway) = &config.gateway {
+        lines.push(format!("gatewayhostname:s:{}", gateway.host));
+        lines.push("gatewayusagemethod:i:1".to_string());
+        lines.push("gatewayprofileusagemethod:i:1".to_string());
+        lines.push("gatewaycredentialssource:i:0".to_string());
+        lines.push(format!("promptcredentialonce:i:{}", gateway.username.is_empty() as u8));
+    }
+    
+    if low_bandwidth {
+        lines.extend(LOW_BANDWIDTH_RDP_FILE.iter().map(|line| line.to_string()));
+    }
+    lines
+}
+
+// Arguments for xfreerdp/wlfreerdp, shared with the command preview
+pub fn freerdp_args(config: &RdpConfig, low_bandwidth: bool) -> Vec<String> {
+    let mut args = vec![
+        format!("/v:{}", config.host),
+        format!("/port:{}", config.port),
+        format!("/u:{}", config.username),
+    ];
+    
+    if !config.password.is_empty() {
+        args.push(format!("/p:{}", config.password));
+    }
+    
+    if let Some(domain) = &config.domain {
+        args.push(format!("/d:{}", domain));
+    }
+    
+    for flag in ["/cert-ignore", "/compression", "/auto-reconnect"] {
+        args.push(flag.to_string());
+    }
+    
+    // Without a gateway user FreeRDP signs in to the gateway with the session's credentials
+    if let Some(gateway) = &config.gateway {
+        args.push(format!("/g:{}", gateway.host));
+        if !gateway.username.is_empty() {
+            args.push(format!("/gu:{}", gateway.username));
+        }
+        if !gateway.password.is_empty() {
+            args.push(format!("/gp:{}", gateway.password));
+        }
+    }
+    
+    let redirect = &config.redirect;
+    for (enabled, flag) in [(redirect.clipboard, "/clipboard"), (redirect.audio, "/sound"), (redirect.microphone, "/microphone"), (redirect.drives, "/drives")] {
+        if enabled {
+            args.push(flag.to_string());
+        }
+    }
     
+    let display = &config.display;
+    if display.multimon {
+        args.extend(["/f".to_string(), "/multimon".to_string()]);
+    } else {
+        match display.mode {
+            RdpScreenMode::Fullscreen => args.push("/f".to_string()),
+            // Without a size FreeRDP opens 1024x768 whatever the screen
+            RdpScreenMode::Windowed if !display.has_size() => args.push("/size:90%".to_string()),
+            RdpScreenMode::Windowed => {}
+        }
+    }
+    if display.has_size() {
+        args.push(format!("/size:{}x{}", display.width, display.height));
+    } else {
+        args.push("/dynamic-resolution".to_string());
+    }
+    if display.scale != 100 {
+        args.push(format!("/scale-desktop:{}", display.scale));
+    }
+    if display.smart_sizing {
+        args.push("/smart-sizing".to_string());
+    }
+    if low_bandwidth {
+        args.extend(LOW_BANDWIDTH_FREERDP.iter().map(|flag| flag.to_string()));
+    }
+    args
+}
+
+pub fn remmina_args(config: &RdpConfig) -> Vec<String> {
     let connection_string = format!(
         "rdp://{}:{}@{}:{}",
         config.username,
@@ -262,16 +519,97 @@ pub async fn connect_with_remmina(config: &RdpConfig) -> Result<()> {
         config.host,
         config.port
     );
+    vec!["-c".to_string(), connection_string]
+}
+
+// Microsoft's client from the App Store, renamed Windows App in 2024; either opens .rdp files
+#[cfg(target_os = "macos")]
+pub const MACOS_RDP_APPS: &[&str] = &["Windows App", "Microsoft Remote Desktop"];
+
+// The name of the installed app, looked for in the system's and the user's Applications
+#[cfg(target_os = "macos")]
+pub fn macos_rdp_app() -> Option<&'static str> {
+    let mut folders = vec![PathBuf::from("/Applications")];
+    if let Some(home) = dirs::home_dir() {
+        folders.push(home.join("Applications"));
+    }
+    MACOS_RDP_APPS
+        .iter()
+        .copied()
+        .find(|app| folders.iter().any(|folder| folder.join(format!("{}.app", app)).exists()))
+}
+
+// The URL scheme Microsoft's client registers, for when the app isn't where macos_rdp_app
+// looks; the app asks for the password itself
+pub fn rdp_url(config: &RdpConfig) -> String {
+    let port = if config.port == 0 { 3389 } else { config.port };
+    let mut url = format!("rdp://full%20address=s:{}:{}", config.host, port);
+    if !config.username.is_empty() {
+        let username = match &config.domain {
+            Some(domain) if !domain.is_empty() => format!("{}%5C{}", domain, config.username),
+            _ => config.username.clone(),
+        };
+        url.push_str(&format!("&username=s:{}", username.replace(' ', "%20")));
+    }
+    url
+}
+
+// The app takes the connection as a .rdp file, which carries the low bandwidth settings as well
+#[cfg(target_os = "macos")]
+pub async fn connect_with_macos_app(config: &RdpConfig, low_bandwidth: bool) -> Result<()> {
+    let Some(app) = macos_rdp_app() else {
+        log::info!("Microsoft Remote Desktop not found in Applications, opening an rdp:// URL");
+        let url = rdp_url(config);
+        let output = Command::new("open").arg(&url).output().await?;
+        client_log::record("rdp", &config.name, "open rdp://", &output);
+        if !output.status.success() {
+            return Err(errors::error(ErrorCode::RdpClientMissing, "macOS"));
+        }
+        return Ok(());
+    };
     
-    cmd.arg("-c");
-    cmd.arg(connection_string);
+    let content = rdp_file_content(config, low_bandwidth);
+    
+    let rdp_file = std::env::temp_dir().join(format!("{}.rdp", config.name));
+    std::fs::write(&rdp_file, content)?;
+    
+    let mut cmd = Command::new("open");
+    cmd.envs(&config.env_vars);
+    cmd.arg("-a").arg(app).arg(&rdp_file);
+    log::info!("Opening {} in {}", rdp_file.display(), app);
+    
+    let output = cmd.output().await?;
+    client_log::record("rdp", &config.name, &format!("open -a {}", app), &output);
+    
+    // open returns once the app has the file, which it reads right away
+    let rdp_file_clone = rdp_file.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+        let _ = std::fs::remove_file(&rdp_file_clone);
+    });
+    
+    if !output.status.success() {
+        return Err(errors::error(
+            ErrorCode::RdpClientFailed,
+            format!("{}: {}", app, String::from_utf8_lossy(&output.stderr).trim())
+        ));
+    }
+    
+    Ok(())
+}
+
+pub async fn connect_with_remmina(config: &RdpConfig) -> Result<()> {
+    let mut cmd = Command::new("remmina");
+    cmd.envs(&config.env_vars);
+    cmd.args(remmina_args(config));
 
     let output = cmd.output().await?;
+    client_log::record("rdp", &config.name, "remmina", &output);
 
     if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to start Remmina RDP connection: {}",
-            String::from_utf8_lossy(&output.stderr)
+        return Err(errors::error(
+            ErrorCode::RdpClientFailed,
+            format!("Remmina: {}", String::from_utf8_lossy(&output.stderr).trim())
         ));
     }
 
@@ -295,13 +633,15 @@ pub fn is_rdp_client_available() -> (bool, bool) {
         (mstsc_available || mstsc_exists, false)
     }
     
-    #[cfg(unix)]
+    // The first is Microsoft's app; there's no Remmina on macOS
+    #[cfg(target_os = "macos")]
     {
-        let xfreerdp_available = std::process::Command::new("which")
-            .arg("xfreerdp")
-            .output()
-            .map(|output| output.status.success())
-            .unwrap_or(false);
+        (macos_rdp_app().is_some(), false)
+    }
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let xfreerdp_available = select_freerdp_client(&DisplaySession::detect()).is_some();
 
         let remmina_available = std::process::Command::new("which")
             .arg("remmina")
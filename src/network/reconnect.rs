@@ -0,0 +1,164 @@
+use crate::config::VpnConfig;
+use crate::network::{dns, retry, routes, NetworkManager, VpnStatus};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Reconnecting a VPN whose tunnel dropped without being disconnected from the app. A background
+// thread waits out the profile's backoff before each attempt and reports how it went. The kill
+// switch, if on, stays in place throughout; its rules already let the client reach the server.
+
+#[derive(Debug, Clone)]
+pub enum ReconnectEvent {
+    // About to try, after the backoff
+    Attempt(u32),
+    Failed { attempt: u32, error: String },
+    Connected,
+    // Out of retries, with the last error
+    GaveUp(String),
+}
+
+// Stops the thread when dropped, checked while waiting and before each attempt
+pub struct Reconnector {
+    pub vpn: String,
+    receiver: Receiver<ReconnectEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Reconnector {
+    pub fn start(network_manager: &NetworkManager, config: &VpnConfig) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stop = cancelled.clone();
+        let mut network_manager = network_manager.clone();
+        let config = config.clone();
+        let vpn = config.name.clone();
+        log::info!("Reconnecting {} (up to {} attempts)", config.name, config.reconnect.max_retries);
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                // Routes and DNS settings went with the old interface; connecting applies them again
+                routes::remove(&config).await;
+                dns::restore(&config).await;
+
+                let policy = &config.reconnect;
+                let mut last_error = String::new();
+                for attempt in 1..=policy.max_retries {
+                    let due = Instant::now() + retry::backoff(attempt, policy.base_delay_secs, policy.max_delay_secs);
+                    while Instant::now() < due {
+                        if stop.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        tokio::time::sleep(Duration::from_millis(250)).await;
+                    }
+                    if stop.load(Ordering::Relaxed) || tx.send(ReconnectEvent::Attempt(attempt)).is_err() {
+                        return;
+                    }
+
+                    // The client may have brought the tunnel back by itself
+                    if matches!(network_manager.check_vpn_status(&config).await, Ok(true)) {
+                        let _ = tx.send(ReconnectEvent::Connected);
+                        return;
+                    }
+
+                    match network_manager.connect_vpn(&config).await {
+                        Ok(()) => {
+                            let _ = tx.send(ReconnectEvent::Connected);
+                            return;
+                        }
+                        Err(e) => {
+                            last_error = e.to_string();
+                            let _ = tx.send(ReconnectEvent::Failed { attempt, error: last_error.clone() });
+                        }
+                    }
+                }
+                let _ = tx.send(ReconnectEvent::GaveUp(last_error));
+            });
+        });
+
+        Self { vpn, receiver: rx, cancelled }
+    }
+
+    pub fn poll(&self) -> Option<ReconnectEvent> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl Drop for Reconnector {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecoveryKind {
+    Rehandshake,
+    // Takes the tunnel down and leaves it to the reconnect loop
+    Restart,
+}
+
+// Re-handshaking or restarting a degraded tunnel, on a thread of its own as either waits on the
+// client and, for a restart, on the tunnel going down
+pub struct Recovery {
+    pub vpn: String,
+    pub kind: RecoveryKind,
+    receiver: Receiver<anyhow::Result<VpnStatus>>,
+}
+
+impl Recovery {
+    pub fn start(network_manager: &NetworkManager, config: &VpnConfig, kind: RecoveryKind) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut network_manager = network_manager.clone();
+        let config = config.clone();
+        let vpn = config.name.clone();
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(async {
+                match kind {
+                    RecoveryKind::Rehandshake => network_manager.rehandshake_vpn(&config).await,
+                    RecoveryKind::Restart => network_manager.restart_vpn(&config).await,
+                }
+            });
+            let _ = tx.send(result.map(|_| network_manager.vpn_status));
+        });
+
+        Self { vpn, kind, receiver: rx }
+    }
+
+    // The status it left once done, None while it runs
+    pub fn poll(&self) -> Option<anyhow::Result<VpnStatus>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow::anyhow!("Recovering {} stopped unexpectedly", self.vpn))),
+        }
+    }
+}
+
+// Status line while a degraded tunnel is being recovered
+pub fn recovering_text(name: &str, kind: RecoveryKind) -> String {
+    match kind {
+        RecoveryKind::Rehandshake => format!("{} degraded, re-handshaking...", name),
+        RecoveryKind::Restart => format!("{} degraded, reconnecting...", name),
+    }
+}
+
+// Status line while the handshake is stale
+pub fn degraded_text(name: &str, handshake_secs: Option<u64>) -> String {
+    match handshake_secs {
+        Some(secs) => format!("{} degraded: last handshake {} ago", name, crate::network::stats::format_uptime(std::time::Duration::from_secs(secs))),
+        None => format!("{} degraded: no handshake yet", name),
+    }
+}
+
+// Status line while reconnecting
+pub fn status_text(name: &str, attempt: u32, max_attempts: u32) -> String {
+    if attempt == 0 {
+        format!("Connection to {} lost, reconnecting...", name)
+    } else {
+        format!("Reconnecting to {} (attempt {}/{})...", name, attempt, max_attempts)
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+// Attempts including the first one
+pub const MAX_ATTEMPTS: u32 = 4;
+
+const BASE_DELAY_SECS: u64 = 5;
+const MAX_DELAY_SECS: u64 = 60;
+
+// Error text that means "try again later" rather than "this will never work"
+const TRANSIENT_PATTERNS: &[&str] = &[
+    "network is unreachable",
+    "network unreachable",
+    "no route to host",
+    "host is unreachable",
+    "temporary failure in name resolution",
+    "failed to lookup address",
+    "dns",
+    "timed out",
+    "connection reset",
+    "os error 101",   // ENETUNREACH
+    "os error 113",   // EHOSTUNREACH
+    "os error 10051", // WSAENETUNREACH
+    "os error 10065", // WSAEHOSTUNREACH
+];
+
+pub fn is_transient(error: &str) -> bool {
+    let error = error.to_lowercase();
+    TRANSIENT_PATTERNS.iter().any(|pattern| error.contains(pattern))
+}
+
+// Exponential backoff after the given failed attempt: 5s, 10s, 20s, ... capped at a minute
+pub fn backoff_delay(attempt: u32) -> Duration {
+    backoff(attempt, BASE_DELAY_SECS, MAX_DELAY_SECS)
+}
+
+// The same doubling from a given base up to a given cap
+pub fn backoff(attempt: u32, base_secs: u64, max_secs: u64) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(16);
+    Duration::from_secs(base_secs.saturating_mul(factor).min(max_secs))
+}
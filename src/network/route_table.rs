@@ -0,0 +1,391 @@
+use crate::config::VpnConfig;
+use crate::network::tunnel;
+use anyhow::Result;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+use tokio::process::Command;
+
+// The system routing table for the diagnostics window, read from `ip route` on Linux,
+// `netstat -rn` on macOS and `route print` on Windows. Routes out of the connected tunnel's
+// interface are marked, which shows at a glance whether a split tunnel took and what a full
+// tunnel left going around it.
+
+// Time for routes pushed by the server to arrive after the status changed
+const SETTLE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub struct RouteEntry {
+    // Network in CIDR form, or "default"
+    pub destination: String,
+    // Next hop; None for routes on the link
+    pub gateway: Option<String>,
+    // Device name on Linux and macOS; the interface address (IPv4) or index (IPv6) on Windows
+    pub interface: String,
+    pub metric: Option<u32>,
+    // Linux routing table other than main, e.g. 51820 where wg-quick puts its default route
+    pub table: Option<String>,
+    // Route type other than unicast, e.g. unreachable or blackhole
+    pub kind: Option<String>,
+}
+
+impl RouteEntry {
+    // Whether the route leaves through one of the tunnel's interfaces
+    pub fn uses(&self, tunnel: &[String]) -> bool {
+        !self.interface.is_empty() && tunnel.iter().any(|key| key.eq_ignore_ascii_case(&self.interface))
+    }
+}
+
+// The routes read at one point, and what the tunnel's interface goes by in them
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub routes: Vec<RouteEntry>,
+    pub vpn: Option<String>,
+    pub tunnel: Vec<String>,
+    pub taken: chrono::DateTime<chrono::Local>,
+}
+
+impl Snapshot {
+    pub fn through_tunnel(&self) -> usize {
+        self.routes.iter().filter(|route| route.uses(&self.tunnel)).count()
+    }
+}
+
+pub async fn read() -> Result<Vec<RouteEntry>> {
+    #[cfg(windows)]
+    {
+        read_windows().await
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        read_macos().await
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        read_linux().await
+    }
+}
+
+// The names, indices and addresses the tunnel's interfaces appear under in the table
+pub async fn tunnel_interfaces(config: &VpnConfig) -> Vec<String> {
+    let mut keys = Vec::new();
+    for adapter in tunnel::adapters(config).await {
+        keys.push(adapter.name.clone());
+        keys.push(adapter.index.to_string());
+        keys.extend(adapter.addr.iter().map(|addr| addr.ip().to_string()));
+    }
+    keys.dedup();
+    keys
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+async fn read_linux() -> Result<Vec<RouteEntry>> {
+    let mut routes = Vec::new();
+    for family in ["-4", "-6"] {
+        let output = Command::new("ip").args([family, "route", "show", "table", "all"]).output().await?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("ip route failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        routes.extend(String::from_utf8_lossy(&output.stdout).lines().filter_map(parse_ip_route));
+    }
+    Ok(routes)
+}
+
+// default via 192.168.1.1 dev wlp3s0 proto dhcp src 192.168.1.5 metric 600
+// unreachable 10.0.0.0/8 table 51820 metric 1024
+#[cfg(all(unix, not(target_os = "macos")))]
+fn parse_ip_route(line: &str) -> Option<RouteEntry> {
+    const KINDS: &[&str] = &["unicast", "unreachable", "blackhole", "prohibit", "throw"];
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let first = *words.first()?;
+    // Broadcast, local and multicast routes live in the local table and only add noise
+    if ["local", "broadcast", "multicast", "anycast"].contains(&first) {
+        return None;
+    }
+    let kind = KINDS.contains(&first).then(|| words.remove(0)).filter(|kind| *kind != "unicast");
+    let destination = words.first()?.to_string();
+    let after = |key: &str| words.iter().position(|w| *w == key).and_then(|i| words.get(i + 1)).map(|w| w.to_string());
+    let table = after("table").filter(|table| table != "main");
+    if table.as_deref() == Some("local") {
+        return None;
+    }
+
+    Some(RouteEntry {
+        destination,
+        gateway: after("via"),
+        interface: after("dev").unwrap_or_default(),
+        metric: after("metric").and_then(|metric| metric.parse().ok()),
+        table,
+        kind: kind.map(|kind| kind.to_string()),
+    })
+}
+
+#[cfg(target_os = "macos")]
+async fn read_macos() -> Result<Vec<RouteEntry>> {
+    let output = Command::new("netstat").args(["-rn"]).output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("netstat failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    // Destination  Gateway  Flags  Netif  Expire, once per address family
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let routes = output_str
+        .lines()
+        .filter_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() < 4 || words[0] == "Destination" {
+                return None;
+            }
+            // link#4 and MAC addresses are the link itself, not a next hop
+            let gateway = words[1];
+            let on_link = gateway.starts_with("link#") || gateway.matches(':').count() == 5;
+            Some(RouteEntry {
+                destination: words[0].to_string(),
+                gateway: (!on_link).then(|| gateway.to_string()),
+                interface: words[3].to_string(),
+                metric: None,
+                table: None,
+                kind: words[2].contains('B').then(|| "blackhole".to_string()),
+            })
+        })
+        .collect();
+    Ok(routes)
+}
+
+#[cfg(windows)]
+async fn read_windows() -> Result<Vec<RouteEntry>> {
+    let mut cmd = Command::new("route");
+    cmd.arg("print")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("route print failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(parse_route_print(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// The "Active Routes:" lists of both tables. IPv4 rows are destination, netmask, gateway,
+// interface address and metric; IPv6 rows are interface index, metric, destination and gateway,
+// the gateway wrapping onto a line of its own after a long destination.
+#[cfg(windows)]
+fn parse_route_print(output: &str) -> Vec<RouteEntry> {
+    let on_link = |gateway: &str| (!gateway.eq_ignore_ascii_case("On-link")).then(|| gateway.to_string());
+    let mut routes = Vec::new();
+    let mut ipv6 = false;
+    let mut active = false;
+    let mut lines = output.lines();
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("IPv4 Route Table") || trimmed.starts_with("IPv6 Route Table") {
+            ipv6 = trimmed.starts_with("IPv6");
+            active = false;
+            continue;
+        }
+        if trimmed.starts_with("Active Routes:") {
+            active = true;
+            continue;
+        }
+        if trimmed.starts_with("===") || trimmed.starts_with("Persistent Routes:") {
+            active = false;
+            continue;
+        }
+        if !active {
+            continue;
+        }
+
+        let words: Vec<&str> = trimmed.split_whitespace().collect();
+        if ipv6 {
+            let (Some(index), Some(metric), Some(destination)) = (words.first(), words.get(1), words.get(2)) else { continue };
+            let Ok(metric) = metric.parse::<u32>() else { continue };
+            let gateway = match words.get(3) {
+                Some(gateway) => gateway.to_string(),
+                None => lines.next().map(|next| next.trim().to_string()).unwrap_or_default(),
+            };
+            routes.push(RouteEntry {
+                destination: destination.to_string(),
+                gateway: on_link(&gateway),
+                interface: index.to_string(),
+                metric: Some(metric),
+                table: None,
+                kind: None,
+            });
+        } else if let [destination, netmask, gateway, interface, metric] = words[..] {
+            let Ok(metric) = metric.parse::<u32>() else { continue };
+            let Ok(mask) = netmask.parse::<std::net::Ipv4Addr>() else { continue };
+            let prefix = u32::from(mask).count_ones();
+            let destination = if destination == "0.0.0.0" && prefix == 0 {
+                "default".to_string()
+            } else {
+                format!("{}/{}", destination, prefix)
+            };
+            routes.push(RouteEntry {
+                destination,
+                gateway: on_link(gateway),
+                interface: interface.to_string(),
+                metric: Some(metric),
+                table: None,
+                kind: None,
+            });
+        }
+    }
+    routes
+}
+
+// Keeps the table read while the viewer is open, reading it again when the connected VPN changes
+#[derive(Default)]
+pub struct RouteTableWatch {
+    pub current: Option<Result<Snapshot, String>>,
+    // The VPN connected at the last read; None until the first
+    seen_for: Option<Option<String>>,
+    receiver: Option<Receiver<Result<Snapshot, String>>>,
+}
+
+impl RouteTableWatch {
+    pub fn is_loading(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    pub fn sync(&mut self, connected: Option<&VpnConfig>) {
+        let name = connected.map(|config| config.name.clone());
+        match &self.seen_for {
+            Some(seen) if *seen == name => {}
+            Some(_) => {
+                self.seen_for = Some(name);
+                self.start(connected.cloned(), SETTLE);
+            }
+            None => {
+                self.seen_for = Some(name);
+                self.start(connected.cloned(), Duration::ZERO);
+            }
+        }
+    }
+
+    pub fn refresh(&mut self, connected: Option<&VpnConfig>) {
+        self.seen_for = Some(connected.map(|config| config.name.clone()));
+        self.start(connected.cloned(), Duration::ZERO);
+    }
+
+    fn start(&mut self, connected: Option<VpnConfig>, delay: Duration) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.receiver = Some(rx);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(async {
+                tokio::time::sleep(delay).await;
+                let routes = read().await?;
+                let tunnel = match &connected {
+                    Some(config) => tunnel_interfaces(config).await,
+                    None => Vec::new(),
+                };
+                Ok::<_, anyhow::Error>(Snapshot {
+                    routes,
+                    vpn: connected.map(|config| config.name),
+                    tunnel,
+                    taken: chrono::Local::now(),
+                })
+            });
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else { return };
+        match receiver.try_recv() {
+            Ok(result) => {
+                self.current = Some(result);
+                self.receiver = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.receiver = None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn reads_ip_route_lines() {
+        let route = parse_ip_route("default via 192.168.1.1 dev wlp3s0 proto dhcp src 192.168.1.5 metric 600").unwrap();
+        assert_eq!(route.destination, "default");
+        assert_eq!(route.gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(route.interface, "wlp3s0");
+        assert_eq!(route.metric, Some(600));
+        assert_eq!((route.table, route.kind), (None, None));
+
+        let route = parse_ip_route("10.8.0.0/24 dev tun0 proto kernel scope link src 10.8.0.2").unwrap();
+        assert_eq!((route.destination.as_str(), route.gateway, route.interface.as_str()), ("10.8.0.0/24", None, "tun0"));
+
+        let route = parse_ip_route("unreachable 10.0.0.0/8 table 51820 metric 1024").unwrap();
+        assert_eq!(route.destination, "10.0.0.0/8");
+        assert_eq!(route.table.as_deref(), Some("51820"));
+        assert_eq!(route.kind.as_deref(), Some("unreachable"));
+        assert_eq!(route.interface, "");
+
+        let route = parse_ip_route("unicast 192.0.2.0/24 via 10.8.0.1 dev tun0 table main").unwrap();
+        assert_eq!((route.table, route.kind), (None, None));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn skips_local_routes() {
+        assert!(parse_ip_route("local 127.0.0.1 dev lo table local proto kernel scope host src 127.0.0.1").is_none());
+        assert!(parse_ip_route("broadcast 192.168.1.255 dev wlp3s0 table local proto kernel scope link").is_none());
+        assert!(parse_ip_route("fe80::/64 dev eth0 table local").is_none());
+        assert!(parse_ip_route("").is_none());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn reads_both_route_print_tables() {
+        let output = "\
+===========================================================================
+Interface List
+ 12...00 15 5d 01 02 03 ......Hyper-V Virtual Ethernet Adapter
+===========================================================================
+
+IPv4 Route Table
+===========================================================================
+Active Routes:
+Network Destination        Netmask          Gateway       Interface  Metric
+          0.0.0.0          0.0.0.0      192.168.1.1    192.168.1.20     25
+         10.8.0.0    255.255.255.0         On-link          10.8.0.2    257
+===========================================================================
+Persistent Routes:
+  Network Address          Netmask  Gateway Address  Metric
+         10.1.0.0      255.255.0.0      192.168.1.1       1
+===========================================================================
+
+IPv6 Route Table
+===========================================================================
+Active Routes:
+ If Metric Network Destination      Gateway
+  1    331 ::1/128                  On-link
+ 14    281 fd00:1234:5678:9abc:def0:1234:5678:9abc/128
+                                    fe80::1
+===========================================================================
+";
+        let routes = parse_route_print(output);
+        let summary: Vec<(&str, Option<&str>, &str, Option<u32>)> = routes.iter()
+            .map(|r| (r.destination.as_str(), r.gateway.as_deref(), r.interface.as_str(), r.metric))
+            .collect();
+        assert_eq!(summary, vec![
+            ("default", Some("192.168.1.1"), "192.168.1.20", Some(25)),
+            ("10.8.0.0/24", None, "10.8.0.2", Some(257)),
+            ("::1/128", None, "1", Some(331)),
+            ("fd00:1234:5678:9abc:def0:1234:5678:9abc/128", Some("fe80::1"), "14", Some(281)),
+        ]);
+    }
+}
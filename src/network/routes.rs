@@ -0,0 +1,343 @@
+use crate::config::VpnConfig;
+use crate::network::tunnel;
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use tokio::process::Command;
+
+// Split tunneling: extra routes added after a VPN connects, sending chosen networks through the
+// tunnel or around it via the regular gateway, and removed again on disconnect
+
+// What was actually added per entry, so disconnect removes exactly that even if the entry's
+// routes were edited in the meantime
+static APPLIED: Mutex<Vec<AppliedRoute>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone)]
+struct AppliedRoute {
+    vpn: String,
+    cidr: String,
+    // Device name on Linux, interface index on Windows
+    interface: String,
+}
+
+// The default route before the tunnel comes up, which bypass routes keep using
+#[derive(Debug, Clone)]
+pub struct Gateway {
+    pub address: IpAddr,
+    pub interface: String,
+}
+
+// Where the system would send packets for one address: the interface (index on Windows) and the
+// next hop, None when the address is on the link
+#[derive(Debug, Clone)]
+pub struct RouteLookup {
+    pub interface: String,
+    pub via: Option<IpAddr>,
+}
+
+pub async fn route_to(address: IpAddr) -> Result<RouteLookup> {
+    #[cfg(windows)]
+    {
+        route_to_windows(address).await
+    }
+
+    #[cfg(unix)]
+    {
+        route_to_unix(address).await
+    }
+}
+
+#[cfg(unix)]
+async fn route_to_unix(address: IpAddr) -> Result<RouteLookup> {
+    // 10.20.0.5 via 10.8.0.1 dev tun0 src 10.8.0.6 uid 1000
+    let output = Command::new("ip").args(["route", "get", &address.to_string()]).output().await?;
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let words: Vec<&str> = output_str.split_whitespace().collect();
+    let after = |key: &str| words.iter().position(|w| *w == key).and_then(|i| words.get(i + 1));
+
+    match after("dev") {
+        Some(interface) => Ok(RouteLookup {
+            interface: interface.to_string(),
+            via: after("via").and_then(|via| via.parse().ok()),
+        }),
+        None => Err(anyhow::anyhow!("No route to {}", address)),
+    }
+}
+
+#[cfg(windows)]
+async fn route_to_windows(address: IpAddr) -> Result<RouteLookup> {
+    // Find-NetRoute returns the source address and the route; only the route has a prefix
+    let script = format!(
+        "Find-NetRoute -RemoteIPAddress {} | Where-Object {{ $_.DestinationPrefix }} | Select-Object -First 1 | \
+         ForEach-Object {{ \"$($_.InterfaceIndex) $($_.NextHop)\" }}",
+        address
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(&["-NoProfile", "-Command", &script])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().await?;
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let mut parts = output_str.split_whitespace();
+
+    match (parts.next(), parts.next().and_then(|hop| hop.parse::<IpAddr>().ok())) {
+        (Some(index), next_hop) => Ok(RouteLookup {
+            interface: index.to_string(),
+            // On-link routes have an unspecified next hop
+            via: next_hop.filter(|hop| !hop.is_unspecified()),
+        }),
+        _ => Err(anyhow::anyhow!("No route to {}", address)),
+    }
+}
+
+// Parses `address/prefix` into the network address and prefix, clearing host bits as the route
+// tools insist; a bare address is taken as a single host
+pub fn parse_cidr(text: &str) -> Option<(IpAddr, u8)> {
+    let text = text.trim();
+    let (address, prefix) = match text.split_once('/') {
+        Some((address, prefix)) => (address.parse::<IpAddr>().ok()?, prefix.parse::<u8>().ok()?),
+        None => {
+            let address = text.parse::<IpAddr>().ok()?;
+            (address, if address.is_ipv4() { 32 } else { 128 })
+        }
+    };
+
+    let network = match address {
+        IpAddr::V4(ip) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            IpAddr::V4((u32::from(ip) & mask).into())
+        }
+        IpAddr::V6(ip) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            IpAddr::V6((u128::from(ip) & mask).into())
+        }
+        _ => return None,
+    };
+    Some((network, prefix))
+}
+
+pub fn needs_gateway(config: &VpnConfig) -> bool {
+    config.split_routes.iter().any(|route| !route.via_tunnel)
+}
+
+pub async fn default_gateway() -> Result<Gateway> {
+    #[cfg(windows)]
+    {
+        default_gateway_windows().await
+    }
+
+    #[cfg(unix)]
+    {
+        default_gateway_unix().await
+    }
+}
+
+#[cfg(unix)]
+pub async fn default_gateway_unix() -> Result<Gateway> {
+    // default via 192.168.1.1 dev wlp3s0 proto dhcp metric 600
+    let output = Command::new("ip").args(["route", "show", "default"]).output().await?;
+    let output_str = String::from_utf8_lossy(&output.stdout);
+
+    output_str
+        .lines()
+        .find_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let after = |key: &str| words.iter().position(|w| *w == key).and_then(|i| words.get(i + 1));
+            Some(Gateway {
+                address: after("via")?.parse().ok()?,
+                interface: after("dev")?.to_string(),
+            })
+        })
+        .ok_or_else(|| anyhow::anyhow!("No default gateway found"))
+}
+
+#[cfg(windows)]
+pub async fn default_gateway_windows() -> Result<Gateway> {
+    let script = "Get-NetRoute -DestinationPrefix 0.0.0.0/0 | Sort-Object RouteMetric | Select-Object -First 1 | \
+                  ForEach-Object { \"$($_.NextHop) $($_.InterfaceIndex)\" }";
+    let mut cmd = Command::new("powershell");
+    cmd.args(&["-NoProfile", "-Command", script])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().await?;
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let mut parts = output_str.split_whitespace();
+
+    match (parts.next().and_then(|a| a.parse().ok()), parts.next()) {
+        (Some(address), Some(index)) => Ok(Gateway { address, interface: index.to_string() }),
+        _ => Err(anyhow::anyhow!("No default gateway found")),
+    }
+}
+
+// Adds the entry's routes; returns an error listing the ones that failed, the rest stay applied
+pub async fn apply(config: &VpnConfig, gateway: Option<&Gateway>) -> Result<()> {
+    if config.split_routes.is_empty() {
+        return Ok(());
+    }
+
+    let tunnel_interface = tunnel::adapters(config).await.into_iter().next().map(|iface| {
+        if cfg!(windows) { iface.index.to_string() } else { iface.name }
+    });
+
+    let mut failures = Vec::new();
+    for route in &config.split_routes {
+        let Some((address, prefix)) = parse_cidr(&route.cidr) else {
+            failures.push(format!("{}: not a valid network", route.cidr));
+            continue;
+        };
+        let cidr = format!("{}/{}", address, prefix);
+
+        let (interface, next_hop) = if route.via_tunnel {
+            match &tunnel_interface {
+                Some(interface) => (interface.clone(), None),
+                None => {
+                    failures.push(format!("{}: tunnel interface not found", cidr));
+                    continue;
+                }
+            }
+        } else {
+            match gateway {
+                Some(gateway) if gateway.address.is_ipv4() == address.is_ipv4() => {
+                    (gateway.interface.clone(), Some(gateway.address))
+                }
+                Some(_) => {
+                    failures.push(format!("{}: no default gateway for this address family", cidr));
+                    continue;
+                }
+                None => {
+                    failures.push(format!("{}: no default gateway", cidr));
+                    continue;
+                }
+            }
+        };
+
+        match add_route(config, &cidr, &interface, next_hop).await {
+            Ok(()) => APPLIED.lock().unwrap().push(AppliedRoute {
+                vpn: config.name.clone(),
+                cidr,
+                interface,
+            }),
+            Err(e) => failures.push(format!("{}: {}", cidr, e)),
+        }
+    }
+
+    if !failures.is_empty() {
+        return Err(anyhow::anyhow!("Split tunnel routes failed: {}", failures.join("; ")));
+    }
+    Ok(())
+}
+
+// Removes what apply() added for this entry. Routes through the tunnel usually vanish with its
+// interface, so failures are only logged.
+pub async fn remove(config: &VpnConfig) {
+    let routes: Vec<AppliedRoute> = {
+        let mut applied = APPLIED.lock().unwrap();
+        let (mine, others) = applied.drain(..).partition(|route| route.vpn == config.name);
+        *applied = others;
+        mine
+    };
+
+    for route in routes {
+        if let Err(e) = delete_route(config, &route.cidr, &route.interface).await {
+            log::debug!("Route {} already gone: {}", route.cidr, e);
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn add_route(config: &VpnConfig, cidr: &str, interface: &str, next_hop: Option<IpAddr>) -> Result<()> {
+    // replace instead of add, so a leftover route from a crashed session doesn't fail the connect
+    let mut args = vec!["ip".to_string(), "route".to_string(), "replace".to_string(), cidr.to_string()];
+    if let Some(next_hop) = next_hop {
+        args.extend(["via".to_string(), next_hop.to_string()]);
+    }
+    args.extend(["dev".to_string(), interface.to_string()]);
+
+    run(config, "sudo", &args).await
+}
+
+#[cfg(unix)]
+async fn delete_route(config: &VpnConfig, cidr: &str, interface: &str) -> Result<()> {
+    let args = ["ip", "route", "del", cidr, "dev", interface].map(String::from);
+    run(config, "sudo", &args).await
+}
+
+#[cfg(windows)]
+async fn add_route(config: &VpnConfig, cidr: &str, interface: &str, next_hop: Option<IpAddr>) -> Result<()> {
+    let family = if cidr.contains(':') { "ipv6" } else { "ipv4" };
+    let mut args = vec![
+        "interface".to_string(),
+        family.to_string(),
+        "add".to_string(),
+        "route".to_string(),
+        format!("prefix={}", cidr),
+        format!("interface={}", interface),
+    ];
+    if let Some(next_hop) = next_hop {
+        args.push(format!("nexthop={}", next_hop));
+    }
+    // Not persisted across reboots
+    args.push("store=active".to_string());
+
+    run(config, "netsh", &args).await
+}
+
+#[cfg(windows)]
+async fn delete_route(config: &VpnConfig, cidr: &str, interface: &str) -> Result<()> {
+    let family = if cidr.contains(':') { "ipv6" } else { "ipv4" };
+    let args = vec![
+        "interface".to_string(),
+        family.to_string(),
+        "delete".to_string(),
+        "route".to_string(),
+        format!("prefix={}", cidr),
+        format!("interface={}", interface),
+        "store=active".to_string(),
+    ];
+    run(config, "netsh", &args).await
+}
+
+async fn run(config: &VpnConfig, program: &str, args: &[String]) -> Result<()> {
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, &format!("{} {}", program, args.join(" ")), &output);
+
+    if !output.status.success() {
+        // netsh reports errors on stdout
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = if stderr.trim().is_empty() {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        } else {
+            stderr.trim().to_string()
+        };
+        return Err(anyhow::anyhow!(message));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,215 @@
+use crate::config::SshConfig;
+use crate::errors::{self, ErrorCode};
+use anyhow::Result;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+#[cfg(unix)]
+use crate::system::session::DisplaySession;
+
+// Interactive ssh sessions opened in a terminal window; the app doesn't follow them afterwards.
+// The terminal is the one set in Settings, else one found: $TERMINAL or a common emulator on
+// Linux, Windows Terminal or else a console window on Windows. PuTTY is no terminal for an ssh
+// command line but a client of its own, so it gets the target as its own options instead.
+
+// How long a woken device gets to boot and start sshd
+pub const WAKE_TIMEOUT: Duration = Duration::from_secs(120);
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+pub fn ssh_args(config: &SshConfig) -> Vec<String> {
+    let mut args = Vec::new();
+    if config.port != 22 {
+        args.extend(["-p".to_string(), config.port.to_string()]);
+    }
+    if !config.key_path.is_empty() {
+        args.extend(["-i".to_string(), config.key_path.clone()]);
+    }
+    args.push("--".to_string());
+    args.push(target(config));
+    args
+}
+
+// A host or user starting with - would be read as an option (-oProxyCommand=...), and terminals
+// and PuTTY may split the command line at whitespace
+pub fn check_target(config: &SshConfig) -> Result<()> {
+    for (what, value) in [("host", &config.host), ("username", &config.username)] {
+        if value.starts_with('-') || value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(anyhow::anyhow!(
+                "The SSH {} {:?} can't start with - or contain spaces or control characters",
+                what,
+                value
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn target(config: &SshConfig) -> String {
+    if config.username.is_empty() {
+        config.host.clone()
+    } else {
+        format!("{}@{}", config.username, config.host)
+    }
+}
+
+// PuTTY's -i only takes keys converted to .ppk
+pub fn putty_args(config: &SshConfig) -> Vec<String> {
+    let mut args = vec!["-ssh".to_string(), "-P".to_string(), config.port.to_string()];
+    if !config.key_path.is_empty() {
+        args.extend(["-i".to_string(), config.key_path.clone()]);
+    }
+    args.push(target(config));
+    args
+}
+
+#[cfg(windows)]
+fn is_windows_terminal(program: &str) -> bool {
+    Path::new(program.trim())
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case("wt"))
+}
+
+pub fn is_putty(program: &str) -> bool {
+    Path::new(program.trim())
+        .file_stem()
+        .is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case("putty"))
+}
+
+// Terminal emulators tried in order, and the argument after which each takes the command to run
+#[cfg(unix)]
+const TERMINALS: &[(&str, &str)] = &[
+    ("x-terminal-emulator", "-e"),
+    ("gnome-terminal", "--"),
+    ("konsole", "-e"),
+    ("xfce4-terminal", "-x"),
+    ("alacritty", "-e"),
+    ("xterm", "-e"),
+];
+
+// Terminal emulator and the arguments after which it takes the command to run; the preferred
+// one, then $TERMINAL, then the first of TERMINALS installed
+#[cfg(unix)]
+pub fn terminal(preferred: &str) -> Option<(String, Vec<String>)> {
+    let flag = |program: &str| {
+        let name = Path::new(program).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        TERMINALS.iter().find(|(binary, _)| *binary == name).map_or("-e", |(_, flag)| flag).to_string()
+    };
+    let from_env = std::env::var("TERMINAL").unwrap_or_default();
+    let found = [preferred.trim(), from_env.trim()]
+        .into_iter()
+        .chain(TERMINALS.iter().map(|(binary, _)| *binary))
+        .find(|program| !program.is_empty() && which::which(program).is_ok());
+    found.map(|program| (program.to_string(), vec![flag(program)]))
+}
+
+// The program to start and its arguments, for the session and its command preview
+pub fn command_line(config: &SshConfig, preferred: &str) -> Result<(String, Vec<String>)> {
+    check_target(config)?;
+    let preferred = preferred.trim();
+    if !preferred.is_empty() && which::which(preferred).is_err() {
+        return Err(errors::error(ErrorCode::TerminalNotFound, preferred));
+    }
+    if is_putty(preferred) {
+        return Ok((preferred.to_string(), putty_args(config)));
+    }
+    if which::which("ssh").is_err() {
+        return Err(errors::error(ErrorCode::SshClientMissing, ""));
+    }
+    
+    #[cfg(windows)]
+    {
+        let terminal = if preferred.is_empty() && which::which("wt").is_ok() { "wt" } else { preferred };
+        if terminal.is_empty() {
+            // Started in a console window of its own, see connect
+            return Ok(("ssh".to_string(), ssh_args(config)));
+        }
+        if is_windows_terminal(terminal) {
+            // wt takes everything after -- as the command, but still splits tabs at ;
+            let mut args = vec!["--".to_string(), "ssh".to_string()];
+            args.extend(ssh_args(config).iter().map(|arg| arg.replace(';', "\\;")));
+            return Ok((terminal.to_string(), args));
+        }
+        let mut args = vec!["ssh".to_string()];
+        args.extend(ssh_args(config));
+        Ok((terminal.to_string(), args))
+    }
+    
+    #[cfg(unix)]
+    {
+        let (terminal, mut args) = terminal(preferred).ok_or_else(|| errors::error(ErrorCode::TerminalMissing, ""))?;
+        args.push("ssh".to_string());
+        args.extend(ssh_args(config));
+        Ok((terminal, args))
+    }
+}
+
+pub fn connect(config: &SshConfig, terminal: &str) -> Result<()> {
+    if config.host.trim().is_empty() {
+        return Err(anyhow::anyhow!("{} has no SSH host", config.name));
+    }
+    log::info!("Opening SSH session to {}:{} for {}", config.host, config.port, config.name);
+    
+    #[cfg(unix)]
+    DisplaySession::detect().require_display("the SSH terminal")?;
+    
+    let (program, args) = command_line(config, terminal)?;
+    let mut cmd = std::process::Command::new(&program);
+    cmd.args(args);
+    
+    #[cfg(windows)]
+    if program == "ssh" {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x00000010); // CREATE_NEW_CONSOLE
+    }
+    
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    cmd.spawn()?;
+    Ok(())
+}
+
+// Whether the session's port accepts connections within the timeout, tried every few seconds;
+// with a zero timeout, a single try
+pub async fn wait_for_port(config: &SshConfig, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let connect = tokio::net::TcpStream::connect((config.host.as_str(), config.port));
+        if let Ok(Ok(_)) = tokio::time::timeout(POLL_INTERVAL, connect).await {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ssh_config(host: &str, username: &str) -> SshConfig {
+        SshConfig {
+            name: "nas".to_string(),
+            host: host.to_string(),
+            port: 22,
+            username: username.to_string(),
+            key_path: String::new(),
+            wake_first: false,
+        }
+    }
+
+    #[test]
+    fn target_comes_after_the_options() {
+        assert_eq!(ssh_args(&ssh_config("nas.lan", "admin")), ["--", "admin@nas.lan"]);
+    }
+
+    #[test]
+    fn rejects_targets_read_as_options() {
+        assert!(check_target(&ssh_config("nas.lan", "admin")).is_ok());
+        assert!(check_target(&ssh_config("-oProxyCommand=touch pwned", "")).is_err());
+        assert!(check_target(&ssh_config("nas.lan", "-oProxyCommand=id")).is_err());
+        assert!(check_target(&ssh_config("nas.lan extra", "")).is_err());
+        assert!(check_target(&ssh_config("nas.lan", "admin\n")).is_err());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::config::VpnConfig;
+use crate::network::captive::{check_captive_portal, CaptivePortalStatus};
+use crate::network::{NetworkManager, VpnStatus};
+use std::sync::mpsc::{Receiver, TryRecvError};
+
+// What the app used to wait for before its first frame: finding the VPN that is already up, the
+// captive portal check and the auto-connect. A background thread does them in that order and
+// reports each; the status reads VpnStatus::Checking until the first report. After logging in to
+// a captive portal, the check and the auto-connect run again the same way.
+
+#[derive(Debug)]
+pub enum StartupEvent {
+    // The status found, and again after an auto-connect
+    Status(VpnStatus),
+    // A captive portal stands in the way, auto-connect waits for the login
+    CaptivePortal(String),
+    // The profile that came up, or why none did
+    AutoConnected { requested: String, result: anyhow::Result<String> },
+}
+
+// What to auto-connect: the profile asked for, then the rest of its failover chain
+pub struct AutoConnect {
+    pub requested: String,
+    pub chain: Vec<VpnConfig>,
+    pub check_captive_portal: bool,
+}
+
+pub struct StartupTask {
+    receiver: Receiver<StartupEvent>,
+}
+
+impl StartupTask {
+    pub fn start(network_manager: &NetworkManager, vpn_configs: Vec<VpnConfig>, auto_connect: Option<AutoConnect>) -> Self {
+        Self::spawn(network_manager, Some(vpn_configs), auto_connect)
+    }
+
+    // The captive portal check, if asked for, and the auto-connect alone
+    pub fn auto_connect(network_manager: &NetworkManager, auto_connect: AutoConnect) -> Self {
+        Self::spawn(network_manager, None, Some(auto_connect))
+    }
+
+    fn spawn(network_manager: &NetworkManager, vpn_configs: Option<Vec<VpnConfig>>, auto_connect: Option<AutoConnect>) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut network_manager = network_manager.clone();
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                if let Some(vpn_configs) = vpn_configs {
+                    // Devices stay with the app's copy
+                    network_manager.vpn_status = VpnStatus::Disconnected;
+                    let _ = network_manager.initialize(&vpn_configs, &[]).await;
+                    if tx.send(StartupEvent::Status(network_manager.vpn_status.clone())).is_err() {
+                        return;
+                    }
+                }
+
+                let Some(auto_connect) = auto_connect else { return };
+                if network_manager.vpn_status.connected_name() == Some(auto_connect.requested.as_str()) {
+                    return;
+                }
+                if auto_connect.check_captive_portal {
+                    match check_captive_portal().await {
+                        Ok(CaptivePortalStatus::Detected(url)) => {
+                            log::info!("Captive portal detected, postponing VPN auto-connect");
+                            let _ = tx.send(StartupEvent::CaptivePortal(url));
+                            return;
+                        }
+                        Ok(CaptivePortalStatus::Unreachable(e)) => log::warn!("Captive portal check could not reach the internet: {}", e),
+                        Ok(CaptivePortalStatus::Clear) => {}
+                        Err(e) => log::warn!("Captive portal check failed: {}", e),
+                    }
+                }
+
+                log::info!("Auto-connecting to {}...", auto_connect.requested);
+                if tx.send(StartupEvent::Status(VpnStatus::Connecting)).is_err() {
+                    return;
+                }
+                let chain: Vec<&VpnConfig> = auto_connect.chain.iter().collect();
+                let result = network_manager.connect_first(&chain).await;
+                let _ = tx.send(StartupEvent::Status(network_manager.vpn_status.clone()));
+                let _ = tx.send(StartupEvent::AutoConnected { requested: auto_connect.requested, result });
+            });
+        });
+
+        Self { receiver: rx }
+    }
+
+    // The next report; Disconnected once everything was reported
+    pub fn poll(&self) -> Result<StartupEvent, TryRecvError> {
+        self.receiver.try_recv()
+    }
+}
@@ -0,0 +1,288 @@
+use crate::config::{VpnConfig, VpnType};
+use crate::errors::{self, ErrorCode};
+use crate::network::health::{self, TunnelHealth};
+use crate::network::{tunnel, vpn, wireguard};
+use crate::system::client_log::{self, LogLevel};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// Traffic counters for the connected VPN, sampled once a second in the background. WireGuard
+// reports per-peer transfer through `wg` and OpenVPN through its management interface; the other
+// clients are read from the tunnel interface's counters, which start at zero when the client
+// creates the interface. The tunnel health ping runs alongside, at its own interval.
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+// Two minutes of history for the graph
+const MAX_SAMPLES: usize = 120;
+// OpenVPN session details are read every this many samples
+const SESSION_EVERY: u32 = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub at: Instant,
+    pub received: u64,
+    pub sent: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrafficStats {
+    pub vpn: String,
+    // When the connection was first seen up
+    pub since: Instant,
+    samples: VecDeque<Sample>,
+    // Why the last read failed, e.g. no tunnel interface found
+    pub error: Option<String>,
+    // Addresses and cipher, for OpenVPN
+    pub session: Option<vpn::SessionInfo>,
+    // Pings through the tunnel, when the entry has a health check
+    pub health: Option<TunnelHealth>,
+}
+
+impl TrafficStats {
+    fn new(config: &VpnConfig) -> Self {
+        let check = &config.health;
+        Self {
+            vpn: config.name.clone(),
+            since: Instant::now(),
+            samples: VecDeque::new(),
+            error: None,
+            session: None,
+            health: (check.enabled && !check.host.is_empty()).then(|| TunnelHealth::new(&check.host)),
+        }
+    }
+
+    fn push(&mut self, sample: Sample) {
+        // Counters going backwards mean the interface was recreated; the old rates mean nothing
+        if self.samples.back().is_some_and(|last| sample.received < last.received || sample.sent < last.sent) {
+            self.samples.clear();
+        }
+        self.samples.push_back(sample);
+        while self.samples.len() > MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn uptime(&self) -> Duration {
+        self.since.elapsed()
+    }
+
+    // Bytes received and sent since the tunnel came up
+    pub fn totals(&self) -> Option<(u64, u64)> {
+        self.samples.back().map(|s| (s.received, s.sent))
+    }
+
+    // Bytes per second received and sent between each pair of samples, oldest first
+    pub fn rates(&self) -> Vec<(f64, f64)> {
+        self.samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(a, b)| {
+                let secs = b.at.duration_since(a.at).as_secs_f64().max(0.001);
+                ((b.received - a.received) as f64 / secs, (b.sent - a.sent) as f64 / secs)
+            })
+            .collect()
+    }
+
+    pub fn throughput(&self) -> Option<(f64, f64)> {
+        self.rates().last().copied()
+    }
+
+    // How long neither counter has moved, within the history kept; zero while traffic flows
+    pub fn idle_for(&self) -> Duration {
+        let Some(last) = self.samples.back() else { return Duration::ZERO };
+        self.samples
+            .iter()
+            .rev()
+            .take_while(|s| s.received == last.received && s.sent == last.sent)
+            .last()
+            .map_or(Duration::ZERO, |first| last.at.duration_since(first.at))
+    }
+}
+
+enum Update {
+    Counters(Result<(u64, u64), String>),
+    Session(vpn::SessionInfo),
+    Ping(Option<Duration>),
+}
+
+// Stops the thread when dropped
+pub struct TrafficSampler {
+    pub stats: TrafficStats,
+    receiver: Receiver<Update>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl TrafficSampler {
+    pub fn start(config: &VpnConfig) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let stop = cancelled.clone();
+        let config = config.clone();
+        let stats = TrafficStats::new(&config);
+        let ping_host = stats.health.as_ref().map(|health| health.host.clone());
+        let ping_interval = Duration::from_secs(config.health.interval_secs.max(1));
+
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                let mut count = 0;
+                let mut last_ping: Option<Instant> = None;
+                while !stop.load(Ordering::Relaxed) {
+                    // A lost probe takes a few seconds, so it doesn't hold up the counters
+                    if let Some(host) = &ping_host {
+                        if last_ping.is_none_or(|at| at.elapsed() >= ping_interval) {
+                            last_ping = Some(Instant::now());
+                            let (tx, config, host) = (tx.clone(), config.clone(), host.clone());
+                            tokio::spawn(async move {
+                                let _ = tx.send(Update::Ping(health::probe(&config, &host).await));
+                            });
+                        }
+                    }
+                    let counters = read_counters(&config).await.map_err(|e| e.to_string());
+                    if tx.send(Update::Counters(counters)).is_err() {
+                        return;
+                    }
+                    if config.vpn_type == VpnType::OpenVpn && count % SESSION_EVERY == 0 {
+                        match vpn::session_info(&config).await {
+                            Ok(session) => {
+                                let _ = tx.send(Update::Session(session));
+                            }
+                            Err(e) => log::debug!("No session details for {}: {}", config.name, e),
+                        }
+                    }
+                    count += 1;
+                    tokio::time::sleep(SAMPLE_INTERVAL).await;
+                }
+            });
+        });
+
+        Self { stats, receiver: rx, cancelled }
+    }
+
+    pub fn poll(&mut self) {
+        while let Ok(update) = self.receiver.try_recv() {
+            match update {
+                Update::Counters(Ok((received, sent))) => {
+                    self.stats.push(Sample { at: Instant::now(), received, sent });
+                    self.stats.error = None;
+                }
+                Update::Counters(Err(e)) => self.stats.error = Some(e),
+                Update::Session(session) => self.stats.session = Some(session),
+                Update::Ping(rtt) => {
+                    let Some(health) = &mut self.stats.health else { continue };
+                    let was_passing = !health.not_passing();
+                    health.push(rtt);
+                    if was_passing && health.not_passing() {
+                        log::warn!("{} is connected but {} doesn't answer through the tunnel", self.stats.vpn, health.host);
+                        client_log::push_live("vpn", &self.stats.vpn, LogLevel::Warning,
+                                              &format!("Connected but not passing traffic: no answer from {}", health.host));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for TrafficSampler {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+// Bytes received and sent through the tunnel
+pub async fn read_counters(config: &VpnConfig) -> Result<(u64, u64)> {
+    if config.vpn_type == VpnType::OpenVpn {
+        match vpn::byte_counts(config).await {
+            Ok(totals) => return Ok(totals),
+            Err(e) => log::debug!("Falling back to interface counters for {}: {}", config.name, e),
+        }
+    }
+    if config.vpn_type == VpnType::WireGuard {
+        let interface = tunnel::interface_pattern(config).await;
+        match wireguard::transfer(&interface).await {
+            Ok(totals) => return Ok(totals),
+            Err(e) => log::debug!("Falling back to interface counters for {}: {}", config.name, e),
+        }
+    }
+
+    let adapters = tunnel::adapters(config).await;
+    if adapters.is_empty() {
+        return Err(errors::error(ErrorCode::TunnelNotFound, ""));
+    }
+
+    let mut totals = (0, 0);
+    for adapter in adapters {
+        #[cfg(unix)]
+        let (received, sent) = interface_counters_unix(&adapter.name)?;
+        #[cfg(windows)]
+        let (received, sent) = interface_counters_windows(adapter.index).await?;
+        totals.0 += received;
+        totals.1 += sent;
+    }
+    Ok(totals)
+}
+
+#[cfg(unix)]
+fn interface_counters_unix(interface: &str) -> Result<(u64, u64)> {
+    let read = |counter: &str| -> Result<u64> {
+        let path = format!("/sys/class/net/{}/statistics/{}", interface, counter);
+        Ok(std::fs::read_to_string(&path)?.trim().parse()?)
+    };
+    Ok((read("rx_bytes")?, read("tx_bytes")?))
+}
+
+#[cfg(windows)]
+async fn interface_counters_windows(index: u32) -> Result<(u64, u64)> {
+    use std::os::windows::process::CommandExt;
+
+    let script = format!(
+        "Get-NetAdapter -InterfaceIndex {} | Get-NetAdapterStatistics | ForEach-Object {{ \"$($_.ReceivedBytes) $($_.SentBytes)\" }}",
+        index
+    );
+    let mut cmd = tokio::process::Command::new("powershell");
+    cmd.args(&["-NoProfile", "-Command", &script])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd.output().await?;
+    let output_str = String::from_utf8_lossy(&output.stdout);
+    let mut parts = output_str.split_whitespace().map(|n| n.parse::<u64>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(received)), Some(Ok(sent))) => Ok((received, sent)),
+        _ => Err(anyhow::anyhow!("No statistics for adapter {}", index)),
+    }
+}
+
+// "1.5 MB" style sizes
+pub fn format_bytes(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{:.0} {}", value, UNITS[unit])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+// "1h 05m 12s" style durations
+pub fn format_uptime(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m {:02}s", secs / 3600, (secs % 3600) / 60, secs % 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
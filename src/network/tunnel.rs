@@ -0,0 +1,72 @@
+use crate::config::{VpnConfig, VpnType};
+use network_interface::{NetworkInterface, NetworkInterfaceConfig};
+
+// Finding the network interface a connected tunnel uses, for firewall rules and routes that
+// have to name it
+
+// Interface name as a pattern with an optional trailing `*`, e.g. "tun*" for clients that pick
+// the next free device. IKEv2 has no interface on Linux (strongSwan uses kernel IPsec policies);
+// on Windows the RAS adapter carries the connection's name.
+pub async fn interface_pattern(config: &VpnConfig) -> String {
+    let pattern = match config.vpn_type {
+        VpnType::OpenVpn => {
+            // dev tun/tap picks the next free device, dev tun0 names it exactly
+            let content = std::fs::read_to_string(config.resolved_config_path()).unwrap_or_default();
+            let dev = content.lines().find_map(|line| {
+                let mut parts = line.split_whitespace();
+                (parts.next()? == "dev").then(|| parts.next()).flatten().map(|d| d.to_string())
+            });
+            match dev.as_deref() {
+                Some("tun") | None => "tun*".to_string(),
+                Some("tap") => "tap*".to_string(),
+                Some(dev) => dev.to_string(),
+            }
+        }
+        VpnType::WireGuard => crate::network::wireguard::get_interface_from_config(&config.resolved_config_path())
+            .await
+            .unwrap_or_else(|_| "wg0".to_string()),
+        VpnType::OpenConnect => "tun*".to_string(),
+        VpnType::Ikev2 => crate::network::ikev2::connection_name(config).to_string(),
+        // Most clients take the next free tun device when the entry doesn't say
+        VpnType::Custom if config.custom.interface.trim().is_empty() => "tun*".to_string(),
+        VpnType::Custom => config.custom.interface.trim().to_string(),
+    };
+    if cfg!(target_os = "macos") {
+        return macos_pattern(config, pattern);
+    }
+    pattern
+}
+
+// macOS only has utun devices, numbered in the order they are opened
+fn macos_pattern(config: &VpnConfig, pattern: String) -> String {
+    match config.vpn_type {
+        VpnType::WireGuard => {
+            let device = crate::network::wireguard::device_name(&pattern);
+            if device == pattern { "utun*".to_string() } else { device }
+        }
+        VpnType::OpenVpn | VpnType::OpenConnect if pattern == "tun*" => "utun*".to_string(),
+        _ => pattern,
+    }
+}
+
+// Interfaces currently up that belong to the tunnel
+pub async fn adapters(config: &VpnConfig) -> Vec<NetworkInterface> {
+    let pattern = interface_pattern(config).await;
+    let Ok(mut interfaces) = NetworkInterface::show() else { return Vec::new() };
+    interfaces.retain(|iface| is_tunnel_adapter(config, &pattern, &iface.name));
+    interfaces
+}
+
+fn is_tunnel_adapter(config: &VpnConfig, pattern: &str, name: &str) -> bool {
+    // Windows adapters have friendly names instead: OpenVPN and OpenConnect use TAP-Windows or
+    // Wintun adapters, WireGuard and RAS name theirs after the tunnel
+    if cfg!(windows) && matches!(config.vpn_type, VpnType::OpenVpn | VpnType::OpenConnect) {
+        let name = name.to_lowercase();
+        return ["openvpn", "tap", "wintun", "openconnect"].iter().any(|known| name.contains(known));
+    }
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name.eq_ignore_ascii_case(pattern),
+    }
+}
@@ -0,0 +1,104 @@
+use crate::config::VncConfig;
+use crate::errors::{self, ErrorCode};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+#[cfg(unix)]
+use crate::system::session::DisplaySession;
+
+// VNC viewers take `host::port` for a raw port (a single colon means a display number);
+// Remmina wants a vnc:// URL instead. A device can name its own viewer, e.g. RealVNC or
+// UltraVNC on Windows; otherwise vncviewer on the PATH is used, then the viewers Windows
+// installers leave off the PATH, then Remmina on Linux.
+
+// Install locations of TigerVNC, RealVNC, TightVNC and UltraVNC
+#[cfg(windows)]
+const WINDOWS_VIEWERS: &[&str] = &[
+    "C:\\Program Files\\TigerVNC\\vncviewer.exe",
+    "C:\\Program Files (x86)\\TigerVNC\\vncviewer.exe",
+    "C:\\Program Files\\RealVNC\\VNC Viewer\\vncviewer.exe",
+    "C:\\Program Files\\TightVNC\\tvnviewer.exe",
+    "C:\\Program Files (x86)\\TightVNC\\tvnviewer.exe",
+    "C:\\Program Files\\uvnc bvba\\UltraVNC\\vncviewer.exe",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Viewer {
+    // Takes host::port as its only argument, which all the common viewers understand
+    Program(PathBuf),
+    Remmina,
+}
+
+impl Viewer {
+    pub fn program(&self) -> String {
+        match self {
+            Viewer::Program(path) => path.display().to_string(),
+            Viewer::Remmina => "remmina".to_string(),
+        }
+    }
+
+    pub fn args(&self, config: &VncConfig) -> Vec<String> {
+        match self {
+            Viewer::Program(_) => vec![viewer_target(config)],
+            Viewer::Remmina => vec!["-c".to_string(), format!("vnc://{}:{}", config.host, config.port)],
+        }
+    }
+}
+
+pub fn viewer_target(config: &VncConfig) -> String {
+    format!("{}::{}", config.host, config.port)
+}
+
+// A viewer installed on this machine, for devices that don't name one
+pub fn installed_viewer() -> Option<Viewer> {
+    if let Ok(path) = which::which("vncviewer") {
+        return Some(Viewer::Program(path));
+    }
+
+    #[cfg(windows)]
+    {
+        let installed = WINDOWS_VIEWERS.iter().map(PathBuf::from).find(|path| path.exists());
+        if let Some(path) = installed.or_else(|| which::which("tvnviewer").ok()) {
+            return Some(Viewer::Program(path));
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        if which::which("remmina").is_ok() {
+            return Some(Viewer::Remmina);
+        }
+    }
+
+    None
+}
+
+// The device's own viewer, a name on the PATH or a full path, else one installed
+pub fn viewer(config: &VncConfig) -> Result<Viewer> {
+    let program = config.viewer.trim();
+    if program.is_empty() {
+        return installed_viewer().ok_or_else(|| errors::error(ErrorCode::VncViewerMissing, ""));
+    }
+    which::which(program)
+        .map(Viewer::Program)
+        .map_err(|_| errors::error(ErrorCode::VncViewerNotFound, program))
+}
+
+pub fn connect(config: &VncConfig) -> Result<()> {
+    if config.host.trim().is_empty() {
+        return Err(anyhow::anyhow!("{} has no VNC host", config.name));
+    }
+    log::info!("Opening VNC viewer for {} at {}:{}", config.name, config.host, config.port);
+    
+    #[cfg(unix)]
+    DisplaySession::detect().require_display("the VNC viewer")?;
+    
+    let viewer = viewer(config)?;
+    let mut cmd = std::process::Command::new(viewer.program());
+    cmd.args(viewer.args(config));
+    
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    cmd.spawn()?;
+    Ok(())
+}
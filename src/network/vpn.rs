@@ -1,172 +1,649 @@
 use crate::config::VpnConfig;
+use crate::errors::{self, ErrorCode};
+use crate::network::{certs, dashboard, otp, proxy};
+use crate::system::client_log::{self, LogLevel};
 use anyhow::Result;
-use tokio::process::Command as TokioCommand;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
 
-pub async fn connect(config: &VpnConfig) -> Result<()> {
-    #[cfg(windows)]
-    {
-        connect_windows(config).await
+// OpenVPN is driven through its management interface: each entry's openvpn listens on its own
+// localhost port, recorded in a file next to the entry's log so it is found again after the app
+// restarts, and writes a pid file that the process on the port is checked against.
+// Credentials are answered when openvpn asks for them, connecting waits for the CONNECTED state,
+// and status, byte counts and disconnects go to that one process instead of matching any
+// openvpn running on the machine. The port is derived from the entry's name within
+// MANAGEMENT_PORT_SLOTS ports from MANAGEMENT_PORT_BASE, and any free port is taken if
+// another program holds it. Disconnecting sends SIGTERM through the interface. openvpn also
+// rewrites a status file every STATUS_INTERVAL_SECS, which is where the session details (virtual
+// IP, server, data cipher) come from.
+//
+// On Windows openvpn is started by the interactive service that comes with OpenVPN when it is
+// running, as the OpenVPN GUI does: the service does the route and adapter changes that need
+// admin rights, so the app runs unelevated, and the process belongs to the service rather than
+// to the app. Without the service openvpn is spawned directly, which needs an elevated app.
+
+const MANAGEMENT_HOST: &str = "127.0.0.1";
+const MANAGEMENT_PORT_BASE: u16 = 17500;
+const MANAGEMENT_PORT_SLOTS: u32 = 1000;
+// For openvpn to open the port after starting
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+// From releasing the hold to the tunnel being up
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(60);
+// For a command's reply and for the process to exit after SIGTERM
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+const EXIT_TIMEOUT: Duration = Duration::from_secs(10);
+// How often openvpn rewrites the status file
+const STATUS_INTERVAL_SECS: u32 = 2;
+
+// Derived from the name so an entry usually keeps its port
+fn preferred_port(config: &VpnConfig) -> u16 {
+    // FNV-1a, which unlike the std hasher is the same on every run
+    let hash = config.name.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    MANAGEMENT_PORT_BASE + (hash % MANAGEMENT_PORT_SLOTS) as u16
+}
+
+fn port_file(config: &VpnConfig) -> PathBuf {
+    client_log::log_path("vpn", &config.name).with_extension("port")
+}
+
+// The port the entry's openvpn was last started with
+pub fn management_port(config: &VpnConfig) -> u16 {
+    std::fs::read_to_string(port_file(config))
+        .ok()
+        .and_then(|port| port.trim().parse().ok())
+        .unwrap_or_else(|| preferred_port(config))
+}
+
+// The preferred port unless something else has it, e.g. another entry whose name hashes the same
+fn assign_port(config: &VpnConfig) -> Result<u16> {
+    let port = match std::net::TcpListener::bind((MANAGEMENT_HOST, preferred_port(config))) {
+        Ok(listener) => listener.local_addr()?.port(),
+        Err(_) => std::net::TcpListener::bind((MANAGEMENT_HOST, 0))?.local_addr()?.port(),
+    };
+    std::fs::write(port_file(config), format!("{}\n", port))?;
+    Ok(port)
+}
+
+// Written by openvpn once it runs
+pub fn pid_file(config: &VpnConfig) -> PathBuf {
+    client_log::log_path("vpn", &config.name).with_extension("pid")
+}
+
+// Any local user can reach the port, so openvpn asks for the password in this file first
+pub fn management_password_file(config: &VpnConfig) -> PathBuf {
+    client_log::log_path("vpn", &config.name).with_extension("mgmt")
+}
+
+// Rewritten by openvpn every few seconds while it runs
+pub fn status_file(config: &VpnConfig) -> PathBuf {
+    client_log::log_path("vpn", &config.name).with_extension("status")
+}
+
+fn write_management_password(config: &VpnConfig) -> Result<()> {
+    let path = management_password_file(config);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
-    
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
     #[cfg(unix)]
     {
-        connect_unix(config).await
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
     }
+    options.open(&path)?.write_all(format!("{}\n", dashboard::generate_token()?).as_bytes())?;
+    Ok(())
 }
 
-#[cfg(windows)]
-pub async fn connect_windows(config: &VpnConfig) -> Result<()> {
-    let mut cmd = TokioCommand::new("openvpn");
-    cmd.arg("--config")
-        .arg(&config.config_path)
-        .arg("--daemon")
-        .arg("--auth-user-pass")
-        .arg("NUL")
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .stdin(std::process::Stdio::null());
-    
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+// A session on an entry's management interface
+pub struct Management {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl Management {
+    // None when nothing listens on the entry's port, i.e. its openvpn isn't running
+    pub async fn open(config: &VpnConfig) -> Result<Option<Self>> {
+        let stream = match TcpStream::connect((MANAGEMENT_HOST, management_port(config))).await {
+            Ok(stream) => stream,
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let (reader, writer) = stream.into_split();
+        let mut management = Self { reader: BufReader::new(reader), writer };
+
+        let password = std::fs::read_to_string(management_password_file(config))
+            .map_err(|_| anyhow::anyhow!("Management password for {} is missing", config.name))?;
+        management.send(password.trim()).await?;
+        // The prompt has no line ending, so the reply arrives on the same line
+        let reply = tokio::time::timeout(REPLY_TIMEOUT, management.next_line()).await
+            .map_err(|_| anyhow::anyhow!("Management interface of {} did not answer", config.name))??;
+        if !reply.contains("SUCCESS:") {
+            return Err(anyhow::anyhow!("Management interface of {} rejected the password", config.name));
+        }
+        Ok(Some(management))
     }
-    
-    let output = cmd.output().await?;
 
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to start OpenVPN: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+    async fn send(&mut self, line: &str) -> Result<()> {
+        self.writer.write_all(format!("{}\n", line).as_bytes()).await?;
+        Ok(())
     }
 
-    Ok(())
-}
+    async fn next_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).await? == 0 {
+            return Err(anyhow::anyhow!("OpenVPN closed the management connection"));
+        }
+        Ok(line.trim_end().to_string())
+    }
 
-#[cfg(unix)]
-pub async fn connect_unix(config: &VpnConfig) -> Result<()> {
-    let output = TokioCommand::new("openvpn")
-        .arg("--config")
-        .arg(&config.config_path)
-        .arg("--daemon")
-        .arg("--auth-user-pass")
-        .arg("/dev/stdin")
-        .output()
-        .await?;
+    // The reply to a command: its SUCCESS: line, or the lines up to END for commands that list
+    // something. Real-time notifications (lines starting with >) in between are skipped.
+    async fn command(&mut self, command: &str) -> Result<Vec<String>> {
+        self.send(command).await?;
+        tokio::time::timeout(REPLY_TIMEOUT, async {
+            let mut lines = Vec::new();
+            loop {
+                let line = self.next_line().await?;
+                if line.starts_with('>') {
+                    continue;
+                }
+                if let Some(error) = line.strip_prefix("ERROR:") {
+                    return Err(anyhow::anyhow!("OpenVPN rejected '{}': {}", command, error.trim()));
+                }
+                if line == "END" || (lines.is_empty() && line.starts_with("SUCCESS:")) {
+                    lines.push(line);
+                    return Ok(lines);
+                }
+                lines.push(line);
+            }
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("OpenVPN did not answer '{}'", command))?
+    }
 
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to start OpenVPN: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+    // CONNECTING, WAIT, AUTH, GET_CONFIG, ASSIGN_IP, CONNECTED, RECONNECTING, EXITING...
+    pub async fn state(&mut self) -> Result<String> {
+        let lines = self.command("state").await?;
+        lines.iter()
+            .find_map(|line| line.split(',').nth(1))
+            .map(String::from)
+            .ok_or_else(|| anyhow::anyhow!("OpenVPN reported no state"))
     }
 
-    Ok(())
+    // Bytes received and sent through the tunnel since it came up
+    pub async fn byte_counts(&mut self) -> Result<(u64, u64)> {
+        let lines = self.command("status").await?;
+        let counter = |name: &str| {
+            lines.iter()
+                .find_map(|line| line.strip_prefix(name))
+                .and_then(|value| value.trim_start_matches(',').trim().parse::<u64>().ok())
+        };
+        // Written to the tunnel device is what came in from the server
+        match (counter("TUN/TAP write bytes"), counter("TUN/TAP read bytes")) {
+            (Some(received), Some(sent)) => Ok((received, sent)),
+            _ => Err(anyhow::anyhow!("OpenVPN reported no byte counts")),
+        }
+    }
+
+    // Whether this is the entry's own openvpn rather than one that took the port since. Without
+    // a pid file there is nothing to compare, and the password already matched.
+    pub async fn belongs_to(&mut self, config: &VpnConfig) -> Result<bool> {
+        let Some(expected) = std::fs::read_to_string(pid_file(config)).ok().and_then(|pid| pid.trim().parse::<u32>().ok()) else {
+            return Ok(true);
+        };
+        // SUCCESS: pid=1234
+        let lines = self.command("pid").await?;
+        let pid = lines.iter()
+            .find_map(|line| line.split("pid=").nth(1))
+            .and_then(|pid| pid.trim().parse::<u32>().ok());
+        Ok(pid == Some(expected))
+    }
+
+    pub async fn signal(&mut self, signal: &str) -> Result<()> {
+        self.command(&format!("signal {}", signal)).await?;
+        Ok(())
+    }
 }
 
-pub async fn disconnect() -> Result<()> {
-    #[cfg(windows)]
-    {
-        disconnect_windows().await
+// Quoted for the management protocol
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub async fn connect(config: &VpnConfig) -> Result<()> {
+    // An instance from an earlier attempt still holds the port
+    if let Some(mut management) = Management::open(config).await.ok().flatten() {
+        if management.belongs_to(config).await.unwrap_or(false) {
+            if management.state().await.is_ok_and(|state| state == "CONNECTED") {
+                return Ok(());
+            }
+            let _ = management.signal("SIGTERM").await;
+            wait_for_exit(config).await;
+        }
     }
-    
+
+    write_management_password(config)?;
+    client_log::prepare("vpn", &config.name);
+    // A previous session's counters and pid would otherwise be taken for this one's
+    let _ = std::fs::remove_file(status_file(config));
+    let _ = std::fs::remove_file(pid_file(config));
+    assign_port(config)?;
+
+    #[cfg(windows)]
+    start_windows(config).await?;
+
     #[cfg(unix)]
-    {
-        disconnect_unix().await
+    start_unix(config).await?;
+
+    establish(config).await
+}
+
+// Arguments passed to openvpn, shared with the command preview
+pub fn openvpn_args(config: &VpnConfig) -> Vec<String> {
+    let mut args = vec![
+        "--config".to_string(),
+        config.resolved_config_path(),
+        "--management".to_string(),
+        MANAGEMENT_HOST.to_string(),
+        management_port(config).to_string(),
+        management_password_file(config).display().to_string(),
+        // Waits for the app to be listening before doing anything
+        "--management-hold".to_string(),
+        "--management-query-passwords".to_string(),
+        "--writepid".to_string(),
+        pid_file(config).display().to_string(),
+        "--status".to_string(),
+        status_file(config).display().to_string(),
+        STATUS_INTERVAL_SECS.to_string(),
+        // The process keeps logging here in the background
+        "--log-append".to_string(),
+        client_log::log_path("vpn", &config.name).display().to_string(),
+    ];
+    // Asked for over the management interface
+    if !config.username.is_empty() {
+        args.push("--auth-user-pass".to_string());
     }
+    args.extend(certs::args(config));
+    args.extend(proxy::args(config));
+    // Windows has no daemon mode; the process is started detached instead
+    if cfg!(unix) {
+        args.push("--daemon".to_string());
+    }
+    args
 }
 
 #[cfg(windows)]
-pub async fn disconnect_windows() -> Result<()> {
-    let mut cmd = TokioCommand::new("taskkill");
-    cmd.arg("/F")
-        .arg("/IM")
-        .arg("openvpn.exe")
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .stdin(std::process::Stdio::null());
-    
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+const SERVICE_PIPE: &str = r"\\.\pipe\openvpn\service";
+
+#[cfg(windows)]
+async fn start_windows(config: &VpnConfig) -> Result<()> {
+    let service_config = config.clone();
+    if tokio::task::spawn_blocking(move || start_with_service(&service_config)).await?? {
+        return Ok(());
     }
-    
-    let output = cmd.output().await?;
+    spawn_windows(config)
+}
 
-    if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to stop OpenVPN: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+// Hands the options to the interactive service over its pipe; false if the service isn't there
+#[cfg(windows)]
+fn start_with_service(config: &VpnConfig) -> Result<bool> {
+    let mut pipe = match std::fs::OpenOptions::new().read(true).write(true).open(SERVICE_PIPE) {
+        Ok(pipe) => pipe,
+        Err(e) => {
+            log::info!("OpenVPN interactive service not available ({}), starting openvpn directly", e);
+            return Ok(false);
+        }
+    };
+
+    let config_path = config.resolved_config_path();
+    let workdir = std::path::Path::new(&config_path)
+        .parent()
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+    let options: Vec<String> = openvpn_args(config).iter().map(|arg| windows_quote(arg)).collect();
+    // Working directory, options and what to write to openvpn's stdin, each ending in NUL, as
+    // UTF-16 in one message. The environment can't be passed, so env_vars don't apply.
+    let startup = format!("{}\0{}\0\0", workdir, options.join(" "));
+    let message: Vec<u8> = startup.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+    pipe.write_all(&message)?;
+
+    // "0x00000000\n0x<pid>\nProcess ID", or an error code, the step that failed and a message
+    let reply = read_service_message(&mut pipe)?;
+    let fields: Vec<&str> = reply.splitn(3, '\n').collect();
+    let code = fields.first().and_then(|code| u32::from_str_radix(code.trim().trim_start_matches("0x"), 16).ok());
+    if code != Some(0) {
+        let message = fields.get(2).or(fields.get(1)).map_or("no reply", |message| message.trim());
+        client_log::push_live("vpn", &config.name, LogLevel::Error, &format!("OpenVPN service: {}", message));
+        return Err(anyhow::anyhow!("The OpenVPN service could not start {}: {}", config.name, message));
     }
+    let pid = fields.get(1).and_then(|pid| u32::from_str_radix(pid.trim().trim_start_matches("0x"), 16).ok());
+    log::info!("OpenVPN service started openvpn for {} (pid {})", config.name, pid.map_or("?".to_string(), |pid| pid.to_string()));
+
+    // The service reports here if openvpn exits with an error, e.g. later on a bad option
+    let name = config.name.clone();
+    std::thread::spawn(move || {
+        while let Ok(message) = read_service_message(&mut pipe) {
+            let message = message.replace('\n', " ");
+            log::warn!("OpenVPN service on {}: {}", name, message);
+            client_log::push_live("vpn", &name, LogLevel::Error, &format!("OpenVPN service: {}", message));
+        }
+    });
+    Ok(true)
+}
+
+#[cfg(windows)]
+fn read_service_message(pipe: &mut std::fs::File) -> Result<String> {
+    use std::io::Read;
+
+    let mut buffer = vec![0u8; 4096];
+    let read = pipe.read(&mut buffer)?;
+    if read == 0 {
+        return Err(anyhow::anyhow!("The OpenVPN service closed the pipe"));
+    }
+    let units: Vec<u16> = buffer[..read].chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    Ok(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string())
+}
+
+// Quoted for a Windows command line, which the service splits like CommandLineToArgvW
+#[cfg(windows)]
+fn windows_quote(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            c => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+// Needs the app to run elevated, and openvpn ends with it
+#[cfg(windows)]
+fn spawn_windows(config: &VpnConfig) -> Result<()> {
+    use std::os::windows::process::CommandExt;
 
+    let mut cmd = std::process::Command::new("openvpn");
+    cmd.args(openvpn_args(config))
+        .envs(&config.env_vars)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .stdin(std::process::Stdio::null());
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    cmd.spawn().map_err(|e| errors::error(ErrorCode::VpnClientFailed, format!("OpenVPN: {}", e)))?;
     Ok(())
 }
 
 #[cfg(unix)]
-pub async fn disconnect_unix() -> Result<()> {
-    let output = TokioCommand::new("pkill")
-        .arg("openvpn")
+async fn start_unix(config: &VpnConfig) -> Result<()> {
+    // Returns once the daemon has forked, or with the option errors
+    let output = tokio::process::Command::new("openvpn")
+        .args(openvpn_args(config))
+        .envs(&config.env_vars)
+        .stdin(std::process::Stdio::null())
         .output()
         .await?;
+    client_log::record("vpn", &config.name, "openvpn", &output);
 
     if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to stop OpenVPN: {}",
-            String::from_utf8_lossy(&output.stderr)
+        return Err(errors::error(
+            ErrorCode::VpnClientFailed,
+            format!("OpenVPN: {}", String::from_utf8_lossy(&output.stderr).trim())
         ));
     }
 
     Ok(())
 }
 
-pub async fn get_status() -> Result<bool> {
-    #[cfg(windows)]
-    {
-        get_status_windows().await
+// Releases the hold and answers openvpn until the tunnel is up; a failed attempt is stopped
+async fn establish(config: &VpnConfig) -> Result<()> {
+    let started = Instant::now();
+    let mut management = loop {
+        match Management::open(config).await {
+            Ok(Some(management)) => break management,
+            Ok(None) if started.elapsed() < STARTUP_TIMEOUT => tokio::time::sleep(Duration::from_millis(250)).await,
+            Ok(None) => return Err(errors::error(ErrorCode::VpnClientFailed, "OpenVPN did not start")),
+            Err(e) => return Err(e),
+        }
+    };
+
+    management.command("state on").await?;
+    // Copied into the live log while connecting, so a failure shows what led up to it
+    management.command("log on").await?;
+    management.command("hold release").await?;
+
+    let result = match tokio::time::timeout(CONNECT_TIMEOUT, answer_until_connected(config, &mut management)).await {
+        Ok(result) => result,
+        Err(_) => Err(errors::error(ErrorCode::VpnConnectTimeout, CONNECT_TIMEOUT.as_secs())),
+    };
+
+    if result.is_err() {
+        // Already gone after an authentication failure
+        if management.signal("SIGTERM").await.is_ok() {
+            wait_for_exit(config).await;
+        }
     }
-    
-    #[cfg(unix)]
-    {
-        get_status_unix().await
+    result
+}
+
+async fn answer_until_connected(config: &VpnConfig, management: &mut Management) -> Result<()> {
+    loop {
+        let line = management.next_line().await?;
+
+        if line.starts_with(">PASSWORD:Need 'Auth'") {
+            // The config file has auth-user-pass of its own; empty answers would only be rejected
+            // by the server after a round trip
+            if config.username.is_empty() {
+                return Err(errors::error(ErrorCode::VpnCredentialsMissing, &config.name));
+            }
+            let password = match otp::take(&config.name) {
+                Some(code) => otp::password(config, &code, &line),
+                // A static challenge wants a code the app can't know
+                None if otp::needs_code(config) || line.contains(" SC:") => {
+                    return Err(errors::error(ErrorCode::VpnOtpMissing, &config.name));
+                }
+                None => config.password.clone(),
+            };
+            management.send(&format!("username \"Auth\" {}", quote(&config.username))).await?;
+            management.send(&format!("password \"Auth\" {}", quote(&password))).await?;
+        } else if let Some(kind) = proxy::prompt_type(&line) {
+            management.send(&format!("username \"{}\" {}", kind, quote(&config.proxy.username))).await?;
+            management.send(&format!("password \"{}\" {}", kind, quote(&config.proxy.password))).await?;
+        } else if line.starts_with(">PASSWORD:Need 'Private Key'") {
+            if config.password.is_empty() {
+                return Err(errors::error(ErrorCode::VpnKeyPassword, ""));
+            }
+            management.send(&format!("password \"Private Key\" {}", quote(&config.password))).await?;
+        } else if line.starts_with(">PASSWORD:Verification Failed") {
+            return Err(errors::error(ErrorCode::VpnAuthFailed, ""));
+        } else if line.starts_with(">HOLD:") {
+            management.send("hold release").await?;
+        } else if let Some(entry) = line.strip_prefix(">LOG:") {
+            // time,flags,message
+            let mut fields = entry.splitn(3, ',');
+            let (flags, message) = (fields.nth(1).unwrap_or_default(), fields.next().unwrap_or_default());
+            let level = if flags.contains('F') || flags.contains('N') {
+                LogLevel::Error
+            } else if flags.contains('W') {
+                LogLevel::Warning
+            } else if flags.contains('D') {
+                LogLevel::Debug
+            } else {
+                LogLevel::Info
+            };
+            client_log::push_live("vpn", &config.name, level, message);
+        } else if let Some(message) = line.strip_prefix(">FATAL:") {
+            return Err(errors::error(ErrorCode::VpnClientFailed, format!("OpenVPN: {}", message)));
+        } else if let Some(state) = line.strip_prefix(">STATE:") {
+            let fields: Vec<&str> = state.split(',').collect();
+            match fields.get(1).copied() {
+                Some("CONNECTED") => {
+                    if fields.get(2) == Some(&"ERROR") {
+                        log::warn!("{} connected with errors, see the client log", config.name);
+                    }
+                    return Ok(());
+                }
+                Some("EXITING") => {
+                    return Err(errors::error(
+                        ErrorCode::VpnClientFailed,
+                        format!("OpenVPN exited: {}", fields.get(2).unwrap_or(&"unknown reason"))
+                    ));
+                }
+                _ => {}
+            }
+        }
     }
 }
 
-pub async fn check_connection_status() -> Result<bool> {
-    get_status().await
+// Until the port closes, which is the last thing openvpn does
+async fn wait_for_exit(config: &VpnConfig) {
+    let started = Instant::now();
+    while started.elapsed() < EXIT_TIMEOUT {
+        if TcpStream::connect((MANAGEMENT_HOST, management_port(config))).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    log::warn!("OpenVPN for {} is still running after SIGTERM", config.name);
 }
 
-#[cfg(windows)]
-pub async fn get_status_windows() -> Result<bool> {
-    let mut cmd = TokioCommand::new("tasklist");
-    cmd.arg("/FI")
-        .arg("IMAGENAME eq openvpn.exe")
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .stdin(std::process::Stdio::null());
-    
-    #[cfg(windows)]
-    {
-        use std::os::windows::process::CommandExt;
-        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    let Some(mut management) = Management::open(config).await? else {
+        log::debug!("OpenVPN for {} is not running", config.name);
+        return Ok(());
+    };
+    if !management.belongs_to(config).await? {
+        return Err(anyhow::anyhow!("The OpenVPN process on port {} is not the one {} started", management_port(config), config.name));
     }
-    
-    let output = cmd.output().await?;
+    management.signal("SIGTERM").await
+        .map_err(|e| anyhow::anyhow!("Failed to stop OpenVPN: {}", e))?;
+    wait_for_exit(config).await;
+    let _ = std::fs::remove_file(status_file(config));
+    let _ = std::fs::remove_file(pid_file(config));
+    Ok(())
+}
 
-    let output_str = String::from_utf8_lossy(&output.stdout);
-    Ok(output_str.contains("openvpn.exe"))
+pub async fn check_connection_status(config: &VpnConfig) -> Result<bool> {
+    match Management::open(config).await? {
+        Some(mut management) => Ok(management.belongs_to(config).await? && management.state().await? == "CONNECTED"),
+        None => Ok(false),
+    }
 }
 
-#[cfg(unix)]
-pub async fn get_status_unix() -> Result<bool> {
-    let output = TokioCommand::new("pgrep")
-        .arg("openvpn")
-        .output()
-        .await?;
+// Details of the running session: addresses from the management state, counters from the status
+// file and the data channel cipher from the log, as neither of the others reports it
+#[derive(Debug, Clone, Default)]
+pub struct SessionInfo {
+    pub virtual_ip: Option<String>,
+    // Server address and port
+    pub remote: Option<String>,
+    pub cipher: Option<String>,
+    pub received: u64,
+    pub sent: u64,
+    // When openvpn last wrote the status file, as it wrote it
+    pub updated: Option<String>,
+}
+
+// The client status file: "name,value" lines between a title and END
+#[derive(Debug, Clone, Default)]
+pub struct StatusFile {
+    pub updated: Option<String>,
+    pub received: Option<u64>,
+    pub sent: Option<u64>,
+}
 
-    Ok(output.status.success())
+pub fn parse_status(text: &str) -> StatusFile {
+    let mut status = StatusFile::default();
+    for line in text.lines() {
+        let Some((name, value)) = line.split_once(',') else { continue };
+        match name {
+            "Updated" => status.updated = Some(value.trim().to_string()),
+            // Written to the tunnel device is what came in from the server
+            "TUN/TAP write bytes" => status.received = value.trim().parse().ok(),
+            "TUN/TAP read bytes" => status.sent = value.trim().parse().ok(),
+            _ => {}
+        }
+    }
+    status
+}
+
+pub fn read_status(config: &VpnConfig) -> Result<StatusFile> {
+    let text = std::fs::read_to_string(status_file(config))
+        .map_err(|_| anyhow::anyhow!("No status file for {}", config.name))?;
+    Ok(parse_status(&text))
+}
+
+// The last cipher the data channel was set up with, e.g. AES-256-GCM. 2.5 and later log
+// "Data Channel: cipher '...'", 2.4 "Outgoing Data Channel: Cipher '...' initialized".
+pub fn parse_cipher(log: &str) -> Option<String> {
+    log.lines().rev().find_map(|line| {
+        let rest = line.split_once("Data Channel: cipher '").or_else(|| line.split_once("Data Channel: Cipher '"))?.1;
+        rest.split('\'').next().filter(|cipher| !cipher.is_empty()).map(String::from)
+    })
+}
+
+// The entry's own openvpn, for reading from
+async fn open_running(config: &VpnConfig) -> Result<Management> {
+    let mut management = Management::open(config).await?
+        .ok_or_else(|| anyhow::anyhow!("OpenVPN for {} is not running", config.name))?;
+    if !management.belongs_to(config).await? {
+        return Err(anyhow::anyhow!("OpenVPN for {} is not running", config.name));
+    }
+    Ok(management)
+}
+
+pub async fn session_info(config: &VpnConfig) -> Result<SessionInfo> {
+    let mut info = SessionInfo::default();
+
+    if let Ok(status) = read_status(config) {
+        info.received = status.received.unwrap_or(0);
+        info.sent = status.sent.unwrap_or(0);
+        info.updated = status.updated;
+    }
+
+    let mut management = open_running(config).await?;
+    // time,state,description,virtual IP,remote IP,remote port,...
+    let lines = management.command("state").await?;
+    if let Some(fields) = lines.iter().map(|line| line.split(',').collect::<Vec<_>>()).find(|fields| fields.len() > 3) {
+        let field = |index: usize| fields.get(index).map(|f| f.trim()).filter(|f| !f.is_empty());
+        info.virtual_ip = field(3).map(String::from);
+        info.remote = match (field(4), field(5)) {
+            (Some(ip), Some(port)) => Some(format!("{}:{}", ip, port)),
+            (ip, _) => ip.map(String::from),
+        };
+    }
+
+    info.cipher = client_log::read("vpn", &config.name).and_then(|log| parse_cipher(&log));
+    Ok(info)
+}
+
+// Live from the management interface, or as of the last status file write
+pub async fn byte_counts(config: &VpnConfig) -> Result<(u64, u64)> {
+    let mut management = open_running(config).await?;
+    management.byte_counts().await.or_else(|e| match read_status(config) {
+        Ok(StatusFile { received: Some(received), sent: Some(sent), .. }) => Ok((received, sent)),
+        _ => Err(e),
+    })
 }
 
 pub fn get_available_configs() -> Result<Vec<String>> {
@@ -197,9 +674,18 @@ pub fn get_available_configs() -> Result<Vec<String>> {
     
     #[cfg(unix)]
     {
-        let config_dir = std::path::Path::new("/etc/openvpn");
+        // Homebrew keeps its etc under its prefix on macOS
+        let config_dirs: &[&str] = if cfg!(target_os = "macos") {
+            &["/etc/openvpn", "/opt/homebrew/etc/openvpn", "/usr/local/etc/openvpn"]
+        } else {
+            &["/etc/openvpn"]
+        };
         
-        if config_dir.exists() {
+        for config_dir in config_dirs {
+            let config_dir = std::path::Path::new(config_dir);
+            if !config_dir.exists() {
+                continue;
+            }
             for entry in std::fs::read_dir(config_dir)? {
                 let entry = entry?;
                 let path = entry.path();
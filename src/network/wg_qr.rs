@@ -0,0 +1,79 @@
+use crate::config::{VpnConfig, VpnType};
+use crate::network::wireguard;
+use crate::system::screenshot;
+use anyhow::Result;
+use std::path::Path;
+
+// WireGuard profiles handed out as QR codes, the way providers and the mobile apps share them:
+// the code holds the whole config file as text. It is read from an image file or a screenshot
+// and saved in the user's WireGuard folder, like a config composed in the app.
+
+pub fn decode_file(path: &Path) -> Result<String> {
+    let image = image::open(path).map_err(|e| anyhow::anyhow!("Could not open {}: {}", path.display(), e))?;
+    decode(&image.to_luma8())
+}
+
+// The text of the first QR code in the image that decodes
+pub fn decode(image: &image::GrayImage) -> Result<String> {
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        image.width() as usize,
+        image.height() as usize,
+        |x, y| image.get_pixel(x as u32, y as u32)[0],
+    );
+    let grids = prepared.detect_grids();
+    if grids.is_empty() {
+        return Err(anyhow::anyhow!("No QR code found in the image"));
+    }
+    grids.iter()
+        .find_map(|grid| grid.decode().ok().map(|(_, content)| content))
+        .ok_or_else(|| anyhow::anyhow!("The QR code could not be read, try a sharper or larger image"))
+}
+
+// Takes a screenshot of the whole screen with the platform's tool and decodes it, so a code
+// shown in a browser or a provider's dashboard can be read without saving it first
+pub async fn capture_screen() -> Result<String> {
+    let path = std::env::temp_dir().join(format!("vpn-manager-qr-{}.png", std::process::id()));
+    let result = screenshot::capture(&path).await.and_then(|_| decode_file(&path));
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
+// Saves a decoded config as a new file for the entry and returns the entry. An existing file
+// of the same name is left alone.
+pub fn import(content: &str, name: &str) -> Result<VpnConfig> {
+    if !content.contains("[Interface]") || !content.contains("[Peer]") {
+        return Err(anyhow::anyhow!("The QR code doesn't hold a WireGuard config"));
+    }
+    let path = wireguard::new_config_path(name);
+    if path.exists() {
+        return Err(anyhow::anyhow!("{} already exists, pick another name", path.display()));
+    }
+    wireguard::write_private(&path, &(content.trim_end().to_string() + "\n"))?;
+    log::info!("Imported WireGuard config {} from a QR code", path.display());
+
+    Ok(VpnConfig {
+        id: crate::config::migrate::new_id(),
+        name: name.to_string(),
+        config_path: path.display().to_string(),
+        username: String::new(),
+        password: String::new(),
+        prompt_otp: false,
+        auto_connect: false,
+        vpn_type: VpnType::WireGuard,
+        env_vars: Default::default(),
+        server: String::new(),
+        group: String::new(),
+        kill_switch: false,
+        split_routes: Vec::new(),
+        app_tunnel: Default::default(),
+        dns_servers: Vec::new(),
+        dns_search_domains: Vec::new(),
+        reconnect: Default::default(),
+        system_service: false,
+        health: Default::default(),
+        proxy: Default::default(),
+        certificates: Default::default(),
+        custom: Default::default(),
+        style: Default::default(),
+    })
+}
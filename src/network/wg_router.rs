@@ -0,0 +1,515 @@
+use crate::config::{migrate, VpnConfig, VpnType};
+use crate::network::wireguard;
+use anyhow::Result;
+use std::path::Path;
+
+// WireGuard entries whose server is a router. Each entry is exported as the peer the router
+// needs for it, in the router's own syntax, and a router's peer list is read back to update the
+// entries it lists, matched by public key: the tunnel address the router assigns and the
+// preshared key, so the two sides agree.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RouterFormat {
+    // RouterOS script, for /import or the terminal
+    MikroTik,
+    // Sections for /etc/config/network
+    OpenWrt,
+    // Peer entries of the WireGuard plugin's config.xml section
+    OpnSense,
+}
+
+impl RouterFormat {
+    pub const ALL: [RouterFormat; 3] = [RouterFormat::MikroTik, RouterFormat::OpenWrt, RouterFormat::OpnSense];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RouterFormat::MikroTik => "MikroTik",
+            RouterFormat::OpenWrt => "OpenWrt",
+            RouterFormat::OpnSense => "OPNsense",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            RouterFormat::MikroTik => "wireguard-peers.rsc",
+            RouterFormat::OpenWrt => "wireguard-peers.uci",
+            RouterFormat::OpnSense => "wireguard-peers.xml",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RouterFormat::MikroTik => "rsc",
+            RouterFormat::OpenWrt => "uci",
+            RouterFormat::OpnSense => "xml",
+        }
+    }
+}
+
+// A peer as the router sees it: the client's public key and the addresses routed to it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RouterPeer {
+    pub name: String,
+    pub public_key: String,
+    pub preshared_key: String,
+    // Comma-separated
+    pub allowed_ips: String,
+    // Seconds, empty for none
+    pub keepalive: String,
+}
+
+// The router's side of an entry. The public key is derived from the entry's private key, and
+// the router routes only the entry's own addresses to it.
+pub async fn peer_for(config: &VpnConfig) -> Result<RouterPeer> {
+    let content = std::fs::read_to_string(config.resolved_config_path())?;
+    let private_key = value(&content, "[Interface]", "PrivateKey")
+        .ok_or_else(|| anyhow::anyhow!("{} has no private key", config.name))?;
+    let addresses = value(&content, "[Interface]", "Address")
+        .ok_or_else(|| anyhow::anyhow!("{} has no address", config.name))?;
+
+    Ok(RouterPeer {
+        name: config.name.clone(),
+        public_key: wireguard::public_key(&private_key).await?,
+        preshared_key: value(&content, "[Peer]", "PresharedKey").unwrap_or_default(),
+        allowed_ips: split(&addresses)
+            .map(|address| {
+                let ip = address.split('/').next().unwrap_or(&address);
+                format!("{}/{}", ip, if ip.contains(':') { 128 } else { 32 })
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+        keepalive: value(&content, "[Peer]", "PersistentKeepalive").unwrap_or_default(),
+    })
+}
+
+// Every WireGuard entry with a private key; the others are reported by name
+pub async fn peers(configs: &[VpnConfig]) -> (Vec<RouterPeer>, Vec<String>) {
+    let mut peers = Vec::new();
+    let mut skipped = Vec::new();
+    for config in configs.iter().filter(|c| c.vpn_type == VpnType::WireGuard) {
+        match peer_for(config).await {
+            Ok(peer) => peers.push(peer),
+            Err(e) => {
+                log::warn!("Not exporting {} as a router peer: {}", config.name, e);
+                skipped.push(config.name.clone());
+            }
+        }
+    }
+    (peers, skipped)
+}
+
+pub fn render(peers: &[RouterPeer], format: RouterFormat, interface: &str) -> String {
+    let mut out = String::new();
+    match format {
+        RouterFormat::MikroTik => {
+            out.push_str(&format!("# Generated by VPN Manager v{}\n/interface wireguard peers\n", env!("CARGO_PKG_VERSION")));
+            for peer in peers {
+                let mut line = format!(
+                    "add interface={} public-key=\"{}\" allowed-address={} comment=\"{}\"",
+                    interface, peer.public_key, peer.allowed_ips, peer.name.replace('"', "\\\"")
+                );
+                if !peer.preshared_key.is_empty() {
+                    line.push_str(&format!(" preshared-key=\"{}\"", peer.preshared_key));
+                }
+                if !peer.keepalive.is_empty() {
+                    line.push_str(&format!(" persistent-keepalive={}s", peer.keepalive));
+                }
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+        RouterFormat::OpenWrt => {
+            out.push_str(&format!("# Generated by VPN Manager v{}\n", env!("CARGO_PKG_VERSION")));
+            let quote = |value: &str| format!("'{}'", value.replace('\'', "'\\''"));
+            for peer in peers {
+                out.push_str(&format!("\nconfig wireguard_{}\n", interface));
+                out.push_str(&format!("\toption description {}\n", quote(&peer.name)));
+                out.push_str(&format!("\toption public_key {}\n", quote(&peer.public_key)));
+                if !peer.preshared_key.is_empty() {
+                    out.push_str(&format!("\toption preshared_key {}\n", quote(&peer.preshared_key)));
+                }
+                if !peer.keepalive.is_empty() {
+                    out.push_str(&format!("\toption persistent_keepalive {}\n", quote(&peer.keepalive)));
+                }
+                for address in split(&peer.allowed_ips) {
+                    out.push_str(&format!("\tlist allowed_ips {}\n", quote(&address)));
+                }
+                out.push_str("\toption route_allowed_ips '1'\n");
+            }
+        }
+        RouterFormat::OpnSense => {
+            out.push_str(&format!("<!-- Generated by VPN Manager v{} -->\n<clients>\n", env!("CARGO_PKG_VERSION")));
+            for peer in peers {
+                out.push_str(&format!("  <client uuid=\"{}\">\n", migrate::new_id()));
+                out.push_str("    <enabled>1</enabled>\n");
+                for (tag, value) in [
+                    ("name", &peer.name),
+                    ("pubkey", &peer.public_key),
+                    ("psk", &peer.preshared_key),
+                    ("tunneladdress", &peer.allowed_ips),
+                    ("keepalive", &peer.keepalive),
+                ] {
+                    out.push_str(&format!("    <{0}>{1}</{0}>\n", tag, escape_xml(value)));
+                }
+                out.push_str("  </client>\n");
+            }
+            out.push_str("</clients>\n");
+        }
+    }
+    out
+}
+
+pub async fn export(configs: &[VpnConfig], format: RouterFormat, interface: &str, path: &Path) -> Result<Vec<String>> {
+    let (peers, skipped) = peers(configs).await;
+    if peers.is_empty() {
+        return Err(anyhow::anyhow!("No WireGuard entry with a private key to export"));
+    }
+    wireguard::write_private(path, &render(&peers, format, interface))?;
+    Ok(skipped)
+}
+
+// Peers in any of the three formats, told apart by their syntax
+pub fn parse(text: &str) -> Vec<RouterPeer> {
+    if text.contains("<client") {
+        parse_opnsense(text)
+    } else if text.contains("config wireguard_") {
+        parse_openwrt(text)
+    } else {
+        parse_mikrotik(text)
+    }
+}
+
+// `add` lines under /interface wireguard peers, as /export or print as-value writes them
+fn parse_mikrotik(text: &str) -> Vec<RouterPeer> {
+    let mut peers = Vec::new();
+    let mut section = String::new();
+    // Long lines are continued with a trailing backslash
+    let joined = text.replace("\\\r\n", "").replace("\\\n", "");
+    for line in joined.lines() {
+        let mut line = line.trim();
+        if line.starts_with('/') {
+            let (path, rest) = line.split_once(" add ").map_or((line, ""), |(path, rest)| (path, rest));
+            section = path.trim().to_string();
+            if rest.is_empty() {
+                continue;
+            }
+            line = rest;
+        } else if let Some(rest) = line.strip_prefix("add ") {
+            line = rest;
+        } else {
+            continue;
+        }
+        if section != "/interface wireguard peers" {
+            continue;
+        }
+
+        let mut peer = RouterPeer::default();
+        for (key, value) in mikrotik_pairs(line) {
+            match key.as_str() {
+                "public-key" => peer.public_key = value,
+                "preshared-key" => peer.preshared_key = value,
+                "allowed-address" => peer.allowed_ips = value,
+                "persistent-keepalive" => peer.keepalive = value.trim_end_matches('s').to_string(),
+                "comment" | "name" if peer.name.is_empty() => peer.name = value,
+                _ => {}
+            }
+        }
+        if !peer.public_key.is_empty() {
+            peers.push(peer);
+        }
+    }
+    peers
+}
+
+fn mikrotik_pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let key: String = std::iter::from_fn(|| chars.next_if(|c| *c != '=' && !c.is_whitespace())).collect();
+        if key.is_empty() {
+            break;
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            continue;
+        }
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next()),
+                    '"' => break,
+                    c => value.push(c),
+                }
+            }
+        } else {
+            value.extend(std::iter::from_fn(|| chars.next_if(|c| !c.is_whitespace())));
+        }
+        pairs.push((key, value));
+    }
+    pairs
+}
+
+// `config wireguard_<interface>` sections of /etc/config/network
+fn parse_openwrt(text: &str) -> Vec<RouterPeer> {
+    let mut peers = Vec::new();
+    let mut current: Option<RouterPeer> = None;
+    for line in text.lines() {
+        let words = uci_words(line);
+        match words.first().map(String::as_str) {
+            Some("config") => {
+                peers.extend(current.take().filter(|peer| !peer.public_key.is_empty()));
+                if words.get(1).is_some_and(|kind| kind.starts_with("wireguard_")) {
+                    current = Some(RouterPeer::default());
+                }
+            }
+            Some(kind @ ("option" | "list")) => {
+                let (Some(peer), Some(key), Some(value)) = (current.as_mut(), words.get(1), words.get(2)) else {
+                    continue;
+                };
+                match (kind, key.as_str()) {
+                    ("option", "description") => peer.name = value.clone(),
+                    ("option", "public_key") => peer.public_key = value.clone(),
+                    ("option", "preshared_key") => peer.preshared_key = value.clone(),
+                    ("option", "persistent_keepalive") => peer.keepalive = value.clone(),
+                    ("list", "allowed_ips") => {
+                        if !peer.allowed_ips.is_empty() {
+                            peer.allowed_ips.push(',');
+                        }
+                        peer.allowed_ips.push_str(value);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    peers.extend(current.filter(|peer| !peer.public_key.is_empty()));
+    peers
+}
+
+// Words of a UCI line, unquoting '...' and "..." and a backslash outside of quotes, which is
+// how a quote inside a value is written: 'O'\''Brien'
+fn uci_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut in_word = false;
+    let mut chars = line.trim().chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '#') if !in_word => break,
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '\\') => {
+                word.extend(chars.next());
+                in_word = true;
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (_, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+// <client> entries of the WireGuard plugin, from config.xml or an export of this app
+fn parse_opnsense(text: &str) -> Vec<RouterPeer> {
+    let client = regex::Regex::new(r"(?s)<client\b[^>]*>(.*?)</client>").unwrap();
+    let field = |body: &str, tag: &str| {
+        regex::Regex::new(&format!(r"(?s)<{0}>(.*?)</{0}>", tag)).unwrap()
+            .captures(body)
+            .map(|captures| unescape_xml(captures[1].trim()))
+            .unwrap_or_default()
+    };
+    client.captures_iter(text)
+        .map(|captures| {
+            let body = &captures[1];
+            RouterPeer {
+                name: field(body, "name"),
+                public_key: field(body, "pubkey"),
+                preshared_key: field(body, "psk"),
+                allowed_ips: field(body, "tunneladdress"),
+                keepalive: field(body, "keepalive"),
+            }
+        })
+        .filter(|peer| !peer.public_key.is_empty())
+        .collect()
+}
+
+// Updates the entries the router lists. Returns the names of the entries whose config changed.
+pub async fn import(configs: &[VpnConfig], peers: &[RouterPeer]) -> Result<Vec<String>> {
+    let mut updated = Vec::new();
+    for config in configs.iter().filter(|c| c.vpn_type == VpnType::WireGuard) {
+        let Ok(own) = peer_for(config).await else { continue };
+        let Some(router_peer) = peers.iter().find(|peer| peer.public_key == own.public_key) else { continue };
+        if apply(config, router_peer)? {
+            updated.push(config.name.clone());
+        }
+    }
+    Ok(updated)
+}
+
+// The entry's addresses are replaced only if the router assigns different ones, keeping the
+// prefix length the entry uses
+fn apply(config: &VpnConfig, peer: &RouterPeer) -> Result<bool> {
+    let path = config.resolved_config_path();
+    let content = std::fs::read_to_string(&path)?;
+    let addresses = value(&content, "[Interface]", "Address").unwrap_or_default();
+
+    let host = |address: &str| address.split('/').next().unwrap_or(address).to_string();
+    let mut own: Vec<String> = split(&addresses).map(|a| host(&a)).collect();
+    let mut assigned: Vec<String> = split(&peer.allowed_ips).map(|a| host(&a)).collect();
+    own.sort();
+    assigned.sort();
+    let address = if own == assigned || assigned.is_empty() { addresses } else { peer.allowed_ips.clone() };
+
+    let new_content = wireguard::set_section_keys(&content, "[Interface]", &[("Address", &address)]);
+    let new_content = wireguard::set_section_keys(&new_content, "[Peer]", &[("PresharedKey", &peer.preshared_key)]);
+    if new_content.trim_end() == content.trim_end() {
+        return Ok(false);
+    }
+    wireguard::write_private(Path::new(&path), &new_content)?;
+    log::info!("Updated {} from the router's peer list", config.name);
+    Ok(true)
+}
+
+// A key's value in the first section of that name
+fn value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut current = "";
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            if current == section {
+                break;
+            }
+            current = line;
+            continue;
+        }
+        if current != section {
+            continue;
+        }
+        // Split at the first '=' only, keys end in base64 padding
+        if let Some((k, v)) = line.split_once('=') {
+            if k.trim() == key {
+                return Some(v.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+fn split(list: &str) -> impl Iterator<Item = String> + '_ {
+    list.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty())
+}
+
+fn escape_xml(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape_xml(value: &str) -> String {
+    value.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peers() -> Vec<RouterPeer> {
+        vec![
+            RouterPeer {
+                name: "Laptop \"work\" & <home>".to_string(),
+                public_key: "xTIBA5rboUvnH4htodjb6e697QjLERt1NAB4mZqp8Dg=".to_string(),
+                preshared_key: "FpCyhws9cxwWoV4xELtfJvjJN+zQVRPISllRWgeopVE=".to_string(),
+                allowed_ips: "10.0.0.2/32,fd00::2/128".to_string(),
+                keepalive: "25".to_string(),
+            },
+            RouterPeer {
+                name: "O'Brien's phone".to_string(),
+                public_key: "TrMvSoP4jYQlY6RIzBgbssQqY3vxI2Pi+y71lOWWXX0=".to_string(),
+                preshared_key: String::new(),
+                allowed_ips: "10.0.0.3/32".to_string(),
+                keepalive: String::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn every_format_reads_back_what_it_writes() {
+        for format in RouterFormat::ALL {
+            assert_eq!(parse(&render(&peers(), format, "wg0")), peers(), "{}", format.label());
+        }
+    }
+
+    #[test]
+    fn reads_a_mikrotik_export() {
+        let export = "\
+# oct/16/2026 10:00:00 by RouterOS 7.15
+/interface wireguard
+add listen-port=13231 mtu=1420 name=wg0
+/interface wireguard peers
+add allowed-address=10.0.0.2/32 comment=laptop interface=wg0 \\
+    persistent-keepalive=25s public-key=\"xTIBA5rboUvnH4htodjb6e697QjLERt1NAB4mZqp8Dg=\"
+add allowed-address=10.0.0.4/32 interface=wg0 name=tablet public-key=\"k7Xw2dw3lDfqDXyvbMxmPVh2dDb3i58dyWHjHT6mdFs=\"
+/ip address
+add address=10.0.0.1/24 interface=wg0 network=10.0.0.0
+";
+        let peers = parse(export);
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].name, "laptop");
+        assert_eq!(peers[0].keepalive, "25");
+        assert_eq!(peers[0].allowed_ips, "10.0.0.2/32");
+        assert_eq!(peers[1].name, "tablet");
+        assert_eq!(peers[1].public_key, "k7Xw2dw3lDfqDXyvbMxmPVh2dDb3i58dyWHjHT6mdFs=");
+
+        let one_line = "/interface wireguard peers add public-key=\"abc=\" allowed-address=10.0.0.5/32";
+        assert_eq!(parse(one_line)[0].allowed_ips, "10.0.0.5/32");
+    }
+
+    #[test]
+    fn reads_only_wireguard_sections_of_openwrt() {
+        let network = "\
+config interface 'wg0'
+\toption proto 'wireguard'
+\toption private_key 'not a peer'
+
+# the laptop
+config wireguard_wg0
+\toption description \"laptop\"
+\toption public_key 'xTIBA5rboUvnH4htodjb6e697QjLERt1NAB4mZqp8Dg='
+\tlist allowed_ips '10.0.0.2/32'
+\tlist allowed_ips 'fd00::2/128'
+
+config wireguard_wg0
+\toption description 'no key'
+
+config interface 'lan'
+\toption public_key 'not a peer either'
+";
+        let peers = parse(network);
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers[0].name, "laptop");
+        assert_eq!(peers[0].allowed_ips, "10.0.0.2/32,fd00::2/128");
+    }
+
+    #[test]
+    fn splits_uci_words() {
+        assert_eq!(uci_words("  option description 'it''s' # note"), vec!["option", "description", "its"]);
+        assert_eq!(uci_words("list allowed_ips \"10.0.0.2/32\""), vec!["list", "allowed_ips", "10.0.0.2/32"]);
+        assert_eq!(uci_words("option name ''"), vec!["option", "name", ""]);
+        assert_eq!(uci_words(r"option description 'O'\''Brien'"), vec!["option", "description", "O'Brien"]);
+    }
+}
@@ -0,0 +1,300 @@
+use crate::config::{VpnConfig, WireGuardBackend};
+use crate::errors::{self, ErrorCode};
+use crate::network::{routes, wireguard};
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU8, Ordering};
+use tokio::process::Command;
+
+// WireGuard without the kernel module: boringtun or wireguard-go creates the interface, `wg
+// setconf` hands it the keys and peers, and the addresses and routes wg-quick would set are
+// added with `ip`. A copy shipped next to the executable is preferred over one on the PATH.
+// Linux only; Windows' WireGuard client and wg-quick on macOS bring their own implementation.
+
+// Backend chosen in the settings, switched at runtime by the app
+static BACKEND: AtomicU8 = AtomicU8::new(WireGuardBackend::Auto as u8);
+
+// Keys only wg-quick understands, which `wg setconf` rejects
+const WG_QUICK_KEYS: &[&str] = &["Address", "DNS", "MTU", "Table", "PreUp", "PostUp", "PreDown", "PostDown", "SaveConfig"];
+const DEFAULT_MTU: u32 = 1420;
+
+pub fn backend() -> WireGuardBackend {
+    match BACKEND.load(Ordering::Relaxed) {
+        x if x == WireGuardBackend::Kernel as u8 => WireGuardBackend::Kernel,
+        x if x == WireGuardBackend::Userspace as u8 => WireGuardBackend::Userspace,
+        _ => WireGuardBackend::Auto,
+    }
+}
+
+pub fn set_backend(backend: WireGuardBackend) {
+    BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Implementation {
+    Boringtun,
+    WireguardGo,
+}
+
+impl Implementation {
+    pub fn program(&self) -> &'static str {
+        match self {
+            Implementation::Boringtun => "boringtun-cli",
+            Implementation::WireguardGo => "wireguard-go",
+        }
+    }
+
+    // Both daemonize by default; boringtun would otherwise switch to nobody and lose access to
+    // the socket wg talks to
+    fn args(&self, interface: &str) -> Vec<String> {
+        match self {
+            Implementation::Boringtun => vec!["--disable-drop-privileges".to_string(), interface.to_string()],
+            Implementation::WireguardGo => vec![interface.to_string()],
+        }
+    }
+}
+
+// The first implementation found, next to the executable before the PATH
+pub fn find_implementation() -> Option<(Implementation, PathBuf)> {
+    let bundled_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()));
+    for implementation in [Implementation::Boringtun, Implementation::WireguardGo] {
+        if let Some(path) = bundled_dir.as_ref().map(|dir| dir.join(implementation.program())).filter(|path| path.is_file()) {
+            return Some((implementation, path));
+        }
+        if let Ok(path) = which::which(implementation.program()) {
+            return Some((implementation, path));
+        }
+    }
+    None
+}
+
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux")
+}
+
+fn kernel_module_available() -> bool {
+    std::path::Path::new("/sys/module/wireguard").exists()
+        || std::process::Command::new("modinfo")
+            .arg("wireguard")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+}
+
+// Whether a connect should go through this module rather than wg-quick
+pub fn should_use() -> bool {
+    if !is_supported() {
+        return false;
+    }
+    match backend() {
+        WireGuardBackend::Kernel => false,
+        WireGuardBackend::Userspace => true,
+        WireGuardBackend::Auto => !kernel_module_available() && find_implementation().is_some(),
+    }
+}
+
+// Records the interface and the endpoint route added for it, so disconnecting after a restart
+// still knows the tunnel was set up here
+fn marker_file(config: &VpnConfig) -> PathBuf {
+    client_log::log_path("vpn", &config.name).with_extension("userspace")
+}
+
+pub fn is_active(config: &VpnConfig) -> bool {
+    marker_file(config).exists()
+}
+
+// The control socket is root's, so `wg show` can't be used to check
+pub fn is_up(config: &VpnConfig) -> bool {
+    std::fs::read_to_string(marker_file(config))
+        .ok()
+        .and_then(|marker| marker.lines().next().map(String::from))
+        .is_some_and(|interface| std::path::Path::new("/sys/class/net").join(interface).exists())
+}
+
+// What connect needs from the config file
+#[derive(Debug, Default)]
+struct TunnelConfig {
+    addresses: Vec<String>,
+    mtu: Option<u32>,
+    allowed_ips: Vec<String>,
+    endpoints: Vec<String>,
+    // The file without wg-quick's keys
+    stripped: String,
+}
+
+fn parse_config(content: &str) -> TunnelConfig {
+    let mut tunnel = TunnelConfig::default();
+    let mut section = String::new();
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            section = trimmed.to_string();
+            tunnel.stripped.push_str(line);
+            tunnel.stripped.push('\n');
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once('=') else {
+            tunnel.stripped.push_str(line);
+            tunnel.stripped.push('\n');
+            continue;
+        };
+        let (key, value) = (key.trim(), value.trim());
+        let list = || value.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        match (section.as_str(), key) {
+            ("[Interface]", "Address") => tunnel.addresses.extend(list()),
+            ("[Interface]", "MTU") => tunnel.mtu = value.parse().ok(),
+            ("[Peer]", "AllowedIPs") => tunnel.allowed_ips.extend(list()),
+            ("[Peer]", "Endpoint") => tunnel.endpoints.push(value.to_string()),
+            _ => {}
+        }
+        if !(section == "[Interface]" && WG_QUICK_KEYS.contains(&key)) {
+            tunnel.stripped.push_str(line);
+            tunnel.stripped.push('\n');
+        }
+    }
+    tunnel
+}
+
+pub async fn connect(config: &VpnConfig) -> Result<()> {
+    if !is_supported() {
+        return Err(anyhow::anyhow!("Userspace WireGuard is only available on Linux"));
+    }
+    let (implementation, program) = find_implementation().ok_or_else(|| {
+        errors::error(ErrorCode::VpnClientMissing, "Userspace WireGuard (boringtun-cli or wireguard-go)")
+    })?;
+
+    let config_path = config.resolved_config_path();
+    let interface = wireguard::get_interface_from_config(&config_path).await?;
+    let tunnel = parse_config(&std::fs::read_to_string(&config_path)?);
+
+    // A full tunnel is split into two halves that outrank the default route, with the servers
+    // kept on the old gateway so the encrypted traffic doesn't loop into the tunnel
+    let full_tunnel = tunnel.allowed_ips.iter().any(|cidr| cidr == "0.0.0.0/0");
+    let mut endpoint_routes = Vec::new();
+    if full_tunnel {
+        let gateway = routes::default_gateway().await?;
+        for endpoint in &tunnel.endpoints {
+            if let Ok(addresses) = tokio::net::lookup_host(endpoint.as_str()).await {
+                for address in addresses.filter(|a| a.is_ipv4()) {
+                    endpoint_routes.push(format!("{}/32 via {} dev {}", address.ip(), gateway.address, gateway.interface));
+                }
+            }
+        }
+    }
+
+    // wg setconf reads the keys from a file, only readable by the user and removed right after
+    let stripped_path = client_log::log_path("vpn", &config.name).with_extension("wg");
+    write_private(&stripped_path, &tunnel.stripped)?;
+
+    let mut script = vec![
+        "set -e".to_string(),
+        format!("{} {}", quote(&program.display().to_string()), implementation.args(&interface).join(" ")),
+        // The implementation creates the interface and its control socket in the background
+        format!("for i in $(seq 50); do [ -S /var/run/wireguard/{}.sock ] && break; sleep 0.1; done", interface),
+        format!("wg setconf {} {}", interface, quote(&stripped_path.display().to_string())),
+    ];
+    for address in &tunnel.addresses {
+        let family = if address.contains(':') { "-6" } else { "-4" };
+        script.push(format!("ip {} address add {} dev {}", family, address, interface));
+    }
+    script.push(format!("ip link set mtu {} up dev {}", tunnel.mtu.unwrap_or(DEFAULT_MTU), interface));
+    for route in &endpoint_routes {
+        script.push(format!("ip route replace {}", route));
+    }
+    for cidr in &tunnel.allowed_ips {
+        match cidr.as_str() {
+            "0.0.0.0/0" => {
+                script.push(format!("ip route replace 0.0.0.0/1 dev {}", interface));
+                script.push(format!("ip route replace 128.0.0.0/1 dev {}", interface));
+            }
+            "::/0" => {
+                script.push(format!("ip -6 route replace ::/1 dev {}", interface));
+                script.push(format!("ip -6 route replace 8000::/1 dev {}", interface));
+            }
+            cidr => {
+                let family = if cidr.contains(':') { "-6" } else { "-4" };
+                script.push(format!("ip {} route replace {} dev {}", family, cidr, interface));
+            }
+        }
+    }
+
+    let result = run(config, &format!("{} up", implementation.program()), &script.join("\n")).await;
+    let _ = std::fs::remove_file(&stripped_path);
+
+    if let Err(e) = result {
+        let _ = run(config, "userspace cleanup", &cleanup_script(&interface, &endpoint_routes)).await;
+        return Err(e);
+    }
+
+    let mut marker = vec![interface];
+    marker.extend(endpoint_routes);
+    std::fs::write(marker_file(config), marker.join("\n"))?;
+    Ok(())
+}
+
+pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    let marker = std::fs::read_to_string(marker_file(config))
+        .map_err(|_| anyhow::anyhow!("{} was not connected with userspace WireGuard", config.name))?;
+    let mut lines = marker.lines().map(String::from);
+    let interface = lines.next().unwrap_or_default();
+    let endpoint_routes: Vec<String> = lines.collect();
+
+    run(config, "userspace down", &cleanup_script(&interface, &endpoint_routes)).await?;
+    let _ = std::fs::remove_file(marker_file(config));
+    Ok(())
+}
+
+// Deleting the interface also ends the implementation's process and its routes; each step may
+// already be gone
+fn cleanup_script(interface: &str, endpoint_routes: &[String]) -> String {
+    let mut script: Vec<String> = endpoint_routes.iter()
+        .map(|route| format!("ip route del {} 2>/dev/null", route))
+        .collect();
+    script.push(format!("ip link del {} 2>/dev/null", interface));
+    script.push(format!("rm -f /var/run/wireguard/{}.sock", interface));
+    script.push("true".to_string());
+    script.join("\n")
+}
+
+fn write_private(path: &std::path::Path, content: &str) -> Result<()> {
+    use std::io::Write;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+async fn run(config: &VpnConfig, label: &str, script: &str) -> Result<()> {
+    let output = executor::output(Command::new("sudo")
+        .args(["sh", "-c", script])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null()))
+        .await?;
+    client_log::record("vpn", &config.name, label, &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "Failed to set up userspace WireGuard: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -1,18 +1,32 @@
+use crate::config::lint::Severity;
 use crate::config::VpnConfig;
+use crate::errors::{self, ErrorCode};
+use crate::network::{routes, wg_userspace};
+use crate::system::{client_log, executor};
 use anyhow::Result;
+use base64::Engine;
 use tokio::process::Command;
 use std::path::Path;
+use std::time::Duration;
 use which;
 
 pub async fn check_connection_status(config: &VpnConfig) -> Result<bool> {
-    let interface_name = get_interface_from_config(&config.config_path).await?;
+    if wg_userspace::is_active(config) {
+        return Ok(wg_userspace::is_up(config));
+    }
+    let interface_name = get_interface_from_config(&config.resolved_config_path()).await?;
     get_status(&interface_name).await
 }
 
 pub async fn connect(config: &VpnConfig) -> Result<()> {
     // Check if config file exists
-    if !Path::new(&config.config_path).exists() {
-        return Err(anyhow::anyhow!("WireGuard config file not found: {}", config.config_path));
+    let config_path = config.resolved_config_path();
+    if !Path::new(&config_path).exists() {
+        return Err(errors::error(ErrorCode::VpnConfigMissing, config_path));
+    }
+    
+    if wg_userspace::should_use() {
+        return wg_userspace::connect(config).await;
     }
     
     #[cfg(windows)]
@@ -50,12 +64,13 @@ pub async fn connect_windows(config: &VpnConfig) -> Result<()> {
     }
     
     let wireguard_exe = wireguard_exe.ok_or_else(|| {
-        anyhow::anyhow!("WireGuard executable not found. Please install WireGuard from https://www.wireguard.com/install/")
+        errors::error(ErrorCode::VpnClientMissing, "WireGuard")
     })?;
     
     // On Windows, use the WireGuard service
     let mut cmd = Command::new(&wireguard_exe);
-    cmd.args(&["/installtunnelservice", &config.config_path])
+    cmd.args(connect_args(config))
+        .envs(&config.env_vars)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .stdin(std::process::Stdio::null());
@@ -66,12 +81,13 @@ pub async fn connect_windows(config: &VpnConfig) -> Result<()> {
         cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
     }
     
-    let output = cmd.output().await?;
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, "wireguard /installtunnelservice", &output);
     
     if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to start WireGuard: {}",
-            String::from_utf8_lossy(&output.stderr)
+        return Err(errors::error(
+            ErrorCode::VpnClientFailed,
+            format!("WireGuard: {}", String::from_utf8_lossy(&output.stderr).trim())
         ));
     }
     
@@ -81,22 +97,48 @@ pub async fn connect_windows(config: &VpnConfig) -> Result<()> {
 #[cfg(unix)]
 pub async fn connect_unix(config: &VpnConfig) -> Result<()> {
     // Use wg-quick to bring up the interface
-    let output = Command::new("sudo")
-        .args(&["wg-quick", "up", &config.config_path])
-        .output()
+    let output = executor::output(Command::new("sudo")
+        .args(connect_args(config))
+        .envs(&config.env_vars))
         .await?;
+    client_log::record("vpn", &config.name, "wg-quick up", &output);
     
     if !output.status.success() {
-        return Err(anyhow::anyhow!(
-            "Failed to start WireGuard: {}",
-            String::from_utf8_lossy(&output.stderr)
+        return Err(errors::error(
+            ErrorCode::VpnClientFailed,
+            format!("WireGuard: {}", String::from_utf8_lossy(&output.stderr).trim())
         ));
     }
     
     Ok(())
 }
 
+// Arguments for the connect invocation (wireguard.exe on Windows, sudo on Unix),
+// shared with the command preview
+pub fn connect_args(config: &VpnConfig) -> Vec<String> {
+    let config_path = config.resolved_config_path();
+    
+    if cfg!(windows) {
+        return vec!["/installtunnelservice".to_string(), config_path];
+    }
+    
+    let mut args = Vec::new();
+    
+    // sudo resets the environment, so explicitly keep the per-entry variables
+    if !config.env_vars.is_empty() {
+        let keys: Vec<&str> = config.env_vars.keys().map(|k| k.as_str()).collect();
+        args.push(format!("--preserve-env={}", keys.join(",")));
+    }
+    
+    args.extend(["wg-quick".to_string(), "up".to_string(), config_path]);
+    args
+}
+
 pub async fn disconnect(config: &VpnConfig) -> Result<()> {
+    if wg_userspace::is_active(config) {
+        return wg_userspace::disconnect(config).await;
+    }
+    
     #[cfg(windows)]
     {
         disconnect_windows(config).await
@@ -110,7 +152,7 @@ pub async fn disconnect(config: &VpnConfig) -> Result<()> {
 
 #[cfg(windows)]
 pub async fn disconnect_windows(config: &VpnConfig) -> Result<()> {
-    let interface_name = get_interface_from_config(&config.config_path).await?;
+    let interface_name = get_interface_from_config(&config.resolved_config_path()).await?;
     
     // Find WireGuard executable
     let wireguard_paths = vec![
@@ -134,7 +176,7 @@ pub async fn disconnect_windows(config: &VpnConfig) -> Result<()> {
     }
     
     let wireguard_exe = wireguard_exe.ok_or_else(|| {
-        anyhow::anyhow!("WireGuard executable not found. Please install WireGuard from https://www.wireguard.com/install/")
+        errors::error(ErrorCode::VpnClientMissing, "WireGuard")
     })?;
     
     let mut cmd = Command::new(&wireguard_exe);
@@ -149,7 +191,8 @@ pub async fn disconnect_windows(config: &VpnConfig) -> Result<()> {
         cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
     }
     
-    let output = cmd.output().await?;
+    let output = executor::output(&mut cmd).await?;
+    client_log::record("vpn", &config.name, "wireguard /uninstalltunnelservice", &output);
     
     if !output.status.success() {
         return Err(anyhow::anyhow!(
@@ -164,10 +207,10 @@ pub async fn disconnect_windows(config: &VpnConfig) -> Result<()> {
 #[cfg(unix)]
 pub async fn disconnect_unix(config: &VpnConfig) -> Result<()> {
     // Use wg-quick to bring down the interface
-    let output = Command::new("sudo")
-        .args(&["wg-quick", "down", &config.config_path])
-        .output()
+    let output = executor::output(Command::new("sudo")
+        .args(["wg-quick", "down", &config.resolved_config_path()]))
         .await?;
+    client_log::record("vpn", &config.name, "wg-quick down", &output);
     
     if !output.status.success() {
         return Err(anyhow::anyhow!(
@@ -213,13 +256,155 @@ pub async fn get_status_windows(interface_name: &str) -> Result<bool> {
 #[cfg(unix)]
 pub async fn get_status_unix(interface_name: &str) -> Result<bool> {
     let output = Command::new("wg")
-        .args(&["show", interface_name])
+        .args(["show", &device_name(interface_name)])
         .output()
         .await?;
     
     Ok(output.status.success())
 }
 
+// Bytes received and sent over the interface, summed over its peers
+pub async fn transfer(interface_name: &str) -> Result<(u64, u64)> {
+    let mut cmd = Command::new("wg");
+    cmd.args(["show", &device_name(interface_name), "transfer"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+    
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("wg show failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    // One line per peer: public key, bytes received, bytes sent
+    let mut totals = (0, 0);
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if let [_, received, sent] = fields[..] {
+            totals.0 += received.parse::<u64>().unwrap_or(0);
+            totals.1 += sent.parse::<u64>().unwrap_or(0);
+        }
+    }
+    Ok(totals)
+}
+
+// The newest handshake across the interface's peers, None if there was none yet, and the bytes
+// sent so far
+#[derive(Debug, Clone, Copy)]
+pub struct Handshake {
+    pub age: Option<Duration>,
+    pub sent: u64,
+}
+
+pub async fn handshake(interface_name: &str) -> Result<Handshake> {
+    let mut cmd = Command::new("wg");
+    cmd.args(["show", &device_name(interface_name), "latest-handshakes"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+    
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("wg show failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    // One line per peer: public key, seconds since the epoch or 0 for never
+    let latest = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split_whitespace().nth(1)?.parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .max();
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH)?.as_secs();
+    let (_, sent) = transfer(interface_name).await?;
+    
+    Ok(Handshake {
+        age: latest.map(|secs| Duration::from_secs(now.saturating_sub(secs))),
+        sent,
+    })
+}
+
+// Sets each peer's endpoint again, which resolves its host name anew and makes the next packet
+// start a fresh handshake
+pub async fn rehandshake(config: &VpnConfig) -> Result<()> {
+    let config_path = config.resolved_config_path();
+    let interface_name = device_name(&get_interface_from_config(&config_path).await?);
+    let content = std::fs::read_to_string(&config_path)?;
+    
+    let mut peers = Vec::new();
+    let mut public_key = None;
+    for line in content.lines().map(|line| line.trim()) {
+        if line.starts_with('[') {
+            public_key = None;
+        } else if let Some((key, value)) = line.split_once('=') {
+            match key.trim() {
+                "PublicKey" => public_key = Some(value.trim().to_string()),
+                "Endpoint" => {
+                    if let Some(public_key) = &public_key {
+                        peers.push((public_key.clone(), value.trim().to_string()));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    if peers.is_empty() {
+        return Err(anyhow::anyhow!("No peer with an endpoint in {}", config_path));
+    }
+    
+    for (public_key, endpoint) in peers {
+        let args = ["set", interface_name.as_str(), "peer", public_key.as_str(), "endpoint", endpoint.as_str()];
+        #[cfg(windows)]
+        let output = {
+            use std::os::windows::process::CommandExt;
+            let mut cmd = Command::new("wg");
+            cmd.args(&args)
+                .stdout(std::process::Stdio::piped())
+                .stderr(std::process::Stdio::piped())
+                .stdin(std::process::Stdio::null());
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+            executor::output(&mut cmd).await?
+        };
+        #[cfg(unix)]
+        let output = executor::output(Command::new("sudo").arg("wg").args(args)).await?;
+        client_log::record("vpn", &config.name, "wg set endpoint", &output);
+        
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Failed to re-handshake: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    
+    Ok(())
+}
+
+// wg-quick on macOS runs the tunnel in wireguard-go on the next free utun device and notes
+// which in its run folder, and wg only knows the tunnel by that; elsewhere the device carries
+// the interface's name
+pub fn device_name(interface_name: &str) -> String {
+    if cfg!(target_os = "macos") {
+        if let Ok(utun) = std::fs::read_to_string(format!("/var/run/wireguard/{}.name", interface_name)) {
+            if !utun.trim().is_empty() {
+                return utun.trim().to_string();
+            }
+        }
+    }
+    interface_name.to_string()
+}
+
 pub async fn get_interface_from_config(config_path: &str) -> Result<String> {
     let content = std::fs::read_to_string(config_path)?;
     
@@ -315,6 +500,182 @@ pub async fn list_interfaces_unix() -> Result<Vec<String>> {
     Ok(interfaces)
 }
 
+#[derive(Debug, Clone)]
+pub struct KeyPair {
+    pub private_key: String,
+    pub public_key: String,
+}
+
+pub async fn generate_keypair() -> Result<KeyPair> {
+    let mut genkey = Command::new("wg");
+    genkey.arg("genkey")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        genkey.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let output = genkey.output().await
+        .map_err(|e| anyhow::anyhow!("Failed to run wg, is wireguard-tools installed? {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("wg genkey failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let private_key = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let public_key = public_key(&private_key).await?;
+    
+    Ok(KeyPair { private_key, public_key })
+}
+
+// The public key belonging to a private key
+pub async fn public_key(private_key: &str) -> Result<String> {
+    use tokio::io::AsyncWriteExt;
+    
+    // wg pubkey reads the private key from stdin, keeping it off the command line
+    let mut pubkey = Command::new("wg");
+    pubkey.arg("pubkey")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .stdin(std::process::Stdio::piped());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        pubkey.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let mut child = pubkey.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(format!("{}\n", private_key).as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("wg pubkey failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+// Where a new config for an entry goes: the user's WireGuard directory, which
+// get_available_configs also lists. The file name becomes the interface name, so it is kept
+// to the 15 characters Linux allows.
+pub fn new_config_path(entry_name: &str) -> std::path::PathBuf {
+    #[cfg(windows)]
+    let dir = std::path::PathBuf::from(std::env::var("APPDATA").unwrap_or_default()).join("WireGuard");
+    #[cfg(unix)]
+    let dir = std::path::PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(".config").join("wireguard");
+    
+    let interface: String = entry_name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .take(15)
+        .collect::<String>()
+        .to_lowercase();
+    let interface = if interface.is_empty() { "wg0".to_string() } else { interface };
+    dir.join(format!("{}.conf", interface))
+}
+
+// Puts the private key into the config's [Interface] section, creating the file if needed,
+// readable only by the user. An existing key is never replaced.
+pub fn set_private_key(config_path: &str, private_key: &str) -> Result<()> {
+    if has_private_key(config_path) {
+        return Err(anyhow::anyhow!("{} already has a private key", config_path));
+    }
+    let content = std::fs::read_to_string(config_path).unwrap_or_default();
+    
+    let key_line = format!("PrivateKey = {}", private_key);
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    match lines.iter().position(|line| line.trim() == "[Interface]") {
+        Some(index) => lines.insert(index + 1, key_line),
+        None => {
+            if !lines.is_empty() {
+                lines.insert(0, String::new());
+            }
+            lines.insert(0, key_line);
+            lines.insert(0, "[Interface]".to_string());
+        }
+    }
+    write_private(Path::new(config_path), &(lines.join("\n") + "\n"))
+}
+
+pub fn has_private_key(config_path: &str) -> bool {
+    std::fs::read_to_string(config_path)
+        .is_ok_and(|content| content.lines().any(|line| line.split('=').next().is_some_and(|key| key.trim() == "PrivateKey")))
+}
+
+// Writes the builder's fields into the config, creating it if needed. Only the fields the
+// builder knows are touched, in the [Interface] section and the first [Peer]; the private key
+// and anything else already in the file stay. An empty field removes its line.
+pub fn write_config(config_path: &str, info: &WireGuardConfigInfo) -> Result<()> {
+    info.validate()?;
+    let content = std::fs::read_to_string(config_path).unwrap_or_default();
+    let content = set_section_keys(&content, "[Interface]", &[("Address", &info.address), ("DNS", &info.dns)]);
+    let content = set_section_keys(&content, "[Peer]", &[
+        ("PublicKey", &info.public_key),
+        ("Endpoint", &info.endpoint),
+        ("AllowedIPs", &info.allowed_ips),
+        ("PersistentKeepalive", &info.persistent_keepalive),
+    ]);
+    write_private(Path::new(config_path), &content)
+}
+
+// Sets the keys in the first section with this header, which is appended if missing
+pub fn set_section_keys(content: &str, section: &str, values: &[(&str, &str)]) -> String {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let start = match lines.iter().position(|line| line.trim() == section) {
+        Some(index) => index,
+        None => {
+            if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(section.to_string());
+            lines.len() - 1
+        }
+    };
+    let end = lines[start + 1..].iter()
+        .position(|line| line.trim().starts_with('['))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    
+    let mut section_lines = lines[start + 1..end].to_vec();
+    for (key, value) in values {
+        let value = value.trim();
+        let existing = section_lines.iter().position(|line| line.split_once('=').is_some_and(|(k, _)| k.trim() == *key));
+        match existing {
+            Some(index) if value.is_empty() => {
+                section_lines.remove(index);
+            }
+            Some(index) => section_lines[index] = format!("{} = {}", key, value),
+            None if value.is_empty() => {}
+            None => {
+                // Before the blank lines separating it from the next section
+                let at = section_lines.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |index| index + 1);
+                section_lines.insert(at, format!("{} = {}", key, value));
+            }
+        }
+    }
+    lines.splice(start + 1..end, section_lines);
+    lines.join("\n") + "\n"
+}
+
+// Config files hold the private key, so only the user may read them
+pub fn write_private(path: &Path, content: &str) -> Result<()> {
+    use std::io::Write;
+    
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
+
 pub fn get_available_configs() -> Result<Vec<String>> {
     let mut configs = Vec::new();
     
@@ -343,10 +704,14 @@ pub fn get_available_configs() -> Result<Vec<String>> {
         let home_dir = std::env::var("HOME").unwrap_or_default();
         let user_config_dir = format!("{}/.config/wireguard", home_dir);
         
-        let config_dirs = vec![
+        let mut config_dirs = vec![
             "/etc/wireguard",
             &user_config_dir,
         ];
+        // Where wg-quick from Homebrew looks for configs on macOS
+        if cfg!(target_os = "macos") {
+            config_dirs.extend(["/opt/homebrew/etc/wireguard", "/usr/local/etc/wireguard"]);
+        }
         
         for config_dir in config_dirs {
             if let Ok(entries) = std::fs::read_dir(config_dir) {
@@ -363,16 +728,107 @@ pub fn get_available_configs() -> Result<Vec<String>> {
     Ok(configs)
 }
 
-pub fn validate_config(config_path: &str) -> Result<bool> {
+// What wg-quick would reject or the tunnel would get wrong, for the add form and the Problems tab.
+// Err only when the file can't be read.
+pub fn validate_config(config_path: &str) -> Result<Vec<(Severity, String)>> {
     let content = std::fs::read_to_string(config_path)?;
-    
-    // Basic validation - check for required sections
-    let has_interface = content.contains("[Interface]");
-    let has_peer = content.contains("[Peer]");
-    let has_private_key = content.contains("PrivateKey");
-    let has_public_key = content.contains("PublicKey");
-    
-    Ok(has_interface && has_peer && has_private_key && has_public_key)
+    let mut problems = Vec::new();
+    let mut error = |message: String| problems.push((Severity::Error, message));
+
+    // Section name and its key/value lines, in file order
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            sections.push((line.to_string(), Vec::new()));
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            error(format!("Not a key = value line: {}", line));
+            continue;
+        };
+        match sections.last_mut() {
+            Some((_, entries)) => entries.push((key.trim().to_string(), value.trim().to_string())),
+            None => error(format!("{} is outside of any section", key.trim())),
+        }
+    }
+
+    let interfaces: Vec<&Vec<(String, String)>> = sections.iter().filter(|(name, _)| name == "[Interface]").map(|(_, e)| e).collect();
+    let peers: Vec<&Vec<(String, String)>> = sections.iter().filter(|(name, _)| name == "[Peer]").map(|(_, e)| e).collect();
+    for (name, _) in sections.iter().filter(|(name, _)| name != "[Interface]" && name != "[Peer]") {
+        problems.push((Severity::Error, format!("Unknown section {}", name)));
+    }
+
+    match interfaces.as_slice() {
+        [] => problems.push((Severity::Error, "No [Interface] section".to_string())),
+        [interface] => {
+            let value = |key: &str| interface.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+            match value("PrivateKey") {
+                None => problems.push((Severity::Error, "The interface has no PrivateKey".to_string())),
+                Some(key) if !is_valid_key(key) => problems.push((Severity::Error, "The interface's PrivateKey is not a valid key (44 characters of base64)".to_string())),
+                Some(_) => {}
+            }
+            match value("Address") {
+                None => problems.push((Severity::Warning, "The interface has no Address".to_string())),
+                Some(addresses) => {
+                    for address in addresses.split(',').map(str::trim).filter(|a| routes::parse_cidr(a).is_none()) {
+                        problems.push((Severity::Error, format!("Address is not an IP address or network: {}", address)));
+                    }
+                }
+            }
+            if let Some(port) = value("ListenPort").filter(|port| port.parse::<u16>().is_err()) {
+                problems.push((Severity::Error, format!("ListenPort is not a port number: {}", port)));
+            }
+        }
+        _ => problems.push((Severity::Error, "More than one [Interface] section".to_string())),
+    }
+
+    if peers.is_empty() {
+        problems.push((Severity::Error, "No [Peer] section".to_string()));
+    }
+    for (n, peer) in peers.iter().enumerate() {
+        let label = if peers.len() == 1 { "The peer".to_string() } else { format!("Peer {}", n + 1) };
+        let value = |key: &str| peer.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+        let mut problem = |severity, message: String| problems.push((severity, format!("{} {}", label, message)));
+        match value("PublicKey") {
+            None => problem(Severity::Error, "has no PublicKey".to_string()),
+            Some(key) if !is_valid_key(key) => problem(Severity::Error, "has a PublicKey that is not a valid key".to_string()),
+            Some(_) => {}
+        }
+        if value("PresharedKey").is_some_and(|key| !is_valid_key(key)) {
+            problem(Severity::Error, "has a PresharedKey that is not a valid key".to_string());
+        }
+        match value("AllowedIPs") {
+            None => problem(Severity::Warning, "has no AllowedIPs, no traffic goes through it".to_string()),
+            Some(allowed) => {
+                for network in allowed.split(',').map(str::trim).filter(|n| !n.is_empty() && routes::parse_cidr(n).is_none()) {
+                    problem(Severity::Error, format!("has an AllowedIPs entry that is not a network: {}", network));
+                }
+            }
+        }
+        match value("Endpoint") {
+            None => problem(Severity::Warning, "has no Endpoint, the tunnel waits for the server to connect".to_string()),
+            Some(endpoint) => {
+                let port = endpoint.rsplit_once(':').and_then(|(host, port)| (!host.is_empty()).then_some(port));
+                if port.and_then(|port| port.parse::<u16>().ok()).is_none() {
+                    problem(Severity::Error, format!("has an Endpoint that is not host:port: {}", endpoint));
+                }
+            }
+        }
+        if let Some(keepalive) = value("PersistentKeepalive").filter(|k| *k != "off" && k.parse::<u16>().is_err()) {
+            problem(Severity::Error, format!("has a PersistentKeepalive that is not a number of seconds: {}", keepalive));
+        }
+    }
+
+    Ok(problems)
+}
+
+// A key as wg prints it: 32 bytes in base64
+pub fn is_valid_key(key: &str) -> bool {
+    base64::engine::general_purpose::STANDARD.decode(key.trim()).is_ok_and(|bytes| bytes.len() == 32)
 }
 
 pub fn get_config_info(config_path: &str) -> Result<WireGuardConfigInfo> {
@@ -380,45 +836,66 @@ pub fn get_config_info(config_path: &str) -> Result<WireGuardConfigInfo> {
     let mut info = WireGuardConfigInfo::default();
     
     let mut current_section = "";
+    let mut peer_seen = false;
     
     for line in content.lines() {
         let line = line.trim();
         
         if line.starts_with('[') && line.ends_with(']') {
+            peer_seen |= current_section == "[Peer]";
             current_section = line;
             continue;
         }
         
-        if current_section == "[Interface]" {
-            if line.starts_with("Address") {
-                if let Some(address) = line.split('=').nth(1) {
-                    info.address = address.trim().to_string();
-                }
-            } else if line.starts_with("DNS") {
-                if let Some(dns) = line.split('=').nth(1) {
-                    info.dns = dns.trim().to_string();
-                }
-            }
-        } else if current_section == "[Peer]" {
-            if line.starts_with("Endpoint") {
-                if let Some(endpoint) = line.split('=').nth(1) {
-                    info.endpoint = endpoint.trim().to_string();
-                }
-            } else if line.starts_with("AllowedIPs") {
-                if let Some(allowed_ips) = line.split('=').nth(1) {
-                    info.allowed_ips = allowed_ips.trim().to_string();
-                }
-            }
+        // Split at the first '=' only, keys end in base64 padding
+        let Some((key, value)) = line.split_once('=') else { continue };
+        let value = value.trim().to_string();
+        match (current_section, key.trim()) {
+            ("[Interface]", "Address") => info.address = value,
+            ("[Interface]", "DNS") => info.dns = value,
+            // Only the first peer, the one write_config manages
+            ("[Peer]", "PublicKey") if !peer_seen => info.public_key = value,
+            ("[Peer]", "Endpoint") if !peer_seen => info.endpoint = value,
+            ("[Peer]", "AllowedIPs") if !peer_seen => info.allowed_ips = value,
+            ("[Peer]", "PersistentKeepalive") if !peer_seen => info.persistent_keepalive = value,
+            _ => {}
         }
     }
     
     Ok(info)
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct WireGuardConfigInfo {
     pub address: String,
     pub dns: String,
+    // The peer's, i.e. the server's
+    pub public_key: String,
     pub endpoint: String,
     pub allowed_ips: String,
+    // Seconds, empty for none
+    pub persistent_keepalive: String,
+}
+
+impl WireGuardConfigInfo {
+    // What wg-quick would reject, with the first problem found
+    pub fn validate(&self) -> Result<()> {
+        if self.address.trim().is_empty() {
+            return Err(anyhow::anyhow!("The interface needs an address, e.g. 10.0.0.2/32"));
+        }
+        if !is_valid_key(&self.public_key) {
+            return Err(anyhow::anyhow!("The peer's public key should be 44 characters of base64"));
+        }
+        let port = self.endpoint.trim().rsplit_once(':').and_then(|(host, port)| (!host.is_empty()).then_some(port));
+        if port.and_then(|port| port.parse::<u16>().ok()).is_none() {
+            return Err(anyhow::anyhow!("The endpoint should be host:port, e.g. vpn.example.com:51820"));
+        }
+        if self.allowed_ips.trim().is_empty() {
+            return Err(anyhow::anyhow!("Allowed IPs can't be empty; 0.0.0.0/0 sends everything through the tunnel"));
+        }
+        if !self.persistent_keepalive.trim().is_empty() && self.persistent_keepalive.trim().parse::<u16>().is_err() {
+            return Err(anyhow::anyhow!("Keepalive should be a number of seconds"));
+        }
+        Ok(())
+    }
 }
\ No newline at end of file
@@ -1,84 +1,157 @@
-use crate::config::WolDevice;
+use crate::config::{SshConfig, WolDevice};
+use crate::errors::{self, ErrorCode};
 use crate::network::monitor::{get_network_interfaces, NetworkInterface};
+use crate::network::{routes, ssh};
 use anyhow::Result;
+use std::process::Stdio;
 use wake_on_lan::MagicPacket;
 
 pub async fn wake_device(device: &WolDevice) -> Result<()> {
     let mac_bytes = parse_mac_address(&device.mac_address)?;
+    if let Some(warning) = route_warning(device).await {
+        log::warn!("Wake {}: {}", device.name, warning);
+    }
     let magic_packet = MagicPacket::new(&mac_bytes);
     
     let socket = std::net::UdpSocket::bind("0.0.0.0:0")?;
     socket.set_broadcast(true)?;
     
     let mut sent_count = 0;
-    let mut errors = Vec::new();
-    
-    // Send to global broadcast
-    let global_broadcast = format!("255.255.255.255:{}", device.port);
-    match socket.send_to(magic_packet.magic_bytes(), &global_broadcast) {
-        Ok(_) => {
-            sent_count += 1;
-            log::info!("WoL packet sent to global broadcast {}", global_broadcast);
-        }
-        Err(e) => {
-            errors.push(format!("Global broadcast failed: {}", e));
-            log::warn!("Failed to send WoL packet to global broadcast: {}", e);
-        }
-    }
+    let mut failures = Vec::new();
     
-    // Send to device IP if specified
-    if !device.ip_address.is_empty() && device.ip_address != "255.255.255.255" {
-        let device_addr = format!("{}:{}", device.ip_address, device.port);
-        match socket.send_to(magic_packet.magic_bytes(), &device_addr) {
+    for target in wake_targets(device).await {
+        match socket.send_to(magic_packet.magic_bytes(), &target) {
             Ok(_) => {
                 sent_count += 1;
-                log::info!("WoL packet sent to device IP {}", device_addr);
+                log::info!("WoL packet sent to {}", target);
             }
             Err(e) => {
-                errors.push(format!("Device IP failed: {}", e));
-                log::warn!("Failed to send WoL packet to device IP: {}", e);
+                failures.push(format!("{} failed: {}", target, e));
+                log::warn!("Failed to send WoL packet to {}: {}", target, e);
             }
         }
     }
     
-    // Send to all possible network broadcast addresses
+    if sent_count > 0 {
+        log::info!("Successfully sent {} WoL packets for device {}", sent_count, device.name);
+        Ok(())
+    } else {
+        Err(errors::error(ErrorCode::WakeFailed, failures.join(", ")))
+    }
+}
+
+// Every address the magic packet goes to: the global broadcast, the device itself,
+// and the broadcast address of each local network
+pub async fn wake_targets(device: &WolDevice) -> Vec<String> {
+    let mut targets = vec![format!("255.255.255.255:{}", device.port)];
+    
+    if !device.ip_address.is_empty() && device.ip_address != "255.255.255.255" {
+        targets.push(format!("{}:{}", device.ip_address, device.port));
+    }
+    
     if let Ok(interfaces) = get_network_interfaces().await {
         for interface in interfaces {
             if let Ok(broadcast_addr) = calculate_broadcast_address(&interface.ip_address) {
-                let broadcast_target = format!("{}:{}", broadcast_addr, device.port);
-                match socket.send_to(magic_packet.magic_bytes(), &broadcast_target) {
-                    Ok(_) => {
-                        sent_count += 1;
-                        log::info!("WoL packet sent to network broadcast {}", broadcast_target);
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to send WoL packet to {}: {}", broadcast_target, e);
-                    }
-                }
+                targets.push(format!("{}:{}", broadcast_addr, device.port));
             }
         }
     }
     
-    if sent_count > 0 {
-        log::info!("Successfully sent {} WoL packets for device {}", sent_count, device.name);
-        Ok(())
-    } else {
-        Err(anyhow::anyhow!("Failed to send any WoL packets. Errors: {}", errors.join(", ")))
+    targets
+}
+
+// A device on a remote LAN is woken with a packet to its own address, which should travel
+// through the VPN to that network. If it would leave through the default gateway instead, it
+// goes out to the ISP, never arrives, and shows the MAC address along the way. Still sent, as
+// a router on the LAN may be the gateway to another local subnet.
+pub async fn route_warning(device: &WolDevice) -> Option<String> {
+    if device.ip_address.is_empty() || device.ip_address == "255.255.255.255" {
+        return None;
+    }
+    let address: std::net::IpAddr = device.ip_address.parse().ok()?;
+    let route = routes::route_to(address)
+        .await
+        .map_err(|e| log::debug!("Route lookup for {} failed: {}", address, e))
+        .ok()?;
+    let gateway = routes::default_gateway().await.ok()?;
+    
+    (route.interface == gateway.interface && route.via == Some(gateway.address)).then(|| {
+        format!(
+            "the packet to {} leaves through the default gateway {} instead of a VPN tunnel; connect the VPN for that network first",
+            address, gateway.address
+        )
+    })
+}
+
+// Runs the wake on the device's relay host. ssh mustn't stop to ask for a password or host key
+// in the background, so it fails instead and the key has to be set up beforehand.
+pub async fn wake_via_relay(device: &WolDevice) -> Result<()> {
+    let relay = device.relay.as_ref()
+        .ok_or_else(|| anyhow::anyhow!("{} has no wake relay", device.name))?;
+    parse_mac_address(&device.mac_address)?;
+    ssh::check_target(relay)?;
+    if which::which("ssh").is_err() {
+        return Err(errors::error(ErrorCode::SshClientMissing, ""));
+    }
+    
+    let mut cmd = tokio::process::Command::new("ssh");
+    cmd.args(relay_args(device, relay))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        return Err(errors::error(
+            ErrorCode::WakeFailed,
+            format!("relay {}: {}", relay.host, String::from_utf8_lossy(&output.stderr).trim())
+        ));
     }
+    log::info!("WoL packet for {} sent through {}", device.name, relay.host);
+    Ok(())
+}
+
+// ssh arguments for the relay, ending with the command it runs
+pub fn relay_args(device: &WolDevice, relay: &SshConfig) -> Vec<String> {
+    let mut args = vec![
+        "-o".to_string(), "BatchMode=yes".to_string(),
+        "-o".to_string(), "ConnectTimeout=10".to_string(),
+    ];
+    args.extend(ssh::ssh_args(relay));
+    args.push(relay_command(device));
+    args
+}
+
+// The first wake tool the relay has; etherwake needs root, which sudo must grant without asking
+fn relay_command(device: &WolDevice) -> String {
+    let mac = format_mac_address(&device.mac_address);
+    format!(
+        "if command -v wakeonlan >/dev/null; then wakeonlan -p {port} {mac}; \
+         elif command -v etherwake >/dev/null; then sudo -n etherwake {mac}; \
+         elif command -v wol >/dev/null; then wol -p {port} {mac}; \
+         else echo 'No wakeonlan, etherwake or wol on the relay' >&2; exit 127; fi",
+        port = device.port,
+        mac = mac
+    )
 }
 
 fn parse_mac_address(mac_str: &str) -> Result<[u8; 6]> {
     let cleaned = mac_str.replace([':', '-'], "");
     
     if cleaned.len() != 12 {
-        return Err(anyhow::anyhow!("Invalid MAC address format"));
+        return Err(errors::error(ErrorCode::InvalidMac, mac_str));
     }
     
     let mut mac_bytes = [0u8; 6];
     for (i, chunk) in cleaned.chars().collect::<Vec<_>>().chunks(2).enumerate() {
         let hex_str: String = chunk.iter().collect();
         mac_bytes[i] = u8::from_str_radix(&hex_str, 16)
-            .map_err(|_| anyhow::anyhow!("Invalid MAC address format"))?;
+            .map_err(|_| errors::error(ErrorCode::InvalidMac, mac_str))?;
     }
     
     Ok(mac_bytes)
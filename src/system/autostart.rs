@@ -0,0 +1,190 @@
+use anyhow::Result;
+#[cfg(not(windows))]
+use std::path::PathBuf;
+#[cfg(windows)]
+use std::process::Command;
+
+// Starting the app when the user logs in, for the always-on VPN (config::AlwaysOn): an XDG
+// autostart entry on Linux, a Task Scheduler logon task on Windows and a LaunchAgent on macOS.
+// The app is started minimized and connects the always-on profile itself. Only the app is
+// registered here; a tunnel that has to be up before login goes through system::boot or
+// system::services instead.
+
+// Passed to the registered command, see main
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+#[cfg(windows)]
+const TASK_NAME: &str = "VPN Manager";
+#[cfg(windows)]
+const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+#[cfg(target_os = "macos")]
+const AGENT_LABEL: &str = "com.vpn-aio.vpn-manager";
+
+pub fn is_supported() -> bool {
+    cfg!(any(target_os = "linux", windows, target_os = "macos"))
+}
+
+// The file the login session reads
+#[cfg(not(windows))]
+fn entry_path() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        Some(dirs::home_dir()?.join("Library").join("LaunchAgents").join("com.vpn-aio.vpn-manager.plist"))
+    } else if cfg!(target_os = "linux") {
+        Some(dirs::config_dir()?.join("autostart").join("vpn-manager.desktop"))
+    } else {
+        None
+    }
+}
+
+pub fn is_registered() -> bool {
+    #[cfg(windows)]
+    {
+        task_exists() || run_value().is_some()
+    }
+
+    #[cfg(not(windows))]
+    {
+        entry_path().is_some_and(|path| path.exists())
+    }
+}
+
+// Registers the running executable, so a moved or updated app registers anew
+pub fn register() -> Result<()> {
+    let exe = std::env::current_exe()?;
+
+    #[cfg(windows)]
+    {
+        register_windows(&exe)
+    }
+
+    #[cfg(not(windows))]
+    {
+        let path = entry_path().ok_or_else(|| anyhow::anyhow!("Starting at login isn't supported on this platform"))?;
+        let content = entry_content(&exe);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, content)?;
+        log::info!("Registered to start at login in {}", path.display());
+        Ok(())
+    }
+}
+
+pub fn unregister() -> Result<()> {
+    #[cfg(windows)]
+    {
+        let _ = schtasks(&["/Delete", "/TN", TASK_NAME, "/F"]);
+        if run_value().is_some() {
+            let key = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER)
+                .open_subkey_with_flags(RUN_KEY, winreg::enums::KEY_SET_VALUE)?;
+            key.delete_value(TASK_NAME)?;
+        }
+        log::info!("No longer starting at login");
+        Ok(())
+    }
+
+    #[cfg(not(windows))]
+    {
+        if let Some(path) = entry_path().filter(|path| path.exists()) {
+            std::fs::remove_file(&path)?;
+            log::info!("Removed {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn entry_content(exe: &std::path::Path) -> String {
+    // Exec quoting per the desktop entry spec
+    let quoted = exe.display().to_string()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('`', "\\`")
+        .replace('$', "\\$");
+    [
+        "[Desktop Entry]".to_string(),
+        "Type=Application".to_string(),
+        "Name=VPN Manager".to_string(),
+        "Comment=Connects the always-on VPN".to_string(),
+        format!("Exec=\"{}\" {}", quoted, MINIMIZED_ARG),
+        "Terminal=false".to_string(),
+        "X-GNOME-Autostart-enabled=true".to_string(),
+    ]
+    .join("\n") + "\n"
+}
+
+// launchd runs the agent at every login; it doesn't restart the app if it is quit
+#[cfg(target_os = "macos")]
+fn entry_content(exe: &std::path::Path) -> String {
+    let escaped = exe.display().to_string()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \t<key>Label</key>\n\
+         \t<string>{}</string>\n\
+         \t<key>ProgramArguments</key>\n\
+         \t<array>\n\
+         \t\t<string>{}</string>\n\
+         \t\t<string>{}</string>\n\
+         \t</array>\n\
+         \t<key>RunAtLoad</key>\n\
+         \t<true/>\n\
+         \t<key>ProcessType</key>\n\
+         \t<string>Interactive</string>\n\
+         </dict>\n\
+         </plist>\n",
+        AGENT_LABEL, escaped, MINIMIZED_ARG
+    )
+}
+
+// A logon task runs the app elevated without a UAC prompt, which the VPN clients need, but only
+// an elevated app may create one. Otherwise the app goes into the user's Run key and starts
+// unelevated.
+#[cfg(windows)]
+fn register_windows(exe: &std::path::Path) -> Result<()> {
+    let command = format!("\"{}\" {}", exe.display(), MINIMIZED_ARG);
+    match schtasks(&["/Create", "/TN", TASK_NAME, "/TR", &command, "/SC", "ONLOGON", "/RL", "HIGHEST", "/F"]) {
+        Ok(()) => {
+            log::info!("Registered the {} logon task", TASK_NAME);
+            return Ok(());
+        }
+        Err(e) => log::info!("No logon task ({}), using the Run key", e),
+    }
+
+    let (key, _) = winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER).create_subkey(RUN_KEY)?;
+    key.set_value(TASK_NAME, &command)?;
+    log::info!("Registered to start at login in HKCU\\{}", RUN_KEY);
+    Ok(())
+}
+
+#[cfg(windows)]
+fn task_exists() -> bool {
+    schtasks(&["/Query", "/TN", TASK_NAME]).is_ok()
+}
+
+#[cfg(windows)]
+fn run_value() -> Option<String> {
+    winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER)
+        .open_subkey(RUN_KEY)
+        .ok()?
+        .get_value::<String, _>(TASK_NAME)
+        .ok()
+}
+
+#[cfg(windows)]
+fn schtasks(args: &[&str]) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+    let output = Command::new("schtasks")
+        .args(args)
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("schtasks failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use crate::config::{VpnConfig, VpnType};
+use crate::errors::{self, ErrorCode};
+use crate::network::{certs, ikev2, proxy};
+use crate::system::{client_log, executor};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+// Starting VPN entries at boot, before anyone logs in, with a systemd unit per entry. An entry
+// with an enabled unit is managed by the system: connecting and disconnecting from the app start
+// and stop the unit, and a running unit counts as connected.
+
+const UNIT_DIR: &str = "/etc/systemd/system";
+// Credentials for units that can't ask for them, readable by root only
+const SECRETS_DIR: &str = "/etc/vpn-manager";
+
+pub fn is_supported() -> bool {
+    cfg!(target_os = "linux") && which::which("systemctl").is_ok()
+}
+
+// OpenConnect wants its password typed in, which nobody can do at boot
+pub fn supports(vpn_type: &VpnType) -> bool {
+    matches!(vpn_type, VpnType::OpenVpn | VpnType::WireGuard | VpnType::Ikev2)
+}
+
+pub fn unit_name(config: &VpnConfig) -> String {
+    let name: String = config.name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    format!("vpn-manager-{}.service", name)
+}
+
+fn unit_path(config: &VpnConfig) -> PathBuf {
+    Path::new(UNIT_DIR).join(unit_name(config))
+}
+
+fn auth_path(config: &VpnConfig) -> PathBuf {
+    Path::new(SECRETS_DIR).join(unit_name(config).replace(".service", ".auth"))
+}
+
+fn proxy_auth_path(config: &VpnConfig) -> PathBuf {
+    Path::new(SECRETS_DIR).join(unit_name(config).replace(".service", ".proxy-auth"))
+}
+
+// Enabling links the unit into the target's wants directory
+pub fn is_enabled(config: &VpnConfig) -> bool {
+    Path::new(UNIT_DIR).join("multi-user.target.wants").join(unit_name(config)).exists()
+}
+
+pub async fn is_active(config: &VpnConfig) -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", &unit_name(config)])
+        .stdin(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+// systemd wants absolute paths to what it runs
+fn program(name: &str) -> Result<String> {
+    which::which(name)
+        .map(|path| path.display().to_string())
+        .map_err(|_| anyhow::anyhow!("{} not found", name))
+}
+
+// Quoted for ExecStart, where % starts a specifier
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"").replace('%', "%%"))
+}
+
+pub fn unit_file(config: &VpnConfig) -> Result<String> {
+    let config_path = config.resolved_config_path();
+    let (oneshot, start, stop) = match config.vpn_type {
+        VpnType::WireGuard => {
+            let wg_quick = program("wg-quick")?;
+            (
+                true,
+                format!("{} up {}", wg_quick, quote(&config_path)),
+                Some(format!("{} down {}", wg_quick, quote(&config_path))),
+            )
+        }
+        VpnType::OpenVpn => {
+            let mut start = format!("{} --config {}", program("openvpn")?, quote(&config_path));
+            if !config.username.is_empty() {
+                start.push_str(&format!(" --auth-user-pass {} --auth-nocache", quote(&auth_path(config).display().to_string())));
+            }
+            for arg in certs::args(config).into_iter().chain(proxy::args_with_auth_file(config, &proxy_auth_path(config))) {
+                start.push_str(&format!(" {}", quote(&arg)));
+            }
+            (false, start, None)
+        }
+        VpnType::Ikev2 => {
+            // The same commands as connecting from the app, without sudo
+            let (_, connect) = ikev2::connect_command(config);
+            let (_, disconnect) = ikev2::disconnect_command(config);
+            let command = |args: &[String]| -> Result<String> {
+                let (tool, rest) = args.split_first().ok_or_else(|| anyhow::anyhow!("No strongSwan command"))?;
+                let rest: Vec<String> = rest.iter().map(|arg| quote(arg)).collect();
+                Ok(format!("{} {}", program(tool)?, rest.join(" ")))
+            };
+            (true, command(&connect)?, Some(command(&disconnect)?))
+        }
+        VpnType::OpenConnect => {
+            return Err(anyhow::anyhow!("OpenConnect connections can't be started at boot"));
+        }
+        VpnType::Custom => {
+            return Err(anyhow::anyhow!("Custom connections can't be started at boot"));
+        }
+    };
+
+    let mut lines = vec![
+        "[Unit]".to_string(),
+        format!("Description=VPN Manager: {}", config.name),
+        "Wants=network-online.target".to_string(),
+        "After=network-online.target".to_string(),
+        String::new(),
+        "[Service]".to_string(),
+    ];
+    if oneshot {
+        lines.push("Type=oneshot".to_string());
+        lines.push("RemainAfterExit=yes".to_string());
+    } else {
+        lines.push("Type=simple".to_string());
+        lines.push("Restart=on-failure".to_string());
+        lines.push("RestartSec=10".to_string());
+    }
+    for (key, value) in &config.env_vars {
+        lines.push(format!("Environment={}", quote(&format!("{}={}", key, value))));
+    }
+    lines.push(format!("ExecStart={}", start));
+    lines.extend(stop.map(|stop| format!("ExecStop={}", stop)));
+    lines.extend([String::new(), "[Install]".to_string(), "WantedBy=multi-user.target".to_string()]);
+
+    Ok(lines.join("\n") + "\n")
+}
+
+pub async fn enable(config: &VpnConfig) -> Result<()> {
+    if !is_supported() {
+        return Err(errors::error(ErrorCode::SystemdMissing, ""));
+    }
+    let unit = unit_file(config)?;
+
+    if config.vpn_type == VpnType::OpenVpn && !config.username.is_empty() {
+        let credentials = format!("{}\n{}\n", config.username, config.password);
+        write_root(config, &auth_path(config), &credentials).await?;
+    }
+    if let Some(credentials) = proxy::credentials(config) {
+        write_root(config, &proxy_auth_path(config), &credentials).await?;
+    }
+    write_root(config, &unit_path(config), &unit).await?;
+
+    systemctl(config, &["daemon-reload"]).await?;
+    systemctl(config, &["enable", &unit_name(config)]).await
+}
+
+// Also stops the tunnel if the unit is running it, since the app couldn't tell it is up
+// once the unit is gone
+pub async fn disable(config: &VpnConfig) -> Result<()> {
+    systemctl(config, &["disable", "--now", &unit_name(config)]).await?;
+    let unit = unit_path(config).display().to_string();
+    let auth = auth_path(config).display().to_string();
+    let proxy_auth = proxy_auth_path(config).display().to_string();
+    run(config, "rm", &["-f", &unit, &auth, &proxy_auth], None).await?;
+    systemctl(config, &["daemon-reload"]).await
+}
+
+pub async fn start(config: &VpnConfig) -> Result<()> {
+    systemctl(config, &["start", &unit_name(config)]).await
+}
+
+pub async fn stop(config: &VpnConfig) -> Result<()> {
+    systemctl(config, &["stop", &unit_name(config)]).await
+}
+
+async fn systemctl(config: &VpnConfig, args: &[&str]) -> Result<()> {
+    run(config, "systemctl", args, None).await
+}
+
+// Written with a umask that keeps the files, credentials included, to root
+pub async fn write_root(config: &VpnConfig, path: &Path, content: &str) -> Result<()> {
+    let script = format!(
+        "umask 077 && mkdir -p \"$(dirname \"$1\")\" && cat > \"$1\" && chmod {} \"$1\"",
+        if path.starts_with(UNIT_DIR) { "644" } else { "600" }
+    );
+    let path = path.display().to_string();
+    run(config, "sh", &["-c", &script, "sh", &path], Some(content)).await
+}
+
+// A command run with sudo in its turn, logged to the entry's client log
+pub async fn run(config: &VpnConfig, program: &str, args: &[&str], input: Option<&str>) -> Result<()> {
+    let _privileged = executor::privileged().await;
+    let mut child = Command::new("sudo")
+        .arg(program)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).await?;
+    }
+    let output = child.wait_with_output().await?;
+    // The arguments may name the credentials file but never hold the credentials
+    client_log::record("vpn", &config.name, &format!("{} {}", program, args.join(" ")), &output);
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Output;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Rotate once a log passes this size, keeping a few older generations
+const MAX_LOG_BYTES: u64 = 256 * 1024;
+const KEEP_ROTATED: usize = 3;
+// Lines kept in memory for the live log, across all entries
+const LIVE_LINES: usize = 2000;
+
+static LIVE: Mutex<VecDeque<LiveLine>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [LogLevel::Debug, LogLevel::Info, LogLevel::Warning, LogLevel::Error];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogLevel::Debug => "Debug",
+            LogLevel::Info => "Info",
+            LogLevel::Warning => "Warning",
+            LogLevel::Error => "Error",
+        }
+    }
+
+    // Clients don't tag their output, so the words in the line decide
+    pub fn guess(line: &str) -> Self {
+        let lower = line.to_lowercase();
+        if ["error", "fatal", "failed", "cannot", "denied", "refused"].iter().any(|word| lower.contains(word)) {
+            LogLevel::Error
+        } else if lower.contains("warn") {
+            LogLevel::Warning
+        } else {
+            LogLevel::Info
+        }
+    }
+}
+
+// A line of client output as it came in, kept in memory next to the log files
+#[derive(Debug, Clone)]
+pub struct LiveLine {
+    pub time: u64,
+    pub kind: String,
+    pub entry: String,
+    pub level: LogLevel,
+    pub text: String,
+}
+
+impl LiveLine {
+    // As copied to the clipboard
+    pub fn to_text(&self) -> String {
+        format!("{} [{}] {}: {}", format_timestamp(self.time), self.level.label(), self.entry, self.text)
+    }
+}
+
+pub fn push_live(kind: &str, entry_name: &str, level: LogLevel, text: &str) {
+    let mut live = LIVE.lock().unwrap();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        if live.len() == LIVE_LINES {
+            live.pop_front();
+        }
+        live.push_back(LiveLine {
+            time: now_secs(),
+            kind: kind.to_string(),
+            entry: entry_name.to_string(),
+            level,
+            text: line.trim_end().to_string(),
+        });
+    }
+}
+
+// Lines of one kind, oldest first; None for the entry takes every entry's
+pub fn live(kind: &str, entry_name: Option<&str>, min_level: LogLevel) -> Vec<LiveLine> {
+    LIVE.lock().unwrap()
+        .iter()
+        .filter(|line| line.kind == kind && entry_name.is_none_or(|name| line.entry == name) && line.level >= min_level)
+        .cloned()
+        .collect()
+}
+
+pub fn clear_live(kind: &str, entry_name: Option<&str>) {
+    LIVE.lock().unwrap().retain(|line| line.kind != kind || entry_name.is_some_and(|name| line.entry != name));
+}
+
+pub fn logs_dir() -> PathBuf {
+    crate::system::paths::logs_dir()
+}
+
+// One log file per connection entry, e.g. logs/vpn-Office.log
+pub fn log_path(kind: &str, entry_name: &str) -> PathBuf {
+    let file_name: String = entry_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    logs_dir().join(format!("{}-{}.log", kind, file_name))
+}
+
+// Prepares the log for a new session and returns its path, for clients that write
+// their own log (openvpn --log-append)
+pub fn prepare(kind: &str, entry_name: &str) -> PathBuf {
+    let path = log_path(kind, entry_name);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    rotate_if_needed(&path);
+    path
+}
+
+// Appends a spawned client's exit status and output to the entry's log
+pub fn record(kind: &str, entry_name: &str, command: &str, output: &Output) {
+    let path = prepare(kind, entry_name);
+
+    let mut entry = format!(
+        "=== {} {} (exit: {}) ===\n",
+        format_timestamp(now_secs()),
+        command,
+        output.status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string())
+    );
+    for (label, bytes) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        let text = String::from_utf8_lossy(bytes);
+        if !text.trim().is_empty() {
+            entry.push_str(&format!("--- {} ---\n{}\n", label, text.trim_end()));
+            for line in text.lines() {
+                push_live(kind, entry_name, LogLevel::guess(line), line);
+            }
+        }
+    }
+    if !output.status.success() {
+        let status = output.status.code().map(|c| format!("exited with {}", c)).unwrap_or_else(|| "was killed".to_string());
+        push_live(kind, entry_name, LogLevel::Error, &format!("{} {}", command, status));
+    }
+
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(entry.as_bytes()));
+    if let Err(e) = result {
+        log::warn!("Failed to write client log {}: {}", path.display(), e);
+    }
+}
+
+// Returns the current log; rotated generations stay on disk next to it
+pub fn read(kind: &str, entry_name: &str) -> Option<String> {
+    std::fs::read_to_string(log_path(kind, entry_name)).ok()
+}
+
+// Follows a renamed entry, rotated generations included; false if it had no log or the new
+// name already has one
+pub fn rename(kind: &str, old: &str, new: &str) -> bool {
+    let (from, to) = (log_path(kind, old), log_path(kind, new));
+    if !from.exists() || to.exists() {
+        return false;
+    }
+    for generation in 1..=KEEP_ROTATED {
+        let _ = std::fs::rename(rotated_path(&from, generation), rotated_path(&to, generation));
+    }
+    std::fs::rename(from, to).is_ok()
+}
+
+pub fn clear(kind: &str, entry_name: &str) -> anyhow::Result<()> {
+    let path = log_path(kind, entry_name);
+    if path.exists() {
+        std::fs::remove_file(&path)?;
+    }
+    for generation in 1..=KEEP_ROTATED {
+        let _ = std::fs::remove_file(rotated_path(&path, generation));
+    }
+    Ok(())
+}
+
+fn rotated_path(path: &std::path::Path, generation: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", generation));
+    PathBuf::from(name)
+}
+
+fn rotate_if_needed(path: &std::path::Path) {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size < MAX_LOG_BYTES {
+        return;
+    }
+
+    // name.log.2 -> name.log.3, name.log.1 -> name.log.2, name.log -> name.log.1
+    for generation in (1..KEEP_ROTATED).rev() {
+        let _ = std::fs::rename(rotated_path(path, generation), rotated_path(path, generation + 1));
+    }
+    if let Err(e) = std::fs::rename(path, rotated_path(path, 1)) {
+        log::warn!("Failed to rotate client log {}: {}", path.display(), e);
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// UTC "YYYY-MM-DD HH:MM:SS" without pulling in a date crate
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time = secs % 86400;
+
+    // Civil-from-days conversion (Howard Hinnant)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day,
+        time / 3600, (time % 3600) / 60, time % 60
+    )
+}
@@ -0,0 +1,280 @@
+use crate::config::Config;
+use crate::system::renderer::Renderer;
+use crate::ui::Panel;
+
+// Shell completion for the command line: `vpn-manager completions <shell>` prints a script for
+// bash, zsh, fish or PowerShell. Panels and subcommands are written into the script; entry
+// names for --select are asked from the app through `complete-entries` each time, so they follow
+// the config without generating the script again.
+
+const BIN: &str = "vpn-manager";
+const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "powershell"];
+// Listed by the scripts; complete-entries is left out since only they call it
+const SUBCOMMANDS: [(&str, &str); 3] = [
+    ("doctor", "Check the environment and print a JSON report"),
+    ("schema", "Print the JSON Schema of config.json"),
+    ("completions", "Print a shell completion script"),
+];
+const FLAGS: [(&str, &str); 5] = [
+    ("--panel", "Open the app on a panel"),
+    ("--select", "Focus an entry"),
+    ("--minimized", "Start minimized"),
+    ("--safe-mode", "Start without auto-connect, status checks or update checks"),
+    ("--renderer", "Draw with glow, wgpu or software rendering"),
+];
+const PANELS: [Panel; 6] = [Panel::Home, Panel::Vpn, Panel::Remote, Panel::Schedule, Panel::Settings, Panel::Problems];
+
+// `completions <shell>`, returning the process exit code
+pub fn run(args: &[String]) -> i32 {
+    let script = match args.first().map(|shell| shell.to_lowercase()).as_deref() {
+        Some("bash") => bash(),
+        Some("zsh") => zsh(),
+        Some("fish") => fish(),
+        Some("powershell") | Some("pwsh") => powershell(),
+        Some(other) => {
+            eprintln!("Error: Unknown shell '{}'", other);
+            eprintln!("Usage: {} completions {}", BIN, SHELLS.join("|"));
+            return 2;
+        }
+        None => {
+            eprintln!("Usage: {} completions {}", BIN, SHELLS.join("|"));
+            return 2;
+        }
+    };
+    print!("{}", script);
+    0
+}
+
+// `complete-entries [panel]`: entry names one per line, those of the panel if one is given.
+// Prints nothing when the config can't be read, which leaves the shell without suggestions.
+pub fn print_entries(panel: Option<&str>) -> i32 {
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            log::debug!("No entries to complete: {}", e);
+            return 0;
+        }
+    };
+    let panel = panel.and_then(Panel::from_name);
+    let vpns = config.vpn_configs.iter().map(|vpn| &vpn.name);
+    let devices = config.devices.iter().map(|device| &device.name);
+    let names: Vec<&String> = match panel {
+        Some(Panel::Vpn) => vpns.collect(),
+        Some(Panel::Remote) => devices.collect(),
+        _ => vpns.chain(devices).collect(),
+    };
+    for name in names {
+        // A name over several lines would be split into several suggestions
+        if !name.contains('\n') {
+            println!("{}", name);
+        }
+    }
+    0
+}
+
+fn panel_names() -> String {
+    PANELS.iter().map(|panel| panel.name()).collect::<Vec<_>>().join(" ")
+}
+
+fn renderer_names() -> String {
+    Renderer::ALL.iter().map(|renderer| renderer.name()).collect::<Vec<_>>().join(" ")
+}
+
+fn bash() -> String {
+    format!(
+        r#"# bash completion for {bin}
+# Load with: source <({bin} completions bash)
+_vpn_manager() {{
+    local cur prev panel i
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        if [[ "${{COMP_WORDS[i]}}" == --panel ]]; then
+            panel="${{COMP_WORDS[i+1]}}"
+        fi
+    done
+
+    case "$prev" in
+        --panel)
+            COMPREPLY=($(compgen -W "{panels}" -- "$cur"))
+            return
+            ;;
+        --renderer)
+            COMPREPLY=($(compgen -W "{renderers}" -- "$cur"))
+            return
+            ;;
+        --select)
+            local IFS=$'\n'
+            COMPREPLY=($(compgen -W "$("${{COMP_WORDS[0]}}" complete-entries $panel 2>/dev/null)" -- "$cur"))
+            COMPREPLY=("${{COMPREPLY[@]// /\\ }}")
+            return
+            ;;
+        completions)
+            COMPREPLY=($(compgen -W "{shells}" -- "$cur"))
+            return
+            ;;
+    esac
+
+    case "${{COMP_WORDS[1]}}" in
+        doctor|schema|completions)
+            return
+            ;;
+    esac
+
+    if [[ "$COMP_CWORD" -eq 1 && "$cur" != -* ]]; then
+        COMPREPLY=($(compgen -W "{subcommands}" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -W "{flags}" -- "$cur"))
+    fi
+}}
+complete -F _vpn_manager {bin}
+"#,
+        bin = BIN,
+        panels = panel_names(),
+        renderers = renderer_names(),
+        shells = SHELLS.join(" "),
+        subcommands = SUBCOMMANDS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(" "),
+        flags = FLAGS.iter().map(|(flag, _)| *flag).collect::<Vec<_>>().join(" "),
+    )
+}
+
+fn zsh() -> String {
+    let subcommands: Vec<String> = SUBCOMMANDS.iter().map(|(name, help)| format!("'{}:{}'", name, help)).collect();
+    format!(
+        r#"#compdef {bin}
+# zsh completion for {bin}
+# Save as _{bin} in a folder on $fpath, or load with: source <({bin} completions zsh)
+
+_vpn_manager() {{
+    local -a subcommands entries
+    local panel i
+    subcommands=({subcommands})
+
+    for ((i = 2; i < CURRENT; i++)); do
+        [[ "$words[i]" == --panel ]] && panel="$words[i+1]"
+    done
+
+    case "$words[CURRENT-1]" in
+        --panel)
+            compadd -- {panels}
+            return
+            ;;
+        --renderer)
+            compadd -- {renderers}
+            return
+            ;;
+        --select)
+            entries=("${{(@f)$("$words[1]" complete-entries $panel 2>/dev/null)}}")
+            compadd -a entries
+            return
+            ;;
+        completions)
+            compadd -- {shells}
+            return
+            ;;
+    esac
+
+    case "$words[2]" in
+        doctor|schema|completions)
+            (( CURRENT > 2 )) && return
+            ;;
+    esac
+
+    if (( CURRENT == 2 )) && [[ "$PREFIX" != -* ]]; then
+        _describe -t commands 'command' subcommands
+    else
+        _arguments -s {flags}
+    fi
+}}
+
+if [[ "$funcstack[1]" == _vpn_manager ]]; then
+    _vpn_manager "$@"
+else
+    compdef _vpn_manager {bin}
+fi
+"#,
+        bin = BIN,
+        subcommands = subcommands.join(" "),
+        panels = panel_names(),
+        renderers = renderer_names(),
+        shells = SHELLS.join(" "),
+        flags = FLAGS.iter().map(|(flag, help)| format!("'{}[{}]'", flag, help)).collect::<Vec<_>>().join(" "),
+    )
+}
+
+fn fish() -> String {
+    let mut lines = vec![
+        format!("# fish completion for {}", BIN),
+        format!("# Save as ~/.config/fish/completions/{}.fish, or load with: {} completions fish | source", BIN, BIN),
+        "function __fish_vpn_manager_panel".to_string(),
+        "    set -l words (commandline -opc)".to_string(),
+        "    set -l index (contains -i -- --panel $words)".to_string(),
+        "    and set -q words[(math $index + 1)]".to_string(),
+        "    and echo $words[(math $index + 1)]".to_string(),
+        "end".to_string(),
+        format!("complete -c {} -f", BIN),
+    ];
+    for (name, help) in SUBCOMMANDS {
+        lines.push(format!("complete -c {} -n '__fish_use_subcommand' -a {} -d '{}'", BIN, name, help));
+    }
+    lines.push(format!("complete -c {} -n '__fish_seen_subcommand_from completions' -a '{}'", BIN, SHELLS.join(" ")));
+
+    let no_subcommand = format!("not __fish_seen_subcommand_from {}", SUBCOMMANDS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(" "));
+    lines.push(format!("complete -c {} -n '{}' -l panel -x -a '{}' -d '{}'", BIN, no_subcommand, panel_names(), FLAGS[0].1));
+    // The panel given before --select narrows the names
+    lines.push(format!(
+        "complete -c {} -n '{}' -l select -x -a '({} complete-entries (__fish_vpn_manager_panel) 2>/dev/null)' -d '{}'",
+        BIN, no_subcommand, BIN, FLAGS[1].1
+    ));
+    lines.push(format!("complete -c {} -n '{}' -l minimized -d '{}'", BIN, no_subcommand, FLAGS[2].1));
+    lines.push(format!("complete -c {} -n '{}' -l safe-mode -d '{}'", BIN, no_subcommand, FLAGS[3].1));
+    lines.push(format!("complete -c {} -n '{}' -l renderer -x -a '{}' -d '{}'", BIN, no_subcommand, renderer_names(), FLAGS[4].1));
+    lines.join("\n") + "\n"
+}
+
+fn powershell() -> String {
+    let subcommands: Vec<String> = SUBCOMMANDS.iter().map(|(name, _)| format!("'{}'", name)).collect();
+    let flags: Vec<String> = FLAGS.iter().map(|(flag, _)| format!("'{}'", flag)).collect();
+    let panels: Vec<String> = PANELS.iter().map(|panel| format!("'{}'", panel.name())).collect();
+    let renderers: Vec<String> = Renderer::ALL.iter().map(|renderer| format!("'{}'", renderer.name())).collect();
+    let shells: Vec<String> = SHELLS.iter().map(|shell| format!("'{}'", shell)).collect();
+    format!(
+        r#"# PowerShell completion for {bin}
+# Load with: {bin} completions powershell | Out-String | Invoke-Expression
+Register-ArgumentCompleter -Native -CommandName '{bin}', '{bin}.exe' -ScriptBlock {{
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $words = @($commandAst.CommandElements | ForEach-Object {{ $_.ToString() }})
+    $count = $words.Count
+    if ($wordToComplete -ne '') {{ $count-- }}
+    $prev = if ($count -ge 1) {{ $words[$count - 1] }} else {{ '' }}
+    $panelIndex = [Array]::IndexOf($words, '--panel')
+    $panel = if ($panelIndex -ge 0 -and $panelIndex + 1 -lt $words.Count) {{ $words[$panelIndex + 1] }} else {{ '' }}
+
+    $candidates = switch ($prev) {{
+        '--panel' {{ {panels} }}
+        '--renderer' {{ {renderers} }}
+        '--select' {{ & $words[0] complete-entries $panel 2>$null }}
+        'completions' {{ {shells} }}
+        default {{
+            if ($words.Count -ge 2 -and @({subcommands}) -contains $words[1] -and $count -gt 1) {{ @() }}
+            elseif ($count -eq 1 -and -not $wordToComplete.StartsWith('-')) {{ {subcommands} }}
+            else {{ {flags} }}
+        }}
+    }}
+
+    $candidates | Where-Object {{ $_ -like "$wordToComplete*" }} | ForEach-Object {{
+        $text = if ($_ -match '\s') {{ "'" + ($_ -replace "'", "''") + "'" }} else {{ $_ }}
+        [System.Management.Automation.CompletionResult]::new($text, $_, 'ParameterValue', $_)
+    }}
+}}
+"#,
+        bin = BIN,
+        panels = panels.join(", "),
+        renderers = renderers.join(", "),
+        shells = shells.join(", "),
+        subcommands = subcommands.join(", "),
+        flags = flags.join(", "),
+    )
+}
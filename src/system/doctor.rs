@@ -0,0 +1,238 @@
+use crate::config::lint::{self, Severity};
+use crate::config::Config;
+use crate::network::monitor;
+use crate::system::session::DisplaySession;
+use crate::system::SystemInfo;
+use serde::Serialize;
+use std::process::Command;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorCheck {
+    pub category: String,
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    pub version: String,
+    pub platform: String,
+    pub distribution: String,
+    pub package_manager: String,
+    pub display_session: String,
+    pub ok: bool,
+    pub checks: Vec<DoctorCheck>,
+}
+
+// Runs every environment check and prints a JSON report, returning the process exit code
+pub fn run() -> i32 {
+    let report = collect_report();
+
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => println!("{}", json),
+        Err(e) => {
+            eprintln!("Failed to serialize doctor report: {}", e);
+            return 2;
+        }
+    }
+
+    if report.ok { 0 } else { 1 }
+}
+
+pub fn collect_report() -> DoctorReport {
+    let mut checks = Vec::new();
+
+    let system_info = SystemInfo::detect();
+    let (distribution, package_manager) = match &system_info {
+        Ok(info) => (info.distribution.clone(), format!("{:?}", info.package_manager)),
+        Err(e) => {
+            checks.push(check("system", "detection", CheckStatus::Fail, e.to_string()));
+            ("Unknown".to_string(), "Unknown".to_string())
+        }
+    };
+
+    if let Ok(info) = &system_info {
+        check_dependencies(info, &mut checks);
+    }
+    check_permissions(&mut checks);
+    check_network_interfaces(&mut checks);
+    check_config(&mut checks);
+
+    let session = DisplaySession::detect();
+    let session_status = if session.has_display() { CheckStatus::Pass } else { CheckStatus::Warn };
+    checks.push(check("environment", "display", session_status, session.description()));
+
+    let ok = !checks.iter().any(|c| c.status == CheckStatus::Fail);
+
+    DoctorReport {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        distribution,
+        package_manager,
+        display_session: session.description(),
+        ok,
+        checks,
+    }
+}
+
+fn check(category: &str, name: &str, status: CheckStatus, detail: String) -> DoctorCheck {
+    DoctorCheck {
+        category: category.to_string(),
+        name: name.to_string(),
+        status,
+        detail,
+    }
+}
+
+fn check_dependencies(system_info: &SystemInfo, checks: &mut Vec<DoctorCheck>) {
+    for dep in &system_info.dependencies {
+        let (status, detail) = if dep.is_installed {
+            (CheckStatus::Pass, dep.version.clone().unwrap_or_else(|| "installed".to_string()))
+        } else if dep.required {
+            (CheckStatus::Fail, format!("missing, install package '{}'", dep.package_name))
+        } else {
+            (CheckStatus::Warn, format!("optional, install package '{}'", dep.package_name))
+        };
+        checks.push(check("dependencies", &dep.name, status, detail));
+    }
+}
+
+fn check_permissions(checks: &mut Vec<DoctorCheck>) {
+    #[cfg(unix)]
+    {
+        let is_root = Command::new("id")
+            .arg("-u")
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "0")
+            .unwrap_or(false);
+
+        if is_root {
+            checks.push(check("permissions", "privileges", CheckStatus::Pass, "running as root".to_string()));
+        } else {
+            // VPN clients are started through sudo, which must not block on a hidden password prompt
+            let passwordless_sudo = Command::new("sudo")
+                .args(["-n", "true"])
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false);
+
+            if passwordless_sudo {
+                checks.push(check("permissions", "privileges", CheckStatus::Pass, "passwordless sudo available".to_string()));
+            } else {
+                checks.push(check("permissions", "privileges", CheckStatus::Warn,
+                    "sudo requires a password; VPN connections may prompt or fail".to_string()));
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    {
+        // `net session` only succeeds from an elevated process
+        let is_admin = Command::new("net")
+            .arg("session")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+
+        let (status, detail) = if is_admin {
+            (CheckStatus::Pass, "running elevated".to_string())
+        } else {
+            (CheckStatus::Warn, "not elevated; VPN tunnels may fail to start".to_string())
+        };
+        checks.push(check("permissions", "privileges", status, detail));
+    }
+
+    let config_path = Config::config_path();
+    match config_path.parent() {
+        Some(dir) if dir.exists() => {
+            let writable = std::fs::metadata(dir).map(|m| !m.permissions().readonly()).unwrap_or(false);
+            let status = if writable { CheckStatus::Pass } else { CheckStatus::Fail };
+            checks.push(check("permissions", "config directory", status, dir.display().to_string()));
+        }
+        Some(dir) => {
+            checks.push(check("permissions", "config directory", CheckStatus::Warn,
+                format!("{} does not exist yet", dir.display())));
+        }
+        None => {}
+    }
+
+    // The config stores credentials, so it should not be readable by other users
+    #[cfg(unix)]
+    if let Ok(metadata) = std::fs::metadata(&config_path) {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode();
+        if mode & 0o077 != 0 {
+            checks.push(check("permissions", "config file", CheckStatus::Warn,
+                format!("{} is accessible by other users (mode {:o})", config_path.display(), mode & 0o777)));
+        } else {
+            checks.push(check("permissions", "config file", CheckStatus::Pass,
+                format!("mode {:o}", mode & 0o777)));
+        }
+    }
+}
+
+fn check_network_interfaces(checks: &mut Vec<DoctorCheck>) {
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            checks.push(check("network", "interfaces", CheckStatus::Fail, e.to_string()));
+            return;
+        }
+    };
+
+    match runtime.block_on(monitor::get_network_interfaces()) {
+        Ok(interfaces) => {
+            let usable: Vec<String> = interfaces
+                .iter()
+                .filter(|iface| iface.is_up && !iface.ip_address.starts_with("127.") && iface.ip_address != "::1")
+                .map(|iface| format!("{} ({})", iface.name, iface.ip_address))
+                .collect();
+
+            if usable.is_empty() {
+                checks.push(check("network", "interfaces", CheckStatus::Fail, "no non-loopback interface is up".to_string()));
+            } else {
+                checks.push(check("network", "interfaces", CheckStatus::Pass, usable.join(", ")));
+            }
+        }
+        Err(e) => {
+            checks.push(check("network", "interfaces", CheckStatus::Fail, e.to_string()));
+        }
+    }
+}
+
+fn check_config(checks: &mut Vec<DoctorCheck>) {
+    let config = match Config::load() {
+        Ok(config) => {
+            checks.push(check("config", "parse", CheckStatus::Pass, Config::config_path().display().to_string()));
+            config
+        }
+        Err(e) => {
+            checks.push(check("config", "parse", CheckStatus::Fail, e.to_string()));
+            return;
+        }
+    };
+
+    let problems = lint::lint(&config);
+    for problem in &problems {
+        let status = match problem.severity {
+            Severity::Error => CheckStatus::Fail,
+            Severity::Warning => CheckStatus::Warn,
+        };
+        let name = format!("{} '{}'", problem.kind.label().to_lowercase(), problem.name);
+        checks.push(check("config", &name, status, problem.message.clone()));
+    }
+
+    if problems.is_empty() {
+        let entries = config.vpn_configs.len() + config.devices.len();
+        checks.push(check("config", "entries", CheckStatus::Pass, format!("{} entries, no problems", entries)));
+    }
+}
@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+use std::process::Output;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard, OwnedMutexGuard};
+
+// Commands that change the system (route and firewall rules, DNS, wg-quick, package installs)
+// run one at a time, in the order they were asked for: two of them editing the routing table
+// or the firewall at once undo each other, and parallel sudo prompts fight over the terminal.
+// They also start a little apart, so a loop gone wrong can't flood sudo's log.
+//
+// Separately, connecting and disconnecting one entry exclude each other, e.g. the UI's
+// disconnect and the reconnect loop's next attempt; the later one waits for the earlier.
+// The locks are tokio's, which work across the runtimes each UI action creates.
+
+const MIN_SPACING: Duration = Duration::from_millis(200);
+
+// When the last privileged command finished
+static PRIVILEGED: AsyncMutex<Option<Instant>> = AsyncMutex::const_new(None);
+static ENTRIES: Mutex<BTreeMap<String, Arc<AsyncMutex<()>>>> = Mutex::new(BTreeMap::new());
+
+// Held while a privileged command runs; the next one waits for it to be dropped
+pub struct Privileged(MutexGuard<'static, Option<Instant>>);
+
+impl Drop for Privileged {
+    fn drop(&mut self) {
+        *self.0 = Some(Instant::now());
+    }
+}
+
+// Waits for the privileged commands queued earlier, for commands that need more than output(),
+// e.g. writing to stdin. Never call it again while holding one.
+pub async fn privileged() -> Privileged {
+    let last = PRIVILEGED.lock().await;
+    if let Some(wait) = last.and_then(|last| MIN_SPACING.checked_sub(last.elapsed())) {
+        tokio::time::sleep(wait).await;
+    }
+    Privileged(last)
+}
+
+// Runs a privileged command in its turn and returns its output
+pub async fn output(cmd: &mut Command) -> std::io::Result<Output> {
+    let _privileged = privileged().await;
+    cmd.output().await
+}
+
+fn entry_lock(name: &str) -> Arc<AsyncMutex<()>> {
+    ENTRIES.lock().unwrap().entry(name.to_string()).or_default().clone()
+}
+
+// Held while an entry connects or disconnects
+pub async fn entry(name: &str) -> OwnedMutexGuard<()> {
+    let lock = entry_lock(name);
+    if let Ok(guard) = lock.clone().try_lock_owned() {
+        return guard;
+    }
+    log::info!("Waiting for the running operation on {} to finish", name);
+    lock.lock_owned().await
+}
+
+// Whether an entry is connecting or disconnecting right now, to keep the UI from starting
+// another operation on it
+pub fn is_busy(name: &str) -> bool {
+    entry_lock(name).try_lock().is_err()
+}
@@ -1,7 +1,10 @@
-use super::{PackageManager, SystemInfo};
+use super::{executor, PackageManager, SystemInfo};
+use crate::errors::{self, ErrorCode};
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::process::Command;
 
+#[derive(Clone)]
 pub struct PackageInstaller {
     package_manager: PackageManager,
 }
@@ -18,21 +21,34 @@ impl PackageInstaller {
             return Ok(());
         }
         
+        let _privileged = executor::privileged().await;
         match self.package_manager {
             PackageManager::Apt => self.install_apt(packages).await,
             PackageManager::Pacman => self.install_pacman(packages).await,
             PackageManager::Dnf => self.install_dnf(packages).await,
             PackageManager::Yum => self.install_yum(packages).await,
             PackageManager::Zypper => self.install_zypper(packages).await,
-            PackageManager::Unknown => Err(anyhow::anyhow!("Unknown package manager")),
+            PackageManager::Unknown => Err(errors::error(ErrorCode::PackageManagerUnknown, "")),
             PackageManager::Chocolatey => self.install_chocolatey(packages).await,
             PackageManager::Scoop => self.install_scoop(packages).await,
-            PackageManager::Winget => self.install_winget(packages).await
+            PackageManager::Winget => self.install_winget(packages).await,
+            PackageManager::Homebrew => self.install_brew(packages).await,
         }
     }
     
     pub async fn update_package_cache(&self) -> Result<()> {
+        let _privileged = executor::privileged().await;
         match self.package_manager {
+            PackageManager::Homebrew => {
+                let output = Command::new("brew")
+                    .arg("update")
+                    .output()?;
+                
+                if !output.status.success() {
+                    return Err(anyhow::anyhow!("Failed to update Homebrew: {}", 
+                        String::from_utf8_lossy(&output.stderr)));
+                }
+            }
             PackageManager::Apt => {
                 let output = Command::new("sudo")
                     .args(&["apt", "update"])
@@ -86,7 +102,7 @@ impl PackageInstaller {
                 }
             }
             PackageManager::Unknown => {
-                return Err(anyhow::anyhow!("Unknown package manager"));
+                return Err(errors::error(ErrorCode::PackageManagerUnknown, ""));
             }
             PackageManager::Chocolatey => {
                 let output = Command::new("choco")
@@ -123,6 +139,89 @@ impl PackageInstaller {
         Ok(())
     }
     
+    // Asks the package manager which of `packages` have a newer version available, returning
+    // package name -> available version. Uses the package manager's cached index, so results
+    // are only as fresh as its last refresh.
+    pub async fn check_upgrades(&self, packages: &[String]) -> Result<BTreeMap<String, String>> {
+        let (program, args): (&str, &[&str]) = match self.package_manager {
+            PackageManager::Apt => ("apt", &["list", "--upgradable"]),
+            PackageManager::Pacman => ("pacman", &["-Qu"]),
+            PackageManager::Dnf => ("dnf", &["check-update", "-q"]),
+            PackageManager::Yum => ("yum", &["check-update", "-q"]),
+            PackageManager::Zypper => ("zypper", &["-q", "list-updates"]),
+            PackageManager::Chocolatey => ("choco", &["outdated", "-r"]),
+            PackageManager::Scoop => ("scoop", &["status"]),
+            PackageManager::Winget => ("winget", &["upgrade", "--accept-source-agreements"]),
+            PackageManager::Homebrew => ("brew", &["outdated", "--formula", "--verbose"]),
+            PackageManager::Unknown => return Ok(BTreeMap::new()),
+        };
+        
+        let output = Command::new(program)
+            .args(args)
+            .output()?;
+        
+        // dnf/yum check-update return 100 when updates are available
+        if !output.status.success() && output.status.code() != Some(100) {
+            return Err(anyhow::anyhow!("Failed to list upgrades: {}", 
+                String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut upgrades = BTreeMap::new();
+        
+        for line in stdout.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let found = match self.package_manager {
+                // openvpn/jammy-updates 2.5.11-0ubuntu0.22.04.1 amd64 [upgradable from: 2.5.9-0ubuntu0.22.04.2]
+                PackageManager::Apt => fields.first()
+                    .and_then(|f| f.split_once('/'))
+                    .zip(fields.get(1))
+                    .map(|((name, _), version)| (name.to_string(), version.to_string())),
+                // openvpn 2.6.8-1 -> 2.6.9-1
+                PackageManager::Pacman if fields.len() == 4 && fields[2] == "->" => {
+                    Some((fields[0].to_string(), fields[3].to_string()))
+                }
+                // openvpn.x86_64    2.6.9-1.fc39    updates
+                PackageManager::Dnf | PackageManager::Yum if fields.len() == 3 => fields[0]
+                    .rsplit_once('.')
+                    .map(|(name, _)| (name.to_string(), fields[1].to_string())),
+                // v | repo-update | openvpn | 2.6.8-1.1 | 2.6.9-1.1 | x86_64
+                PackageManager::Zypper => {
+                    let columns: Vec<&str> = line.split('|').map(|c| c.trim()).collect();
+                    (columns.len() >= 6 && columns[0] == "v")
+                        .then(|| (columns[2].to_string(), columns[4].to_string()))
+                }
+                // openvpn|2.6.8|2.6.9|false
+                PackageManager::Chocolatey => {
+                    let columns: Vec<&str> = line.split('|').collect();
+                    (columns.len() >= 3).then(|| (columns[0].to_string(), columns[2].to_string()))
+                }
+                // Name    Installed Version Latest Version Missing Dependencies Info
+                // openvpn 2.6.8             2.6.9
+                PackageManager::Scoop if fields.len() >= 3 => Some((fields[0].to_string(), fields[2].to_string())),
+                // Names can contain spaces, so find the row by package id:
+                // OpenVPN 2.6.8 OpenVPN.OpenVPN 2.6.8.0 2.6.9.0 winget
+                PackageManager::Winget => packages.iter().find_map(|package| {
+                    let position = fields.iter().position(|f| f.eq_ignore_ascii_case(package))?;
+                    fields.get(position + 2).map(|version| (package.clone(), version.to_string()))
+                }),
+                // openvpn (2.6.8) < 2.6.9
+                PackageManager::Homebrew if fields.len() >= 4 && fields[fields.len() - 2] == "<" => {
+                    Some((fields[0].to_string(), fields[fields.len() - 1].to_string()))
+                }
+                _ => None,
+            };
+            
+            if let Some((name, version)) = found {
+                if packages.contains(&name) {
+                    upgrades.insert(name, version);
+                }
+            }
+        }
+        
+        Ok(upgrades)
+    }
+    
     async fn install_apt(&self, packages: &[String]) -> Result<()> {
         let mut args = vec!["apt", "install", "-y"];
         for package in packages {
@@ -261,6 +360,25 @@ impl PackageInstaller {
         Ok(())
     }
     
+    // Homebrew refuses to run as root, so this one goes without sudo
+    async fn install_brew(&self, packages: &[String]) -> Result<()> {
+        let mut args = vec!["install"];
+        for package in packages {
+            args.push(package);
+        }
+        
+        let output = Command::new("brew")
+            .args(&args)
+            .output()?;
+        
+        if !output.status.success() {
+            return Err(anyhow::anyhow!("Failed to install packages: {}", 
+                String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        Ok(())
+    }
+    
     pub fn get_install_command(&self, packages: &[String]) -> String {
         match self.package_manager {
             PackageManager::Apt => format!("sudo apt install -y {}", packages.join(" ")),
@@ -270,7 +388,13 @@ impl PackageInstaller {
             PackageManager::Zypper => format!("sudo zypper install -y {}", packages.join(" ")),
             PackageManager::Chocolatey => format!("choco install -y {}", packages.join(" ")),
             PackageManager::Scoop => format!("scoop install {}", packages.join(" ")),
-            PackageManager::Winget => format!("winget install {}", packages.join(" ")),
+            PackageManager::Homebrew => format!("brew install {}", packages.join(" ")),
+            // Matches install_winget, which installs one package id per invocation
+            PackageManager::Winget => packages
+                .iter()
+                .map(|p| format!("winget install --id {} --silent --accept-source-agreements --accept-package-agreements", p))
+                .collect::<Vec<_>>()
+                .join("\n"),
             PackageManager::Unknown => {
                 #[cfg(windows)]
                 {
@@ -313,4 +437,23 @@ impl PackageInstaller {
             }
         }
     }
+    
+    pub fn get_upgrade_command(&self, packages: &[String]) -> String {
+        match self.package_manager {
+            PackageManager::Apt => format!("sudo apt install --only-upgrade -y {}", packages.join(" ")),
+            PackageManager::Pacman => format!("sudo pacman -S --noconfirm {}", packages.join(" ")),
+            PackageManager::Dnf => format!("sudo dnf upgrade -y {}", packages.join(" ")),
+            PackageManager::Yum => format!("sudo yum update -y {}", packages.join(" ")),
+            PackageManager::Zypper => format!("sudo zypper update -y {}", packages.join(" ")),
+            PackageManager::Chocolatey => format!("choco upgrade -y {}", packages.join(" ")),
+            PackageManager::Scoop => format!("scoop update {}", packages.join(" ")),
+            PackageManager::Homebrew => format!("brew upgrade {}", packages.join(" ")),
+            PackageManager::Winget => packages
+                .iter()
+                .map(|p| format!("winget upgrade --id {} --silent --accept-source-agreements --accept-package-agreements", p))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            PackageManager::Unknown => "No package manager detected. Please upgrade packages manually.".to_string(),
+        }
+    }
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::collections::BTreeMap;
 use std::process::Command;
 use which::which;
 
@@ -7,7 +8,23 @@ use winreg::enums::*;
 #[cfg(windows)]
 use winreg::RegKey;
 
+pub mod autostart;
+pub mod completions;
+pub mod paths;
+pub mod renderer;
+pub mod boot;
+pub mod client_log;
+pub mod doctor;
+pub mod executor;
 pub mod installer;
+pub mod notify;
+pub mod recovery;
+pub mod screenshot;
+pub mod services;
+pub mod session;
+pub mod sound;
+pub mod sun;
+pub mod thumbnails;
 pub mod updater;
 
 #[derive(Debug, Clone)]
@@ -27,6 +44,7 @@ pub enum PackageManager {
     Chocolatey, // Windows
     Scoop,      // Windows
     Winget,     // Windows
+    Homebrew,   // macOS
     Unknown,
 }
 
@@ -38,6 +56,8 @@ pub struct Dependency {
     pub is_installed: bool,
     pub version: Option<String>,
     pub required: bool,
+    // Newer version the package manager could install, if an upgrade check found one
+    pub upgrade_version: Option<String>,
 }
 
 impl SystemInfo {
@@ -54,10 +74,32 @@ impl SystemInfo {
     }
     
     pub fn refresh_dependencies(&mut self) -> Result<()> {
-        self.dependencies = check_dependencies(&self.package_manager)?;
+        let previous = std::mem::replace(&mut self.dependencies, check_dependencies(&self.package_manager)?);
+        
+        // Keep upgrade hints for packages that weren't upgraded in the meantime
+        for dep in &mut self.dependencies {
+            if let Some(old) = previous.iter().find(|old| old.package_name == dep.package_name && old.version == dep.version) {
+                dep.upgrade_version = old.upgrade_version.clone();
+            }
+        }
         Ok(())
     }
     
+    // Applies the result of PackageInstaller::check_upgrades, keyed by package name
+    pub fn set_upgrades(&mut self, upgrades: &BTreeMap<String, String>) {
+        for dep in &mut self.dependencies {
+            dep.upgrade_version = if dep.is_installed {
+                upgrades.get(&dep.package_name).cloned()
+            } else {
+                None
+            };
+        }
+    }
+    
+    pub fn get_upgradable_dependencies(&self) -> Vec<&Dependency> {
+        self.dependencies.iter().filter(|dep| dep.upgrade_version.is_some()).collect()
+    }
+    
     pub fn get_missing_dependencies(&self) -> Vec<&Dependency> {
         self.dependencies.iter().filter(|dep| !dep.is_installed).collect()
     }
@@ -73,7 +115,12 @@ fn detect_distribution() -> Result<String> {
         return detect_windows_version();
     }
     
-    #[cfg(unix)]
+    #[cfg(target_os = "macos")]
+    {
+        return detect_macos_version();
+    }
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
     {
         if let Ok(output) = Command::new("lsb_release").arg("-d").output() {
             if output.status.success() {
@@ -113,6 +160,18 @@ fn detect_windows_version() -> Result<String> {
     }
 }
 
+#[cfg(target_os = "macos")]
+fn detect_macos_version() -> Result<String> {
+    let output = Command::new("sw_vers").arg("-productVersion").output()?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    
+    if version.is_empty() {
+        Ok("macOS".to_string())
+    } else {
+        Ok(format!("macOS {}", version))
+    }
+}
+
 fn detect_package_manager() -> PackageManager {
     #[cfg(windows)]
     {
@@ -127,7 +186,16 @@ fn detect_package_manager() -> PackageManager {
         }
     }
     
-    #[cfg(unix)]
+    #[cfg(target_os = "macos")]
+    {
+        if which("brew").is_ok() {
+            PackageManager::Homebrew
+        } else {
+            PackageManager::Unknown
+        }
+    }
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
     {
         if which("apt").is_ok() {
             PackageManager::Apt
@@ -153,24 +221,49 @@ fn check_dependencies(package_manager: &PackageManager) -> Result<Vec<Dependency
         // VPN dependencies for Windows
         dependencies.push(check_dependency_windows("OpenVPN", "openvpn", get_package_name("openvpn", package_manager), true)?);
         dependencies.push(check_dependency_windows("WireGuard", "wireguard", get_package_name("wireguard", package_manager), true)?);
+        dependencies.push(check_dependency_windows("IKEv2 (RAS)", "rasdial", "builtin".to_string(), false)?);
+        dependencies.push(check_dependency_windows("OpenConnect", "openconnect", get_package_name("openconnect", package_manager), false)?);
         
         // RDP dependencies (built into Windows)
         dependencies.push(check_dependency_windows("Remote Desktop", "mstsc", "builtin".to_string(), false)?);
+        dependencies.push(check_dependency_windows("VNC Viewer", "vncviewer", get_package_name("tigervnc", package_manager), false)?);
         
         // Network tools (built into Windows)
         dependencies.push(check_dependency_windows("Ping", "ping", "builtin".to_string(), true)?);
     }
     
-    #[cfg(unix)]
+    #[cfg(target_os = "macos")]
+    {
+        // wg-quick runs the tunnel with wireguard-go, macOS has no WireGuard in the kernel
+        dependencies.push(check_dependency("OpenVPN", "openvpn", get_package_name("openvpn", package_manager), true)?);
+        dependencies.push(check_dependency("WireGuard", "wg", get_package_name("wireguard-tools", package_manager), true)?);
+        dependencies.push(check_dependency("wireguard-go", "wireguard-go", get_package_name("wireguard-go", package_manager), true)?);
+        dependencies.push(check_dependency("OpenConnect", "openconnect", get_package_name("openconnect", package_manager), false)?);
+        
+        // RDP goes through Microsoft's app from the App Store
+        dependencies.push(check_dependency_macos_app("Microsoft Remote Desktop", false));
+        dependencies.push(check_dependency("TigerVNC", "vncviewer", get_package_name("tigervnc", package_manager), false)?);
+        
+        // Network tools (built into macOS)
+        dependencies.push(check_dependency("Ping", "ping", "builtin".to_string(), true)?);
+        dependencies.push(check_dependency("Sudo", "sudo", "builtin".to_string(), true)?);
+    }
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
     {
         // VPN dependencies for Unix-like systems
         dependencies.push(check_dependency("OpenVPN", "openvpn", get_package_name("openvpn", package_manager), true)?);
         dependencies.push(check_dependency("WireGuard", "wg", get_package_name("wireguard-tools", package_manager), true)?);
+        dependencies.push(check_dependency("strongSwan", "swanctl", get_package_name("strongswan", package_manager), false)?);
+        dependencies.push(check_dependency("OpenConnect", "openconnect", get_package_name("openconnect", package_manager), false)?);
         
         // RDP dependencies
         dependencies.push(check_dependency("FreeRDP", "xfreerdp", get_package_name("freerdp", package_manager), false)?);
         dependencies.push(check_dependency("Remmina", "remmina", get_package_name("remmina", package_manager), false)?);
         
+        // VNC, also covered by Remmina
+        dependencies.push(check_dependency("TigerVNC", "vncviewer", get_package_name("tigervnc", package_manager), false)?);
+        
         // Network tools
         dependencies.push(check_dependency("Ping", "ping", get_package_name("iputils-ping", package_manager), true)?);
         dependencies.push(check_dependency("Sudo", "sudo", get_package_name("sudo", package_manager), true)?);
@@ -194,9 +287,45 @@ fn check_dependency(name: &str, binary: &str, package: String, required: bool) -
         is_installed,
         version,
         required,
+        upgrade_version: None,
     })
 }
 
+// An app bundle in Applications rather than a binary on the PATH, under either of its names
+#[cfg(target_os = "macos")]
+fn check_dependency_macos_app(name: &str, required: bool) -> Dependency {
+    let found = crate::network::rdp::macos_rdp_app();
+    Dependency {
+        name: name.to_string(),
+        binary_name: found.unwrap_or(crate::network::rdp::MACOS_RDP_APPS[0]).to_string(),
+        package_name: "app-store".to_string(),
+        is_installed: found.is_some(),
+        version: found.map(|app| format!("{}.app", app)),
+        required,
+        upgrade_version: None,
+    }
+}
+
+// An app started from Finder or the Dock gets launchd's PATH, which lacks the folders Homebrew
+// installs brew, openvpn, wg-quick and openconnect to (/opt/homebrew on Apple silicon,
+// /usr/local on Intel). Added once at startup, so `which` and the clients started through sudo
+// find them.
+#[cfg(target_os = "macos")]
+pub fn add_homebrew_to_path() {
+    let mut paths: Vec<std::path::PathBuf> = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).collect())
+        .unwrap_or_default();
+    for dir in ["/opt/homebrew/bin", "/opt/homebrew/sbin", "/usr/local/bin", "/usr/local/sbin"] {
+        let dir = std::path::PathBuf::from(dir);
+        if dir.exists() && !paths.contains(&dir) {
+            paths.push(dir);
+        }
+    }
+    if let Ok(path) = std::env::join_paths(paths) {
+        std::env::set_var("PATH", path);
+    }
+}
+
 #[cfg(windows)]
 fn check_dependency_windows(name: &str, binary: &str, package: String, required: bool) -> Result<Dependency> {
     let is_installed = match binary {
@@ -224,6 +353,16 @@ fn check_dependency_windows(name: &str, binary: &str, package: String, required:
             let mstsc_path = format!("{}\\System32\\mstsc.exe", windir);
             std::path::Path::new(&mstsc_path).exists()
         },
+        "vncviewer" => {
+            // TigerVNC, RealVNC, TightVNC or UltraVNC, wherever its installer put it
+            crate::network::vnc::installed_viewer().is_some()
+        },
+        "rasdial" => {
+            // The RAS dialer ships with Windows
+            let windir = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string());
+            let rasdial_path = format!("{}\\System32\\rasdial.exe", windir);
+            std::path::Path::new(&rasdial_path).exists()
+        },
         "ping" => {
             // Ping is built into Windows
             let windir = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string());
@@ -246,6 +385,7 @@ fn check_dependency_windows(name: &str, binary: &str, package: String, required:
         is_installed,
         version,
         required,
+        upgrade_version: None,
     })
 }
 
@@ -258,6 +398,8 @@ fn get_package_name(default: &str, package_manager: &PackageManager) -> String {
         ("wireguard", PackageManager::Winget) => "WireGuard.WireGuard".to_string(),
         ("wireguard", PackageManager::Chocolatey) => "wireguard".to_string(),
         ("wireguard", PackageManager::Scoop) => "wireguard".to_string(),
+        ("tigervnc", PackageManager::Winget) => "TigerVNC.TigerVNC".to_string(),
+        ("tigervnc", PackageManager::Chocolatey) => "tigervnc-viewer".to_string(),
         
         // Linux package names
         ("freerdp", PackageManager::Apt) => "freerdp2-x11".to_string(),
@@ -267,6 +409,13 @@ fn get_package_name(default: &str, package_manager: &PackageManager) -> String {
         ("wireguard-tools", PackageManager::Apt) => "wireguard-tools".to_string(),
         ("wireguard-tools", PackageManager::Pacman) => "wireguard-tools".to_string(),
         ("wireguard-tools", PackageManager::Dnf) => "wireguard-tools".to_string(),
+        ("strongswan", PackageManager::Apt) => "strongswan-swanctl".to_string(),
+        ("tigervnc", PackageManager::Apt) => "tigervnc-viewer".to_string(),
+        
+        // Homebrew formula names
+        ("wireguard-tools", PackageManager::Homebrew) => "wireguard-tools".to_string(),
+        ("wireguard-go", PackageManager::Homebrew) => "wireguard-go".to_string(),
+        ("tigervnc", PackageManager::Homebrew) => "tiger-vnc".to_string(),
         
         _ => default.to_string(),
     }
@@ -388,4 +537,26 @@ fn get_version_windows(binary: &str) -> Option<String> {
     }
     
     None
-}
\ No newline at end of file
+}
+pub fn open_url(url: &str) {
+    #[cfg(windows)]
+    {
+        let _ = Command::new("cmd")
+            .args(["/c", "start", "", url])
+            .spawn();
+    }
+    
+    #[cfg(target_os = "macos")]
+    {
+        let _ = Command::new("open")
+            .arg(url)
+            .spawn();
+    }
+    
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let _ = Command::new("xdg-open")
+            .arg(url)
+            .spawn();
+    }
+}
@@ -0,0 +1,221 @@
+use crate::config::history::{self, HistoryEventKind};
+use crate::config::Config;
+use crate::network::DeviceStatus;
+use crate::system::sound::{self, Cue};
+use std::collections::HashMap;
+use std::sync::mpsc::{Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+// How long after a wake a device coming online counts as woken by it
+const WAKE_WINDOW: Duration = Duration::from_secs(300);
+
+// Desktop notifications for devices going online or offline, as seen by the periodic checks.
+// Each device is compared against its previous check result, so a notification means the state
+// actually changed rather than that a check ran. The same results feed the availability history.
+// Clicking a notification, or its button where the platform shows one, hands its action back
+// to the app. Sound cues for the same changes, and for a VPN connecting, are played from here.
+pub struct StatusNotifier {
+    devices: HashMap<String, TrackedDevice>,
+    // The connected VPN as last seen; None before the first look
+    vpn: Option<Option<String>>,
+    // Devices sent a wake, and when, for the cue when they come up
+    woken: HashMap<String, Instant>,
+    actions: Sender<NotificationAction>,
+    clicked: Receiver<NotificationAction>,
+    // Wakes the UI so a click is handled without waiting for the next frame
+    waker: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+// What a notification offers to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationAction {
+    OpenDevice(String),
+    ReconnectVpn(String),
+}
+
+impl NotificationAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotificationAction::OpenDevice(_) => "Open",
+            NotificationAction::ReconnectVpn(_) => "Reconnect",
+        }
+    }
+}
+
+impl Default for StatusNotifier {
+    fn default() -> Self {
+        let (actions, clicked) = std::sync::mpsc::channel();
+        Self {
+            devices: HashMap::new(),
+            vpn: None,
+            woken: HashMap::new(),
+            actions,
+            clicked,
+            waker: None,
+        }
+    }
+}
+
+struct TrackedDevice {
+    checked_at: Instant,
+    // None until the first real check
+    online: Option<bool>,
+    in_maintenance: bool,
+}
+
+impl StatusNotifier {
+    pub fn set_waker(&mut self, waker: impl Fn() + Send + Sync + 'static) {
+        self.waker = Some(Arc::new(waker));
+    }
+
+    // The action of a notification clicked since the last call
+    pub fn poll_action(&self) -> Option<NotificationAction> {
+        self.clicked.try_recv().ok()
+    }
+
+    // Plays the cue unless quiet hours or a mute hold notifications about `name` back
+    fn cue(&self, config: &Config, name: &str, cue: Cue) {
+        let notifications = &config.notifications;
+        if !notifications.is_quiet() && !notifications.is_muted(name) {
+            sound::play(&notifications.sounds, cue);
+        }
+    }
+
+    // Only for the cue; a tunnel coming up is no news worth a notification
+    pub fn observe_vpn(&mut self, config: &Config, connected: Option<&str>) {
+        let connected = connected.map(str::to_string);
+        let previous = self.vpn.replace(connected.clone());
+        if let (Some(previous), Some(name)) = (previous, &connected) {
+            if previous.as_ref() != Some(name) {
+                self.cue(config, name, Cue::VpnConnected);
+            }
+        }
+    }
+
+    pub fn wake_sent(&mut self, name: &str) {
+        self.woken.insert(name.to_string(), Instant::now());
+    }
+
+    // A tunnel that went down without being disconnected from the app and isn't being
+    // reconnected, or that reconnecting gave up on
+    pub fn vpn_dropped(&self, config: &Config, name: &str, reason: Option<&str>) {
+        self.cue(config, name, Cue::VpnDropped);
+        if !config.notifications.allows(name) {
+            log::debug!("Notification for {} suppressed", name);
+            return;
+        }
+        let summary = match reason {
+            Some(_) => format!("Reconnecting to {} failed", name),
+            None => format!("VPN {} dropped", name),
+        };
+        self.send(&summary, reason.unwrap_or("The tunnel is down"), Some(NotificationAction::ReconnectVpn(name.to_string())));
+    }
+
+    // The tunnel dropped and the next profile in the failover order took over
+    pub fn vpn_failed_over(&self, config: &Config, from: &str, to: &str) {
+        if !config.notifications.allows(from) {
+            log::debug!("Notification for {} suppressed", from);
+            return;
+        }
+        self.send(&format!("Switched to VPN {}", to), &format!("{} dropped", from), None);
+    }
+
+    pub fn observe_devices(&mut self, config: &Config, statuses: &[DeviceStatus]) {
+        self.devices.retain(|name, _| statuses.iter().any(|status| status.name == *name));
+        self.woken.retain(|_, at| at.elapsed() < WAKE_WINDOW);
+
+        for status in statuses {
+            let name = &status.name;
+            let Some(tracked) = self.devices.get_mut(name) else {
+                // Devices start out offline until checked, so the first state seen isn't a result
+                self.devices.insert(name.clone(), TrackedDevice {
+                    checked_at: status.last_checked,
+                    online: None,
+                    in_maintenance: false,
+                });
+                continue;
+            };
+
+            if tracked.checked_at == status.last_checked {
+                continue;
+            }
+            tracked.checked_at = status.last_checked;
+
+            let previous = tracked.online.replace(status.is_online);
+            let was_in_maintenance = tracked.in_maintenance;
+            tracked.in_maintenance = config.device(name).is_some_and(|d| d.in_maintenance());
+
+            if tracked.in_maintenance {
+                if !was_in_maintenance {
+                    history::record(HistoryEventKind::DeviceMaintenance, name, true);
+                }
+                continue;
+            }
+
+            let changed = previous.is_some_and(|was_online| was_online != status.is_online);
+            if changed || previous.is_none() || was_in_maintenance {
+                let kind = if status.is_online { HistoryEventKind::DeviceOnline } else { HistoryEventKind::DeviceOffline };
+                history::record(kind, name, true);
+            }
+
+            // A device coming back from maintenance was expected to flap, so only report changes
+            // seen outside the window
+            if !changed || was_in_maintenance {
+                continue;
+            }
+            let cue = match (status.is_online, self.woken.remove(name).is_some()) {
+                (true, true) => Cue::DeviceWoken,
+                (true, false) => Cue::DeviceOnline,
+                (false, _) => Cue::DeviceOffline,
+            };
+            self.cue(config, name, cue);
+            if !config.notifications.allows(name) {
+                log::debug!("Notification for {} suppressed", name);
+                continue;
+            }
+
+            let summary = if status.is_online { format!("{} is online", name) } else { format!("{} went offline", name) };
+            self.send(&summary, &status.address, Some(NotificationAction::OpenDevice(name.clone())));
+        }
+    }
+
+    // Shown from a thread since the platform call can block (D-Bus on Linux), which then waits
+    // for the notification to be clicked or closed
+    fn send(&self, summary: &str, body: &str, action: Option<NotificationAction>) {
+        let summary = summary.to_string();
+        let body = body.to_string();
+        let actions = self.actions.clone();
+        let waker = self.waker.clone();
+
+        std::thread::spawn(move || {
+            let mut notification = notify_rust::Notification::new();
+            notification.appname("VPN Manager").summary(&summary).body(&body);
+            let Some(action) = action else {
+                if let Err(e) = notification.show() {
+                    log::warn!("Failed to show notification: {}", e);
+                }
+                return;
+            };
+            // "default" is a click on the notification itself
+            notification.action("default", action.label()).action("action", action.label());
+
+            let handle = match notification.show() {
+                Ok(handle) => handle,
+                Err(e) => {
+                    log::warn!("Failed to show notification: {}", e);
+                    return;
+                }
+            };
+
+            handle.wait_for_action(|clicked| {
+                if clicked == "default" || clicked == "action" {
+                    let _ = actions.send(action);
+                    if let Some(waker) = &waker {
+                        waker();
+                    }
+                }
+            });
+        });
+    }
+}
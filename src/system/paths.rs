@@ -0,0 +1,96 @@
+use crate::config::Config;
+use std::path::{Path, PathBuf};
+
+// Where the app keeps what isn't settings. Linux follows the XDG base directories: history and
+// logs under $XDG_STATE_HOME (~/.local/state), caches under $XDG_CACHE_HOME (~/.cache). Windows
+// keeps them in %LOCALAPPDATA%, which doesn't roam with the profile, and macOS in
+// ~/Library/Application Support, ~/Library/Logs and ~/Library/Caches. Settings and the files
+// imported with them stay in Config::config_dir.
+
+const APP_DIR: &str = "vpn-manager";
+
+// History and other records kept between runs
+pub fn state_dir() -> PathBuf {
+    // Tests record history like the app does, away from the real one
+    if cfg!(test) {
+        return std::env::temp_dir().join(APP_DIR).join("test-state");
+    }
+    let base = if cfg!(target_os = "linux") { dirs::state_dir() } else { dirs::data_local_dir() };
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+pub fn logs_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join("Library").join("Logs").join(APP_DIR);
+        }
+    }
+    state_dir().join("logs")
+}
+
+// Whatever can be fetched again, e.g. the last update check
+pub fn cache_dir() -> PathBuf {
+    if cfg!(windows) {
+        // %LOCALAPPDATA% holds the state too
+        return state_dir().join("cache");
+    }
+    dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+// Copies of the app from before an update
+pub fn backups_dir() -> PathBuf {
+    state_dir().join("backups")
+}
+
+// For Settings, in the order shown
+pub fn locations() -> Vec<(&'static str, PathBuf)> {
+    vec![
+        ("Settings", Config::config_dir()),
+        ("Imported VPN files", Config::managed_files_dir()),
+        ("Thumbnails", crate::system::thumbnails::dir()),
+        ("History", state_dir()),
+        ("Logs", logs_dir()),
+        ("Cache", cache_dir()),
+        ("Backups", backups_dir()),
+    ]
+}
+
+// Opens the folder in the file manager, creating it first so there is something to open
+pub fn open(dir: &Path) {
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        log::warn!("Could not create {}: {}", dir.display(), e);
+    }
+    crate::system::open_url(&dir.display().to_string());
+}
+
+// Earlier versions kept history and logs in the data folder (roaming on Windows) and the update
+// check next to the settings. Moves what is still there and returns how many were moved; called
+// before logging starts, so the app log finds its new place free.
+pub fn migrate_legacy() -> usize {
+    let Some(old_data) = dirs::data_dir().map(|dir| dir.join(APP_DIR)) else {
+        return 0;
+    };
+    let moves = [
+        (old_data.join("history.json"), state_dir().join("history.json")),
+        (old_data.join("logs"), logs_dir()),
+        (old_data.join("vpn-manager.log"), logs_dir().join("vpn-manager.log")),
+        (Config::config_dir().join("update-check.json"), cache_dir().join("update-check.json")),
+    ];
+
+    let mut moved = 0;
+    for (from, to) in moves {
+        if from == to || !from.exists() || to.exists() {
+            continue;
+        }
+        if let Some(parent) = to.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        // A different file system (rename fails) leaves the file where it was
+        if std::fs::rename(&from, &to).is_ok() {
+            moved += 1;
+        }
+    }
+    // Only removed once empty
+    let _ = std::fs::remove_dir(&old_data);
+    moved
+}
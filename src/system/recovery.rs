@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+// Session restore after a crash. While the app runs, what it is in the middle of is kept in
+// session.json in the state folder: the VPN that is up, wakes still waiting for their packet to
+// go out or for a retry, and the open tab. A clean exit deletes the file, so finding one at
+// startup means the last run crashed or was killed, and the app offers to pick up from there.
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    #[serde(default)]
+    pub vpn: Option<String>,
+    // Devices whose wake hadn't finished
+    #[serde(default)]
+    pub pending_wakes: Vec<String>,
+    // Panel::name of the open tab
+    #[serde(default)]
+    pub panel: String,
+}
+
+impl SessionState {
+    // Nothing that restoring would bring back
+    pub fn is_empty(&self) -> bool {
+        self.vpn.is_none() && self.pending_wakes.is_empty() && (self.panel.is_empty() || self.panel == "home")
+    }
+}
+
+fn session_path() -> PathBuf {
+    crate::system::paths::state_dir().join("session.json")
+}
+
+// The session a previous run left behind, with when it was last written; None after a clean exit
+pub fn unclean_exit() -> Option<(SessionState, Option<chrono::DateTime<chrono::Local>>)> {
+    let path = session_path();
+    let content = std::fs::read_to_string(&path).ok()?;
+    let state: SessionState = match serde_json::from_str(&content) {
+        Ok(state) => state,
+        Err(e) => {
+            log::warn!("Ignoring unreadable {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let saved_at = std::fs::metadata(&path).and_then(|meta| meta.modified()).ok().map(chrono::DateTime::from);
+    Some((state, saved_at))
+}
+
+pub fn save(state: &SessionState) -> anyhow::Result<()> {
+    let path = session_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Written aside and moved over, so a crash mid-write doesn't leave half a file
+    let temp = path.with_extension("json.tmp");
+    std::fs::write(&temp, serde_json::to_string(state)?)?;
+    std::fs::rename(temp, path)?;
+    Ok(())
+}
+
+// Called on a clean exit
+pub fn clear() {
+    let path = session_path();
+    if let Err(e) = std::fs::remove_file(&path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Could not remove {}: {}", path.display(), e);
+        }
+    }
+}
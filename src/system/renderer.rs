@@ -0,0 +1,237 @@
+use crate::system::session::{DisplaySession, SessionType};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::sync::Mutex;
+
+// How the window is drawn. OpenGL (glow) works nearly everywhere, wgpu takes Vulkan, Metal or
+// Direct3D instead, and software is OpenGL through Mesa's llvmpipe for machines without a usable
+// GPU: VMs without 3D acceleration, X forwarded over SSH, servers. `--renderer` picks one;
+// otherwise the environment is looked at first, and a renderer that fails to start hands over
+// to the next one by starting the app again with it.
+
+pub const RENDERER_ARG: &str = "--renderer";
+// Set for the app started again after a renderer failed, with why; keeps the fallback going
+const FALLBACK_ENV: &str = "VPN_MANAGER_RENDERER_FALLBACK";
+// Display drivers that only put pixels on the screen
+const NO_3D_DRIVERS: &[&str] = &["bochs-drm", "bochs", "cirrus", "cirrus-qemu", "vboxvideo", "hyperv_drm", "simpledrm", "efifb", "mgag200", "ast"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Renderer {
+    Glow,
+    Wgpu,
+    Software,
+}
+
+// The renderer in use and how it came to be, for the About card
+#[derive(Debug, Clone)]
+pub struct Choice {
+    pub renderer: Renderer,
+    // Why it isn't the default; empty when it is
+    pub reason: String,
+    // Not forced with --renderer, so a failure falls back
+    pub automatic: bool,
+    // The GPU or software rasterizer, once the window is up
+    pub device: String,
+}
+
+static ACTIVE: Mutex<Option<Choice>> = Mutex::new(None);
+
+impl Renderer {
+    pub const ALL: [Renderer; 3] = [Renderer::Glow, Renderer::Wgpu, Renderer::Software];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Renderer::Glow => "glow",
+            Renderer::Wgpu => "wgpu",
+            Renderer::Software => "software",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|renderer| renderer.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Renderer::Glow => "OpenGL (glow)",
+            Renderer::Wgpu => "wgpu",
+            Renderer::Software => "Software (llvmpipe)",
+        }
+    }
+
+    // Tried after this one failed to start. Mesa's software rasterizer is Linux only; wgpu on
+    // Windows already ends up on WARP when there is no GPU.
+    pub fn next(self) -> Option<Self> {
+        match self {
+            Renderer::Glow => Some(Renderer::Wgpu),
+            Renderer::Wgpu if cfg!(target_os = "linux") => Some(Renderer::Software),
+            Renderer::Wgpu | Renderer::Software => None,
+        }
+    }
+
+    // Sets up the window options, and the environment Mesa reads, for this renderer
+    pub fn apply(self, options: &mut eframe::NativeOptions) {
+        options.renderer = match self {
+            Renderer::Glow | Renderer::Software => eframe::Renderer::Glow,
+            Renderer::Wgpu => eframe::Renderer::Wgpu,
+        };
+        if self == Renderer::Software {
+            // Still single threaded here, nothing reads the environment concurrently
+            std::env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+            std::env::set_var("GALLIUM_DRIVER", "llvmpipe");
+        }
+    }
+}
+
+// Takes `--renderer <name>` or `--renderer=<name>` out of the arguments
+pub fn take_arg(args: &mut Vec<String>) -> Result<Option<Renderer>> {
+    let Some(index) = args.iter().position(|arg| arg == RENDERER_ARG || arg.starts_with(&format!("{}=", RENDERER_ARG))) else {
+        return Ok(None);
+    };
+    let arg = args.remove(index);
+    let value = match arg.split_once('=') {
+        Some((_, value)) => value.to_string(),
+        None if index < args.len() => args.remove(index),
+        None => return Err(anyhow!("{} needs a value", RENDERER_ARG)),
+    };
+    let renderer = Renderer::from_name(&value).ok_or_else(|| {
+        let names: Vec<&str> = Renderer::ALL.iter().map(|renderer| renderer.name()).collect();
+        anyhow!("Unknown renderer '{}', expected {}", value, names.join(", "))
+    })?;
+    if renderer == Renderer::Software && !cfg!(target_os = "linux") {
+        log::warn!("Software rendering needs Mesa and is only available on Linux, using wgpu");
+        return Ok(Some(Renderer::Wgpu));
+    }
+    Ok(Some(renderer))
+}
+
+// The renderer to start with: the one asked for, or what suits the environment
+pub fn choose(requested: Option<Renderer>) -> Choice {
+    // Started again after a failure: the renderer given is the next in line
+    if let Ok(reason) = std::env::var(FALLBACK_ENV) {
+        let renderer = requested.unwrap_or(Renderer::Glow);
+        return Choice { renderer, reason, automatic: true, device: String::new() };
+    }
+    if let Some(renderer) = requested {
+        return Choice { renderer, reason: format!("Chosen with {}", RENDERER_ARG), automatic: false, device: String::new() };
+    }
+    let (renderer, reason) = detect().unwrap_or((Renderer::Glow, String::new()));
+    Choice { renderer, reason, automatic: true, device: String::new() }
+}
+
+// A renderer other than OpenGL when the environment has no use for it, with why
+fn detect() -> Option<(Renderer, String)> {
+    if cfg!(windows) {
+        // Remote Desktop often offers only OpenGL 1.1, Direct3D works there
+        let session = std::env::var("SESSIONNAME").unwrap_or_default();
+        return session.starts_with("RDP-").then(|| (Renderer::Wgpu, "Remote Desktop session".to_string()));
+    }
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    let session = DisplaySession::detect();
+    if session.session_type == SessionType::X11 {
+        // A forwarded display is host:n; OpenGL over it is indirect at best
+        let display = session.display.clone().unwrap_or_default();
+        if !display.starts_with(':') && !display.starts_with("unix:") {
+            return Some((Renderer::Software, format!("X11 display {} is on another machine", display)));
+        }
+    }
+
+    let drivers = gpu_drivers();
+    if drivers.is_empty() && !Path::new("/dev/dri").exists() {
+        return Some((Renderer::Software, "No GPU found (/dev/dri is missing)".to_string()));
+    }
+    if !drivers.is_empty() && drivers.iter().all(|driver| NO_3D_DRIVERS.contains(&driver.as_str())) {
+        let adapter = match virtual_machine() {
+            Some(vm) => format!("{} virtual machine", vm),
+            None => "Display adapter".to_string(),
+        };
+        return Some((Renderer::Software, format!("{} without 3D acceleration ({})", adapter, drivers.join(", "))));
+    }
+    None
+}
+
+// Kernel drivers of the display adapters, e.g. i915, amdgpu or vboxvideo
+fn gpu_drivers() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/drm") else { return Vec::new() };
+    let mut drivers: Vec<String> = entries
+        .flatten()
+        .filter(|entry| {
+            // card0, not its connectors like card0-HDMI-A-1
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix("card").is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()))
+        })
+        .filter_map(|entry| std::fs::read_link(entry.path().join("device/driver")).ok())
+        .filter_map(|driver| driver.file_name().map(|name| name.to_string_lossy().to_string()))
+        .collect();
+    drivers.sort();
+    drivers.dedup();
+    drivers
+}
+
+fn virtual_machine() -> Option<&'static str> {
+    let read = |file: &str| std::fs::read_to_string(Path::new("/sys/class/dmi/id").join(file)).unwrap_or_default();
+    let dmi = format!("{} {}", read("sys_vendor"), read("product_name"));
+    [
+        ("VirtualBox", "VirtualBox"),
+        ("VMware", "VMware"),
+        ("QEMU", "QEMU"),
+        ("KVM", "KVM"),
+        ("Microsoft Corporation Virtual Machine", "Hyper-V"),
+        ("Parallels", "Parallels"),
+        ("Xen", "Xen"),
+    ]
+    .into_iter()
+    .find(|(marker, _)| dmi.contains(marker))
+    .map(|(_, name)| name)
+}
+
+// Whether the error is the renderer failing to start, which another renderer may not
+pub fn is_renderer_error(error: &eframe::Error) -> bool {
+    matches!(error, eframe::Error::Glutin(_) | eframe::Error::NoGlutinConfigs(..) | eframe::Error::OpenGL(_) | eframe::Error::Wgpu(_))
+}
+
+// Starts the app again with the next renderer and waits for it, returning its exit code
+pub fn relaunch(failed: Renderer, next: Renderer, error: &eframe::Error, args: &[String]) -> Result<i32> {
+    let reason = format!("{} failed to start: {}", failed.label(), error);
+    log::warn!("{}, trying {}", reason, next.label());
+    let status = std::process::Command::new(std::env::current_exe()?)
+        .args(args)
+        .arg(RENDERER_ARG)
+        .arg(next.name())
+        .env(FALLBACK_ENV, reason)
+        .status()?;
+    Ok(status.code().unwrap_or(1))
+}
+
+pub fn set_active(choice: Choice) {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = Some(choice);
+    }
+}
+
+// Fills in the device of the renderer that started
+pub fn set_device(cc: &eframe::CreationContext<'_>) {
+    let device = if let Some(gl) = &cc.gl {
+        use eframe::glow::HasContext;
+        // Only reads a string from the context eframe just made current
+        unsafe { gl.get_parameter_string(eframe::glow::RENDERER) }
+    } else if let Some(state) = &cc.wgpu_render_state {
+        let info = state.adapter.get_info();
+        format!("{} ({:?})", info.name, info.backend)
+    } else {
+        return;
+    };
+    log::info!("Rendering with {}", device);
+    if let Ok(mut active) = ACTIVE.lock() {
+        if let Some(choice) = active.as_mut() {
+            choice.device = device;
+        }
+    }
+}
+
+pub fn active() -> Option<Choice> {
+    ACTIVE.lock().ok()?.clone()
+}
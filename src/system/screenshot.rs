@@ -0,0 +1,63 @@
+use anyhow::Result;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::errors::{self, ErrorCode};
+
+// Screenshots of the whole screen with the platform's own tool, as PNG
+
+#[cfg(unix)]
+pub async fn capture(path: &Path) -> Result<()> {
+    let path = path.display().to_string();
+    let tools: [(&str, Vec<&str>); 6] = [
+        ("grim", vec![&path]),
+        ("gnome-screenshot", vec!["-f", &path]),
+        ("spectacle", vec!["-b", "-n", "-f", "-o", &path]),
+        ("scrot", vec!["-o", &path]),
+        ("import", vec!["-window", "root", &path]),
+        ("screencapture", vec!["-x", &path]),
+    ];
+    let (tool, args) = tools.iter()
+        .find(|(tool, _)| which::which(tool).is_ok())
+        .ok_or_else(|| errors::error(ErrorCode::ScreenshotToolMissing, ""))?;
+
+    let output = Command::new(tool)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("{} failed: {}", tool, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub async fn capture(path: &Path) -> Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms,System.Drawing; \
+         $b = [System.Windows.Forms.SystemInformation]::VirtualScreen; \
+         $bmp = New-Object System.Drawing.Bitmap $b.Width, $b.Height; \
+         [System.Drawing.Graphics]::FromImage($bmp).CopyFromScreen($b.Left, $b.Top, 0, 0, $bmp.Size); \
+         $bmp.Save('{}', [System.Drawing.Imaging.ImageFormat]::Png)",
+        path.display().to_string().replace('\'', "''")
+    );
+    let mut cmd = Command::new("powershell");
+    cmd.args(&["-NoProfile", "-Command", &script])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdin(Stdio::null());
+    cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = cmd.output().await?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("Screenshot failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
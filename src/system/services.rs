@@ -0,0 +1,170 @@
+use crate::config::{VpnConfig, VpnType};
+use crate::network::{certs, ovpn_bundle, proxy};
+use crate::errors::{self, ErrorCode};
+use crate::system::boot;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+// VPN profiles installed as the units the distributions ship with the clients, openvpn-client@
+// and wg-quick@, for tunnels that keep running when the app quits and, once enabled, come up at
+// boot. Unlike system::boot, which writes a unit of its own, the profile is copied into the
+// client's folder under /etc, so the tunnel works the same as one set up by hand and can be
+// managed with systemctl without the app. An installed entry connects and disconnects by
+// starting and stopping its unit; reinstalling picks up changes to its file.
+
+const OPENVPN_DIR: &str = "/etc/openvpn/client";
+const WIREGUARD_DIR: &str = "/etc/wireguard";
+
+pub fn is_supported() -> bool {
+    boot::is_supported()
+}
+
+// The only clients with template units in the distributions' packages
+pub fn supports(vpn_type: &VpnType) -> bool {
+    matches!(vpn_type, VpnType::OpenVpn | VpnType::WireGuard)
+}
+
+// The unit's instance, which names the profile in the client's folder. wg-quick names the
+// interface after it, so a WireGuard entry keeps the name of its file.
+pub fn instance(config: &VpnConfig) -> String {
+    let name = match config.vpn_type {
+        VpnType::WireGuard => Path::new(&config.resolved_config_path())
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| config.name.clone()),
+        _ => format!("vpn-manager-{}", config.name),
+    };
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+pub fn unit_name(config: &VpnConfig) -> String {
+    let template = if config.vpn_type == VpnType::WireGuard { "wg-quick" } else { "openvpn-client" };
+    format!("{}@{}.service", template, instance(config))
+}
+
+pub fn profile_path(config: &VpnConfig) -> PathBuf {
+    let dir = if config.vpn_type == VpnType::WireGuard { WIREGUARD_DIR } else { OPENVPN_DIR };
+    Path::new(dir).join(format!("{}.conf", instance(config)))
+}
+
+fn auth_path(config: &VpnConfig) -> PathBuf {
+    Path::new(OPENVPN_DIR).join(format!("{}.auth", instance(config)))
+}
+
+fn proxy_auth_path(config: &VpnConfig) -> PathBuf {
+    Path::new(OPENVPN_DIR).join(format!("{}.proxy-auth", instance(config)))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ServiceStatus {
+    pub active: bool,
+    pub enabled: bool,
+}
+
+// Read from systemctl, which any user may ask
+pub async fn status(config: &VpnConfig) -> ServiceStatus {
+    let unit = unit_name(config);
+    ServiceStatus {
+        active: query(&["is-active", "--quiet", &unit]).await,
+        enabled: query(&["is-enabled", "--quiet", &unit]).await,
+    }
+}
+
+pub async fn is_active(config: &VpnConfig) -> bool {
+    query(&["is-active", "--quiet", &unit_name(config)]).await
+}
+
+async fn query(args: &[&str]) -> bool {
+    Command::new("systemctl")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .is_ok_and(|status| status.success())
+}
+
+// The profile as the unit reads it: an OpenVPN profile has the files it names inlined, since
+// the unit runs it from another folder, as are the entry's attached certificates, and its
+// credentials and the entry's proxy in files next to it
+fn profile(config: &VpnConfig) -> Result<String> {
+    let path = config.resolved_config_path();
+    if config.vpn_type == VpnType::WireGuard {
+        return Ok(std::fs::read_to_string(&path)?);
+    }
+
+    let content = certs::inject(&ovpn_bundle::standalone(Path::new(&path))?, config)?;
+    let content = proxy::inject(&content, config, &proxy_auth_path(config));
+    if config.username.is_empty() {
+        return Ok(content);
+    }
+    let auth = format!("auth-user-pass {}", auth_path(config).display());
+    let mut lines: Vec<String> = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("auth-user-pass"))
+        .map(|line| line.to_string())
+        .collect();
+    lines.push(auth);
+    lines.push("auth-nocache".to_string());
+    Ok(lines.join("\n") + "\n")
+}
+
+// Also replaces a previous install, so edits to the entry's file carry over
+pub async fn install(config: &VpnConfig) -> Result<()> {
+    if !is_supported() {
+        return Err(errors::error(ErrorCode::SystemdMissing, ""));
+    }
+    if !supports(&config.vpn_type) {
+        return Err(anyhow::anyhow!("Only OpenVPN and WireGuard entries can be installed as a system service"));
+    }
+    let content = profile(config)?;
+
+    if config.vpn_type == VpnType::OpenVpn && !config.username.is_empty() {
+        let credentials = format!("{}\n{}\n", config.username, config.password);
+        boot::write_root(config, &auth_path(config), &credentials).await?;
+    }
+    if let Some(credentials) = proxy::credentials(config) {
+        boot::write_root(config, &proxy_auth_path(config), &credentials).await?;
+    }
+    boot::write_root(config, &profile_path(config), &content).await?;
+    log::info!("Installed {} as {}", config.name, unit_name(config));
+    Ok(())
+}
+
+// Stops the tunnel first, the app couldn't tell it is up once the unit is gone
+pub async fn uninstall(config: &VpnConfig) -> Result<()> {
+    systemctl(config, &["disable", "--now", &unit_name(config)]).await?;
+    let profile = profile_path(config).display().to_string();
+    let auth = auth_path(config).display().to_string();
+    let proxy_auth = proxy_auth_path(config).display().to_string();
+    let mut args = vec!["-f", profile.as_str()];
+    if config.vpn_type == VpnType::OpenVpn {
+        args.push(&auth);
+        args.push(&proxy_auth);
+    }
+    boot::run(config, "rm", &args, None).await?;
+    log::info!("Removed the system service of {}", config.name);
+    Ok(())
+}
+
+// Whether the unit starts at boot
+pub async fn set_enabled(config: &VpnConfig, enabled: bool) -> Result<()> {
+    systemctl(config, &[if enabled { "enable" } else { "disable" }, &unit_name(config)]).await
+}
+
+pub async fn start(config: &VpnConfig) -> Result<()> {
+    systemctl(config, &["start", &unit_name(config)]).await
+}
+
+pub async fn stop(config: &VpnConfig) -> Result<()> {
+    systemctl(config, &["stop", &unit_name(config)]).await
+}
+
+async fn systemctl(config: &VpnConfig, args: &[&str]) -> Result<()> {
+    boot::run(config, "systemctl", args, None).await
+}
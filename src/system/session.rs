@@ -0,0 +1,99 @@
+use crate::errors::{self, ErrorCode};
+use anyhow::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionType {
+    X11,
+    Wayland,
+    Headless,
+    Native, // Windows and macOS always have a compositor
+}
+
+#[derive(Debug, Clone)]
+pub struct DisplaySession {
+    pub session_type: SessionType,
+    pub display: Option<String>,
+    pub wayland_display: Option<String>,
+    pub is_ssh: bool,
+}
+
+impl DisplaySession {
+    pub fn detect() -> Self {
+        let display = non_empty_env("DISPLAY");
+        let wayland_display = non_empty_env("WAYLAND_DISPLAY");
+        let is_ssh = non_empty_env("SSH_CONNECTION").is_some()
+            || non_empty_env("SSH_CLIENT").is_some()
+            || non_empty_env("SSH_TTY").is_some();
+
+        let session_type = if cfg!(any(windows, target_os = "macos")) {
+            SessionType::Native
+        } else {
+            // XDG_SESSION_TYPE is authoritative when set, but only if the socket variable backs it up
+            match non_empty_env("XDG_SESSION_TYPE").as_deref() {
+                Some("wayland") if wayland_display.is_some() => SessionType::Wayland,
+                Some("x11") if display.is_some() => SessionType::X11,
+                _ if wayland_display.is_some() => SessionType::Wayland,
+                _ if display.is_some() => SessionType::X11,
+                _ => SessionType::Headless,
+            }
+        };
+
+        Self {
+            session_type,
+            display,
+            wayland_display,
+            is_ssh,
+        }
+    }
+
+    pub fn has_display(&self) -> bool {
+        self.session_type != SessionType::Headless
+    }
+
+    // XWayland lets X11-only clients run inside a Wayland session
+    pub fn supports_x11_clients(&self) -> bool {
+        match self.session_type {
+            SessionType::X11 => true,
+            SessionType::Wayland => self.display.is_some(),
+            _ => false,
+        }
+    }
+
+    pub fn description(&self) -> String {
+        let base = match self.session_type {
+            SessionType::X11 => format!("X11 ({})", self.display.as_deref().unwrap_or("?")),
+            SessionType::Wayland => {
+                if self.display.is_some() {
+                    format!("Wayland ({}, XWayland available)", self.wayland_display.as_deref().unwrap_or("?"))
+                } else {
+                    format!("Wayland ({})", self.wayland_display.as_deref().unwrap_or("?"))
+                }
+            }
+            SessionType::Headless => "No display".to_string(),
+            SessionType::Native => "Native".to_string(),
+        };
+
+        if self.is_ssh {
+            format!("{} over SSH", base)
+        } else {
+            base
+        }
+    }
+
+    // Returns an actionable error when a GUI client can't possibly open a window
+    pub fn require_display(&self, client: &str) -> Result<()> {
+        if self.has_display() {
+            return Ok(());
+        }
+
+        if self.is_ssh {
+            Err(errors::error(ErrorCode::SshWithoutDisplay, client))
+        } else {
+            Err(errors::error(ErrorCode::NoDisplay, client))
+        }
+    }
+}
+
+fn non_empty_env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
@@ -0,0 +1,150 @@
+use crate::config::SoundCues;
+use std::f32::consts::TAU;
+use std::process::{Command, Stdio};
+
+// Short tones for status changes, for when the window is hidden or can't be seen. Each cue is a
+// few sine notes rendered to a WAV file at the chosen volume and handed to the platform's
+// player, so no audio library or bundled sound files are needed.
+
+const SAMPLE_RATE: u32 = 22050;
+// Ramped in and out, so the notes don't click
+const FADE_SECS: f32 = 0.008;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cue {
+    VpnConnected,
+    VpnDropped,
+    DeviceWoken,
+    DeviceOnline,
+    DeviceOffline,
+}
+
+impl Cue {
+    pub const ALL: [Cue; 5] = [Cue::VpnConnected, Cue::VpnDropped, Cue::DeviceWoken, Cue::DeviceOnline, Cue::DeviceOffline];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Cue::VpnConnected => "VPN connected",
+            Cue::VpnDropped => "VPN dropped",
+            Cue::DeviceWoken => "Device woke up",
+            Cue::DeviceOnline => "Device online",
+            Cue::DeviceOffline => "Device offline",
+        }
+    }
+
+    pub fn toggle<'a>(&self, cues: &'a mut SoundCues) -> &'a mut bool {
+        match self {
+            Cue::VpnConnected => &mut cues.vpn_connected,
+            Cue::VpnDropped => &mut cues.vpn_dropped,
+            Cue::DeviceWoken => &mut cues.device_woken,
+            Cue::DeviceOnline => &mut cues.device_online,
+            Cue::DeviceOffline => &mut cues.device_offline,
+        }
+    }
+
+    fn is_on(&self, cues: &SoundCues) -> bool {
+        *self.toggle(&mut cues.clone())
+    }
+
+    // Frequency (Hz) and length (s) of each note; rising for good news, falling for bad
+    fn notes(&self) -> &'static [(f32, f32)] {
+        match self {
+            Cue::VpnConnected => &[(660.0, 0.09), (880.0, 0.14)],
+            Cue::VpnDropped => &[(660.0, 0.09), (440.0, 0.18)],
+            Cue::DeviceWoken => &[(523.0, 0.07), (659.0, 0.07), (784.0, 0.12)],
+            Cue::DeviceOnline => &[(880.0, 0.1)],
+            Cue::DeviceOffline => &[(392.0, 0.14)],
+        }
+    }
+}
+
+// Plays the cue if it is switched on
+pub fn play(cues: &SoundCues, cue: Cue) {
+    if cues.enabled && cue.is_on(cues) {
+        preview(cue, cues.volume);
+    }
+}
+
+// Plays the cue regardless of the settings, e.g. to try the volume
+pub fn preview(cue: Cue, volume: u8) {
+    let wav = render(cue, volume);
+    std::thread::spawn(move || {
+        let path = std::env::temp_dir().join(format!("vpn-manager-cue-{}-{:?}.wav", std::process::id(), cue));
+        if let Err(e) = std::fs::write(&path, wav) {
+            log::warn!("Could not write sound cue: {}", e);
+            return;
+        }
+        if !play_file(&path) {
+            log::debug!("No audio player found for sound cues");
+        }
+        let _ = std::fs::remove_file(&path);
+    });
+}
+
+fn render(cue: Cue, volume: u8) -> Vec<u8> {
+    let amplitude = i16::MAX as f32 * 0.6 * (volume.min(100) as f32 / 100.0);
+    let fade = (FADE_SECS * SAMPLE_RATE as f32) as usize;
+    let mut samples: Vec<i16> = Vec::new();
+    for &(frequency, secs) in cue.notes() {
+        let count = (secs * SAMPLE_RATE as f32) as usize;
+        for i in 0..count {
+            let envelope = (i.min(count - i) as f32 / fade as f32).min(1.0);
+            let value = (TAU * frequency * i as f32 / SAMPLE_RATE as f32).sin();
+            samples.push((value * envelope * amplitude) as i16);
+        }
+    }
+
+    // 16-bit mono PCM
+    let data_len = (samples.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+// Waits for the sound to finish; false if no player could be started
+fn play_file(path: &std::path::Path) -> bool {
+    let path = path.display().to_string();
+    let players: Vec<(&str, Vec<String>)> = if cfg!(windows) {
+        let script = format!("(New-Object Media.SoundPlayer '{}').PlaySync()", path.replace('\'', "''"));
+        vec![("powershell", vec!["-NoProfile".to_string(), "-Command".to_string(), script])]
+    } else if cfg!(target_os = "macos") {
+        vec![("afplay", vec![path])]
+    } else {
+        // PulseAudio or PipeWire, then plain ALSA
+        vec![
+            ("paplay", vec![path.clone()]),
+            ("pw-play", vec![path.clone()]),
+            ("aplay", vec!["-q".to_string(), path]),
+        ]
+    };
+
+    for (program, args) in players {
+        let mut cmd = Command::new(program);
+        cmd.args(&args).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+        if let Ok(status) = cmd.status() {
+            if status.success() {
+                return true;
+            }
+        }
+    }
+    false
+}
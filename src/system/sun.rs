@@ -0,0 +1,39 @@
+// Approximate sunrise/sunset from the sunrise equation, accurate to a few minutes,
+// which is plenty for picking a theme
+// https://en.wikipedia.org/wiki/Sunrise_equation
+
+const UNIX_EPOCH_JULIAN: f64 = 2440587.5;
+const J2000: f64 = 2451545.0;
+
+// Returns true if the sun is above the horizon at `unix_secs` for the given position
+// (latitude north positive, longitude east positive)
+pub fn is_daylight(unix_secs: i64, latitude: f64, longitude: f64) -> bool {
+    let now = unix_secs as f64 / 86400.0 + UNIX_EPOCH_JULIAN;
+
+    // Solar noon closest to now, so the check is always against the current solar day
+    let n = (now - J2000 + longitude / 360.0).round();
+    let mean_solar_time = n - longitude / 360.0;
+
+    let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let m = mean_anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.0200 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic_longitude = (mean_anomaly + center + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+
+    let transit = J2000 + mean_solar_time + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397_f64.to_radians().sin()).asin();
+    let phi = latitude.to_radians();
+    let cos_hour_angle = ((-0.833_f64).to_radians().sin() - phi.sin() * declination.sin())
+        / (phi.cos() * declination.cos());
+
+    // Polar night and midnight sun
+    if cos_hour_angle > 1.0 {
+        return false;
+    }
+    if cos_hour_angle < -1.0 {
+        return true;
+    }
+
+    let half_day = cos_hour_angle.acos().to_degrees() / 360.0;
+    (now - transit).abs() < half_day
+}
@@ -0,0 +1,66 @@
+use crate::config::Config;
+use crate::system::screenshot;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Small pictures of what a host's desktop looks like, shown on its card so similar hosts are
+// told apart at a glance. One PNG per device, named after it, either chosen by the user or a
+// screenshot taken while a session is on screen.
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 100;
+// After launching a session, for the client to connect and show the remote desktop
+pub const SESSION_DELAY: Duration = Duration::from_secs(15);
+
+pub fn dir() -> PathBuf {
+    Config::config_dir().join("thumbnails")
+}
+
+pub fn path(device_name: &str) -> PathBuf {
+    let name: String = device_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    dir().join(format!("{}.png", name))
+}
+
+// Scaled down to fit the card and saved for the device, replacing its previous one
+pub fn set_from_file(device_name: &str, source: &Path) -> Result<()> {
+    let image = image::open(source).map_err(|e| anyhow::anyhow!("Could not open {}: {}", source.display(), e))?;
+    let target = path(device_name);
+    std::fs::create_dir_all(dir())?;
+    image.thumbnail(WIDTH, HEIGHT).save(&target)?;
+    Ok(())
+}
+
+// Takes a screenshot after the delay and keeps it as the device's thumbnail
+pub async fn capture(device_name: &str, delay: Duration) -> Result<()> {
+    tokio::time::sleep(delay).await;
+    let shot = std::env::temp_dir().join(format!("vpn-manager-thumbnail-{}.png", std::process::id()));
+    let result = screenshot::capture(&shot).await.and_then(|_| set_from_file(device_name, &shot));
+    let _ = std::fs::remove_file(&shot);
+    result
+}
+
+// Captures in the background, e.g. once a session the app just launched is on screen
+pub fn capture_later(device_name: &str, delay: Duration) {
+    let device_name = device_name.to_string();
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        match rt.block_on(capture(&device_name, delay)) {
+            Ok(()) => log::info!("Updated the thumbnail of {}", device_name),
+            Err(e) => log::warn!("Could not capture a thumbnail of {}: {}", device_name, e),
+        }
+    });
+}
+
+pub fn remove(device_name: &str) {
+    let _ = std::fs::remove_file(path(device_name));
+}
+
+// Follows a renamed device; false if it had none or the new name already has one
+pub fn rename(old: &str, new: &str) -> bool {
+    let (from, to) = (path(old), path(new));
+    from.exists() && !to.exists() && std::fs::rename(from, to).is_ok()
+}
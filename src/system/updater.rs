@@ -1,6 +1,20 @@
+use crate::config::{UpdateSource, UpdateSourceKind};
+use crate::errors::{self, ErrorCode};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// A successful check is reused for this long instead of hitting the network on every start
+pub const CHECK_INTERVAL_SECS: u64 = 24 * 3600;
+
+// Fail fast when offline instead of leaving the UI spinning
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+// For the connectivity probe before a check
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateInfo {
@@ -9,15 +23,42 @@ pub struct UpdateInfo {
     pub update_available: bool,
     pub download_url: String,
     pub release_notes: String,
+    // Every release after the installed one up to the latest, newest first
+    #[serde(default)]
+    pub changelog: Vec<ReleaseNotes>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub notes: String,
+}
+
+// Last successful check, kept on disk so the result survives restarts and offline periods
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedUpdateCheck {
+    pub checked_at: u64,
+    pub info: UpdateInfo,
+}
+
+impl CachedUpdateCheck {
+    pub fn is_fresh(&self) -> bool {
+        now_secs().saturating_sub(self.checked_at) < CHECK_INTERVAL_SECS
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct GitHubRelease {
     tag_name: String,
     name: String,
-    body: String,
+    // null for releases published without notes
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
     assets: Vec<GitHubAsset>,
     prerelease: bool,
+    #[serde(default)]
+    draft: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,29 +67,104 @@ struct GitHubAsset {
     browser_download_url: String,
 }
 
+// Self-hosted alternative to GitHub releases:
+// {"version": "0.2.0", "notes": "...", "downloads": {"linux": "https://...", "windows": "https://..."}}
+// A single "url" can be given instead of per-OS downloads
+#[derive(Debug, Deserialize)]
+struct UpdateManifest {
+    version: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    downloads: HashMap<String, String>,
+    // Optional notes for earlier releases, so skipped versions can be shown too
+    #[serde(default)]
+    changes: Vec<ReleaseNotes>,
+}
+
 #[derive(Clone)]
 pub struct AppUpdater {
-    repo_owner: String,
-    repo_name: String,
+    source: UpdateSource,
     current_version: String,
+    // Explicit proxy URL; None uses the system proxy settings
+    proxy: Option<String>,
 }
 
 impl AppUpdater {
-    pub fn new(repo_owner: &str, repo_name: &str, current_version: &str) -> Self {
+    pub fn new(source: UpdateSource, current_version: &str) -> Self {
         Self {
-            repo_owner: repo_owner.to_string(),
-            repo_name: repo_name.to_string(),
+            source,
             current_version: current_version.to_string(),
+            proxy: None,
+        }
+    }
+    
+    pub fn source(&self) -> &UpdateSource {
+        &self.source
+    }
+    
+    pub fn set_source(&mut self, source: UpdateSource) {
+        self.source = source;
+    }
+    
+    pub fn set_proxy(&mut self, proxy: &str) {
+        let proxy = proxy.trim();
+        self.proxy = (!proxy.is_empty()).then(|| proxy.to_string());
+    }
+    
+    pub fn proxy(&self) -> Option<&str> {
+        self.proxy.as_deref()
+    }
+    
+    // Whether the update server, or the proxy in front of it, takes a TCP connection. Offline
+    // machines fail the lookup right away, so a check can be skipped without waiting for
+    // the HTTP client's timeouts or logging a failure on every start.
+    pub async fn is_online(&self) -> bool {
+        let url = match (&self.proxy, &self.source.kind) {
+            (Some(proxy), _) => proxy.clone(),
+            (None, UpdateSourceKind::GitHub) => self.source.github_api_url.clone(),
+            (None, UpdateSourceKind::Manifest) => self.source.manifest_url.clone(),
+        };
+        let Ok(url) = reqwest::Url::parse(&url) else { return true };
+        let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else { return true };
+        let probe = tokio::net::TcpStream::connect((host, port));
+        matches!(tokio::time::timeout(PROBE_TIMEOUT, probe).await, Ok(Ok(_)))
+    }
+    
+    // reqwest picks up HTTP(S)_PROXY and the platform proxy settings unless one is configured here
+    fn http_client(&self, timeout: Option<Duration>) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy.as_str())
+                .map_err(|e| anyhow::anyhow!("Invalid proxy '{}': {}", proxy, e))?;
+            builder = builder.proxy(proxy);
         }
+        Ok(builder.build()?)
     }
     
     pub async fn check_for_updates(&self) -> Result<UpdateInfo> {
+        let info = match self.source.kind {
+            UpdateSourceKind::GitHub => self.check_github().await?,
+            UpdateSourceKind::Manifest => self.check_manifest().await?,
+        };
+        self.save_cached_check(&info);
+        
+        Ok(info)
+    }
+    
+    async fn check_github(&self) -> Result<UpdateInfo> {
         let url = format!(
-            "https://api.github.com/repos/{}/{}/releases/latest",
-            self.repo_owner, self.repo_name
+            "{}/repos/{}/releases/latest",
+            self.source.github_api_url.trim_end_matches('/'),
+            self.source.github_repo.trim_matches('/')
         );
         
-        let client = reqwest::Client::new();
+        let client = self.http_client(Some(CHECK_TIMEOUT))?;
         let response = client
             .get(&url)
             .header("User-Agent", "vpn-manager")
@@ -56,7 +172,7 @@ impl AppUpdater {
             .await?;
         
         if !response.status().is_success() {
-            return Err(anyhow::anyhow!("Failed to fetch release info: {}", response.status()));
+            return Err(errors::error(ErrorCode::UpdateCheckFailed, response.status()));
         }
         
         let release: GitHubRelease = response.json().await?;
@@ -68,16 +184,154 @@ impl AppUpdater {
         
         // Find the appropriate asset for the current platform
         let download_url = self.get_download_url(&release.assets)?;
+        let release_notes = release.body.clone().unwrap_or_default();
+        
+        let changelog = if update_available {
+            self.fetch_github_changelog(&client, current_version, latest_version).await
+                .unwrap_or_else(|e| {
+                    log::warn!("Failed to fetch intermediate releases, showing only the latest: {}", e);
+                    vec![ReleaseNotes { version: latest_version.to_string(), notes: release_notes.clone() }]
+                })
+        } else {
+            Vec::new()
+        };
         
         Ok(UpdateInfo {
             current_version: current_version.to_string(),
             latest_version: latest_version.to_string(),
             update_available,
             download_url,
-            release_notes: release.body,
+            release_notes,
+            changelog,
         })
     }
     
+    async fn fetch_github_changelog(&self, client: &reqwest::Client, current: &str, latest: &str) -> Result<Vec<ReleaseNotes>> {
+        // 100 releases is far more than anyone skips
+        let url = format!(
+            "{}/repos/{}/releases?per_page=100",
+            self.source.github_api_url.trim_end_matches('/'),
+            self.source.github_repo.trim_matches('/')
+        );
+        
+        let response = client
+            .get(&url)
+            .header("User-Agent", "vpn-manager")
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            return Err(errors::error(ErrorCode::UpdateCheckFailed, response.status()));
+        }
+        
+        let releases: Vec<GitHubRelease> = response.json().await?;
+        let notes = releases
+            .into_iter()
+            .filter(|release| !release.draft && !release.prerelease)
+            .map(|release| ReleaseNotes {
+                version: release.tag_name.strip_prefix('v').unwrap_or(&release.tag_name).to_string(),
+                notes: release.body.unwrap_or_default(),
+            })
+            .collect();
+        
+        Ok(releases_between(notes, current, latest))
+    }
+    
+    async fn check_manifest(&self) -> Result<UpdateInfo> {
+        let url = self.source.manifest_url.trim();
+        if url.is_empty() {
+            return Err(anyhow::anyhow!("No update manifest URL configured"));
+        }
+        
+        let client = self.http_client(Some(CHECK_TIMEOUT))?;
+        let response = client
+            .get(url)
+            .header("User-Agent", "vpn-manager")
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            return Err(errors::error(ErrorCode::UpdateCheckFailed, response.status()));
+        }
+        
+        let manifest: UpdateManifest = response.json().await
+            .map_err(|e| anyhow::anyhow!("Invalid update manifest: {}", e))?;
+        
+        let latest_version = manifest.version.strip_prefix('v').unwrap_or(&manifest.version);
+        let current_version = self.current_version.strip_prefix('v').unwrap_or(&self.current_version);
+        
+        let download_url = manifest.downloads
+            .get(std::env::consts::OS)
+            .cloned()
+            .or(manifest.url)
+            .ok_or_else(|| errors::error(ErrorCode::UpdateAssetMissing, std::env::consts::OS))?;
+        
+        let update_available = self.is_newer_version(latest_version, current_version)?;
+        let changelog = if !update_available {
+            Vec::new()
+        } else if manifest.changes.is_empty() {
+            vec![ReleaseNotes { version: latest_version.to_string(), notes: manifest.notes.clone() }]
+        } else {
+            releases_between(manifest.changes, current_version, latest_version)
+        };
+        
+        Ok(UpdateInfo {
+            current_version: current_version.to_string(),
+            latest_version: latest_version.to_string(),
+            update_available,
+            download_url,
+            release_notes: manifest.notes,
+            changelog,
+        })
+    }
+    
+    fn cache_path() -> PathBuf {
+        crate::system::paths::cache_dir().join("update-check.json")
+    }
+    
+    // Cached result of the last successful check, ignored once this build's version differs
+    pub fn load_cached_check(&self) -> Option<CachedUpdateCheck> {
+        let content = std::fs::read_to_string(Self::cache_path()).ok()?;
+        let cached: CachedUpdateCheck = serde_json::from_str(&content).ok()?;
+        let current_version = self.current_version.strip_prefix('v').unwrap_or(&self.current_version);
+        (cached.info.current_version == current_version).then_some(cached)
+    }
+    
+    fn save_cached_check(&self, info: &UpdateInfo) {
+        let cached = CachedUpdateCheck {
+            checked_at: now_secs(),
+            info: info.clone(),
+        };
+        let result = serde_json::to_string_pretty(&cached)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| {
+                if let Some(parent) = Self::cache_path().parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Ok(std::fs::write(Self::cache_path(), json)?)
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to cache update check: {}", e);
+        }
+    }
+    
+    // Turns a failed check into a message that says what went wrong with the network
+    pub fn describe_error(error: &anyhow::Error) -> String {
+        let Some(e) = error.downcast_ref::<reqwest::Error>() else {
+            return error.to_string();
+        };
+        
+        if e.is_timeout() {
+            "Timed out reaching the update server; check your connection or proxy".to_string()
+        } else if e.is_connect() {
+            format!("Could not connect to the update server (offline, or blocked by a proxy/firewall): {}", e)
+        } else if let Some(status) = e.status() {
+            format!("Update server returned {}", status)
+        } else {
+            format!("Network error while checking for updates: {}", e)
+        }
+    }
+    
     fn is_newer_version(&self, latest: &str, current: &str) -> Result<bool> {
         use semver::Version;
         
@@ -111,7 +365,7 @@ impl AppUpdater {
             return Ok(asset.browser_download_url.clone());
         }
         
-        Err(anyhow::anyhow!("No suitable download asset found"))
+        Err(errors::error(ErrorCode::UpdateAssetMissing, std::env::consts::OS))
     }
     
     pub async fn download_and_install_update(&self, update_info: &UpdateInfo) -> Result<()> {
@@ -126,8 +380,8 @@ impl AppUpdater {
         let filename = format!("vpn-manager-{}{}", update_info.latest_version, extension);
         let temp_file = temp_dir.join(&filename);
         
-        // Download the update
-        let client = reqwest::Client::new();
+        // Download the update; no overall timeout since the binary can be large
+        let client = self.http_client(None)?;
         let response = client.get(&update_info.download_url).send().await?;
         
         if !response.status().is_success() {
@@ -148,10 +402,13 @@ impl AppUpdater {
         
         // Get current executable path
         let current_exe = std::env::current_exe()?;
-        let backup_path = format!("{}.backup", current_exe.display());
+        let backup_dir = crate::system::paths::backups_dir();
+        let backup_path = backup_dir.join(format!("vpn-manager-{}{}.backup", update_info.current_version, extension));
         
         // Create backup of current executable
+        std::fs::create_dir_all(&backup_dir)?;
         std::fs::copy(&current_exe, &backup_path)?;
+        log::info!("Backed up the current version to {}", backup_path.display());
         
         // On Windows, we need to handle the file replacement differently
         #[cfg(windows)]
@@ -196,10 +453,33 @@ del "%~f0""#,
         std::process::exit(0);
     }
     
-    pub fn get_changelog_url(&self) -> String {
-        format!(
-            "https://github.com/{}/{}/releases",
-            self.repo_owner, self.repo_name
-        )
+    pub fn get_changelog_url(&self) -> Option<String> {
+        self.source.releases_page()
     }
-}
\ No newline at end of file
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Releases newer than `current` up to and including `latest`, newest first
+fn releases_between(releases: Vec<ReleaseNotes>, current: &str, latest: &str) -> Vec<ReleaseNotes> {
+    use semver::Version;
+    
+    let (Ok(current), Ok(latest)) = (Version::parse(current), Version::parse(latest)) else {
+        return Vec::new();
+    };
+    
+    let mut between: Vec<(Version, ReleaseNotes)> = releases
+        .into_iter()
+        .filter_map(|release| {
+            let version = Version::parse(release.version.strip_prefix('v').unwrap_or(&release.version)).ok()?;
+            (version > current && version <= latest).then_some((version, release))
+        })
+        .collect();
+    between.sort_by(|a, b| b.0.cmp(&a.0));
+    between.into_iter().map(|(_, release)| release).collect()
+}
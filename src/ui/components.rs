@@ -1,5 +1,7 @@
 use eframe::egui::{self, Color32, Rounding, Stroke, Vec2};
-use crate::ui::theme::{Theme, DeviceType, ActionType};
+use crate::config::EntryStyle;
+use crate::ui::route::{Navigation, Route};
+use crate::ui::theme::{Theme, DeviceType, ActionType, StatusState};
 
 pub struct GlassPanel;
 
@@ -59,11 +61,14 @@ impl StatusIndicator {
             let circle_size = 12.0;
             let (rect, _) = ui.allocate_exact_size(Vec2::splat(circle_size), egui::Sense::hover());
             
-            ui.painter().circle_filled(
-                rect.center(),
-                circle_size / 2.0,
-                color,
-            );
+            let state = if is_connecting {
+                StatusState::Connecting
+            } else if is_connected {
+                StatusState::Online
+            } else {
+                StatusState::Offline
+            };
+            StatusDot::paint(ui.painter(), theme, rect.center(), circle_size / 2.0, state, color);
             
             // Add outer ring for connecting animation
             if is_connecting {
@@ -87,6 +92,377 @@ impl StatusIndicator {
     }
 }
 
+// Status marker that, with shapes enabled, differs by form as well as color:
+// filled circle online, hollow ring offline, diamond connecting, cross on error
+pub struct StatusDot;
+
+impl StatusDot {
+    pub fn show(ui: &mut egui::Ui, theme: &Theme, state: StatusState, size: f32) {
+        let (rect, _) = ui.allocate_exact_size(Vec2::splat(size), egui::Sense::hover());
+        if ui.is_rect_visible(rect) {
+            StatusDot::paint(ui.painter(), theme, rect.center(), size / 2.0, state, theme.get_state_color(state));
+        }
+    }
+    
+    pub fn paint(painter: &egui::Painter, theme: &Theme, center: egui::Pos2, radius: f32, state: StatusState, color: Color32) {
+        if !theme.status_shapes {
+            painter.circle_filled(center, radius, color);
+            return;
+        }
+        
+        match state {
+            StatusState::Online => {
+                painter.circle_filled(center, radius, color);
+            }
+            StatusState::Offline => {
+                painter.circle_stroke(center, radius - 1.0, Stroke::new(2.0, color));
+            }
+            StatusState::Connecting => {
+                let points = vec![
+                    center + Vec2::new(0.0, -radius),
+                    center + Vec2::new(radius, 0.0),
+                    center + Vec2::new(0.0, radius),
+                    center + Vec2::new(-radius, 0.0),
+                ];
+                painter.add(egui::Shape::convex_polygon(points, color, Stroke::NONE));
+            }
+            StatusState::Error => {
+                let r = radius * 0.8;
+                let stroke = Stroke::new(2.5, color);
+                painter.line_segment([center + Vec2::new(-r, -r), center + Vec2::new(r, r)], stroke);
+                painter.line_segment([center + Vec2::new(-r, r), center + Vec2::new(r, -r)], stroke);
+            }
+        }
+    }
+}
+
+// "2h 05m" style countdown for badges and the schedule editor
+pub fn format_countdown(secs: i64) -> String {
+    let secs = secs.max(0);
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+// Small countdown to the next scheduled action on a card; returns true when clicked
+pub struct ScheduleBadge;
+
+impl ScheduleBadge {
+    pub fn show(ui: &mut egui::Ui, theme: &Theme, run: chrono::DateTime<chrono::Local>) -> bool {
+        let remaining = (run - chrono::Local::now()).num_seconds();
+        ui.add(
+            egui::Button::new(
+                egui::RichText::new(format!("⏰ {}", format_countdown(remaining)))
+                    .size(9.0)
+                    .color(theme.primary)
+            )
+            .fill(theme.primary.gamma_multiply(0.15))
+            .stroke(Stroke::NONE)
+            .rounding(Rounding::same(4.0))
+            .small()
+        )
+        .on_hover_text(format!("Scheduled for {}, click to edit", run.format("%a %H:%M")))
+        .clicked()
+    }
+}
+
+// Marks a device in maintenance, with the time left in the window
+pub struct MaintenanceBadge;
+
+impl MaintenanceBadge {
+    pub fn show(ui: &mut egui::Ui, theme: &Theme, until: chrono::DateTime<chrono::Local>) {
+        let remaining = (until - chrono::Local::now()).num_seconds();
+        egui::Frame::none()
+            .fill(theme.warning.gamma_multiply(0.2))
+            .rounding(Rounding::same(4.0))
+            .inner_margin(egui::Margin::symmetric(6.0, 2.0))
+            .show(ui, |ui| {
+                ui.label(
+                    egui::RichText::new(format!("🔧 Maintenance {}", format_countdown(remaining)))
+                        .size(9.0)
+                        .color(theme.warning)
+                );
+            })
+            .response
+            .on_hover_text(format!("Alerts and availability tracking paused until {}", until.format("%a %H:%M")));
+    }
+}
+
+// Throughput of the connected VPN as two lines, received in the primary color and sent in the
+// accent color, on one scale topped by the peak rate shown in the corner
+pub struct TrafficGraph;
+
+impl TrafficGraph {
+    pub fn show(ui: &mut egui::Ui, theme: &Theme, rates: &[(f64, f64)], height: f32) {
+        let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), height), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, Rounding::same(4.0), theme.surface_variant);
+        if rates.len() < 2 {
+            return;
+        }
+        let peak = rates.iter().map(|(down, up)| down.max(*up)).fold(1.0, f64::max);
+        let step = rect.width() / (rates.len() - 1) as f32;
+        let line = |value: fn(&(f64, f64)) -> f64| -> Vec<egui::Pos2> {
+            rates.iter().enumerate().map(|(i, rate)| {
+                egui::pos2(rect.left() + i as f32 * step, rect.bottom() - 2.0 - (value(rate) / peak) as f32 * (rect.height() - 4.0))
+            }).collect()
+        };
+        painter.add(egui::Shape::line(line(|rate| rate.0), Stroke::new(1.5, theme.primary)));
+        painter.add(egui::Shape::line(line(|rate| rate.1), Stroke::new(1.5, theme.accent)));
+        painter.text(rect.right_top() + egui::vec2(-4.0, 2.0), egui::Align2::RIGHT_TOP,
+                     format!("{}/s", crate::network::stats::format_bytes(peak)), egui::FontId::proportional(10.0), theme.text_secondary);
+    }
+}
+
+// Outlines the entry a deep link pointed at, scrolling it into view the first time it's drawn
+pub struct FocusFrame;
+
+impl FocusFrame {
+    pub fn show<R>(
+        ui: &mut egui::Ui,
+        theme: &Theme,
+        navigation: &Navigation,
+        entry_name: &str,
+        add_contents: impl FnOnce(&mut egui::Ui) -> R,
+    ) -> R {
+        let stroke = if navigation.is_focused(entry_name) {
+            Stroke::new(2.0, theme.primary)
+        } else {
+            Stroke::NONE
+        };
+        
+        let inner = egui::Frame::none()
+            .stroke(stroke)
+            .rounding(Rounding::same(Theme::CARD_RADIUS))
+            .inner_margin(egui::Margin::same(Theme::SPACING_SM))
+            .show(ui, add_contents);
+        
+        if navigation.should_scroll(entry_name) {
+            inner.response.scroll_to_me(Some(egui::Align::Center));
+        }
+        inner.inner
+    }
+}
+
+// Scrolling list that only lays out the rows in view, so hundreds of entries cost no more per
+// frame than a screenful. Rows may differ in height: each is measured when drawn and the rest
+// are assumed to match the estimate until they scroll into view.
+pub struct VirtualList;
+
+impl VirtualList {
+    pub fn show(
+        ui: &mut egui::Ui,
+        id_salt: &str,
+        row_count: usize,
+        estimated_height: f32,
+        // Row to bring into view this frame, e.g. a deep link's entry
+        scroll_to: Option<usize>,
+        mut add_row: impl FnMut(&mut egui::Ui, usize),
+    ) {
+        let id = ui.make_persistent_id(id_salt);
+        let mut heights: Vec<f32> = ui.data(|d| d.get_temp(id)).unwrap_or_default();
+        heights.resize(row_count, estimated_height);
+        let row_spacing = ui.spacing().item_spacing.y;
+        
+        let mut area = egui::ScrollArea::vertical().id_salt(id).auto_shrink([false, true]);
+        if let Some(index) = scroll_to.filter(|&index| index < row_count) {
+            area = area.vertical_scroll_offset(heights[..index].iter().sum());
+        }
+        
+        area.show_viewport(ui, |ui, viewport| {
+            ui.set_width(ui.available_width());
+            // Rows carry their own spacing so offsets are plain sums of heights
+            ui.spacing_mut().item_spacing.y = 0.0;
+            
+            let mut first = 0;
+            let mut top = 0.0;
+            while first < row_count && top + heights[first] < viewport.min.y {
+                top += heights[first];
+                first += 1;
+            }
+            ui.add_space(top);
+            
+            let mut index = first;
+            let mut bottom = top;
+            while index < row_count && bottom < viewport.max.y {
+                let response = ui.scope(|ui| {
+                    ui.spacing_mut().item_spacing.y = row_spacing;
+                    add_row(ui, index);
+                    ui.add_space(row_spacing);
+                }).response;
+                heights[index] = response.rect.height();
+                bottom += heights[index];
+                index += 1;
+            }
+            ui.add_space(heights[index..].iter().sum());
+        });
+        
+        ui.data_mut(|d| d.insert_temp(id, heights));
+    }
+}
+
+// A tool such as a log, shown in a window inside the app or popped out into a native window of
+// its own that can go to another monitor while the dashboard stays usable. Which of the two is
+// remembered for the session. Where the platform can't open more windows it stays inside.
+pub struct ToolWindow;
+
+impl ToolWindow {
+    // Returns false once the user closed it
+    pub fn show(
+        ctx: &egui::Context,
+        id_salt: &str,
+        title: &str,
+        default_size: Vec2,
+        mut add_contents: impl FnMut(&mut egui::Ui),
+    ) -> bool {
+        let popped_id = egui::Id::new(("tool_window_popped", id_salt));
+        let popped = ctx.data(|d| d.get_temp::<bool>(popped_id)).unwrap_or(false) && !ctx.embed_viewports();
+        let mut open = true;
+        let mut toggle = false;
+        
+        if popped {
+            let builder = egui::ViewportBuilder::default()
+                .with_title(title)
+                .with_inner_size(default_size);
+            ctx.show_viewport_immediate(egui::ViewportId::from_hash_of(popped_id), builder, |ctx, _| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            toggle = ui.small_button("⮌").on_hover_text("Back into the main window").clicked();
+                        });
+                    });
+                    add_contents(ui);
+                });
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    open = false;
+                }
+            });
+        } else {
+            egui::Window::new(title)
+                .id(egui::Id::new(("tool_window", id_salt)))
+                .collapsible(false)
+                .resizable(true)
+                .default_size(default_size)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    if !ctx.embed_viewports() {
+                        ui.horizontal(|ui| {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                toggle = ui.small_button("⧉").on_hover_text("Open in a window of its own").clicked();
+                            });
+                        });
+                    }
+                    add_contents(ui);
+                });
+        }
+        
+        if toggle {
+            ctx.data_mut(|d| d.insert_temp(popped_id, !popped));
+        }
+        open
+    }
+}
+
+// Entry name that offers a deep link to itself on right-click
+pub fn entry_label(ui: &mut egui::Ui, text: impl Into<egui::WidgetText>, route: Route) -> egui::Response {
+    let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+    response.context_menu(|ui| {
+        if ui.button("🔗 Copy link").clicked() {
+            ui.output_mut(|o| o.copied_text = route.to_link());
+            ui.close_menu();
+        }
+    });
+    response
+}
+
+// Link to the docs for an error's code, if it has one
+pub fn error_help(ui: &mut egui::Ui, code: Option<crate::errors::ErrorCode>) {
+    if let Some(code) = code {
+        ui.hyperlink_to(format!("❓ Help on {}", code.id()), code.docs_url());
+    }
+}
+
+// Icons an entry can pick from, limited to glyphs the bundled emoji font has
+pub const ENTRY_ICONS: &[&str] = &[
+    "🖥", "💻", "🗄", "🖧", "📡", "🖨", "📱", "🎮", "📷", "🔬",
+    "🏠", "🏢", "🏭", "🌐", "☁", "🔒", "🛡", "⚙", "⭐", "🔥",
+];
+
+// Accent colors offered next to the icons; readable on both the dark and the light theme
+pub const ENTRY_COLORS: &[[u8; 3]] = &[
+    [239, 83, 80], [255, 152, 0], [253, 216, 53], [102, 187, 106], [38, 166, 154],
+    [41, 182, 246], [92, 107, 192], [171, 71, 188], [236, 64, 122], [141, 110, 99],
+];
+
+// Accent color an entry picked, if any
+pub fn entry_accent(style: &EntryStyle) -> Option<Color32> {
+    style.color.map(|[r, g, b]| Color32::from_rgb(r, g, b))
+}
+
+// Entry's icon in its accent color, or the given default in the given color
+pub fn entry_icon(style: &EntryStyle, default_icon: &str, default_color: Color32, size: f32) -> egui::RichText {
+    let icon = if style.icon.is_empty() { default_icon } else { style.icon.as_str() };
+    egui::RichText::new(icon).size(size).color(entry_accent(style).unwrap_or(default_color))
+}
+
+// The entry's icon as a menu button for choosing the icon and accent color; returns true when
+// either changed
+pub struct StylePicker;
+
+impl StylePicker {
+    pub fn show(ui: &mut egui::Ui, theme: &Theme, style: &mut EntryStyle, default_icon: &str) -> bool {
+        let mut changed = false;
+        let button = entry_icon(style, default_icon, theme.text_secondary, 16.0);
+        
+        ui.menu_button(button, |ui| {
+            ui.label(egui::RichText::new("Icon").color(theme.text_secondary));
+            egui::Grid::new("entry_icons").spacing(Vec2::splat(2.0)).show(ui, |ui| {
+                for (i, icon) in ENTRY_ICONS.iter().enumerate() {
+                    if ui.selectable_label(style.icon == *icon, egui::RichText::new(*icon).size(16.0)).clicked() {
+                        style.icon = icon.to_string();
+                        changed = true;
+                    }
+                    if i % 10 == 9 {
+                        ui.end_row();
+                    }
+                }
+            });
+            if !style.icon.is_empty() && ui.small_button("Default icon").clicked() {
+                style.icon.clear();
+                changed = true;
+            }
+            
+            ui.separator();
+            ui.label(egui::RichText::new("Accent").color(theme.text_secondary));
+            ui.horizontal(|ui| {
+                ui.spacing_mut().item_spacing.x = 4.0;
+                for &[r, g, b] in ENTRY_COLORS {
+                    let selected = style.color == Some([r, g, b]);
+                    let swatch = egui::Button::new("")
+                        .fill(Color32::from_rgb(r, g, b))
+                        .stroke(if selected { Stroke::new(2.0, theme.text_primary) } else { Stroke::NONE })
+                        .rounding(Rounding::same(4.0))
+                        .min_size(Vec2::splat(18.0));
+                    if ui.add(swatch).clicked() {
+                        style.color = Some([r, g, b]);
+                        changed = true;
+                    }
+                }
+            });
+            if style.color.is_some() && ui.small_button("Theme color").clicked() {
+                style.color = None;
+                changed = true;
+            }
+        }).response.on_hover_text("Icon and color");
+        
+        changed
+    }
+}
+
 pub struct GlassButton;
 
 impl GlassButton {
@@ -629,11 +1005,8 @@ impl DeviceCard {
                             .inner_margin(egui::Margin::symmetric(6.0, 2.0))
                             .show(ui, |ui| {
                                 ui.horizontal(|ui| {
-                                    ui.label(
-                                        egui::RichText::new("●")
-                                            .size(8.0)
-                                            .color(status_color)
-                                    );
+                                    let state = if is_online { StatusState::Online } else { StatusState::Offline };
+                                    StatusDot::show(ui, theme, state, 8.0);
                                     ui.label(
                                         egui::RichText::new(status_text)
                                             .size(9.0)
@@ -0,0 +1,220 @@
+use crate::config::DeveloperSettings;
+use crate::ui::theme::Theme;
+use crate::ui::Panel;
+use eframe::egui;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// Diagnostics for reports of a laggy UI, behind the hidden developer settings. Frame times are
+// kept for the last few seconds of frames: the app's whole update (which includes the blocking
+// status checks), the open panel's share of it, and the CPU time eframe reports for the frame
+// including tessellation. egui's own debug overlays cover layout problems.
+
+// Frames kept, about five seconds at 60 Hz
+const WINDOW: usize = 300;
+// Frames slower than this miss a 60 Hz refresh
+const SLOW_FRAME: Duration = Duration::from_micros(16_667);
+const GRAPH_SIZE: egui::Vec2 = egui::vec2(260.0, 48.0);
+
+#[derive(Debug, Clone)]
+struct Sample {
+    panel: Panel,
+    update: Duration,
+    panel_time: Duration,
+    // eframe's figure for the frame before, None on platforms that don't report it
+    frame: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    pub count: usize,
+    pub average: Duration,
+    pub p95: Duration,
+    pub max: Duration,
+    pub slow: usize,
+}
+
+impl Summary {
+    fn of(mut times: Vec<Duration>) -> Option<Self> {
+        if times.is_empty() {
+            return None;
+        }
+        times.sort();
+        let count = times.len();
+        Some(Self {
+            count,
+            average: times.iter().sum::<Duration>() / count as u32,
+            p95: times[(count * 95 / 100).min(count - 1)],
+            max: times[count - 1],
+            slow: times.iter().filter(|time| **time > SLOW_FRAME).count(),
+        })
+    }
+
+    fn describe(&self) -> String {
+        format!("avg {} · p95 {} · max {} · {} slow of {}", ms(self.average), ms(self.p95), ms(self.max), self.slow, self.count)
+    }
+}
+
+fn ms(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[derive(Default)]
+pub struct FrameStats {
+    samples: VecDeque<Sample>,
+}
+
+impl FrameStats {
+    pub fn record(&mut self, panel: &Panel, update: Duration, panel_time: Duration, frame: Option<f32>) {
+        self.samples.push_back(Sample {
+            panel: panel.clone(),
+            update,
+            panel_time,
+            frame: frame.map(Duration::from_secs_f32),
+        });
+        while self.samples.len() > WINDOW {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn update_summary(&self) -> Option<Summary> {
+        Summary::of(self.samples.iter().map(|sample| sample.update).collect())
+    }
+
+    pub fn frame_summary(&self) -> Option<Summary> {
+        Summary::of(self.samples.iter().filter_map(|sample| sample.frame).collect())
+    }
+
+    // The panel's own drawing, over the frames it was open
+    pub fn panel_summary(&self, panel: &Panel) -> Option<Summary> {
+        Summary::of(self.samples.iter().filter(|sample| sample.panel == *panel).map(|sample| sample.panel_time).collect())
+    }
+
+    // Plain text for a bug report
+    pub fn report(&self) -> String {
+        let mut lines = vec![format!("VPN Manager v{} on {}", env!("CARGO_PKG_VERSION"), std::env::consts::OS)];
+        if let Some(summary) = self.update_summary() {
+            lines.push(format!("App update: {}", summary.describe()));
+        }
+        if let Some(summary) = self.frame_summary() {
+            lines.push(format!("Frame CPU: {}", summary.describe()));
+        }
+        let mut panels: Vec<Panel> = Vec::new();
+        for sample in &self.samples {
+            if !panels.contains(&sample.panel) {
+                panels.push(sample.panel.clone());
+            }
+        }
+        for panel in panels {
+            if let Some(summary) = self.panel_summary(&panel) {
+                lines.push(format!("{:?} panel: {}", panel, summary.describe()));
+            }
+        }
+        lines.join("\n")
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+// egui's debug options follow the settings; checked every frame since applying the theme
+// replaces the style
+pub fn apply(ctx: &egui::Context, settings: &DeveloperSettings) {
+    let debug_on_hover = settings.unlocked && settings.debug_on_hover;
+    let widget_hits = settings.unlocked && settings.widget_hits;
+    let debug = ctx.style().debug;
+    if debug.debug_on_hover != debug_on_hover || debug.show_widget_hits != widget_hits {
+        ctx.style_mut(|style| {
+            style.debug.debug_on_hover = debug_on_hover;
+            style.debug.show_widget_hits = widget_hits;
+        });
+    }
+    if settings.unlocked && settings.continuous_repaint {
+        ctx.request_repaint();
+    }
+}
+
+pub fn draw(ctx: &egui::Context, theme: &Theme, settings: &mut DeveloperSettings, stats: &mut FrameStats, panel: &Panel) {
+    if !settings.unlocked {
+        return;
+    }
+
+    if settings.frame_stats {
+        let mut open = true;
+        egui::Window::new("Frame Stats")
+            .collapsible(true)
+            .resizable(false)
+            .open(&mut open)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .show(ctx, |ui| {
+                let rows = [
+                    ("App update", stats.update_summary()),
+                    ("Frame CPU", stats.frame_summary()),
+                    ("This panel", stats.panel_summary(panel)),
+                ];
+                for (label, summary) in rows {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(label).strong());
+                        match summary {
+                            Some(summary) => {
+                                let color = if summary.p95 > SLOW_FRAME { theme.warning } else { theme.text_secondary };
+                                ui.label(egui::RichText::new(summary.describe()).monospace().size(11.0).color(color));
+                            }
+                            None => {
+                                ui.label(egui::RichText::new("no frames yet").color(theme.text_secondary));
+                            }
+                        }
+                    });
+                }
+                draw_graph(ui, theme, stats);
+                ui.horizontal(|ui| {
+                    if ui.button("📋 Copy Report").clicked() {
+                        ui.output_mut(|o| o.copied_text = stats.report());
+                    }
+                    if ui.button("Reset").clicked() {
+                        stats.clear();
+                    }
+                    ui.checkbox(&mut settings.continuous_repaint, "Repaint continuously")
+                        .on_hover_text("Frames are normally only drawn on input and timers; this draws them back to back");
+                });
+            });
+        settings.frame_stats = open;
+    }
+
+    if settings.inspection {
+        let mut open = true;
+        egui::Window::new("egui Inspection")
+            .default_size(egui::vec2(420.0, 480.0))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ctx.inspection_ui(ui);
+                    ui.collapsing("Memory", |ui| ctx.memory_ui(ui));
+                    ui.collapsing("Textures", |ui| ctx.texture_ui(ui));
+                });
+            });
+        settings.inspection = open;
+    }
+}
+
+// Update time of the recent frames as bars, the 60 Hz budget as a line
+fn draw_graph(ui: &mut egui::Ui, theme: &Theme, stats: &FrameStats) {
+    let (rect, _) = ui.allocate_exact_size(GRAPH_SIZE, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, theme.surface);
+
+    let scale = SLOW_FRAME.as_secs_f32() * 2.0;
+    let width = rect.width() / WINDOW as f32;
+    for (index, sample) in stats.samples.iter().enumerate() {
+        let share = (sample.update.as_secs_f32() / scale).min(1.0);
+        let x = rect.left() + index as f32 * width;
+        let color = if sample.update > SLOW_FRAME { theme.warning } else { theme.primary };
+        painter.line_segment(
+            [egui::pos2(x, rect.bottom()), egui::pos2(x, rect.bottom() - share * rect.height())],
+            egui::Stroke::new(width.max(1.0), color),
+        );
+    }
+    let budget = rect.bottom() - rect.height() / 2.0;
+    painter.line_segment([egui::pos2(rect.left(), budget), egui::pos2(rect.right(), budget)], egui::Stroke::new(1.0, theme.text_secondary));
+}
@@ -17,21 +17,51 @@ pub struct DeviceOperationResult {
     pub operation: String,
     pub success: bool,
     pub message: String,
+    // Of a failure, for the help link next to its message
+    pub code: Option<crate::errors::ErrorCode>,
+    pub attempt: u32,
+    // Set when the failure looks transient and the operation may be retried
+    pub retry_with: Option<DeviceOperationType>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingRetry {
+    pub operation: DeviceOperationType,
+    pub attempt: u32,
+    pub due: std::time::Instant,
 }
 
 #[derive(Debug, Clone)]
 pub enum DeviceOperationType {
     Wake(crate::config::WolDevice),
-    Ping(crate::config::WolDevice),
+    Ping { name: String, address: String },
     RdpConnect(crate::config::RdpConfig),
+    SshConnect(crate::config::SshConfig),
+    // Wakes the device and waits for its SSH port first, see NetworkManager::wake_then_ssh
+    WakeThenSsh(crate::config::WolDevice, crate::config::SshConfig),
+    VncConnect(crate::config::VncConfig),
+}
+
+// What a walk along the failover order was started for, to word its outcome
+#[derive(Debug, Clone)]
+pub enum FailoverPurpose {
+    Scheduled(String),
+    AlwaysOn(String),
+    FailOver { dropped: String },
+    Code(String),
+    Notification(String),
+    Restore(String),
 }
 
 pub mod theme;
 pub mod components;
+pub mod devtools;
 pub mod panels;
+pub mod route;
 
 use theme::Theme;
-use panels::{HomePanel, VpnPanel, RemotePanel, SettingsPanel};
+use route::Route;
+use panels::{HomePanel, VpnPanel, RemotePanel, SettingsPanel, ProblemsPanel, SchedulePanel};
 use components::{ModernButton, Spacing, Typography};
 
 pub struct App {
@@ -40,7 +70,8 @@ pub struct App {
     theme: Theme,
     current_panel: Panel,
     show_settings: bool,
-    error_message: Option<String>,
+    // With the code of the error, if it has one
+    error_message: Option<(String, Option<crate::errors::ErrorCode>)>,
     system_info: SystemInfo,
     package_installer: PackageInstaller,
     app_updater: AppUpdater,
@@ -52,19 +83,29 @@ pub struct App {
     new_vpn_username: String,
     new_vpn_password: String,
     new_vpn_type: VpnType,
-    new_rdp_name: String,
-    new_rdp_host: String,
-    new_rdp_port: String,
-    new_rdp_username: String,
-    new_rdp_password: String,
-    new_rdp_domain: String,
-    new_wol_name: String,
-    new_wol_mac: String,
-    new_wol_ip: String,
-    new_wol_port: String,
+    new_vpn_env: String,
+    new_vpn_server: String,
+    new_vpn_group: String,
+    new_vpn_proxy: crate::config::VpnProxy,
+    // Commands of the Custom entry being added
+    new_vpn_custom: crate::config::CustomCommands,
+    new_vpn_prompt_otp: bool,
+    // Id of the VPN the form above is editing; None adds a new one
+    editing_vpn: Option<String>,
+    // Key pair generated for the WireGuard entry being added
+    new_vpn_keys: Option<Result<crate::network::wireguard::KeyPair, String>>,
+    new_vpn_draft: panels::vpn::WireGuardDraft,
+    // Problems found in the config file picked in the add form
+    new_vpn_check: Option<panels::vpn::ConfigCheck>,
+    new_vpn_bundle: Option<Result<String, String>>,
+    new_device: panels::remote::DeviceForm,
     // Feedback states
     is_connecting: bool,
     connection_feedback: Option<String>,
+    // Offered as a button next to the feedback it was given with, e.g. reconnecting a dropped VPN
+    feedback_action: Option<(String, crate::system::notify::NotificationAction)>,
+    // The code of the error a feedback message reports, for a link to its help
+    feedback_code: Option<(String, crate::errors::ErrorCode)>,
     loading_actions: std::collections::HashSet<String>,
     animation_time: f32,
     checking_updates: bool,
@@ -72,23 +113,127 @@ pub struct App {
     update_progress: String,
     update_notification: Option<String>,
     last_update_check: std::time::Instant,
-    update_check_receiver: Option<std::sync::mpsc::Receiver<Result<crate::system::updater::UpdateInfo, String>>>,
+    // None when the check was skipped for being offline
+    update_check_receiver: Option<std::sync::mpsc::Receiver<Option<Result<crate::system::updater::UpdateInfo, String>>>>,
     update_check_timeout: std::time::Instant,
-    // Device operation feedback
-    device_operations: std::collections::HashMap<String, DeviceOperationState>,
+    // Last time a check found no network, to try again once it is back
+    update_offline_at: Option<std::time::Instant>,
+    // Why the last check failed, shown in Settings next to the cached result
+    update_error: Option<String>,
+    update_checked_at: Option<u64>,
+    update_check_requested: bool,
+    // Package manager query for newer dependency versions, run once when Settings is first opened
+    checking_dependency_upgrades: bool,
+    dependency_upgrades_checked: bool,
+    dependency_upgrade_requested: bool,
+    dependency_upgrade_receiver: Option<std::sync::mpsc::Receiver<Result<std::collections::BTreeMap<String, String>, String>>>,
+    // Device operation feedback and when it was set, keyed "<device>_<operation>"
+    device_operations: std::collections::HashMap<String, (DeviceOperationState, std::time::Instant)>,
     device_feedback_receiver: Option<std::sync::mpsc::Receiver<DeviceOperationResult>>,
+    device_feedback_sender: std::sync::mpsc::Sender<DeviceOperationResult>,
+    // Failed operations waiting for an automatic retry, keyed like device_operations
+    pending_retries: std::collections::HashMap<String, PendingRetry>,
+    // Captive portal login page, if one blocked auto-connect
+    captive_portal_url: Option<String>,
+    // Shown when references to renamed entries couldn't all be carried over, see config::migrate
+    migration_report: Option<crate::config::migrate::MigrationReport>,
+    // Entry waiting for its one-time code before connecting, see network::otp
+    otp_prompt: Option<panels::vpn::OtpPrompt>,
+    // Config lint results, refreshed periodically so fixed files disappear from the list
+    problems: Vec<crate::config::lint::Problem>,
+    last_lint: std::time::Instant,
+    // Command an action would run, shown instead of running it
+    command_preview: Option<crate::network::preview::CommandPreview>,
+    // Entry whose client log is open, as (kind, name)
+    client_log_view: Option<(String, String)>,
+    // Scheduled actions fire for runs between the previous check and now
+    last_schedule_check: chrono::DateTime<chrono::Local>,
+    // Deep link routing and the entry it focused
+    navigation: route::Navigation,
+    new_schedule_kind: crate::config::schedule::ScheduledActionKind,
+    new_schedule_target: String,
+    new_schedule_time: String,
+    // Local web dashboard, restarted whenever its settings change
+    dashboard: Option<crate::network::dashboard::DashboardServer>,
+    dashboard_started_with: Option<crate::config::WebDashboard>,
+    dashboard_error: Option<String>,
+    // Pairing link and its QR code while one is on screen
+    dashboard_pairing: Option<(String, TextureHandle)>,
+    dashboard_pair_requested: bool,
+    provider_browser: panels::settings::ProviderBrowser,
+    // Settings → Config Encryption, and the unlock prompt while config.locked
+    encryption_form: panels::settings::EncryptionForm,
+    status_notifier: crate::system::notify::StatusNotifier,
+    bulk_edit: panels::remote::BulkEdit,
+    notes_window: panels::remote::NotesWindow,
+    frame_stats: devtools::FrameStats,
+    thumbnails: panels::remote::Thumbnails,
+    duplicates_open: bool,
+    route_editor: panels::vpn::RouteEditor,
+    leak_tests: panels::vpn::LeakTests,
+    service_statuses: panels::vpn::ServiceStatuses,
+    // Running while the status is Reconnecting
+    reconnector: Option<crate::network::reconnect::Reconnector>,
+    // Degraded tunnel that was re-handshaken automatically, and when
+    stale_recovery: Option<(String, std::time::Instant)>,
+    // Re-handshake or restart of a degraded tunnel under way, by hand or automatically
+    recovery: Option<crate::network::reconnect::Recovery>,
+    // Recent drops per profile, for passing over the ones that keep dropping
+    vpn_drops: crate::network::failover::DropTracker,
+    // When the always-on profile is tried again after the reconnect loop gave up
+    always_on_retry: Option<std::time::Instant>,
+    // Sampling traffic of the connected VPN
+    traffic: Option<crate::network::stats::TrafficSampler>,
+    // Public IP on the Home tab, looked up again when the connected VPN changes
+    public_ip: crate::network::publicip::PublicIpWatch,
+    route_table: crate::network::route_table::RouteTableWatch,
+    // Periodic status checks, run from whichever frame comes after they're due
+    last_vpn_refresh: std::time::Instant,
+    last_quick_device_check: std::time::Instant,
+    last_full_device_check: std::time::Instant,
+    // Started with SAFE_MODE_ARG: nothing connects, polls or checks for updates on its own
+    safe_mode: bool,
+    // Status check and auto-connect after the window opened, see network::startup
+    startup: Option<crate::network::startup::StartupTask>,
+    // Connecting along the failover order, see network::failover
+    failover: Option<(crate::network::failover::FailoverTask, FailoverPurpose)>,
+    // Left behind by a run that didn't exit cleanly, offered until restored or dismissed
+    previous_session: Option<(crate::system::recovery::SessionState, Option<chrono::DateTime<chrono::Local>>)>,
+    // Last written to disk, to write only on changes
+    session_saved: Option<crate::system::recovery::SessionState>,
 }
 
+// Starts the window without auto-connect, status and device checks, scheduled actions or update
+// checks, for getting at the settings when something they start hangs the app
+pub const SAFE_MODE_ARG: &str = "--safe-mode";
+
+// How often the periodic checks run
+const VPN_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+const QUICK_DEVICE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+const FULL_DEVICE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+// Between connectivity probes while an update check waits for the network
+const OFFLINE_UPDATE_RETRY: std::time::Duration = std::time::Duration::from_secs(60);
+// From re-handshaking a degraded tunnel to reconnecting it, a few status refreshes
+const STALE_RECOVERY_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+// Frame rate while something is animating or a background result is awaited
+const ACTIVE_REPAINT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+const FEEDBACK_SECS: f32 = 3.0;
+// How long a device button shows the result of its last operation before going back to Idle
+const SUCCESS_STATUS_TTL: std::time::Duration = std::time::Duration::from_secs(10);
+const ERROR_STATUS_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Panel {
     Home,
     Vpn,
     Remote,
     Settings,
+    Problems,
+    Schedule,
 }
 
 impl App {
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Result<Self, String> {
+    pub fn new(cc: &eframe::CreationContext<'_>, route: Option<Route>, safe_mode: bool) -> Result<Self, String> {
         use log::{info, warn};
         
         info!("Detecting system information...");
@@ -103,27 +248,50 @@ impl App {
         
         info!("Detected system: {}", system_info.distribution);
         let package_installer = PackageInstaller::new(&system_info);
-        let app_updater = AppUpdater::new("EmmanouelKontos", "vpn-aio-rust", env!("CARGO_PKG_VERSION"));
         
         info!("Loading configuration...");
-        let config = Config::load().unwrap_or_else(|e| {
+        let mut config = Config::load().unwrap_or_else(|e| {
             warn!("Failed to load config: {}, using default", e);
             Config::default()
         });
         
+        // References to renamed entries follow them; the ids and names are saved right away
+        let saved_names = config.entry_names.clone();
+        let migration = crate::config::migrate::on_load(&mut config);
+        for line in &migration.moved {
+            info!("{}", line);
+        }
+        for line in &migration.unmapped {
+            warn!("{}", line);
+        }
+        if config.entry_names != saved_names {
+            if let Err(e) = config.save() {
+                warn!("Failed to save config after migrating: {}", e);
+            }
+        }
+        let mut app_updater = AppUpdater::new(config.update_source.clone(), env!("CARGO_PKG_VERSION"));
+        app_updater.set_proxy(&config.update_proxy);
+        
         info!("Initializing network manager...");
         let mut network_manager = NetworkManager::new();
         
-        // Initialize VPN status and WoL devices based on current system state
-        let runtime = tokio::runtime::Runtime::new().unwrap();
-        let _ = runtime.block_on(async {
-            network_manager.initialize(&config.vpn_configs, &config.wol_devices).await
-        });
+        // Monitored devices right away; which VPN is up is found after the window opens
+        network_manager.sync_devices(&config.devices);
+        if safe_mode {
+            warn!("Safe mode: skipping status checks, auto-connect and update checks");
+        }
+        
+        let (device_feedback_sender, device_feedback_receiver) = std::sync::mpsc::channel();
+        Theme::set_dark(config.effective_dark_mode());
+        Theme::set_status_palette(config.status_palette, config.status_shapes);
+        crate::network::wg_userspace::set_backend(config.wireguard_backend);
+        crate::network::nmcli::set_enabled(config.use_network_manager);
+        crate::errors::set_language(config.language);
         
         let mut app = Self {
             config,
             network_manager,
-            theme: Theme::new(),
+            theme: Theme::current(),
             current_panel: Panel::Home,
             show_settings: false,
             error_message: None,
@@ -138,19 +306,23 @@ impl App {
             new_vpn_username: String::new(),
             new_vpn_password: String::new(),
             new_vpn_type: VpnType::OpenVpn,
-            new_rdp_name: String::new(),
-            new_rdp_host: String::new(),
-            new_rdp_port: String::from("3389"),
-            new_rdp_username: String::new(),
-            new_rdp_password: String::new(),
-            new_rdp_domain: String::new(),
-            new_wol_name: String::new(),
-            new_wol_mac: String::new(),
-            new_wol_ip: String::new(),
-            new_wol_port: String::from("9"),
+            new_vpn_env: String::new(),
+            new_vpn_server: String::new(),
+            new_vpn_group: String::new(),
+            new_vpn_proxy: Default::default(),
+            new_vpn_custom: Default::default(),
+            new_vpn_prompt_otp: false,
+            editing_vpn: None,
+            new_vpn_keys: None,
+            new_vpn_draft: Default::default(),
+            new_vpn_check: None,
+            new_vpn_bundle: None,
+            new_device: Default::default(),
             // Initialize feedback states
             is_connecting: false,
             connection_feedback: None,
+            feedback_action: None,
+            feedback_code: None,
             loading_actions: std::collections::HashSet::new(),
             animation_time: 0.0,
             checking_updates: false,
@@ -160,36 +332,266 @@ impl App {
             last_update_check: std::time::Instant::now(),
             update_check_receiver: None,
             update_check_timeout: std::time::Instant::now(),
+            update_offline_at: None,
+            update_error: None,
+            update_checked_at: None,
+            update_check_requested: false,
+            checking_dependency_upgrades: false,
+            dependency_upgrades_checked: false,
+            dependency_upgrade_requested: false,
+            dependency_upgrade_receiver: None,
             // Initialize device operation states
             device_operations: std::collections::HashMap::new(),
-            device_feedback_receiver: None,
+            device_feedback_receiver: Some(device_feedback_receiver),
+            device_feedback_sender,
+            pending_retries: std::collections::HashMap::new(),
+            captive_portal_url: None,
+            migration_report: migration.needs_attention().then_some(migration),
+            otp_prompt: None,
+            problems: Vec::new(),
+            last_lint: std::time::Instant::now(),
+            command_preview: None,
+            client_log_view: None,
+            last_schedule_check: chrono::Local::now(),
+            navigation: route::Navigation::default(),
+            new_schedule_kind: crate::config::schedule::ScheduledActionKind::WakeDevice,
+            new_schedule_target: String::new(),
+            new_schedule_time: String::from("08:00"),
+            dashboard: None,
+            dashboard_started_with: None,
+            dashboard_error: None,
+            dashboard_pairing: None,
+            dashboard_pair_requested: false,
+            provider_browser: Default::default(),
+            encryption_form: Default::default(),
+            status_notifier: Default::default(),
+            bulk_edit: Default::default(),
+            notes_window: Default::default(),
+            frame_stats: Default::default(),
+            thumbnails: Default::default(),
+            duplicates_open: false,
+            route_editor: Default::default(),
+            leak_tests: Default::default(),
+            service_statuses: Default::default(),
+            reconnector: None,
+            stale_recovery: None,
+            recovery: None,
+            vpn_drops: Default::default(),
+            always_on_retry: None,
+            traffic: None,
+            public_ip: Default::default(),
+            route_table: Default::default(),
+            last_vpn_refresh: std::time::Instant::now(),
+            last_quick_device_check: std::time::Instant::now(),
+            last_full_device_check: std::time::Instant::now(),
+            safe_mode,
+            startup: None,
+            failover: None,
+            previous_session: None,
+            session_saved: None,
         };
 
-        // Auto-connect to VPN if enabled
-        if app.config.auto_connect_vpn && !app.config.vpn_configs.is_empty() {
-            info!("Auto-connecting to VPN...");
-            if let Some(vpn_config) = app.config.vpn_configs.first() {
-                let runtime = tokio::runtime::Runtime::new().unwrap();
-                let _ = runtime.block_on(async {
-                    app.network_manager.connect_vpn(vpn_config).await
-                });
+        let repaint = cc.egui_ctx.clone();
+        app.status_notifier.set_waker(move || repaint.request_repaint());
+
+        info!("Checking configuration...");
+        app.relint();
+        if !app.problems.is_empty() {
+            warn!("Configuration has {} problem(s)", app.problems.len());
+            app.connection_feedback = Some(format!(
+                "Found {} configuration problem(s), see the Problems panel", app.problems.len()));
+        }
+
+        // Registered anew, so the entry follows the app when it moves; the setting follows an
+        // entry left from an earlier install
+        if app.config.always_on.start_at_login {
+            if let Err(e) = crate::system::autostart::register() {
+                warn!("Could not register to start at login: {}", e);
+            }
+        } else if crate::system::autostart::is_registered() {
+            app.config.always_on.start_at_login = true;
+        }
+        
+        // An encrypted config waits for the master password, see draw_unlock
+        if app.config.locked {
+            info!("Config is encrypted, waiting for the master password");
+        } else if !app.safe_mode {
+            app.start_startup_checks();
+        }
+
+        // Safe mode leaves the file for the next normal start
+        if !app.safe_mode {
+            if let Some((mut state, saved_at)) = crate::system::recovery::unclean_exit() {
+                warn!("The previous session didn't exit cleanly");
+                // A tab asked for on the command line wins
+                if route.is_some() {
+                    state.panel.clear();
+                }
+                app.previous_session = (!state.is_empty()).then_some((state, saved_at));
             }
         }
 
+        if let Some(route) = route {
+            info!("Opening {}", route.to_link());
+            app.navigate(route);
+        }
+
         info!("Setting up fonts and styles...");
         app.setup_fonts(cc);
-        app.setup_style(cc);
+        app.apply_style(&cc.egui_ctx);
         
         info!("Loading logo texture...");
         app.load_logo_texture(cc);
         
-        info!("Checking for updates...");
-        app.schedule_update_check();
+        // Reuse a recent result so starting offline doesn't wait on the network
+        match app.app_updater.load_cached_check() {
+            Some(cached) if cached.is_fresh() => {
+                info!("Using cached update check");
+                app.update_checked_at = Some(cached.checked_at);
+                app.update_info = Some(cached.info);
+            }
+            cached => {
+                if let Some(cached) = cached {
+                    app.update_checked_at = Some(cached.checked_at);
+                    app.update_info = Some(cached.info);
+                }
+                if !app.safe_mode {
+                    info!("Checking for updates...");
+                    app.schedule_update_check();
+                }
+            }
+        }
         
         info!("Application initialized successfully");
         Ok(app)
     }
 
+    // Finds the VPN that is already up and auto-connects in the background, so the window doesn't
+    // wait on the network; an always-on profile is connected whether auto-connect is on or not
+    fn start_startup_checks(&mut self) {
+        use crate::network::startup::StartupTask;
+        
+        let auto_connect = self.auto_connect_request(self.config.check_captive_portal);
+        self.network_manager.vpn_status = crate::network::VpnStatus::Checking;
+        self.startup = Some(StartupTask::start(&self.network_manager, self.config.vpn_configs.clone(), auto_connect));
+    }
+    
+    // The profile to auto-connect and its failover chain, unless it waits for a one-time code
+    fn auto_connect_request(&mut self, check_captive_portal: bool) -> Option<crate::network::startup::AutoConnect> {
+        let vpn = self.config.always_on_vpn().or_else(|| self.config.startup_vpn().filter(|_| self.config.auto_connect_vpn));
+        let requested = vpn.map(|vpn_config| vpn_config.name.clone()).filter(|name| !self.ask_for_code(name))?;
+        let max_drops = self.config.failover.max_drops;
+        let mut chain: Vec<crate::config::VpnConfig> = self.config.failover_chain(&requested).into_iter().cloned().collect();
+        chain.sort_by_key(|vpn_config| self.vpn_drops.is_unstable(&vpn_config.name, max_drops));
+        Some(crate::network::startup::AutoConnect { requested, chain, check_captive_portal })
+    }
+    
+    fn poll_startup(&mut self) {
+        use crate::network::startup::StartupEvent;
+        use std::sync::mpsc::TryRecvError;
+        
+        let Some(startup) = &self.startup else { return };
+        let mut events = Vec::new();
+        let finished = loop {
+            match startup.poll() {
+                Ok(event) => events.push(event),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if finished {
+            self.startup = None;
+            self.last_vpn_refresh = std::time::Instant::now();
+        }
+        
+        for event in events {
+            match event {
+                StartupEvent::Status(status) => {
+                    self.network_manager.vpn_status = status;
+                }
+                StartupEvent::CaptivePortal(url) => {
+                    self.captive_portal_url = Some(url);
+                }
+                StartupEvent::AutoConnected { requested, result } => {
+                    match result {
+                        Ok(connected) if connected != requested => {
+                            self.connection_feedback = Some(format!("Auto-connect to {} failed, connected {} instead", requested, connected));
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            log::error!("Auto-connect to {} failed: {}", requested, e);
+                            self.connection_feedback = Some(format!("Auto-connect to {} failed: {}", requested, e));
+                            self.attach_error_code(&e);
+                        }
+                    }
+                    self.animation_time = 0.0;
+                }
+            }
+        }
+        // The thread ended without reporting, e.g. it panicked
+        if finished && matches!(self.network_manager.vpn_status, crate::network::VpnStatus::Checking) {
+            self.network_manager.vpn_status = crate::network::VpnStatus::Disconnected;
+        }
+    }
+    
+    // Once past the captive portal: checks again unless told to go ahead, then auto-connects, in
+    // the background like at startup
+    fn retry_auto_connect(&mut self, check_captive_portal: bool) {
+        use crate::network::startup::StartupTask;
+        
+        self.captive_portal_url = None;
+        if self.startup.is_some() {
+            return;
+        }
+        if let Some(auto_connect) = self.auto_connect_request(check_captive_portal) {
+            self.startup = Some(StartupTask::auto_connect(&self.network_manager, auto_connect));
+        }
+    }
+    
+    fn run_scheduled_actions(&mut self) {
+        use crate::config::schedule::ScheduledActionKind;
+        
+        let now = chrono::Local::now();
+        let due: Vec<_> = self.config.scheduled_actions
+            .iter()
+            .filter(|action| action.is_due(self.last_schedule_check, now))
+            .cloned()
+            .collect();
+        self.last_schedule_check = now;
+        
+        for action in due {
+            match action.kind {
+                ScheduledActionKind::WakeDevice => {
+                    let device = self.config.wol_devices().find(|d| d.name == action.target).cloned();
+                    match device {
+                        Some(device) => {
+                            log::info!("Running scheduled wake for {}", device.name);
+                            self.start_device_operation(device.name.clone(), "wake".to_string(), DeviceOperationType::Wake(device));
+                        }
+                        None => log::warn!("Scheduled wake target {} no longer exists", action.target),
+                    }
+                }
+                ScheduledActionKind::ConnectVpn => {
+                    if self.ask_for_code(&action.target) {
+                        log::info!("Scheduled connect to {} waits for its one-time code", action.target);
+                    } else if self.config.vpn_configs.iter().any(|v| v.name == action.target) {
+                        log::info!("Running scheduled connect for {}", action.target);
+                        if !self.connect_with_failover(&action.target, None, FailoverPurpose::Scheduled(action.target.clone()), None) {
+                            log::warn!("Scheduled connect to {} skipped, another connect is under way", action.target);
+                        }
+                    } else {
+                        log::warn!("Scheduled connect target {} no longer exists", action.target);
+                    }
+                }
+            }
+        }
+    }
+    
+    fn relint(&mut self) {
+        self.problems = crate::config::lint::lint(&self.config);
+        self.last_lint = std::time::Instant::now();
+    }
+    
     fn setup_fonts(&self, cc: &eframe::CreationContext<'_>) {
         let fonts = egui::FontDefinitions::default();
         
@@ -206,10 +608,11 @@ impl App {
         cc.egui_ctx.set_fonts(fonts);
     }
 
-    fn setup_style(&self, cc: &eframe::CreationContext<'_>) {
-        let mut style = (*cc.egui_ctx.style()).clone();
+    fn apply_style(&self, ctx: &egui::Context) {
+        let mut style = (*ctx.style()).clone();
         
-        style.visuals.dark_mode = self.config.dark_mode;
+        // Start from egui's own visuals so nothing from the previous mode lingers
+        style.visuals = if Theme::is_dark() { egui::Visuals::dark() } else { egui::Visuals::light() };
         style.visuals.window_fill = self.theme.background;
         style.visuals.panel_fill = self.theme.surface;
         style.visuals.window_stroke = Stroke::new(1.0, self.theme.border);
@@ -284,7 +687,7 @@ impl App {
             FontId::new(13.0, FontFamily::Monospace),
         );
 
-        cc.egui_ctx.set_style(style);
+        ctx.set_style(style);
     }
     
     fn load_logo_texture(&mut self, cc: &eframe::CreationContext<'_>) {
@@ -313,7 +716,7 @@ impl App {
     }
     
     fn schedule_update_check(&mut self) {
-        if self.checking_updates || self.update_info.is_some() {
+        if self.checking_updates {
             return;
         }
         
@@ -328,12 +731,16 @@ impl App {
         std::thread::spawn(move || {
             let rt = tokio::runtime::Runtime::new().unwrap();
             rt.block_on(async {
+                if !app_updater.is_online().await {
+                    let _ = tx.send(None);
+                    return;
+                }
                 match app_updater.check_for_updates().await {
                     Ok(info) => {
-                        let _ = tx.send(Ok(info));
+                        let _ = tx.send(Some(Ok(info)));
                     }
                     Err(e) => {
-                        let _ = tx.send(Err(e.to_string()));
+                        let _ = tx.send(Some(Err(AppUpdater::describe_error(&e))));
                     }
                 }
             });
@@ -350,9 +757,20 @@ impl App {
                     // Update check completed
                     self.checking_updates = false;
                     self.update_check_receiver = None;
+                    self.update_offline_at = None;
                     
                     match result {
-                        Ok(info) => {
+                        None => {
+                            log::debug!("Offline, update check skipped until the network is back");
+                            self.update_offline_at = Some(std::time::Instant::now());
+                            self.update_error = Some("Offline; checking again once the network is back".to_string());
+                        }
+                        Some(Ok(info)) => {
+                            self.update_error = None;
+                            self.update_checked_at = Some(std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .map(|d| d.as_secs())
+                                .unwrap_or(0));
                             if info.update_available {
                                 log::info!("Update available: {} -> {}", info.current_version, info.latest_version);
                                 self.update_info = Some(info.clone());
@@ -362,17 +780,20 @@ impl App {
                                 self.update_info = Some(info);
                             }
                         }
-                        Err(e) => {
+                        Some(Err(e)) => {
                             log::warn!("Failed to check for updates: {}", e);
+                            self.update_error = Some(e);
                         }
                     }
                 }
                 Err(std::sync::mpsc::TryRecvError::Empty) => {
                     // Still waiting for result, check for timeout
-                    if self.update_check_timeout.elapsed().as_secs() > 30 {
-                        log::warn!("Update check timed out after 30 seconds");
+                    // The HTTP client gives up sooner; this only guards against a stuck thread
+                    if self.update_check_timeout.elapsed().as_secs() > 20 {
+                        log::warn!("Update check timed out after 20 seconds");
                         self.checking_updates = false;
                         self.update_check_receiver = None;
+                        self.update_error = Some("Update check timed out".to_string());
                     }
                 }
                 Err(std::sync::mpsc::TryRecvError::Disconnected) => {
@@ -385,16 +806,192 @@ impl App {
         }
     }
     
+    fn check_dependency_upgrades(&mut self) {
+        if self.checking_dependency_upgrades {
+            return;
+        }
+        
+        let packages: Vec<String> = self.system_info.dependencies.iter()
+            .filter(|dep| dep.is_installed && dep.package_name != "builtin")
+            .map(|dep| dep.package_name.clone())
+            .collect();
+        if packages.is_empty() {
+            return;
+        }
+        
+        self.checking_dependency_upgrades = true;
+        let package_installer = self.package_installer.clone();
+        let (tx, rx) = std::sync::mpsc::channel();
+        
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(package_installer.check_upgrades(&packages));
+            let _ = tx.send(result.map_err(|e| e.to_string()));
+        });
+        
+        self.dependency_upgrade_receiver = Some(rx);
+    }
+    
+    fn poll_dependency_upgrades(&mut self) {
+        let Some(receiver) = &self.dependency_upgrade_receiver else { return };
+        
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(std::sync::mpsc::TryRecvError::Empty) => return,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => Err("Upgrade check stopped unexpectedly".to_string()),
+        };
+        
+        self.checking_dependency_upgrades = false;
+        self.dependency_upgrade_receiver = None;
+        
+        match result {
+            Ok(upgrades) => {
+                log::info!("{} dependency upgrade(s) available", upgrades.len());
+                self.system_info.set_upgrades(&upgrades);
+            }
+            Err(e) => log::warn!("Failed to check dependency upgrades: {}", e),
+        }
+    }
+    
+    // Starts, restarts or stops the web dashboard to match the config, then exchanges state with it
+    fn sync_dashboard(&mut self) {
+        use crate::network::dashboard::{DashboardCommand, DashboardDevice, DashboardServer, DashboardSnapshot, DashboardVpn};
+        use crate::network::VpnStatus;
+        
+        let settings = &self.config.web_dashboard;
+        if !settings.enabled {
+            self.dashboard = None;
+            self.dashboard_started_with = None;
+            self.dashboard_error = None;
+            self.dashboard_pairing = None;
+            return;
+        }
+        
+        // Only retried when the settings change, not every frame after a failure
+        if self.dashboard_started_with.as_ref() != Some(settings) {
+            self.dashboard = None;
+            self.dashboard_pairing = None;
+            self.dashboard_started_with = Some(settings.clone());
+            match DashboardServer::start(settings) {
+                Ok(server) => {
+                    self.dashboard = Some(server);
+                    self.dashboard_error = None;
+                }
+                Err(e) => {
+                    log::warn!("Failed to start web dashboard: {}", e);
+                    self.dashboard_error = Some(e.to_string());
+                }
+            }
+        }
+        
+        let Some(dashboard) = &self.dashboard else { return };
+        
+        // Hide the QR code once it has been scanned or expired, and void the code if it was closed
+        if self.dashboard_pairing.is_some() && !dashboard.pairing_active() {
+            self.dashboard_pairing = None;
+        } else if self.dashboard_pairing.is_none() && dashboard.pairing_active() {
+            dashboard.cancel_pairing();
+        }
+        
+        let mut wakes = Vec::new();
+        while let Some(command) = dashboard.try_recv() {
+            match command {
+                DashboardCommand::Wake(name) => wakes.push(name),
+            }
+        }
+        
+        dashboard.update(DashboardSnapshot {
+            vpn_status: match &self.network_manager.vpn_status {
+                VpnStatus::Checking => "Checking...".to_string(),
+                VpnStatus::Disconnected => "Disconnected".to_string(),
+                VpnStatus::Connecting => "Connecting...".to_string(),
+                VpnStatus::Connected(name) => format!("Connected to {}", name),
+                VpnStatus::Reconnecting { name, attempt, max_attempts } => {
+                    crate::network::reconnect::status_text(name, *attempt, *max_attempts)
+                }
+                VpnStatus::Degraded { name, handshake_secs } => crate::network::reconnect::degraded_text(name, *handshake_secs),
+                VpnStatus::Error(e) => format!("Error: {}", e),
+            },
+            vpns: self.config.vpn_configs.iter().map(|vpn_config| DashboardVpn {
+                name: vpn_config.name.clone(),
+                connected: self.network_manager.vpn_status.connected_name() == Some(vpn_config.name.as_str()),
+            }).collect(),
+            devices: self.config.devices.iter().filter(|device| device.wol.is_some()).map(|device| DashboardDevice {
+                name: device.name.clone(),
+                ip_address: device.monitor_address().unwrap_or_default(),
+                online: self.network_manager.is_online(&device.name),
+                waking: matches!(
+                    self.device_operations.get(&format!("{}_wake", device.name)),
+                    Some((DeviceOperationState::Loading, _))
+                ),
+            }).collect(),
+        });
+        
+        for name in wakes {
+            let device = self.config.wol_devices().find(|d| d.name == name).cloned();
+            if let Some(device) = device {
+                log::info!("Wake of {} requested from the web dashboard", name);
+                self.start_device_operation(name, "wake".to_string(), DeviceOperationType::Wake(device));
+            }
+        }
+    }
+    
+    fn start_dashboard_pairing(&mut self, ctx: &egui::Context) {
+        let Some(dashboard) = &self.dashboard else { return };
+        
+        let code = match dashboard.start_pairing() {
+            Ok(code) => code,
+            Err(e) => {
+                self.dashboard_error = Some(e.to_string());
+                return;
+            }
+        };
+        let Some(url) = crate::network::dashboard::pairing_url(&self.config.web_dashboard, &code) else {
+            dashboard.cancel_pairing();
+            self.dashboard_error = Some("No network address to pair over".to_string());
+            return;
+        };
+        
+        match qrcode::QrCode::new(url.as_bytes()) {
+            Ok(code) => {
+                // One pixel per module plus the 4-module quiet zone scanners expect; the texture is
+                // scaled up with nearest filtering so the edges stay sharp
+                let quiet = 4;
+                let width = code.width();
+                let size = width + quiet * 2;
+                let mut pixels = vec![egui::Color32::WHITE; size * size];
+                for (i, color) in code.to_colors().iter().enumerate() {
+                    if *color == qrcode::Color::Dark {
+                        pixels[(i / width + quiet) * size + i % width + quiet] = egui::Color32::BLACK;
+                    }
+                }
+                
+                let color_image = ColorImage { size: [size, size], pixels };
+                let texture = ctx.load_texture("dashboard-pairing", color_image, egui::TextureOptions::NEAREST);
+                self.dashboard_pairing = Some((url, texture));
+            }
+            Err(e) => {
+                dashboard.cancel_pairing();
+                log::warn!("Failed to create pairing QR code: {}", e);
+            }
+        }
+    }
+    
     fn start_device_operation(&mut self, device_name: String, operation: String, operation_type: DeviceOperationType) {
+        // A manual attempt replaces any retry that was waiting
+        self.pending_retries.remove(&format!("{}_{}", device_name, operation));
+        self.run_device_operation(device_name, operation, operation_type, 1);
+    }
+    
+    fn run_device_operation(&mut self, device_name: String, operation: String, operation_type: DeviceOperationType, attempt: u32) {
+        use crate::network::retry;
+        
         // Set device state to loading
-        self.device_operations.insert(
-            format!("{}_{}", device_name, operation), 
-            DeviceOperationState::Loading
-        );
+        self.set_device_operation(format!("{}_{}", device_name, operation), DeviceOperationState::Loading);
         
-        // Use a channel to communicate results back
-        use std::sync::mpsc;
-        let (tx, rx) = mpsc::channel();
+        // All operations report back over the same channel so concurrent ones aren't lost
+        let tx = self.device_feedback_sender.clone();
+        let retry_with = operation_type.clone();
         
         match operation_type {
             DeviceOperationType::Wake(wol_device) => {
@@ -402,13 +999,20 @@ impl App {
                 std::thread::spawn(move || {
                     let rt = tokio::runtime::Runtime::new().unwrap();
                     rt.block_on(async {
+                        let route_warning = crate::network::wol::route_warning(&wol_device).await;
                         match network_manager.wake_device(&wol_device).await {
                             Ok(_) => {
                                 let _ = tx.send(DeviceOperationResult {
                                     device_name: device_name.clone(),
                                     operation: operation.clone(),
                                     success: true,
-                                    message: format!("Wake-on-LAN packet sent to {}", device_name),
+                                    message: match route_warning {
+                                        Some(warning) => format!("Wake-on-LAN packet sent to {}, but {}", device_name, warning),
+                                        None => format!("Wake-on-LAN packet sent to {}", device_name),
+                                    },
+                                    code: None,
+                                    attempt,
+                                    retry_with: None,
                                 });
                             }
                             Err(e) => {
@@ -417,38 +1021,55 @@ impl App {
                                     operation: operation.clone(),
                                     success: false,
                                     message: format!("Failed to wake {}: {}", device_name, e),
+                                    code: crate::errors::code_of(&e),
+                                    attempt,
+                                    retry_with: retry::is_transient(&e.to_string()).then_some(retry_with),
                                 });
                             }
                         }
                     });
                 });
             }
-            DeviceOperationType::Ping(wol_device) => {
+            DeviceOperationType::Ping { name, address } => {
                 let mut network_manager = self.network_manager.clone();
                 std::thread::spawn(move || {
                     let rt = tokio::runtime::Runtime::new().unwrap();
                     rt.block_on(async {
-                        let is_online = network_manager.check_device_status(&wol_device).await;
+                        let is_online = network_manager.check_device_status(&name, &address).await;
                         let _ = tx.send(DeviceOperationResult {
                             device_name: device_name.clone(),
                             operation: operation.clone(),
                             success: true,
                             message: format!("{} is {}", device_name, if is_online { "online" } else { "offline" }),
+                            code: None,
+                            attempt,
+                            retry_with: None,
                         });
                     });
                 });
             }
             DeviceOperationType::RdpConnect(rdp_config) => {
+                let mut network_manager = self.network_manager.clone();
+                let capture_thumbnail = self.config.capture_thumbnails;
                 std::thread::spawn(move || {
                     let rt = tokio::runtime::Runtime::new().unwrap();
                     rt.block_on(async {
-                        match crate::network::rdp::connect(&rdp_config).await {
-                            Ok(_) => {
+                        match network_manager.connect_rdp(&rdp_config).await {
+                            Ok(slow_link) => {
+                                if capture_thumbnail {
+                                    crate::system::thumbnails::capture_later(&device_name, crate::system::thumbnails::SESSION_DELAY);
+                                }
                                 let _ = tx.send(DeviceOperationResult {
                                     device_name: device_name.clone(),
                                     operation: operation.clone(),
                                     success: true,
-                                    message: format!("RDP connection initiated to {}", device_name),
+                                    message: match slow_link {
+                                        Some(slow_link) => slow_link.message(&device_name),
+                                        None => format!("RDP connection initiated to {}", device_name),
+                                    },
+                                    code: None,
+                                    attempt,
+                                    retry_with: None,
                                 });
                             }
                             Err(e) => {
@@ -457,19 +1078,75 @@ impl App {
                                     operation: operation.clone(),
                                     success: false,
                                     message: format!("Failed to connect to {}: {}", device_name, e),
+                                    code: crate::errors::code_of(&e),
+                                    attempt,
+                                    retry_with: retry::is_transient(&e.to_string()).then_some(retry_with),
                                 });
                             }
                         }
                     });
                 });
             }
+            DeviceOperationType::WakeThenSsh(wol_device, ssh_config) => {
+                let mut network_manager = self.network_manager.clone();
+                let terminal = self.config.ssh_terminal.clone();
+                std::thread::spawn(move || {
+                    let rt = tokio::runtime::Runtime::new().unwrap();
+                    let result = rt.block_on(network_manager.wake_then_ssh(&wol_device, &ssh_config, &terminal));
+                    let _ = tx.send(DeviceOperationResult {
+                        success: result.is_ok(),
+                        code: result.as_ref().err().and_then(crate::errors::code_of),
+                        message: match result {
+                            Ok(()) => format!("SSH session opened to {}", device_name),
+                            Err(e) => format!("Failed to open SSH to {}: {}", device_name, e),
+                        },
+                        device_name,
+                        operation,
+                        attempt,
+                        retry_with: None,
+                    });
+                });
+            }
+            // Both only spawn a window, so there's nothing to wait for or retry
+            DeviceOperationType::SshConnect(ssh_config) => {
+                let result = self.network_manager.connect_ssh(&ssh_config, &self.config.ssh_terminal);
+                let _ = tx.send(DeviceOperationResult {
+                    success: result.is_ok(),
+                    code: result.as_ref().err().and_then(crate::errors::code_of),
+                    message: match result {
+                        Ok(()) => format!("SSH session opened to {}", device_name),
+                        Err(e) => format!("Failed to open SSH to {}: {}", device_name, e),
+                    },
+                    device_name,
+                    operation,
+                    attempt,
+                    retry_with: None,
+                });
+            }
+            DeviceOperationType::VncConnect(vnc_config) => {
+                let result = self.network_manager.connect_vnc(&vnc_config);
+                if result.is_ok() && self.config.capture_thumbnails {
+                    crate::system::thumbnails::capture_later(&device_name, crate::system::thumbnails::SESSION_DELAY);
+                }
+                let _ = tx.send(DeviceOperationResult {
+                    success: result.is_ok(),
+                    code: result.as_ref().err().and_then(crate::errors::code_of),
+                    message: match result {
+                        Ok(()) => format!("VNC viewer opened for {}", device_name),
+                        Err(e) => format!("Failed to open VNC to {}: {}", device_name, e),
+                    },
+                    device_name,
+                    operation,
+                    attempt,
+                    retry_with: None,
+                });
+            }
         }
-        
-        // Store the receiver for polling in the main thread
-        self.device_feedback_receiver = Some(rx);
     }
     
     fn poll_device_operations(&mut self) {
+        use crate::network::retry;
+        
         if let Some(receiver) = &self.device_feedback_receiver {
             match receiver.try_recv() {
                 Ok(result) => {
@@ -477,10 +1154,26 @@ impl App {
                     let key = format!("{}_{}", result.device_name, result.operation);
                     
                     if result.success {
-                        self.device_operations.insert(key, DeviceOperationState::Success(result.message.clone()));
+                        if result.operation == "wake" {
+                            self.status_notifier.wake_sent(&result.device_name);
+                        }
+                        self.set_device_operation(key, DeviceOperationState::Success(result.message.clone()));
                         self.connection_feedback = Some(result.message);
+                    } else if let Some(operation) = result.retry_with.filter(|_| result.attempt < retry::MAX_ATTEMPTS) {
+                        // Transient failure, try again after a backoff
+                        let delay = retry::backoff_delay(result.attempt);
+                        let message = format!("{} (retrying in {}s)", result.message, delay.as_secs());
+                        self.pending_retries.insert(key.clone(), PendingRetry {
+                            operation,
+                            attempt: result.attempt + 1,
+                            due: std::time::Instant::now() + delay,
+                        });
+                        self.set_device_operation(key, DeviceOperationState::Error(message.clone()));
+                        self.feedback_code = result.code.map(|code| (message.clone(), code));
+                        self.connection_feedback = Some(message);
                     } else {
-                        self.device_operations.insert(key, DeviceOperationState::Error(result.message.clone()));
+                        self.set_device_operation(key, DeviceOperationState::Error(result.message.clone()));
+                        self.feedback_code = result.code.map(|code| (result.message.clone(), code));
                         self.connection_feedback = Some(result.message);
                     }
                     
@@ -501,106 +1194,629 @@ impl App {
         }
     }
     
-    fn get_device_operation_state(&self, device_name: &str, operation: &str) -> &DeviceOperationState {
-        let key = format!("{}_{}", device_name, operation);
-        self.device_operations.get(&key).unwrap_or(&DeviceOperationState::Idle)
-    }
-
-    fn draw_sidebar(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
-        ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
-            Spacing::md(ui);
-            
-            // Display logo if available
-            if let Some(logo_texture) = &self.logo_texture {
-                ui.horizontal(|ui| {
-                    Spacing::sm(ui);
-                    ui.add(egui::Image::new(logo_texture)
-                        .max_width(40.0)
-                        .max_height(40.0)
-                        .rounding(egui::Rounding::same(6.0)));
-                    Spacing::sm(ui);
-                    ui.vertical(|ui| {
-                        Typography::heading(ui, &self.theme, "VPN Manager");
-                        Typography::small(ui, &self.theme, "All-in-One");
-                    });
-                });
-            } else {
-                ui.horizontal(|ui| {
-                    Spacing::sm(ui);
-                    ui.vertical(|ui| {
-                        Typography::heading(ui, &self.theme, "VPN Manager");
-                        Typography::small(ui, &self.theme, "All-in-One");
-                    });
-                });
-            }
-            Spacing::lg(ui);
-
-            let button_size = egui::vec2(180.0, 28.0);
-            
-            // Navigation buttons with consistent styling
-            let home_selected = self.current_panel == Panel::Home;
-            if self.draw_nav_button(ui, "🏠 Home", button_size, home_selected) {
-                self.current_panel = Panel::Home;
-            }
-            Spacing::xs(ui);
-            
-            let vpn_selected = self.current_panel == Panel::Vpn;
-            if self.draw_nav_button(ui, "🔒 VPN", button_size, vpn_selected) {
-                self.current_panel = Panel::Vpn;
-            }
-            Spacing::xs(ui);
-            
-            let remote_selected = self.current_panel == Panel::Remote;
-            if self.draw_nav_button(ui, "🖥️ Remote", button_size, remote_selected) {
-                self.current_panel = Panel::Remote;
-            }
-            
-            Spacing::sm(ui);
-            
-            // Show update indicator on Settings button if update is available
-            let settings_text = if let Some(update) = &self.update_info {
-                if update.update_available {
-                    "⚙️ Settings 🔴"
-                } else {
-                    "⚙️ Settings"
-                }
-            } else if self.checking_updates {
-                "⚙️ Settings ⏳"
-            } else {
-                "⚙️ Settings"
-            };
-            
-            let settings_selected = self.current_panel == Panel::Settings;
-            if self.draw_nav_button(ui, settings_text, button_size, settings_selected) {
-                self.current_panel = Panel::Settings;
+    fn run_due_retries(&mut self) {
+        let now = std::time::Instant::now();
+        let due: Vec<String> = self.pending_retries
+            .iter()
+            .filter(|(_, retry)| retry.due <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        
+        for key in due {
+            if let Some(retry) = self.pending_retries.remove(&key) {
+                let (device_name, operation) = match &retry.operation {
+                    DeviceOperationType::Wake(device) => (device.name.clone(), "wake"),
+                    DeviceOperationType::Ping { name, .. } => (name.clone(), "ping"),
+                    DeviceOperationType::RdpConnect(rdp_config) => (rdp_config.name.clone(), "connect"),
+                    DeviceOperationType::SshConnect(ssh_config) | DeviceOperationType::WakeThenSsh(_, ssh_config) => (ssh_config.name.clone(), "ssh"),
+                    DeviceOperationType::VncConnect(vnc_config) => (vnc_config.name.clone(), "vnc"),
+                };
+                log::info!("Retrying {} for {} (attempt {} of {})", operation, device_name, retry.attempt,
+                    crate::network::retry::MAX_ATTEMPTS);
+                self.run_device_operation(device_name, operation.to_string(), retry.operation, retry.attempt);
             }
-        });
+        }
     }
     
-    fn draw_nav_button(&self, ui: &mut egui::Ui, text: &str, size: egui::Vec2, is_selected: bool) -> bool {
-        let button_color = if is_selected {
-            self.theme.primary
-        } else {
-            self.theme.surface_variant
-        };
+    // Starts reconnecting once a status refresh finds the tunnel dropped, and follows the attempts
+    fn poll_reconnect(&mut self) {
+        use crate::network::reconnect::{ReconnectEvent, Reconnector};
+        use crate::network::VpnStatus;
         
-        let text_color = if is_selected {
-            egui::Color32::WHITE
-        } else {
-            self.theme.text_primary
+        let reconnecting = match &self.network_manager.vpn_status {
+            VpnStatus::Reconnecting { name, .. } => Some(name.clone()),
+            _ => None,
         };
+        // Back up by other means, or stopped from the UI
+        if self.reconnector.as_ref().is_some_and(|r| reconnecting.as_ref() != Some(&r.vpn)) {
+            self.reconnector = None;
+        }
+        let Some(name) = reconnecting else { return };
         
-        let button = egui::Button::new(
-            egui::RichText::new(text).color(text_color).size(12.0)
-        )
-        .fill(button_color)
-        .stroke(egui::Stroke::new(if is_selected { 0.0 } else { 1.0 }, self.theme.border))
-        .rounding(egui::Rounding::same(4.0));
+        if self.reconnector.is_none() {
+            let Some(vpn_config) = self.config.vpn_configs.iter().find(|c| c.name == name) else {
+                self.network_manager.vpn_status = VpnStatus::Disconnected;
+                return;
+            };
+            let vpn_config = if self.is_always_on(&name) {
+                crate::network::always_on::with_reconnect(vpn_config)
+            } else {
+                vpn_config.clone()
+            };
+            self.reconnector = Some(Reconnector::start(&self.network_manager, &vpn_config));
+        }
+        
+        while let Some(event) = self.reconnector.as_ref().and_then(|r| r.poll()) {
+            match event {
+                ReconnectEvent::Attempt(attempt) => {
+                    if let VpnStatus::Reconnecting { attempt: current, .. } = &mut self.network_manager.vpn_status {
+                        *current = attempt;
+                    }
+                }
+                ReconnectEvent::Failed { attempt, error } => {
+                    log::warn!("Reconnect attempt {} for {} failed: {}", attempt, name, error);
+                }
+                ReconnectEvent::Connected => {
+                    log::info!("Reconnected to {}", name);
+                    self.connection_feedback = Some(format!("Reconnected to {}", name));
+                    self.network_manager.vpn_status = VpnStatus::Connected(name.clone());
+                    self.reconnector = None;
+                }
+                ReconnectEvent::GaveUp(error) => {
+                    log::error!("Giving up reconnecting to {}: {}", name, error);
+                    self.network_manager.vpn_status = VpnStatus::Error(format!("Reconnecting to {} failed: {}", name, error));
+                    self.reconnector = None;
+                    if self.is_always_on(&name) {
+                        self.always_on_retry = Some(std::time::Instant::now() + crate::network::always_on::RETRY_AFTER);
+                    }
+                    if self.in_failover_order(&name) {
+                        self.fail_over(&name, true);
+                    } else {
+                        self.vpn_dropped(&name, Some(&error));
+                    }
+                }
+            }
+        }
+    }
+    
+    // A tunnel went down on its own. Its reconnect policy gets the first go unless the profile
+    // keeps dropping; profiles in the failover order then fall back along it.
+    fn vpn_lost(&mut self, name: &str) {
+        use crate::network::VpnStatus;
+        
+        let drops = self.vpn_drops.record(name);
+        let reconnecting = matches!(self.network_manager.vpn_status, VpnStatus::Reconnecting { .. });
+        // The always-on profile is reconnected even when its own policy is off
+        if !reconnecting && !self.in_failover_order(name) && self.is_always_on(name) {
+            if let Some(vpn_config) = self.config.vpn_configs.iter().find(|c| c.name == name) {
+                self.network_manager.vpn_status = VpnStatus::Reconnecting {
+                    name: name.to_string(),
+                    attempt: 0,
+                    max_attempts: crate::network::always_on::with_reconnect(vpn_config).reconnect.max_retries,
+                };
+                return;
+            }
+        }
+        if !self.in_failover_order(name) {
+            if !reconnecting {
+                self.vpn_dropped(name, None);
+            }
+            return;
+        }
+        let unstable = self.vpn_drops.is_unstable(name, self.config.failover.max_drops);
+        if reconnecting && !unstable {
+            return;
+        }
+        if unstable {
+            log::warn!("{} dropped {} times in {} minutes, passing it over", name, drops,
+                crate::network::failover::DROP_WINDOW.as_secs() / 60);
+        }
+        self.fail_over(name, unstable);
+    }
+    
+    fn is_always_on(&self, name: &str) -> bool {
+        self.config.always_on_vpn().is_some_and(|vpn_config| vpn_config.name == name)
+    }
+    
+    // Tries the always-on profile again once the wait after a failed reconnect is over. A tunnel
+    // that came up meanwhile, by hand or through failover, ends the wait.
+    fn enforce_always_on(&mut self) {
+        let Some(at) = self.always_on_retry else { return };
+        let Some(name) = self.config.always_on_vpn().map(|vpn_config| vpn_config.name.clone()) else {
+            self.always_on_retry = None;
+            return;
+        };
+        if self.network_manager.vpn_status.connected_name().is_some() {
+            self.always_on_retry = None;
+            return;
+        }
+        if std::time::Instant::now() < at || self.failover.is_some() {
+            return;
+        }
+        
+        self.always_on_retry = None;
+        if self.ask_for_code(&name) {
+            return;
+        }
+        log::info!("Always-on: trying {} again", name);
+        self.connect_with_failover(&name, None, FailoverPurpose::AlwaysOn(name.clone()), None);
+    }
+    
+    fn in_failover_order(&self, name: &str) -> bool {
+        self.config.failover.enabled && self.config.failover.order.iter().any(|entry| entry == name)
+    }
+    
+    // Connects the most preferred profile of the failover order that comes up, leaving out the
+    // one that dropped if it is to be passed over
+    fn fail_over(&mut self, dropped: &str, skip_dropped: bool) {
+        let Some(first) = self.config.failover.order.iter().find(|name| !(skip_dropped && *name == dropped)).cloned() else {
+            self.vpn_dropped(dropped, None);
+            return;
+        };
+        // Routes and DNS settings went with the old interface, the task cleans them up first
+        let dropped_config = self.config.vpn_configs.iter().find(|c| c.name == dropped).cloned();
+        let purpose = FailoverPurpose::FailOver { dropped: dropped.to_string() };
+        if !self.connect_with_failover(&first, skip_dropped.then_some(dropped), purpose, dropped_config) {
+            log::warn!("{} dropped while another connect was under way", dropped);
+        }
+    }
+    
+    // Starts connecting `name` and, if it fails, the rest of the failover order in the
+    // background; profiles that keep dropping are tried last. poll_failover reports the outcome
+    // for `purpose`. False if another walk is still under way.
+    fn connect_with_failover(&mut self, name: &str, skip: Option<&str>, purpose: FailoverPurpose, dropped: Option<crate::config::VpnConfig>) -> bool {
+        if self.failover.is_some() {
+            return false;
+        }
+        let max_drops = self.config.failover.max_drops;
+        let mut chain: Vec<crate::config::VpnConfig> = self.config.failover_chain(name).into_iter().cloned().collect();
+        chain.retain(|vpn_config| Some(vpn_config.name.as_str()) != skip);
+        chain.sort_by_key(|vpn_config| self.vpn_drops.is_unstable(&vpn_config.name, max_drops));
+        
+        self.network_manager.vpn_status = crate::network::VpnStatus::Connecting;
+        let task = crate::network::failover::FailoverTask::start(&self.network_manager, chain, dropped);
+        self.failover = Some((task, purpose));
+        true
+    }
+    
+    fn poll_failover(&mut self) {
+        use crate::network::failover::FailoverEvent;
+        use std::sync::mpsc::TryRecvError;
+        
+        let Some((task, _)) = &self.failover else { return };
+        let mut events = Vec::new();
+        let finished = loop {
+            match task.poll() {
+                Ok(event) => events.push(event),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        let purpose = self.failover.as_ref().map(|(_, purpose)| purpose.clone()).unwrap();
+        if finished {
+            self.failover = None;
+            self.last_vpn_refresh = std::time::Instant::now();
+        }
+        
+        for event in events {
+            match event {
+                FailoverEvent::Trying(name) => {
+                    self.connection_feedback = Some(format!("Connecting to {}...", name));
+                }
+                FailoverEvent::Finished { status, result } => {
+                    self.network_manager.vpn_status = status;
+                    self.failover_finished(purpose.clone(), result);
+                }
+            }
+        }
+        // The thread ended without reporting, e.g. it panicked
+        if finished && matches!(self.network_manager.vpn_status, crate::network::VpnStatus::Connecting) {
+            self.network_manager.vpn_status = crate::network::VpnStatus::Disconnected;
+        }
+    }
+    
+    fn failover_finished(&mut self, purpose: FailoverPurpose, result: anyhow::Result<String>) {
+        let feedback = match (&purpose, &result) {
+            (FailoverPurpose::Scheduled(name), Ok(connected)) if connected == name => format!("Scheduled connect to {} started", name),
+            (FailoverPurpose::Scheduled(name), Ok(connected)) => format!("Scheduled connect to {} failed, connected {} instead", name, connected),
+            (FailoverPurpose::Scheduled(name), Err(e)) => format!("Scheduled connect to {} failed: {}", name, e),
+            (FailoverPurpose::AlwaysOn(_), Ok(connected)) => format!("Connected to {}", connected),
+            (FailoverPurpose::AlwaysOn(name), Err(e)) => {
+                log::warn!("Always-on: connecting {} failed: {}", name, e);
+                self.always_on_retry = Some(std::time::Instant::now() + crate::network::always_on::RETRY_AFTER);
+                return;
+            }
+            (FailoverPurpose::FailOver { dropped }, Ok(name)) if name == dropped => format!("Reconnected to {}", name),
+            (FailoverPurpose::FailOver { dropped }, Ok(name)) => {
+                log::info!("Failed over from {} to {}", dropped, name);
+                self.status_notifier.vpn_failed_over(&self.config, dropped, name);
+                format!("{} dropped, switched to {}", dropped, name)
+            }
+            (FailoverPurpose::FailOver { dropped }, Err(e)) => {
+                log::error!("Failover after {} dropped found no working profile: {}", dropped, e);
+                self.vpn_dropped(dropped, Some(&e.to_string()));
+                self.attach_error_code(e);
+                return;
+            }
+            (FailoverPurpose::Code(name), _) => {
+                // Not left for a later connect if this one never got to use it
+                crate::network::otp::take(name);
+                match &result {
+                    Ok(connected) if connected == name => format!("Connected to {}", name),
+                    Ok(connected) => format!("{} failed, connected {} instead", name, connected),
+                    Err(e) => format!("Connecting to {} failed: {}", name, e),
+                }
+            }
+            (FailoverPurpose::Notification(name), Ok(connected)) if connected == name => format!("Reconnected to {}", name),
+            (FailoverPurpose::Notification(name), Ok(connected)) => format!("{} failed, connected {} instead", name, connected),
+            (FailoverPurpose::Notification(name), Err(e)) => format!("Reconnecting to {} failed: {}", name, e),
+            (FailoverPurpose::Restore(name), Ok(connected)) if connected == name => format!("Restored the connection to {}", name),
+            (FailoverPurpose::Restore(name), Ok(connected)) => format!("Restoring {} failed, connected {} instead", name, connected),
+            (FailoverPurpose::Restore(name), Err(e)) => format!("Restoring the connection to {} failed: {}", name, e),
+        };
+        self.connection_feedback = Some(feedback);
+        if let Err(e) = &result {
+            self.attach_error_code(e);
+        }
+        self.animation_time = 0.0;
+    }
+    
+    // Opens the prompt instead when the profile wants a one-time code; true if it did. A prompt
+    // already open for another entry is left alone.
+    fn ask_for_code(&mut self, name: &str) -> bool {
+        let Some(vpn_config) = self.config.vpn_configs.iter().find(|c| c.name == name && crate::network::otp::needs_code(c)) else {
+            return false;
+        };
+        if self.otp_prompt.is_none() {
+            log::info!("Asking for the one-time code of {}", name);
+            self.otp_prompt = Some(panels::vpn::OtpPrompt::new(vpn_config));
+        }
+        true
+    }
+    
+    fn connect_with_code(&mut self, name: &str, code: &str) {
+        crate::network::otp::provide(name, code);
+        if !self.connect_with_failover(name, None, FailoverPurpose::Code(name.to_string()), None) {
+            crate::network::otp::take(name);
+            self.connection_feedback = Some(format!("Another connect is under way, try {} again once it is done", name));
+            self.animation_time = 0.0;
+        }
+    }
+    
+    // Left up longer while it offers something to do
+    fn feedback_secs(&self) -> f32 {
+        if self.current_feedback_action().is_some() { FEEDBACK_SECS * 4.0 } else { FEEDBACK_SECS }
+    }
+    
+    // Only while the feedback it came with is still shown
+    fn current_feedback_action(&self) -> Option<&crate::system::notify::NotificationAction> {
+        match (&self.feedback_action, &self.connection_feedback) {
+            (Some((message, action)), Some(feedback)) if message == feedback => Some(action),
+            _ => None,
+        }
+    }
+    
+    // Only while the feedback it came with is still shown, like the action
+    fn current_feedback_code(&self) -> Option<crate::errors::ErrorCode> {
+        match (&self.feedback_code, &self.connection_feedback) {
+            (Some((message, code)), Some(feedback)) if message == feedback => Some(*code),
+            _ => None,
+        }
+    }
+    
+    // Links the feedback just set to the help on the error it reports
+    fn attach_error_code(&mut self, error: &anyhow::Error) {
+        self.feedback_code = self.connection_feedback.clone().zip(crate::errors::code_of(error));
+    }
+    
+    // Tells about a tunnel that is down for good, in the app and as a desktop notification, both
+    // offering to reconnect
+    fn vpn_dropped(&mut self, name: &str, reason: Option<&str>) {
+        use crate::system::notify::NotificationAction;
+        
+        self.status_notifier.vpn_dropped(&self.config, name, reason);
+        let message = match reason {
+            Some(reason) => format!("Reconnecting to {} failed: {}", name, reason),
+            None => format!("VPN {} dropped", name),
+        };
+        self.feedback_action = Some((message.clone(), NotificationAction::ReconnectVpn(name.to_string())));
+        self.connection_feedback = Some(message);
+        self.animation_time = 0.0;
+    }
+    
+    // A notification or toast button was clicked
+    fn run_notification_action(&mut self, ctx: &egui::Context, action: crate::system::notify::NotificationAction) {
+        use crate::system::notify::NotificationAction;
+        
+        match action {
+            NotificationAction::OpenDevice(name) => {
+                self.navigate(Route::new(Panel::Remote, &name));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Visible(true));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Minimized(false));
+                ctx.send_viewport_cmd(egui::ViewportCommand::Focus);
+            }
+            NotificationAction::ReconnectVpn(name) => {
+                if !self.config.vpn_configs.iter().any(|c| c.name == name) {
+                    log::warn!("VPN {} no longer exists", name);
+                    return;
+                }
+                log::info!("Reconnecting to {} from a notification", name);
+                self.vpn_drops.forget(&name);
+                if self.ask_for_code(&name) {
+                    return;
+                }
+                self.feedback_action = None;
+                if !self.connect_with_failover(&name, None, FailoverPurpose::Notification(name.clone()), None) {
+                    self.connection_feedback = Some(format!("Another connect is under way, try {} again once it is done", name));
+                    self.animation_time = 0.0;
+                }
+            }
+        }
+    }
+    
+    // For profiles that ask for it, re-handshakes a degraded tunnel and reconnects it if it is
+    // still degraded once the handshake had time to happen
+    fn recover_degraded(&mut self) {
+        use crate::network::reconnect::{Recovery, RecoveryKind};
+        use crate::network::VpnStatus;
+        
+        let VpnStatus::Degraded { name, .. } = &self.network_manager.vpn_status else {
+            self.stale_recovery = None;
+            return;
+        };
+        let Some(vpn_config) = self.config.vpn_configs.iter().find(|c| &c.name == name && c.reconnect.on_stale_handshake).cloned() else {
+            return;
+        };
+        if self.recovery.is_some() {
+            return;
+        }
+        
+        let kind = match &self.stale_recovery {
+            Some((tried, at)) if *tried == vpn_config.name => {
+                if at.elapsed() < STALE_RECOVERY_WAIT {
+                    return;
+                }
+                log::warn!("{} is still degraded after re-handshaking, reconnecting", vpn_config.name);
+                self.stale_recovery = None;
+                RecoveryKind::Restart
+            }
+            _ => {
+                log::info!("Re-handshaking degraded {}", vpn_config.name);
+                self.stale_recovery = Some((vpn_config.name.clone(), std::time::Instant::now()));
+                RecoveryKind::Rehandshake
+            }
+        };
+        self.recovery = Some(Recovery::start(&self.network_manager, &vpn_config, kind));
+    }
+    
+    // A restart hands the tunnel to the reconnect loop; a failed one leaves the error
+    fn poll_recovery(&mut self) {
+        use crate::network::reconnect::RecoveryKind;
+        use crate::network::VpnStatus;
+        
+        let Some(result) = self.recovery.as_ref().and_then(|recovery| recovery.poll()) else { return };
+        let Some(recovery) = self.recovery.take() else { return };
+        self.last_vpn_refresh = std::time::Instant::now();
+        match (recovery.kind, result) {
+            (RecoveryKind::Rehandshake, Ok(_)) => {}
+            (RecoveryKind::Rehandshake, Err(e)) => log::warn!("Failed to re-handshake {}: {}", recovery.vpn, e),
+            (RecoveryKind::Restart, Ok(status)) => self.network_manager.vpn_status = status,
+            (RecoveryKind::Restart, Err(e)) => self.network_manager.vpn_status = VpnStatus::Error(e.to_string()),
+        }
+    }
+    
+    // Samples traffic for whichever VPN is connected, starting over when that changes
+    fn sync_traffic(&mut self) {
+        use crate::network::stats::TrafficSampler;
+        
+        let connected = self.network_manager.vpn_status.connected_name();
+        if self.traffic.as_ref().map(|t| t.stats.vpn.as_str()) != connected {
+            self.traffic = connected
+                .and_then(|name| self.config.vpn_configs.iter().find(|c| c.name == name))
+                .map(TrafficSampler::start);
+        }
+        if let Some(traffic) = &mut self.traffic {
+            traffic.poll();
+        }
+    }
+    
+    fn get_pending_retry(&self, device_name: &str, operation: &str) -> Option<&PendingRetry> {
+        self.pending_retries.get(&format!("{}_{}", device_name, operation))
+    }
+    
+    fn cancel_retry(&mut self, device_name: &str, operation: &str) {
+        let key = format!("{}_{}", device_name, operation);
+        if self.pending_retries.remove(&key).is_some() {
+            self.device_operations.remove(&key);
+        }
+    }
+    
+    fn get_device_operation_state(&self, device_name: &str, operation: &str) -> &DeviceOperationState {
+        let key = format!("{}_{}", device_name, operation);
+        self.device_operations.get(&key).map_or(&DeviceOperationState::Idle, |(state, _)| state)
+    }
+    
+    fn set_device_operation(&mut self, key: String, state: DeviceOperationState) {
+        self.device_operations.insert(key, (state, std::time::Instant::now()));
+    }
+    
+    // Back to Idle by hand, giving up a retry that was waiting
+    fn clear_device_operation(&mut self, device_name: &str, operation: &str) {
+        let key = format!("{}_{}", device_name, operation);
+        self.pending_retries.remove(&key);
+        self.device_operations.remove(&key);
+    }
+    
+    // Results go back to Idle once shown for a while, unless a retry is still coming, and
+    // feedback for devices that were removed or renamed is dropped
+    fn expire_device_operations(&mut self) {
+        let config = &self.config;
+        let exists = |key: &str| key.rsplit_once('_').is_some_and(|(name, _)| config.device(name).is_some());
+        self.pending_retries.retain(|key, _| exists(key));
+        let pending = &self.pending_retries;
+        self.device_operations.retain(|key, (state, since)| {
+            let ttl = match state {
+                DeviceOperationState::Success(_) => SUCCESS_STATUS_TTL,
+                DeviceOperationState::Error(_) if !pending.contains_key(key) => ERROR_STATUS_TTL,
+                _ => return exists(key),
+            };
+            exists(key) && since.elapsed() < ttl
+        });
+    }
+    
+    // When the next result shown on a device goes back to Idle
+    fn next_status_expiry(&self) -> Option<std::time::Instant> {
+        self.device_operations.iter().filter_map(|(key, (state, since))| match state {
+            DeviceOperationState::Success(_) => Some(*since + SUCCESS_STATUS_TTL),
+            DeviceOperationState::Error(_) if !self.pending_retries.contains_key(key) => Some(*since + ERROR_STATUS_TTL),
+            _ => None,
+        }).min()
+    }
+
+    fn draw_sidebar(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
+        ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
+            Spacing::md(ui);
+            
+            // Display logo if available
+            if let Some(logo_texture) = &self.logo_texture {
+                ui.horizontal(|ui| {
+                    Spacing::sm(ui);
+                    ui.add(egui::Image::new(logo_texture)
+                        .max_width(40.0)
+                        .max_height(40.0)
+                        .rounding(egui::Rounding::same(6.0)));
+                    Spacing::sm(ui);
+                    ui.vertical(|ui| {
+                        Typography::heading(ui, &self.theme, "VPN Manager");
+                        Typography::small(ui, &self.theme, "All-in-One");
+                    });
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    Spacing::sm(ui);
+                    ui.vertical(|ui| {
+                        Typography::heading(ui, &self.theme, "VPN Manager");
+                        Typography::small(ui, &self.theme, "All-in-One");
+                    });
+                });
+            }
+            
+            if let Some(vpn) = self.config.always_on_vpn().map(|vpn_config| vpn_config.name.clone()) {
+                self.draw_always_on_shield(ui, &vpn);
+            }
+            Spacing::lg(ui);
+
+            let button_size = egui::vec2(180.0, 28.0);
+            
+            // Navigation buttons with consistent styling
+            let home_selected = self.current_panel == Panel::Home;
+            if self.draw_nav_button(ui, "🏠 Home", button_size, home_selected) {
+                self.current_panel = Panel::Home;
+            }
+            Spacing::xs(ui);
+            
+            let vpn_selected = self.current_panel == Panel::Vpn;
+            if self.draw_nav_button(ui, "🔒 VPN", button_size, vpn_selected) {
+                self.current_panel = Panel::Vpn;
+            }
+            Spacing::xs(ui);
+            
+            let remote_selected = self.current_panel == Panel::Remote;
+            if self.draw_nav_button(ui, "🖥️ Remote", button_size, remote_selected) {
+                self.current_panel = Panel::Remote;
+            }
+            Spacing::xs(ui);
+            
+            let schedule_selected = self.current_panel == Panel::Schedule;
+            if self.draw_nav_button(ui, "⏰ Schedule", button_size, schedule_selected) {
+                self.current_panel = Panel::Schedule;
+            }
+            
+            if !self.problems.is_empty() || self.current_panel == Panel::Problems {
+                Spacing::xs(ui);
+                let problems_text = format!("⚠ Problems ({})", self.problems.len());
+                let problems_selected = self.current_panel == Panel::Problems;
+                if self.draw_nav_button(ui, &problems_text, button_size, problems_selected) {
+                    self.current_panel = Panel::Problems;
+                }
+            }
+            
+            Spacing::sm(ui);
+            
+            // Show update indicator on Settings button if update is available
+            let settings_text = if let Some(update) = &self.update_info {
+                if update.update_available {
+                    "⚙️ Settings 🔴"
+                } else {
+                    "⚙️ Settings"
+                }
+            } else if self.checking_updates {
+                "⚙️ Settings ⏳"
+            } else {
+                "⚙️ Settings"
+            };
+            
+            let settings_selected = self.current_panel == Panel::Settings;
+            if self.draw_nav_button(ui, settings_text, button_size, settings_selected) {
+                self.current_panel = Panel::Settings;
+            }
+        });
+    }
+    
+    // Whether the always-on profile is up, clicking opens the VPN tab
+    fn draw_always_on_shield(&mut self, ui: &mut egui::Ui, vpn: &str) {
+        use crate::network::always_on::Shield;
+        
+        let shield = Shield::of(vpn, &self.network_manager.vpn_status, self.always_on_retry);
+        let (text, color) = match shield {
+            Shield::Enforced => ("🛡 Always-on", self.theme.success),
+            Shield::Restoring => ("🛡 Always-on: restoring", self.theme.warning),
+            Shield::Substituted(_) => ("🛡 Always-on: fallback", self.theme.warning),
+            Shield::Down => ("🛡 Always-on: down", self.theme.error),
+        };
+        Spacing::sm(ui);
+        ui.horizontal(|ui| {
+            Spacing::sm(ui);
+            let label = egui::Label::new(egui::RichText::new(text).color(color).size(12.0)).sense(egui::Sense::click());
+            if ui.add(label).on_hover_text(shield.describe(vpn, self.always_on_retry)).clicked() {
+                self.current_panel = Panel::Vpn;
+            }
+        });
+    }
+    
+    fn draw_nav_button(&self, ui: &mut egui::Ui, text: &str, size: egui::Vec2, is_selected: bool) -> bool {
+        let button_color = if is_selected {
+            self.theme.primary
+        } else {
+            self.theme.surface_variant
+        };
+        
+        let text_color = if is_selected {
+            egui::Color32::WHITE
+        } else {
+            self.theme.text_primary
+        };
+        
+        let button = egui::Button::new(
+            egui::RichText::new(text).color(text_color).size(12.0)
+        )
+        .fill(button_color)
+        .stroke(egui::Stroke::new(if is_selected { 0.0 } else { 1.0 }, self.theme.border))
+        .rounding(egui::Rounding::same(4.0));
         
         ui.add_sized(size, button).clicked()
     }
 
-    fn draw_main_content(&mut self, _ctx: &egui::Context, ui: &mut egui::Ui) {
+    fn draw_main_content(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        // Focus only lasts while its panel stays open
+        if self.navigation.focused_panel().is_some_and(|panel| *panel != self.current_panel) {
+            self.navigation.clear_focus();
+        }
+        
+        if self.safe_mode {
+            self.draw_safe_mode_banner(ui);
+        }
+        
         match self.current_panel {
             Panel::Home => {
                 HomePanel::draw(ui, self);
@@ -609,39 +1825,440 @@ impl App {
                 VpnPanel::draw(ui, &mut self.config, &mut self.network_manager, 
                     &mut self.new_vpn_name, &mut self.new_vpn_config_path, 
                     &mut self.new_vpn_username, &mut self.new_vpn_password, 
-                    &mut self.new_vpn_type, &self.loading_actions, self.animation_time);
+                    &mut self.new_vpn_type, &mut self.new_vpn_env, &mut self.new_vpn_server, &mut self.new_vpn_group,
+                    &mut self.new_vpn_proxy, &mut self.new_vpn_custom, &mut self.new_vpn_prompt_otp, &mut self.editing_vpn, &mut self.new_vpn_keys, &mut self.new_vpn_draft, &mut self.new_vpn_check, &mut self.new_vpn_bundle, &mut self.command_preview,
+                    &mut self.client_log_view, &mut self.navigation, &self.loading_actions, self.animation_time,
+                    &mut self.route_editor, &mut self.leak_tests, &mut self.service_statuses, &mut self.otp_prompt, self.traffic.as_ref().map(|t| &t.stats),
+                    &mut self.migration_report, &mut self.recovery);
             }
             Panel::Remote => {
-                RemotePanel::draw(ui, &mut self.config, &mut self.network_manager,
-                    &mut self.new_rdp_name, &mut self.new_rdp_host, &mut self.new_rdp_port,
-                    &mut self.new_rdp_username, &mut self.new_rdp_password, &mut self.new_rdp_domain,
-                    &mut self.new_wol_name, &mut self.new_wol_mac, 
-                    &mut self.new_wol_ip, &mut self.new_wol_port);
+                let feedback = self.connection_feedback.clone();
+                let operation = RemotePanel::draw(ui, &mut self.config, &mut self.network_manager,
+                    &mut self.new_device, &mut self.command_preview,
+                    &mut self.client_log_view, &mut self.navigation, &mut self.bulk_edit,
+                    &mut self.duplicates_open, &mut self.notes_window, &mut self.thumbnails,
+                    &mut self.connection_feedback, &mut self.migration_report);
+                if self.connection_feedback != feedback {
+                    self.animation_time = 0.0;
+                }
+                if let Some((device_name, operation, operation_type)) = operation {
+                    self.start_device_operation(device_name, operation.to_string(), operation_type);
+                }
             }
             Panel::Settings => {
-                SettingsPanel::draw(ui, &mut self.config, &mut self.system_info, &self.package_installer, &self.app_updater, &mut self.update_info, &mut self.checking_updates, &mut self.installing_update, &mut self.update_progress);
+                SettingsPanel::draw(ui, &mut self.config, &mut self.system_info, &self.package_installer, &self.app_updater, &mut self.update_info, &mut self.checking_updates, &mut self.installing_update, &mut self.update_progress,
+                    &self.update_error, self.update_checked_at, &mut self.update_check_requested,
+                    self.checking_dependency_upgrades, &mut self.dependency_upgrade_requested, &self.dashboard_error,
+                    &mut self.dashboard_pairing, &mut self.dashboard_pair_requested, &mut self.provider_browser, &mut self.encryption_form);
+                if std::mem::take(&mut self.update_check_requested) {
+                    self.schedule_update_check();
+                }
+                if std::mem::take(&mut self.dependency_upgrade_requested) || !self.dependency_upgrades_checked {
+                    self.dependency_upgrades_checked = true;
+                    self.check_dependency_upgrades();
+                }
+                if std::mem::take(&mut self.dashboard_pair_requested) {
+                    self.start_dashboard_pairing(ctx);
+                }
+            }
+            Panel::Problems => {
+                if ProblemsPanel::draw(ui, &mut self.config, &self.problems) {
+                    self.save_config();
+                    self.relint();
+                }
+            }
+            Panel::Schedule => {
+                SchedulePanel::draw(ui, &mut self.config, &mut self.new_schedule_kind,
+                    &mut self.new_schedule_target, &mut self.new_schedule_time, &self.navigation);
+            }
+        }
+        
+        self.navigation.finish_scroll();
+        
+        // A panel linked somewhere else, e.g. a countdown badge to the schedule editor
+        if let Some(route) = self.navigation.take_request() {
+            self.navigate(route);
+        }
+    }
+    
+    pub fn navigate(&mut self, route: Route) {
+        let panel = route.panel.clone()
+            .or_else(|| route.entry.as_deref().and_then(|entry| self.panel_for_entry(entry)))
+            .unwrap_or(Panel::Home);
+        
+        match route.entry {
+            Some(entry) => {
+                if panel != Panel::Schedule && self.panel_for_entry(&entry).is_none() {
+                    log::warn!("No entry named '{}' to select", entry);
+                    self.connection_feedback = Some(format!("No entry named '{}'", entry));
+                    self.animation_time = 0.0;
+                }
+                self.navigation.focus(panel.clone(), entry);
             }
+            None => self.navigation.clear_focus(),
+        }
+        self.current_panel = panel;
+    }
+    
+    fn panel_for_entry(&self, entry: &str) -> Option<Panel> {
+        if self.config.vpn_configs.iter().any(|v| v.name == entry) {
+            Some(Panel::Vpn)
+        } else if self.config.device(entry).is_some() {
+            Some(Panel::Remote)
+        } else {
+            None
         }
     }
 
+    // How long the app can sit idle before the next frame is needed: immediately-ish while
+    // something animates or a background result is pending, otherwise when the next timer fires.
+    // Input events repaint on their own.
+    fn next_repaint(&self) -> std::time::Duration {
+        use crate::network::VpnStatus;
+        use std::time::{Duration, Instant};
+        
+        let animating = matches!(self.network_manager.vpn_status, VpnStatus::Connecting | VpnStatus::Reconnecting { .. })
+            || self.is_connecting
+            || self.checking_updates
+            || self.installing_update
+            || !self.loading_actions.is_empty()
+            || self.device_operations.values().any(|(state, _)| matches!(state, DeviceOperationState::Loading));
+        let awaiting = self.update_check_receiver.is_some()
+            || self.dependency_upgrade_receiver.is_some()
+            || self.reconnector.is_some()
+            || self.startup.is_some()
+            || self.recovery.is_some();
+        if animating || awaiting {
+            return ACTIVE_REPAINT_INTERVAL;
+        }
+        
+        let now = Instant::now();
+        let until = |last: Instant, interval: Duration| (last + interval).saturating_duration_since(now);
+        let mut next = Duration::from_secs(60);
+        
+        if !self.config.vpn_configs.is_empty() {
+            next = next.min(until(self.last_vpn_refresh, VPN_REFRESH_INTERVAL));
+        }
+        if let Some(at) = self.always_on_retry {
+            next = next.min(at.saturating_duration_since(now));
+        }
+        if !self.network_manager.devices.is_empty() {
+            next = next.min(until(self.last_quick_device_check, QUICK_DEVICE_CHECK_INTERVAL));
+            next = next.min(until(self.last_full_device_check, FULL_DEVICE_CHECK_INTERVAL));
+        }
+        if self.connection_feedback.is_some() {
+            next = next.min(Duration::from_secs_f32((self.feedback_secs() - self.animation_time).max(0.0)));
+        }
+        if self.update_notification.is_some() {
+            next = next.min(until(self.last_update_check, Duration::from_secs(10)));
+        }
+        if let Some(at) = self.update_offline_at {
+            next = next.min(until(at, OFFLINE_UPDATE_RETRY));
+        }
+        if let Some(expiry) = self.next_status_expiry() {
+            next = next.min(expiry.saturating_duration_since(now));
+        }
+        // Retry countdowns tick every second, as does the traffic graph, and a public IP lookup
+        // is picked up when done; the web dashboard only sees state and delivers commands on frames
+        if !self.pending_retries.is_empty() || self.traffic.is_some() || self.dashboard.is_some() || self.public_ip.is_checking() {
+            next = next.min(Duration::from_secs(1));
+        }
+        let wall_now = chrono::Local::now();
+        if let Some(run) = self.config.scheduled_actions.iter().filter_map(|action| action.next_run(wall_now)).min() {
+            next = next.min((run - wall_now).to_std().unwrap_or_default());
+        }
+        
+        // Never a tight loop, even when a timer is already overdue
+        next.max(ACTIVE_REPAINT_INTERVAL)
+    }
+    
+    fn draw_safe_mode_banner(&mut self, ui: &mut egui::Ui) {
+        let mut resume = false;
+        egui::Frame::none()
+            .fill(self.theme.warning.linear_multiply(0.15))
+            .rounding(egui::Rounding::same(6.0))
+            .inner_margin(egui::Margin::same(8.0))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("⚠ Safe mode").strong().color(self.theme.warning));
+                    ui.label("Auto-connect, status checks, scheduled actions and update checks are off.");
+                    if ui.button("Resume Checks").on_hover_text("Turn status checks, scheduled actions and update checks back on; nothing is connected").clicked() {
+                        resume = true;
+                    }
+                });
+            });
+        ui.add_space(8.0);
+        
+        if resume {
+            log::info!("Leaving safe mode");
+            self.safe_mode = false;
+            let now = std::time::Instant::now();
+            self.last_vpn_refresh = now.checked_sub(VPN_REFRESH_INTERVAL).unwrap_or(now);
+            // Whatever came due meanwhile is skipped rather than run late
+            self.last_schedule_check = chrono::Local::now();
+        }
+    }
+    
+    fn draw_migration_report(&mut self, ctx: &egui::Context) {
+        let Some(report) = &self.migration_report else { return };
+        let dangling = crate::config::migrate::dangling(&self.config);
+        let mut open = true;
+        let mut remove = false;
+        let mut close = false;
+        
+        egui::Window::new("Migration Report")
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .open(&mut open)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                for (old, new) in &report.renamed {
+                    ui.label(format!("{} was renamed to {}", old, new));
+                }
+                if !report.moved.is_empty() {
+                    ui.add_space(4.0);
+                    ui.label(egui::RichText::new("Carried over").strong());
+                    for line in &report.moved {
+                        ui.label(egui::RichText::new(format!("✔ {}", line)).color(self.theme.success));
+                    }
+                }
+                ui.add_space(4.0);
+                ui.label(egui::RichText::new("Couldn't be carried over").strong());
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for line in &report.unmapped {
+                        ui.label(egui::RichText::new(format!("⚠ {}", line)).color(self.theme.warning));
+                    }
+                });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if !dangling.is_empty() && ui.button("Remove References")
+                        .on_hover_text("Delete the scheduled actions, failover places, always-on setting and mutes that point at no entry")
+                        .clicked()
+                    {
+                        remove = true;
+                    }
+                    if ui.button("Keep").on_hover_text("Leave them; rename an entry to match to reattach them").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        
+        if remove {
+            crate::config::migrate::remove_dangling(&mut self.config);
+            self.save_config();
+        }
+        if remove || close || !open {
+            self.migration_report = None;
+        }
+    }
+    
     fn save_config(&mut self) {
         if let Err(e) = self.config.save() {
-            self.error_message = Some(format!("Failed to save config: {}", e));
+            self.error_message = Some((format!("Failed to save config: {}", e), crate::errors::code_of(&e)));
+        }
+    }
+    
+    // Keeps the session file in step with the connected VPN, waiting wakes and open tab
+    fn sync_session(&mut self) {
+        use crate::network::VpnStatus;
+        
+        // Not while the status is unknown or the previous session is still on offer
+        if self.safe_mode || self.startup.is_some() || self.previous_session.is_some() {
+            return;
+        }
+        let vpn = match &self.network_manager.vpn_status {
+            VpnStatus::Checking | VpnStatus::Connecting => return,
+            VpnStatus::Reconnecting { name, .. } => Some(name.clone()),
+            status => status.connected_name().map(|name| name.to_string()),
+        };
+        let mut pending_wakes: Vec<String> = self.device_operations
+            .iter()
+            .filter(|(_, (state, _))| matches!(state, DeviceOperationState::Loading))
+            .filter_map(|(key, _)| key.strip_suffix("_wake").map(|name| name.to_string()))
+            .chain(self.pending_retries.values().filter_map(|retry| match &retry.operation {
+                DeviceOperationType::Wake(device) => Some(device.name.clone()),
+                _ => None,
+            }))
+            .collect();
+        pending_wakes.sort();
+        pending_wakes.dedup();
+        
+        let state = crate::system::recovery::SessionState { vpn, pending_wakes, panel: self.current_panel.name().to_string() };
+        if self.session_saved.as_ref() == Some(&state) {
+            return;
+        }
+        if let Err(e) = crate::system::recovery::save(&state) {
+            log::warn!("Could not save the session: {}", e);
+        }
+        self.session_saved = Some(state);
+    }
+    
+    // Reattaches or reconnects the VPN, wakes the devices again and opens the tab of the run that
+    // crashed
+    fn restore_session(&mut self, state: crate::system::recovery::SessionState) {
+        let mut restored = Vec::new();
+        if let Some(panel) = Panel::from_name(&state.panel) {
+            self.current_panel = panel;
+        }
+        
+        if let Some(name) = state.vpn.filter(|name| self.config.vpn_configs.iter().any(|c| &c.name == name)) {
+            if self.network_manager.vpn_status.connected_name() == Some(name.as_str()) {
+                // The tunnel outlived the app and the startup check picked it up again
+                log::info!("{} is still connected, reattached", name);
+                restored.push(format!("{} still connected", name));
+            } else if !self.ask_for_code(&name) {
+                log::info!("Restoring the connection to {}", name);
+                if self.connect_with_failover(&name, None, FailoverPurpose::Restore(name.clone()), None) {
+                    restored.push(format!("reconnecting {}", name));
+                }
+            }
+        }
+        
+        for name in state.pending_wakes {
+            let device = self.config.wol_devices().find(|d| d.name == name).cloned();
+            if let Some(device) = device {
+                log::info!("Restoring the wake of {}", name);
+                restored.push(format!("waking {}", name));
+                self.start_device_operation(name, "wake".to_string(), DeviceOperationType::Wake(device));
+            }
+        }
+        
+        self.connection_feedback = Some(if restored.is_empty() {
+            "Previous session restored".to_string()
+        } else {
+            format!("Previous session restored: {}", restored.join(", "))
+        });
+        self.animation_time = 0.0;
+    }
+    
+    fn draw_session_restore(&mut self, ctx: &egui::Context) {
+        let Some((state, saved_at)) = &self.previous_session else { return };
+        let checking = matches!(self.network_manager.vpn_status, crate::network::VpnStatus::Checking);
+        let mut restore = false;
+        let mut dismiss = false;
+        
+        egui::Window::new("Restore Previous Session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                let when = saved_at.map(|at| format!(" (last seen {})", at.format("%Y-%m-%d %H:%M"))).unwrap_or_default();
+                ui.label(format!("VPN Manager didn't shut down cleanly last time{}.", when));
+                ui.add_space(4.0);
+                ui.label("Restoring will:");
+                if let Some(vpn) = &state.vpn {
+                    let line = match self.network_manager.vpn_status.connected_name() {
+                        Some(connected) if connected == vpn => format!("• keep {}, which is still connected", vpn),
+                        _ => format!("• connect {} again", vpn),
+                    };
+                    ui.label(line);
+                }
+                if !state.pending_wakes.is_empty() {
+                    ui.label(format!("• wake {} again", state.pending_wakes.join(", ")));
+                }
+                if let Some(panel) = Panel::from_name(&state.panel).filter(|panel| *panel != Panel::Home) {
+                    ui.label(format!("• open the {} tab", panel.name()));
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    restore = ui.add_enabled(!checking, egui::Button::new("Restore"))
+                        .on_disabled_hover_text("Waiting for the VPN status check")
+                        .clicked();
+                    dismiss = ui.button("Dismiss").clicked();
+                    if checking {
+                        ui.spinner();
+                    }
+                });
+            });
+        
+        if restore {
+            if let Some((state, _)) = self.previous_session.take() {
+                self.restore_session(state);
+            }
+        } else if dismiss {
+            self.previous_session = None;
+        }
+    }
+    
+    // Asks for the master password of an encrypted config before the app does anything else
+    fn draw_unlock(&mut self, ctx: &egui::Context) {
+        let mut unlocked = false;
+        let mut reset = false;
+        
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 4.0);
+                ui.heading("🔐 Unlock Configuration");
+                ui.add_space(8.0);
+                ui.label("The passwords and keys in config.json are encrypted. Enter the master password to continue.");
+                ui.add_space(12.0);
+                
+                let response = ui.add(egui::TextEdit::singleline(&mut self.encryption_form.current)
+                    .password(true)
+                    .hint_text("Master password")
+                    .desired_width(260.0));
+                response.request_focus();
+                let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.add_space(8.0);
+                if ui.button("Unlock").clicked() || submitted {
+                    match crate::config::secrets::unlock(&mut self.config, &self.encryption_form.current) {
+                        Ok(()) => unlocked = true,
+                        Err(e) => self.encryption_form.error = Some(e.to_string()),
+                    }
+                    self.encryption_form.current.clear();
+                }
+                if let Some(error) = &self.encryption_form.error {
+                    ui.label(egui::RichText::new(error).color(self.theme.error));
+                }
+                
+                ui.add_space(24.0);
+                ui.collapsing("Forgot the password?", |ui| {
+                    ui.label("It can't be recovered. Resetting deletes the encrypted passwords and keys and turns encryption off; \
+                        the connections and devices stay, and their passwords have to be entered again.");
+                    if ui.button(egui::RichText::new("Reset Encrypted Fields").color(self.theme.error)).clicked() {
+                        reset = true;
+                    }
+                });
+            });
+        });
+        
+        if reset {
+            let cleared = crate::config::secrets::reset(&mut self.config);
+            self.save_config();
+            self.connection_feedback = Some(format!("Cleared {} encrypted passwords and keys, enter them again where needed", cleared));
+            self.animation_time = 0.0;
+            unlocked = true;
+        }
+        if unlocked {
+            self.encryption_form = Default::default();
+            self.relint();
+            if !self.safe_mode {
+                self.start_startup_checks();
+            }
         }
     }
 }
 
 impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint_after(std::time::Duration::from_millis(100));
-
-        // Update animation time
-        self.animation_time += 0.016; // ~60 FPS
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        let update_started = std::time::Instant::now();
+        
+        // Frames only come on input or when requested below, so advance by the real time between them
+        self.animation_time += ctx.input(|i| i.unstable_dt);
+        
+        // Nothing runs on passwords that are still encrypted
+        if self.config.locked {
+            self.draw_unlock(ctx);
+            return;
+        }
         
         // Clear feedback after 3 seconds
         if let Some(_) = &self.connection_feedback {
-            if self.animation_time > 3.0 {
+            if self.animation_time > self.feedback_secs() {
                 self.connection_feedback = None;
+                self.feedback_action = None;
+                self.feedback_code = None;
                 self.animation_time = 0.0;
             }
         }
@@ -655,29 +2272,94 @@ impl eframe::App for App {
         
         // Poll update check results
         self.poll_update_check();
+        self.poll_dependency_upgrades();
+        self.sync_dashboard();
         
         // Poll device operation results
         self.poll_device_operations();
+        self.expire_device_operations();
+        self.run_due_retries();
+        self.poll_startup();
+        self.poll_failover();
+        self.poll_recovery();
+        if !self.safe_mode {
+            self.run_scheduled_actions();
+        }
+        
+        // Update checks go through the configured proxy and source
+        if self.app_updater.proxy().unwrap_or("") != self.config.update_proxy.trim() {
+            self.app_updater.set_proxy(&self.config.update_proxy);
+        }
+        if *self.app_updater.source() != self.config.update_source {
+            self.app_updater.set_source(self.config.update_source.clone());
+        }
+        if crate::network::wg_userspace::backend() != self.config.wireguard_backend {
+            crate::network::wg_userspace::set_backend(self.config.wireguard_backend);
+        }
+        if crate::network::nmcli::is_enabled() != self.config.use_network_manager {
+            crate::network::nmcli::set_enabled(self.config.use_network_manager);
+        }
+        if crate::errors::language() != self.config.language {
+            crate::errors::set_language(self.config.language);
+        }
         
         // Check for updates periodically (every 24 hours)
-        if self.last_update_check.elapsed().as_secs() > 86400 && !self.checking_updates {
+        if self.last_update_check.elapsed().as_secs() > 86400 && !self.checking_updates && !self.safe_mode {
             self.schedule_update_check();
             self.last_update_check = std::time::Instant::now();
         }
+        // A check skipped while offline runs once the network answers again
+        if self.update_offline_at.is_some_and(|at| at.elapsed() >= OFFLINE_UPDATE_RETRY) && !self.checking_updates {
+            self.schedule_update_check();
+        }
 
         // Refresh VPN status periodically (every 10 seconds)
-        if self.animation_time.rem_euclid(10.0) < 0.1 && !self.config.vpn_configs.is_empty() {
+        if self.last_vpn_refresh.elapsed() >= VPN_REFRESH_INTERVAL && !self.config.vpn_configs.is_empty() && !self.safe_mode && self.startup.is_none() && self.failover.is_none() && self.recovery.is_none() {
+            self.last_vpn_refresh = std::time::Instant::now();
+            let was_connected = self.network_manager.vpn_status.connected_name().map(str::to_string);
             let runtime = tokio::runtime::Runtime::new().unwrap();
             let _ = runtime.block_on(async {
                 self.network_manager.refresh_vpn_status(&self.config.vpn_configs).await
             });
+            if let Some(name) = was_connected {
+                if self.network_manager.vpn_status.connected_name().is_none() {
+                    self.vpn_lost(&name);
+                }
+            }
+        }
+        if !self.safe_mode {
+            self.recover_degraded();
+            self.poll_reconnect();
+            self.enforce_always_on();
+            self.sync_traffic();
+            self.public_ip.sync(self.network_manager.vpn_status.connected_name());
+            self.public_ip.poll();
         }
+        self.sync_session();
         
-        // Sync WoL devices with config changes
-        self.network_manager.sync_wol_devices(&self.config.wol_devices);
+        // Follow the theme, status palette and schedule settings without a restart
+        let dark_mode = self.config.effective_dark_mode();
+        if dark_mode != Theme::is_dark()
+            || self.config.status_palette != Theme::status_palette()
+            || self.config.status_shapes != Theme::status_shapes()
+        {
+            Theme::set_dark(dark_mode);
+            Theme::set_status_palette(self.config.status_palette, self.config.status_shapes);
+            self.theme = Theme::current();
+            self.apply_style(ctx);
+        }
+        
+        // Re-validate the config so edits and fixed files are reflected
+        if self.last_lint.elapsed().as_secs() >= 2 {
+            self.relint();
+        }
+        
+        // Sync monitored devices with config changes
+        self.network_manager.sync_devices(&self.config.devices);
         
         // Quick update device statuses more frequently (every 10 seconds)
-        if self.animation_time.rem_euclid(10.0) < 0.1 && !self.config.wol_devices.is_empty() {
+        if self.last_quick_device_check.elapsed() >= QUICK_DEVICE_CHECK_INTERVAL && !self.network_manager.devices.is_empty() && !self.safe_mode {
+            self.last_quick_device_check = std::time::Instant::now();
             let runtime = tokio::runtime::Runtime::new().unwrap();
             let _ = runtime.block_on(async {
                 self.network_manager.quick_update_device_statuses().await
@@ -685,12 +2367,19 @@ impl eframe::App for App {
         }
         
         // Full device status update less frequently (every 60 seconds)
-        if self.animation_time.rem_euclid(60.0) < 0.1 && !self.config.wol_devices.is_empty() {
+        if self.last_full_device_check.elapsed() >= FULL_DEVICE_CHECK_INTERVAL && !self.network_manager.devices.is_empty() && !self.safe_mode {
+            self.last_full_device_check = std::time::Instant::now();
             let runtime = tokio::runtime::Runtime::new().unwrap();
             let _ = runtime.block_on(async {
                 self.network_manager.update_device_statuses().await
             });
         }
+        
+        self.status_notifier.observe_devices(&self.config, &self.network_manager.devices);
+        self.status_notifier.observe_vpn(&self.config, self.network_manager.vpn_status.connected_name());
+        while let Some(action) = self.status_notifier.poll_action() {
+            self.run_notification_action(ctx, action);
+        }
 
         // Removed automatic device status updates to prevent CMD spawning issues
         // Status updates will be manual or triggered by user actions only
@@ -704,17 +2393,20 @@ impl eframe::App for App {
                 self.draw_sidebar(ctx, ui);
             });
 
+        let panel_started = std::time::Instant::now();
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.style_mut().visuals.panel_fill = self.theme.background;
             self.draw_main_content(ctx, ui);
         });
+        let panel_time = panel_started.elapsed();
 
-        if let Some(error) = &self.error_message.clone() {
+        if let Some((error, code)) = self.error_message.clone() {
             egui::Window::new("Error")
                 .collapsible(false)
                 .resizable(false)
                 .show(ctx, |ui| {
                     ui.label(error);
+                    components::error_help(ui, code);
                     if ui.button("OK").clicked() {
                         self.error_message = None;
                     }
@@ -723,6 +2415,7 @@ impl eframe::App for App {
 
         // Show feedback notifications
         if let Some(feedback) = &self.connection_feedback {
+            let mut clicked = None;
             egui::Window::new("Status")
                 .collapsible(false)
                 .resizable(false)
@@ -731,8 +2424,143 @@ impl eframe::App for App {
                     ui.horizontal(|ui| {
                         ui.label("ℹ️");
                         ui.label(feedback);
+                        components::error_help(ui, self.current_feedback_code());
+                        if let Some(action) = self.current_feedback_action() {
+                            if ui.button(action.label()).clicked() {
+                                clicked = Some(action.clone());
+                            }
+                        }
                     });
                 });
+            if let Some(action) = clicked {
+                self.run_notification_action(ctx, action);
+            }
+        }
+        
+        // Show the exact invocation for an action without running it
+        if let Some(preview) = self.command_preview.clone() {
+            let mut open = true;
+            
+            egui::Window::new(format!("Command: {}", preview.title))
+                .collapsible(false)
+                .resizable(true)
+                .open(&mut open)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label(egui::RichText::new("Dry run, nothing was executed. Secrets are redacted.").color(self.theme.text_secondary));
+                    ui.add_space(8.0);
+                    for line in &preview.lines {
+                        ui.code(line);
+                    }
+                    for note in &preview.notes {
+                        ui.label(egui::RichText::new(note).color(self.theme.text_secondary));
+                    }
+                    ui.add_space(8.0);
+                    if ui.button("Copy").clicked() {
+                        ui.output_mut(|o| o.copied_text = preview.text());
+                    }
+                });
+            
+            if !open {
+                self.command_preview = None;
+            }
+        }
+        
+        if self.migration_report.is_some() {
+            self.draw_migration_report(ctx);
+        }
+        self.draw_session_restore(ctx);
+        
+        if let Some((name, code)) = VpnPanel::draw_otp_prompt(ctx, &mut self.otp_prompt) {
+            self.connect_with_code(&name, &code);
+        }
+        
+        // Tools that may be popped out into windows of their own, drawn whatever the tab
+        RemotePanel::draw_notes_window(ctx, &mut self.config, &mut self.notes_window);
+        VpnPanel::draw_log_window(ctx, &self.config);
+        let connected = self.network_manager.vpn_status.connected_name()
+            .and_then(|name| self.config.vpn_configs.iter().find(|c| c.name == name));
+        VpnPanel::draw_routes_window(ctx, &mut self.route_table, connected);
+        
+        // Output captured from the VPN/RDP client for one entry
+        if let Some((kind, name)) = self.client_log_view.clone() {
+            use crate::system::client_log;
+            
+            let open = components::ToolWindow::show(ctx, "client_log", &format!("Client Log: {}", name), egui::vec2(600.0, 400.0), |ui| {
+                let content = client_log::read(&kind, &name);
+                ui.horizontal(|ui| {
+                    if ui.button("Open Folder").clicked() {
+                        crate::system::paths::open(&client_log::logs_dir());
+                    }
+                    if ui.button("Clear").clicked() {
+                        if let Err(e) = client_log::clear(&kind, &name) {
+                            self.error_message = Some((format!("Failed to clear log: {}", e), crate::errors::code_of(&e)));
+                        }
+                    }
+                    ui.label(egui::RichText::new(client_log::log_path(&kind, &name).display().to_string())
+                        .color(self.theme.text_secondary).small());
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+                    match content {
+                        Some(text) => {
+                            ui.add(egui::Label::new(egui::RichText::new(text).monospace()).wrap());
+                        }
+                        None => {
+                            ui.label(egui::RichText::new("No output captured yet").color(self.theme.text_secondary));
+                        }
+                    }
+                });
+            });
+            
+            if !open {
+                self.client_log_view = None;
+            }
+        }
+        
+        // Ask the user to sign in to the captive portal before connecting
+        if let Some(portal_url) = self.captive_portal_url.clone() {
+            let mut open_login = false;
+            let mut check_again = false;
+            let mut connect_anyway = false;
+            let mut skip = false;
+            
+            egui::Window::new("Network Login Required")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.label("This network requires you to sign in before the VPN can connect.");
+                    ui.label(egui::RichText::new(&portal_url).color(self.theme.text_secondary));
+                    ui.add_space(8.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Open Login Page").clicked() {
+                            open_login = true;
+                        }
+                        if ui.button("Check Again").clicked() {
+                            check_again = true;
+                        }
+                        if ui.button("Connect Anyway").clicked() {
+                            connect_anyway = true;
+                        }
+                        if ui.button("Skip").clicked() {
+                            skip = true;
+                        }
+                    });
+                });
+            
+            if open_login {
+                crate::system::open_url(&portal_url);
+            }
+            if check_again {
+                self.retry_auto_connect(true);
+            }
+            if connect_anyway {
+                self.retry_auto_connect(false);
+            }
+            if skip {
+                self.captive_portal_url = None;
+            }
         }
         
         // Show update notifications
@@ -765,9 +2593,20 @@ impl eframe::App for App {
                 self.update_notification = None;
             }
         }
+        
+        devtools::apply(ctx, &self.config.developer);
+        devtools::draw(ctx, &self.theme, &mut self.config.developer, &mut self.frame_stats, &self.current_panel);
+        if self.config.developer.unlocked {
+            self.frame_stats.record(&self.current_panel, update_started.elapsed(), panel_time, frame.info().cpu_usage);
+        }
+        
+        ctx.request_repaint_after(self.next_repaint());
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
         self.save_config();
+        if !self.safe_mode {
+            crate::system::recovery::clear();
+        }
     }
 }
\ No newline at end of file
@@ -1,20 +1,36 @@
 use eframe::egui;
 use crate::config::Config;
-use crate::network::{NetworkManager, VpnStatus};
-use crate::ui::components::{StatusIndicator, ModernCard, Spacing, Typography};
-use crate::ui::theme::{Theme, DeviceType, ActionType};
+use crate::config::schedule::ScheduledActionKind;
+use crate::network::{otp, NetworkManager, VpnStatus};
+use crate::network::publicip::PublicIpWatch;
+use crate::network::stats::{self, TrafficStats};
+use crate::system::executor;
+use crate::ui::panels::vpn::OtpPrompt;
+use crate::ui::components::{entry_accent, entry_icon, StatusIndicator, StatusDot, ModernCard, MaintenanceBadge, ScheduleBadge, Spacing, TrafficGraph, Typography, VirtualList};
+use crate::ui::theme::{Theme, DeviceType, ActionType, StatusState};
+
+// Counters standing still this long while connected get a warning next to the graph
+const IDLE_WARNING: std::time::Duration = std::time::Duration::from_secs(30);
 
 #[derive(Clone, Copy)]
-enum WolAction {
+enum DeviceAction {
     Wake,
+    Connect,
+    Ssh,
+    Vnc,
     Ping,
+    // Operation whose retry was cancelled
+    CancelRetry(&'static str),
+    // Result of the operation dismissed
+    ClearStatus(&'static str),
+    OpenSchedule,
 }
 
 pub struct HomePanel;
 
 impl HomePanel {
     pub fn draw(ui: &mut egui::Ui, app: &mut crate::ui::App) {
-        let theme = Theme::new();
+        let theme = Theme::current();
         
         // Modern header with improved typography
         ui.vertical(|ui| {
@@ -25,28 +41,42 @@ impl HomePanel {
         Spacing::lg(ui);
         
         // VPN Status Overview
-        Self::draw_vpn_overview(ui, &theme, &app.config, &mut app.network_manager);
+        Self::draw_vpn_overview(ui, &theme, &app.config, &mut app.network_manager, &mut app.public_ip, &mut app.otp_prompt,
+                                app.traffic.as_ref().map(|t| &t.stats));
         Spacing::md(ui);
         
         // Remote Devices Grid with improved layout
         Self::draw_remote_devices(ui, &theme, app);
     }
     
-    fn draw_vpn_overview(ui: &mut egui::Ui, theme: &Theme, config: &Config, network_manager: &mut NetworkManager) {
+    fn draw_vpn_overview(ui: &mut egui::Ui, theme: &Theme, config: &Config, network_manager: &mut NetworkManager, public_ip: &mut PublicIpWatch,
+                         otp_prompt: &mut Option<OtpPrompt>, traffic: Option<&TrafficStats>) {
         ModernCard::show(ui, theme, "VPN Status", |ui| {
             ui.horizontal(|ui| {
                 // VPN Status with modern indicator
                 match &network_manager.vpn_status {
+                    VpnStatus::Checking => {
+                        StatusIndicator::show_with_animation(ui, theme, false, "Checking VPN status...", true, ui.input(|i| i.time) as f32);
+                    }
                     VpnStatus::Disconnected => {
                         StatusIndicator::show(ui, theme, false, "No VPN Connection");
                     }
                     VpnStatus::Connecting => {
-                        StatusIndicator::show(ui, theme, false, "Connecting...");
+                        StatusIndicator::show_with_animation(ui, theme, false, "Connecting...", true, ui.input(|i| i.time) as f32);
                     }
                     VpnStatus::Connected(name) => {
                         StatusIndicator::show(ui, theme, true, &format!("Connected to {}", name));
                     }
+                    VpnStatus::Reconnecting { name, attempt, max_attempts } => {
+                        let text = crate::network::reconnect::status_text(name, *attempt, *max_attempts);
+                        StatusIndicator::show_with_animation(ui, theme, false, &text, true, ui.input(|i| i.time) as f32);
+                    }
+                    VpnStatus::Degraded { name, handshake_secs } => {
+                        StatusDot::show(ui, theme, StatusState::Connecting, 12.0);
+                        ui.label(egui::RichText::new(crate::network::reconnect::degraded_text(name, *handshake_secs)).color(theme.warning));
+                    }
                     VpnStatus::Error(err) => {
+                        StatusDot::show(ui, theme, StatusState::Error, 12.0);
                         ui.label(egui::RichText::new(format!("VPN Error: {}", err)).color(theme.error));
                     }
                 }
@@ -56,6 +86,14 @@ impl HomePanel {
                 });
             });
             
+            if let Some(traffic) = traffic.filter(|t| network_manager.vpn_status.connected_name() == Some(t.vpn.as_str())) {
+                Spacing::sm(ui);
+                Self::draw_traffic(ui, theme, traffic);
+            }
+            
+            Spacing::sm(ui);
+            Self::draw_public_ip(ui, theme, public_ip);
+            
             // VPN Connection Controls
             if !config.vpn_configs.is_empty() {
                 Spacing::md(ui);
@@ -69,18 +107,26 @@ impl HomePanel {
                     // VPN selector
                     let mut selected_vpn = None;
                     for (index, vpn_config) in config.vpn_configs.iter().enumerate() {
-                        if ui.selectable_label(false, &vpn_config.name).clicked() {
+                        let text = if vpn_config.style.icon.is_empty() {
+                            vpn_config.name.clone()
+                        } else {
+                            format!("{} {}", vpn_config.style.icon, vpn_config.name)
+                        };
+                        let label = egui::RichText::new(text).color(entry_accent(&vpn_config.style).unwrap_or(theme.text_primary));
+                        if ui.selectable_label(false, label).clicked() {
                             selected_vpn = Some(index);
                         }
                     }
                     
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         // Connect/Disconnect button
-                        let is_connected = matches!(&network_manager.vpn_status, VpnStatus::Connected(_));
-                        let is_connecting = matches!(&network_manager.vpn_status, VpnStatus::Connecting);
+                        let is_connected = network_manager.vpn_status.connected_name().is_some();
+                        let is_connecting = matches!(&network_manager.vpn_status, VpnStatus::Checking | VpnStatus::Connecting | VpnStatus::Reconnecting { .. });
+                        // Another connect or disconnect of the entry, e.g. the reconnect loop's
+                        let busy = config.vpn_configs.first().is_some_and(|c| executor::is_busy(&c.name));
                         
                         if is_connected {
-                            if ui.add(egui::Button::new("Disconnect")
+                            if ui.add_enabled(!busy, egui::Button::new("Disconnect")
                                 .fill(theme.error)
                                 .rounding(egui::Rounding::same(6.0))
                                 .min_size(egui::vec2(80.0, 32.0))).clicked() {
@@ -92,18 +138,26 @@ impl HomePanel {
                                 }
                             }
                         } else if !config.vpn_configs.is_empty() {
-                            let button_text = if is_connecting { "Connecting..." } else { "Connect" };
-                            let button_enabled = !is_connecting;
+                            let button_text = match &network_manager.vpn_status {
+                                VpnStatus::Checking => "Checking...",
+                                _ if is_connecting => "Connecting...",
+                                _ => "Connect",
+                            };
+                            let button_enabled = !is_connecting && !busy;
                             
                             if ui.add_enabled(button_enabled, egui::Button::new(button_text)
                                 .fill(theme.primary)
                                 .rounding(egui::Rounding::same(6.0))
                                 .min_size(egui::vec2(80.0, 32.0))).clicked() {
                                 if let Some(vpn_config) = config.vpn_configs.first() {
-                                    let runtime = tokio::runtime::Runtime::new().unwrap();
-                                    runtime.block_on(async {
-                                        let _ = network_manager.connect_vpn(vpn_config).await;
-                                    });
+                                    if otp::needs_code(vpn_config) {
+                                        *otp_prompt = Some(OtpPrompt::new(vpn_config));
+                                    } else {
+                                        let runtime = tokio::runtime::Runtime::new().unwrap();
+                                        runtime.block_on(async {
+                                            let _ = network_manager.connect_first(&config.failover_chain(&vpn_config.name)).await;
+                                        });
+                                    }
                                 }
                             }
                         }
@@ -111,10 +165,14 @@ impl HomePanel {
                     
                     if let Some(selected_index) = selected_vpn {
                         if let Some(vpn_config) = config.vpn_configs.get(selected_index) {
-                            let runtime = tokio::runtime::Runtime::new().unwrap();
-                            runtime.block_on(async {
-                                let _ = network_manager.connect_vpn(vpn_config).await;
-                            });
+                            if otp::needs_code(vpn_config) {
+                                *otp_prompt = Some(OtpPrompt::new(vpn_config));
+                            } else {
+                                let runtime = tokio::runtime::Runtime::new().unwrap();
+                                runtime.block_on(async {
+                                    let _ = network_manager.connect_first(&config.failover_chain(&vpn_config.name)).await;
+                                });
+                            }
                         }
                     }
                 });
@@ -122,15 +180,69 @@ impl HomePanel {
         });
     }
     
+    // Throughput of the tunnel over the last two minutes, sampled by the app's TrafficSampler;
+    // a flat line while connected means nothing goes through it
+    fn draw_traffic(ui: &mut egui::Ui, theme: &Theme, traffic: &TrafficStats) {
+        if let Some(error) = &traffic.error {
+            Typography::small(ui, theme, &format!("Traffic counters unavailable: {}", error));
+            return;
+        }
+        ui.horizontal(|ui| {
+            let (down, up) = traffic.throughput().unwrap_or((0.0, 0.0));
+            ui.label(egui::RichText::new(format!("↓ {}/s", stats::format_bytes(down))).color(theme.primary));
+            ui.label(egui::RichText::new(format!("↑ {}/s", stats::format_bytes(up))).color(theme.accent));
+            let idle = traffic.idle_for();
+            if idle >= IDLE_WARNING {
+                ui.label(egui::RichText::new(format!("⚠ No traffic for {} s", idle.as_secs())).color(theme.warning))
+                    .on_hover_text("The tunnel's byte counters haven't moved. It may be up without carrying anything; see the VPN tab's health check.");
+            }
+        });
+        TrafficGraph::show(ui, theme, &traffic.rates(), 40.0);
+    }
+    
+    // Where traffic leaves for the internet, with the address seen before the last connect or
+    // disconnect; the same address on both sides means the tunnel isn't carrying it
+    fn draw_public_ip(ui: &mut egui::Ui, theme: &Theme, public_ip: &mut PublicIpWatch) {
+        ui.horizontal(|ui| {
+            Typography::small(ui, theme, "🌐 Public IP:");
+            match &public_ip.current {
+                Some(Ok(found)) => {
+                    ui.label(egui::RichText::new(found.describe()).color(theme.text_primary))
+                        .on_hover_text(format!("Checked {}", found.checked_at.format("%H:%M:%S")));
+                }
+                Some(Err(e)) => {
+                    ui.label(egui::RichText::new("unknown").color(theme.warning)).on_hover_text(e);
+                }
+                None => Typography::small(ui, theme, "checking..."),
+            }
+            if let Some(before) = &public_ip.before {
+                let change = if public_ip.vpn().is_some() { "connecting" } else { "disconnecting" };
+                Typography::small(ui, theme, &format!("was {} before {}", before.describe(), change));
+                let unchanged = matches!(&public_ip.current, Some(Ok(found)) if found.ip == before.ip);
+                if unchanged && public_ip.vpn().is_some() && !public_ip.is_checking() {
+                    ui.label(egui::RichText::new("⚠ unchanged, traffic may not go through the VPN").color(theme.warning));
+                }
+            }
+            
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if public_ip.is_checking() {
+                    ui.spinner();
+                } else if ui.small_button("⟳").on_hover_text("Look up the public IP again").clicked() {
+                    public_ip.refresh();
+                }
+            });
+        });
+    }
+    
     fn draw_remote_devices(ui: &mut egui::Ui, theme: &Theme, app: &mut crate::ui::App) {
         ModernCard::show(ui, theme, "Remote Devices", |ui| {
-            if app.config.rdp_configs.is_empty() && app.config.wol_devices.is_empty() {
+            if app.config.devices.is_empty() {
                 ui.vertical_centered(|ui| {
                     Spacing::lg(ui);
                     ui.label(egui::RichText::new("🖥️").size(32.0).color(theme.text_disabled));
                     Spacing::md(ui);
                     Typography::heading(ui, theme, "No devices configured");
-                    Typography::secondary(ui, theme, "Add RDP or WoL devices to get started");
+                    Typography::secondary(ui, theme, "Add a device with Wake-on-LAN, RDP, SSH or VNC to get started");
                     Spacing::lg(ui);
                 });
                 return;
@@ -143,95 +255,76 @@ impl HomePanel {
             let cards_per_row = ((available_width + spacing) / (card_width + spacing)).floor() as usize;
             let cards_per_row = cards_per_row.max(1).min(4); // Max 4 cards per row for better visibility
             
-            let total_devices = app.config.rdp_configs.len() + app.config.wol_devices.len();
+            let total_devices = app.config.devices.len();
             
             // Collect device operation actions separately to avoid borrow conflicts
             let mut pending_operations = Vec::new();
+            let mut cancelled_retries: Vec<(String, &str)> = Vec::new();
+            let mut cleared_statuses: Vec<(String, &str)> = Vec::new();
+            let mut schedule_link = None;
             
-            // Show devices in a responsive grid
-            egui::Grid::new("device_grid")
-                .num_columns(cards_per_row)
-                .spacing(egui::vec2(spacing, spacing))
-                .show(ui, |ui| {
-                    let mut device_count = 0;
+            // Show devices in a responsive grid, laying out only the rows in view
+            let row_count = total_devices.div_ceil(cards_per_row);
+            let cell_width = (available_width - spacing * (cards_per_row - 1) as f32) / cards_per_row as f32;
+            // Leaves room for the summary below
+            let grid_height = (ui.available_height() - 48.0).max(200.0);
+            ui.allocate_ui(egui::vec2(available_width, grid_height), |ui| VirtualList::show(ui, "device_grid", row_count, 170.0, None, |ui, row| {
+                ui.horizontal_top(|ui| {
+                    ui.spacing_mut().item_spacing.x = spacing;
                     
-                    // RDP Devices
-                    for rdp_config in &app.config.rdp_configs {
-                        let connect_state = app.get_device_operation_state(&rdp_config.name, "connect");
-                        
-                        if Self::draw_rdp_device_card_with_state(ui, theme, rdp_config, connect_state) {
-                            // Queue async RDP connection
-                            pending_operations.push(crate::ui::DeviceOperationType::RdpConnect(rdp_config.clone()));
-                        }
-                        
-                        device_count += 1;
-                        if device_count % cards_per_row == 0 {
-                            ui.end_row();
-                        }
-                    }
-                    
-                    // WOL Devices
-                    for wol_device in &app.config.wol_devices {
-                        let is_online = app.network_manager.wol_devices
-                            .iter()
-                            .find(|d| d.device.name == wol_device.name)
-                            .map(|d| d.is_online)
-                            .unwrap_or(false);
-                        
-                        let wake_state = app.get_device_operation_state(&wol_device.name, "wake");
-                        let ping_state = app.get_device_operation_state(&wol_device.name, "ping");
+                    for device in app.config.devices.iter().skip(row * cards_per_row).take(cards_per_row) {
+                        let action = ui.vertical(|ui| {
+                            ui.set_width(cell_width);
+                            Self::draw_device_card_with_state(ui, theme, app, device)
+                        }).inner;
                         
-                        let action = Self::draw_wol_device_card_with_state(ui, theme, wol_device, is_online, wake_state, ping_state);
-                        
-                        match action {
-                            Some(WolAction::Wake) => {
-                                // Queue async Wake on LAN
-                                pending_operations.push(crate::ui::DeviceOperationType::Wake(wol_device.clone()));
+                        // Queue async operations
+                        let operation = match action {
+                            Some(DeviceAction::Wake) => device.wol.clone().map(|wol| ("wake", crate::ui::DeviceOperationType::Wake(wol))),
+                            Some(DeviceAction::Connect) => device.rdp.clone().map(|rdp| ("connect", crate::ui::DeviceOperationType::RdpConnect(rdp))),
+                            Some(DeviceAction::Ssh) => device.ssh.clone().map(|ssh| match device.wol.clone().filter(|_| ssh.wake_first) {
+                                Some(wol) => ("ssh", crate::ui::DeviceOperationType::WakeThenSsh(wol, ssh)),
+                                None => ("ssh", crate::ui::DeviceOperationType::SshConnect(ssh)),
+                            }),
+                            Some(DeviceAction::Vnc) => device.vnc.clone().map(|vnc| ("vnc", crate::ui::DeviceOperationType::VncConnect(vnc))),
+                            Some(DeviceAction::Ping) => device.monitor_address().map(|address| {
+                                ("ping", crate::ui::DeviceOperationType::Ping { name: device.name.clone(), address })
+                            }),
+                            Some(DeviceAction::CancelRetry(operation)) => {
+                                cancelled_retries.push((device.name.clone(), operation));
+                                None
                             }
-                            Some(WolAction::Ping) => {
-                                // Queue async Ping
-                                pending_operations.push(crate::ui::DeviceOperationType::Ping(wol_device.clone()));
+                            Some(DeviceAction::ClearStatus(operation)) => {
+                                cleared_statuses.push((device.name.clone(), operation));
+                                None
                             }
-                            None => {}
-                        }
-                        
-                        device_count += 1;
-                        if device_count % cards_per_row == 0 {
-                            ui.end_row();
+                            Some(DeviceAction::OpenSchedule) => {
+                                schedule_link = Some(device.name.clone());
+                                None
+                            }
+                            None => None,
+                        };
+                        if let Some((name, operation)) = operation {
+                            pending_operations.push((device.name.clone(), name, operation));
                         }
                     }
-                    
-                    // End the last row if needed
-                    if device_count % cards_per_row != 0 {
-                        ui.end_row();
-                    }
                 });
+                ui.add_space(spacing);
+            }));
+            
+            for (device_name, operation) in cancelled_retries {
+                app.cancel_retry(&device_name, operation);
+            }
+            for (device_name, operation) in cleared_statuses {
+                app.clear_device_operation(&device_name, operation);
+            }
+            if let Some(name) = schedule_link {
+                app.navigation.request(crate::ui::route::Route::new(crate::ui::Panel::Schedule, &name));
+            }
             
             // Process pending operations after all borrows are done
-            for operation in pending_operations {
-                match &operation {
-                    crate::ui::DeviceOperationType::RdpConnect(rdp_config) => {
-                        app.start_device_operation(
-                            rdp_config.name.clone(),
-                            "connect".to_string(),
-                            operation
-                        );
-                    }
-                    crate::ui::DeviceOperationType::Wake(wol_device) => {
-                        app.start_device_operation(
-                            wol_device.name.clone(),
-                            "wake".to_string(),
-                            operation
-                        );
-                    }
-                    crate::ui::DeviceOperationType::Ping(wol_device) => {
-                        app.start_device_operation(
-                            wol_device.name.clone(),
-                            "ping".to_string(),
-                            operation
-                        );
-                    }
-                }
+            for (device_name, name, operation) in pending_operations {
+                app.start_device_operation(device_name, name.to_string(), operation);
             }
             
             // Device summary
@@ -242,126 +335,40 @@ impl HomePanel {
                 
                 ui.horizontal(|ui| {
                     Typography::small(ui, theme, &format!("Total: {} devices", total_devices));
-                    if !app.config.rdp_configs.is_empty() {
-                        ui.label(egui::RichText::new("•").color(theme.text_disabled));
-                        Typography::small(ui, theme, &format!("{} RDP", app.config.rdp_configs.len()));
+                    for label in ["WoL", "RDP", "SSH", "VNC"] {
+                        let count = app.config.devices.iter().filter(|d| d.capabilities().contains(&label)).count();
+                        if count > 0 {
+                            ui.label(egui::RichText::new("•").color(theme.text_disabled));
+                            Typography::small(ui, theme, &format!("{} {}", count, label));
+                        }
                     }
-                    if !app.config.wol_devices.is_empty() {
+                    if !app.network_manager.devices.is_empty() {
                         ui.label(egui::RichText::new("•").color(theme.text_disabled));
-                        let online_count = app.network_manager.wol_devices.iter().filter(|d| d.is_online).count();
-                        Typography::small(ui, theme, &format!("{} WoL ({} online)", app.config.wol_devices.len(), online_count));
+                        let online_count = app.network_manager.devices.iter().filter(|d| d.is_online).count();
+                        Typography::small(ui, theme, &format!("{} online", online_count));
                     }
                 });
             }
         });
     }
     
-    fn draw_rdp_device_card_with_state(ui: &mut egui::Ui, theme: &Theme, rdp_config: &crate::config::RdpConfig, operation_state: &crate::ui::DeviceOperationState) -> bool {
-        let response = ui.allocate_response(egui::vec2(200.0, 70.0), egui::Sense::hover());
-        let is_hovered = response.hovered();
-        
-        let (bg_color, border_color, border_width) = theme.get_card_colors(is_hovered, false);
-        
-        let mut clicked = false;
-        
-        egui::Frame::none()
-            .fill(bg_color)
-            .stroke(egui::Stroke::new(border_width, border_color))
-            .rounding(egui::Rounding::same(8.0))
-            .inner_margin(egui::Margin::same(12.0))
-            .shadow(theme.get_shadow(is_hovered))
-            .show(ui, |ui| {
-                ui.horizontal(|ui| {
-                    // Device icon with type-specific background
-                    let icon_bg = theme.primary.gamma_multiply(0.15);
-                    egui::Frame::none()
-                        .fill(icon_bg)
-                        .rounding(egui::Rounding::same(6.0))
-                        .inner_margin(egui::Margin::same(8.0))
-                        .show(ui, |ui| {
-                            ui.label(
-                                egui::RichText::new("🖥️")
-                                    .size(20.0)
-                                    .color(theme.get_device_icon_color(DeviceType::RDP, true))
-                            );
-                        });
-                    
-                    ui.add_space(12.0);
-                    
-                    // Device information
-                    ui.vertical(|ui| {
-                        ui.label(
-                            egui::RichText::new(&rdp_config.name)
-                                .strong()
-                                .size(14.0)
-                                .color(theme.text_primary)
-                        );
-                        ui.label(
-                            egui::RichText::new(format!("{}:{}", rdp_config.host, rdp_config.port))
-                                .size(11.0)
-                                .color(theme.text_secondary)
-                        );
-                        
-                        // Connection type badge
-                        egui::Frame::none()
-                            .fill(theme.primary.gamma_multiply(0.2))
-                            .rounding(egui::Rounding::same(4.0))
-                            .inner_margin(egui::Margin::symmetric(6.0, 2.0))
-                            .show(ui, |ui| {
-                                ui.label(
-                                    egui::RichText::new("RDP")
-                                        .size(9.0)
-                                        .color(theme.primary)
-                                );
-                            });
-                    });
-                    
-                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        let (button_text, button_color, button_enabled) = match operation_state {
-                            crate::ui::DeviceOperationState::Idle => ("Connect", theme.get_action_button_color(ActionType::Primary), true),
-                            crate::ui::DeviceOperationState::Loading => ("Connecting...", theme.loading, false),
-                            crate::ui::DeviceOperationState::Success(_) => ("Connected ✓", theme.success, true),
-                            crate::ui::DeviceOperationState::Error(_) => ("Failed ✗", theme.error, true),
-                        };
-                        
-                        if ui.add_enabled(button_enabled,
-                            egui::Button::new(button_text)
-                                .fill(button_color)
-                                .rounding(egui::Rounding::same(6.0))
-                                .min_size(egui::vec2(80.0, 30.0))
-                        ).clicked() && button_enabled {
-                            clicked = true;
-                        }
-                        
-                        // Show operation feedback as tooltip
-                        if let crate::ui::DeviceOperationState::Success(msg) | crate::ui::DeviceOperationState::Error(msg) = operation_state {
-                            if ui.rect_contains_pointer(ui.max_rect()) {
-                                egui::show_tooltip_text(ui.ctx(), egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("tooltip")), egui::Id::new(format!("rdp_tooltip_{}", rdp_config.name)), msg);
-                            }
-                        }
-                    });
-                });
-            });
+    // One card per device with a button for each capability
+    fn draw_device_card_with_state(ui: &mut egui::Ui, theme: &Theme, app: &crate::ui::App, device: &crate::config::Device) -> Option<DeviceAction> {
+        let address = device.monitor_address();
+        let monitored = address.is_some();
+        let is_online = app.network_manager.is_online(&device.name);
+        let scheduled_wake = device.wol.as_ref()
+            .and_then(|_| app.config.upcoming_action(ScheduledActionKind::WakeDevice, &device.name));
         
-        clicked
-    }
-    
-    fn draw_wol_device_card_with_state(
-        ui: &mut egui::Ui, 
-        theme: &Theme, 
-        wol_device: &crate::config::WolDevice, 
-        is_online: bool,
-        wake_state: &crate::ui::DeviceOperationState,
-        ping_state: &crate::ui::DeviceOperationState
-    ) -> Option<WolAction> {
         let response = ui.allocate_response(egui::vec2(200.0, 70.0), egui::Sense::hover());
         let is_hovered = response.hovered();
         
         let (bg_color, border_color, border_width) = theme.get_card_colors(is_hovered, is_online);
+        let accent = entry_accent(&device.style);
         
         let mut action = None;
         
-        egui::Frame::none()
+        let card = egui::Frame::none()
             .fill(bg_color)
             .stroke(egui::Stroke::new(border_width, border_color))
             .rounding(egui::Rounding::same(8.0))
@@ -369,11 +376,19 @@ impl HomePanel {
             .shadow(theme.get_shadow(is_hovered))
             .show(ui, |ui| {
                 ui.horizontal(|ui| {
-                    // Device icon with status-specific background
-                    let icon_bg = if is_online {
-                        theme.success.gamma_multiply(0.15)
+                    // Device icon on its accent color, or a status-specific background
+                    let device_type = if device.rdp.is_some() {
+                        DeviceType::RDP
+                    } else if device.vnc.is_some() {
+                        DeviceType::VNC
                     } else {
-                        theme.text_disabled.gamma_multiply(0.15)
+                        DeviceType::WOL
+                    };
+                    let icon_bg = match accent {
+                        Some(accent) => accent.gamma_multiply(0.2),
+                        None if is_online => theme.success.gamma_multiply(0.15),
+                        None if device_type == DeviceType::VNC => theme.accent.gamma_multiply(0.15),
+                        None => theme.primary.gamma_multiply(0.15),
                     };
                     
                     egui::Frame::none()
@@ -381,11 +396,12 @@ impl HomePanel {
                         .rounding(egui::Rounding::same(6.0))
                         .inner_margin(egui::Margin::same(8.0))
                         .show(ui, |ui| {
-                            ui.label(
-                                egui::RichText::new("💻")
-                                    .size(20.0)
-                                    .color(theme.get_device_icon_color(DeviceType::WOL, is_online))
-                            );
+                            ui.label(entry_icon(
+                                &device.style,
+                                device.default_icon(),
+                                theme.get_device_icon_color(device_type, is_online || !monitored),
+                                20.0,
+                            ));
                         });
                     
                     ui.add_space(12.0);
@@ -393,98 +409,149 @@ impl HomePanel {
                     // Device information
                     ui.vertical(|ui| {
                         ui.label(
-                            egui::RichText::new(&wol_device.name)
+                            egui::RichText::new(&device.name)
                                 .strong()
                                 .size(14.0)
                                 .color(theme.text_primary)
                         );
                         ui.label(
-                            egui::RichText::new(&wol_device.ip_address)
+                            egui::RichText::new(address.as_deref().unwrap_or("Not monitored"))
                                 .size(11.0)
                                 .color(theme.text_secondary)
                         );
                         
-                        // Status badge
-                        let status_bg = if is_online {
-                            theme.success.gamma_multiply(0.2)
-                        } else {
-                            theme.text_disabled.gamma_multiply(0.2)
-                        };
-                        let status_color = theme.get_device_status_color(is_online);
-                        let status_text = if is_online { "Online" } else { "Offline" };
+                        ui.horizontal(|ui| {
+                            // Capability badges
+                            for label in device.capabilities() {
+                                egui::Frame::none()
+                                    .fill(theme.primary.gamma_multiply(0.2))
+                                    .rounding(egui::Rounding::same(4.0))
+                                    .inner_margin(egui::Margin::symmetric(6.0, 2.0))
+                                    .show(ui, |ui| {
+                                        ui.label(egui::RichText::new(label).size(9.0).color(theme.primary));
+                                    });
+                            }
+                            
+                            // Status badge
+                            if monitored {
+                                let status_bg = if is_online {
+                                    theme.success.gamma_multiply(0.2)
+                                } else {
+                                    theme.text_disabled.gamma_multiply(0.2)
+                                };
+                                let status_color = theme.get_device_status_color(is_online);
+                                let status_text = if is_online { "Online" } else { "Offline" };
+                                
+                                egui::Frame::none()
+                                    .fill(status_bg)
+                                    .rounding(egui::Rounding::same(4.0))
+                                    .inner_margin(egui::Margin::symmetric(6.0, 2.0))
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            let state = if is_online { StatusState::Online } else { StatusState::Offline };
+                                            StatusDot::show(ui, theme, state, 8.0);
+                                            ui.label(
+                                                egui::RichText::new(status_text)
+                                                    .size(9.0)
+                                                    .color(status_color)
+                                            );
+                                        });
+                                    });
+                            }
+                        });
                         
-                        egui::Frame::none()
-                            .fill(status_bg)
-                            .rounding(egui::Rounding::same(4.0))
-                            .inner_margin(egui::Margin::symmetric(6.0, 2.0))
-                            .show(ui, |ui| {
-                                ui.horizontal(|ui| {
-                                    ui.label(
-                                        egui::RichText::new("●")
-                                            .size(8.0)
-                                            .color(status_color)
-                                    );
-                                    ui.label(
-                                        egui::RichText::new(status_text)
-                                            .size(9.0)
-                                            .color(status_color)
-                                    );
-                                });
-                            });
+                        if let Some(until) = device.maintenance_end() {
+                            MaintenanceBadge::show(ui, theme, until);
+                        }
+                        
+                        // Only wake and connect retry automatically
+                        for operation in ["wake", "connect"] {
+                            if let Some(retry) = app.get_pending_retry(&device.name, operation) {
+                                if Self::draw_retry_badge(ui, theme, retry) {
+                                    action = Some(DeviceAction::CancelRetry(operation));
+                                }
+                            }
+                        }
+                        
+                        if let Some(run) = scheduled_wake {
+                            if ScheduleBadge::show(ui, theme, run) {
+                                action = Some(DeviceAction::OpenSchedule);
+                            }
+                        }
                     });
                     
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        ui.horizontal(|ui| {
-                            // Wake button with state
-                            let (wake_text, wake_color, wake_enabled) = match wake_state {
-                                crate::ui::DeviceOperationState::Idle => ("Wake", theme.get_action_button_color(ActionType::Success), true),
-                                crate::ui::DeviceOperationState::Loading => ("Waking...", theme.loading, false),
-                                crate::ui::DeviceOperationState::Success(_) => ("Sent ✓", theme.success, true),
-                                crate::ui::DeviceOperationState::Error(_) => ("Failed ✗", theme.error, true),
-                            };
-                            
-                            if ui.add_enabled(wake_enabled,
-                                egui::Button::new(wake_text)
-                                    .fill(wake_color)
-                                    .rounding(egui::Rounding::same(6.0))
-                                    .min_size(egui::vec2(60.0, 28.0))
-                            ).clicked() && wake_enabled {
-                                action = Some(WolAction::Wake);
+                        // Right to left, so the first button ends up rightmost
+                        let buttons = [
+                            (monitored, "ping", ["Ping", "Pinging...", "Ping ✓"], ActionType::Secondary, DeviceAction::Ping),
+                            (device.vnc.is_some(), "vnc", ["VNC", "Opening...", "VNC ✓"], ActionType::Secondary, DeviceAction::Vnc),
+                            (device.ssh.is_some(), "ssh", ["SSH", "Opening...", "SSH ✓"], ActionType::Secondary, DeviceAction::Ssh),
+                            (device.rdp.is_some(), "connect", ["Connect", "Connecting...", "Connected ✓"], ActionType::Primary, DeviceAction::Connect),
+                            (device.wol.is_some(), "wake", ["Wake", "Waking...", "Sent ✓"], ActionType::Success, DeviceAction::Wake),
+                        ];
+                        
+                        for (shown, operation, [idle, loading, done], action_type, button_action) in buttons {
+                            if !shown {
+                                continue;
                             }
-                            
-                            // Ping button with state
-                            let (ping_text, ping_color, ping_enabled) = match ping_state {
-                                crate::ui::DeviceOperationState::Idle => ("Ping", theme.get_action_button_color(ActionType::Secondary), true),
-                                crate::ui::DeviceOperationState::Loading => ("Pinging...", theme.loading, false),
-                                crate::ui::DeviceOperationState::Success(_) => ("Ping ✓", theme.success, true),
+                            let state = app.get_device_operation_state(&device.name, operation);
+                            let (text, color, enabled) = match state {
+                                crate::ui::DeviceOperationState::Idle => (idle, theme.get_action_button_color(action_type), true),
+                                crate::ui::DeviceOperationState::Loading => (loading, theme.loading, false),
+                                crate::ui::DeviceOperationState::Success(_) => (done, theme.success, true),
                                 crate::ui::DeviceOperationState::Error(_) => ("Failed ✗", theme.error, true),
                             };
                             
-                            if ui.add_enabled(ping_enabled,
-                                egui::Button::new(ping_text)
-                                    .fill(ping_color)
+                            let response = ui.add_enabled(enabled,
+                                egui::Button::new(text)
+                                    .fill(color)
                                     .rounding(egui::Rounding::same(6.0))
                                     .min_size(egui::vec2(60.0, 28.0))
-                            ).clicked() && ping_enabled {
-                                action = Some(WolAction::Ping);
-                            }
-                        });
-                        
-                        // Show operation feedback as tooltips
-                        if let crate::ui::DeviceOperationState::Success(msg) | crate::ui::DeviceOperationState::Error(msg) = wake_state {
-                            if ui.rect_contains_pointer(ui.max_rect()) {
-                                egui::show_tooltip_text(ui.ctx(), egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("tooltip")), egui::Id::new(format!("wake_tooltip_{}", wol_device.name)), msg);
+                            );
+                            if response.clicked() && enabled {
+                                action = Some(button_action);
                             }
-                        }
-                        if let crate::ui::DeviceOperationState::Success(msg) | crate::ui::DeviceOperationState::Error(msg) = ping_state {
-                            if ui.rect_contains_pointer(ui.max_rect()) {
-                                egui::show_tooltip_text(ui.ctx(), egui::LayerId::new(egui::Order::Tooltip, egui::Id::new("tooltip")), egui::Id::new(format!("ping_tooltip_{}", wol_device.name)), msg);
+                            
+                            // Show operation feedback as tooltip, with a way to dismiss it early
+                            if let crate::ui::DeviceOperationState::Success(msg) | crate::ui::DeviceOperationState::Error(msg) = state {
+                                response.on_hover_text(msg).context_menu(|ui| {
+                                    if ui.button("Clear status").clicked() {
+                                        action = Some(DeviceAction::ClearStatus(operation));
+                                        ui.close_menu();
+                                    }
+                                });
                             }
                         }
                     });
                 });
             });
         
+        // Accent stripe along the left edge
+        if let Some(accent) = accent {
+            let rect = card.response.rect;
+            let stripe = egui::Rect::from_min_size(rect.min, egui::vec2(4.0, rect.height()));
+            ui.painter().rect_filled(stripe, egui::Rounding { nw: 8.0, sw: 8.0, ne: 0.0, se: 0.0 }, accent);
+        }
+        
         action
     }
-}
\ No newline at end of file
+    
+    // Countdown for an automatic retry; returns true when the user cancels it
+    fn draw_retry_badge(ui: &mut egui::Ui, theme: &Theme, retry: &crate::ui::PendingRetry) -> bool {
+        let remaining = retry.due.saturating_duration_since(std::time::Instant::now()).as_secs();
+        let mut cancelled = false;
+        
+        ui.horizontal(|ui| {
+            ui.label(
+                egui::RichText::new(format!("↻ Retry {}/{} in {}s", retry.attempt, crate::network::retry::MAX_ATTEMPTS, remaining))
+                    .size(9.0)
+                    .color(theme.warning)
+            );
+            if ui.small_button("✕").on_hover_text("Cancel retry").clicked() {
+                cancelled = true;
+            }
+        });
+        
+        cancelled
+    }
+}
@@ -2,8 +2,12 @@ pub mod home;
 pub mod vpn;
 pub mod remote;
 pub mod settings;
+pub mod problems;
+pub mod schedule;
 
 pub use home::HomePanel;
 pub use vpn::VpnPanel;
 pub use remote::RemotePanel;
-pub use settings::SettingsPanel;
\ No newline at end of file
+pub use settings::SettingsPanel;
+pub use problems::ProblemsPanel;
+pub use schedule::SchedulePanel;
\ No newline at end of file
@@ -0,0 +1,88 @@
+use eframe::egui;
+use crate::config::{Config, VpnType};
+use crate::config::lint::{self, FixIt, Problem, Severity};
+use crate::ui::components::Card;
+use crate::ui::theme::Theme;
+
+pub struct ProblemsPanel;
+
+impl ProblemsPanel {
+    // Returns true when a fix-it changed the config
+    pub fn draw(ui: &mut egui::Ui, config: &mut Config, problems: &[Problem]) -> bool {
+        let theme = Theme::current();
+
+        ui.heading("Problems");
+        ui.add_space(20.0);
+
+        let mut pending_fix = None;
+
+        Card::show(ui, &theme, "Configuration Problems", |ui| {
+            if problems.is_empty() {
+                ui.label(egui::RichText::new("✅ No problems found").color(theme.success));
+                return;
+            }
+
+            for (problem_index, problem) in problems.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    let (icon, color) = match problem.severity {
+                        Severity::Error => ("❌", theme.error),
+                        Severity::Warning => ("⚠", theme.warning),
+                    };
+                    ui.label(egui::RichText::new(icon).color(color));
+
+                    ui.vertical(|ui| {
+                        ui.label(format!("{} · {}", problem.kind.label(), problem.name));
+                        ui.label(egui::RichText::new(&problem.message).color(theme.text_secondary));
+                    });
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        // Right-to-left layout, so reverse to keep the first fix leftmost
+                        for fix in problem.fixes.iter().rev() {
+                            if ui.button(fix.label()).clicked() {
+                                pending_fix = Some((problem_index, fix.clone()));
+                            }
+                        }
+                    });
+                });
+                ui.separator();
+            }
+        });
+
+        match pending_fix {
+            Some((problem_index, fix)) => Self::apply(config, &problems[problem_index], &fix),
+            None => false,
+        }
+    }
+
+    fn apply(config: &mut Config, problem: &Problem, fix: &FixIt) -> bool {
+        if *fix != FixIt::BrowseConfigFile {
+            return lint::apply_fix(config, problem, fix);
+        }
+
+        let manage_files = config.manage_config_files;
+        let Some(vpn_config) = config.vpn_configs.get_mut(problem.index) else { return false };
+        let file_filter = match vpn_config.vpn_type {
+            VpnType::OpenVpn => &["ovpn"],
+            VpnType::WireGuard => &["conf"],
+            VpnType::Ikev2 | VpnType::OpenConnect | VpnType::Custom => return false,
+        };
+
+        match rfd::FileDialog::new()
+            .add_filter("VPN Config", file_filter)
+            .pick_file()
+        {
+            Some(path) => {
+                vpn_config.config_path = if manage_files {
+                    Config::import_config_file(&path).unwrap_or_else(|e| {
+                        log::warn!("Failed to copy {} into the managed directory: {}", path.display(), e);
+                        path.display().to_string()
+                    })
+                } else {
+                    path.display().to_string()
+                };
+                true
+            }
+            None => false,
+        }
+    }
+}
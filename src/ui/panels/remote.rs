@@ -1,29 +1,442 @@
 use eframe::egui;
-use crate::config::{Config, RdpConfig, WolDevice};
-use crate::network::NetworkManager;
-use crate::ui::components::{Card, GlassButton, StatusIndicator};
+use crate::config::{Config, Device, LatencyPolicy, MonitorSettings, RdpConfig, RdpDisplay, RdpGateway, RdpRedirection, RdpScreenMode, SlowLinkAction, SshConfig, VncConfig, WolDevice};
+use crate::config::lint::EntryKind;
+use crate::config::migrate::{self, MigrationReport};
+use crate::config::duplicates::{self, DuplicateGroup};
+use crate::config::vault::{self, SecretNote};
+use crate::config::schedule::ScheduledActionKind;
+use crate::network::{latency, NetworkManager};
+use crate::network::preview::{self, CommandPreview};
+use crate::system::thumbnails;
+use crate::ui::components::{entry_label, Card, FocusFrame, GlassButton, MaintenanceBadge, ScheduleBadge, StatusIndicator, StylePicker, ToolWindow, VirtualList};
+use std::collections::{BTreeSet, HashMap};
+use crate::ui::route::{Navigation, Route};
+use crate::ui::{DeviceOperationType, Panel};
 use crate::ui::theme::Theme;
 
+// Merge or ignore, applied to a group once the window is done with it
+type DuplicateAction = fn(&mut Config, &DuplicateGroup);
+
+// Offered in the Display section of the RDP settings; 0 × 0 fits the screen or window
+const RDP_RESOLUTIONS: [(u32, u32); 7] = [(0, 0), (1280, 720), (1600, 900), (1920, 1080), (2560, 1440), (3440, 1440), (3840, 2160)];
+
+// A Measure Now round trip in milliseconds, None while it is being taken
+type Measurement = std::sync::Arc<std::sync::Mutex<Option<Result<u64, String>>>>;
+
 pub struct RemotePanel;
 
+// Devices ticked for bulk editing and the values to apply; each field only changes when its
+// checkbox is set, so one edit can touch just the port or just the credentials
+#[derive(Default)]
+pub struct BulkEdit {
+    pub devices: BTreeSet<String>,
+    pub open: bool,
+    pub set_port: bool,
+    pub port: String,
+    pub set_domain: bool,
+    pub domain: String,
+    pub set_tag: bool,
+    pub tag: String,
+    pub set_credentials: bool,
+    pub username: String,
+    pub password: String,
+}
+
+impl BulkEdit {
+    fn selected_count(&self) -> usize {
+        self.devices.len()
+    }
+    
+    // Selections that no longer match a device (removed or renamed) are dropped
+    fn retain_existing(&mut self, config: &Config) {
+        self.devices.retain(|name| config.device(name).is_some());
+    }
+    
+    fn selected<'a>(&'a self, config: &'a Config) -> impl Iterator<Item = &'a Device> {
+        config.devices.iter().filter(|d| self.devices.contains(&d.name))
+    }
+    
+    fn apply(&mut self, config: &mut Config) {
+        let port = self.port.trim().parse::<u16>().ok();
+        
+        for device in config.devices.iter_mut().filter(|d| self.devices.contains(&d.name)) {
+            if self.set_tag {
+                device.tag = self.tag.trim().to_string();
+            }
+            if let Some(wol_device) = &mut device.wol {
+                if let Some(port) = port.filter(|_| self.set_port) {
+                    wol_device.port = port;
+                }
+            }
+            if let Some(rdp_config) = &mut device.rdp {
+                if self.set_domain {
+                    let domain = self.domain.trim();
+                    rdp_config.domain = if domain.is_empty() { None } else { Some(domain.to_string()) };
+                }
+                if self.set_credentials {
+                    rdp_config.username = self.username.clone();
+                    rdp_config.password = self.password.clone();
+                }
+            }
+        }
+        
+        log::info!("Bulk edited {} device(s)", self.devices.len());
+        // Keep the selection, but don't carry a password around longer than needed
+        self.password.clear();
+        self.open = false;
+    }
+}
+
+// Encrypted notes window: the device whose notes are open, the passphrase as typed, and the note
+// being added
+#[derive(Default)]
+pub struct NotesWindow {
+    pub device: Option<String>,
+    pub passphrase: String,
+    pub confirm: String,
+    pub new_title: String,
+    pub new_text: String,
+    pub error: Option<String>,
+}
+
+// Device thumbnails loaded as textures, reloaded when their file changes
+#[derive(Default)]
+pub struct Thumbnails {
+    loaded: HashMap<String, LoadedThumbnail>,
+}
+
+struct LoadedThumbnail {
+    modified: Option<std::time::SystemTime>,
+    checked: std::time::Instant,
+    texture: Option<egui::TextureHandle>,
+}
+
+impl Thumbnails {
+    // How often the file is looked at again, for captures finishing in the background
+    const RECHECK: std::time::Duration = std::time::Duration::from_secs(2);
+    
+    fn get(&mut self, ctx: &egui::Context, device_name: &str) -> Option<egui::TextureHandle> {
+        let stale = self.loaded.get(device_name).is_none_or(|loaded| loaded.checked.elapsed() >= Self::RECHECK);
+        if stale {
+            let path = thumbnails::path(device_name);
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+            let unchanged = self.loaded.get(device_name).is_some_and(|loaded| loaded.modified == modified);
+            let texture = if unchanged {
+                self.loaded.get(device_name).and_then(|loaded| loaded.texture.clone())
+            } else {
+                modified.and_then(|_| image::open(&path).ok()).map(|image| {
+                    let image = image.to_rgba8();
+                    let size = [image.width() as usize, image.height() as usize];
+                    let color_image = egui::ColorImage::from_rgba_unmultiplied(size, image.as_flat_samples().as_slice());
+                    ctx.load_texture(format!("thumbnail_{}", device_name), color_image, egui::TextureOptions::LINEAR)
+                })
+            };
+            self.loaded.insert(device_name.to_string(), LoadedThumbnail { modified, checked: std::time::Instant::now(), texture });
+        }
+        self.loaded.get(device_name).and_then(|loaded| loaded.texture.clone())
+    }
+}
+
+// Add Device form. The host is shared by RDP, SSH and VNC; each capability is only added when
+// its section is ticked. Filled from a device by ✏, it saves over that device instead.
+pub struct DeviceForm {
+    // Id of the device being edited; None adds a new one
+    pub editing: Option<String>,
+    // Scrolls the form into view on the next frame
+    reveal: bool,
+    pub name: String,
+    pub tag: String,
+    pub host: String,
+    pub wol: bool,
+    pub wol_mac: String,
+    // Target for the magic packet, empty broadcasts
+    pub wol_ip: String,
+    pub wol_port: String,
+    // SSH host that sends the packet when it doesn't get through from here
+    pub wol_relay: bool,
+    pub wol_relay_host: String,
+    pub wol_relay_port: String,
+    pub wol_relay_username: String,
+    pub wol_relay_key_path: String,
+    pub rdp: bool,
+    pub rdp_port: String,
+    pub rdp_username: String,
+    pub rdp_password: String,
+    pub rdp_domain: String,
+    pub rdp_env: String,
+    pub rdp_display: RdpDisplay,
+    pub rdp_redirect: RdpRedirection,
+    pub rdp_store_credentials: bool,
+    pub rdp_gateway_enabled: bool,
+    pub rdp_gateway: RdpGateway,
+    pub ssh: bool,
+    pub ssh_port: String,
+    pub ssh_username: String,
+    pub ssh_key_path: String,
+    pub ssh_wake_first: bool,
+    pub vnc: bool,
+    pub vnc_port: String,
+    pub vnc_viewer: String,
+    pub monitor: bool,
+}
+
+impl Default for DeviceForm {
+    fn default() -> Self {
+        Self {
+            editing: None,
+            reveal: false,
+            name: String::new(),
+            tag: String::new(),
+            host: String::new(),
+            wol: false,
+            wol_mac: String::new(),
+            wol_ip: String::new(),
+            wol_port: String::from("9"),
+            wol_relay: false,
+            wol_relay_host: String::new(),
+            wol_relay_port: String::from("22"),
+            wol_relay_username: String::new(),
+            wol_relay_key_path: String::new(),
+            rdp: false,
+            rdp_port: String::from("3389"),
+            rdp_username: String::new(),
+            rdp_password: String::new(),
+            rdp_domain: String::new(),
+            rdp_env: String::new(),
+            rdp_display: RdpDisplay::default(),
+            rdp_redirect: RdpRedirection::default(),
+            rdp_store_credentials: false,
+            rdp_gateway_enabled: false,
+            rdp_gateway: RdpGateway::default(),
+            ssh: false,
+            ssh_port: String::from("22"),
+            ssh_username: String::new(),
+            ssh_key_path: String::new(),
+            ssh_wake_first: false,
+            vnc: false,
+            vnc_port: String::from("5900"),
+            vnc_viewer: String::new(),
+            monitor: true,
+        }
+    }
+}
+
+impl DeviceForm {
+    // The form showing a device, to edit it. Capabilities that are off keep the defaults.
+    fn from_device(device: &Device) -> Self {
+        let mut form = Self {
+            editing: Some(device.id.clone()),
+            reveal: true,
+            name: device.name.clone(),
+            tag: device.tag.clone(),
+            monitor: device.monitor.enabled,
+            ..Self::default()
+        };
+        // Devices merged from the old lists may have a host per capability; RDP's wins
+        form.host = [device.rdp.as_ref().map(|rdp| &rdp.host), device.ssh.as_ref().map(|ssh| &ssh.host), device.vnc.as_ref().map(|vnc| &vnc.host)]
+            .into_iter()
+            .flatten()
+            .next()
+            .cloned()
+            .unwrap_or_default();
+        
+        if let Some(wol) = &device.wol {
+            form.wol = true;
+            form.wol_mac = wol.mac_address.clone();
+            form.wol_ip = if wol.ip_address == "255.255.255.255" { String::new() } else { wol.ip_address.clone() };
+            form.wol_port = wol.port.to_string();
+            if let Some(relay) = &wol.relay {
+                form.wol_relay = true;
+                form.wol_relay_host = relay.host.clone();
+                form.wol_relay_port = relay.port.to_string();
+                form.wol_relay_username = relay.username.clone();
+                form.wol_relay_key_path = relay.key_path.clone();
+            }
+        }
+        if let Some(rdp) = &device.rdp {
+            form.rdp = true;
+            form.rdp_port = rdp.port.to_string();
+            form.rdp_username = rdp.username.clone();
+            form.rdp_password = rdp.password.clone();
+            form.rdp_domain = rdp.domain.clone().unwrap_or_default();
+            form.rdp_env = crate::config::format_env_vars(&rdp.env_vars);
+            form.rdp_display = rdp.display.clone();
+            form.rdp_redirect = rdp.redirect.clone();
+            form.rdp_store_credentials = rdp.store_credentials;
+            if let Some(gateway) = &rdp.gateway {
+                form.rdp_gateway_enabled = true;
+                form.rdp_gateway = gateway.clone();
+            }
+        }
+        if let Some(ssh) = &device.ssh {
+            form.ssh = true;
+            form.ssh_port = ssh.port.to_string();
+            form.ssh_username = ssh.username.clone();
+            form.ssh_key_path = ssh.key_path.clone();
+            form.ssh_wake_first = ssh.wake_first;
+        }
+        if let Some(vnc) = &device.vnc {
+            form.vnc = true;
+            form.vnc_port = vnc.port.to_string();
+            form.vnc_viewer = vnc.viewer.clone();
+        }
+        form
+    }
+    
+    // The edited device: the form's values, with what the form doesn't show kept from the existing one
+    fn update(&self, existing: &Device) -> Option<Device> {
+        let mut device = self.build()?;
+        device.id = existing.id.clone();
+        device.style = existing.style.clone();
+        device.notes = existing.notes.clone();
+        device.monitor = MonitorSettings { enabled: self.monitor, ..existing.monitor.clone() };
+        if let (Some(rdp), Some(previous)) = (&mut device.rdp, &existing.rdp) {
+            rdp.latency = previous.latency.clone();
+        }
+        Some(device)
+    }
+    
+    // None until a name, at least one capability and the fields it needs are filled in
+    fn build(&self) -> Option<Device> {
+        let name = self.name.trim();
+        let host = self.host.trim();
+        let needs_host = self.rdp || self.ssh || self.vnc;
+        if name.is_empty() || !(self.wol || needs_host) || (needs_host && host.is_empty())
+            || (self.wol && self.wol_mac.trim().is_empty())
+            || (self.wol && self.wol_relay && self.wol_relay_host.trim().is_empty())
+        {
+            return None;
+        }
+        
+        let mut device = Device::new(name);
+        device.tag = self.tag.trim().to_string();
+        device.monitor.enabled = self.monitor;
+        
+        if self.wol {
+            let ip_address = if self.wol_ip.trim().is_empty() {
+                "255.255.255.255".to_string()
+            } else {
+                self.wol_ip.trim().to_string()
+            };
+            device.wol = Some(WolDevice {
+                name: String::new(),
+                mac_address: self.wol_mac.trim().to_string(),
+                ip_address,
+                port: self.wol_port.parse::<u16>().unwrap_or(9),
+                relay: self.wol_relay.then(|| SshConfig {
+                    name: String::new(),
+                    host: self.wol_relay_host.trim().to_string(),
+                    port: self.wol_relay_port.parse::<u16>().unwrap_or(22),
+                    username: self.wol_relay_username.trim().to_string(),
+                    key_path: self.wol_relay_key_path.trim().to_string(),
+                    wake_first: false,
+                }),
+            });
+        }
+        if self.rdp {
+            device.rdp = Some(RdpConfig {
+                name: String::new(),
+                host: host.to_string(),
+                port: self.rdp_port.parse::<u16>().unwrap_or(3389),
+                username: self.rdp_username.clone(),
+                password: self.rdp_password.clone(),
+                domain: if self.rdp_domain.is_empty() { None } else { Some(self.rdp_domain.clone()) },
+                env_vars: crate::config::parse_env_vars(&self.rdp_env),
+                latency: Default::default(),
+                display: self.rdp_display.clone(),
+                redirect: self.rdp_redirect.clone(),
+                store_credentials: self.rdp_store_credentials,
+                gateway: (self.rdp_gateway_enabled && !self.rdp_gateway.host.trim().is_empty()).then(|| RdpGateway {
+                    host: self.rdp_gateway.host.trim().to_string(),
+                    username: self.rdp_gateway.username.trim().to_string(),
+                    password: self.rdp_gateway.password.clone(),
+                }),
+            });
+        }
+        if self.ssh {
+            device.ssh = Some(SshConfig {
+                name: String::new(),
+                host: host.to_string(),
+                port: self.ssh_port.parse::<u16>().unwrap_or(22),
+                username: self.ssh_username.trim().to_string(),
+                key_path: self.ssh_key_path.trim().to_string(),
+                wake_first: self.ssh_wake_first && self.wol,
+            });
+        }
+        if self.vnc {
+            device.vnc = Some(VncConfig {
+                name: String::new(),
+                host: host.to_string(),
+                port: self.vnc_port.parse::<u16>().unwrap_or(5900),
+                viewer: self.vnc_viewer.trim().to_string(),
+            });
+        }
+        
+        device.sync_names();
+        Some(device)
+    }
+}
+
+// Row buttons, acted on after the list is drawn
+enum DeviceAction {
+    Wake,
+    Connect,
+    Ssh,
+    Vnc,
+    Ping,
+    ExportRdp,
+    // New window length, None ending maintenance early
+    Maintenance(Option<chrono::Duration>),
+    Remove,
+}
+
 impl RemotePanel {
     pub fn draw(ui: &mut egui::Ui, config: &mut Config, network_manager: &mut NetworkManager,
-                new_rdp_name: &mut String, new_rdp_host: &mut String, new_rdp_port: &mut String,
-                new_rdp_username: &mut String, new_rdp_password: &mut String, new_rdp_domain: &mut String,
-                new_wol_name: &mut String, new_wol_mac: &mut String,
-                new_wol_ip: &mut String, new_wol_port: &mut String) {
-        let theme = Theme::new();
+                new_device: &mut DeviceForm, command_preview: &mut Option<CommandPreview>,
+                client_log_view: &mut Option<(String, String)>, navigation: &mut Navigation, bulk_edit: &mut BulkEdit,
+                duplicates_open: &mut bool, notes_window: &mut NotesWindow, thumbnail_cache: &mut Thumbnails,
+                feedback: &mut Option<String>, migration_report: &mut Option<MigrationReport>) -> Option<(String, &'static str, DeviceOperationType)> {
+        let theme = Theme::current();
         
-        ui.heading("Remote Access");
+        bulk_edit.retain_existing(config);
+        let duplicate_groups = duplicates::find(config);
+        
+        ui.horizontal(|ui| {
+            ui.heading("Remote Access");
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                let count = bulk_edit.selected_count();
+                if count > 0 {
+                    if ui.small_button("Clear Selection").clicked() {
+                        bulk_edit.devices.clear();
+                    }
+                    if GlassButton::show(ui, &theme, &format!("Bulk Edit ({})", count), true).clicked() {
+                        bulk_edit.open = true;
+                    }
+                } else {
+                    ui.label(egui::RichText::new("Tick devices to edit several at once").color(theme.text_secondary));
+                }
+                
+                if !duplicate_groups.is_empty() {
+                    let text = egui::RichText::new(format!("⚠ {} possible duplicate(s)", duplicate_groups.len())).color(theme.warning);
+                    if ui.button(text).on_hover_text("Devices pointing at the same host, IP or MAC").clicked() {
+                        *duplicates_open = true;
+                    }
+                }
+            });
+        });
         ui.add_space(20.0);
         
+        Self::draw_bulk_edit_window(ui.ctx(), &theme, config, bulk_edit);
+        Self::draw_duplicates_window(ui.ctx(), &theme, config, &duplicate_groups, duplicates_open);
+        
+        let mut operation = None;
         ui.horizontal(|ui| {
             ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
                 ui.vertical(|ui| {
-                    ui.set_width(ui.available_width() * 0.5 - 8.0);
+                    ui.set_width(ui.available_width() * 0.6 - 8.0);
                     
-                    Self::draw_rdp_section(ui, &theme, config, new_rdp_name, new_rdp_host, new_rdp_port,
-                                         new_rdp_username, new_rdp_password, new_rdp_domain);
+                    operation = Self::draw_devices_card(ui, &theme, config, network_manager, new_device, command_preview,
+                                                        client_log_view, navigation, &mut bulk_edit.devices, notes_window, thumbnail_cache, feedback);
                 });
             });
             
@@ -33,233 +446,855 @@ impl RemotePanel {
                 ui.vertical(|ui| {
                     ui.set_width(ui.available_width());
                     
-                    Self::draw_wol_section(ui, &theme, config, network_manager, new_wol_name, new_wol_mac, new_wol_ip, new_wol_port);
+                    Self::draw_add_device_card(ui, &theme, config, new_device, migration_report);
                 });
             });
         });
+        operation
     }
     
-    fn draw_rdp_section(ui: &mut egui::Ui, theme: &Theme, config: &mut Config,
-                       new_rdp_name: &mut String, new_rdp_host: &mut String, new_rdp_port: &mut String,
-                       new_rdp_username: &mut String, new_rdp_password: &mut String, new_rdp_domain: &mut String) {
-        
-        // RDP Connections List
-        Card::show(ui, theme, "Remote Desktop (RDP)", |ui| {
-            if config.rdp_configs.is_empty() {
-                ui.label(egui::RichText::new("No RDP connections configured").color(theme.text_secondary));
-            } else {
-                let mut to_remove = None;
+    fn draw_devices_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, network_manager: &mut NetworkManager,
+                         form: &mut DeviceForm, command_preview: &mut Option<CommandPreview>, client_log_view: &mut Option<(String, String)>,
+                         navigation: &mut Navigation, selected: &mut BTreeSet<String>, notes_window: &mut NotesWindow,
+                         thumbnail_cache: &mut Thumbnails, feedback: &mut Option<String>) -> Option<(String, &'static str, DeviceOperationType)> {
+        let ssh_terminal = config.ssh_terminal.clone();
+        Card::show(ui, theme, "Devices", |ui| {
+            if config.devices.is_empty() {
+                ui.label(egui::RichText::new("No devices configured").color(theme.text_secondary));
+                return None;
+            }
+            
+            let mut action: Option<(usize, DeviceAction)> = None;
+            let mut edit = None;
+            let mut open_schedule = None;
+            let mut restyle = None;
+            let mut new_latency = None;
+            
+            let focused = config.devices.iter().position(|device| navigation.should_scroll(&device.name));
+            VirtualList::show(ui, "devices", config.devices.len(), 80.0, focused, |ui, index| {
+                let device = &config.devices[index];
+                let monitored = device.monitor_address();
                 
-                for (index, rdp_config) in config.rdp_configs.iter().enumerate() {
-                    ui.horizontal(|ui| {
-                        ui.vertical(|ui| {
-                            ui.label(egui::RichText::new(&rdp_config.name).strong());
-                            ui.label(egui::RichText::new(format!("{}:{}", rdp_config.host, rdp_config.port)).color(theme.text_secondary));
+                FocusFrame::show(ui, theme, navigation, &device.name, |ui| ui.horizontal(|ui| {
+                    Self::selection_checkbox(ui, selected, &device.name);
+                    let mut style = device.style.clone();
+                    if StylePicker::show(ui, theme, &mut style, device.default_icon()) {
+                        restyle = Some((index, style));
+                    }
+                    if let Some(texture) = thumbnail_cache.get(ui.ctx(), &device.name) {
+                        ui.add(egui::Image::new(&texture).fit_to_exact_size(egui::vec2(64.0, 40.0)).rounding(4.0))
+                            .on_hover_ui(|ui| {
+                                ui.add(egui::Image::new(&texture));
+                            });
+                    }
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            entry_label(ui, egui::RichText::new(&device.name).strong(), Route::new(Panel::Remote, &device.name));
+                            Self::tag_chip(ui, theme, &device.tag);
+                            for label in device.capabilities() {
+                                ui.label(egui::RichText::new(label).size(10.0).color(theme.text_secondary));
+                            }
                         });
                         
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            if ui.button("🗑").clicked() {
-                                to_remove = Some(index);
+                        let mut details = Vec::new();
+                        if let Some(rdp_config) = &device.rdp {
+                            details.push(format!("RDP {}:{}", rdp_config.host, rdp_config.port));
+                        }
+                        if let Some(ssh_config) = &device.ssh {
+                            details.push(format!("SSH {}:{}", ssh_config.host, ssh_config.port));
+                        }
+                        if let Some(vnc_config) = &device.vnc {
+                            details.push(format!("VNC {}:{}", vnc_config.host, vnc_config.port));
+                        }
+                        if let Some(wol_device) = &device.wol {
+                            details.push(format!("MAC {}", wol_device.mac_address));
+                        }
+                        ui.label(egui::RichText::new(details.join("  ·  ")).color(theme.text_secondary));
+                        
+                        if device.wol.is_some() {
+                            if let Some(run) = config.upcoming_action(ScheduledActionKind::WakeDevice, &device.name) {
+                                if ScheduleBadge::show(ui, theme, run) {
+                                    open_schedule = Some(device.name.clone());
+                                }
                             }
-                            
-                            if GlassButton::show(ui, theme, "Connect", true).clicked() {
-                                let runtime = tokio::runtime::Runtime::new().unwrap();
-                                runtime.block_on(async {
-                                    match crate::network::rdp::connect(rdp_config).await {
-                                        Ok(_) => log::info!("RDP connection initiated successfully"),
-                                        Err(e) => log::error!("RDP connection failed: {}", e),
+                        }
+                        if let Some(until) = device.maintenance_end() {
+                            MaintenanceBadge::show(ui, theme, until);
+                        }
+                    });
+                    
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("🗑").clicked() {
+                            action = Some((index, DeviceAction::Remove));
+                        }
+                        if ui.button("✏").on_hover_text("Edit").clicked() {
+                            edit = Some(index);
+                        }
+                        
+                        let notes_label = if device.notes.is_empty() { "🔒".to_string() } else { format!("🔒 {}", device.notes.len()) };
+                        if ui.small_button(notes_label).on_hover_text("Encrypted notes").clicked() {
+                            notes_window.device = Some(device.name.clone());
+                            notes_window.error = None;
+                        }
+                        
+                        ui.menu_button("🖼", |ui| {
+                            if ui.button("Choose Image...").clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter("Image", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
+                                    .pick_file()
+                                {
+                                    if let Err(e) = thumbnails::set_from_file(&device.name, &path) {
+                                        log::error!("Failed to set the thumbnail of {}: {}", device.name, e);
                                     }
-                                });
+                                }
+                                ui.close_menu();
                             }
-                            
-                            #[cfg(windows)]
-                            if ui.small_button("🧪").clicked() {
-                                let runtime = tokio::runtime::Runtime::new().unwrap();
-                                runtime.block_on(async {
-                                    match crate::network::rdp::test_mstsc_basic().await {
-                                        Ok(_) => log::info!("mstsc test passed"),
-                                        Err(e) => log::error!("mstsc test failed: {}", e),
+                            if ui.button("Capture Screen in 5 s")
+                                .on_hover_text("Bring the session's window to the front; the whole screen is captured")
+                                .clicked()
+                            {
+                                thumbnails::capture_later(&device.name, std::time::Duration::from_secs(5));
+                                ui.close_menu();
+                            }
+                            if thumbnails::path(&device.name).exists() && ui.button("Remove").clicked() {
+                                thumbnails::remove(&device.name);
+                                ui.close_menu();
+                            }
+                        }).response.on_hover_text("Thumbnail");
+                        
+                        if device.rdp.is_some() && ui.small_button("📄").on_hover_text("RDP client log").clicked() {
+                            *client_log_view = Some(("rdp".to_string(), device.name.clone()));
+                        }
+                        
+                        if device.rdp.is_some() && ui.small_button("💾").on_hover_text("Export as .rdp file").clicked() {
+                            action = Some((index, DeviceAction::ExportRdp));
+                        }
+                        
+                        if let Some(rdp_config) = &device.rdp {
+                            let mut policy = rdp_config.latency.clone();
+                            ui.menu_button("📶", |ui| {
+                                Self::draw_latency_policy(ui, theme, &mut policy, rdp_config);
+                            }).response.on_hover_text(if policy.enabled {
+                                format!("Round trip checked before connecting, limit {} ms", policy.max_rtt_ms)
+                            } else {
+                                "Round trip not checked before connecting".to_string()
+                            });
+                            if policy != rdp_config.latency {
+                                new_latency = Some((index, policy));
+                            }
+                        }
+                        
+                        ui.menu_button("⌨", |ui| {
+                            if let Some(wol_device) = &device.wol {
+                                if ui.button("Wake").clicked() {
+                                    let runtime = tokio::runtime::Runtime::new().unwrap();
+                                    *command_preview = Some(runtime.block_on(preview::wake(wol_device)));
+                                    ui.close_menu();
+                                }
+                            }
+                            if let Some(rdp_config) = &device.rdp {
+                                if ui.button("RDP").clicked() {
+                                    *command_preview = Some(preview::rdp_connect(rdp_config));
+                                    ui.close_menu();
+                                }
+                            }
+                            if let Some(ssh_config) = &device.ssh {
+                                if ui.button("SSH").clicked() {
+                                    *command_preview = Some(preview::ssh_connect(ssh_config, &ssh_terminal));
+                                    ui.close_menu();
+                                }
+                            }
+                            if let Some(vnc_config) = &device.vnc {
+                                if ui.button("VNC").clicked() {
+                                    *command_preview = Some(preview::vnc_connect(vnc_config));
+                                    ui.close_menu();
+                                }
+                            }
+                        }).response.on_hover_text("Show command");
+                        
+                        if monitored.is_some() {
+                            ui.menu_button("🔧", |ui| {
+                                ui.label("Maintenance for");
+                                for (label, hours) in [("1 hour", 1), ("4 hours", 4), ("12 hours", 12), ("1 day", 24), ("1 week", 24 * 7)] {
+                                    if ui.button(label).clicked() {
+                                        action = Some((index, DeviceAction::Maintenance(Some(chrono::Duration::hours(hours)))));
+                                        ui.close_menu();
                                     }
-                                });
+                                }
+                                if device.in_maintenance() {
+                                    ui.separator();
+                                    if ui.button("End maintenance").clicked() {
+                                        action = Some((index, DeviceAction::Maintenance(None)));
+                                        ui.close_menu();
+                                    }
+                                }
+                            }).response.on_hover_text("Maintenance mode: no offline alerts, not counted in availability");
+                            
+                            if GlassButton::show(ui, theme, "Ping", false).clicked() {
+                                action = Some((index, DeviceAction::Ping));
                             }
-                        });
+                        }
+                        
+                        if device.vnc.is_some() && GlassButton::show(ui, theme, "VNC", false).clicked() {
+                            action = Some((index, DeviceAction::Vnc));
+                        }
+                        if device.ssh.is_some() && GlassButton::show(ui, theme, "SSH", false).clicked() {
+                            action = Some((index, DeviceAction::Ssh));
+                        }
+                        if device.rdp.is_some() && GlassButton::show(ui, theme, "Connect", true).clicked() {
+                            action = Some((index, DeviceAction::Connect));
+                        }
+                        if device.wol.is_some() && GlassButton::show(ui, theme, "Wake", true).clicked() {
+                            action = Some((index, DeviceAction::Wake));
+                        }
+                        
+                        #[cfg(windows)]
+                        if device.rdp.is_some() && ui.small_button("🧪").clicked() {
+                            let runtime = tokio::runtime::Runtime::new().unwrap();
+                            runtime.block_on(async {
+                                match crate::network::rdp::test_mstsc_basic().await {
+                                    Ok(_) => log::info!("mstsc test passed"),
+                                    Err(e) => log::error!("mstsc test failed: {}", e),
+                                }
+                            });
+                        }
+                        
+                        if monitored.is_some() {
+                            let is_online = network_manager.is_online(&device.name);
+                            StatusIndicator::show(ui, theme, is_online, if is_online { "Online" } else { "Offline" });
+                        }
                     });
-                    ui.separator();
+                }));
+                ui.separator();
+            });
+            
+            let operation = action.and_then(|(index, action)| Self::run_action(config, feedback, index, action));
+            if let Some(device) = edit.and_then(|index| config.devices.get(index)) {
+                *form = DeviceForm::from_device(device);
+            }
+            if let Some((index, style)) = restyle {
+                if let Some(device) = config.devices.get_mut(index) {
+                    device.style = style;
                 }
-                
-                if let Some(index) = to_remove {
-                    config.rdp_configs.remove(index);
+            }
+            if let Some((index, policy)) = new_latency {
+                if let Some(rdp_config) = config.devices.get_mut(index).and_then(|device| device.rdp.as_mut()) {
+                    rdp_config.latency = policy;
                 }
             }
-        });
-        
-        ui.add_space(16.0);
-        
-        // Add RDP Connection
-        Card::show(ui, theme, "Add RDP Connection", |ui| {
+            if let Some(name) = open_schedule {
+                navigation.request(Route::new(Panel::Schedule, &name));
+            }
+            operation
+        })
+    }
+    
+    fn draw_rdp_display(ui: &mut egui::Ui, theme: &Theme, display: &mut RdpDisplay) {
+        ui.checkbox(&mut display.multimon, "Use all monitors")
+            .on_hover_text("Spans the session over every monitor here, fullscreen at their combined size");
+        ui.add_enabled_ui(!display.multimon, |ui| {
             ui.horizontal(|ui| {
-                ui.label("Name:");
-                ui.text_edit_singleline(new_rdp_name);
+                ui.radio_value(&mut display.mode, RdpScreenMode::Fullscreen, "Fullscreen");
+                ui.radio_value(&mut display.mode, RdpScreenMode::Windowed, "Window");
             });
+        });
+        
+        egui::Grid::new("device_form_rdp_display").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+            ui.label("Resolution:");
+            ui.add_enabled_ui(!display.multimon, |ui| ui.horizontal(|ui| {
+                let fit = if display.is_fullscreen() { "Fit the screen" } else { "Fit the window" };
+                let selected = if display.has_size() { format!("{} × {}", display.width, display.height) } else { fit.to_string() };
+                egui::ComboBox::from_id_salt("rdp_resolution").selected_text(selected).show_ui(ui, |ui| {
+                    for (width, height) in RDP_RESOLUTIONS {
+                        let label = if width == 0 { fit.to_string() } else { format!("{} × {}", width, height) };
+                        if ui.selectable_label(display.width == width && display.height == height, label).clicked() {
+                            display.width = width;
+                            display.height = height;
+                        }
+                    }
+                });
+                if display.has_size() {
+                    ui.add(egui::DragValue::new(&mut display.width).range(640..=8192));
+                    ui.label("×");
+                    ui.add(egui::DragValue::new(&mut display.height).range(480..=8192));
+                }
+            }));
+            ui.end_row();
             
-            ui.horizontal(|ui| {
-                ui.label("Host:");
-                ui.text_edit_singleline(new_rdp_host);
+            ui.label("Scaling:");
+            egui::ComboBox::from_id_salt("rdp_scale").selected_text(format!("{}%", display.scale)).show_ui(ui, |ui| {
+                for scale in RdpDisplay::SCALES {
+                    ui.selectable_value(&mut display.scale, scale, format!("{}%", scale));
+                }
             });
-            
+            ui.end_row();
+        });
+        ui.checkbox(&mut display.smart_sizing, "Shrink the desktop to fit the window");
+        let mut hint = "Scaling enlarges text and apps in the session, e.g. 200% on a 4K screen.".to_string();
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            hint.push_str(" Remmina keeps the display settings of its own profile.");
+        }
+        ui.label(egui::RichText::new(hint).color(theme.text_secondary));
+    }
+    
+    fn draw_latency_policy(ui: &mut egui::Ui, theme: &Theme, policy: &mut LatencyPolicy, rdp_config: &RdpConfig) {
+        ui.checkbox(&mut policy.enabled, "Check the round trip before connecting");
+        ui.add_enabled_ui(policy.enabled, |ui| {
             ui.horizontal(|ui| {
-                ui.label("Port:");
-                ui.text_edit_singleline(new_rdp_port);
+                ui.label("Limit");
+                ui.add(egui::DragValue::new(&mut policy.max_rtt_ms).range(10..=2000).suffix(" ms"));
             });
-            
-            ui.horizontal(|ui| {
-                ui.label("Username:");
-                ui.text_edit_singleline(new_rdp_username);
+            ui.label("Over the limit:");
+            ui.radio_value(&mut policy.on_slow, SlowLinkAction::Warn, "Warn");
+            ui.radio_value(&mut policy.on_slow, SlowLinkAction::LowerQuality, "Connect with reduced quality");
+        });
+        ui.separator();
+        
+        // The last measurement stays in the menu until it closes; it is taken on a thread, which
+        // fills it in
+        let id = egui::Id::new(("latency_measured", &rdp_config.host, rdp_config.port));
+        let measurement = ui.data(|d| d.get_temp::<Measurement>(id)).map(|m| m.lock().unwrap().clone());
+        let measuring = matches!(measurement, Some(None));
+        if ui.add_enabled(!measuring, egui::Button::new("Measure Now")).clicked() {
+            let slot = Measurement::default();
+            ui.data_mut(|d| d.insert_temp(id, slot.clone()));
+            let (host, port) = (rdp_config.host.clone(), rdp_config.port);
+            let ctx = ui.ctx().clone();
+            std::thread::spawn(move || {
+                let runtime = tokio::runtime::Runtime::new().unwrap();
+                let result = runtime.block_on(latency::measure(&host, port))
+                    .map(|rtt| rtt.as_millis() as u64)
+                    .map_err(|e| e.to_string());
+                *slot.lock().unwrap() = Some(result);
+                ctx.request_repaint();
             });
-            
-            ui.horizontal(|ui| {
-                ui.label("Password:");
-                ui.add(egui::TextEdit::singleline(new_rdp_password).password(true));
+        }
+        match measurement {
+            Some(Some(Ok(rtt))) => {
+                let color = if rtt > policy.max_rtt_ms as u64 { theme.warning } else { theme.success };
+                ui.label(egui::RichText::new(format!("{} ms round trip", rtt)).color(color));
+            }
+            Some(Some(Err(e))) => {
+                ui.label(egui::RichText::new(e).color(theme.error));
+            }
+            Some(None) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(egui::RichText::new("Measuring…").color(theme.text_secondary));
+                });
+            }
+            None => {}
+        }
+    }
+    
+    // Acts on a row button; what takes a while is returned to run in the background, as the
+    // Home cards do
+    fn run_action(config: &mut Config, feedback: &mut Option<String>, index: usize, action: DeviceAction) -> Option<(String, &'static str, DeviceOperationType)> {
+        let device = config.devices.get_mut(index)?;
+        
+        match action {
+            DeviceAction::Wake => {
+                let wol_device = device.wol.clone()?;
+                return Some((device.name.clone(), "wake", DeviceOperationType::Wake(wol_device)));
+            }
+            DeviceAction::Connect => {
+                let rdp_config = device.rdp.clone()?;
+                return Some((device.name.clone(), "connect", DeviceOperationType::RdpConnect(rdp_config)));
+            }
+            DeviceAction::Ssh => {
+                let ssh_config = device.ssh.clone()?;
+                let operation = match device.wol.clone().filter(|_| ssh_config.wake_first) {
+                    Some(wol_device) => DeviceOperationType::WakeThenSsh(wol_device, ssh_config),
+                    None => DeviceOperationType::SshConnect(ssh_config),
+                };
+                return Some((device.name.clone(), "ssh", operation));
+            }
+            DeviceAction::Vnc => {
+                let vnc_config = device.vnc.clone()?;
+                return Some((device.name.clone(), "vnc", DeviceOperationType::VncConnect(vnc_config)));
+            }
+            DeviceAction::Ping => {
+                let address = device.monitor_address()?;
+                return Some((device.name.clone(), "ping", DeviceOperationType::Ping { name: device.name.clone(), address }));
+            }
+            DeviceAction::ExportRdp => {
+                let Some(rdp_config) = &device.rdp else { return None };
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Remote Desktop", &["rdp"])
+                    .set_file_name(format!("{}.rdp", device.name))
+                    .save_file()
+                {
+                    *feedback = Some(match crate::network::rdp::export_rdp_file(rdp_config, &path) {
+                        Ok(()) => format!("Saved {} to {}", device.name, path.display()),
+                        Err(e) => format!("Failed to export {}: {}", device.name, e),
+                    });
+                }
+            }
+            DeviceAction::Maintenance(Some(duration)) => device.start_maintenance(duration),
+            DeviceAction::Maintenance(None) => device.monitor.maintenance_until = None,
+            DeviceAction::Remove => {
+                thumbnails::remove(&device.name);
+                config.devices.remove(index);
+            }
+        }
+        None
+    }
+    
+    fn draw_add_device_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, form: &mut DeviceForm,
+                            migration_report: &mut Option<MigrationReport>) {
+        let editing = form.editing.as_ref().and_then(|id| config.devices.iter().position(|device| device.id == *id));
+        if form.editing.is_some() && editing.is_none() {
+            // Removed while being edited
+            *form = DeviceForm::default();
+        }
+        
+        Card::show(ui, theme, if editing.is_some() { "Edit Device" } else { "Add Device" }, |ui| {
+            if std::mem::take(&mut form.reveal) {
+                ui.scroll_to_cursor(Some(egui::Align::TOP));
+            }
+            egui::Grid::new("device_form_general").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut form.name);
+                ui.end_row();
+                
+                ui.label("Host:");
+                ui.add(egui::TextEdit::singleline(&mut form.host).hint_text("For RDP, SSH and VNC"));
+                ui.end_row();
+                
+                ui.label("Tag:");
+                ui.text_edit_singleline(&mut form.tag);
+                ui.end_row();
             });
+            ui.checkbox(&mut form.monitor, "Monitor online status");
             
-            ui.horizontal(|ui| {
-                ui.label("Domain:");
-                ui.text_edit_singleline(new_rdp_domain);
-            });
+            ui.add_space(8.0);
+            ui.checkbox(&mut form.wol, "Wake-on-LAN");
+            if form.wol {
+                egui::Grid::new("device_form_wol").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    ui.label("MAC Address:");
+                    ui.text_edit_singleline(&mut form.wol_mac);
+                    ui.end_row();
+                    
+                    ui.label("IP Address:");
+                    ui.add(egui::TextEdit::singleline(&mut form.wol_ip).hint_text("Empty broadcasts"));
+                    ui.end_row();
+                    
+                    ui.label("Port:");
+                    ui.text_edit_singleline(&mut form.wol_port);
+                    ui.end_row();
+                });
+                ui.checkbox(&mut form.wol_relay, "Fall back to an SSH relay")
+                    .on_hover_text("When the packet doesn't wake the device from here, an always-on machine on its network sends it with wakeonlan or etherwake");
+                if form.wol_relay {
+                    egui::Grid::new("device_form_wol_relay").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                        ui.label("Relay Host:");
+                        ui.add(egui::TextEdit::singleline(&mut form.wol_relay_host).hint_text("e.g. a router or NAS on the device's LAN"));
+                        ui.end_row();
+                        
+                        ui.label("SSH Port:");
+                        ui.text_edit_singleline(&mut form.wol_relay_port);
+                        ui.end_row();
+                        
+                        ui.label("Username:");
+                        ui.text_edit_singleline(&mut form.wol_relay_username);
+                        ui.end_row();
+                        
+                        ui.label("Key File:");
+                        ui.add(egui::TextEdit::singleline(&mut form.wol_relay_key_path).hint_text("Optional, must not need a passphrase prompt"));
+                        ui.end_row();
+                    });
+                }
+            }
             
-            ui.add_space(12.0);
+            ui.checkbox(&mut form.rdp, "Remote Desktop (RDP)");
+            if form.rdp {
+                egui::Grid::new("device_form_rdp").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    ui.label("Port:");
+                    ui.text_edit_singleline(&mut form.rdp_port);
+                    ui.end_row();
+                    
+                    ui.label("Username:");
+                    ui.text_edit_singleline(&mut form.rdp_username);
+                    ui.end_row();
+                    
+                    ui.label("Password:");
+                    ui.add(egui::TextEdit::singleline(&mut form.rdp_password).password(true));
+                    ui.end_row();
+                    
+                    ui.label("Domain:");
+                    ui.text_edit_singleline(&mut form.rdp_domain);
+                    ui.end_row();
+                });
+                if cfg!(windows) {
+                    ui.checkbox(&mut form.rdp_store_credentials, "Sign in with the saved password")
+                        .on_hover_text("Saves the username and password for the host with cmdkey just before mstsc starts, and removes them 30 seconds later. Otherwise mstsc asks for them");
+                }
+                ui.collapsing("Display", |ui| Self::draw_rdp_display(ui, theme, &mut form.rdp_display));
+                ui.collapsing("Redirection", |ui| {
+                    let redirect = &mut form.rdp_redirect;
+                    ui.checkbox(&mut redirect.clipboard, "Share the clipboard");
+                    ui.checkbox(&mut redirect.audio, "Play sound here");
+                    ui.checkbox(&mut redirect.microphone, "Use the microphone");
+                    ui.checkbox(&mut redirect.drives, "Share local drives")
+                        .on_hover_text("All drives on Windows, all mount points on Linux and macOS");
+                });
+                ui.collapsing("Gateway", |ui| {
+                    ui.checkbox(&mut form.rdp_gateway_enabled, "Connect through a Remote Desktop Gateway");
+                    if form.rdp_gateway_enabled {
+                        let gateway = &mut form.rdp_gateway;
+                        egui::Grid::new("device_form_rdp_gateway").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                            ui.label("Gateway:");
+                            ui.add(egui::TextEdit::singleline(&mut gateway.host).hint_text("rdgw.example.com"));
+                            ui.end_row();
+                            
+                            ui.label("Username:");
+                            ui.add(egui::TextEdit::singleline(&mut gateway.username).hint_text("Empty uses the RDP login"));
+                            ui.end_row();
+                            
+                            ui.label("Password:");
+                            ui.add(egui::TextEdit::singleline(&mut gateway.password).password(true));
+                            ui.end_row();
+                        });
+                        if cfg!(any(windows, target_os = "macos")) {
+                            ui.label(egui::RichText::new("The Remote Desktop client asks for the gateway password itself").color(theme.text_secondary));
+                        }
+                    }
+                });
+                ui.collapsing("Environment Variables", |ui| {
+                    ui.label(egui::RichText::new("One KEY=VALUE per line, e.g. KRB5CCNAME for Kerberos").color(theme.text_secondary));
+                    ui.add(egui::TextEdit::multiline(&mut form.rdp_env).desired_rows(3));
+                });
+            }
             
-            if GlassButton::show(ui, theme, "Add Connection", true).clicked() {
-                if !new_rdp_name.is_empty() && !new_rdp_host.is_empty() {
-                    let port = new_rdp_port.parse::<u16>().unwrap_or(3389);
-                    let domain = if new_rdp_domain.is_empty() { None } else { Some(new_rdp_domain.clone()) };
+            ui.checkbox(&mut form.ssh, "SSH");
+            if form.ssh {
+                egui::Grid::new("device_form_ssh").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    ui.label("Port:");
+                    ui.text_edit_singleline(&mut form.ssh_port);
+                    ui.end_row();
                     
-                    config.rdp_configs.push(RdpConfig {
-                        name: new_rdp_name.clone(),
-                        host: new_rdp_host.clone(),
-                        port,
-                        username: new_rdp_username.clone(),
-                        password: new_rdp_password.clone(),
-                        domain,
-                    });
+                    ui.label("Username:");
+                    ui.text_edit_singleline(&mut form.ssh_username);
+                    ui.end_row();
                     
-                    // Clear input fields
-                    new_rdp_name.clear();
-                    new_rdp_host.clear();
-                    *new_rdp_port = String::from("3389");
-                    new_rdp_username.clear();
-                    new_rdp_password.clear();
-                    new_rdp_domain.clear();
+                    ui.label("Key File:");
+                    ui.add(egui::TextEdit::singleline(&mut form.ssh_key_path).hint_text("Optional"));
+                    ui.end_row();
+                });
+                ui.add_enabled(form.wol, egui::Checkbox::new(&mut form.ssh_wake_first, "Wake the device first"))
+                    .on_hover_text("Sends the Wake-on-LAN packet unless the SSH port already answers, then waits up to two minutes for it before opening the session")
+                    .on_disabled_hover_text("Needs Wake-on-LAN");
+            }
+            
+            ui.checkbox(&mut form.vnc, "VNC");
+            if form.vnc {
+                egui::Grid::new("device_form_vnc").num_columns(2).spacing([8.0, 6.0]).show(ui, |ui| {
+                    ui.label("Port:");
+                    ui.text_edit_singleline(&mut form.vnc_port);
+                    ui.end_row();
+                    
+                    ui.label("Viewer:");
+                    ui.add(egui::TextEdit::singleline(&mut form.vnc_viewer).hint_text("Optional, found automatically"))
+                        .on_hover_text("e.g. the RealVNC or UltraVNC viewer on Windows; leave empty to use the one found");
+                    ui.end_row();
+                });
+            }
+            
+            ui.add_space(12.0);
+            
+            let Some(index) = editing else {
+                let device = form.build();
+                if GlassButton::show(ui, theme, "Add Device", true).clicked() {
+                    if let Some(device) = device {
+                        config.devices.push(device);
+                        *form = DeviceForm::default();
+                    }
                 }
+                return;
+            };
+            
+            let name_taken = config.devices.iter().enumerate().any(|(i, device)| i != index && device.name == form.name.trim());
+            if name_taken {
+                ui.label(egui::RichText::new(format!("⚠ Another device is called {}", form.name.trim())).color(theme.warning));
             }
+            let device = form.update(&config.devices[index]).filter(|_| !name_taken);
+            ui.horizontal(|ui| {
+                if GlassButton::show(ui, theme, "Save Changes", true).clicked() {
+                    if let Some(device) = device {
+                        let new_name = device.name.clone();
+                        let previous = std::mem::replace(&mut config.devices[index], device);
+                        // Schedules, mutes, history and the thumbnail go by the name
+                        if previous.name != new_name {
+                            migrate::rename(config, EntryKind::Device, &previous.name, &new_name).queue(migration_report);
+                        }
+                        *form = DeviceForm::default();
+                    }
+                }
+                if ui.button("Cancel").clicked() {
+                    *form = DeviceForm::default();
+                }
+            });
         });
     }
     
-    fn draw_wol_section(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, network_manager: &mut NetworkManager,
-                       new_wol_name: &mut String, new_wol_mac: &mut String,
-                       new_wol_ip: &mut String, new_wol_port: &mut String) {
-        
-        // WOL Devices List
-        Card::show(ui, theme, "Wake-on-LAN Devices", |ui| {
-            if config.wol_devices.is_empty() {
-                ui.label(egui::RichText::new("No WOL devices configured").color(theme.text_secondary));
+    fn selection_checkbox(ui: &mut egui::Ui, selected: &mut BTreeSet<String>, name: &str) {
+        let mut checked = selected.contains(name);
+        if ui.checkbox(&mut checked, "").on_hover_text("Select for bulk edit").changed() {
+            if checked {
+                selected.insert(name.to_string());
             } else {
-                let mut to_remove = None;
+                selected.remove(name);
+            }
+        }
+    }
+    
+    fn tag_chip(ui: &mut egui::Ui, theme: &Theme, tag: &str) {
+        if tag.is_empty() {
+            return;
+        }
+        egui::Frame::none()
+            .fill(theme.primary.gamma_multiply(0.15))
+            .rounding(egui::Rounding::same(4.0))
+            .inner_margin(egui::Margin::symmetric(6.0, 1.0))
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(tag).size(10.0).color(theme.primary));
+            });
+    }
+    
+    fn draw_duplicates_window(ctx: &egui::Context, theme: &Theme, config: &mut Config, groups: &[DuplicateGroup],
+                              open: &mut bool) {
+        if !*open {
+            return;
+        }
+        if groups.is_empty() {
+            *open = false;
+            return;
+        }
+        
+        let mut action: Option<(usize, DuplicateAction)> = None;
+        
+        egui::Window::new("Possible Duplicates")
+            .collapsible(false)
+            .open(open)
+            .default_width(460.0)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("These devices point at the same machine").color(theme.text_secondary));
+                ui.add_space(8.0);
                 
-                for (index, device) in config.wol_devices.iter().enumerate() {
-                    ui.horizontal(|ui| {
-                        ui.vertical(|ui| {
-                            ui.label(egui::RichText::new(&device.name).strong());
-                            ui.label(egui::RichText::new(format!("IP: {}", device.ip_address)).color(theme.text_secondary));
-                            ui.label(egui::RichText::new(format!("MAC: {}", device.mac_address)).color(theme.text_secondary));
-                        });
-                        
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                            if ui.button("🗑").clicked() {
-                                to_remove = Some(index);
-                            }
-                            
-                            if GlassButton::show(ui, theme, "Ping", false).clicked() {
-                                let runtime = tokio::runtime::Runtime::new().unwrap();
-                                runtime.block_on(async {
-                                    let _ = network_manager.check_device_status(device).await;
-                                });
-                            }
-                            
-                            if GlassButton::show(ui, theme, "Wake", true).clicked() {
-                                let runtime = tokio::runtime::Runtime::new().unwrap();
-                                runtime.block_on(async {
-                                    let _ = network_manager.wake_device(device).await;
+                egui::ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+                    for (index, group) in groups.iter().enumerate() {
+                        egui::Frame::none()
+                            .fill(theme.surface_variant)
+                            .rounding(egui::Rounding::same(6.0))
+                            .inner_margin(egui::Margin::same(10.0))
+                            .show(ui, |ui| {
+                                ui.set_width(ui.available_width());
+                                ui.label(egui::RichText::new(format!("Same {}", group.shared.join(", "))).strong());
+                                
+                                for &i in &group.devices {
+                                    let device = &config.devices[i];
+                                    ui.label(format!("{}  ({})", device.name, device.capabilities().join(", ")));
+                                }
+                                
+                                ui.add_space(6.0);
+                                ui.horizontal(|ui| {
+                                    if ui.button("Merge")
+                                        .on_hover_text("Combine into the first device, taking missing capabilities and empty fields from the others")
+                                        .clicked()
+                                    {
+                                        action = Some((index, duplicates::merge));
+                                    }
+                                    if ui.button("Not Duplicates").clicked() {
+                                        action = Some((index, duplicates::ignore));
+                                    }
                                 });
-                            }
-                            
-                            let is_online = network_manager.wol_devices
-                                .iter()
-                                .find(|d| d.device.name == device.name)
-                                .map(|d| d.is_online)
-                                .unwrap_or(false);
-                            
-                            StatusIndicator::show(ui, theme, is_online, if is_online { "Online" } else { "Offline" });
-                        });
-                    });
-                    ui.separator();
+                            });
+                        ui.add_space(8.0);
+                    }
+                });
+            });
+        
+        if let Some((index, apply)) = action {
+            apply(config, &groups[index]);
+        }
+    }
+    
+    // Drawn by the app on every tab, so it stays open when popped out into its own window
+    pub fn draw_notes_window(ctx: &egui::Context, config: &mut Config, notes_window: &mut NotesWindow) {
+        let Some(name) = notes_window.device.clone() else { return };
+        let Some(index) = config.devices.iter().position(|device| device.name == name) else {
+            notes_window.device = None;
+            return;
+        };
+        
+        let theme = Theme::current();
+        let open = ToolWindow::show(ctx, "device_notes", &format!("Notes: {}", name), egui::vec2(420.0, 360.0), |ui| {
+            if vault::is_unlocked() {
+                Self::draw_notes(ui, &theme, &mut config.devices[index].notes, notes_window);
+                // Repainted so the window locks itself on time
+                ctx.request_repaint_after(std::time::Duration::from_secs(1));
+            } else if let Some(settings) = &config.vault {
+                ui.label(egui::RichText::new(format!("{} note(s), encrypted", config.devices[index].notes.len())).color(theme.text_secondary));
+                for note in &config.devices[index].notes {
+                    ui.label(format!("🔒 {}", note.title));
                 }
-                
-                if let Some(index) = to_remove {
-                    config.wol_devices.remove(index);
+                ui.add_space(8.0);
+                let response = ui.add(egui::TextEdit::singleline(&mut notes_window.passphrase).password(true).hint_text("Passphrase"));
+                let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if ui.button("Unlock").clicked() || submitted {
+                    notes_window.error = vault::unlock(settings, &notes_window.passphrase).err().map(|e| e.to_string());
+                    notes_window.passphrase.clear();
                 }
+            } else {
+                ui.label("Choose a passphrase for encrypted notes. It can't be recovered if you forget it.");
+                ui.add(egui::TextEdit::singleline(&mut notes_window.passphrase).password(true).hint_text("Passphrase"));
+                ui.add(egui::TextEdit::singleline(&mut notes_window.confirm).password(true).hint_text("Repeat passphrase"));
+                if ui.button("Create Vault").clicked() {
+                    if notes_window.passphrase != notes_window.confirm {
+                        notes_window.error = Some("The passphrases don't match".to_string());
+                    } else {
+                        match vault::create(&notes_window.passphrase) {
+                            Ok(settings) => {
+                                config.vault = Some(settings);
+                                notes_window.error = None;
+                            }
+                            Err(e) => notes_window.error = Some(e.to_string()),
+                        }
+                    }
+                    notes_window.passphrase.clear();
+                    notes_window.confirm.clear();
+                }
+            }
+            
+            if let Some(error) = &notes_window.error {
+                ui.label(egui::RichText::new(error).color(theme.error));
             }
         });
         
-        ui.add_space(16.0);
-        
-        // Add WOL Device
-        Card::show(ui, theme, "Add WOL Device", |ui| {
-            ui.horizontal(|ui| {
-                ui.label("Device Name:");
-                ui.text_edit_singleline(new_wol_name);
-            });
-            
-            ui.horizontal(|ui| {
-                ui.label("MAC Address:");
-                ui.text_edit_singleline(new_wol_mac);
-            });
-            
-            ui.horizontal(|ui| {
-                ui.label("IP Address:");
-                ui.text_edit_singleline(new_wol_ip);
-            });
-            
+        if !open {
+            notes_window.device = None;
+            notes_window.new_title.clear();
+            notes_window.new_text.clear();
+        }
+    }
+    
+    fn draw_notes(ui: &mut egui::Ui, theme: &Theme, notes: &mut Vec<SecretNote>, notes_window: &mut NotesWindow) {
+        let mut remove = None;
+        for (index, note) in notes.iter().enumerate() {
             ui.horizontal(|ui| {
-                ui.label("Port:");
-                ui.text_edit_singleline(new_wol_port);
+                ui.label(egui::RichText::new(&note.title).strong());
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if ui.small_button("🗑").clicked() {
+                        remove = Some(index);
+                    }
+                    if let Ok(text) = note.text() {
+                        if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                            ui.ctx().copy_text(text);
+                        }
+                    }
+                });
             });
-            
-            ui.add_space(12.0);
-            
-            if GlassButton::show(ui, theme, "Add Device", true).clicked() {
-                if !new_wol_name.is_empty() && !new_wol_mac.is_empty() {
-                    let port = new_wol_port.parse::<u16>().unwrap_or(9);
-                    let ip_address = if new_wol_ip.is_empty() { 
-                        "255.255.255.255".to_string() 
-                    } else { 
-                        new_wol_ip.clone() 
-                    };
-                    
-                    config.wol_devices.push(WolDevice {
-                        name: new_wol_name.clone(),
-                        mac_address: new_wol_mac.clone(),
-                        ip_address,
-                        port,
-                    });
-                    
-                    // Clear input fields
-                    new_wol_name.clear();
-                    new_wol_mac.clear();
-                    new_wol_ip.clear();
-                    *new_wol_port = String::from("9");
+            match note.text() {
+                Ok(text) => {
+                    ui.add(egui::Label::new(egui::RichText::new(text).monospace()).selectable(true));
                 }
+                Err(e) => {
+                    ui.label(egui::RichText::new(e.to_string()).color(theme.error));
+                }
+            }
+            ui.separator();
+        }
+        if let Some(index) = remove {
+            notes.remove(index);
+        }
+        
+        ui.add(egui::TextEdit::singleline(&mut notes_window.new_title).hint_text("Title, e.g. BIOS password"));
+        ui.add(egui::TextEdit::multiline(&mut notes_window.new_text).desired_rows(3).hint_text("Note"));
+        ui.horizontal(|ui| {
+            let can_add = !notes_window.new_title.trim().is_empty() && !notes_window.new_text.is_empty();
+            if ui.add_enabled(can_add, egui::Button::new("Add Note")).clicked() {
+                match SecretNote::new(&notes_window.new_title, &notes_window.new_text) {
+                    Ok(note) => {
+                        notes.push(note);
+                        notes_window.new_title.clear();
+                        notes_window.new_text.clear();
+                        notes_window.error = None;
+                    }
+                    Err(e) => notes_window.error = Some(e.to_string()),
+                }
+            }
+            if ui.button("🔒 Lock").clicked() {
+                vault::lock();
+            }
+            if let Some(remaining) = vault::remaining() {
+                ui.label(egui::RichText::new(format!("Locks in {} min", remaining.as_secs() / 60 + 1)).color(theme.text_secondary));
             }
         });
     }
-}
\ No newline at end of file
+    
+    fn draw_bulk_edit_window(ctx: &egui::Context, theme: &Theme, config: &mut Config, bulk_edit: &mut BulkEdit) {
+        if !bulk_edit.open {
+            return;
+        }
+        
+        let mut open = true;
+        let mut apply = false;
+        
+        let any_wol = bulk_edit.selected(config).any(|d| d.wol.is_some());
+        let any_rdp = bulk_edit.selected(config).any(|d| d.rdp.is_some());
+        
+        egui::Window::new(format!("Bulk Edit ({} devices)", bulk_edit.selected_count()))
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new("Only ticked fields are changed").color(theme.text_secondary));
+                ui.add_space(8.0);
+                
+                egui::Grid::new("bulk_edit_fields").num_columns(2).spacing([12.0, 8.0]).show(ui, |ui| {
+                    ui.checkbox(&mut bulk_edit.set_tag, "Tag");
+                    ui.add_enabled(bulk_edit.set_tag, egui::TextEdit::singleline(&mut bulk_edit.tag).hint_text("Empty removes the tag"));
+                    ui.end_row();
+                    
+                    if any_wol {
+                        ui.checkbox(&mut bulk_edit.set_port, "WoL port");
+                        ui.add_enabled(bulk_edit.set_port, egui::TextEdit::singleline(&mut bulk_edit.port).hint_text("9"));
+                        ui.end_row();
+                    }
+                    
+                    if any_rdp {
+                        ui.checkbox(&mut bulk_edit.set_domain, "RDP domain");
+                        ui.add_enabled(bulk_edit.set_domain, egui::TextEdit::singleline(&mut bulk_edit.domain).hint_text("Empty removes the domain"));
+                        ui.end_row();
+                        
+                        ui.checkbox(&mut bulk_edit.set_credentials, "RDP username");
+                        ui.add_enabled(bulk_edit.set_credentials, egui::TextEdit::singleline(&mut bulk_edit.username));
+                        ui.end_row();
+                        
+                        ui.label("RDP password");
+                        ui.add_enabled(bulk_edit.set_credentials, egui::TextEdit::singleline(&mut bulk_edit.password).password(true));
+                        ui.end_row();
+                    }
+                });
+                
+                let port_invalid = bulk_edit.set_port && any_wol && bulk_edit.port.trim().parse::<u16>().is_err();
+                if port_invalid {
+                    ui.label(egui::RichText::new("Port must be a number from 0 to 65535").color(theme.error));
+                }
+                
+                ui.add_space(12.0);
+                let anything = bulk_edit.set_tag || bulk_edit.set_port || bulk_edit.set_domain || bulk_edit.set_credentials;
+                if ui.add_enabled(anything && !port_invalid, egui::Button::new("Apply")).clicked() {
+                    apply = true;
+                }
+            });
+        
+        if apply {
+            bulk_edit.apply(config);
+        } else if !open {
+            bulk_edit.open = false;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use eframe::egui;
+use crate::config::Config;
+use crate::config::schedule::{ScheduledAction, ScheduledActionKind};
+use crate::ui::components::{format_countdown, Card, FocusFrame};
+use crate::ui::route::Navigation;
+use crate::ui::theme::Theme;
+
+const DAY_LABELS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+pub struct SchedulePanel;
+
+impl SchedulePanel {
+    pub fn draw(
+        ui: &mut egui::Ui,
+        config: &mut Config,
+        new_kind: &mut ScheduledActionKind,
+        new_target: &mut String,
+        new_time: &mut String,
+        navigation: &Navigation,
+    ) {
+        let theme = Theme::current();
+
+        ui.heading("Schedule");
+        ui.add_space(20.0);
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            Self::draw_actions_card(ui, &theme, config, navigation);
+            ui.add_space(20.0);
+            Self::draw_add_card(ui, &theme, config, new_kind, new_target, new_time);
+        });
+    }
+
+    fn draw_actions_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, navigation: &Navigation) {
+        let now = chrono::Local::now();
+        let mut to_remove = None;
+
+        Card::show(ui, theme, "Scheduled Actions", |ui| {
+            if config.scheduled_actions.is_empty() {
+                ui.label("No scheduled actions");
+                return;
+            }
+
+            let vpn_names: Vec<String> = config.vpn_configs.iter().map(|v| v.name.clone()).collect();
+            let device_names: Vec<String> = config.wol_devices().map(|d| d.name.clone()).collect();
+
+            for (index, action) in config.scheduled_actions.iter_mut().enumerate() {
+                let target = action.target.clone();
+                FocusFrame::show(ui, theme, navigation, &target, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut action.enabled, "");
+                        let verb = match action.kind {
+                            ScheduledActionKind::WakeDevice => "Wake",
+                            ScheduledActionKind::ConnectVpn => "Connect",
+                        };
+                        ui.label(egui::RichText::new(format!("{} {}", verb, action.target)).strong());
+                        ui.label("at");
+                        ui.add(egui::TextEdit::singleline(&mut action.time).desired_width(50.0));
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("🗑").on_hover_text("Remove").clicked() {
+                                to_remove = Some(index);
+                            }
+                        });
+                    });
+
+                    ui.horizontal(|ui| {
+                        for (day, label) in action.days.iter_mut().zip(DAY_LABELS) {
+                            if ui.selectable_label(*day, label).clicked() {
+                                *day = !*day;
+                            }
+                        }
+
+                        ui.add_space(8.0);
+                        let targets = match action.kind {
+                            ScheduledActionKind::WakeDevice => &device_names,
+                            ScheduledActionKind::ConnectVpn => &vpn_names,
+                        };
+                        if !targets.contains(&action.target) {
+                            ui.label(egui::RichText::new("Target no longer exists").color(theme.error));
+                        } else if action.parsed_time().is_none() {
+                            ui.label(egui::RichText::new("Use 24-hour HH:MM").color(theme.error));
+                        } else if let Some(run) = action.next_run(now) {
+                            ui.label(egui::RichText::new(format!(
+                                "Next: {} (in {})",
+                                run.format("%a %H:%M"),
+                                format_countdown((run - now).num_seconds())
+                            )).color(theme.text_secondary));
+                        } else if action.enabled {
+                            ui.label(egui::RichText::new("No days selected").color(theme.warning));
+                        }
+                    });
+                });
+            }
+        });
+
+        if let Some(index) = to_remove {
+            config.scheduled_actions.remove(index);
+        }
+    }
+
+    fn draw_add_card(
+        ui: &mut egui::Ui,
+        theme: &Theme,
+        config: &mut Config,
+        new_kind: &mut ScheduledActionKind,
+        new_target: &mut String,
+        new_time: &mut String,
+    ) {
+        Card::show(ui, theme, "Add Scheduled Action", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Action:");
+                let previous_kind = *new_kind;
+                egui::ComboBox::from_id_salt("schedule_kind")
+                    .selected_text(match new_kind {
+                        ScheduledActionKind::WakeDevice => "Wake device",
+                        ScheduledActionKind::ConnectVpn => "Connect VPN",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(new_kind, ScheduledActionKind::WakeDevice, "Wake device");
+                        ui.selectable_value(new_kind, ScheduledActionKind::ConnectVpn, "Connect VPN");
+                    });
+                if *new_kind != previous_kind {
+                    new_target.clear();
+                }
+            });
+
+            let targets: Vec<&String> = match new_kind {
+                ScheduledActionKind::WakeDevice => config.wol_devices().map(|d| &d.name).collect(),
+                ScheduledActionKind::ConnectVpn => config.vpn_configs.iter().map(|v| &v.name).collect(),
+            };
+
+            ui.horizontal(|ui| {
+                ui.label("Target:");
+                egui::ComboBox::from_id_salt("schedule_target")
+                    .selected_text(if new_target.is_empty() { "Select..." } else { new_target.as_str() })
+                    .show_ui(ui, |ui| {
+                        for target in &targets {
+                            ui.selectable_value(new_target, target.to_string(), target.as_str());
+                        }
+                    });
+
+                ui.label("Time:");
+                ui.add(egui::TextEdit::singleline(new_time).desired_width(50.0).hint_text("HH:MM"));
+            });
+
+            if targets.is_empty() {
+                ui.label(egui::RichText::new("Add a device or VPN first").color(theme.text_secondary));
+            }
+
+            ui.add_space(8.0);
+
+            let action = ScheduledAction::new(*new_kind, new_target.clone(), new_time.trim().to_string());
+            let time_valid = action.parsed_time().is_some();
+            if !time_valid {
+                ui.label(egui::RichText::new("Use 24-hour HH:MM").color(theme.error));
+            }
+            if ui.add_enabled(time_valid && !new_target.is_empty(), egui::Button::new("Add")).clicked() {
+                config.scheduled_actions.push(action);
+                new_target.clear();
+            }
+        });
+    }
+}
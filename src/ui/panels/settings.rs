@@ -1,14 +1,110 @@
 use eframe::egui;
-use crate::config::Config;
+use crate::config::export::{self, ExportFormat};
+use crate::config::secrets;
+use crate::network::{dashboard, nmcli, providers::{self, Provider, Server}, wg_router::{self, RouterFormat}, wg_userspace};
+use crate::config::{Config, VpnType, SoundCues, StatusPalette, ThemeScheduleMode, UpdateSource, UpdateSourceKind, WireGuardBackend};
 use crate::system::{SystemInfo, installer::PackageInstaller, updater::{AppUpdater, UpdateInfo}};
-use crate::ui::components::{Card, GlassButton};
-use crate::ui::theme::Theme;
+use crate::system::autostart;
+use crate::system::sound::{self, Cue};
+use crate::ui::components::{Card, GlassButton, StatusDot};
+use crate::ui::theme::{StatusState, Theme};
 
 pub struct SettingsPanel;
 
+// The VPN Providers card: the server list of the chosen provider, loaded in the background, and
+// the server picked from it
+pub struct ProviderBrowser {
+    provider: Provider,
+    servers: Option<Result<Vec<Server>, String>>,
+    receiver: Option<std::sync::mpsc::Receiver<Result<Vec<Server>, String>>>,
+    country: String,
+    // Empty for any city
+    city: String,
+    // Empty for the least loaded
+    server: String,
+    vpn_type: VpnType,
+    name: String,
+    result: Option<Result<String, String>>,
+}
+
+impl Default for ProviderBrowser {
+    fn default() -> Self {
+        Self {
+            provider: Provider::Mullvad,
+            servers: None,
+            receiver: None,
+            country: String::new(),
+            city: String::new(),
+            server: String::new(),
+            vpn_type: VpnType::WireGuard,
+            name: String::new(),
+            result: None,
+        }
+    }
+}
+
+impl ProviderBrowser {
+    fn load(&mut self) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let provider = self.provider;
+        self.receiver = Some(rx);
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let _ = tx.send(rt.block_on(provider.servers()).map_err(|e| e.to_string()));
+        });
+    }
+    
+    fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else { return };
+        match receiver.try_recv() {
+            Ok(servers) => {
+                self.servers = Some(servers);
+                self.receiver = None;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => self.receiver = None,
+        }
+    }
+}
+
+// The Config Encryption card, and the unlock prompt at startup: the passwords as typed and the
+// outcome of the last change
+#[derive(Default)]
+pub struct EncryptionForm {
+    pub current: String,
+    pub password: String,
+    pub confirm: String,
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
+impl EncryptionForm {
+    fn clear(&mut self) {
+        self.current.clear();
+        self.password.clear();
+        self.confirm.clear();
+    }
+    
+    // The new password, when both fields agree
+    fn new_password(&mut self) -> Option<String> {
+        if self.password.is_empty() {
+            self.error = Some("Enter a password".to_string());
+        } else if self.password != self.confirm {
+            self.error = Some("The passwords don't match".to_string());
+        } else {
+            return Some(self.password.clone());
+        }
+        None
+    }
+}
+
 impl SettingsPanel {
-    pub fn draw(ui: &mut egui::Ui, config: &mut Config, system_info: &mut SystemInfo, package_installer: &PackageInstaller, app_updater: &AppUpdater, update_info: &mut Option<UpdateInfo>, checking_updates: &mut bool, installing_update: &mut bool, update_progress: &mut String) {
-        let theme = Theme::new();
+    pub fn draw(ui: &mut egui::Ui, config: &mut Config, system_info: &mut SystemInfo, package_installer: &PackageInstaller, app_updater: &AppUpdater, update_info: &mut Option<UpdateInfo>, checking_updates: &mut bool, installing_update: &mut bool, update_progress: &mut String,
+                update_error: &Option<String>, update_checked_at: Option<u64>, update_check_requested: &mut bool,
+                checking_dependency_upgrades: bool, dependency_upgrade_requested: &mut bool, dashboard_error: &Option<String>,
+                dashboard_pairing: &mut Option<(String, egui::TextureHandle)>, dashboard_pair_requested: &mut bool,
+                provider_browser: &mut ProviderBrowser, encryption_form: &mut EncryptionForm) {
+        let theme = Theme::current();
         
         egui::ScrollArea::vertical().show(ui, |ui| {
             ui.heading("Settings");
@@ -20,16 +116,52 @@ impl SettingsPanel {
         Self::draw_vpn_settings_card(ui, &theme, config);
         ui.add_space(16.0);
         
+        Self::draw_always_on_card(ui, &theme, config);
+        ui.add_space(16.0);
+        
+        Self::draw_remote_access_card(ui, &theme, config);
+        ui.add_space(16.0);
+        
+        Self::draw_stats_card(ui, &theme);
+        ui.add_space(16.0);
+        
+        Self::draw_export_card(ui, &theme, config);
+        ui.add_space(16.0);
+        
+        Self::draw_router_peers_card(ui, &theme, config);
+        ui.add_space(16.0);
+        
+        Self::draw_providers_card(ui, &theme, config, provider_browser);
+        ui.add_space(16.0);
+        
+        Self::draw_dashboard_card(ui, &theme, config, dashboard_error, dashboard_pairing, dashboard_pair_requested);
+        ui.add_space(16.0);
+        
+        Self::draw_encryption_card(ui, &theme, config, encryption_form);
+        ui.add_space(16.0);
+        
+        Self::draw_notifications_card(ui, &theme, config);
+        ui.add_space(16.0);
+        
         Self::draw_system_info_card(ui, &theme, system_info);
         ui.add_space(16.0);
         
-        Self::draw_dependencies_card(ui, &theme, system_info, package_installer);
+        Self::draw_storage_card(ui, &theme);
+        ui.add_space(16.0);
+        
+        Self::draw_dependencies_card(ui, &theme, system_info, package_installer, checking_dependency_upgrades, dependency_upgrade_requested);
         ui.add_space(16.0);
         
-        Self::draw_updates_card(ui, &theme, app_updater, update_info, checking_updates, installing_update, update_progress);
+        Self::draw_updates_card(ui, &theme, app_updater, update_info, checking_updates, installing_update, update_progress,
+                                update_error, update_checked_at, update_check_requested, config);
         ui.add_space(16.0);
         
-            Self::draw_about_card(ui, &theme);
+        if config.developer.unlocked {
+            Self::draw_developer_card(ui, &theme, config);
+            ui.add_space(16.0);
+        }
+        
+            Self::draw_about_card(ui, &theme, config);
         });
     }
     
@@ -48,8 +180,103 @@ impl SettingsPanel {
                 }
             });
             
+            
             ui.add_space(12.0);
-            ui.label(egui::RichText::new("Restart required for theme changes to take effect").color(theme.text_secondary));
+            ui.horizontal(|ui| {
+                ui.label("Switch automatically:");
+                egui::ComboBox::from_id_salt("theme_schedule_mode")
+                    .selected_text(match config.theme_schedule.mode {
+                        ThemeScheduleMode::Off => "Off",
+                        ThemeScheduleMode::Fixed => "Fixed schedule",
+                        ThemeScheduleMode::SunriseSunset => "Sunrise / sunset",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut config.theme_schedule.mode, ThemeScheduleMode::Off, "Off");
+                        ui.selectable_value(&mut config.theme_schedule.mode, ThemeScheduleMode::Fixed, "Fixed schedule");
+                        ui.selectable_value(&mut config.theme_schedule.mode, ThemeScheduleMode::SunriseSunset, "Sunrise / sunset");
+                    });
+            });
+            
+            match config.theme_schedule.mode {
+                ThemeScheduleMode::Off => {}
+                ThemeScheduleMode::Fixed => {
+                    ui.horizontal(|ui| {
+                        ui.label("Light from:");
+                        ui.add(egui::TextEdit::singleline(&mut config.theme_schedule.light_from).desired_width(60.0));
+                        ui.label("Dark from:");
+                        ui.add(egui::TextEdit::singleline(&mut config.theme_schedule.dark_from).desired_width(60.0));
+                    });
+                    if config.theme_schedule.prefers_dark().is_none() {
+                        ui.label(egui::RichText::new("Use 24-hour HH:MM times").color(theme.error));
+                    }
+                }
+                ThemeScheduleMode::SunriseSunset => {
+                    ui.horizontal(|ui| {
+                        ui.label("Latitude:");
+                        ui.add(egui::DragValue::new(&mut config.theme_schedule.latitude).range(-90.0..=90.0).speed(0.1));
+                        ui.label("Longitude:");
+                        ui.add(egui::DragValue::new(&mut config.theme_schedule.longitude).range(-180.0..=180.0).speed(0.1));
+                    });
+                    ui.label(egui::RichText::new("Light between local sunrise and sunset, dark otherwise").color(theme.text_secondary));
+                }
+            }
+            
+            if config.theme_schedule.mode != ThemeScheduleMode::Off {
+                let current = if config.effective_dark_mode() { "Dark" } else { "Light" };
+                ui.label(egui::RichText::new(format!("Currently: {} (overrides the theme above)", current)).color(theme.text_secondary));
+            }
+            
+            ui.add_space(12.0);
+            ui.horizontal(|ui| {
+                ui.label("Status colors:");
+                egui::ComboBox::from_id_salt("status_palette")
+                    .selected_text(match config.status_palette {
+                        StatusPalette::Standard => "Standard",
+                        StatusPalette::HighContrast => "High contrast",
+                        StatusPalette::ColorblindSafe => "Colorblind-friendly",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut config.status_palette, StatusPalette::Standard, "Standard");
+                        ui.selectable_value(&mut config.status_palette, StatusPalette::HighContrast, "High contrast");
+                        ui.selectable_value(&mut config.status_palette, StatusPalette::ColorblindSafe, "Colorblind-friendly");
+                    });
+            });
+            ui.checkbox(&mut config.status_shapes, "Use different shapes for each status");
+            
+            // Preview of the palette and shapes currently applied
+            ui.horizontal(|ui| {
+                for (state, label) in [
+                    (StatusState::Online, "Online"),
+                    (StatusState::Offline, "Offline"),
+                    (StatusState::Connecting, "Connecting"),
+                    (StatusState::Error, "Error"),
+                ] {
+                    StatusDot::show(ui, theme, state, 12.0);
+                    ui.label(egui::RichText::new(label).color(theme.get_state_color(state)));
+                    ui.add_space(8.0);
+                }
+            });
+            
+            ui.add_space(8.0);
+            ui.checkbox(&mut config.capture_thumbnails, "Capture device thumbnails from RDP and VNC sessions");
+            ui.label(egui::RichText::new(format!(
+                "The whole screen is captured {} seconds after a session starts, so keep its window in front; saved in {}",
+                crate::system::thumbnails::SESSION_DELAY.as_secs(),
+                crate::system::thumbnails::dir().display()
+            )).color(theme.text_secondary));
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label("Error messages:");
+                egui::ComboBox::from_id_salt("error_language")
+                    .selected_text(config.language.label())
+                    .show_ui(ui, |ui| {
+                        for language in crate::config::Language::ALL {
+                            ui.selectable_value(&mut config.language, language, language.label());
+                        }
+                    });
+            });
+            ui.label(egui::RichText::new("Errors keep their code, e.g. VPN-002, in every language").color(theme.text_secondary));
         });
     }
     
@@ -63,8 +290,708 @@ impl SettingsPanel {
             });
             
             ui.add_space(8.0);
-            ui.label(egui::RichText::new("Auto-connect will use the first available VPN configuration").color(theme.text_secondary));
+            let startup = config.startup_vpn().map(|vpn_config| vpn_config.name.clone());
+            let mut selected = startup.clone();
+            ui.horizontal(|ui| {
+                ui.label("Connection:");
+                egui::ComboBox::from_id_salt("startup_vpn")
+                    .selected_text(selected.as_deref().unwrap_or("None"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "None");
+                        for vpn_config in &config.vpn_configs {
+                            ui.selectable_value(&mut selected, Some(vpn_config.name.clone()), &vpn_config.name);
+                        }
+                    });
+            });
+            if selected != startup {
+                config.set_startup_vpn(selected.as_deref());
+            }
+            ui.label(egui::RichText::new("Also set with the Startup checkbox on a connection in the VPN tab").color(theme.text_secondary));
+            
+            ui.add_space(8.0);
+            ui.checkbox(&mut config.check_captive_portal, "Check for captive portals before auto-connect");
+            ui.label(egui::RichText::new("Hotel and airport Wi-Fi often require a browser login before a VPN can connect").color(theme.text_secondary));
+            
+            ui.add_space(8.0);
+            ui.checkbox(&mut config.manage_config_files, "Keep copies of VPN config files with the app settings");
+            ui.label(egui::RichText::new(format!(
+                "Imported files are copied to {} and stored as relative paths, so the settings folder can move between machines",
+                Config::managed_files_dir().display()
+            )).color(theme.text_secondary));
+            
+            if config.manage_config_files {
+                let external = config.vpn_configs.iter()
+                    .filter(|c| c.uses_config_file() && std::path::Path::new(&c.config_path).is_absolute())
+                    .count();
+                if external > 0 && ui.button(format!("Copy {} Existing File(s)", external)).clicked() {
+                    match config.adopt_config_files() {
+                        Ok(count) => log::info!("Copied {} VPN config file(s) into the managed directory", count),
+                        Err(e) => log::warn!("Failed to copy VPN config files: {}", e),
+                    }
+                }
+            }
+            
+            if wg_userspace::is_supported() {
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    ui.label("WireGuard backend:");
+                    egui::ComboBox::from_id_salt("wireguard_backend")
+                        .selected_text(match config.wireguard_backend {
+                            WireGuardBackend::Auto => "Automatic",
+                            WireGuardBackend::Kernel => "Kernel (wg-quick)",
+                            WireGuardBackend::Userspace => "Userspace",
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut config.wireguard_backend, WireGuardBackend::Auto, "Automatic");
+                            ui.selectable_value(&mut config.wireguard_backend, WireGuardBackend::Kernel, "Kernel (wg-quick)");
+                            ui.selectable_value(&mut config.wireguard_backend, WireGuardBackend::Userspace, "Userspace");
+                        });
+                });
+                let found = match wg_userspace::find_implementation() {
+                    Some((_, path)) => format!("Userspace implementation: {}", path.display()),
+                    None => "No userspace implementation found (boringtun-cli or wireguard-go)".to_string(),
+                };
+                ui.label(egui::RichText::new(found).color(theme.text_secondary));
+                ui.label(egui::RichText::new("Automatic uses the kernel module when it is loaded or installed, otherwise the userspace implementation").color(theme.text_secondary));
+            }
+            
+            if nmcli::is_supported() {
+                ui.add_space(8.0);
+                ui.checkbox(&mut config.use_network_manager, "Connect OpenVPN and WireGuard through NetworkManager");
+                ui.label(egui::RichText::new(
+                    "Profiles are imported into NetworkManager and activated with nmcli, without sudo. Takes effect on the next connect; the other VPN types keep their own clients"
+                ).color(theme.text_secondary));
+            }
+        });
+    }
+    
+    fn draw_always_on_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
+        Card::show(ui, theme, "Always-on VPN", |ui| {
+            let always_on = &mut config.always_on;
+            ui.checkbox(&mut always_on.enabled, "Keep a VPN connected");
+            ui.label(egui::RichText::new(
+                "Connected when the app starts and reconnected whenever it drops, even if its own reconnect setting is off. Disconnecting it by hand pauses this until it is connected again"
+            ).color(theme.text_secondary));
+            
+            ui.add_space(8.0);
+            ui.add_enabled_ui(always_on.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Connection:");
+                    egui::ComboBox::from_id_salt("always_on_vpn")
+                        .selected_text(if always_on.vpn.is_empty() { "None" } else { always_on.vpn.as_str() })
+                        .show_ui(ui, |ui| {
+                            for vpn_config in &config.vpn_configs {
+                                ui.selectable_value(&mut always_on.vpn, vpn_config.name.clone(), &vpn_config.name);
+                            }
+                        });
+                });
+            });
+            
+            if !autostart::is_supported() {
+                return;
+            }
+            ui.add_space(8.0);
+            let error_id = egui::Id::new("always_on_autostart_error");
+            let mut start_at_login = always_on.start_at_login;
+            if ui.checkbox(&mut start_at_login, "Start VPN Manager minimized at login").changed() {
+                let result = if start_at_login { autostart::register() } else { autostart::unregister() };
+                match result {
+                    Ok(()) => {
+                        always_on.start_at_login = start_at_login;
+                        ui.data_mut(|d| d.remove::<String>(error_id));
+                    }
+                    Err(e) => {
+                        log::warn!("Could not change starting at login: {}", e);
+                        ui.data_mut(|d| d.insert_temp(error_id, e.to_string()));
+                    }
+                }
+            }
+            if let Some(error) = ui.data(|d| d.get_temp::<String>(error_id)) {
+                ui.label(egui::RichText::new(error).color(theme.error));
+            }
+            let hint = if cfg!(windows) {
+                "A logon task when the app runs elevated, so the VPN clients get admin rights; otherwise a Run entry for your user"
+            } else if cfg!(target_os = "macos") {
+                "A LaunchAgent in ~/Library/LaunchAgents"
+            } else {
+                "An autostart entry in ~/.config/autostart. To have the tunnel up before login instead, use ⏻ or ⚙ on the connection in the VPN tab"
+            };
+            ui.label(egui::RichText::new(hint).color(theme.text_secondary));
+        });
+    }
+    
+    fn draw_remote_access_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
+        Card::show(ui, theme, "Remote Access", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("SSH terminal:");
+                let hint = if cfg!(windows) { "wt, putty or a full path" } else { "e.g. konsole or putty" };
+                ui.add(egui::TextEdit::singleline(&mut config.ssh_terminal).hint_text(hint));
+            });
+            let hint = if cfg!(windows) {
+                "Empty opens SSH sessions in Windows Terminal when it is installed, else in a console window. PuTTY gets the host, port and key as its own options, and needs the key in .ppk format"
+            } else {
+                "Empty uses $TERMINAL, else the first of x-terminal-emulator, gnome-terminal, konsole, xfce4-terminal, alacritty and xterm. PuTTY gets the host, port and key as its own options"
+            };
+            ui.label(egui::RichText::new(hint).color(theme.text_secondary));
+        });
+    }
+    
+    fn draw_stats_card(ui: &mut egui::Ui, theme: &Theme) {
+        use crate::config::history;
+        
+        Card::show(ui, theme, "Usage Statistics", |ui| {
+            let stats = history::stats();
+            
+            ui.horizontal(|ui| {
+                ui.label("Most used VPN:");
+                let most_used = match &stats.most_used_vpn {
+                    Some((name, count)) => format!("{} ({} connections)", name, count),
+                    None => "None yet".to_string(),
+                };
+                ui.label(egui::RichText::new(most_used).color(theme.text_secondary));
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Total tunnel time:");
+                let hours = stats.total_tunnel_secs as f64 / 3600.0;
+                ui.label(egui::RichText::new(format!("{:.1} hours", hours)).color(theme.text_secondary));
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Wake success rate:");
+                let rate = match stats.wake_success_rate() {
+                    Some(rate) => format!("{:.0}% ({} of {})", rate, stats.wake_successes, stats.wake_attempts),
+                    None => "No wake attempts yet".to_string(),
+                };
+                ui.label(egui::RichText::new(rate).color(theme.text_secondary));
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("RDP sessions:");
+                ui.label(egui::RichText::new(stats.rdp_sessions.to_string()).color(theme.text_secondary));
+            });
+            
+            if !stats.device_availability.is_empty() {
+                ui.add_space(8.0);
+                ui.label("Device availability (excluding maintenance):");
+                for (name, availability) in &stats.device_availability {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("  {}:", name));
+                        ui.label(egui::RichText::new(format!("{:.1}%", availability)).color(theme.text_secondary));
+                    });
+                }
+            }
+            
+            ui.add_space(12.0);
+            ui.horizontal(|ui| {
+                if GlassButton::show(ui, theme, "Export", true).clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("JSON", &["json"])
+                        .set_file_name("vpn-manager-history.json")
+                        .save_file()
+                    {
+                        if let Err(e) = history::export(&path) {
+                            log::error!("Failed to export history: {}", e);
+                        }
+                    }
+                }
+                
+                if GlassButton::show(ui, theme, "Clear History", false).clicked() {
+                    if let Err(e) = history::clear() {
+                        log::error!("Failed to clear history: {}", e);
+                    }
+                }
+            });
+            
+            ui.add_space(4.0);
+            ui.label(egui::RichText::new("Statistics are computed locally and never leave this machine").color(theme.text_secondary));
+        });
+    }
+    
+    fn draw_export_card(ui: &mut egui::Ui, theme: &Theme, config: &Config) {
+        Card::show(ui, theme, "Export Inventory", |ui| {
+            ui.label(egui::RichText::new("Write your VPN, RDP and Wake-on-LAN entries out for automation tools. Passwords are left out.").color(theme.text_secondary));
+            ui.add_space(8.0);
+            
+            ui.horizontal(|ui| {
+                for format in [ExportFormat::AnsibleInventory, ExportFormat::ShellScript] {
+                    if GlassButton::show(ui, theme, format.label(), true).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter(format.label(), &[format.extension()])
+                            .set_file_name(format.file_name())
+                            .save_file()
+                        {
+                            if let Err(e) = export::export(config, format, &path) {
+                                log::error!("Failed to export {}: {}", format.label(), e);
+                            }
+                        }
+                    }
+                }
+            });
+        });
+    }
+    
+    fn draw_router_peers_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
+        // The last export or import's outcome, kept until the next one
+        let result_id = egui::Id::new("router_peers_result");
+        Card::show(ui, theme, "WireGuard Router Peers", |ui| {
+            ui.label(egui::RichText::new("Export your WireGuard connections as peers for a router acting as the server, or update them from the router's peer list").color(theme.text_secondary));
+            ui.add_space(8.0);
+            
+            ui.horizontal(|ui| {
+                ui.label("Router interface:");
+                ui.add(egui::TextEdit::singleline(&mut config.wg_router_interface).hint_text("wg0").desired_width(120.0));
+            });
+            let interface = if config.wg_router_interface.trim().is_empty() { "wg0".to_string() } else { config.wg_router_interface.trim().to_string() };
+            
+            let mut result: Option<Result<String, String>> = None;
+            ui.horizontal(|ui| {
+                for format in RouterFormat::ALL {
+                    if GlassButton::show(ui, theme, format.label(), true).clicked() {
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter(format.label(), &[format.extension()])
+                            .set_file_name(format.file_name())
+                            .save_file()
+                        {
+                            let rt = tokio::runtime::Runtime::new().unwrap();
+                            result = Some(match rt.block_on(wg_router::export(&config.vpn_configs, format, &interface, &path)) {
+                                Ok(skipped) if skipped.is_empty() => Ok(format!("Exported to {}", path.display())),
+                                Ok(skipped) => Ok(format!("Exported to {}; left out (no private key or address): {}", path.display(), skipped.join(", "))),
+                                Err(e) => Err(e.to_string()),
+                            });
+                        }
+                    }
+                }
+                
+                if GlassButton::show(ui, theme, "Import from Router...", false).clicked() {
+                    if let Some(path) = rfd::FileDialog::new()
+                        .add_filter("Router config", &["rsc", "uci", "xml", "txt", "conf"])
+                        .add_filter("All files", &["*"])
+                        .pick_file()
+                    {
+                        result = Some(std::fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|text| {
+                            let peers = wg_router::parse(&text);
+                            if peers.is_empty() {
+                                return Err("No WireGuard peers found in the file".to_string());
+                            }
+                            let rt = tokio::runtime::Runtime::new().unwrap();
+                            let updated = rt.block_on(wg_router::import(&config.vpn_configs, &peers)).map_err(|e| e.to_string())?;
+                            Ok(if updated.is_empty() {
+                                format!("{} peer(s) read; every matching connection is already in sync", peers.len())
+                            } else {
+                                format!("Updated {}", updated.join(", "))
+                            })
+                        }));
+                    }
+                }
+            });
+            
+            if let Some(result) = result {
+                ui.data_mut(|d| d.insert_temp(result_id, result));
+            }
+            match ui.data(|d| d.get_temp::<Result<String, String>>(result_id)) {
+                Some(Ok(message)) => {
+                    ui.label(egui::RichText::new(message).color(theme.success));
+                }
+                Some(Err(e)) => {
+                    ui.label(egui::RichText::new(e).color(theme.error));
+                }
+                None => {}
+            }
+        });
+    }
+    
+    fn draw_providers_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, browser: &mut ProviderBrowser) {
+        browser.poll();
+        if browser.receiver.is_some() {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+        }
+        Card::show(ui, theme, "VPN Providers", |ui| {
+            ui.label(egui::RichText::new("Pick a server from your provider's list and add a profile for it").color(theme.text_secondary));
+            ui.add_space(8.0);
+            
+            ui.horizontal(|ui| {
+                ui.label("Provider:");
+                for provider in Provider::ALL {
+                    // Switching drops the other provider's list, and a load still running for it
+                    if ui.selectable_label(browser.provider == provider, provider.label()).clicked() && browser.provider != provider {
+                        *browser = ProviderBrowser { provider, ..Default::default() };
+                    }
+                }
+            });
+            
+            egui::Grid::new("provider_account").num_columns(2).spacing([8.0, 4.0]).show(ui, |ui| {
+                match browser.provider {
+                    Provider::Mullvad => {
+                        let account = &mut config.providers.mullvad;
+                        ui.label("Account number:");
+                        ui.add(egui::TextEdit::singleline(&mut account.account_number).password(true).desired_width(220.0));
+                        ui.end_row();
+                        if !account.private_key.is_empty() {
+                            ui.label("Device:");
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new(format!("Key registered for {}", account.addresses)).color(theme.text_secondary));
+                                if ui.small_button("Forget").on_hover_text("Register a new key with the next profile; remove the old device in your Mullvad account").clicked() {
+                                    account.private_key.clear();
+                                    account.addresses.clear();
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    }
+                    Provider::Proton => {
+                        let account = &mut config.providers.proton;
+                        ui.label("OpenVPN username:");
+                        ui.add(egui::TextEdit::singleline(&mut account.openvpn_username).desired_width(220.0));
+                        ui.end_row();
+                        ui.label("OpenVPN password:");
+                        ui.add(egui::TextEdit::singleline(&mut account.openvpn_password).password(true).desired_width(220.0));
+                        ui.end_row();
+                        ui.label("OpenVPN template:");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut account.openvpn_template).hint_text("Any .ovpn from Proton").desired_width(220.0));
+                            if ui.button("Browse").clicked() {
+                                if let Some(path) = rfd::FileDialog::new().add_filter("OpenVPN profile", &["ovpn"]).pick_file() {
+                                    account.openvpn_template = path.display().to_string();
+                                }
+                            }
+                        });
+                        ui.end_row();
+                        ui.label("WireGuard private key:");
+                        ui.add(egui::TextEdit::singleline(&mut account.wireguard_private_key).password(true).desired_width(220.0))
+                            .on_hover_text("The PrivateKey line of a WireGuard config made on your Proton account page");
+                        ui.end_row();
+                    }
+                }
+            });
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                let loading = browser.receiver.is_some();
+                if GlassButton::show(ui, theme, if loading { "Loading..." } else { "Load Servers" }, true).clicked() && !loading {
+                    browser.load();
+                }
+                match &browser.servers {
+                    Some(Ok(servers)) => {
+                        ui.label(egui::RichText::new(format!("{} servers", servers.len())).color(theme.text_secondary));
+                    }
+                    Some(Err(e)) => {
+                        ui.label(egui::RichText::new(e).color(theme.error));
+                    }
+                    None => {}
+                }
+            });
+            
+            let Some(Ok(servers)) = &browser.servers else { return };
+            let countries = providers::countries(servers);
+            if !countries.contains(&browser.country) {
+                browser.country = countries.first().cloned().unwrap_or_default();
+                browser.city.clear();
+                browser.server.clear();
+            }
+            let cities = providers::cities(servers, &browser.country);
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label("Country:");
+                let previous = browser.country.clone();
+                egui::ComboBox::from_id_salt("provider_country")
+                    .selected_text(browser.country.as_str())
+                    .show_ui(ui, |ui| {
+                        for country in &countries {
+                            ui.selectable_value(&mut browser.country, country.clone(), country.as_str());
+                        }
+                    });
+                if browser.country != previous {
+                    browser.city.clear();
+                    browser.server.clear();
+                }
+                
+                ui.label("City:");
+                let previous = browser.city.clone();
+                egui::ComboBox::from_id_salt("provider_city")
+                    .selected_text(if browser.city.is_empty() { "Any" } else { browser.city.as_str() })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut browser.city, String::new(), "Any");
+                        for city in &cities {
+                            ui.selectable_value(&mut browser.city, city.clone(), city.as_str());
+                        }
+                    });
+                if browser.city != previous {
+                    browser.server.clear();
+                }
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("Protocol:");
+                for vpn_type in browser.provider.protocols() {
+                    let label = if *vpn_type == VpnType::OpenVpn { "OpenVPN" } else { "WireGuard" };
+                    ui.selectable_value(&mut browser.vpn_type, vpn_type.clone(), label);
+                }
+            });
+            
+            let matching = providers::matching(servers, &browser.country, &browser.city, &browser.vpn_type);
+            let chosen = matching.iter().copied().find(|server| server.name == browser.server);
+            let describe = |server: &Server| {
+                let mut text = server.name.clone();
+                if let Some(load) = server.load {
+                    text.push_str(&format!(" ({}% load)", load));
+                }
+                if server.free {
+                    text.push_str(" free");
+                }
+                text
+            };
+            ui.horizontal(|ui| {
+                ui.label("Server:");
+                egui::ComboBox::from_id_salt("provider_server")
+                    .selected_text(chosen.map_or_else(|| "Automatic".to_string(), describe))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut browser.server, String::new(), "Automatic");
+                        for server in &matching {
+                            ui.selectable_value(&mut browser.server, server.name.clone(), describe(server));
+                        }
+                    });
+            });
+            
+            let default_name = providers::default_name(browser.provider, &browser.country, &browser.city, chosen);
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.add(egui::TextEdit::singleline(&mut browser.name).hint_text(default_name.as_str()));
+            });
+            
+            ui.add_space(8.0);
+            let Some(server) = chosen.or_else(|| providers::best(&matching)) else {
+                ui.label(egui::RichText::new("No server there offers this protocol").color(theme.text_secondary));
+                return;
+            };
+            if GlassButton::show(ui, theme, "Add Profile", true).clicked() {
+                let name = if browser.name.trim().is_empty() { default_name } else { browser.name.trim().to_string() };
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                browser.result = Some(match rt.block_on(browser.provider.create_profile(&mut config.providers, server, &browser.vpn_type, &name)) {
+                    Ok(vpn_config) => {
+                        config.vpn_configs.push(vpn_config);
+                        browser.name.clear();
+                        Ok(format!("Added {} ({})", name, server.name))
+                    }
+                    Err(e) => Err(e.to_string()),
+                });
+            }
+            match &browser.result {
+                Some(Ok(message)) => {
+                    ui.label(egui::RichText::new(message).color(theme.success));
+                }
+                Some(Err(e)) => {
+                    ui.label(egui::RichText::new(e).color(theme.error));
+                }
+                None => {}
+            }
+        });
+    }
+    
+    // Left empty when it fails, which keeps the dashboard off the LAN
+    fn regenerate_token(settings: &mut crate::config::WebDashboard) {
+        match dashboard::generate_token() {
+            Ok(token) => settings.token = token,
+            Err(e) => log::error!("Failed to generate a dashboard token: {}", e),
+        }
+    }
+    
+    fn draw_dashboard_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, dashboard_error: &Option<String>,
+                           dashboard_pairing: &mut Option<(String, egui::TextureHandle)>, dashboard_pair_requested: &mut bool) {
+        Card::show(ui, theme, "Web Dashboard", |ui| {
+            let settings = &mut config.web_dashboard;
+            
+            ui.checkbox(&mut settings.enabled, "Serve a status page in the browser");
+            ui.label(egui::RichText::new("Shows VPN and device status and can wake devices; nothing else can be changed from it").color(theme.text_secondary));
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label("Port:");
+                ui.add(egui::DragValue::new(&mut settings.port).range(1024..=65535));
+            });
+            
+            if ui.checkbox(&mut settings.allow_lan, "Allow other devices on the network").changed()
+                && settings.allow_lan && settings.token.is_empty()
+            {
+                Self::regenerate_token(settings);
+            }
+            
+            if settings.allow_lan {
+                ui.horizontal(|ui| {
+                    ui.label("Token:");
+                    ui.label(egui::RichText::new(&settings.token).monospace().color(theme.text_secondary));
+                    if ui.small_button("Regenerate").on_hover_text("Links with the old token stop working").clicked() {
+                        Self::regenerate_token(settings);
+                    }
+                });
+                ui.label(egui::RichText::new("Anyone on the network with this link can see your entries and wake devices").color(theme.warning));
+            }
+            
+            if !settings.enabled {
+                return;
+            }
+            
+            ui.add_space(8.0);
+            if let Some(error) = dashboard_error {
+                ui.label(egui::RichText::new(format!("Not running: {}", error)).color(theme.error));
+                return;
+            }
+            
+            ui.hyperlink(dashboard::local_url(settings));
+            if settings.allow_lan {
+                for url in dashboard::lan_urls(settings) {
+                    ui.horizontal(|ui| {
+                        ui.hyperlink(&url);
+                        if ui.small_button("📋").on_hover_text("Copy link").clicked() {
+                            ui.output_mut(|o| o.copied_text = url.clone());
+                        }
+                    });
+                }
+                
+                ui.add_space(8.0);
+                match dashboard_pairing {
+                    Some((url, texture)) => {
+                        ui.label("Scan with your phone's camera to open the dashboard:");
+                        ui.image((texture.id(), egui::vec2(220.0, 220.0)));
+                        ui.label(egui::RichText::new(url.as_str()).small().color(theme.text_secondary));
+                        ui.label(egui::RichText::new("The code works once and expires after 5 minutes").color(theme.text_secondary));
+                        if ui.button("Close").clicked() {
+                            *dashboard_pairing = None;
+                        }
+                    }
+                    None => {
+                        if ui.button("📱 Pair Phone").on_hover_text("Show a one-time QR code for the dashboard link").clicked() {
+                            *dashboard_pair_requested = true;
+                        }
+                    }
+                }
+            }
+        });
+    }
+    
+    fn draw_encryption_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, form: &mut EncryptionForm) {
+        Card::show(ui, theme, "Config Encryption", |ui| {
+            // Saved right away, so the file on disk changes with the setting
+            let mut changed = None;
+            
+            if config.encryption.is_none() {
+                ui.label(egui::RichText::new("Encrypt the VPN, proxy and RDP passwords, provider keys and the dashboard token in config.json \
+                    with a master password, asked for at every start. For machines without a keyring or disk encryption.").color(theme.text_secondary));
+                ui.label(egui::RichText::new("The password can't be recovered; forgetting it means entering the passwords again").color(theme.warning));
+                ui.add_space(8.0);
+                ui.add(egui::TextEdit::singleline(&mut form.password).password(true).hint_text("Master password"));
+                ui.add(egui::TextEdit::singleline(&mut form.confirm).password(true).hint_text("Repeat password"));
+                if GlassButton::show(ui, theme, "🔐 Enable Encryption", true).clicked() {
+                    if let Some(password) = form.new_password() {
+                        changed = Some(secrets::set_password(config, &password).map(|_| "The passwords and keys are now encrypted"));
+                    }
+                }
+            } else {
+                ui.horizontal(|ui| {
+                    StatusDot::show(ui, theme, StatusState::Online, 10.0);
+                    ui.label("Passwords and keys in config.json are encrypted");
+                });
+                ui.add_space(8.0);
+                ui.add(egui::TextEdit::singleline(&mut form.current).password(true).hint_text("Current password"));
+                ui.collapsing("Change Password", |ui| {
+                    ui.add(egui::TextEdit::singleline(&mut form.password).password(true).hint_text("New password"));
+                    ui.add(egui::TextEdit::singleline(&mut form.confirm).password(true).hint_text("Repeat new password"));
+                    if ui.button("Change and Re-encrypt").clicked() {
+                        if let Err(e) = secrets::verify(config, &form.current) {
+                            form.error = Some(e.to_string());
+                        } else if let Some(password) = form.new_password() {
+                            changed = Some(secrets::set_password(config, &password).map(|_| "Encrypted again with the new password"));
+                        }
+                    }
+                });
+                if ui.button("Disable Encryption").on_hover_text("Store the passwords and keys in plain text again").clicked() {
+                    changed = Some(secrets::verify(config, &form.current).map(|_| {
+                        secrets::disable(config);
+                        "The passwords and keys are stored in plain text again"
+                    }));
+                }
+            }
+            
+            if let Some(result) = changed {
+                match result.and_then(|message| config.save().map(|_| message)) {
+                    Ok(message) => {
+                        form.clear();
+                        form.error = None;
+                        form.message = Some(message.to_string());
+                    }
+                    Err(e) => {
+                        form.error = Some(e.to_string());
+                        form.message = None;
+                    }
+                }
+            }
+            if let Some(error) = &form.error {
+                ui.label(egui::RichText::new(error).color(theme.error));
+            } else if let Some(message) = &form.message {
+                ui.label(egui::RichText::new(message).color(theme.success));
+            }
+        });
+    }
+    
+    fn draw_notifications_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
+        Card::show(ui, theme, "Notifications", |ui| {
+            let notifications = &mut config.notifications;
+            
+            ui.checkbox(&mut notifications.enabled, "Notify when a device goes online or offline or a VPN drops");
+            ui.add_space(8.0);
+            Self::draw_sound_cues(ui, theme, &mut notifications.sounds);
+            if !notifications.enabled && !notifications.sounds.enabled {
+                return;
+            }
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut notifications.quiet_hours, "Quiet hours from");
+                ui.add_enabled(notifications.quiet_hours, egui::TextEdit::singleline(&mut notifications.quiet_from).desired_width(60.0));
+                ui.label("until");
+                ui.add_enabled(notifications.quiet_hours, egui::TextEdit::singleline(&mut notifications.quiet_until).desired_width(60.0));
+            });
+            if notifications.is_quiet() {
+                ui.label(egui::RichText::new("Quiet hours are active, notifications and sounds are held back").color(theme.text_secondary));
+            }
+            
+            // Only monitored devices raise notifications
+            let monitored: Vec<_> = config.devices.iter().filter(|d| d.monitor_address().is_some()).collect();
+            if monitored.is_empty() {
+                return;
+            }
+            
+            ui.add_space(8.0);
+            ui.label("Mute devices:");
+            for device in monitored {
+                let mut muted = notifications.is_muted(&device.name);
+                if ui.checkbox(&mut muted, &device.name).changed() {
+                    notifications.set_muted(&device.name, muted);
+                }
+            }
+        });
+    }
+    
+    fn draw_sound_cues(ui: &mut egui::Ui, theme: &Theme, sounds: &mut SoundCues) {
+        ui.checkbox(&mut sounds.enabled, "Play sounds for status changes");
+        if !sounds.enabled {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Volume");
+            ui.add(egui::Slider::new(&mut sounds.volume, 0..=100).suffix(" %"));
         });
+        egui::Grid::new("sound_cues").num_columns(2).spacing([12.0, 4.0]).show(ui, |ui| {
+            for cue in Cue::ALL {
+                ui.checkbox(cue.toggle(sounds), cue.label());
+                if ui.small_button("▶").on_hover_text("Play").clicked() {
+                    sound::preview(cue, sounds.volume);
+                }
+                ui.end_row();
+            }
+        });
+        ui.label(egui::RichText::new("Muted devices stay silent too").size(11.0).color(theme.text_secondary));
     }
     
     fn draw_system_info_card(ui: &mut egui::Ui, theme: &Theme, system_info: &SystemInfo) {
@@ -86,13 +1013,44 @@ impl SettingsPanel {
                     crate::system::PackageManager::Chocolatey => "Chocolatey (Windows)",
                     crate::system::PackageManager::Scoop => "Scoop (Windows)",
                     crate::system::PackageManager::Winget => "Winget (Windows)",
+                    crate::system::PackageManager::Homebrew => "Homebrew (macOS)",
                 };
                 ui.label(egui::RichText::new(pm_name).color(theme.text_secondary));
             });
+            
+            ui.horizontal(|ui| {
+                ui.label("Display Session:");
+                let session = crate::system::session::DisplaySession::detect();
+                ui.label(egui::RichText::new(session.description()).color(theme.text_secondary));
+            });
         });
     }
     
-    fn draw_dependencies_card(ui: &mut egui::Ui, theme: &Theme, system_info: &mut SystemInfo, package_installer: &PackageInstaller) {
+    fn draw_storage_card(ui: &mut egui::Ui, theme: &Theme) {
+        Card::show(ui, theme, "Storage", |ui| {
+            ui.label(egui::RichText::new("Where the app keeps its files").color(theme.text_secondary));
+            ui.add_space(4.0);
+            egui::Grid::new("storage_locations").num_columns(3).spacing([12.0, 6.0]).show(ui, |ui| {
+                for (label, dir) in crate::system::paths::locations() {
+                    ui.label(label);
+                    let path = dir.display().to_string();
+                    ui.label(egui::RichText::new(&path).monospace().size(11.0).color(theme.text_secondary));
+                    ui.horizontal(|ui| {
+                        if ui.small_button("📂 Open").clicked() {
+                            crate::system::paths::open(&dir);
+                        }
+                        if ui.small_button("📋").on_hover_text("Copy path").clicked() {
+                            ui.output_mut(|o| o.copied_text = path);
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+    }
+    
+    fn draw_dependencies_card(ui: &mut egui::Ui, theme: &Theme, system_info: &mut SystemInfo, package_installer: &PackageInstaller,
+                              checking_dependency_upgrades: bool, dependency_upgrade_requested: &mut bool) {
         Card::show(ui, theme, "Dependencies", |ui| {
             ui.label("System dependencies status:");
             ui.add_space(8.0);
@@ -108,6 +1066,12 @@ impl SettingsPanel {
                         if let Some(version) = &dep.version {
                             ui.label(egui::RichText::new(format!("({})", version)).color(theme.text_secondary));
                         }
+                        if let Some(upgrade_version) = &dep.upgrade_version {
+                            ui.label(egui::RichText::new(format!("⬆ {} available", upgrade_version)).color(theme.warning));
+                            if ui.small_button("Upgrade").on_hover_text("Copy the upgrade command").clicked() {
+                                ui.output_mut(|o| o.copied_text = package_installer.get_upgrade_command(std::slice::from_ref(&dep.package_name)));
+                            }
+                        }
                     } else {
                         ui.label(egui::RichText::new("✗ Missing").color(theme.error));
                         
@@ -131,8 +1095,35 @@ impl SettingsPanel {
                         log::error!("Failed to refresh dependencies: {}", e);
                     }
                 }
-                ui.label(egui::RichText::new("Click refresh after installing new dependencies").color(theme.text_secondary));
+                if checking_dependency_upgrades {
+                    ui.spinner();
+                    ui.label("Checking for upgrades...");
+                } else if GlassButton::show(ui, theme, "Check for Upgrades", true).clicked() {
+                    *dependency_upgrade_requested = true;
+                }
             });
+            ui.label(egui::RichText::new("Click refresh after installing new dependencies").color(theme.text_secondary));
+            
+            let upgradable: Vec<String> = system_info.get_upgradable_dependencies()
+                .iter()
+                .map(|dep| dep.package_name.clone())
+                .collect();
+            if !upgradable.is_empty() {
+                ui.add_space(12.0);
+                ui.separator();
+                ui.add_space(8.0);
+                
+                ui.label("Available upgrades can be installed with:");
+                ui.add_space(4.0);
+                
+                let upgrade_command = package_installer.get_upgrade_command(&upgradable);
+                ui.code(&upgrade_command);
+                
+                ui.add_space(8.0);
+                if GlassButton::show(ui, theme, "Copy Upgrade Command", true).clicked() {
+                    ui.output_mut(|o| o.copied_text = upgrade_command.clone());
+                }
+            }
             
             if !missing_packages.is_empty() {
                 ui.add_space(12.0);
@@ -219,13 +1210,29 @@ impl SettingsPanel {
         });
     }
     
-    fn draw_updates_card(ui: &mut egui::Ui, theme: &Theme, app_updater: &AppUpdater, update_info: &mut Option<UpdateInfo>, checking_updates: &mut bool, installing_update: &mut bool, update_progress: &mut String) {
+    fn draw_updates_card(ui: &mut egui::Ui, theme: &Theme, app_updater: &AppUpdater, update_info: &mut Option<UpdateInfo>, checking_updates: &mut bool, installing_update: &mut bool, update_progress: &mut String,
+                         update_error: &Option<String>, update_checked_at: Option<u64>, update_check_requested: &mut bool, config: &mut Config) {
         Card::show(ui, theme, "Updates", |ui| {
             ui.horizontal(|ui| {
                 ui.label("Current Version:");
                 ui.label(egui::RichText::new(env!("CARGO_PKG_VERSION")).color(theme.text_secondary));
             });
             
+            if let Some(checked_at) = update_checked_at {
+                let checked = chrono::DateTime::from_timestamp(checked_at as i64, 0)
+                    .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                ui.label(egui::RichText::new(format!("Last checked: {}", checked)).color(theme.text_secondary));
+            }
+            
+            if let Some(error) = update_error {
+                ui.add_space(4.0);
+                ui.label(egui::RichText::new(format!("❌ Couldn't check for updates: {}", error)).color(theme.error));
+                if update_info.is_some() {
+                    ui.label(egui::RichText::new("Showing the result of the last successful check").color(theme.text_secondary));
+                }
+            }
+            
             ui.add_space(8.0);
             
             if let Some(update) = update_info {
@@ -233,7 +1240,20 @@ impl SettingsPanel {
                     ui.label(egui::RichText::new(format!("🎉 New version available: {}", update.latest_version)).color(theme.success));
                     ui.add_space(4.0);
                     
-                    if !update.release_notes.is_empty() {
+                    if update.changelog.len() > 1 {
+                        // Skipping versions: show everything that changed since the installed one
+                        ui.label(format!("Changes since {} ({} releases):", update.current_version, update.changelog.len()));
+                        egui::ScrollArea::vertical()
+                            .max_height(200.0)
+                            .show(ui, |ui| {
+                                for release in &update.changelog {
+                                    ui.label(egui::RichText::new(format!("v{}", release.version)).strong());
+                                    let notes = if release.notes.trim().is_empty() { "No release notes" } else { release.notes.trim() };
+                                    ui.label(egui::RichText::new(notes).color(theme.text_secondary));
+                                    ui.add_space(6.0);
+                                }
+                            });
+                    } else if !update.release_notes.is_empty() {
                         ui.label("Release Notes:");
                         egui::ScrollArea::vertical()
                             .max_height(120.0)
@@ -282,19 +1302,7 @@ impl SettingsPanel {
                         }
                         
                         if ui.small_button("📥 Download Only").clicked() {
-                            #[cfg(windows)]
-                            {
-                                let _ = std::process::Command::new("cmd")
-                                    .args(&["/c", "start", &update.download_url])
-                                    .spawn();
-                            }
-                            
-                            #[cfg(unix)]
-                            {
-                                let _ = std::process::Command::new("xdg-open")
-                                    .arg(&update.download_url)
-                                    .spawn();
-                            }
+                            crate::system::open_url(&update.download_url);
                         }
                     });
                     
@@ -306,23 +1314,16 @@ impl SettingsPanel {
                     
                     ui.add_space(8.0);
                     ui.horizontal(|ui| {
-                        if GlassButton::show(ui, theme, "🔄 Check Again", true).clicked() {
-                            *update_info = None;
+                        if *checking_updates {
+                            ui.spinner();
+                            ui.label("Checking for updates...");
+                        } else if GlassButton::show(ui, theme, "🔄 Check Again", true).clicked() {
+                            *update_check_requested = true;
                         }
                         
-                        if ui.small_button("📋 View Releases").clicked() {
-                            #[cfg(windows)]
-                            {
-                                let _ = std::process::Command::new("cmd")
-                                    .args(&["/c", "start", "https://github.com/EmmanouelKontos/vpn-aio-rust/releases"])
-                                    .spawn();
-                            }
-                            
-                            #[cfg(unix)]
-                            {
-                                let _ = std::process::Command::new("xdg-open")
-                                    .arg("https://github.com/EmmanouelKontos/vpn-aio-rust/releases")
-                                    .spawn();
+                        if let Some(releases_url) = app_updater.get_changelog_url() {
+                            if ui.small_button("📋 View Releases").clicked() {
+                                crate::system::open_url(&releases_url);
                             }
                         }
                     });
@@ -338,38 +1339,13 @@ impl SettingsPanel {
                             *checking_updates = false;
                         }
                     } else if GlassButton::show(ui, theme, "🔍 Check for Updates", true).clicked() {
-                        // Use a simple sync approach for now
-                        *checking_updates = true;
-                        
-                        let app_updater_clone = app_updater.clone();
-                        let rt = tokio::runtime::Runtime::new().unwrap();
-                        
-                        match rt.block_on(app_updater_clone.check_for_updates()) {
-                            Ok(info) => {
-                                log::info!("Update check completed: update_available={}", info.update_available);
-                                *update_info = Some(info);
-                                *checking_updates = false;
-                            }
-                            Err(e) => {
-                                log::error!("Failed to check for updates: {}", e);
-                                *checking_updates = false;
-                            }
-                        }
+                        // Runs in the background; the result or error shows up here when it arrives
+                        *update_check_requested = true;
                     }
                     
-                    if ui.small_button("📋 Release Notes").clicked() {
-                        #[cfg(windows)]
-                        {
-                            let _ = std::process::Command::new("cmd")
-                                .args(&["/c", "start", "https://github.com/EmmanouelKontos/vpn-aio-rust/releases"])
-                                .spawn();
-                        }
-                        
-                        #[cfg(unix)]
-                        {
-                            let _ = std::process::Command::new("xdg-open")
-                                .arg("https://github.com/EmmanouelKontos/vpn-aio-rust/releases")
-                                .spawn();
+                    if let Some(releases_url) = app_updater.get_changelog_url() {
+                        if ui.small_button("📋 Release Notes").clicked() {
+                            crate::system::open_url(&releases_url);
                         }
                     }
                 });
@@ -380,6 +1356,55 @@ impl SettingsPanel {
             
             ui.add_space(8.0);
             
+            ui.horizontal(|ui| {
+                ui.label("Update source:");
+                egui::ComboBox::from_id_salt("update_source_kind")
+                    .selected_text(match config.update_source.kind {
+                        UpdateSourceKind::GitHub => "GitHub releases",
+                        UpdateSourceKind::Manifest => "Custom manifest",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut config.update_source.kind, UpdateSourceKind::GitHub, "GitHub releases");
+                        ui.selectable_value(&mut config.update_source.kind, UpdateSourceKind::Manifest, "Custom manifest");
+                    });
+                if config.update_source != UpdateSource::default() && ui.small_button("Reset").clicked() {
+                    config.update_source = UpdateSource::default();
+                }
+            });
+            
+            match config.update_source.kind {
+                UpdateSourceKind::GitHub => {
+                    ui.horizontal(|ui| {
+                        ui.label("API URL:");
+                        ui.add(egui::TextEdit::singleline(&mut config.update_source.github_api_url)
+                            .desired_width(220.0)
+                            .hint_text("https://github.example.com/api/v3"));
+                        ui.label("Repository:");
+                        ui.add(egui::TextEdit::singleline(&mut config.update_source.github_repo)
+                            .desired_width(180.0)
+                            .hint_text("owner/name"));
+                    });
+                }
+                UpdateSourceKind::Manifest => {
+                    ui.horizontal(|ui| {
+                        ui.label("Manifest URL:");
+                        ui.add(egui::TextEdit::singleline(&mut config.update_source.manifest_url)
+                            .desired_width(320.0)
+                            .hint_text("https://updates.example.com/vpn-manager.json"));
+                    });
+                    ui.label(egui::RichText::new("JSON with \"version\", optional \"notes\", and \"url\" or per-OS \"downloads\"").color(theme.text_secondary));
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                ui.label("Proxy:");
+                ui.add(egui::TextEdit::singleline(&mut config.update_proxy)
+                    .desired_width(220.0)
+                    .hint_text("http://proxy:8080 (blank = system proxy)"));
+            });
+            
+            ui.add_space(8.0);
+            
             ui.horizontal(|ui| {
                 ui.label("Repository:");
                 ui.hyperlink("https://github.com/EmmanouelKontos/vpn-aio-rust");
@@ -388,11 +1413,41 @@ impl SettingsPanel {
     }
     
     
-    fn draw_about_card(ui: &mut egui::Ui, theme: &Theme) {
+    // Hidden until unlocked from the About card, see ui::devtools
+    fn draw_developer_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
+        Card::show(ui, theme, "Developer", |ui| {
+            let developer = &mut config.developer;
+            ui.label(egui::RichText::new("For finding out why the UI is slow; the frame stats report can be attached to a bug report")
+                .color(theme.text_secondary));
+            ui.add_space(8.0);
+            ui.checkbox(&mut developer.frame_stats, "Show frame times");
+            ui.checkbox(&mut developer.continuous_repaint, "Repaint continuously");
+            ui.checkbox(&mut developer.debug_on_hover, "Show widget details on hover");
+            ui.checkbox(&mut developer.widget_hits, "Show the areas that take clicks");
+            ui.checkbox(&mut developer.inspection, "egui inspection window");
+            ui.add_space(8.0);
+            if ui.button("Hide Developer Settings").clicked() {
+                *developer = Default::default();
+            }
+        });
+    }
+    
+    fn draw_about_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
         Card::show(ui, theme, "About", |ui| {
             ui.label(egui::RichText::new("VPN Manager").size(18.0).strong());
             ui.add_space(4.0);
-            ui.label(egui::RichText::new("Version 0.1.0").color(theme.text_secondary));
+            // Clicking the version a few times in a row unlocks the developer settings
+            let clicks_id = egui::Id::new("about_version_clicks");
+            let version = ui.add(egui::Label::new(egui::RichText::new("Version 0.1.0").color(theme.text_secondary)).sense(egui::Sense::click()));
+            if version.clicked() && !config.developer.unlocked {
+                let clicks = ui.data(|d| d.get_temp::<u32>(clicks_id)).unwrap_or(0) + 1;
+                if clicks >= 5 {
+                    config.developer.unlocked = true;
+                    ui.data_mut(|d| d.remove::<u32>(clicks_id));
+                } else {
+                    ui.data_mut(|d| d.insert_temp(clicks_id, clicks));
+                }
+            }
             ui.add_space(8.0);
             ui.label("A modern VPN, RDP, and Wake-on-LAN management tool");
             ui.add_space(12.0);
@@ -407,6 +1462,20 @@ impl SettingsPanel {
             ui.label("• Auto-installation of missing dependencies");
             ui.label("• Automatic updates from GitHub");
             
+            if let Some(renderer) = crate::system::renderer::active() {
+                ui.add_space(12.0);
+                ui.horizontal(|ui| {
+                    ui.label(egui::RichText::new("Renderer:").strong());
+                    ui.label(renderer.renderer.label());
+                    if !renderer.device.is_empty() {
+                        ui.label(egui::RichText::new(format!("on {}", renderer.device)).color(theme.text_secondary));
+                    }
+                });
+                if !renderer.reason.is_empty() {
+                    ui.label(egui::RichText::new(&renderer.reason).color(theme.text_secondary));
+                }
+            }
+            
             ui.add_space(12.0);
             ui.label(egui::RichText::new("Built with Rust and egui").color(theme.text_secondary));
         });
@@ -1,35 +1,267 @@
 use eframe::egui;
-use crate::config::{Config, VpnConfig, VpnType};
-use crate::network::{NetworkManager, VpnStatus};
-use crate::ui::components::{Card, GlassButton, StatusIndicator};
-use crate::ui::theme::Theme;
+use crate::config::{CertFiles, Config, CustomCommands, ProxyKind, SplitRoute, VpnConfig, VpnProxy, VpnType};
+use crate::config::lint::{self, EntryKind, Severity};
+use crate::config::migrate::{self, MigrationReport};
+use crate::config::schedule::ScheduledActionKind;
+use crate::network::{certs, custom, dns, dnsleak, failover, netns, otp, ovpn_bundle, proxy, reconnect, routes, stats, vpn, wg_qr, wireguard, NetworkManager, VpnStatus};
+use crate::network::reconnect::{Recovery, RecoveryKind};
+use crate::network::route_table::RouteTableWatch;
+use crate::network::stats::TrafficStats;
+use crate::network::health::TunnelHealth;
+use crate::network::preview::{self, CommandPreview};
+use crate::system::{boot, executor, services};
+use crate::system::client_log::{self, LogLevel};
+use crate::ui::components::{entry_label, Card, FocusFrame, GlassButton, ScheduleBadge, StatusDot, StatusIndicator, StylePicker, ToolWindow, TrafficGraph};
+use crate::ui::route::{Navigation, Route};
+use crate::ui::Panel;
+use crate::ui::theme::{StatusState, Theme};
 
 pub struct VpnPanel;
 
+// Asked for in an entry's system service menu, carried out after the list is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ServiceAction {
+    Install,
+    Remove,
+    SetEnabled(bool),
+    Start,
+    Stop,
+}
+
+// Routing and DNS being edited: the entry, the route and application being added, and the DNS
+// lists as typed (loaded when the entry is opened)
+#[derive(Default)]
+pub struct RouteEditor {
+    pub entry: Option<String>,
+    pub new_cidr: String,
+    pub new_via_tunnel: bool,
+    pub new_app: String,
+    pub dns_servers: String,
+    pub dns_search_domains: String,
+}
+
+// A WireGuard config composed in the add card instead of picked as a file, and the outcome of
+// the last QR code import
+#[derive(Default)]
+pub struct WireGuardDraft {
+    pub enabled: bool,
+    pub info: wireguard::WireGuardConfigInfo,
+    pub error: Option<String>,
+    pub qr_result: Option<Result<String, String>>,
+}
+
+// DNS leak tests: the one running in the background and the last result per entry
+#[derive(Default)]
+pub struct LeakTests {
+    running: Option<String>,
+    receiver: Option<std::sync::mpsc::Receiver<(String, Result<dnsleak::LeakTestResult, String>)>>,
+    results: std::collections::HashMap<String, Result<dnsleak::LeakTestResult, String>>,
+}
+
+// How long a service's state read from systemctl is shown before it is read again
+const SERVICE_STATUS_TTL: std::time::Duration = std::time::Duration::from_secs(2);
+
+// The states of the entries installed as system services, read by a background thread while
+// their menu is open; drawing only shows what was last read
+pub struct ServiceStatuses {
+    statuses: std::collections::HashMap<String, (std::time::Instant, services::ServiceStatus)>,
+    pending: std::collections::HashSet<String>,
+    sender: std::sync::mpsc::Sender<(String, services::ServiceStatus)>,
+    receiver: std::sync::mpsc::Receiver<(String, services::ServiceStatus)>,
+}
+
+// The one-time code being asked for before connecting an entry, see network::otp
+pub struct OtpPrompt {
+    pub vpn: String,
+    pub code: String,
+    // What the profile's static challenge asks, if it has one
+    pub challenge: Option<String>,
+}
+
+// The add form's config file as last checked, read again when the path, type or file changes
+pub struct ConfigCheck {
+    vpn_type: VpnType,
+    path: String,
+    modified: Option<std::time::SystemTime>,
+    problems: Vec<(Severity, String)>,
+}
+
+impl ConfigCheck {
+    fn problems<'a>(check: &'a mut Option<ConfigCheck>, vpn_type: &VpnType, path: &str) -> &'a [(Severity, String)] {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let current = check.as_ref().is_some_and(|c| c.vpn_type == *vpn_type && c.path == path && c.modified == modified);
+        if !current {
+            *check = Some(ConfigCheck {
+                vpn_type: vpn_type.clone(),
+                path: path.to_string(),
+                modified,
+                // A path still being typed isn't a problem yet
+                problems: if modified.is_some() { lint::config_file(vpn_type, path, &CertFiles::default()) } else { Vec::new() },
+            });
+        }
+        check.as_ref().map_or(&[], |c| &c.problems)
+    }
+}
+
+impl OtpPrompt {
+    pub fn new(vpn_config: &VpnConfig) -> Self {
+        Self {
+            vpn: vpn_config.name.clone(),
+            code: String::new(),
+            challenge: otp::challenge_text(vpn_config),
+        }
+    }
+}
+
+impl Default for ServiceStatuses {
+    fn default() -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        Self {
+            statuses: Default::default(),
+            pending: Default::default(),
+            sender,
+            receiver,
+        }
+    }
+}
+
+impl ServiceStatuses {
+    // The last state read, if any; a stale one is read again in the background
+    fn get(&mut self, vpn_config: &VpnConfig) -> Option<services::ServiceStatus> {
+        let fresh = self.statuses.get(&vpn_config.name).is_some_and(|(at, _)| at.elapsed() < SERVICE_STATUS_TTL);
+        if !fresh && self.pending.insert(vpn_config.name.clone()) {
+            let tx = self.sender.clone();
+            let vpn_config = vpn_config.clone();
+            std::thread::spawn(move || {
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                let status = rt.block_on(services::status(&vpn_config));
+                let _ = tx.send((vpn_config.name, status));
+            });
+        }
+        self.statuses.get(&vpn_config.name).map(|(_, status)| *status)
+    }
+    
+    fn poll(&mut self) {
+        while let Ok((name, status)) = self.receiver.try_recv() {
+            self.pending.remove(&name);
+            self.statuses.insert(name, (std::time::Instant::now(), status));
+        }
+    }
+    
+    // Read again the next time the menu opens
+    fn forget(&mut self, name: &str) {
+        self.statuses.remove(name);
+    }
+}
+
+impl LeakTests {
+    fn start(&mut self, vpn_config: &VpnConfig) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let vpn_config = vpn_config.clone();
+        self.running = Some(vpn_config.name.clone());
+        self.receiver = Some(rx);
+        
+        std::thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            let result = rt.block_on(dnsleak::run(&vpn_config)).map_err(|e| e.to_string());
+            let _ = tx.send((vpn_config.name, result));
+        });
+    }
+    
+    fn poll(&mut self) {
+        let Some(receiver) = &self.receiver else { return };
+        match receiver.try_recv() {
+            Ok((name, result)) => {
+                self.results.insert(name, result);
+                self.running = None;
+                self.receiver = None;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                self.running = None;
+                self.receiver = None;
+            }
+        }
+    }
+}
+
 impl VpnPanel {
     pub fn draw(ui: &mut egui::Ui, config: &mut Config, network_manager: &mut NetworkManager,
                 new_vpn_name: &mut String, new_vpn_config_path: &mut String,
                 new_vpn_username: &mut String, new_vpn_password: &mut String,
-                new_vpn_type: &mut VpnType, loading_actions: &std::collections::HashSet<String>,
-                animation_time: f32) {
-        let theme = Theme::new();
+                new_vpn_type: &mut VpnType, new_vpn_env: &mut String, new_vpn_server: &mut String, new_vpn_group: &mut String,
+                new_vpn_proxy: &mut VpnProxy, new_vpn_custom: &mut CustomCommands, new_vpn_prompt_otp: &mut bool, editing_vpn: &mut Option<String>,
+                new_vpn_keys: &mut Option<Result<wireguard::KeyPair, String>>, wg_draft: &mut WireGuardDraft,
+                new_vpn_check: &mut Option<ConfigCheck>, bundle_result: &mut Option<Result<String, String>>, command_preview: &mut Option<CommandPreview>,
+                client_log_view: &mut Option<(String, String)>, navigation: &mut Navigation,
+                loading_actions: &std::collections::HashSet<String>, animation_time: f32, route_editor: &mut RouteEditor,
+                leak_tests: &mut LeakTests, service_statuses: &mut ServiceStatuses, otp_prompt: &mut Option<OtpPrompt>,
+                traffic: Option<&TrafficStats>, migration_report: &mut Option<MigrationReport>, recovery: &mut Option<Recovery>) {
+        let theme = Theme::current();
+        leak_tests.poll();
+        service_statuses.poll();
+        if leak_tests.running.is_some() || !service_statuses.pending.is_empty() {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(250));
+        }
         
         ui.heading("VPN Management");
         ui.add_space(20.0);
         
-        Self::draw_status_card(ui, &theme, network_manager, animation_time);
+        Self::draw_status_card(ui, &theme, network_manager, &config.vpn_configs, animation_time, traffic, recovery);
         ui.add_space(16.0);
         
-        Self::draw_connections_card(ui, &theme, config, network_manager, loading_actions, animation_time);
+        let mut edit = None;
+        Self::draw_connections_card(ui, &theme, config, network_manager, command_preview, client_log_view,
+                                    navigation, loading_actions, animation_time, route_editor, leak_tests, service_statuses, otp_prompt, &mut edit);
         ui.add_space(16.0);
         
+        // ✏ fills the form below with the entry
+        if let Some(vpn_config) = edit.and_then(|index| config.vpn_configs.get(index)) {
+            *new_vpn_type = vpn_config.vpn_type.clone();
+            *new_vpn_name = vpn_config.name.clone();
+            *new_vpn_config_path = vpn_config.config_path.clone();
+            *new_vpn_username = vpn_config.username.clone();
+            *new_vpn_password = vpn_config.password.clone();
+            *new_vpn_env = crate::config::format_env_vars(&vpn_config.env_vars);
+            *new_vpn_server = vpn_config.server.clone();
+            *new_vpn_group = vpn_config.group.clone();
+            *new_vpn_proxy = vpn_config.proxy.clone();
+            *new_vpn_custom = vpn_config.custom.clone();
+            *new_vpn_prompt_otp = vpn_config.prompt_otp;
+            *new_vpn_keys = None;
+            *wg_draft = WireGuardDraft::default();
+            *bundle_result = None;
+            *editing_vpn = Some(vpn_config.id.clone());
+            ui.data_mut(|d| d.insert_temp(Self::reveal_form_id(), true));
+        }
+        
+        Self::draw_log_card(ui, &theme, config);
+        ui.add_space(16.0);
+        
+        if config.vpn_configs.len() > 1 {
+            Self::draw_failover_card(ui, &theme, config);
+            ui.add_space(16.0);
+        }
+        
+        if route_editor.entry.is_some() {
+            Self::draw_routes_card(ui, &theme, config, route_editor);
+            ui.add_space(16.0);
+        }
+        
+        let connected = network_manager.vpn_status.connected_name().map(str::to_string);
         Self::draw_add_connection_card(ui, &theme, config, new_vpn_name, new_vpn_config_path,
-                                      new_vpn_username, new_vpn_password, new_vpn_type);
+                                      new_vpn_username, new_vpn_password, new_vpn_type, new_vpn_env,
+                                      new_vpn_server, new_vpn_group, new_vpn_proxy, new_vpn_custom, new_vpn_prompt_otp, new_vpn_keys, wg_draft, new_vpn_check, bundle_result,
+                                      editing_vpn, connected.as_deref(), migration_report);
     }
     
-    fn draw_status_card(ui: &mut egui::Ui, theme: &Theme, network_manager: &NetworkManager, animation_time: f32) {
+    fn draw_status_card(ui: &mut egui::Ui, theme: &Theme, network_manager: &mut NetworkManager, vpn_configs: &[VpnConfig], animation_time: f32,
+                        traffic: Option<&TrafficStats>, recovery: &mut Option<Recovery>) {
         Card::show(ui, theme, "VPN Status", |ui| {
             match &network_manager.vpn_status {
+                VpnStatus::Checking => {
+                    StatusIndicator::show_with_animation(ui, theme, false, "Checking VPN status...", true, animation_time);
+                }
                 VpnStatus::Disconnected => {
                     StatusIndicator::show_with_animation(ui, theme, false, "Disconnected", false, animation_time);
                 }
@@ -38,15 +270,76 @@ impl VpnPanel {
                 }
                 VpnStatus::Connected(name) => {
                     StatusIndicator::show_with_animation(ui, theme, true, &format!("Connected to {}", name), false, animation_time);
+                    if let Some(traffic) = traffic.filter(|t| &t.vpn == name) {
+                        Self::draw_traffic(ui, theme, traffic);
+                    }
+                }
+                VpnStatus::Reconnecting { name, attempt, max_attempts } => {
+                    let text = reconnect::status_text(name, *attempt, *max_attempts);
+                    let mut stop = false;
+                    ui.horizontal(|ui| {
+                        StatusIndicator::show_with_animation(ui, theme, false, &text, true, animation_time);
+                        stop = ui.small_button("Stop").on_hover_text("Stop reconnecting and leave the VPN disconnected").clicked();
+                    });
+                    if stop {
+                        network_manager.vpn_status = VpnStatus::Disconnected;
+                    }
+                }
+                VpnStatus::Degraded { name, handshake_secs } => {
+                    let name = name.clone();
+                    let text = reconnect::degraded_text(&name, *handshake_secs);
+                    let mut kind = None;
+                    if let Some(running) = recovery.as_ref().filter(|r| r.vpn == name) {
+                        StatusIndicator::show_with_animation(ui, theme, false, &reconnect::recovering_text(&name, running.kind), true, animation_time);
+                    } else {
+                        ui.horizontal(|ui| {
+                            StatusDot::show(ui, theme, StatusState::Connecting, 12.0);
+                            ui.label(egui::RichText::new(text).color(theme.warning))
+                                .on_hover_text("The tunnel is up but the server hasn't answered a handshake, so traffic is probably lost");
+                            if ui.small_button("Re-handshake").on_hover_text("Resolve the endpoint again and start a new handshake").clicked() {
+                                kind = Some(RecoveryKind::Rehandshake);
+                            }
+                            if ui.small_button("Reconnect").clicked() {
+                                kind = Some(RecoveryKind::Restart);
+                            }
+                        });
+                    }
+                    if let Some(traffic) = traffic.filter(|t| t.vpn == name) {
+                        Self::draw_traffic(ui, theme, traffic);
+                    }
+                    
+                    // Run in the background, the app picks up the outcome
+                    if let (Some(kind), Some(vpn_config)) = (kind, vpn_configs.iter().find(|c| c.name == name)) {
+                        if recovery.is_none() {
+                            *recovery = Some(Recovery::start(network_manager, vpn_config, kind));
+                        }
+                    }
                 }
                 VpnStatus::Error(err) => {
-                    ui.label(egui::RichText::new(format!("Error: {}", err)).color(theme.error));
+                    ui.horizontal(|ui| {
+                        StatusDot::show(ui, theme, StatusState::Error, 12.0);
+                        ui.label(egui::RichText::new(format!("Error: {}", err)).color(theme.error));
+                        if ui.small_button("Show log").on_hover_text("Open the client output below").clicked() {
+                            let mut log = egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), Self::log_id(), false);
+                            log.set_open(true);
+                            log.store(ui.ctx());
+                        }
+                    });
                 }
             }
+            
+            ui.add_space(4.0);
+            if ui.small_button("🧭 Routing Table").on_hover_text("Show the system's routes, with those through the VPN highlighted").clicked() {
+                ui.data_mut(|d| d.insert_temp(Self::routes_window_id(), true));
+            }
         });
     }
     
-    fn draw_connections_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, network_manager: &mut NetworkManager, loading_actions: &std::collections::HashSet<String>, animation_time: f32) {
+    fn draw_connections_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, network_manager: &mut NetworkManager,
+                             command_preview: &mut Option<CommandPreview>, client_log_view: &mut Option<(String, String)>,
+                             navigation: &mut Navigation, loading_actions: &std::collections::HashSet<String>, animation_time: f32,
+                             route_editor: &mut RouteEditor, leak_tests: &mut LeakTests, service_statuses: &mut ServiceStatuses,
+                             otp_prompt: &mut Option<OtpPrompt>, edit: &mut Option<usize>) {
         Card::show(ui, theme, "VPN Connections", |ui| {
             if config.vpn_configs.is_empty() {
                 ui.label(egui::RichText::new("No VPN configurations found").color(theme.text_secondary));
@@ -54,30 +347,84 @@ impl VpnPanel {
             }
             
             let mut to_remove = None;
+            let mut open_schedule = None;
+            let mut toggle_kill_switch = None;
+            let mut toggle_otp = None;
+            let mut start_leak_test = None;
+            let mut restyle = None;
+            let mut new_policy = None;
+            let mut new_health = None;
+            let mut new_proxy = None;
+            let mut new_certificates = None;
+            let mut new_commands = None;
+            let mut toggle_boot = None;
+            let mut toggle_startup = None;
+            let mut service_action = None;
+            let boot_supported = boot::is_supported();
             
             for (index, vpn_config) in config.vpn_configs.iter().enumerate() {
-                ui.horizontal(|ui| {
+                let managed = boot_supported && boot::is_enabled(vpn_config);
+                FocusFrame::show(ui, theme, navigation, &vpn_config.name, |ui| ui.horizontal(|ui| {
+                    let mut style = vpn_config.style.clone();
+                    if StylePicker::show(ui, theme, &mut style, "🌐") {
+                        restyle = Some((index, style));
+                    }
                     ui.vertical(|ui| {
-                        ui.label(&vpn_config.name);
+                        entry_label(ui, &vpn_config.name, Route::new(Panel::Vpn, &vpn_config.name));
                         let vpn_type_str = match vpn_config.vpn_type {
                             VpnType::OpenVpn => "OpenVPN",
                             VpnType::WireGuard => "WireGuard",
+                            VpnType::Ikev2 => "IKEv2/IPsec",
+                            VpnType::OpenConnect => "OpenConnect",
+                            VpnType::Custom => "Custom",
                         };
-                        ui.label(egui::RichText::new(vpn_type_str).color(theme.text_secondary));
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(vpn_type_str).color(theme.text_secondary));
+                            if managed {
+                                ui.label(egui::RichText::new("Managed by system").small().color(theme.primary))
+                                    .on_hover_text(format!("Started at boot by {}; connecting and disconnecting start and stop it", boot::unit_name(vpn_config)));
+                            }
+                            if vpn_config.system_service {
+                                ui.label(egui::RichText::new("System service").small().color(theme.primary))
+                                    .on_hover_text(format!("Runs as {}, also while the app is closed; connecting and disconnecting start and stop it", services::unit_name(vpn_config)));
+                            }
+                            if let Some((kind, not_after)) = certs::expiry(vpn_config).into_iter().min_by_key(|(_, date)| *date) {
+                                let date = not_after.format("%Y-%m-%d");
+                                let (text, color) = if not_after < chrono::Utc::now() {
+                                    (format!("Certificate expired {}", date), theme.error)
+                                } else if certs::expiring(vpn_config).is_some() {
+                                    (format!("Certificate expires {}", date), theme.warning)
+                                } else {
+                                    (format!("Certificate until {}", date), theme.text_secondary)
+                                };
+                                ui.label(egui::RichText::new(text).small().color(color))
+                                    .on_hover_text(format!("{} valid until {}, see 📜", kind.label(), not_after.format("%Y-%m-%d %H:%M UTC")));
+                            }
+                            if config.always_on_vpn().is_some_and(|always_on| always_on.name == vpn_config.name) {
+                                ui.label(egui::RichText::new("Always-on").small().color(theme.primary))
+                                    .on_hover_text("Kept connected while the app runs, see Settings → Always-on VPN");
+                            }
+                            if let Some(run) = config.upcoming_action(ScheduledActionKind::ConnectVpn, &vpn_config.name) {
+                                if ScheduleBadge::show(ui, theme, run) {
+                                    open_schedule = Some(vpn_config.name.clone());
+                                }
+                            }
+                        });
                     });
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         if ui.button("🗑").clicked() {
                             to_remove = Some(index);
                         }
+                        if ui.button("✏").on_hover_text("Edit").clicked() {
+                            *edit = Some(index);
+                        }
                         
-                        let is_connected = matches!(
-                            &network_manager.vpn_status,
-                            VpnStatus::Connected(name) if name == &vpn_config.name
-                        );
+                        let is_connected = network_manager.vpn_status.connected_name() == Some(vpn_config.name.as_str());
                         
+                        // Still checking counts too, the profile may turn out to be up already
                         let is_connecting = matches!(
                             &network_manager.vpn_status,
-                            VpnStatus::Connecting
+                            VpnStatus::Checking | VpnStatus::Connecting | VpnStatus::Reconnecting { .. }
                         );
                         
                         let disconnect_action = format!("disconnect_{}", vpn_config.name);
@@ -93,36 +440,1087 @@ impl VpnPanel {
                             }
                         }
                         
+                        let kill_switch_hint = if vpn_config.kill_switch {
+                            "Kill switch on: traffic outside the tunnel is blocked until you disconnect"
+                        } else {
+                            "Kill switch off: click to block traffic outside the tunnel while connected"
+                        };
+                        if ui.selectable_label(vpn_config.kill_switch, "🛡").on_hover_text(kill_switch_hint).clicked() {
+                            toggle_kill_switch = Some(index);
+                        }
+                        
+                        let editing_routes = route_editor.entry.as_deref() == Some(vpn_config.name.as_str());
+                        let routes_hint = format!("Split tunneling and DNS ({} routes)", vpn_config.split_routes.len());
+                        if ui.selectable_label(editing_routes, "🔀").on_hover_text(routes_hint).clicked() {
+                            route_editor.entry = if editing_routes { None } else { Some(vpn_config.name.clone()) };
+                            route_editor.dns_servers = vpn_config.dns_servers.join(", ");
+                            route_editor.dns_search_domains = vpn_config.dns_search_domains.join(", ");
+                        }
+                        
+                        if ui.small_button("📄").on_hover_text("Client log").clicked() {
+                            *client_log_view = Some(("vpn".to_string(), vpn_config.name.clone()));
+                        }
+                        
+                        // One or the other: both would bring the tunnel up at boot
+                        if boot_supported && services::supports(&vpn_config.vpn_type) && !managed {
+                            let service_hint = if vpn_config.system_service {
+                                format!("Installed as {}", services::unit_name(vpn_config))
+                            } else {
+                                "Install as a systemd service that keeps running without the app".to_string()
+                            };
+                            ui.menu_button(egui::RichText::new("⚙").color(if vpn_config.system_service { theme.primary } else { theme.text_secondary }), |ui| {
+                                if let Some(action) = Self::draw_system_service(ui, theme, vpn_config, service_statuses) {
+                                    service_action = Some((index, action));
+                                    ui.close_menu();
+                                }
+                            }).response.on_hover_text(service_hint);
+                        }
+                        
+                        if boot_supported && boot::supports(&vpn_config.vpn_type) && !vpn_config.system_service {
+                            let boot_hint = if managed {
+                                "Starts at boot: click to remove the system service, which also stops a tunnel it started"
+                            } else {
+                                "Start at boot, before login, as a system service"
+                            };
+                            if ui.selectable_label(managed, "⏻").on_hover_text(boot_hint).clicked() {
+                                toggle_boot = Some((index, managed));
+                            }
+                        }
+                        
+                        let mut startup = vpn_config.auto_connect;
+                        let startup_hint = if config.auto_connect_vpn {
+                            "Connect this VPN when the app starts"
+                        } else {
+                            "Connect this VPN when the app starts (also turns on auto-connect in Settings)"
+                        };
+                        if ui.checkbox(&mut startup, "Startup").on_hover_text(startup_hint).changed() {
+                            toggle_startup = Some((index, startup));
+                        }
+                        
+                        let mut policy = vpn_config.reconnect.clone();
+                        let reconnect_hint = if policy.enabled {
+                            format!("Auto reconnect on: up to {} attempts", policy.max_retries)
+                        } else {
+                            "Auto reconnect off".to_string()
+                        };
+                        ui.menu_button(egui::RichText::new("🔁").color(if policy.enabled { theme.primary } else { theme.text_secondary }), |ui| {
+                            Self::draw_reconnect_policy(ui, theme, &mut policy, &vpn_config.vpn_type);
+                        }).response.on_hover_text(reconnect_hint);
+                        if policy != vpn_config.reconnect {
+                            new_policy = Some((index, policy));
+                        }
+                        
+                        let mut health = vpn_config.health.clone();
+                        let health_hint = if health.enabled && !health.host.is_empty() {
+                            format!("Tunnel ping to {} every {} s", health.host, health.interval_secs)
+                        } else {
+                            "Tunnel ping off".to_string()
+                        };
+                        ui.menu_button(egui::RichText::new("💓").color(if health.enabled { theme.primary } else { theme.text_secondary }), |ui| {
+                            Self::draw_health_check(ui, theme, &mut health);
+                        }).response.on_hover_text(health_hint);
+                        if health != vpn_config.health {
+                            new_health = Some((index, health));
+                        }
+                        
+                        if vpn_config.vpn_type == VpnType::Custom {
+                            let mut commands = vpn_config.custom.clone();
+                            ui.menu_button(egui::RichText::new("🧰").color(theme.text_secondary), |ui| {
+                                Self::draw_custom_commands(ui, theme, &mut commands);
+                            }).response.on_hover_text("Connect, disconnect and status commands");
+                            if commands != vpn_config.custom {
+                                new_commands = Some((index, commands));
+                            }
+                        }
+                        
+                        if vpn_config.vpn_type == VpnType::OpenVpn {
+                            let mut entry_proxy = vpn_config.proxy.clone();
+                            let proxy_hint = if proxy::is_set(vpn_config) {
+                                format!("Through the {} proxy {}:{} (applies on the next connect)", entry_proxy.kind.label(), entry_proxy.host, entry_proxy.port)
+                            } else {
+                                "No proxy".to_string()
+                            };
+                            ui.menu_button(egui::RichText::new("🌐").color(if proxy::is_set(vpn_config) { theme.primary } else { theme.text_secondary }), |ui| {
+                                Self::draw_proxy_form(ui, theme, &mut entry_proxy);
+                            }).response.on_hover_text(proxy_hint);
+                            if entry_proxy != vpn_config.proxy {
+                                new_proxy = Some((index, entry_proxy));
+                            }
+                            
+                            let mut files = vpn_config.certificates.clone();
+                            let attached = certs::CertKind::ALL.iter().filter(|kind| !kind.path(&files).trim().is_empty()).count();
+                            let certs_hint = if attached > 0 {
+                                format!("{} attached certificate and key files (applies on the next connect)", attached)
+                            } else {
+                                "Certificates and keys from the profile".to_string()
+                            };
+                            ui.menu_button(egui::RichText::new("📜").color(if attached > 0 { theme.primary } else { theme.text_secondary }), |ui| {
+                                Self::draw_certificates(ui, theme, vpn_config, &mut files, config.manage_config_files);
+                            }).response.on_hover_text(certs_hint);
+                            if files != vpn_config.certificates {
+                                new_certificates = Some((index, files));
+                            }
+                            
+                            let otp_hint = if vpn_config.prompt_otp {
+                                "Asks for a one-time code on each connect"
+                            } else {
+                                "Ask for a one-time code on each connect"
+                            };
+                            if ui.selectable_label(vpn_config.prompt_otp, "🔑").on_hover_text(otp_hint).clicked() {
+                                toggle_otp = Some(index);
+                            }
+                        }
+                        
+                        let testing = leak_tests.running.as_deref() == Some(vpn_config.name.as_str());
+                        let leak_hint = if is_connected { "Check for DNS leaks" } else { "Check for DNS leaks (connect first)" };
+                        if ui.add_enabled(is_connected && leak_tests.running.is_none(), egui::Button::new(if testing { "⏳" } else { "🕵" }).small())
+                            .on_hover_text(leak_hint)
+                            .on_disabled_hover_text(leak_hint)
+                            .clicked()
+                        {
+                            start_leak_test = Some(index);
+                        }
+                        
+                        if ui.small_button("⌨").on_hover_text("Show command").clicked() {
+                            *command_preview = Some(if is_connected {
+                                preview::vpn_disconnect(vpn_config)
+                            } else {
+                                preview::vpn_connect(vpn_config)
+                            });
+                        }
+                        
+                        // The reconnect loop or a scheduled action may be working on the entry
+                        let busy = executor::is_busy(&vpn_config.name);
+                        let busy_hint = "Another connect or disconnect of this VPN is still running";
                         if is_connected {
-                            if ui.button("Disconnect").clicked() {
+                            if ui.add_enabled(!busy, egui::Button::new("Disconnect")).on_disabled_hover_text(busy_hint).clicked() {
                                 // Set status to disconnected for now
                                 // TODO: Implement proper async disconnect
                                 network_manager.vpn_status = VpnStatus::Disconnected;
                             }
                         } else {
-                            if ui.button("Connect").clicked() && !is_connecting {
-                                // Set status to connecting for now  
-                                // TODO: Implement proper async connect
-                                network_manager.vpn_status = VpnStatus::Connecting;
+                            if ui.add_enabled(!busy, egui::Button::new("Connect")).on_disabled_hover_text(busy_hint).clicked() && !is_connecting {
+                                if otp::needs_code(vpn_config) {
+                                    *otp_prompt = Some(OtpPrompt::new(vpn_config));
+                                } else {
+                                    // Set status to connecting for now  
+                                    // TODO: Implement proper async connect
+                                    network_manager.vpn_status = VpnStatus::Connecting;
+                                }
                             }
                         }
                     });
-                });
+                }));
+                if let Some(result) = leak_tests.results.get(&vpn_config.name) {
+                    Self::draw_leak_test_result(ui, theme, result);
+                }
                 ui.separator();
             }
             
+            if let Some(vpn_config) = start_leak_test.and_then(|index| config.vpn_configs.get(index)) {
+                leak_tests.start(vpn_config);
+            }
+            
+            if let Some(vpn_config) = toggle_kill_switch.and_then(|index| config.vpn_configs.get_mut(index)) {
+                vpn_config.kill_switch = !vpn_config.kill_switch;
+            }
+            if let Some(vpn_config) = toggle_otp.and_then(|index| config.vpn_configs.get_mut(index)) {
+                vpn_config.prompt_otp = !vpn_config.prompt_otp;
+            }
+            if let Some((index, startup)) = toggle_startup {
+                let name = config.vpn_configs[index].name.clone();
+                config.set_startup_vpn(startup.then_some(name.as_str()));
+                config.auto_connect_vpn |= startup;
+            }
+            if let Some((index, style)) = restyle {
+                config.vpn_configs[index].style = style;
+            }
+            if let Some((index, policy)) = new_policy {
+                config.vpn_configs[index].reconnect = policy;
+            }
+            if let Some((index, health)) = new_health {
+                config.vpn_configs[index].health = health;
+            }
+            if let Some((index, proxy)) = new_proxy {
+                config.vpn_configs[index].proxy = proxy;
+            }
+            if let Some((index, files)) = new_certificates {
+                config.vpn_configs[index].certificates = files;
+            }
+            if let Some((index, commands)) = new_commands {
+                config.vpn_configs[index].custom = commands;
+            }
+            if let Some((vpn_config, managed)) = toggle_boot.and_then(|(index, managed)| config.vpn_configs.get(index).map(|c| (c, managed))) {
+                let runtime = tokio::runtime::Runtime::new().unwrap();
+                let result = if managed {
+                    runtime.block_on(boot::disable(vpn_config))
+                } else {
+                    runtime.block_on(boot::enable(vpn_config))
+                };
+                if let Err(e) = result {
+                    log::error!("Failed to change start at boot for {}: {}", vpn_config.name, e);
+                }
+            }
+            if let Some((index, action)) = service_action {
+                Self::apply_service_action(&mut config.vpn_configs[index], action, service_statuses);
+            }
             if let Some(index) = to_remove {
-                config.vpn_configs.remove(index);
+                let removed = config.vpn_configs.remove(index);
+                // Its unit would keep running a tunnel the app no longer lists
+                if removed.system_service {
+                    let runtime = tokio::runtime::Runtime::new().unwrap();
+                    if let Err(e) = runtime.block_on(services::uninstall(&removed)) {
+                        log::error!("Failed to remove the system service of {}: {}", removed.name, e);
+                    }
+                }
+                config.failover.order.retain(|name| *name != removed.name);
+                if config.always_on.vpn == removed.name {
+                    config.always_on.vpn.clear();
+                }
+            }
+            if let Some(name) = open_schedule {
+                navigation.request(Route::new(Panel::Schedule, &name));
+            }
+        });
+    }
+    
+    fn draw_traffic(ui: &mut egui::Ui, theme: &Theme, traffic: &TrafficStats) {
+        ui.add_space(8.0);
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(format!("⏱ {}", stats::format_uptime(traffic.uptime()))).color(theme.text_secondary))
+                .on_hover_text("Time since the connection was first seen up");
+            if let Some((received, sent)) = traffic.totals() {
+                let (down, up) = traffic.throughput().unwrap_or((0.0, 0.0));
+                ui.label(egui::RichText::new(format!("↓ {} ({}/s)", stats::format_bytes(received as f64), stats::format_bytes(down))).color(theme.primary));
+                ui.label(egui::RichText::new(format!("↑ {} ({}/s)", stats::format_bytes(sent as f64), stats::format_bytes(up))).color(theme.accent));
+            }
+        });
+        if let Some(health) = &traffic.health {
+            Self::draw_health(ui, theme, health);
+        }
+        if let Some(session) = &traffic.session {
+            Self::draw_session(ui, theme, session);
+        }
+        if let Some(error) = &traffic.error {
+            ui.label(egui::RichText::new(format!("Traffic counters unavailable: {}", error)).size(11.0).color(theme.text_secondary));
+            return;
+        }
+        
+        // Throughput over the last two minutes, both directions on one scale
+        TrafficGraph::show(ui, theme, &traffic.rates(), 48.0);
+    }
+    
+    fn draw_health(ui: &mut egui::Ui, theme: &Theme, health: &TunnelHealth) {
+        let Some(loss) = health.loss_percent() else {
+            ui.label(egui::RichText::new(format!("Pinging {} through the tunnel...", health.host)).size(11.0).color(theme.text_secondary));
+            return;
+        };
+        if health.not_passing() {
+            ui.label(egui::RichText::new(format!("⚠ Connected but not passing traffic: {} doesn't answer", health.host)).color(theme.error))
+                .on_hover_text("The tunnel is up but the last pings through it got no answer. The server may have dropped the session; try reconnecting.");
+            return;
+        }
+        let rtt = health.last_rtt().map_or("no answer".to_string(), |rtt| format!("{} ms", rtt.as_millis()));
+        let color = if loss > 0 { theme.warning } else { theme.text_secondary };
+        ui.label(egui::RichText::new(format!("💓 {}: {}, {}% loss", health.host, rtt, loss)).size(11.0).color(color))
+            .on_hover_text("Ping through the tunnel, loss over the last 10 probes");
+    }
+    
+    fn draw_session(ui: &mut egui::Ui, theme: &Theme, session: &vpn::SessionInfo) {
+        let rows = [
+            ("Virtual IP", session.virtual_ip.as_deref()),
+            ("Server", session.remote.as_deref()),
+            ("Cipher", session.cipher.as_deref()),
+        ];
+        egui::Grid::new("openvpn_session").num_columns(2).spacing([12.0, 2.0]).show(ui, |ui| {
+            for (label, value) in rows {
+                ui.label(egui::RichText::new(label).size(11.0).color(theme.text_secondary));
+                ui.label(egui::RichText::new(value.unwrap_or("—")).size(11.0).color(theme.text_primary));
+                ui.end_row();
+            }
+        });
+        if let Some(updated) = &session.updated {
+            ui.label(egui::RichText::new(format!("Status file updated {}", updated)).size(10.0).color(theme.text_secondary));
+        }
+    }
+    
+    fn log_id() -> egui::Id {
+        egui::Id::new("vpn_live_log")
+    }
+    
+    // Whether the log is open in a window instead of the card
+    fn log_window_id() -> egui::Id {
+        egui::Id::new("vpn_live_log_window")
+    }
+    
+    // Whether the routing table window is open
+    fn routes_window_id() -> egui::Id {
+        egui::Id::new("vpn_routes_window")
+    }
+    
+    // Set by ✏ to scroll the connection form into view
+    fn reveal_form_id() -> egui::Id {
+        egui::Id::new("vpn_reveal_form")
+    }
+    
+    // Entry shown (None for all) and the lowest level shown, shared by the card and the window
+    fn log_filter_id() -> egui::Id {
+        egui::Id::new("vpn_live_log_filter")
+    }
+    
+    // Output of the VPN clients as it comes in, kept in memory for the whole session; the files
+    // behind the 📄 buttons hold the same and more
+    fn draw_log_card(ui: &mut egui::Ui, theme: &Theme, config: &Config) {
+        let (entry, min_level) = ui.data(|d| d.get_temp::<(Option<String>, LogLevel)>(Self::log_filter_id()))
+            .unwrap_or((None, LogLevel::Info));
+        let lines = client_log::live("vpn", entry.as_deref(), min_level);
+        let in_window = ui.data(|d| d.get_temp::<bool>(Self::log_window_id())).unwrap_or(false);
+        
+        Card::show(ui, theme, "Connection Log", |ui| {
+            let errors = lines.iter().filter(|line| line.level == LogLevel::Error).count();
+            let summary = match errors {
+                0 => format!("{} line(s)", lines.len()),
+                errors => format!("{} line(s), {} error(s)", lines.len(), errors),
+            };
+            if in_window {
+                ui.label(egui::RichText::new(format!("{}, shown in its own window", summary)).color(theme.text_secondary));
+                return;
             }
+            let state = egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), Self::log_id(), false);
+            let is_open = state.is_open();
+            state.show_header(ui, |ui| {
+                ui.label(egui::RichText::new(summary).color(if errors > 0 { theme.error } else { theme.text_secondary }));
+                if ui.small_button("⧉").on_hover_text("Open in a window, e.g. to keep it in view on another monitor").clicked() {
+                    ui.data_mut(|d| d.insert_temp(Self::log_window_id(), true));
+                }
+            })
+            .body(|ui| Self::draw_log(ui, theme, config, 240.0));
+            
+            if is_open {
+                ui.ctx().request_repaint_after(std::time::Duration::from_millis(500));
+            }
+        });
+    }
+    
+    // The connection log opened from the card's ⧉, drawn by the app on every tab
+    pub fn draw_log_window(ctx: &egui::Context, config: &Config) {
+        if !ctx.data(|d| d.get_temp::<bool>(Self::log_window_id())).unwrap_or(false) {
+            return;
+        }
+        let theme = Theme::current();
+        let open = ToolWindow::show(ctx, "vpn_live_log", "Connection Log", egui::vec2(700.0, 420.0), |ui| {
+            Self::draw_log(ui, &theme, config, f32::INFINITY);
         });
+        if !open {
+            ctx.data_mut(|d| d.insert_temp(Self::log_window_id(), false));
+        }
+        ctx.request_repaint_after(std::time::Duration::from_millis(500));
+    }
+    
+    // The routing table opened from the status card, read again whenever the connected VPN
+    // changes. Rows leaving through the tunnel's interface are highlighted.
+    pub fn draw_routes_window(ctx: &egui::Context, watch: &mut RouteTableWatch, connected: Option<&VpnConfig>) {
+        if !ctx.data(|d| d.get_temp::<bool>(Self::routes_window_id())).unwrap_or(false) {
+            return;
+        }
+        watch.sync(connected);
+        watch.poll();
+        if watch.is_loading() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(250));
+        }
+        
+        let theme = Theme::current();
+        let mut refresh = false;
+        let mut only_tunnel = ctx.data(|d| d.get_temp::<bool>(Self::routes_window_id().with("only_tunnel"))).unwrap_or(false);
+        let open = ToolWindow::show(ctx, "vpn_routes", "Routing Table", egui::vec2(720.0, 440.0), |ui| {
+            ui.horizontal(|ui| {
+                refresh = ui.add_enabled(!watch.is_loading(), egui::Button::new("⟳ Refresh")).clicked();
+                ui.checkbox(&mut only_tunnel, "Only routes through the VPN");
+                if watch.is_loading() {
+                    ui.spinner();
+                }
+            });
+            
+            let snapshot = match &watch.current {
+                None => return,
+                Some(Err(e)) => {
+                    ui.label(egui::RichText::new(format!("Couldn't read the routing table: {}", e)).color(theme.error));
+                    return;
+                }
+                Some(Ok(snapshot)) => snapshot,
+            };
+            let summary = match (&snapshot.vpn, snapshot.tunnel.is_empty()) {
+                (None, _) => format!("{} route(s), no VPN connected", snapshot.routes.len()),
+                (Some(vpn), true) => format!("{} route(s), interface of {} not found", snapshot.routes.len(), vpn),
+                (Some(vpn), false) => format!("{} route(s), {} through {}", snapshot.routes.len(), snapshot.through_tunnel(), vpn),
+            };
+            ui.label(egui::RichText::new(format!("{} · read at {}", summary, snapshot.taken.format("%H:%M:%S")))
+                .color(theme.text_secondary).small());
+            ui.separator();
+            
+            let show_table = snapshot.routes.iter().any(|route| route.table.is_some());
+            egui::ScrollArea::both().auto_shrink([false, false]).show(ui, |ui| {
+                egui::Grid::new("vpn_routes_grid").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+                    let header = |ui: &mut egui::Ui, text: &str| {
+                        ui.label(egui::RichText::new(text).strong());
+                    };
+                    header(ui, "Destination");
+                    header(ui, "Gateway");
+                    header(ui, "Interface");
+                    header(ui, "Metric");
+                    if show_table {
+                        header(ui, "Table");
+                    }
+                    ui.end_row();
+                    
+                    for route in &snapshot.routes {
+                        let tunnel = route.uses(&snapshot.tunnel);
+                        if only_tunnel && !tunnel {
+                            continue;
+                        }
+                        let color = if tunnel { theme.primary } else { theme.text_primary };
+                        let cell = |ui: &mut egui::Ui, text: String| {
+                            let text = egui::RichText::new(text).monospace().color(color);
+                            ui.label(if tunnel { text.strong() } else { text });
+                        };
+                        let destination = match &route.kind {
+                            Some(kind) => format!("{} ({})", route.destination, kind),
+                            None => route.destination.clone(),
+                        };
+                        cell(ui, destination);
+                        cell(ui, route.gateway.clone().unwrap_or_else(|| "on-link".to_string()));
+                        cell(ui, route.interface.clone());
+                        cell(ui, route.metric.map(|metric| metric.to_string()).unwrap_or_default());
+                        if show_table {
+                            cell(ui, route.table.clone().unwrap_or_default());
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+        
+        if refresh {
+            watch.refresh(connected);
+        }
+        ctx.data_mut(|d| d.insert_temp(Self::routes_window_id().with("only_tunnel"), only_tunnel));
+        if !open {
+            ctx.data_mut(|d| d.insert_temp(Self::routes_window_id(), false));
+            // Read afresh the next time it is opened
+            *watch = RouteTableWatch::default();
+        }
+    }
+    
+    // Asks for the code of an entry that wants one; returns the entry and the code once entered
+    pub fn draw_otp_prompt(ctx: &egui::Context, prompt: &mut Option<OtpPrompt>) -> Option<(String, String)> {
+        let current = prompt.as_mut()?;
+        let theme = Theme::current();
+        let mut submit = false;
+        let mut cancel = false;
+        
+        egui::Window::new("One-Time Code")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!("{} asks for a one-time code with the password.", current.vpn));
+                if let Some(challenge) = current.challenge.as_deref().filter(|text| !text.is_empty()) {
+                    ui.label(egui::RichText::new(challenge).color(theme.text_secondary));
+                }
+                ui.add_space(8.0);
+                let field = ui.add(egui::TextEdit::singleline(&mut current.code)
+                    .hint_text("Code from your authenticator")
+                    .desired_width(220.0));
+                if !field.has_focus() && current.code.is_empty() {
+                    field.request_focus();
+                }
+                let entered = !current.code.trim().is_empty();
+                if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && entered {
+                    submit = true;
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(entered, egui::Button::new("Connect")).clicked() {
+                        submit = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+        
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            cancel = true;
+        }
+        if cancel {
+            *prompt = None;
+            return None;
+        }
+        if submit {
+            return prompt.take().map(|prompt| (prompt.vpn, prompt.code));
+        }
+        None
+    }
+    
+    fn draw_log(ui: &mut egui::Ui, theme: &Theme, config: &Config, max_height: f32) {
+        let (mut entry, mut min_level) = ui.data(|d| d.get_temp::<(Option<String>, LogLevel)>(Self::log_filter_id()))
+            .unwrap_or((None, LogLevel::Info));
+        let lines = client_log::live("vpn", entry.as_deref(), min_level);
+        
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("vpn_live_log_entry")
+                .selected_text(entry.as_deref().unwrap_or("All connections"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut entry, None, "All connections");
+                    for vpn_config in &config.vpn_configs {
+                        ui.selectable_value(&mut entry, Some(vpn_config.name.clone()), &vpn_config.name);
+                    }
+                });
+            egui::ComboBox::from_id_salt("vpn_live_log_level")
+                .selected_text(format!("{} and above", min_level.label()))
+                .show_ui(ui, |ui| {
+                    for level in LogLevel::ALL {
+                        ui.selectable_value(&mut min_level, level, format!("{} and above", level.label()));
+                    }
+                });
+            if ui.button("📋 Copy").clicked() {
+                let text: Vec<String> = lines.iter().map(|line| line.to_text()).collect();
+                ui.output_mut(|o| o.copied_text = text.join("\n"));
+            }
+            if ui.button("Clear").clicked() {
+                client_log::clear_live("vpn", entry.as_deref());
+            }
+        });
+        ui.add_space(4.0);
+        
+        egui::ScrollArea::vertical().max_height(max_height).stick_to_bottom(true).show(ui, |ui| {
+            if lines.is_empty() {
+                ui.label(egui::RichText::new("Nothing yet. Output appears here while connecting.").color(theme.text_secondary));
+            }
+            for line in &lines {
+                let color = match line.level {
+                    LogLevel::Error => theme.error,
+                    LogLevel::Warning => theme.warning,
+                    LogLevel::Info => theme.text_primary,
+                    LogLevel::Debug => theme.text_secondary,
+                };
+                let prefix = if entry.is_none() { format!("{}: ", line.entry) } else { String::new() };
+                ui.add(egui::Label::new(egui::RichText::new(format!("{}{}", prefix, line.text)).monospace().size(11.0).color(color)).wrap())
+                    .on_hover_text(client_log::format_timestamp(line.time));
+            }
+        });
+        
+        ui.data_mut(|d| d.insert_temp(Self::log_filter_id(), (entry, min_level)));
+    }
+    
+    // Priority list of the profiles tried one after another
+    fn draw_failover_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config) {
+        Card::show(ui, theme, "Failover", |ui| {
+            let policy = &mut config.failover;
+            ui.checkbox(&mut policy.enabled, "Try the next profile when one fails to connect or keeps dropping");
+            ui.label(egui::RichText::new("Profiles are tried from the top; connecting one of them falls back to the ones below it")
+                .size(11.0).color(theme.text_secondary));
+            if !policy.enabled {
+                return;
+            }
+            
+            ui.add_space(8.0);
+            let mut move_up = None;
+            let mut remove = None;
+            let count = policy.order.len();
+            for (index, name) in policy.order.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}.", index + 1));
+                    ui.label(name);
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("✖").on_hover_text("Leave out of failover").clicked() {
+                            remove = Some(index);
+                        }
+                        if ui.add_enabled(index + 1 < count, egui::Button::new("⬇").small()).clicked() {
+                            move_up = Some(index + 1);
+                        }
+                        if ui.add_enabled(index > 0, egui::Button::new("⬆").small()).clicked() {
+                            move_up = Some(index);
+                        }
+                    });
+                });
+            }
+            if let Some(index) = move_up {
+                policy.order.swap(index - 1, index);
+            }
+            if let Some(index) = remove {
+                policy.order.remove(index);
+            }
+            
+            let available: Vec<&String> = config.vpn_configs.iter()
+                .map(|vpn_config| &vpn_config.name)
+                .filter(|name| !policy.order.contains(name))
+                .collect();
+            if !available.is_empty() {
+                let mut added = None;
+                egui::ComboBox::from_id_salt("failover_add")
+                    .selected_text("Add profile...")
+                    .show_ui(ui, |ui| {
+                        for name in available {
+                            if ui.selectable_label(false, name.as_str()).clicked() {
+                                added = Some(name.clone());
+                            }
+                        }
+                    });
+                if let Some(name) = added {
+                    policy.order.push(name);
+                }
+            }
+            
+            ui.add_space(8.0);
+            ui.horizontal(|ui| {
+                ui.label("Pass a profile over after");
+                ui.add(egui::DragValue::new(&mut policy.max_drops).range(1..=20));
+                ui.label(format!("drops in {} minutes", failover::DROP_WINDOW.as_secs() / 60));
+            });
+        });
+    }
+    
+    fn draw_reconnect_policy(ui: &mut egui::Ui, theme: &Theme, policy: &mut crate::config::ReconnectPolicy, vpn_type: &VpnType) {
+        ui.checkbox(&mut policy.enabled, "Reconnect when the tunnel drops");
+        ui.add_enabled_ui(policy.enabled, |ui| {
+            egui::Grid::new("reconnect_policy").num_columns(2).spacing([8.0, 4.0]).show(ui, |ui| {
+                ui.label("Attempts");
+                ui.add(egui::DragValue::new(&mut policy.max_retries).range(1..=50));
+                ui.end_row();
+                ui.label("First wait");
+                ui.add(egui::DragValue::new(&mut policy.base_delay_secs).range(1..=600).suffix(" s"));
+                ui.end_row();
+                ui.label("Longest wait");
+                ui.add(egui::DragValue::new(&mut policy.max_delay_secs).range(policy.base_delay_secs..=3600).suffix(" s"));
+                ui.end_row();
+            });
+        });
+        ui.label(egui::RichText::new("The wait doubles after each failed attempt").size(11.0).color(theme.text_secondary));
+        if *vpn_type == VpnType::WireGuard {
+            ui.separator();
+            ui.checkbox(&mut policy.on_stale_handshake, "Recover when the handshake goes stale");
+            ui.label(egui::RichText::new("Re-handshakes first, then reconnects if the server still doesn't answer").size(11.0).color(theme.text_secondary));
+        }
+    }
+    
+    fn draw_health_check(ui: &mut egui::Ui, theme: &Theme, health: &mut crate::config::HealthCheck) {
+        ui.checkbox(&mut health.enabled, "Ping a host through the tunnel");
+        ui.add_enabled_ui(health.enabled, |ui| {
+            egui::Grid::new("health_check").num_columns(2).spacing([8.0, 4.0]).show(ui, |ui| {
+                ui.label("Host");
+                ui.add(egui::TextEdit::singleline(&mut health.host).hint_text("10.8.0.1").desired_width(140.0));
+                ui.end_row();
+                ui.label("Every");
+                ui.add(egui::DragValue::new(&mut health.interval_secs).range(2..=600).suffix(" s"));
+                ui.end_row();
+            });
+        });
+        ui.label(egui::RichText::new("The VPN gateway or a server only reachable over the VPN; checked from the next connect").size(11.0).color(theme.text_secondary));
+    }
+    
+    // Install, start and stop controls, with the unit's state read from systemctl now and then
+    // while the menu is open
+    fn draw_system_service(ui: &mut egui::Ui, theme: &Theme, vpn_config: &VpnConfig, service_statuses: &mut ServiceStatuses) -> Option<ServiceAction> {
+        let mut action = None;
+        if !vpn_config.system_service {
+            ui.label(format!("Copies the profile to {} and runs it as {}", services::profile_path(vpn_config).display(), services::unit_name(vpn_config)));
+            ui.label(egui::RichText::new("The tunnel then survives closing the app and can start at boot. Reinstall after editing the file.").size(11.0).color(theme.text_secondary));
+            if ui.button("Install").clicked() {
+                action = Some(ServiceAction::Install);
+            }
+            return action;
+        }
+
+        ui.label(egui::RichText::new(services::unit_name(vpn_config)).monospace());
+        let Some(status) = service_statuses.get(vpn_config) else {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.label(egui::RichText::new("Checking…").color(theme.text_secondary));
+            });
+            return action;
+        };
+        let (state, color) = if status.active { ("Running", theme.success) } else { ("Stopped", theme.text_secondary) };
+        ui.label(egui::RichText::new(state).color(color));
+        ui.horizontal(|ui| {
+            if status.active {
+                if ui.button("Stop").clicked() {
+                    action = Some(ServiceAction::Stop);
+                }
+            } else if ui.button("Start").clicked() {
+                action = Some(ServiceAction::Start);
+            }
+        });
+        let mut enabled = status.enabled;
+        if ui.checkbox(&mut enabled, "Start at boot").changed() {
+            action = Some(ServiceAction::SetEnabled(enabled));
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("Reinstall").on_hover_text("Copy the profile again, e.g. after editing it").clicked() {
+                action = Some(ServiceAction::Install);
+            }
+            if ui.button("Remove").on_hover_text("Stop the tunnel and delete the copied profile").clicked() {
+                action = Some(ServiceAction::Remove);
+            }
+        });
+        action
+    }
+    
+    fn apply_service_action(vpn_config: &mut VpnConfig, action: ServiceAction, service_statuses: &mut ServiceStatuses) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = match action {
+            ServiceAction::Install => runtime.block_on(services::install(vpn_config)),
+            ServiceAction::Remove => runtime.block_on(services::uninstall(vpn_config)),
+            ServiceAction::SetEnabled(enabled) => runtime.block_on(services::set_enabled(vpn_config, enabled)),
+            ServiceAction::Start => runtime.block_on(services::start(vpn_config)),
+            ServiceAction::Stop => runtime.block_on(services::stop(vpn_config)),
+        };
+        match result {
+            Ok(()) => match action {
+                ServiceAction::Install => vpn_config.system_service = true,
+                ServiceAction::Remove => vpn_config.system_service = false,
+                _ => {}
+            },
+            Err(e) => log::error!("System service action for {} failed: {}", vpn_config.name, e),
+        }
+        service_statuses.forget(&vpn_config.name);
+    }
+    
+    fn draw_leak_test_result(ui: &mut egui::Ui, theme: &Theme, result: &Result<dnsleak::LeakTestResult, String>) {
+        let result = match result {
+            Ok(result) => result,
+            Err(e) => {
+                ui.label(egui::RichText::new(format!("DNS leak test failed: {}", e)).color(theme.warning));
+                return;
+            }
+        };
+        
+        let (summary, color) = if result.passed() {
+            (format!("✔ No DNS leak: {} resolver(s), all through the tunnel", result.resolvers.len()), theme.success)
+        } else {
+            (format!("✖ DNS leak: {} of {} resolver(s) outside the tunnel", result.leaks(), result.resolvers.len()), theme.error)
+        };
+        let exit = match &result.exit_ip {
+            Some(ip) => format!("Exit {} ({}), tested {}", ip, result.exit_network, result.tested_at.format("%H:%M")),
+            None => format!("Tested {}", result.tested_at.format("%H:%M")),
+        };
+        
+        egui::CollapsingHeader::new(egui::RichText::new(summary).color(color))
+            .id_salt(("dns_leak", result.tested_at.timestamp()))
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new(exit).color(theme.text_secondary));
+                egui::Grid::new(("dns_leak_resolvers", result.tested_at.timestamp())).num_columns(4).spacing([12.0, 4.0]).show(ui, |ui| {
+                    for resolver in &result.resolvers {
+                        let (verdict, color) = if resolver.through_tunnel { ("Tunnel", theme.success) } else { ("Leak", theme.error) };
+                        ui.label(egui::RichText::new(verdict).color(color).strong());
+                        ui.label(&resolver.ip);
+                        ui.label(egui::RichText::new(&resolver.network).color(theme.text_secondary));
+                        ui.label(egui::RichText::new(&resolver.country).color(theme.text_secondary));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+    
+    fn draw_routes_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, route_editor: &mut RouteEditor) {
+        let Some(name) = route_editor.entry.clone() else { return };
+        let Some(vpn_config) = config.vpn_configs.iter_mut().find(|c| c.name == name) else {
+            route_editor.entry = None;
+            return;
+        };
+        
+        Card::show(ui, theme, &format!("Routing & DNS: {}", name), |ui| {
+            ui.label(egui::RichText::new("Routes are added after connecting and removed on disconnect; changes apply on the next connect")
+                .color(theme.text_secondary));
+            ui.add_space(8.0);
+            
+            let mut to_remove = None;
+            egui::Grid::new("split_routes").num_columns(3).spacing([12.0, 6.0]).show(ui, |ui| {
+                for (index, route) in vpn_config.split_routes.iter_mut().enumerate() {
+                    let valid = routes::parse_cidr(&route.cidr).is_some();
+                    let text_color = if valid { theme.text_primary } else { theme.error };
+                    ui.add(egui::TextEdit::singleline(&mut route.cidr).desired_width(180.0).text_color(text_color));
+                    Self::route_direction_combo(ui, ("split_route_direction", index), &mut route.via_tunnel);
+                    if ui.small_button("🗑").clicked() {
+                        to_remove = Some(index);
+                    }
+                    ui.end_row();
+                }
+                
+                ui.add(egui::TextEdit::singleline(&mut route_editor.new_cidr).desired_width(180.0).hint_text("10.0.0.0/8"));
+                Self::route_direction_combo(ui, "split_route_new_direction", &mut route_editor.new_via_tunnel);
+                let valid = routes::parse_cidr(&route_editor.new_cidr).is_some();
+                if ui.add_enabled(valid, egui::Button::new("Add")).clicked() {
+                    vpn_config.split_routes.push(SplitRoute {
+                        cidr: route_editor.new_cidr.trim().to_string(),
+                        via_tunnel: route_editor.new_via_tunnel,
+                    });
+                    route_editor.new_cidr.clear();
+                }
+                ui.end_row();
+            });
+            
+            if let Some(index) = to_remove {
+                vpn_config.split_routes.remove(index);
+            }
+            
+            if vpn_config.kill_switch && vpn_config.split_routes.iter().any(|r| !r.via_tunnel) {
+                ui.add_space(8.0);
+                ui.label(egui::RichText::new("The kill switch blocks traffic that bypasses the VPN").color(theme.warning));
+            }
+            
+            if netns::is_supported() {
+                ui.add_space(12.0);
+                ui.separator();
+                Self::draw_app_tunnel(ui, theme, vpn_config, route_editor);
+            }
+            
+            ui.add_space(12.0);
+            ui.separator();
+            Self::draw_dns(ui, theme, vpn_config, route_editor);
+        });
+    }
+    
+    fn draw_dns(ui: &mut egui::Ui, theme: &Theme, vpn_config: &mut VpnConfig, route_editor: &mut RouteEditor) {
+        ui.label(egui::RichText::new("DNS").strong());
+        ui.label(egui::RichText::new("Replaces the DNS servers the VPN pushes while connected; leave empty to keep them")
+            .color(theme.text_secondary));
+        ui.add_space(6.0);
+        
+        egui::Grid::new("vpn_dns").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+            ui.label("Servers:");
+            let invalid = vpn_config.dns_servers.iter().any(|s| s.parse::<std::net::IpAddr>().is_err());
+            let color = if invalid { theme.error } else { theme.text_primary };
+            let edit = egui::TextEdit::singleline(&mut route_editor.dns_servers)
+                .desired_width(260.0)
+                .text_color(color)
+                .hint_text("10.0.0.53, 10.0.0.54");
+            if ui.add(edit).changed() {
+                vpn_config.dns_servers = dns::parse_list(&route_editor.dns_servers);
+            }
+            ui.end_row();
+            
+            ui.label("Search domains:");
+            let invalid = vpn_config.dns_search_domains.iter().any(|d| !dns::is_valid_domain(d));
+            let color = if invalid { theme.error } else { theme.text_primary };
+            let edit = egui::TextEdit::singleline(&mut route_editor.dns_search_domains)
+                .desired_width(260.0)
+                .text_color(color)
+                .hint_text("corp.example.com");
+            if ui.add(edit).changed() {
+                vpn_config.dns_search_domains = dns::parse_list(&route_editor.dns_search_domains);
+            }
+            ui.end_row();
+        });
+    }
+    
+    fn draw_app_tunnel(ui: &mut egui::Ui, theme: &Theme, vpn_config: &mut VpnConfig, route_editor: &mut RouteEditor) {
+        let app_tunnel = &mut vpn_config.app_tunnel;
+        
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut app_tunnel.enabled, "Per-application tunneling");
+            Self::route_direction_combo(ui, "app_tunnel_direction", &mut app_tunnel.via_tunnel);
+        });
+        ui.label(egui::RichText::new(if app_tunnel.via_tunnel {
+            "Only these applications use the VPN. Works best when the VPN doesn't take over the default route."
+        } else {
+            "These applications go around the VPN through the regular gateway"
+        }).color(theme.text_secondary));
+        
+        if !app_tunnel.enabled {
+            return;
+        }
+        
+        ui.checkbox(&mut app_tunnel.launch_on_connect, "Start them when the VPN connects");
+        ui.add_space(6.0);
+        
+        // Launching needs the namespace, which only exists while connected
+        let active = netns::is_active(vpn_config);
+        let mut to_remove = None;
+        let mut to_launch = None;
+        egui::Grid::new("app_tunnel_apps").num_columns(3).spacing([12.0, 6.0]).show(ui, |ui| {
+            for (index, app) in vpn_config.app_tunnel.apps.iter_mut().enumerate() {
+                ui.add(egui::TextEdit::singleline(app).desired_width(260.0));
+                if ui.add_enabled(active, egui::Button::new("▶").small())
+                    .on_hover_text("Start in the tunnel's namespace")
+                    .on_disabled_hover_text("Available while connected")
+                    .clicked()
+                {
+                    to_launch = Some(app.clone());
+                }
+                if ui.small_button("🗑").clicked() {
+                    to_remove = Some(index);
+                }
+                ui.end_row();
+            }
+            
+            ui.add(egui::TextEdit::singleline(&mut route_editor.new_app).desired_width(260.0).hint_text("firefox --new-instance"));
+            if ui.add_enabled(!route_editor.new_app.trim().is_empty(), egui::Button::new("Add")).clicked() {
+                vpn_config.app_tunnel.apps.push(route_editor.new_app.trim().to_string());
+                route_editor.new_app.clear();
+            }
+            ui.end_row();
+        });
+        
+        if let Some(index) = to_remove {
+            vpn_config.app_tunnel.apps.remove(index);
+        }
+        if let Some(app) = to_launch {
+            if let Err(e) = netns::launch(vpn_config, &app) {
+                log::error!("Failed to start {}: {}", app, e);
+            }
+        }
+    }
+    
+    fn route_direction_combo(ui: &mut egui::Ui, id: impl std::hash::Hash, via_tunnel: &mut bool) {
+        egui::ComboBox::from_id_salt(id)
+            .selected_text(if *via_tunnel { "Through VPN" } else { "Bypass VPN" })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(via_tunnel, true, "Through VPN");
+                ui.selectable_value(via_tunnel, false, "Bypass VPN");
+            });
+    }
+    
+    fn draw_custom_commands(ui: &mut egui::Ui, theme: &Theme, commands: &mut CustomCommands) {
+        egui::Grid::new(ui.id().with("custom_commands")).num_columns(2).spacing([8.0, 4.0]).show(ui, |ui| {
+            ui.label("Connect:");
+            ui.add(egui::TextEdit::singleline(&mut commands.connect)
+                .hint_text("sudo openfortivpn {server} -u {username} -c {config}")
+                .desired_width(320.0));
+            ui.end_row();
+            ui.label("Disconnect:");
+            ui.add(egui::TextEdit::singleline(&mut commands.disconnect)
+                .hint_text("Optional, else the connect command is stopped")
+                .desired_width(320.0));
+            ui.end_row();
+            ui.label("Status:");
+            ui.add(egui::TextEdit::singleline(&mut commands.status)
+                .hint_text("Optional, exits with 0 while connected")
+                .desired_width(320.0));
+            ui.end_row();
+            ui.label("Interface:");
+            ui.add(egui::TextEdit::singleline(&mut commands.interface)
+                .hint_text("Optional, e.g. cscotun0 or ppp*")
+                .desired_width(320.0));
+            ui.end_row();
+        });
+        ui.label(egui::RichText::new(format!(
+            "Run through the shell. {} are replaced by the entry's values, which are also in VPN_MANAGER_* environment variables. Use $VPN_MANAGER_PASSWORD to keep the password out of the process list",
+            custom::PLACEHOLDERS.join(" ")
+        )).color(theme.text_secondary).small());
+    }
+    
+    fn draw_proxy_form(ui: &mut egui::Ui, theme: &Theme, proxy: &mut VpnProxy) {
+        ui.horizontal(|ui| {
+            ui.label("Type:");
+            for kind in ProxyKind::ALL {
+                ui.selectable_value(&mut proxy.kind, kind, kind.label());
+            }
+        });
+        if proxy.kind == ProxyKind::None {
+            ui.label(egui::RichText::new("For networks that only let traffic out through a proxy").color(theme.text_secondary));
+            return;
+        }
+        
+        ui.horizontal(|ui| {
+            ui.label("Host:");
+            ui.add(egui::TextEdit::singleline(&mut proxy.host).hint_text("proxy.example.com").desired_width(180.0));
+            ui.label("Port:");
+            let default_port = if proxy.kind == ProxyKind::Socks { 1080 } else { 8080 };
+            if proxy.port == 0 {
+                proxy.port = default_port;
+            }
+            ui.add(egui::DragValue::new(&mut proxy.port).range(1..=65535));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Username:");
+            ui.add(egui::TextEdit::singleline(&mut proxy.username).hint_text("Optional").desired_width(120.0));
+            ui.label("Password:");
+            ui.add(egui::TextEdit::singleline(&mut proxy.password).password(true).desired_width(120.0));
+        });
+        ui.label(egui::RichText::new(if proxy.kind == ProxyKind::Http {
+            "Replaces a proxy set in the profile. An HTTP proxy only carries TCP, so the profile needs proto tcp-client"
+        } else {
+            "Replaces a proxy set in the profile"
+        }).color(theme.text_secondary));
+    }
+    
+    // Files attached in place of the profile's, what they are checked against, and when the
+    // certificates in use expire
+    fn draw_certificates(ui: &mut egui::Ui, theme: &Theme, vpn_config: &VpnConfig, files: &mut CertFiles, manage_files: bool) {
+        egui::Grid::new(format!("certificates_{}", vpn_config.name)).num_columns(3).spacing([8.0, 4.0]).show(ui, |ui| {
+            for kind in certs::CertKind::ALL {
+                ui.label(kind.label());
+                let path = kind.path_mut(files);
+                if path.trim().is_empty() {
+                    ui.label(egui::RichText::new("From the profile").color(theme.text_secondary));
+                } else {
+                    let name = std::path::Path::new(path.as_str()).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_else(|| path.clone());
+                    ui.label(name).on_hover_text(path.as_str());
+                }
+                ui.horizontal(|ui| {
+                    if ui.small_button("Browse...").clicked() {
+                        if let Some(picked) = rfd::FileDialog::new().pick_file() {
+                            // Copied next to the profile like the config file, so it keeps working if the original moves
+                            *path = if manage_files {
+                                Config::import_config_file(&picked).unwrap_or_else(|e| {
+                                    log::warn!("Failed to copy {} into the managed directory: {}", picked.display(), e);
+                                    picked.display().to_string()
+                                })
+                            } else {
+                                picked.display().to_string()
+                            };
+                        }
+                    }
+                    if !path.is_empty() && ui.small_button("✖").on_hover_text("Use the profile's again").clicked() {
+                        path.clear();
+                    }
+                });
+                ui.end_row();
+            }
+        });
+        
+        let expiry = certs::expiry(vpn_config);
+        if !expiry.is_empty() {
+            ui.separator();
+            for (kind, not_after) in expiry {
+                ui.label(format!("{} valid until {}", kind.label(), not_after.format("%Y-%m-%d")));
+            }
+        }
+        let problems = certs::problems(vpn_config);
+        if !problems.is_empty() {
+            ui.separator();
+            for (severity, message) in problems {
+                let color = if severity == Severity::Error { theme.error } else { theme.warning };
+                ui.label(egui::RichText::new(format!("⚠ {}", message)).color(color));
+            }
+        }
+        ui.label(egui::RichText::new("Attached files replace the ones the profile names or has inline").color(theme.text_secondary));
     }
     
     fn draw_add_connection_card(ui: &mut egui::Ui, theme: &Theme, config: &mut Config,
                                new_vpn_name: &mut String, new_vpn_config_path: &mut String,
                                new_vpn_username: &mut String, new_vpn_password: &mut String,
-                               new_vpn_type: &mut VpnType) {
-        Card::show(ui, theme, "Add VPN Connection", |ui| {
-            ui.label("Add new VPN connection configuration");
+                               new_vpn_type: &mut VpnType, new_vpn_env: &mut String,
+                               new_vpn_server: &mut String, new_vpn_group: &mut String, new_vpn_proxy: &mut VpnProxy, new_vpn_custom: &mut CustomCommands,
+                               new_vpn_prompt_otp: &mut bool, new_vpn_keys: &mut Option<Result<wireguard::KeyPair, String>>, wg_draft: &mut WireGuardDraft,
+                               new_vpn_check: &mut Option<ConfigCheck>, bundle_result: &mut Option<Result<String, String>>,
+                               editing_vpn: &mut Option<String>, connected: Option<&str>, migration_report: &mut Option<MigrationReport>) {
+        let editing = editing_vpn.as_ref().and_then(|id| config.vpn_configs.iter().position(|c| c.id == *id));
+        if editing.is_none() {
+            // Also when the entry was removed while being edited
+            *editing_vpn = None;
+        }
+        
+        Card::show(ui, theme, if editing.is_some() { "Edit VPN Connection" } else { "Add VPN Connection" }, |ui| {
+            if ui.data_mut(|d| d.remove_temp::<bool>(Self::reveal_form_id())).is_some() {
+                ui.scroll_to_cursor(Some(egui::Align::TOP));
+            }
+            match editing {
+                Some(index) => ui.label(format!("Changes are saved to {}; its other settings stay as they are", config.vpn_configs[index].name)),
+                None => ui.label("Add new VPN connection configuration"),
+            };
             ui.add_space(8.0);
             
             ui.horizontal(|ui| {
@@ -130,6 +1528,9 @@ impl VpnPanel {
                 ui.add_space(8.0);
                 ui.selectable_value(new_vpn_type, VpnType::OpenVpn, "OpenVPN");
                 ui.selectable_value(new_vpn_type, VpnType::WireGuard, "WireGuard");
+                ui.selectable_value(new_vpn_type, VpnType::Ikev2, "IKEv2/IPsec");
+                ui.selectable_value(new_vpn_type, VpnType::OpenConnect, "OpenConnect");
+                ui.selectable_value(new_vpn_type, VpnType::Custom, "Custom");
             });
             
             ui.add_space(8.0);
@@ -139,26 +1540,122 @@ impl VpnPanel {
                 ui.text_edit_singleline(new_vpn_name);
             });
             
-            ui.horizontal(|ui| {
-                ui.label("Config Path:");
-                ui.text_edit_singleline(new_vpn_config_path);
+            if *new_vpn_type == VpnType::Ikev2 {
+                ui.horizontal(|ui| {
+                    ui.label("Connection:");
+                    ui.add(egui::TextEdit::singleline(new_vpn_config_path)
+                        .hint_text(if cfg!(windows) { "Windows VPN connection name" } else { "strongSwan connection name" }));
+                });
+                ui.label(egui::RichText::new(if cfg!(windows) {
+                    "Create the connection in Settings > Network & Internet > VPN first"
+                } else {
+                    "Define the connection in swanctl.conf or ipsec.conf first"
+                }).color(theme.text_secondary));
+            } else if *new_vpn_type == VpnType::OpenConnect {
+                ui.horizontal(|ui| {
+                    ui.label("Server:");
+                    ui.add(egui::TextEdit::singleline(new_vpn_server).hint_text("vpn.example.com"));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Group:");
+                    ui.add(egui::TextEdit::singleline(new_vpn_group).hint_text("Optional"));
+                });
+            } else if *new_vpn_type == VpnType::Custom {
+                Self::draw_custom_commands(ui, theme, new_vpn_custom);
+                ui.horizontal(|ui| {
+                    ui.label("Config Path:");
+                    ui.add(egui::TextEdit::singleline(new_vpn_config_path).hint_text("Optional, for {config}"));
+                    if ui.button("Browse").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().pick_file() {
+                            *new_vpn_config_path = path.display().to_string();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Server:");
+                    ui.add(egui::TextEdit::singleline(new_vpn_server).hint_text("Optional, for {server}"));
+                });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label("Config Path:");
+                    ui.text_edit_singleline(new_vpn_config_path);
+                    
+                    if ui.button("Browse").clicked() {
+                        let file_filter = match new_vpn_type {
+                            VpnType::OpenVpn => &["ovpn"],
+                            VpnType::WireGuard | VpnType::Ikev2 | VpnType::OpenConnect | VpnType::Custom => &["conf"],
+                        };
+                        
+                        if let Some(path) = rfd::FileDialog::new()
+                            .add_filter("VPN Config", file_filter)
+                            .pick_file()
+                        {
+                            *new_vpn_config_path = path.display().to_string();
+                        }
+                    }
+                });
                 
-                if ui.button("Browse").clicked() {
-                    let file_filter = match new_vpn_type {
-                        VpnType::OpenVpn => &["ovpn"],
-                        VpnType::WireGuard => &["conf"],
-                    };
+                for (severity, message) in ConfigCheck::problems(new_vpn_check, new_vpn_type, new_vpn_config_path.trim()) {
+                    let color = if *severity == Severity::Error { theme.error } else { theme.warning };
+                    ui.label(egui::RichText::new(format!("⚠ {}", message)).color(color));
+                }
+                
+                if *new_vpn_type == VpnType::OpenVpn {
+                    Self::draw_bundle_import(ui, theme, config, new_vpn_name, new_vpn_username, new_vpn_password, bundle_result);
+                }
+                
+                if *new_vpn_type == VpnType::WireGuard {
+                    Self::draw_qr_import(ui, theme, config, new_vpn_name, wg_draft);
+                    
+                    if ui.checkbox(&mut wg_draft.enabled, "Compose the config here").changed() && wg_draft.enabled {
+                        // Starts from the chosen file, if there is one
+                        wg_draft.info = wireguard::get_config_info(new_vpn_config_path).unwrap_or_default();
+                        wg_draft.error = None;
+                    }
+                    if wg_draft.enabled {
+                        Self::draw_wireguard_draft(ui, theme, wg_draft);
+                        if ui.button("💾 Write Config")
+                            .on_hover_text("Write these settings to the config file (a new one in your WireGuard folder if no path is set), with a new private key if it has none")
+                            .clicked()
+                        {
+                            Self::write_wireguard_draft(new_vpn_name, new_vpn_config_path, wg_draft, new_vpn_keys);
+                        }
+                    }
                     
-                    if let Some(path) = rfd::FileDialog::new()
-                        .add_filter("VPN Config", file_filter)
-                        .pick_file()
-                    {
-                        *new_vpn_config_path = path.display().to_string();
+                    let generate = ui.button("🔑 Generate Key Pair")
+                        .on_hover_text("Create a private key in the config file (a new one in your WireGuard folder if no path is set) and show the public key for the server")
+                        .clicked();
+                    if generate {
+                        if new_vpn_config_path.trim().is_empty() {
+                            *new_vpn_config_path = wireguard::new_config_path(new_vpn_name).display().to_string();
+                        }
+                        let rt = tokio::runtime::Runtime::new().unwrap();
+                        *new_vpn_keys = Some(rt.block_on(wireguard::generate_keypair())
+                            .and_then(|keys| wireguard::set_private_key(new_vpn_config_path, &keys.private_key).map(|_| keys))
+                            .map_err(|e| e.to_string()));
+                    }
+                    
+                    match new_vpn_keys {
+                        Some(Ok(keys)) => {
+                            ui.horizontal(|ui| {
+                                ui.label("Public key:");
+                                ui.label(egui::RichText::new(&keys.public_key).monospace());
+                                if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                                    ui.ctx().copy_text(keys.public_key.clone());
+                                }
+                            });
+                            ui.label(egui::RichText::new(format!("The private key was written to {}; add this public key as a peer on the server", new_vpn_config_path)).color(theme.text_secondary));
+                        }
+                        Some(Err(e)) => {
+                            ui.label(egui::RichText::new(format!("Key generation failed: {}", e)).color(theme.error));
+                        }
+                        None => {}
                     }
                 }
-            });
+            }
             
-            if *new_vpn_type == VpnType::OpenVpn {
+            // strongSwan keeps IKEv2 credentials in its own config, only rasdial takes them here
+            if matches!(new_vpn_type, VpnType::OpenVpn | VpnType::OpenConnect | VpnType::Custom) || (*new_vpn_type == VpnType::Ikev2 && cfg!(windows)) {
                 ui.horizontal(|ui| {
                     ui.label("Username:");
                     ui.text_edit_singleline(new_vpn_username);
@@ -170,27 +1667,291 @@ impl VpnPanel {
                 });
             }
             
+            if *new_vpn_type == VpnType::OpenVpn {
+                ui.checkbox(new_vpn_prompt_otp, "Ask for a one-time code on connect")
+                    .on_hover_text("For servers that want a code from an authenticator app with the password");
+            }
+            
+            if *new_vpn_type == VpnType::OpenVpn {
+                ui.collapsing("Proxy", |ui| {
+                    Self::draw_proxy_form(ui, theme, new_vpn_proxy);
+                });
+            }
+            
+            ui.collapsing("Environment Variables", |ui| {
+                ui.label(egui::RichText::new("One KEY=VALUE per line, set when the VPN client is started").color(theme.text_secondary));
+                ui.add(egui::TextEdit::multiline(new_vpn_env)
+                    .desired_rows(3)
+                    .hint_text("HTTPS_PROXY=http://proxy:8080"));
+            });
+            
             ui.add_space(12.0);
             
-            if GlassButton::show(ui, theme, "Add Connection", true).clicked() {
-                if !new_vpn_name.is_empty() && !new_vpn_config_path.is_empty() {
-                    config.vpn_configs.push(VpnConfig {
-                        name: new_vpn_name.clone(),
-                        config_path: new_vpn_config_path.clone(),
-                        username: new_vpn_username.clone(),
-                        password: new_vpn_password.clone(),
-                        auto_connect: false,
-                        vpn_type: new_vpn_type.clone(),
-                    });
+            // Renaming takes the schedules, failover place and history along, but not a running tunnel
+            let previous_name = editing.map(|index| config.vpn_configs[index].name.clone());
+            let renamed = previous_name.as_deref().filter(|name| *name != new_vpn_name.as_str());
+            let name_taken = renamed.is_some() && config.vpn_configs.iter().any(|c| c.name == *new_vpn_name);
+            let renaming_connected = renamed.is_some() && renamed == connected;
+            if name_taken {
+                ui.label(egui::RichText::new(format!("⚠ Another connection is called {}", new_vpn_name)).color(theme.warning));
+            }
+            if renaming_connected {
+                ui.label(egui::RichText::new(format!("⚠ Disconnect {} before renaming it", connected.unwrap_or_default())).color(theme.warning));
+            }
+            
+            let mut save = false;
+            let mut clear = false;
+            ui.horizontal(|ui| {
+                save = GlassButton::show(ui, theme, if editing.is_some() { "Save Changes" } else { "Add Connection" }, true).clicked()
+                    && !name_taken && !renaming_connected;
+                clear = editing.is_some() && ui.button("Cancel").clicked();
+            });
+            
+            if save {
+                // A composed config is written first; the connection isn't added if that fails
+                let written = *new_vpn_type != VpnType::WireGuard || !wg_draft.enabled || new_vpn_name.is_empty()
+                    || Self::write_wireguard_draft(new_vpn_name, new_vpn_config_path, wg_draft, new_vpn_keys);
+                let has_target = written && match new_vpn_type {
+                    VpnType::OpenConnect => !new_vpn_server.trim().is_empty(),
+                    VpnType::Custom => !new_vpn_custom.connect.trim().is_empty(),
+                    _ => !new_vpn_config_path.is_empty(),
+                };
+                if !new_vpn_name.is_empty() && has_target {
+                    let config_path = if *new_vpn_type == VpnType::OpenConnect {
+                        String::new()
+                    } else if editing.is_some_and(|index| config.vpn_configs[index].config_path == *new_vpn_config_path) {
+                        // Unchanged, already copied in when it was added
+                        new_vpn_config_path.clone()
+                    } else if config.manage_config_files && !matches!(new_vpn_type, VpnType::Ikev2 | VpnType::Custom) {
+                        Config::import_config_file(std::path::Path::new(new_vpn_config_path.as_str()))
+                            .unwrap_or_else(|e| {
+                                log::warn!("Failed to copy {} into the managed directory: {}", new_vpn_config_path, e);
+                                new_vpn_config_path.clone()
+                            })
+                    } else {
+                        new_vpn_config_path.clone()
+                    };
+                    
+                    let proxy = if *new_vpn_type == VpnType::OpenVpn { new_vpn_proxy.clone() } else { Default::default() };
+                    let custom = if *new_vpn_type == VpnType::Custom { new_vpn_custom.clone() } else { Default::default() };
                     
-                    // Clear input fields
-                    new_vpn_name.clear();
-                    new_vpn_config_path.clear();
-                    new_vpn_username.clear();
-                    new_vpn_password.clear();
-                    *new_vpn_type = VpnType::OpenVpn;
+                    if let Some(index) = editing {
+                        let vpn_config = &mut config.vpn_configs[index];
+                        vpn_config.name = new_vpn_name.clone();
+                        vpn_config.config_path = config_path;
+                        vpn_config.username = new_vpn_username.clone();
+                        vpn_config.password = new_vpn_password.clone();
+                        vpn_config.prompt_otp = *new_vpn_type == VpnType::OpenVpn && *new_vpn_prompt_otp;
+                        vpn_config.vpn_type = new_vpn_type.clone();
+                        vpn_config.env_vars = crate::config::parse_env_vars(new_vpn_env);
+                        vpn_config.server = new_vpn_server.trim().to_string();
+                        vpn_config.group = new_vpn_group.trim().to_string();
+                        vpn_config.proxy = proxy;
+                        vpn_config.custom = custom;
+                        if let Some(previous_name) = previous_name.filter(|name| name != new_vpn_name) {
+                            migrate::rename(config, EntryKind::Vpn, &previous_name, new_vpn_name).queue(migration_report);
+                        }
+                    } else {
+                        config.vpn_configs.push(VpnConfig {
+                            id: migrate::new_id(),
+                            name: new_vpn_name.clone(),
+                            config_path,
+                            username: new_vpn_username.clone(),
+                            password: new_vpn_password.clone(),
+                            prompt_otp: *new_vpn_type == VpnType::OpenVpn && *new_vpn_prompt_otp,
+                            auto_connect: false,
+                            vpn_type: new_vpn_type.clone(),
+                            env_vars: crate::config::parse_env_vars(new_vpn_env),
+                            server: new_vpn_server.trim().to_string(),
+                            group: new_vpn_group.trim().to_string(),
+                            kill_switch: false,
+                            split_routes: Vec::new(),
+                            app_tunnel: Default::default(),
+                            dns_servers: Vec::new(),
+                            dns_search_domains: Vec::new(),
+                            reconnect: Default::default(),
+                            system_service: false,
+                            health: Default::default(),
+                            proxy,
+                            certificates: Default::default(),
+                            custom,
+                            style: Default::default(),
+                        });
+                    }
+                    clear = true;
                 }
             }
+            
+            if clear {
+                new_vpn_name.clear();
+                new_vpn_config_path.clear();
+                new_vpn_username.clear();
+                new_vpn_password.clear();
+                new_vpn_env.clear();
+                new_vpn_server.clear();
+                new_vpn_group.clear();
+                *new_vpn_proxy = Default::default();
+                *new_vpn_custom = Default::default();
+                *new_vpn_prompt_otp = false;
+                *new_vpn_keys = None;
+                *wg_draft = WireGuardDraft::default();
+                *new_vpn_type = VpnType::OpenVpn;
+                *editing_vpn = None;
+            }
+        });
+    }
+    
+    fn draw_qr_import(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, new_vpn_name: &mut String, wg_draft: &mut WireGuardDraft) {
+        let mut decoded = None;
+        ui.horizontal(|ui| {
+            if ui.button("📷 QR from Image...").on_hover_text("Import a WireGuard config from a picture of its QR code").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Image", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
+                    .pick_file()
+                {
+                    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                    decoded = Some((wg_qr::decode_file(&path), stem));
+                }
+            }
+            if ui.button("🖥 QR from Screen").on_hover_text("Take a screenshot and import the WireGuard QR code shown on it").clicked() {
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                decoded = Some((rt.block_on(wg_qr::capture_screen()), String::new()));
+            }
+        });
+        
+        if let Some((content, stem)) = decoded {
+            // The name typed in, else the image's, made unique among the entries
+            let base = [new_vpn_name.trim(), stem.trim()].into_iter().find(|n| !n.is_empty()).unwrap_or("WireGuard");
+            let name = Self::unique_name(config, base);
+            
+            wg_draft.qr_result = Some(content.and_then(|content| wg_qr::import(&content, &name)).map(|mut vpn_config| {
+                if config.manage_config_files {
+                    match Config::import_config_file(std::path::Path::new(&vpn_config.config_path)) {
+                        Ok(managed) => vpn_config.config_path = managed,
+                        Err(e) => log::warn!("Failed to copy {} into the managed directory: {}", vpn_config.config_path, e),
+                    }
+                }
+                let message = format!("Added {} from the QR code ({})", vpn_config.name, vpn_config.config_path);
+                config.vpn_configs.push(vpn_config);
+                new_vpn_name.clear();
+                message
+            }).map_err(|e| e.to_string()));
+        }
+        
+        match &wg_draft.qr_result {
+            Some(Ok(message)) => {
+                ui.label(egui::RichText::new(message).color(theme.success));
+            }
+            Some(Err(e)) => {
+                ui.label(egui::RichText::new(format!("QR import failed: {}", e)).color(theme.error));
+            }
+            None => {}
+        }
+    }
+    
+    fn unique_name(config: &Config, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut suffix = 2;
+        while config.vpn_configs.iter().any(|c| c.name == name) {
+            name = format!("{} {}", base, suffix);
+            suffix += 1;
+        }
+        name
+    }
+    
+    fn draw_bundle_import(ui: &mut egui::Ui, theme: &Theme, config: &mut Config, new_vpn_name: &mut String,
+                          new_vpn_username: &mut String, new_vpn_password: &mut String,
+                          bundle_result: &mut Option<Result<String, String>>) {
+        let mut picked = None;
+        ui.horizontal(|ui| {
+            if ui.button("📦 Import Bundle...").on_hover_text("Import the profiles in a provider's zip, with their certificates and keys").clicked() {
+                picked = rfd::FileDialog::new().add_filter("Zip archive", &["zip"]).pick_file();
+            }
+            if ui.button("📁 Import .tblk...").on_hover_text("Import a Tunnelblick configuration folder").clicked() {
+                picked = rfd::FileDialog::new().pick_folder();
+            }
+        });
+        
+        if let Some(path) = picked {
+            *bundle_result = Some(ovpn_bundle::import(&path, new_vpn_name).map(|imported| {
+                let names: Vec<String> = imported.into_iter().map(|mut vpn_config| {
+                    vpn_config.name = Self::unique_name(config, &vpn_config.name);
+                    // Credentials typed in the form go to the profiles that came without any
+                    if vpn_config.username.is_empty() {
+                        vpn_config.username = new_vpn_username.clone();
+                        vpn_config.password = new_vpn_password.clone();
+                    }
+                    let name = vpn_config.name.clone();
+                    config.vpn_configs.push(vpn_config);
+                    name
+                }).collect();
+                new_vpn_name.clear();
+                new_vpn_username.clear();
+                new_vpn_password.clear();
+                format!("Added {}", names.join(", "))
+            }).map_err(|e| e.to_string()));
+        }
+        
+        match bundle_result {
+            Some(Ok(message)) => {
+                ui.label(egui::RichText::new(message.as_str()).color(theme.success));
+            }
+            Some(Err(e)) => {
+                ui.label(egui::RichText::new(format!("Bundle import failed: {}", e)).color(theme.error));
+            }
+            None => {}
+        }
+    }
+    
+    fn draw_wireguard_draft(ui: &mut egui::Ui, theme: &Theme, wg_draft: &mut WireGuardDraft) {
+        let info = &mut wg_draft.info;
+        egui::Grid::new("wireguard_draft").num_columns(2).spacing([8.0, 4.0]).show(ui, |ui| {
+            ui.label(egui::RichText::new("Interface").strong());
+            ui.end_row();
+            ui.label("Address:");
+            ui.add(egui::TextEdit::singleline(&mut info.address).hint_text("10.0.0.2/32"));
+            ui.end_row();
+            ui.label("DNS:");
+            ui.add(egui::TextEdit::singleline(&mut info.dns).hint_text("Optional, e.g. 1.1.1.1"));
+            ui.end_row();
+            
+            ui.label(egui::RichText::new("Peer").strong());
+            ui.end_row();
+            ui.label("Public key:");
+            ui.add(egui::TextEdit::singleline(&mut info.public_key).hint_text("The server's public key").font(egui::TextStyle::Monospace));
+            ui.end_row();
+            ui.label("Endpoint:");
+            ui.add(egui::TextEdit::singleline(&mut info.endpoint).hint_text("vpn.example.com:51820"));
+            ui.end_row();
+            ui.label("Allowed IPs:");
+            ui.add(egui::TextEdit::singleline(&mut info.allowed_ips).hint_text("0.0.0.0/0, ::/0"));
+            ui.end_row();
+            ui.label("Keepalive:");
+            ui.add(egui::TextEdit::singleline(&mut info.persistent_keepalive).hint_text("Optional, seconds, e.g. 25"));
+            ui.end_row();
+        });
+        if let Some(error) = &wg_draft.error {
+            ui.label(egui::RichText::new(error).color(theme.error));
+        }
+    }
+    
+    // Writes the composed config, generating a private key if the file has none, and reports
+    // whether it worked
+    fn write_wireguard_draft(new_vpn_name: &str, new_vpn_config_path: &mut String, wg_draft: &mut WireGuardDraft,
+                             new_vpn_keys: &mut Option<Result<wireguard::KeyPair, String>>) -> bool {
+        if new_vpn_config_path.trim().is_empty() {
+            *new_vpn_config_path = wireguard::new_config_path(new_vpn_name).display().to_string();
+        }
+        let result = wireguard::write_config(new_vpn_config_path, &wg_draft.info).and_then(|_| {
+            if !wireguard::has_private_key(new_vpn_config_path) {
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                let keys = rt.block_on(wireguard::generate_keypair())?;
+                wireguard::set_private_key(new_vpn_config_path, &keys.private_key)?;
+                *new_vpn_keys = Some(Ok(keys));
+            }
+            Ok(())
         });
+        wg_draft.error = result.err().map(|e| e.to_string());
+        wg_draft.error.is_none()
     }
 }
\ No newline at end of file
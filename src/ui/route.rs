@@ -0,0 +1,178 @@
+use super::Panel;
+use anyhow::{anyhow, bail};
+
+// Links that open the app on a panel and entry, e.g. vpn-aio://remote/Office%20PC
+pub const LINK_SCHEME: &str = "vpn-aio://";
+
+pub const USAGE: &str = "Usage: vpn-manager [--panel home|vpn|remote|schedule|settings|problems] [--select <entry>] [vpn-aio://<panel>/<entry>] [--minimized] [--safe-mode] [--renderer glow|wgpu|software]";
+
+// Where to take the UI; without a panel the entry's own panel is looked up
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub panel: Option<Panel>,
+    pub entry: Option<String>,
+}
+
+impl Route {
+    pub fn new(panel: Panel, entry: &str) -> Self {
+        Self {
+            panel: Some(panel),
+            entry: Some(entry.to_string()),
+        }
+    }
+
+    // Parses `--panel remote --select "Office PC"` or a single deep link from the command line
+    pub fn from_args(args: &[String]) -> anyhow::Result<Option<Self>> {
+        let mut route = Route { panel: None, entry: None };
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+                _ => (arg.as_str(), None),
+            };
+
+            match flag {
+                "--panel" | "--select" => {
+                    let value = inline_value
+                        .or_else(|| args.next().cloned())
+                        .ok_or_else(|| anyhow!("{} needs a value", flag))?;
+                    if flag == "--panel" {
+                        route.panel = Some(Panel::from_name(&value)
+                            .ok_or_else(|| anyhow!("Unknown panel '{}'", value))?);
+                    } else {
+                        route.entry = Some(value);
+                    }
+                }
+                link if link.starts_with(LINK_SCHEME) => route = Self::from_link(link)?,
+                other => bail!("Unknown argument '{}'", other),
+            }
+        }
+
+        Ok((route.panel.is_some() || route.entry.is_some()).then_some(route))
+    }
+
+    pub fn from_link(link: &str) -> anyhow::Result<Self> {
+        let path = link
+            .strip_prefix(LINK_SCHEME)
+            .ok_or_else(|| anyhow!("Not a {} link: {}", LINK_SCHEME, link))?
+            .trim_end_matches('/');
+
+        let (panel_name, entry) = match path.split_once('/') {
+            Some((panel_name, entry)) => (panel_name, Some(percent_decode(entry)?)),
+            None => (path, None),
+        };
+
+        Ok(Route {
+            panel: Some(Panel::from_name(panel_name).ok_or_else(|| anyhow!("Unknown panel '{}'", panel_name))?),
+            entry: entry.filter(|entry| !entry.is_empty()),
+        })
+    }
+
+    pub fn to_link(&self) -> String {
+        let panel = self.panel.as_ref().unwrap_or(&Panel::Home).name();
+        match &self.entry {
+            Some(entry) => format!("{}{}/{}", LINK_SCHEME, panel, percent_encode(entry)),
+            None => format!("{}{}", LINK_SCHEME, panel),
+        }
+    }
+}
+
+impl Panel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Panel::Home => "home",
+            Panel::Vpn => "vpn",
+            Panel::Remote => "remote",
+            Panel::Settings => "settings",
+            Panel::Problems => "problems",
+            Panel::Schedule => "schedule",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "home" => Some(Panel::Home),
+            "vpn" => Some(Panel::Vpn),
+            "remote" | "rdp" | "wol" => Some(Panel::Remote),
+            "settings" => Some(Panel::Settings),
+            "problems" => Some(Panel::Problems),
+            "schedule" => Some(Panel::Schedule),
+            _ => None,
+        }
+    }
+}
+
+// Entry a route focused, outlined and scrolled into view by the panel that shows it
+#[derive(Debug, Default)]
+pub struct Navigation {
+    requested: Option<Route>,
+    focused: Option<(Panel, String)>,
+    scroll_pending: bool,
+}
+
+impl Navigation {
+    // Called from panels; the app switches to the route after the frame
+    pub fn request(&mut self, route: Route) {
+        self.requested = Some(route);
+    }
+
+    pub fn take_request(&mut self) -> Option<Route> {
+        self.requested.take()
+    }
+
+    pub fn focus(&mut self, panel: Panel, entry: String) {
+        self.focused = Some((panel, entry));
+        self.scroll_pending = true;
+    }
+
+    pub fn focused_panel(&self) -> Option<&Panel> {
+        self.focused.as_ref().map(|(panel, _)| panel)
+    }
+
+    pub fn clear_focus(&mut self) {
+        self.focused = None;
+        self.scroll_pending = false;
+    }
+
+    pub fn is_focused(&self, entry: &str) -> bool {
+        self.focused.as_ref().is_some_and(|(_, focused)| focused == entry)
+    }
+
+    pub fn should_scroll(&self, entry: &str) -> bool {
+        self.scroll_pending && self.is_focused(entry)
+    }
+
+    // The focused entry has been drawn (and scrolled to) once
+    pub fn finish_scroll(&mut self) {
+        self.scroll_pending = false;
+    }
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> anyhow::Result<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3).ok_or_else(|| anyhow!("Truncated escape in link"))?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| anyhow!("Invalid escape '%{}' in link", hex))?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    Ok(String::from_utf8(decoded)?)
+}
@@ -1,11 +1,27 @@
+use crate::config::StatusPalette;
 use eframe::egui::Color32;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+// Palette currently applied to the UI, switched at runtime by the app
+static DARK_MODE: AtomicBool = AtomicBool::new(true);
+static STATUS_PALETTE: AtomicU8 = AtomicU8::new(StatusPalette::Standard as u8);
+static STATUS_SHAPES: AtomicBool = AtomicBool::new(false);
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum DeviceType {
     RDP,
+    VNC,
     WOL,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatusState {
+    Online,
+    Offline,
+    Connecting,
+    Error,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum ActionType {
     Primary,
@@ -34,9 +50,91 @@ pub struct Theme {
     pub selection_text: Color32,
     pub hover_bg: Color32,
     pub loading: Color32,
+    // Status indicators use distinct shapes per state instead of color alone
+    pub status_shapes: bool,
 }
 
 impl Theme {
+    // Palette for the mode the app is currently showing
+    pub fn current() -> Self {
+        let mut theme = if Self::is_dark() {
+            Self::new()
+        } else {
+            Self::light()
+        };
+        theme.apply_status_palette(Self::status_palette());
+        theme.status_shapes = Self::status_shapes();
+        theme
+    }
+    
+    pub fn is_dark() -> bool {
+        DARK_MODE.load(Ordering::Relaxed)
+    }
+    
+    pub fn set_dark(dark: bool) {
+        DARK_MODE.store(dark, Ordering::Relaxed);
+    }
+    
+    pub fn status_palette() -> StatusPalette {
+        match STATUS_PALETTE.load(Ordering::Relaxed) {
+            x if x == StatusPalette::HighContrast as u8 => StatusPalette::HighContrast,
+            x if x == StatusPalette::ColorblindSafe as u8 => StatusPalette::ColorblindSafe,
+            _ => StatusPalette::Standard,
+        }
+    }
+    
+    pub fn status_shapes() -> bool {
+        STATUS_SHAPES.load(Ordering::Relaxed)
+    }
+    
+    pub fn set_status_palette(palette: StatusPalette, shapes: bool) {
+        STATUS_PALETTE.store(palette as u8, Ordering::Relaxed);
+        STATUS_SHAPES.store(shapes, Ordering::Relaxed);
+    }
+    
+    // Overrides the state colors (and for high contrast, text and borders) of the base palette
+    fn apply_status_palette(&mut self, palette: StatusPalette) {
+        let dark = Self::is_dark();
+        match palette {
+            StatusPalette::Standard => {}
+            StatusPalette::HighContrast => {
+                if dark {
+                    self.success = Color32::from_rgb(0, 230, 118);
+                    self.warning = Color32::from_rgb(255, 214, 0);
+                    self.error = Color32::from_rgb(255, 82, 82);
+                    self.loading = Color32::from_rgb(64, 196, 255);
+                    self.text_primary = Color32::WHITE;
+                    self.text_secondary = Color32::from_rgb(214, 220, 228);
+                    self.text_disabled = Color32::from_rgb(170, 178, 189);
+                    self.border = Color32::from_rgb(160, 170, 185);
+                } else {
+                    self.success = Color32::from_rgb(0, 110, 40);
+                    self.warning = Color32::from_rgb(160, 90, 0);
+                    self.error = Color32::from_rgb(190, 0, 0);
+                    self.loading = Color32::from_rgb(0, 70, 180);
+                    self.text_primary = Color32::BLACK;
+                    self.text_secondary = Color32::from_rgb(30, 30, 30);
+                    self.text_disabled = Color32::from_rgb(85, 85, 85);
+                    self.border = Color32::from_rgb(60, 60, 60);
+                }
+            }
+            StatusPalette::ColorblindSafe => {
+                // Blue / orange / vermillion / reddish purple never rely on telling red from green
+                if dark {
+                    self.success = Color32::from_rgb(86, 180, 233);
+                    self.warning = Color32::from_rgb(230, 159, 0);
+                    self.error = Color32::from_rgb(213, 94, 0);
+                    self.loading = Color32::from_rgb(204, 121, 167);
+                } else {
+                    self.success = Color32::from_rgb(0, 114, 178);
+                    self.warning = Color32::from_rgb(190, 120, 0);
+                    self.error = Color32::from_rgb(213, 94, 0);
+                    self.loading = Color32::from_rgb(174, 81, 137);
+                }
+            }
+        }
+    }
+    
     pub fn new() -> Self {
         Self {
             background: Color32::from_rgba_premultiplied(15, 15, 20, 255),
@@ -57,6 +155,7 @@ impl Theme {
             selection_text: Color32::from_rgba_premultiplied(255, 255, 255, 255),
             hover_bg: Color32::from_rgba_premultiplied(45, 55, 72, 255),
             loading: Color32::from_rgba_premultiplied(139, 92, 246, 255),
+            status_shapes: false,
         }
     }
 
@@ -80,6 +179,7 @@ impl Theme {
             selection_text: Color32::from_rgba_premultiplied(255, 255, 255, 255),
             hover_bg: Color32::from_rgba_premultiplied(241, 245, 249, 180),
             loading: Color32::from_rgba_premultiplied(139, 92, 246, 255),
+            status_shapes: false,
         }
     }
 
@@ -91,6 +191,15 @@ impl Theme {
         }
     }
 
+    pub fn get_state_color(&self, state: StatusState) -> Color32 {
+        match state {
+            StatusState::Online => self.success,
+            StatusState::Offline => self.text_disabled,
+            StatusState::Connecting => self.warning,
+            StatusState::Error => self.error,
+        }
+    }
+
     pub fn get_button_color(&self, is_primary: bool) -> Color32 {
         if is_primary {
             self.primary
@@ -149,6 +258,7 @@ impl Theme {
     pub fn get_device_icon_color(&self, device_type: DeviceType, is_online: bool) -> eframe::egui::Color32 {
         match device_type {
             DeviceType::RDP => self.primary,
+            DeviceType::VNC => self.accent,
             DeviceType::WOL => {
                 if is_online {
                     self.success